@@ -0,0 +1,86 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of the order-stream decode hot path.
+//!
+//! Run with `cargo bench -p boundless-market --bench order_decode`. To compare a change against a
+//! baseline, save the baseline on the base commit and compare on the change:
+//!
+//! ```sh
+//! cargo bench -p boundless-market --bench order_decode -- --save-baseline main
+//! cargo bench -p boundless-market --bench order_decode -- --baseline main
+//! ```
+
+use std::hint::black_box;
+
+use alloy::{
+    primitives::{Address, U256},
+    signers::{local::PrivateKeySigner, SignerSync},
+};
+use boundless_market::{
+    contracts::{Offer, Predicate, PredicateType, RequestId, RequestInput, Requirements},
+    order_stream_client::{decode_order, decode_order_frame, encode_order, Order, OrderData},
+    ProofRequest,
+};
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use risc0_zkvm::sha::Digest;
+
+fn order_data() -> OrderData {
+    let signer = PrivateKeySigner::random();
+    let request = ProofRequest::new(
+        RequestId::new(signer.address(), 1),
+        Requirements::new(
+            Digest::ZERO,
+            Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+        ),
+        "https://dev.null",
+        RequestInput::builder().write_slice(&[0x41; 1024]).build_inline().unwrap(),
+        Offer {
+            minPrice: U256::from(0),
+            maxPrice: U256::from(1),
+            biddingStart: 0,
+            timeout: 1000,
+            rampUpPeriod: 1,
+            lockTimeout: 1000,
+            lockStake: U256::from(0),
+        },
+    );
+    let request_digest = request.signing_hash(Address::ZERO, 1).unwrap();
+    let signature = signer.sign_hash_sync(&request_digest).unwrap();
+    OrderData {
+        id: 1,
+        order: Order::new(request, request_digest, signature),
+        created_at: Utc::now(),
+    }
+}
+
+fn decode(c: &mut Criterion) {
+    let order_data = order_data();
+
+    let mut group = c.benchmark_group("decode_order");
+    group.throughput(Throughput::Elements(1));
+    let text = serde_json::to_string(&order_data).unwrap();
+    group.bench_function("text", |b| b.iter(|| decode_order(black_box(&text)).unwrap()));
+    for encoding in ["json", "json+deflate", "msgpack", "msgpack+deflate"] {
+        let payload = encode_order(&order_data, encoding.parse().unwrap()).unwrap();
+        group.bench_function(encoding, |b| {
+            b.iter(|| decode_order_frame(black_box(&payload)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
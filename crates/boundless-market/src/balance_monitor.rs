@@ -0,0 +1,190 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use alloy::{
+    primitives::{utils::format_ether, Address, U256},
+    providers::Provider,
+};
+
+use crate::contracts::{
+    boundless_market::{BoundlessMarketService, MarketError},
+    ProofRequest, RequestStatus,
+};
+
+/// Status of a requestor's market balance relative to its open requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceStatus {
+    /// The balance covers all open requests, plus the configured margin.
+    Sufficient,
+    /// The balance covers all open requests, but with less than the configured margin left.
+    Low,
+    /// The balance does not cover the max price of all open requests. Locking some of them will
+    /// fail once the balance is exhausted.
+    Insufficient,
+}
+
+/// Snapshot of a requestor's market balance and outstanding commitments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceReport {
+    /// Balance of the requestor in the market, in wei.
+    pub balance: U256,
+    /// Sum of the max prices of all tracked requests that are still open, in wei.
+    pub committed: U256,
+    /// Number of tracked requests that are still open.
+    pub open_requests: usize,
+    /// Status derived from the balance, commitments and margin.
+    pub status: BalanceStatus,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct OpenRequest {
+    max_price: U256,
+    lock_expires_at: u64,
+}
+
+/// Watch-only monitor of a requestor's market balance.
+///
+/// Payment for a request is taken from the requestor's market balance when a prover locks it,
+/// at a price of up to the request's `maxPrice`. The monitor tracks submitted requests which are
+/// still open (not yet locked, fulfilled or past their lock deadline) and compares the sum of
+/// their max prices with the current balance, logging a warning before locks start failing due
+/// to an insufficient balance. The monitor never sends transactions.
+///
+/// # Examples
+/// ```no_run
+/// # use alloy::primitives::utils::parse_ether;
+/// # use boundless_market::{balance_monitor::RequestorBalanceMonitor, Client, ProofRequest};
+/// # async fn example(client: Client, request: ProofRequest) -> anyhow::Result<()> {
+/// let monitor = RequestorBalanceMonitor::new(
+///     client.boundless_market.clone(),
+///     request.client_address(),
+/// )
+/// .with_warn_margin(parse_ether("0.01")?);
+///
+/// client.submit_request_onchain(&request).await?;
+/// monitor.track(&request);
+///
+/// let report = monitor.check().await?;
+/// println!("{} of {} wei committed", report.committed, report.balance);
+/// # Ok(())
+/// # }
+/// ```
+pub struct RequestorBalanceMonitor<P> {
+    market: BoundlessMarketService<P>,
+    account: Address,
+    warn_margin: U256,
+    open: Mutex<HashMap<U256, OpenRequest>>,
+}
+
+impl<P: Provider> RequestorBalanceMonitor<P> {
+    /// Creates a new monitor for the given requestor account.
+    pub fn new(market: BoundlessMarketService<P>, account: impl Into<Address>) -> Self {
+        Self {
+            market,
+            account: account.into(),
+            warn_margin: U256::ZERO,
+            open: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the margin, in wei, below which a covered balance is reported as [BalanceStatus::Low].
+    pub fn with_warn_margin(self, warn_margin: U256) -> Self {
+        Self { warn_margin, ..self }
+    }
+
+    /// Start tracking a submitted request.
+    pub fn track(&self, request: &ProofRequest) {
+        self.open.lock().unwrap().insert(
+            request.id,
+            OpenRequest {
+                max_price: request.offer.maxPrice,
+                lock_expires_at: request.lock_expires_at(),
+            },
+        );
+    }
+
+    /// Stop tracking a request, e.g. if it was abandoned before being submitted.
+    pub fn untrack(&self, request_id: U256) {
+        self.open.lock().unwrap().remove(&request_id);
+    }
+
+    /// Returns the sum of the max prices of the tracked open requests, without refreshing them.
+    pub fn committed(&self) -> U256 {
+        self.open.lock().unwrap().values().map(|r| r.max_price).sum()
+    }
+
+    /// Refresh the status of the tracked requests and compare the commitments with the balance.
+    ///
+    /// Requests that have been locked, fulfilled, or can no longer be locked are dropped from
+    /// the tracked set. A warning is logged if the status is not [BalanceStatus::Sufficient].
+    pub async fn check(&self) -> Result<BalanceReport, MarketError> {
+        let tracked: Vec<(U256, OpenRequest)> =
+            self.open.lock().unwrap().iter().map(|(id, r)| (*id, *r)).collect();
+
+        for (request_id, open) in tracked {
+            let status = self.market.get_status(request_id, Some(open.lock_expires_at)).await?;
+            if status != RequestStatus::Unknown {
+                tracing::trace!("Request {request_id:x} is no longer open: {status:?}");
+                self.untrack(request_id);
+            }
+        }
+
+        let balance = self.market.balance_of(self.account).await?;
+        let (committed, open_requests) = {
+            let open = self.open.lock().unwrap();
+            (open.values().map(|r| r.max_price).sum::<U256>(), open.len())
+        };
+
+        let status = if balance < committed {
+            BalanceStatus::Insufficient
+        } else if balance - committed < self.warn_margin {
+            BalanceStatus::Low
+        } else {
+            BalanceStatus::Sufficient
+        };
+
+        match status {
+            BalanceStatus::Insufficient => tracing::warn!(
+                "Market balance of {} ({} ETH) does not cover the max price of {open_requests} open requests ({} ETH); locks will fail once the balance is exhausted",
+                self.account,
+                format_ether(balance),
+                format_ether(committed)
+            ),
+            BalanceStatus::Low => tracing::warn!(
+                "Market balance of {} ({} ETH) is within the warning margin of the max price of {open_requests} open requests ({} ETH)",
+                self.account,
+                format_ether(balance),
+                format_ether(committed)
+            ),
+            BalanceStatus::Sufficient => tracing::trace!(
+                "Market balance of {} ({} ETH) covers {open_requests} open requests ({} ETH)",
+                self.account,
+                format_ether(balance),
+                format_ether(committed)
+            ),
+        }
+
+        Ok(BalanceReport { balance, committed, open_requests, status })
+    }
+
+    /// Run [Self::check] every `interval` until an error occurs.
+    pub async fn watch(&self, interval: Duration) -> Result<(), MarketError> {
+        loop {
+            self.check().await?;
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
@@ -24,6 +24,7 @@ use alloy::{
     },
 };
 use alloy_primitives::{Signature, B256};
+use alloy_sol_types::SolStruct;
 use anyhow::{anyhow, bail, Context, Result};
 use risc0_aggregation::SetInclusionReceipt;
 use risc0_ethereum_contracts::set_verifier::SetVerifierService;
@@ -33,13 +34,14 @@ use url::Url;
 use crate::{
     balance_alerts_layer::{BalanceAlertConfig, BalanceAlertLayer},
     contracts::{
-        boundless_market::{BoundlessMarketService, MarketError},
-        ProofRequest, RequestError,
+        boundless_market::{BoundlessMarketService, FulfillmentWaitOptions, MarketError},
+        erc1271::SmartContractSigner,
+        Offer, ProofRequest, RequestError, RequestId, RequestStatus,
     },
     deployments::Deployment,
     dynamic_gas_filler::DynamicGasFiller,
     nonce_layer::NonceProvider,
-    order_stream_client::OrderStreamClient,
+    order_stream_client::{OrderStreamClient, OrderStreamErr},
     request_builder::{
         FinalizerConfigBuilder, OfferLayer, OfferLayerConfigBuilder, RequestBuilder,
         RequestIdLayer, RequestIdLayerConfigBuilder, StandardRequestBuilder,
@@ -481,6 +483,12 @@ pub enum ClientError {
     /// Error when trying to construct a [RequestBuilder].
     #[error("Error building RequestBuilder {0}")]
     BuilderError(#[from] StandardRequestBuilderBuilderError),
+    /// Order stream error
+    #[error("Order stream error {0}")]
+    OrderStreamError(#[from] OrderStreamErr),
+    /// The request can no longer be repriced, as it is not open for bidding.
+    #[error("Request 0x{0:x} can not be repriced, status {1:?}")]
+    RequestNotOpen(U256, RequestStatus),
     /// General error
     #[error("Error {0}")]
     Error(#[from] anyhow::Error),
@@ -743,6 +751,27 @@ where
         Ok((request_id, request.expires_at()))
     }
 
+    /// Submit a proof request signed by a smart contract in an onchain transaction.
+    ///
+    /// The smart contract signed flag is set on the request ID, and the signature is checked by
+    /// the market with ERC-1271 against the client, which must be the address of the signer.
+    pub async fn submit_request_onchain_with_smart_contract_signer(
+        &self,
+        request: &ProofRequest,
+        signer: &SmartContractSigner,
+    ) -> Result<(U256, u64), ClientError> {
+        let request = self.prepare_smart_contract_signed_request(request, signer).await?;
+        request.validate()?;
+
+        let chain_id = self.boundless_market.get_chain_id().await?;
+        let signature = signer
+            .sign_request(&request, *self.boundless_market.instance().address(), chain_id)
+            .await?;
+        let request_id =
+            self.boundless_market.submit_request_with_signature(&request, signature).await?;
+        Ok((request_id, request.expires_at()))
+    }
+
     /// Build and submit a proof request offchain via the order stream service.
     ///
     /// Requires a [Signer] to be provided to sign the request, and a [RequestBuilder] to be
@@ -810,6 +839,125 @@ where
         Ok((order.request.id, request.expires_at()))
     }
 
+    /// Submit a proof request signed by a smart contract offchain via the order stream service.
+    ///
+    /// The smart contract signed flag is set on the request ID, and the signature is checked by
+    /// the order stream and the market with ERC-1271 against the client, which must be the
+    /// address of the signer.
+    pub async fn submit_request_offchain_with_smart_contract_signer(
+        &self,
+        request: &ProofRequest,
+        signer: &SmartContractSigner,
+    ) -> Result<(U256, u64), ClientError> {
+        let offchain_client = self
+            .offchain_client
+            .as_ref()
+            .context("Order stream client not available. Please provide an order stream URL")?;
+        let request = self.prepare_smart_contract_signed_request(request, signer).await?;
+        let balance = self.boundless_market.balance_of(signer.address()).await?;
+        if balance < U256::from(request.offer.maxPrice) {
+            return Err(ClientError::Error(anyhow!(
+                "Insufficient balance to cover request: {} < {}.\nMake sure to top up your balance by depositing on the Boundless Market.",
+                balance,
+                request.offer.maxPrice
+            )));
+        }
+
+        let order = offchain_client.submit_smart_contract_signed_request(&request, signer).await?;
+
+        Ok((order.request.id, request.expires_at()))
+    }
+
+    /// Set a random ID on the request if not set, and the smart contract signed flag, checking
+    /// the client of the request is the signer.
+    async fn prepare_smart_contract_signed_request(
+        &self,
+        request: &ProofRequest,
+        signer: &SmartContractSigner,
+    ) -> Result<ProofRequest, ClientError> {
+        let mut request = request.clone();
+        if request.id == U256::ZERO {
+            let index = self.boundless_market.index_from_rand().await?;
+            request.id = RequestId::u256(signer.address(), index);
+        };
+        let client_address = request.client_address();
+        if client_address != signer.address() {
+            return Err(MarketError::AddressMismatch(client_address, signer.address()))?;
+        };
+        Ok(request.with_smart_contract_signed_flag())
+    }
+
+    /// Reprice a submitted request that has not been locked, resubmitting it with the given offer.
+    ///
+    /// Requires a signer to be set to sign the repriced request.
+    pub async fn reprice_request(
+        &self,
+        request: &ProofRequest,
+        offer: Offer,
+    ) -> Result<(B256, u64), ClientError>
+    where
+        Si: Signer,
+    {
+        let signer = self.signer.as_ref().context("signer not set")?;
+        self.reprice_request_with_signer(request, offer, signer).await
+    }
+
+    /// Reprice a submitted request that has not been locked, resubmitting it with the given offer.
+    ///
+    /// The repriced request keeps the ID of the original request and is signed again, giving it
+    /// a new digest. If the original request is found in the order stream, it is withdrawn and
+    /// the repriced request is submitted offchain. Otherwise, it is submitted onchain. Returns
+    /// the digest of the repriced request and the time at which it expires.
+    ///
+    /// The market contract does not support cancelling requests, so the original request remains
+    /// valid onchain, and provers that already received it can still lock it. As both share the
+    /// same ID, at most one of them can be locked and fulfilled.
+    pub async fn reprice_request_with_signer(
+        &self,
+        request: &ProofRequest,
+        offer: Offer,
+        signer: &impl Signer,
+    ) -> Result<(B256, u64), ClientError> {
+        let client_address = request.client_address();
+        if client_address != signer.address() {
+            return Err(MarketError::AddressMismatch(client_address, signer.address()))?;
+        };
+        let status =
+            self.boundless_market.get_status(request.id, Some(request.expires_at())).await?;
+        if status != RequestStatus::Unknown {
+            return Err(ClientError::RequestNotOpen(request.id, status));
+        }
+
+        let repriced = ProofRequest { offer, ..request.clone() };
+        repriced.validate()?;
+        let domain = self.boundless_market.eip712_domain().await?;
+        let digest = request.eip712_signing_hash(&domain.alloy_struct());
+        let repriced_digest = repriced.eip712_signing_hash(&domain.alloy_struct());
+
+        let withdrawn = match &self.offchain_client {
+            Some(offchain_client) => {
+                match offchain_client.withdraw_order(request.id, digest, signer).await {
+                    Ok(()) => true,
+                    Err(OrderStreamErr::NotFound) => false,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            None => false,
+        };
+        let (_, expires_at) = if withdrawn {
+            tracing::debug!("Withdrew request 0x{:x} from the order stream", request.id);
+            self.submit_request_offchain_with_signer(&repriced, signer).await?
+        } else {
+            self.submit_request_onchain_with_signer(&repriced, signer).await?
+        };
+        tracing::info!(
+            "Repriced request 0x{:x}, digest {digest} replaced by {repriced_digest}",
+            request.id
+        );
+
+        Ok((repriced_digest, expires_at))
+    }
+
     /// Wait for a request to be fulfilled.
     ///
     /// The check interval is the time between each check for fulfillment.
@@ -826,6 +974,37 @@ where
             .await?)
     }
 
+    /// Wait for a request to be fulfilled, returning its journal and seal.
+    ///
+    /// The status of the request is polled with exponential backoff, as configured by `opts`.
+    /// Fails with [MarketError::RequestHasExpired] if the request expires, or with
+    /// [MarketError::TimeoutReached] once the optional timeout is reached.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use alloy::primitives::{Bytes, U256};
+    /// use anyhow::Result;
+    /// use boundless_market::client::ClientBuilder;
+    /// use boundless_market::contracts::boundless_market::FulfillmentWaitOptions;
+    ///
+    /// async fn wait(request_id: U256, expires_at: u64) -> Result<(Bytes, Bytes)> {
+    ///     let client = ClientBuilder::new().build().await?;
+    ///     let opts = FulfillmentWaitOptions::default()
+    ///         .with_expires_at(expires_at)
+    ///         .with_timeout(Duration::from_secs(600));
+    ///     Ok(client.wait_for_fulfillment(request_id, opts).await?)
+    /// }
+    /// ```
+    pub async fn wait_for_fulfillment(
+        &self,
+        request_id: U256,
+        opts: FulfillmentWaitOptions,
+    ) -> Result<(Bytes, Bytes), ClientError> {
+        Ok(self.boundless_market.wait_for_fulfillment(request_id, opts).await?)
+    }
+
     /// Get the [SetInclusionReceipt] for a request.
     ///
     /// # Examples
@@ -882,17 +1061,16 @@ where
             match order_stream_client.fetch_order(request_id, request_digest).await {
                 Ok(order) => {
                     tracing::debug!("Found request 0x{request_id:x} offchain");
-                    return Ok((order.request, Bytes::from(order.signature.as_bytes())));
+                    let signature = order.client_signature();
+                    return Ok((order.request, signature));
+                }
+                Err(OrderStreamErr::NotFound) => {
+                    tracing::debug!("Request 0x{request_id:x} not found offchain");
                 }
                 Err(err) => {
-                    // TODO: Provide a type-safe way to handle this error.
-                    if err.to_string().contains("No order found") {
-                        tracing::debug!("Request 0x{request_id:x} not found offchain");
-                    } else {
-                        tracing::error!(
-                            "Error querying order stream for request 0x{request_id:x}; err = {err}"
-                        );
-                    }
+                    tracing::error!(
+                        "Error querying order stream for request 0x{request_id:x}; err = {err}"
+                    );
                 }
             }
         } else {
@@ -13,9 +13,13 @@
 // limitations under the License.
 
 use std::{
+    collections::HashMap,
     fmt::Debug,
-    sync::atomic::{AtomicU64, Ordering},
-    time::Duration,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 use alloy::{
@@ -23,22 +27,26 @@ use alloy::{
     eips::BlockNumberOrTag,
     network::Ethereum,
     primitives::{utils::format_ether, Address, Bytes, B256, U256},
-    providers::{PendingTransactionBuilder, PendingTransactionError, Provider},
-    rpc::types::{Log, TransactionReceipt},
+    providers::Provider,
+    rpc::types::{Log, TransactionReceipt, TransactionRequest},
     signers::Signer,
 };
 
 use alloy_sol_types::{SolCall, SolEvent};
 use anyhow::{anyhow, Context, Result};
+use futures_util::StreamExt;
 use risc0_ethereum_contracts::event_query::EventQueryConfig;
 use thiserror::Error;
 
-use crate::contracts::token::{IERC20Permit, IHitPoints::IHitPointsErrors, Permit, IERC20};
+use crate::contracts::{
+    token::{IERC20Permit, IHitPoints::IHitPointsErrors, Permit, IERC20},
+    tx_submitter::{TxOutcome, TxSubmitter},
+};
 
 use super::{
     eip712_domain, AssessorReceipt, EIP712DomainSaltless, Fulfillment,
     IBoundlessMarket::{self, IBoundlessMarketInstance},
-    Offer, ProofRequest, RequestError, RequestId, RequestStatus, TxnErr, TXN_CONFIRM_TIMEOUT,
+    Offer, ProofRequest, RequestError, RequestId, RequestStatus, TxnErr,
 };
 
 /// Fraction of stake the protocol gives to the prover who fills an order that was locked by another prover but expired
@@ -94,6 +102,10 @@ pub enum MarketError {
     #[error("Lock request reverted, possibly outbid: txn_hash: {0}")]
     LockRevert(B256),
 
+    /// Fulfill request reverted.
+    #[error("Fulfill request reverted: txn_hash: {0}")]
+    FulfillRevert(B256),
+
     /// Lock request reverted, possibly outbid.
     #[error("Slash request reverted, possibly already slashed: txn_hash: {0}")]
     SlashRevert(B256),
@@ -105,6 +117,10 @@ pub enum MarketError {
     /// Timeout reached.
     #[error("Timeout: 0x{0:x}")]
     TimeoutReached(U256),
+
+    /// Too many transactions are already waiting to be sent.
+    #[error("Transaction queue full: {0} transactions waiting")]
+    TxQueueFull(usize),
 }
 
 impl From<alloy::contract::Error> for MarketError {
@@ -120,24 +136,12 @@ pub struct BoundlessMarketService<P> {
     // Chain ID with caching to ensure we fetch it at most once.
     chain_id: AtomicU64,
     caller: Address,
-    timeout: Duration,
     event_query_config: EventQueryConfig,
     balance_alert_config: StakeBalanceAlertConfig,
-    receipt_query_config: ReceiptQueryConfig,
-}
-
-#[derive(Clone, Debug)]
-struct ReceiptQueryConfig {
-    /// Interval at which the transaction receipts are polled.
-    retry_interval: Duration,
-    /// Number of retries for querying receipt of lock transactions.
-    retry_count: usize,
-}
-
-impl Default for ReceiptQueryConfig {
-    fn default() -> Self {
-        Self { retry_count: 10, retry_interval: Duration::from_millis(500) }
-    }
+    tx_submitter: TxSubmitter,
+    // Journal and seal of fulfilled requests, shared by clones. Fulfillments never change once
+    // delivered, so they are kept for the lifetime of the service.
+    fulfillments: Arc<Mutex<HashMap<U256, (Bytes, Bytes)>>>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -154,14 +158,82 @@ impl<P: Clone> Clone for BoundlessMarketService<P> {
             instance: self.instance.clone(),
             chain_id: self.chain_id.load(Ordering::Relaxed).into(),
             caller: self.caller,
-            timeout: self.timeout,
             event_query_config: self.event_query_config.clone(),
             balance_alert_config: self.balance_alert_config.clone(),
-            receipt_query_config: self.receipt_query_config.clone(),
+            tx_submitter: self.tx_submitter.clone(),
+            fulfillments: self.fulfillments.clone(),
+        }
+    }
+}
+
+/// Options for [BoundlessMarketService::wait_for_fulfillment].
+///
+/// The status of the request is first checked right away, then after `initial_interval`, with
+/// the interval growing by `multiplier` after each check up to `max_interval`.
+#[derive(Clone, Debug)]
+pub struct FulfillmentWaitOptions {
+    /// Interval between the first and second status checks.
+    pub initial_interval: Duration,
+    /// Maximum interval between status checks.
+    pub max_interval: Duration,
+    /// Factor the interval grows by after each status check.
+    pub multiplier: f64,
+    /// Maximum time to wait, after which [MarketError::TimeoutReached] is returned.
+    ///
+    /// If not set, waits until the request is fulfilled or expires.
+    pub timeout: Option<Duration>,
+    /// Expiration of the request, as a UNIX timestamp in seconds.
+    ///
+    /// If not set, only expiration of a locked request is detected, from its onchain deadline.
+    pub expires_at: Option<u64>,
+}
+
+impl Default for FulfillmentWaitOptions {
+    fn default() -> Self {
+        Self {
+            initial_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(30),
+            multiplier: 2.0,
+            timeout: None,
+            expires_at: None,
         }
     }
 }
 
+impl FulfillmentWaitOptions {
+    /// Sets the interval between the first and second status checks.
+    pub fn with_initial_interval(self, initial_interval: Duration) -> Self {
+        Self { initial_interval, ..self }
+    }
+
+    /// Sets the maximum interval between status checks.
+    pub fn with_max_interval(self, max_interval: Duration) -> Self {
+        Self { max_interval, ..self }
+    }
+
+    /// Sets the factor the interval grows by after each status check.
+    ///
+    /// A multiplier of 1 checks the status at a fixed interval.
+    pub fn with_multiplier(self, multiplier: f64) -> Self {
+        Self { multiplier, ..self }
+    }
+
+    /// Sets the maximum time to wait.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout: Some(timeout), ..self }
+    }
+
+    /// Sets the expiration of the request, as a UNIX timestamp in seconds.
+    pub fn with_expires_at(self, expires_at: u64) -> Self {
+        Self { expires_at: Some(expires_at), ..self }
+    }
+
+    /// Returns the interval following the given one.
+    fn next_interval(&self, interval: Duration) -> Duration {
+        interval.mul_f64(self.multiplier.max(1.0)).min(self.max_interval)
+    }
+}
+
 fn extract_tx_log<E: SolEvent + Debug + Clone>(
     receipt: &TransactionReceipt,
 ) -> Result<Log<E>, anyhow::Error> {
@@ -209,16 +281,23 @@ impl<P: Provider> BoundlessMarketService<P> {
             instance,
             chain_id: AtomicU64::new(0),
             caller: caller.into(),
-            timeout: TXN_CONFIRM_TIMEOUT,
             event_query_config: EventQueryConfig::default(),
             balance_alert_config: StakeBalanceAlertConfig::default(),
-            receipt_query_config: ReceiptQueryConfig::default(),
+            tx_submitter: TxSubmitter::default(),
+            fulfillments: Default::default(),
         }
     }
 
     /// Sets the transaction timeout.
     pub fn with_timeout(self, timeout: Duration) -> Self {
-        Self { timeout, ..self }
+        Self { tx_submitter: self.tx_submitter.with_timeout(timeout), ..self }
+    }
+
+    /// Sets the submitter used to send lock and fulfill transactions.
+    ///
+    /// The transaction timeout and receipt retry settings are those of the submitter.
+    pub fn with_tx_submitter(self, tx_submitter: TxSubmitter) -> Self {
+        Self { tx_submitter, ..self }
     }
 
     /// Sets the event query configuration.
@@ -242,15 +321,13 @@ impl<P: Provider> BoundlessMarketService<P> {
     }
 
     /// Retry count for confirmed transactions receipts.
-    pub fn with_receipt_retry_count(mut self, count: usize) -> Self {
-        self.receipt_query_config.retry_count = count;
-        self
+    pub fn with_receipt_retry_count(self, count: usize) -> Self {
+        Self { tx_submitter: self.tx_submitter.with_receipt_retry_count(count), ..self }
     }
 
     /// Retry polling interval for confirmed transactions receipts.
-    pub fn with_receipt_retry_interval(mut self, interval: Duration) -> Self {
-        self.receipt_query_config.retry_interval = interval;
-        self
+    pub fn with_receipt_retry_interval(self, interval: Duration) -> Self {
+        Self { tx_submitter: self.tx_submitter.with_receipt_retry_interval(interval), ..self }
     }
 
     /// Returns the market contract instance.
@@ -277,7 +354,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         let pending_tx = call.send().await?;
         tracing::debug!("Broadcasting deposit tx {}", pending_tx.tx_hash());
         let tx_hash = pending_tx
-            .with_timeout(Some(self.timeout))
+            .with_timeout(Some(self.tx_submitter.timeout()))
             .watch()
             .await
             .context("failed to confirm tx")?;
@@ -293,7 +370,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         let pending_tx = call.send().await?;
         tracing::debug!("Broadcasting withdraw tx {}", pending_tx.tx_hash());
         let tx_hash = pending_tx
-            .with_timeout(Some(self.timeout))
+            .with_timeout(Some(self.tx_submitter.timeout()))
             .watch()
             .await
             .context("failed to confirm tx")?;
@@ -342,7 +419,8 @@ impl<P: Provider> BoundlessMarketService<P> {
             request.id
         );
 
-        let receipt = self.get_receipt_with_retry(pending_tx).await?;
+        let receipt =
+            self.tx_submitter.get_receipt_with_retry(self.instance.provider(), pending_tx).await?;
 
         // Look for the logs for submitting the transaction.
         let log = extract_tx_log::<IBoundlessMarket::RequestSubmitted>(&receipt)?;
@@ -366,7 +444,8 @@ impl<P: Provider> BoundlessMarketService<P> {
             request.id
         );
 
-        let receipt = self.get_receipt_with_retry(pending_tx).await?;
+        let receipt =
+            self.tx_submitter.get_receipt_with_retry(self.instance.provider(), pending_tx).await?;
 
         // Look for the logs for submitting the transaction.
         let log = extract_tx_log::<IBoundlessMarket::RequestSubmitted>(&receipt)?;
@@ -405,6 +484,18 @@ impl<P: Provider> BoundlessMarketService<P> {
         client_sig: impl Into<Bytes>,
         priority_gas: Option<u64>,
     ) -> Result<u64, MarketError> {
+        let receipt = self.lock_request_receipt(request, client_sig, priority_gas).await?;
+        Ok(receipt.block_number.context("TXN Receipt missing block number")?)
+    }
+
+    /// Lock the request to the prover, as [Self::lock_request], returning the receipt of the lock
+    /// transaction.
+    pub async fn lock_request_receipt(
+        &self,
+        request: &ProofRequest,
+        client_sig: impl Into<Bytes>,
+        priority_gas: Option<u64>,
+    ) -> Result<TransactionReceipt, MarketError> {
         tracing::trace!("Calling requestIsLocked({:x})", request.id);
         let is_locked_in: bool =
             self.instance.requestIsLocked(request.id).call().await.context("call failed")?;
@@ -415,34 +506,12 @@ impl<P: Provider> BoundlessMarketService<P> {
         let client_sig_bytes = client_sig.into();
         tracing::trace!("Calling lockRequest({:x?}, {:x?})", request, client_sig_bytes);
 
-        let mut call =
-            self.instance.lockRequest(request.clone(), client_sig_bytes).from(self.caller);
-
-        if let Some(gas) = priority_gas {
-            let priority_fee = self
-                .instance
-                .provider()
-                .estimate_eip1559_fees()
-                .await
-                .context("Failed to get priority gas fee")?;
-
-            call = call
-                .max_fee_per_gas(priority_fee.max_fee_per_gas + gas as u128)
-                .max_priority_fee_per_gas(priority_fee.max_priority_fee_per_gas + gas as u128);
-        }
-
-        tracing::trace!("Sending tx {}", format!("{:?}", call));
-        let pending_tx = call.send().await?;
-
-        let tx_hash = *pending_tx.tx_hash();
-        tracing::trace!("Broadcasting lock request tx {}", tx_hash);
-
-        let receipt = self.get_receipt_with_retry(pending_tx).await?;
-
-        if !receipt.status() {
+        let call = self.instance.lockRequest(request.clone(), client_sig_bytes).from(self.caller);
+        let receipt = self
+            .submit_private(call.into_transaction_request(), priority_gas.map(u128::from))
+            .await?
             // TODO: Get + print revertReason
-            return Err(MarketError::LockRevert(receipt.transaction_hash));
-        }
+            .confirmed_or(MarketError::LockRevert)?;
 
         tracing::info!(
             "Locked request {:x}, transaction hash: {}",
@@ -452,7 +521,7 @@ impl<P: Provider> BoundlessMarketService<P> {
 
         self.check_stake_balance().await?;
 
-        Ok(receipt.block_number.context("TXN Receipt missing block number")?)
+        Ok(receipt)
     }
 
     /// Lock the request to the prover, giving them exclusive rights to be paid to
@@ -467,8 +536,23 @@ impl<P: Provider> BoundlessMarketService<P> {
         request: &ProofRequest,
         client_sig: impl Into<Bytes>,
         prover_sig: impl Into<Bytes>,
-        _priority_gas: Option<u128>,
+        priority_gas: Option<u128>,
     ) -> Result<u64, MarketError> {
+        let receipt = self
+            .lock_request_with_signature_receipt(request, client_sig, prover_sig, priority_gas)
+            .await?;
+        Ok(receipt.block_number.context("TXN Receipt missing block number")?)
+    }
+
+    /// Lock the request to the prover with the given prover signature, as
+    /// [Self::lock_request_with_signature], returning the receipt of the lock transaction.
+    pub async fn lock_request_with_signature_receipt(
+        &self,
+        request: &ProofRequest,
+        client_sig: impl Into<Bytes>,
+        prover_sig: impl Into<Bytes>,
+        priority_gas: Option<u128>,
+    ) -> Result<TransactionReceipt, MarketError> {
         tracing::trace!("Calling requestIsLocked({:x})", request.id);
         let is_locked_in: bool =
             self.instance.requestIsLocked(request.id).call().await.context("call failed")?;
@@ -489,14 +573,11 @@ impl<P: Provider> BoundlessMarketService<P> {
             .instance
             .lockRequestWithSignature(request.clone(), client_sig_bytes.clone(), prover_sig_bytes)
             .from(self.caller);
-        let pending_tx = call.send().await.context("Failed to lock")?;
-        tracing::trace!("Broadcasting lock request with signature tx {}", pending_tx.tx_hash());
-
-        let receipt = self.get_receipt_with_retry(pending_tx).await?;
-        if !receipt.status() {
+        let receipt = self
+            .submit_private(call.into_transaction_request(), priority_gas)
+            .await?
             // TODO: Get + print revertReason
-            return Err(MarketError::LockRevert(receipt.transaction_hash));
-        }
+            .confirmed_or(MarketError::LockRevert)?;
 
         tracing::info!(
             "Locked request {:x}, transaction hash: {}",
@@ -504,59 +585,27 @@ impl<P: Provider> BoundlessMarketService<P> {
             receipt.transaction_hash
         );
 
-        Ok(receipt.block_number.context("TXN Receipt missing block number")?)
+        Ok(receipt)
     }
 
-    async fn get_receipt_with_retry(
+    /// Submits the transaction with the [TxSubmitter], through its private relay if set, adding
+    /// `priority_gas` wei to the estimated fees.
+    async fn submit_private(
         &self,
-        pending_tx: PendingTransactionBuilder<Ethereum>,
-    ) -> Result<TransactionReceipt, MarketError> {
-        let tx_hash = *pending_tx.tx_hash();
-
-        // Get the nonce of the transaction for debugging purposes.
-        // It is possible that the transaction is not found immediately after broadcast, so we don't error if it's not found.
-        let tx_result = self.instance.provider().get_transaction_by_hash(tx_hash).await;
-        if let Ok(Some(tx)) = tx_result {
-            let nonce = tx.nonce();
-            tracing::debug!("Tx {} broadcasted with nonce {}", tx_hash, nonce);
-        } else {
-            tracing::debug!(
-                "Tx {} not found immediately after broadcast. Can't get nonce.",
-                tx_hash
-            );
-        }
-
-        match pending_tx.with_timeout(Some(self.timeout)).get_receipt().await {
-            Ok(receipt) => Ok(receipt),
-            Err(PendingTransactionError::TransportError(err)) if err.is_null_resp() => {
-                tracing::debug!("failed to query receipt of confirmed transaction, retrying");
-                // There is a race condition with some providers where a transaction will be
-                // confirmed through the RPC, but querying the receipt returns null when requested
-                // immediately after.
-                for _ in 0..self.receipt_query_config.retry_count {
-                    if let Ok(Some(receipt)) =
-                        self.instance.provider().get_transaction_receipt(tx_hash).await
-                    {
-                        return Ok(receipt);
-                    }
-
-                    tokio::time::sleep(self.receipt_query_config.retry_interval).await;
-                }
+        tx: TransactionRequest,
+        priority_gas: Option<u128>,
+    ) -> Result<TxOutcome, MarketError> {
+        self.tx_submitter.submit_private(self.instance.provider(), tx, priority_gas).await
+    }
 
-                Err(anyhow!(
-                    "Transaction {:?} confirmed, but receipt was not found after {} retries.",
-                    tx_hash,
-                    self.receipt_query_config.retry_count
-                )
-                .into())
-            }
-            Err(e) => Err(MarketError::TxnConfirmationError(anyhow!(
-                "failed to confirm tx {:?} within timeout {:?}: {}",
-                tx_hash,
-                self.timeout,
-                e
-            ))),
-        }
+    /// Submits the transaction with the [TxSubmitter], adding `priority_gas` wei to the estimated
+    /// fees.
+    async fn submit(
+        &self,
+        tx: TransactionRequest,
+        priority_gas: Option<u128>,
+    ) -> Result<TxOutcome, MarketError> {
+        self.tx_submitter.submit(self.instance.provider(), tx, priority_gas).await
     }
 
     /// When a prover fails to fulfill a request by the deadline, this function can be used to burn
@@ -574,7 +623,8 @@ impl<P: Provider> BoundlessMarketService<P> {
         let pending_tx = call.send().await?;
         tracing::debug!("Broadcasting tx {}", pending_tx.tx_hash());
 
-        let receipt = self.get_receipt_with_retry(pending_tx).await?;
+        let receipt =
+            self.tx_submitter.get_receipt_with_retry(self.instance.provider(), pending_tx).await?;
 
         if !receipt.status() {
             return Err(MarketError::SlashRevert(receipt.transaction_hash));
@@ -586,6 +636,16 @@ impl<P: Provider> BoundlessMarketService<P> {
 
     /// Submits a `FulfillmentTx`.
     pub async fn fulfill(&self, tx: FulfillmentTx) -> Result<(), MarketError> {
+        self.fulfill_with_receipt(tx).await?;
+        Ok(())
+    }
+
+    /// Submits a `FulfillmentTx`, returning the receipt of the transaction, e.g. to account for
+    /// the gas it used.
+    pub async fn fulfill_with_receipt(
+        &self,
+        tx: FulfillmentTx,
+    ) -> Result<TransactionReceipt, MarketError> {
         let FulfillmentTx { root, unlocked_requests, fulfillments, assessor_receipt, withdraw } =
             tx;
         let price = !unlocked_requests.is_empty();
@@ -645,19 +705,19 @@ impl<P: Provider> BoundlessMarketService<P> {
         &self,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         let fill_ids = fulfillments.iter().map(|fill| fill.id).collect::<Vec<_>>();
         tracing::trace!("Calling fulfill({fulfillments:?}, {assessor_fill:?})");
         let call = self.instance.fulfill(fulfillments, assessor_fill).from(self.caller);
         tracing::trace!("Calldata: {:x}", call.calldata());
-        let pending_tx = call.send().await?;
-        tracing::debug!("Broadcasting tx {}", pending_tx.tx_hash());
-
-        let receipt = self.get_receipt_with_retry(pending_tx).await?;
+        let receipt = self
+            .submit(call.into_transaction_request(), None)
+            .await?
+            .confirmed_or(MarketError::FulfillRevert)?;
 
         tracing::info!("Submitted proof for batch {:?}: {}", fill_ids, receipt.transaction_hash);
 
-        Ok(())
+        Ok(receipt)
     }
 
     /// Fulfill a batch of requests by delivering the proof for each application and withdraw from the prover balance.
@@ -667,19 +727,19 @@ impl<P: Provider> BoundlessMarketService<P> {
         &self,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         let fill_ids = fulfillments.iter().map(|fill| fill.id).collect::<Vec<_>>();
         tracing::trace!("Calling fulfillAndWithdraw({fulfillments:?}, {assessor_fill:?})");
         let call = self.instance.fulfillAndWithdraw(fulfillments, assessor_fill).from(self.caller);
         tracing::trace!("Calldata: {:x}", call.calldata());
-        let pending_tx = call.send().await?;
-        tracing::debug!("Broadcasting tx {}", pending_tx.tx_hash());
-
-        let receipt = self.get_receipt_with_retry(pending_tx).await?;
+        let receipt = self
+            .submit(call.into_transaction_request(), None)
+            .await?
+            .confirmed_or(MarketError::FulfillRevert)?;
 
         tracing::info!("Submitted proof for batch {:?}: {}", fill_ids, receipt.transaction_hash);
 
-        Ok(())
+        Ok(receipt)
     }
 
     /// Combined function to submit a new merkle root to the set-verifier and call `fulfill`.
@@ -689,7 +749,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         root: Root,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         tracing::trace!(
             "Calling submitRootAndFulfill({:?}, {:x}, {fulfillments:?}, {assessor_fill:?})",
             root.root,
@@ -706,13 +766,14 @@ impl<P: Provider> BoundlessMarketService<P> {
             )
             .from(self.caller);
         tracing::trace!("Calldata: {}", call.calldata());
-        let pending_tx = call.send().await?;
-        tracing::debug!("Broadcasting tx {}", pending_tx.tx_hash());
-        let tx_receipt = self.get_receipt_with_retry(pending_tx).await?;
+        let tx_receipt = self
+            .submit(call.into_transaction_request(), None)
+            .await?
+            .confirmed_or(MarketError::FulfillRevert)?;
 
         tracing::info!("Submitted merkle root and proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// Combined function to submit a new merkle root to the set-verifier and call `fulfillAndWithdraw`.
@@ -722,7 +783,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         root: Root,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         tracing::trace!("Calling submitRootAndFulfillAndWithdraw({:?}, {:x}, {fulfillments:?}, {assessor_fill:?})", root.root, root.seal);
         let call = self
             .instance
@@ -735,13 +796,14 @@ impl<P: Provider> BoundlessMarketService<P> {
             )
             .from(self.caller);
         tracing::trace!("Calldata: {}", call.calldata());
-        let pending_tx = call.send().await?;
-        tracing::debug!("Broadcasting tx {}", pending_tx.tx_hash());
-        let tx_receipt = self.get_receipt_with_retry(pending_tx).await?;
+        let tx_receipt = self
+            .submit(call.into_transaction_request(), None)
+            .await?
+            .confirmed_or(MarketError::FulfillRevert)?;
 
         tracing::info!("Submitted merkle root and proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// A combined call to `IBoundlessMarket.priceRequest` and `IBoundlessMarket.fulfill`.
@@ -753,38 +815,25 @@ impl<P: Provider> BoundlessMarketService<P> {
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
         priority_gas: Option<u64>,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         tracing::trace!("Calling priceAndFulfill({fulfillments:?}, {assessor_fill:?})");
 
         let (requests, client_sigs): (Vec<_>, Vec<_>) =
             unlocked_requests.into_iter().map(|ur| (ur.request, ur.client_sig)).unzip();
-        let mut call = self
+        let call = self
             .instance
             .priceAndFulfill(requests, client_sigs, fulfillments, assessor_fill)
             .from(self.caller);
         tracing::trace!("Calldata: {}", call.calldata());
 
-        if let Some(gas) = priority_gas {
-            let priority_fee = self
-                .instance
-                .provider()
-                .estimate_eip1559_fees()
-                .await
-                .context("Failed to get priority gas fee")?;
-
-            call = call
-                .max_fee_per_gas(priority_fee.max_fee_per_gas + gas as u128)
-                .max_priority_fee_per_gas(priority_fee.max_priority_fee_per_gas + gas as u128);
-        }
-
-        let pending_tx = call.send().await?;
-        tracing::debug!("Broadcasting tx {}", pending_tx.tx_hash());
-
-        let tx_receipt = self.get_receipt_with_retry(pending_tx).await?;
+        let tx_receipt = self
+            .submit(call.into_transaction_request(), priority_gas.map(u128::from))
+            .await?
+            .confirmed_or(MarketError::FulfillRevert)?;
 
         tracing::info!("Fulfilled proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// A combined call to `IBoundlessMarket.priceRequest` and `IBoundlessMarket.fulfillAndWithdraw`.
@@ -796,38 +845,25 @@ impl<P: Provider> BoundlessMarketService<P> {
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
         priority_gas: Option<u64>,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         tracing::trace!("Calling priceAndFulfillAndWithdraw({fulfillments:?}, {assessor_fill:?})");
 
         let (requests, client_sigs): (Vec<_>, Vec<_>) =
             unlocked_requests.into_iter().map(|ur| (ur.request, ur.client_sig)).unzip();
-        let mut call = self
+        let call = self
             .instance
             .priceAndFulfillAndWithdraw(requests, client_sigs, fulfillments, assessor_fill)
             .from(self.caller);
         tracing::trace!("Calldata: {}", call.calldata());
 
-        if let Some(gas) = priority_gas {
-            let priority_fee = self
-                .instance
-                .provider()
-                .estimate_eip1559_fees()
-                .await
-                .context("Failed to get priority gas fee")?;
-
-            call = call
-                .max_fee_per_gas(priority_fee.max_fee_per_gas + gas as u128)
-                .max_priority_fee_per_gas(priority_fee.max_priority_fee_per_gas + gas as u128);
-        }
-
-        let pending_tx = call.send().await?;
-        tracing::debug!("Broadcasting tx {}", pending_tx.tx_hash());
-
-        let tx_receipt = self.get_receipt_with_retry(pending_tx).await?;
+        let tx_receipt = self
+            .submit(call.into_transaction_request(), priority_gas.map(u128::from))
+            .await?
+            .confirmed_or(MarketError::FulfillRevert)?;
 
         tracing::info!("Fulfilled proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// Combined function to submit a new merkle root to the set-verifier and call `priceAndfulfill`.
@@ -838,7 +874,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         unlocked_requests: Vec<UnlockedRequest>,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         let (requests, client_sigs): (Vec<_>, Vec<_>) =
             unlocked_requests.into_iter().map(|ur| (ur.request, ur.client_sig)).unzip();
         tracing::trace!("Calling submitRootAndPriceAndFulfill({:?}, {:x}, {:?}, {:?}, {fulfillments:?}, {assessor_fill:?})", root.root, root.seal, requests, client_sigs);
@@ -855,17 +891,14 @@ impl<P: Provider> BoundlessMarketService<P> {
             )
             .from(self.caller);
         tracing::trace!("Calldata: {}", call.calldata());
-        let pending_tx = call.send().await?;
-        tracing::debug!("Broadcasting tx {}", pending_tx.tx_hash());
-        let tx_receipt = pending_tx
-            .with_timeout(Some(self.timeout))
-            .get_receipt()
-            .await
-            .context("failed to confirm tx")?;
+        let tx_receipt = self
+            .submit(call.into_transaction_request(), None)
+            .await?
+            .confirmed_or(MarketError::FulfillRevert)?;
 
         tracing::info!("Submitted merkle root and proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// Combined function to submit a new merkle root to the set-verifier and call `priceAndFulfillAndWithdraw`.
@@ -876,7 +909,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         unlocked_requests: Vec<UnlockedRequest>,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         let (requests, client_sigs): (Vec<_>, Vec<_>) =
             unlocked_requests.into_iter().map(|ur| (ur.request, ur.client_sig)).unzip();
         tracing::trace!("Calling submitRootAndPriceAndFulfillAndWithdraw({:?}, {:x}, {:?}, {:?}, {fulfillments:?}, {assessor_fill:?})", root.root, root.seal, requests, client_sigs);
@@ -893,17 +926,14 @@ impl<P: Provider> BoundlessMarketService<P> {
             )
             .from(self.caller);
         tracing::trace!("Calldata: {}", call.calldata());
-        let pending_tx = call.send().await?;
-        tracing::debug!("Broadcasting tx {}", pending_tx.tx_hash());
-        let tx_receipt = pending_tx
-            .with_timeout(Some(self.timeout))
-            .get_receipt()
-            .await
-            .context("failed to confirm tx")?;
+        let tx_receipt = self
+            .submit(call.into_transaction_request(), None)
+            .await?
+            .confirmed_or(MarketError::FulfillRevert)?;
 
         tracing::info!("Submitted merkle root and proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// Checks if a request is locked in.
@@ -1093,16 +1123,27 @@ impl<P: Provider> BoundlessMarketService<P> {
         &self,
         request_id: U256,
     ) -> Result<(Bytes, Bytes), MarketError> {
+        if let Some(fulfillment) = self.cached_fulfillment(request_id) {
+            return Ok(fulfillment);
+        }
         match self.get_status(request_id, None).await? {
             RequestStatus::Expired => Err(MarketError::RequestHasExpired(request_id)),
-            RequestStatus::Fulfilled => {
-                let (journal, seal, _) = self.query_fulfilled_event(request_id, None, None).await?;
-                Ok((journal, seal))
-            }
+            RequestStatus::Fulfilled => self.fetch_fulfillment(request_id).await,
             _ => Err(MarketError::RequestNotFulfilled(request_id)),
         }
     }
 
+    fn cached_fulfillment(&self, request_id: U256) -> Option<(Bytes, Bytes)> {
+        self.fulfillments.lock().unwrap().get(&request_id).cloned()
+    }
+
+    /// Fetch the journal and seal of a fulfilled request from its event, caching them.
+    async fn fetch_fulfillment(&self, request_id: U256) -> Result<(Bytes, Bytes), MarketError> {
+        let (journal, seal, _) = self.query_fulfilled_event(request_id, None, None).await?;
+        self.fulfillments.lock().unwrap().insert(request_id, (journal.clone(), seal.clone()));
+        Ok((journal, seal))
+    }
+
     /// Returns the prover address for a request that is fulfilled.
     pub async fn get_request_fulfillment_prover(
         &self,
@@ -1144,37 +1185,107 @@ impl<P: Provider> BoundlessMarketService<P> {
     /// Returns journal and seal if the request is fulfilled.
     ///
     /// This method will poll the status of the request until it is Fulfilled or Expired.
-    /// Polling is done at intervals of `retry_interval` until the request is Fulfilled, Expired or
-    /// the optional timeout is reached.
+    /// Polling is done at intervals of `retry_interval` until the request is Fulfilled or Expired.
     pub async fn wait_for_request_fulfillment(
         &self,
         request_id: U256,
         retry_interval: Duration,
         expires_at: u64,
     ) -> Result<(Bytes, Bytes), MarketError> {
+        let opts = FulfillmentWaitOptions::default()
+            .with_initial_interval(retry_interval)
+            .with_max_interval(retry_interval)
+            .with_multiplier(1.0)
+            .with_expires_at(expires_at);
+        self.wait_for_fulfillment(request_id, opts).await
+    }
+
+    /// Returns journal and seal once the request is fulfilled.
+    ///
+    /// Polls the status of the request with exponential backoff, as configured by `opts`,
+    /// until it is Fulfilled or Expired, or the timeout is reached. Fulfillments are cached, so
+    /// waiting again for a fulfilled request returns without querying the chain.
+    pub async fn wait_for_fulfillment(
+        &self,
+        request_id: U256,
+        opts: FulfillmentWaitOptions,
+    ) -> Result<(Bytes, Bytes), MarketError> {
+        if let Some(fulfillment) = self.cached_fulfillment(request_id) {
+            return Ok(fulfillment);
+        }
+        let deadline = opts.timeout.map(|timeout| Instant::now() + timeout);
+        let mut interval = opts.initial_interval;
         loop {
-            let status = self.get_status(request_id, Some(expires_at)).await?;
+            let status = self.get_status(request_id, opts.expires_at).await?;
             match status {
                 RequestStatus::Expired => return Err(MarketError::RequestHasExpired(request_id)),
-                RequestStatus::Fulfilled => {
-                    let (journal, seal, _) =
-                        self.query_fulfilled_event(request_id, None, None).await?;
-                    return Ok((journal, seal));
-                }
+                RequestStatus::Fulfilled => return self.fetch_fulfillment(request_id).await,
                 _ => {
+                    let mut sleep = interval;
+                    if let Some(deadline) = deadline {
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        if remaining.is_zero() {
+                            return Err(MarketError::TimeoutReached(request_id));
+                        }
+                        sleep = sleep.min(remaining);
+                    }
                     tracing::info!(
                         "Request {:x} status: {:?}. Retrying in {:?}",
                         request_id,
                         status,
-                        retry_interval
+                        sleep
                     );
-                    tokio::time::sleep(retry_interval).await;
-                    continue;
+                    tokio::time::sleep(sleep).await;
+                    interval = opts.next_interval(interval);
                 }
             }
         }
     }
 
+    /// Returns journal and seal once the request is fulfilled, watching for its fulfillment event.
+    ///
+    /// Unlike [Self::wait_for_fulfillment], the status of the request is only checked once: a
+    /// filter for the [IBoundlessMarket::ProofDelivered] events of the request is installed first,
+    /// then the journal and seal are returned from the first event delivered. Expiration of the
+    /// request is only detected by the initial check, after which [MarketError::TimeoutReached]
+    /// is returned once `timeout` passes.
+    pub async fn watch_fulfillment(
+        &self,
+        request_id: U256,
+        timeout: Duration,
+    ) -> Result<(Bytes, Bytes), MarketError> {
+        if let Some(fulfillment) = self.cached_fulfillment(request_id) {
+            return Ok(fulfillment);
+        }
+
+        // Install the filter before checking the status, so a fulfillment landing in between is
+        // delivered by the filter.
+        let mut event_filter = self.instance.ProofDelivered_filter();
+        event_filter.filter = event_filter.filter.topic1(request_id);
+        let mut events = event_filter
+            .watch()
+            .await
+            .context("Failed to install ProofDelivered filter")?
+            .into_stream();
+
+        match self.get_status(request_id, None).await? {
+            RequestStatus::Expired => return Err(MarketError::RequestHasExpired(request_id)),
+            RequestStatus::Fulfilled => return self.fetch_fulfillment(request_id).await,
+            status => tracing::debug!("Request {request_id:x} status: {status:?}. Watching"),
+        }
+
+        let event = match tokio::time::timeout(timeout, events.next()).await {
+            Ok(Some(event)) => event.context("Failed to decode ProofDelivered event")?.0,
+            Ok(None) => {
+                return Err(MarketError::Error(anyhow!("ProofDelivered filter was uninstalled")))
+            }
+            Err(_) => return Err(MarketError::TimeoutReached(request_id)),
+        };
+        let fulfillment = (event.fulfillment.journal, event.fulfillment.seal);
+        self.fulfillments.lock().unwrap().insert(request_id, fulfillment.clone());
+        Ok(fulfillment)
+    }
+
     /// Generates a request index based on the EOA nonce.
     ///
     /// It does not guarantee that the index is not in use by the time the caller uses it.
@@ -1266,7 +1377,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         let pending_tx = call.send().await.map_err(IHitPointsErrors::decode_error)?;
         tracing::debug!("Broadcasting tx {}", pending_tx.tx_hash());
         let tx_hash = pending_tx
-            .with_timeout(Some(self.timeout))
+            .with_timeout(Some(self.tx_submitter.timeout()))
             .watch()
             .await
             .context("failed to confirm tx")?;
@@ -1286,7 +1397,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         let pending_tx = call.send().await?;
         tracing::debug!("Broadcasting stake deposit tx {}", pending_tx.tx_hash());
         let tx_hash = pending_tx
-            .with_timeout(Some(self.timeout))
+            .with_timeout(Some(self.tx_submitter.timeout()))
             .watch()
             .await
             .context("failed to confirm tx")?;
@@ -1338,7 +1449,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         let pending_tx = call.send().await?;
         tracing::debug!("Broadcasting stake deposit tx {}", pending_tx.tx_hash());
         let tx_hash = pending_tx
-            .with_timeout(Some(self.timeout))
+            .with_timeout(Some(self.tx_submitter.timeout()))
             .watch()
             .await
             .context("failed to confirm tx")?;
@@ -1353,7 +1464,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         let pending_tx = call.send().await?;
         tracing::debug!("Broadcasting stake withdraw tx {}", pending_tx.tx_hash());
         let tx_hash = pending_tx
-            .with_timeout(Some(self.timeout))
+            .with_timeout(Some(self.tx_submitter.timeout()))
             .watch()
             .await
             .context("failed to confirm tx")?;
@@ -1585,8 +1696,10 @@ impl FulfillmentTx {
 
 #[cfg(test)]
 mod tests {
+    use super::FulfillmentWaitOptions;
     use crate::contracts::Offer;
     use alloy::primitives::{utils::parse_ether, U256};
+    use std::time::Duration;
     fn ether(value: &str) -> U256 {
         parse_ether(value).unwrap()
     }
@@ -1640,4 +1753,22 @@ mod tests {
         // Price cannot exceed maxPrice
         assert!(offer.time_at_price(ether("3")).is_err());
     }
+
+    #[test]
+    fn test_fulfillment_wait_backoff() {
+        let opts = FulfillmentWaitOptions::default()
+            .with_initial_interval(Duration::from_secs(1))
+            .with_max_interval(Duration::from_secs(5));
+        let mut interval = opts.initial_interval;
+        let mut intervals = vec![];
+        for _ in 0..5 {
+            intervals.push(interval.as_secs());
+            interval = opts.next_interval(interval);
+        }
+        assert_eq!(intervals, [1, 2, 4, 5, 5]);
+
+        // A multiplier below 1 does not shrink the interval
+        let opts = opts.with_multiplier(0.5);
+        assert_eq!(opts.next_interval(Duration::from_secs(2)), Duration::from_secs(2));
+    }
 }
@@ -0,0 +1,202 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, future::Future, sync::Arc};
+
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, Bytes, B256},
+    providers::Provider,
+    signers::Error as SignerErr,
+};
+use futures_util::future::BoxFuture;
+
+use super::{eip712_domain, ProofRequest, RequestError};
+
+#[allow(missing_docs)]
+mod interface {
+    alloy::sol! {
+        #[sol(rpc)]
+        interface IERC1271 {
+            function isValidSignature(bytes32 hash, bytes memory signature) external view returns (bytes4 magicValue);
+        }
+    }
+}
+pub use interface::IERC1271;
+
+/// Value returned by `isValidSignature` when the signature is valid.
+pub const ERC1271_MAGIC_VALUE: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+type SignFn = dyn Fn(B256) -> BoxFuture<'static, anyhow::Result<Bytes>> + Send + Sync;
+
+#[derive(Clone)]
+enum ContractSignature {
+    Prepared(Bytes),
+    Callback(Arc<SignFn>),
+}
+
+/// Signer of proof requests on behalf of a smart contract wallet.
+///
+/// Requests signed by a smart contract have the smart contract signed flag set on their ID, and
+/// their signature is checked by calling `isValidSignature` on the client address, as specified
+/// by ERC-1271. As the signature format is up to the contract, the signature is either prepared
+/// ahead of time, or produced by a callback given the EIP-712 signing hash of the request.
+#[derive(Clone)]
+pub struct SmartContractSigner {
+    address: Address,
+    signature: ContractSignature,
+}
+
+impl SmartContractSigner {
+    /// Creates a signer returning the given signature, prepared for a single request.
+    ///
+    /// The signature must be for the request as submitted, that is with the smart contract signed
+    /// flag set on its ID, see [ProofRequest::with_smart_contract_signed_flag].
+    pub fn prepared(address: Address, signature: impl Into<Bytes>) -> Self {
+        Self { address, signature: ContractSignature::Prepared(signature.into()) }
+    }
+
+    /// Creates a signer calling `sign` with the EIP-712 signing hash of each request.
+    pub fn from_fn<F, Fut>(address: Address, sign: F) -> Self
+    where
+        F: Fn(B256) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<Bytes>> + Send + 'static,
+    {
+        let sign: Arc<SignFn> = Arc::new(move |hash| Box::pin(sign(hash)));
+        Self { address, signature: ContractSignature::Callback(sign) }
+    }
+
+    /// Address of the smart contract wallet.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Signs the request with the EIP-712 domain derived from the given contract address and
+    /// chain ID.
+    ///
+    /// The request must have the smart contract signed flag set, and the signer as its client.
+    pub async fn sign_request(
+        &self,
+        request: &ProofRequest,
+        contract_addr: Address,
+        chain_id: u64,
+    ) -> Result<Bytes, RequestError> {
+        if !request.is_smart_contract_signed() {
+            return Err(RequestError::NotSmartContractSigned);
+        }
+        if request.client_address() != self.address {
+            return Err(SignerErr::other(format!(
+                "request client {} is not the smart contract signer {}",
+                request.client_address(),
+                self.address
+            ))
+            .into());
+        }
+        match &self.signature {
+            ContractSignature::Prepared(signature) => Ok(signature.clone()),
+            ContractSignature::Callback(sign) => {
+                let hash = request.signing_hash(contract_addr, chain_id)?;
+                sign(hash).await.map_err(|err| SignerErr::other(err).into())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for SmartContractSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let signature = match &self.signature {
+            ContractSignature::Prepared(_) => "prepared",
+            ContractSignature::Callback(_) => "callback",
+        };
+        f.debug_struct("SmartContractSigner")
+            .field("address", &self.address)
+            .field("signature", &signature)
+            .finish()
+    }
+}
+
+impl ProofRequest {
+    /// Verifies the ERC-1271 signature of the request by calling `isValidSignature` on its client,
+    /// with the EIP-712 domain derived from the given contract address and chain ID.
+    pub async fn verify_erc1271_signature<P: Provider<Ethereum>>(
+        &self,
+        signature: &Bytes,
+        contract_addr: Address,
+        chain_id: u64,
+        provider: P,
+    ) -> Result<(), RequestError> {
+        if !self.is_smart_contract_signed() {
+            return Err(RequestError::NotSmartContractSigned);
+        }
+        let domain = eip712_domain(contract_addr, chain_id);
+        let hash = self.eip712_signing_hash(&domain.alloy_struct());
+        let magic_value = IERC1271::new(self.client_address(), provider)
+            .isValidSignature(hash, signature.clone())
+            .call()
+            .await
+            .map_err(SignerErr::other)?;
+        if magic_value != ERC1271_MAGIC_VALUE {
+            return Err(SignerErr::other(format!(
+                "isValidSignature of {} returned 0x{magic_value:x}",
+                self.client_address()
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::{Offer, Predicate, RequestId, RequestInput, Requirements};
+    use alloy::primitives::address;
+    use risc0_zkvm::sha::Digest;
+
+    fn request(id: RequestId) -> ProofRequest {
+        ProofRequest::new(
+            id,
+            Requirements::new(Digest::from([1u32; 8]), Predicate::prefix_match([])),
+            "https://dev.null",
+            RequestInput::builder().build_inline().unwrap(),
+            Offer::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn sign_request() {
+        let wallet = address!("0x0000000000000000000000000000000000000001");
+        let market = Address::ZERO;
+        let signer = SmartContractSigner::from_fn(wallet, |hash| async move {
+            Ok(Bytes::copy_from_slice(hash.as_slice()))
+        });
+
+        // The flag must be set on the request ID
+        let unflagged = request(RequestId::new(wallet, 0));
+        let err = signer.sign_request(&unflagged, market, 1).await.unwrap_err();
+        assert!(matches!(err, RequestError::NotSmartContractSigned));
+
+        let flagged = unflagged.with_smart_contract_signed_flag();
+        let signature = signer.sign_request(&flagged, market, 1).await.unwrap();
+        assert_eq!(signature.as_ref(), flagged.signing_hash(market, 1).unwrap().as_slice());
+
+        // The signer must be the client of the request
+        let other = request(RequestId::new(Address::with_last_byte(2), 0))
+            .with_smart_contract_signed_flag();
+        signer.sign_request(&other, market, 1).await.unwrap_err();
+
+        let prepared = SmartContractSigner::prepared(wallet, [0xaa; 4]);
+        assert_eq!(prepared.sign_request(&flagged, market, 1).await.unwrap().as_ref(), [0xaa; 4]);
+    }
+}
@@ -28,7 +28,7 @@ use alloy_primitives::{
     aliases::{U160, U32, U96},
     Address, Bytes, FixedBytes, B256, U256,
 };
-use alloy_sol_types::{eip712_domain, Eip712Domain};
+use alloy_sol_types::{eip712_domain, Eip712Domain, SolValue};
 use serde::{Deserialize, Serialize};
 #[cfg(not(target_os = "zkvm"))]
 use std::time::Duration;
@@ -346,6 +346,10 @@ pub enum RequestError {
     /// Request digest mismatch.
     #[error("request digest mismatch")]
     DigestMismatch,
+
+    /// The request ID does not have the smart contract signed flag set.
+    #[error("request ID does not have the smart contract signed flag set")]
+    NotSmartContractSigned,
 }
 
 #[cfg(not(target_os = "zkvm"))]
@@ -414,6 +418,12 @@ impl ProofRequest {
         RequestId::from_lossy(self.id).smart_contract_signed
     }
 
+    /// Returns the request with the smart contract signed flag set on its ID, so that its
+    /// signature is checked with ERC-1271 against the client address.
+    pub fn with_smart_contract_signed_flag(self) -> Self {
+        Self { id: RequestId::from_lossy(self.id).set_smart_contract_signed_flag().into(), ..self }
+    }
+
     /// Check that the request is valid and internally consistent.
     ///
     /// If any field are empty, or if two fields conflict (e.g. the max price is less than the min
@@ -477,6 +487,25 @@ impl ProofRequest {
         Ok(signer.sign_hash(&hash).await?)
     }
 
+    /// Signs a [LockRequest] for this request with the given signer and EIP-712 domain derived
+    /// from the given contract address and chain ID.
+    ///
+    /// The resulting signature authorizes locking the request on behalf of the signer, e.g. with
+    /// [BoundlessMarketService::lock_request_with_signature] sent from a different address.
+    ///
+    /// [BoundlessMarketService::lock_request_with_signature]: crate::BoundlessMarketService::lock_request_with_signature
+    pub async fn sign_lock_request(
+        &self,
+        signer: &impl Signer,
+        contract_addr: Address,
+        chain_id: u64,
+    ) -> Result<Signature, RequestError> {
+        let domain = eip712_domain(contract_addr, chain_id);
+        let hash =
+            LockRequest { request: self.clone() }.eip712_signing_hash(&domain.alloy_struct());
+        Ok(signer.sign_hash(&hash).await?)
+    }
+
     /// Returns the EIP-712 signing hash for the request.
     pub fn signing_hash(
         &self,
@@ -568,6 +597,55 @@ impl Predicate {
     pub fn prefix_match(prefix: impl Into<Bytes>) -> Self {
         Self { predicateType: PredicateType::PrefixMatch, data: prefix.into() }
     }
+
+    /// Returns a predicate to match the digest of a journal containing only the given value, as
+    /// committed by the guest with `env::commit(&value)`.
+    ///
+    /// The value is encoded with the RISC Zero serde format, so the digest is computed over the
+    /// exact journal bytes rather than over the value itself.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use boundless_market::contracts::Predicate;
+    ///
+    /// let predicate = Predicate::digest_of(&(42u32, true))?;
+    /// assert!(predicate.eval(&[42u8, 0, 0, 0, 1, 0, 0, 0]));
+    /// # anyhow::Ok(())
+    /// ```
+    pub fn digest_of<T: Serialize + ?Sized>(value: &T) -> Result<Self, risc0_zkvm::serde::Error> {
+        Ok(Self::digest_match(sha256_digest(&encode_journal(value)?)))
+    }
+
+    /// Returns a predicate to match the digest of a journal containing only the ABI encoding of
+    /// the given value, as committed by the guest with `env::commit_slice(&value.abi_encode())`.
+    pub fn digest_of_abi<T: SolValue>(value: &T) -> Self {
+        Self::digest_match(sha256_digest(&value.abi_encode()))
+    }
+
+    /// Returns a predicate to match journals starting with the given value, as committed by the
+    /// guest with `env::commit(&value)` before committing any other values.
+    ///
+    /// The value is encoded with the RISC Zero serde format. Note that a byte slice is encoded
+    /// with its length, use [Predicate::prefix_match] to match raw journal bytes.
+    pub fn prefix_of<T: Serialize + ?Sized>(value: &T) -> Result<Self, risc0_zkvm::serde::Error> {
+        Ok(Self::prefix_match(encode_journal(value)?))
+    }
+
+    /// Returns a predicate to match journals starting with the ABI encoding of the given value,
+    /// as committed by the guest with `env::commit_slice(&value.abi_encode())`.
+    pub fn prefix_of_abi<T: SolValue>(value: &T) -> Self {
+        Self::prefix_match(value.abi_encode())
+    }
+}
+
+/// Encode a value into journal bytes, as `env::commit` does in the guest.
+fn encode_journal<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, risc0_zkvm::serde::Error> {
+    Ok(risc0_zkvm::serde::to_vec(value)?.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
+
+fn sha256_digest(bytes: &[u8]) -> Digest {
+    Digest::from(<[u8; 32]>::from(Sha256::digest(bytes)))
 }
 
 impl Callback {
@@ -722,8 +800,14 @@ impl Predicate {
 /// The Boundless market module.
 pub mod boundless_market;
 #[cfg(not(target_os = "zkvm"))]
+/// Signing and verification of requests signed by smart contracts, with ERC-1271.
+pub mod erc1271;
+#[cfg(not(target_os = "zkvm"))]
 /// The Hit Points module.
 pub mod hit_points;
+#[cfg(not(target_os = "zkvm"))]
+/// The transaction submission module.
+pub mod tx_submitter;
 
 #[cfg(not(target_os = "zkvm"))]
 #[derive(Error, Debug)]
@@ -921,6 +1005,27 @@ mod tests {
         req.verify_signature(&Bytes::from(client_sig), contract_addr, chain_id).unwrap();
     }
 
+    #[tokio::test]
+    async fn sign_lock_request() {
+        let client: PrivateKeySigner = PrivateKeySigner::random();
+        let prover: PrivateKeySigner = PrivateKeySigner::random();
+        let contract_addr = Address::ZERO;
+        let chain_id = 1;
+
+        let (req, client_sig) =
+            create_order(&client, client.address(), 1, contract_addr, chain_id).await;
+        let prover_sig = req.sign_lock_request(&prover, contract_addr, chain_id).await.unwrap();
+
+        let domain = eip712_domain(contract_addr, chain_id);
+        let hash = LockRequest { request: req.clone() }.eip712_signing_hash(&domain.alloy_struct());
+        assert_eq!(prover_sig.recover_address_from_prehash(&hash).unwrap(), prover.address());
+        // The lock signature is over the LockRequest, not the ProofRequest.
+        assert!(req
+            .verify_signature(&Bytes::from(prover_sig.as_bytes()), contract_addr, chain_id)
+            .is_err());
+        assert_ne!(prover_sig.as_bytes(), client_sig);
+    }
+
     #[tokio::test]
     async fn test_request_id() {
         // Test case 1: Regular signature
@@ -961,4 +1066,29 @@ mod tests {
         assert_eq!(request_id1_u256, raw_id1);
         assert_eq!(request_id2_u256, raw_id2);
     }
+
+    #[test]
+    fn typed_predicates() {
+        let value = (7u32, String::from("hello"));
+        let journal = encode_journal(&value).unwrap();
+        let mut longer_journal = journal.clone();
+        longer_journal.extend_from_slice(&encode_journal(&1u64).unwrap());
+
+        let predicate = Predicate::digest_of(&value).unwrap();
+        assert!(predicate.eval(&journal));
+        assert!(!predicate.eval(&longer_journal));
+        // Matching the digest of the value's own bytes is the common mistake
+        assert!(!Predicate::digest_match(sha256_digest(b"hello")).eval(&journal));
+
+        let predicate = Predicate::prefix_of(&value).unwrap();
+        assert!(predicate.eval(&journal));
+        assert!(predicate.eval(&longer_journal));
+        assert!(!predicate.eval(&encode_journal(&(8u32, "hello")).unwrap()));
+
+        let abi_value = (U256::from(1), Address::ZERO);
+        let journal = abi_value.abi_encode();
+        assert!(Predicate::digest_of_abi(&abi_value).eval(&journal));
+        assert!(Predicate::prefix_of_abi(&U256::from(1)).eval(&journal));
+        assert!(!Predicate::digest_of(&journal).unwrap().eval(&journal));
+    }
 }
@@ -0,0 +1,789 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Submission of transactions with simulation, fee strategy, resubmission and confirmation
+//! tracking, shared by the lock and fulfillment paths.
+//!
+//! A [TxSubmitter] can be given the [NonceTracker] of the
+//! [NonceProvider](crate::nonce_layer::NonceProvider) sending its transactions, to release and
+//! fill the nonces of transactions it gave up on, and a [TxQueue] bounding the number of
+//! transactions in flight. Cloned submitters share both.
+//!
+//! Transactions submitted with [TxSubmitter::submit_private] are sent through a [PrivateRelay] if
+//! one is set, keeping them out of the public mempool until included or past a deadline.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use alloy::{
+    consensus::{Transaction, TxEnvelope},
+    eips::Encodable2718,
+    network::{Ethereum, EthereumWallet, NetworkWallet, TransactionBuilder},
+    primitives::{Address, B256, U256},
+    providers::{PendingTransactionBuilder, PendingTransactionError, Provider, RootProvider},
+    rpc::types::{TransactionReceipt, TransactionRequest},
+    transports::TransportError,
+};
+use anyhow::{anyhow, Context};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+use super::{boundless_market::MarketError, TXN_CONFIRM_TIMEOUT};
+use crate::nonce_layer::NonceTracker;
+
+#[derive(Clone, Debug)]
+struct ReceiptQueryConfig {
+    /// Interval at which the transaction receipts are polled.
+    retry_interval: Duration,
+    /// Number of retries for querying receipt of lock transactions.
+    retry_count: usize,
+}
+
+impl Default for ReceiptQueryConfig {
+    fn default() -> Self {
+        Self { retry_count: 10, retry_interval: Duration::from_millis(500) }
+    }
+}
+
+/// Outcome of a transaction that was included in a block.
+#[derive(Clone, Debug)]
+pub enum TxOutcome {
+    /// The transaction succeeded.
+    Confirmed(TransactionReceipt),
+    /// The transaction reverted.
+    Reverted(TransactionReceipt),
+}
+
+impl TxOutcome {
+    /// Returns the receipt of the transaction.
+    pub fn receipt(&self) -> &TransactionReceipt {
+        match self {
+            TxOutcome::Confirmed(receipt) | TxOutcome::Reverted(receipt) => receipt,
+        }
+    }
+
+    /// Returns the receipt of a successful transaction, or the error built from the transaction
+    /// hash if it reverted.
+    pub fn confirmed_or(
+        self,
+        revert_err: impl FnOnce(B256) -> MarketError,
+    ) -> Result<TransactionReceipt, MarketError> {
+        match self {
+            TxOutcome::Confirmed(receipt) => Ok(receipt),
+            TxOutcome::Reverted(receipt) => Err(revert_err(receipt.transaction_hash)),
+        }
+    }
+}
+
+/// Gas used by a plain transfer, sent to fill nonce gaps.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Bounded queue of transactions waiting to be sent.
+///
+/// At most `max_in_flight` transactions are submitted at once, the others wait their turn.
+/// Submissions fail with [MarketError::TxQueueFull] when `max_queued` transactions are already
+/// waiting, rather than piling up behind a congested mempool.
+#[derive(Clone, Debug)]
+pub struct TxQueue {
+    permits: Arc<Semaphore>,
+    queued: Arc<AtomicUsize>,
+    max_queued: usize,
+}
+
+impl TxQueue {
+    /// Create a queue of up to `max_queued` transactions, sending `max_in_flight` at once.
+    pub fn new(max_in_flight: usize, max_queued: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_in_flight.max(1))),
+            queued: Default::default(),
+            max_queued,
+        }
+    }
+
+    /// Wait for a transaction to be allowed in flight.
+    async fn acquire(&self) -> Result<OwnedSemaphorePermit, MarketError> {
+        if let Ok(permit) = self.permits.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+        if self.queued.fetch_add(1, Ordering::SeqCst) >= self.max_queued {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            return Err(MarketError::TxQueueFull(self.max_queued));
+        }
+        let permit = self.permits.clone().acquire_owned().await;
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+        Ok(permit.expect("tx queue semaphore is never closed"))
+    }
+}
+
+/// Private relay, such as Flashbots Protect, that transactions are sent to instead of the public
+/// mempool, so that they can't be front-run.
+///
+/// Transactions are signed locally and sent with `eth_sendRawTransaction`. Those not included
+/// within `fallback_after` are sent to the public mempool.
+#[derive(Clone)]
+pub struct PrivateRelay {
+    url: Url,
+    relay: RootProvider,
+    wallet: EthereumWallet,
+    fallback_after: Duration,
+}
+
+impl std::fmt::Debug for PrivateRelay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrivateRelay")
+            .field("url", &self.url)
+            .field("fallback_after", &self.fallback_after)
+            .finish()
+    }
+}
+
+impl PrivateRelay {
+    /// Create a relay sending transactions signed by the wallet to the given RPC URL.
+    pub fn new(url: Url, wallet: EthereumWallet, fallback_after: Duration) -> Self {
+        Self { relay: RootProvider::new_http(url.clone()), url, wallet, fallback_after }
+    }
+
+    /// Fill the fields of the transaction usually filled by the provider, and sign it.
+    async fn sign<P: Provider>(
+        &self,
+        provider: &P,
+        mut tx: TransactionRequest,
+    ) -> Result<TxEnvelope, MarketError> {
+        let from = *tx.from.get_or_insert_with(|| {
+            <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&self.wallet)
+        });
+        if tx.nonce.is_none() {
+            tx.set_nonce(
+                provider.get_transaction_count(from).pending().await.map_err(transport_err)?,
+            );
+        }
+        tx.set_chain_id(provider.get_chain_id().await.context("Failed to get chain ID")?);
+        if tx.gas.is_none() {
+            let gas = provider.estimate_gas(tx.clone()).await.map_err(transport_err)?;
+            // Same margin as the public provider, the state may change before inclusion
+            tx.set_gas_limit(gas + gas / 5);
+        }
+        Ok(tx.build(&self.wallet).await.context("Failed to sign transaction")?)
+    }
+}
+
+/// Hash of a sent transaction, and the signed transaction if it was sent privately.
+type SentTx = (B256, Option<TxEnvelope>);
+
+fn transport_err(err: TransportError) -> MarketError {
+    MarketError::from(alloy::contract::Error::TransportError(err))
+}
+
+/// Fees of a transaction, in wei.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Fees {
+    max_fee_per_gas: u128,
+    max_priority_fee_per_gas: u128,
+}
+
+/// Submits transactions and tracks them until they are included.
+///
+/// If simulation is enabled, transactions are simulated before they are sent, so that calls that
+/// would revert fail with the decoded contract error without spending gas. Transactions not
+/// confirmed within the timeout are resubmitted with the same nonce and fees bumped by
+/// `fee_bump_percent`, up to `max_resubmissions` times. Fees are capped at `max_fee_per_gas` if
+/// set.
+///
+/// By default transactions are neither simulated nor resubmitted, like transactions sent without
+/// a submitter.
+///
+/// With a [NonceTracker], the nonces of transactions given up on are released, and a transaction
+/// not confirmed within the timeout because it is held up by such nonces has them filled with
+/// transfers to self. Nonces of transactions this submitter did not send are never replaced.
+#[derive(Clone, Debug)]
+pub struct TxSubmitter {
+    timeout: Duration,
+    receipt_query_config: ReceiptQueryConfig,
+    simulate: bool,
+    max_resubmissions: u32,
+    fee_bump_percent: u64,
+    max_fee_per_gas: Option<u128>,
+    nonce_tracker: Option<NonceTracker>,
+    queue: Option<TxQueue>,
+    private_relay: Option<PrivateRelay>,
+}
+
+impl Default for TxSubmitter {
+    fn default() -> Self {
+        Self {
+            timeout: TXN_CONFIRM_TIMEOUT,
+            receipt_query_config: ReceiptQueryConfig::default(),
+            simulate: false,
+            max_resubmissions: 0,
+            fee_bump_percent: 20,
+            max_fee_per_gas: None,
+            nonce_tracker: None,
+            queue: None,
+            private_relay: None,
+        }
+    }
+}
+
+impl TxSubmitter {
+    /// Sets the timeout for each submission of a transaction to be confirmed.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Retry count for confirmed transactions receipts.
+    pub fn with_receipt_retry_count(mut self, count: usize) -> Self {
+        self.receipt_query_config.retry_count = count;
+        self
+    }
+
+    /// Retry polling interval for confirmed transactions receipts.
+    pub fn with_receipt_retry_interval(mut self, interval: Duration) -> Self {
+        self.receipt_query_config.retry_interval = interval;
+        self
+    }
+
+    /// Sets whether transactions are simulated before they are sent, disabled by default.
+    pub fn with_simulation(self, simulate: bool) -> Self {
+        Self { simulate, ..self }
+    }
+
+    /// Sets the number of times a transaction that is not confirmed within the timeout is
+    /// resubmitted, 0 by default.
+    pub fn with_max_resubmissions(self, max_resubmissions: u32) -> Self {
+        Self { max_resubmissions, ..self }
+    }
+
+    /// Sets the percentage by which fees are bumped on resubmission.
+    ///
+    /// Most nodes only accept a replacement transaction with fees at least 10% higher.
+    pub fn with_fee_bump_percent(self, fee_bump_percent: u64) -> Self {
+        Self { fee_bump_percent, ..self }
+    }
+
+    /// Sets the maximum fee per gas, in wei, of any submission of a transaction.
+    pub fn with_max_fee_per_gas(self, max_fee_per_gas: Option<u128>) -> Self {
+        Self { max_fee_per_gas, ..self }
+    }
+
+    /// Sets the tracker of the nonces assigned by the provider the transactions are sent with.
+    pub fn with_nonce_tracker(self, nonce_tracker: NonceTracker) -> Self {
+        Self { nonce_tracker: Some(nonce_tracker), ..self }
+    }
+
+    /// Sets the queue bounding the number of transactions in flight.
+    pub fn with_queue(self, queue: TxQueue) -> Self {
+        Self { queue: Some(queue), ..self }
+    }
+
+    /// Sets the relay transactions submitted with [TxSubmitter::submit_private] are sent to.
+    pub fn with_private_relay(self, private_relay: PrivateRelay) -> Self {
+        Self { private_relay: Some(private_relay), ..self }
+    }
+
+    /// Returns the timeout for each submission of a transaction to be confirmed.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Submit the transaction, adding `priority_gas` wei to the estimated fees, and wait for it
+    /// to be included.
+    pub async fn submit<P: Provider>(
+        &self,
+        provider: &P,
+        tx: TransactionRequest,
+        priority_gas: Option<u128>,
+    ) -> Result<TxOutcome, MarketError> {
+        self.submit_with(provider, tx, priority_gas, None).await
+    }
+
+    /// Submit the transaction like [TxSubmitter::submit], sending it through the private relay
+    /// if one is set.
+    ///
+    /// If the transaction is not included within the fallback deadline of the relay, or the
+    /// relay fails to accept it, the same transaction is sent to the public mempool.
+    pub async fn submit_private<P: Provider>(
+        &self,
+        provider: &P,
+        tx: TransactionRequest,
+        priority_gas: Option<u128>,
+    ) -> Result<TxOutcome, MarketError> {
+        self.submit_with(provider, tx, priority_gas, self.private_relay.as_ref()).await
+    }
+
+    /// Send the transaction, through the relay if given, returning its hash and the signed
+    /// transaction if it was sent privately.
+    ///
+    /// Privately sent transactions are signed locally, with the next nonce of the tracker if set.
+    async fn send<P: Provider>(
+        &self,
+        provider: &P,
+        relay: Option<&PrivateRelay>,
+        tx: TransactionRequest,
+    ) -> Result<SentTx, MarketError> {
+        let Some(relay) = relay else {
+            let pending_tx = provider.send_transaction(tx).await.map_err(transport_err)?;
+            return Ok((*pending_tx.tx_hash(), None));
+        };
+        match (&self.nonce_tracker, tx.from, tx.nonce) {
+            (Some(nonce_tracker), Some(from), None) => {
+                let pending =
+                    provider.get_transaction_count(from).pending().await.map_err(transport_err)?;
+                nonce_tracker
+                    .send_with(from, pending, |nonce| {
+                        Self::send_private(provider, relay, tx.with_nonce(nonce))
+                    })
+                    .await
+            }
+            _ => Self::send_private(provider, relay, tx).await,
+        }
+    }
+
+    async fn send_private<P: Provider>(
+        provider: &P,
+        relay: &PrivateRelay,
+        tx: TransactionRequest,
+    ) -> Result<SentTx, MarketError> {
+        let signed = relay.sign(provider, tx).await?;
+        let encoded = signed.encoded_2718();
+        match relay.relay.send_raw_transaction(&encoded).await {
+            Ok(_) => Ok((*signed.tx_hash(), Some(signed))),
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to send tx {} to the private relay {}, sending it publicly: {err}",
+                    signed.tx_hash(),
+                    relay.url
+                );
+                let pending_tx =
+                    provider.send_raw_transaction(&encoded).await.map_err(transport_err)?;
+                Ok((*pending_tx.tx_hash(), None))
+            }
+        }
+    }
+
+    async fn submit_with<P: Provider>(
+        &self,
+        provider: &P,
+        mut tx: TransactionRequest,
+        priority_gas: Option<u128>,
+        relay: Option<&PrivateRelay>,
+    ) -> Result<TxOutcome, MarketError> {
+        if self.simulate {
+            provider
+                .call(tx.clone())
+                .await
+                .map_err(|err| MarketError::from(alloy::contract::Error::TransportError(err)))?;
+        }
+
+        // Fees are set on privately sent transactions, which are not filled by the provider
+        if priority_gas.is_some() || self.max_fee_per_gas.is_some() || relay.is_some() {
+            let fees = self.estimate_fees(provider, priority_gas).await?;
+            tx.set_max_fee_per_gas(fees.max_fee_per_gas);
+            tx.set_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+        }
+
+        let _permit = match &self.queue {
+            Some(queue) => Some(queue.acquire().await?),
+            None => None,
+        };
+
+        tracing::trace!("Sending tx {:?}", tx);
+        let (tx_hash, signed) = self.send(provider, relay, tx.clone()).await?;
+        let mut tx_hashes = vec![tx_hash];
+        let pending_tx = PendingTransactionBuilder::new(provider.root().clone(), tx_hash);
+        // Once the transaction is given up on, its nonce is reused unless it gets included
+        let from = tx.from;
+        let give_up = |nonce: Option<u64>| {
+            if let (Some(nonce_tracker), Some(from)) = (&self.nonce_tracker, from) {
+                match nonce {
+                    Some(nonce) => nonce_tracker.give_up(from, nonce),
+                    None => nonce_tracker.resync(from),
+                }
+            }
+        };
+
+        let receipt = match (&signed, relay) {
+            (Some(_), Some(relay)) => {
+                tracing::debug!("Sent tx {tx_hash} to the private relay {}", relay.url);
+                self.get_receipt_within(provider, pending_tx, relay.fallback_after).await
+            }
+            _ => {
+                tracing::debug!("Broadcasting tx {tx_hash}");
+                self.get_receipt_with_retry(provider, pending_tx).await
+            }
+        };
+        let mut last_err = match receipt {
+            Ok(receipt) => return Ok(Self::outcome(receipt)),
+            Err(err) => err,
+        };
+
+        // The nonce and fees of the first submission, needed to replace it
+        let sent = match signed {
+            Some(signed) => {
+                tracing::info!(
+                    "Tx {tx_hash} not included by the private relay within {:?}, sending it publicly",
+                    relay.map(|relay| relay.fallback_after).unwrap_or_default()
+                );
+                match provider.send_raw_transaction(&signed.encoded_2718()).await {
+                    Ok(pending_tx) => {
+                        last_err = match self.get_receipt_with_retry(provider, pending_tx).await {
+                            Ok(receipt) => return Ok(Self::outcome(receipt)),
+                            Err(err) => err,
+                        };
+                    }
+                    Err(err) => tracing::debug!("Failed to send tx {tx_hash} publicly: {err}"),
+                }
+                Some(signed)
+            }
+            None => provider
+                .get_transaction_by_hash(tx_hash)
+                .await
+                .ok()
+                .flatten()
+                .map(|sent| sent.inner.into_inner()),
+        };
+        let Some(sent) = sent else {
+            tracing::debug!("Tx {} not found, can't resubmit", tx_hashes[0]);
+            give_up(None);
+            return Err(last_err);
+        };
+        let nonce = sent.nonce();
+        tx.set_nonce(nonce);
+        let mut fees = Fees {
+            max_fee_per_gas: sent.max_fee_per_gas(),
+            max_priority_fee_per_gas: sent.max_priority_fee_per_gas().unwrap_or_default(),
+        };
+        if let Some(from) = from {
+            self.fill_nonce_gaps(provider, from, nonce, priority_gas).await;
+        }
+
+        for resubmission in 1..=self.max_resubmissions {
+            if let Some(receipt) = self.find_receipt(provider, &tx_hashes).await {
+                return Ok(Self::outcome(receipt));
+            }
+
+            let Some(bumped) = self.bump_fees(provider, fees, priority_gas).await? else {
+                tracing::warn!(
+                    "Not resubmitting tx {}, bumped fees would exceed the max fee per gas",
+                    tx_hashes[0]
+                );
+                break;
+            };
+            fees = bumped;
+            tx.set_max_fee_per_gas(fees.max_fee_per_gas);
+            tx.set_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+
+            tracing::info!(
+                "Tx {} not confirmed within {:?}, resubmitting with max fee {} and priority fee {} (attempt {resubmission}/{})",
+                tx_hashes[0],
+                self.timeout,
+                fees.max_fee_per_gas,
+                fees.max_priority_fee_per_gas,
+                self.max_resubmissions
+            );
+            let pending_tx = match provider.send_transaction(tx.clone()).await {
+                Ok(pending_tx) => pending_tx,
+                Err(err) => {
+                    // Replacing fails once a previous submission was included
+                    tracing::debug!("Failed to resubmit tx {}: {err}", tx_hashes[0]);
+                    break;
+                }
+            };
+            tx_hashes.push(*pending_tx.tx_hash());
+            tracing::debug!("Broadcasting tx {}", pending_tx.tx_hash());
+
+            last_err = match self.get_receipt_with_retry(provider, pending_tx).await {
+                Ok(receipt) => return Ok(Self::outcome(receipt)),
+                Err(err) => err,
+            };
+        }
+
+        match self.find_receipt(provider, &tx_hashes).await {
+            Some(receipt) => Ok(Self::outcome(receipt)),
+            None => {
+                give_up(Some(nonce));
+                Err(last_err)
+            }
+        }
+    }
+
+    /// Fill the nonces holding up the transaction with `nonce` with transfers to self, if they
+    /// are of transactions this submitter sent and gave up on.
+    async fn fill_nonce_gaps<P: Provider>(
+        &self,
+        provider: &P,
+        from: Address,
+        nonce: u64,
+        priority_gas: Option<u128>,
+    ) {
+        let Some(nonce_tracker) = &self.nonce_tracker else {
+            return;
+        };
+        let mined = match provider.get_transaction_count(from).latest().await {
+            Ok(mined) => mined,
+            Err(err) => {
+                tracing::debug!("Failed to query the nonce of {from}: {err}");
+                return;
+            }
+        };
+        let gaps = nonce_tracker.given_up(from, mined, nonce);
+        if gaps.is_empty() {
+            return;
+        }
+        tracing::warn!(
+            "Tx with nonce {nonce} of {from} held up by nonces given up on {gaps:?}, filling them"
+        );
+        let fees = match self.estimate_fees(provider, priority_gas).await {
+            Ok(fees) => fees,
+            Err(err) => {
+                tracing::warn!("Failed to estimate fees to fill nonce gaps: {err}");
+                return;
+            }
+        };
+        // Bumped, to replace transactions that were given up on but are still in the mempool
+        let bump = |fee: u128| fee + (fee * self.fee_bump_percent as u128).div_ceil(100);
+        for gap in gaps {
+            let tx = TransactionRequest::default()
+                .with_from(from)
+                .with_to(from)
+                .with_value(U256::ZERO)
+                .with_nonce(gap)
+                .with_gas_limit(TRANSFER_GAS)
+                .with_max_fee_per_gas(bump(fees.max_fee_per_gas))
+                .with_max_priority_fee_per_gas(bump(fees.max_priority_fee_per_gas));
+            match provider.send_transaction(tx).await {
+                Ok(pending_tx) => {
+                    tracing::info!("Filling nonce {gap} of {from} with tx {}", pending_tx.tx_hash())
+                }
+                Err(err) => tracing::warn!("Failed to fill nonce {gap} of {from}: {err}"),
+            }
+        }
+    }
+
+    fn outcome(receipt: TransactionReceipt) -> TxOutcome {
+        if receipt.status() {
+            TxOutcome::Confirmed(receipt)
+        } else {
+            TxOutcome::Reverted(receipt)
+        }
+    }
+
+    async fn estimate_fees<P: Provider>(
+        &self,
+        provider: &P,
+        priority_gas: Option<u128>,
+    ) -> Result<Fees, MarketError> {
+        let estimate =
+            provider.estimate_eip1559_fees().await.context("Failed to estimate gas fees")?;
+        let priority_gas = priority_gas.unwrap_or_default();
+        let fees = Fees {
+            max_fee_per_gas: estimate.max_fee_per_gas + priority_gas,
+            max_priority_fee_per_gas: estimate.max_priority_fee_per_gas + priority_gas,
+        };
+        Ok(self.cap_fees(fees))
+    }
+
+    fn cap_fees(&self, fees: Fees) -> Fees {
+        let Some(max_fee_per_gas) = self.max_fee_per_gas else {
+            return fees;
+        };
+        if fees.max_fee_per_gas > max_fee_per_gas {
+            tracing::warn!(
+                "Estimated max fee per gas {} exceeds the max of {max_fee_per_gas}, capping",
+                fees.max_fee_per_gas
+            );
+        }
+        Fees {
+            max_fee_per_gas: fees.max_fee_per_gas.min(max_fee_per_gas),
+            max_priority_fee_per_gas: fees.max_priority_fee_per_gas.min(max_fee_per_gas),
+        }
+    }
+
+    /// Fees for replacing a transaction sent with `fees`, or None if the bump would exceed the
+    /// max fee per gas.
+    async fn bump_fees<P: Provider>(
+        &self,
+        provider: &P,
+        fees: Fees,
+        priority_gas: Option<u128>,
+    ) -> Result<Option<Fees>, MarketError> {
+        let bump = |fee: u128| fee + (fee * self.fee_bump_percent as u128).div_ceil(100);
+        let estimate = self.estimate_fees(provider, priority_gas).await?;
+        let bumped = Fees {
+            max_fee_per_gas: bump(fees.max_fee_per_gas).max(estimate.max_fee_per_gas),
+            max_priority_fee_per_gas: bump(fees.max_priority_fee_per_gas)
+                .max(estimate.max_priority_fee_per_gas),
+        };
+        match self.max_fee_per_gas {
+            Some(max_fee_per_gas) if bumped.max_fee_per_gas > max_fee_per_gas => Ok(None),
+            _ => Ok(Some(bumped)),
+        }
+    }
+
+    /// Returns the receipt of any of the given transactions that was included.
+    async fn find_receipt<P: Provider>(
+        &self,
+        provider: &P,
+        tx_hashes: &[B256],
+    ) -> Option<TransactionReceipt> {
+        for tx_hash in tx_hashes {
+            if let Ok(Some(receipt)) = provider.get_transaction_receipt(*tx_hash).await {
+                return Some(receipt);
+            }
+        }
+        None
+    }
+
+    pub(crate) async fn get_receipt_with_retry<P: Provider>(
+        &self,
+        provider: &P,
+        pending_tx: PendingTransactionBuilder<Ethereum>,
+    ) -> Result<TransactionReceipt, MarketError> {
+        self.get_receipt_within(provider, pending_tx, self.timeout).await
+    }
+
+    async fn get_receipt_within<P: Provider>(
+        &self,
+        provider: &P,
+        pending_tx: PendingTransactionBuilder<Ethereum>,
+        timeout: Duration,
+    ) -> Result<TransactionReceipt, MarketError> {
+        let tx_hash = *pending_tx.tx_hash();
+
+        // Get the nonce of the transaction for debugging purposes.
+        // It is possible that the transaction is not found immediately after broadcast, so we don't error if it's not found.
+        let tx_result = provider.get_transaction_by_hash(tx_hash).await;
+        if let Ok(Some(tx)) = tx_result {
+            let nonce = tx.nonce();
+            tracing::debug!("Tx {} broadcasted with nonce {}", tx_hash, nonce);
+        } else {
+            tracing::debug!(
+                "Tx {} not found immediately after broadcast. Can't get nonce.",
+                tx_hash
+            );
+        }
+
+        match pending_tx.with_timeout(Some(timeout)).get_receipt().await {
+            Ok(receipt) => Ok(receipt),
+            Err(PendingTransactionError::TransportError(err)) if err.is_null_resp() => {
+                tracing::debug!("failed to query receipt of confirmed transaction, retrying");
+                // There is a race condition with some providers where a transaction will be
+                // confirmed through the RPC, but querying the receipt returns null when requested
+                // immediately after.
+                for _ in 0..self.receipt_query_config.retry_count {
+                    if let Ok(Some(receipt)) = provider.get_transaction_receipt(tx_hash).await {
+                        return Ok(receipt);
+                    }
+
+                    tokio::time::sleep(self.receipt_query_config.retry_interval).await;
+                }
+
+                Err(anyhow!(
+                    "Transaction {:?} confirmed, but receipt was not found after {} retries.",
+                    tx_hash,
+                    self.receipt_query_config.retry_count
+                )
+                .into())
+            }
+            Err(e) => Err(MarketError::TxnConfirmationError(anyhow!(
+                "failed to confirm tx {:?} within timeout {:?}: {}",
+                tx_hash,
+                timeout,
+                e
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        node_bindings::Anvil, providers::ProviderBuilder, signers::local::PrivateKeySigner,
+    };
+    use httpmock::prelude::*;
+
+    #[test]
+    fn caps_fees() {
+        let fees = Fees { max_fee_per_gas: 100, max_priority_fee_per_gas: 20 };
+        let submitter = TxSubmitter::default();
+        assert_eq!(submitter.cap_fees(fees), fees);
+
+        let submitter = submitter.with_max_fee_per_gas(Some(50));
+        assert_eq!(
+            submitter.cap_fees(fees),
+            Fees { max_fee_per_gas: 50, max_priority_fee_per_gas: 20 }
+        );
+
+        let submitter = submitter.with_max_fee_per_gas(Some(10));
+        assert_eq!(
+            submitter.cap_fees(fees),
+            Fees { max_fee_per_gas: 10, max_priority_fee_per_gas: 10 }
+        );
+    }
+
+    #[tokio::test]
+    async fn bounds_queued_transactions() {
+        let queue = TxQueue::new(1, 1);
+        let in_flight = queue.acquire().await.unwrap();
+        let waiting = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire().await.map(drop) }
+        });
+        while queue.queued.load(Ordering::SeqCst) == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert!(matches!(queue.acquire().await, Err(MarketError::TxQueueFull(1))));
+
+        drop(in_flight);
+        waiting.await.unwrap().unwrap();
+        queue.acquire().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_public_mempool() {
+        let anvil = Anvil::new().spawn();
+        let signer: PrivateKeySigner = anvil.keys()[0].clone().into();
+        let from = signer.address();
+        let provider =
+            ProviderBuilder::new().wallet(signer.clone()).connect_http(anvil.endpoint_url());
+
+        // The relay accepts the transaction, but never includes it
+        let relay = MockServer::start_async().await;
+        let accepted = relay
+            .mock_async(|when, then| {
+                when.method(POST).body_contains("eth_sendRawTransaction");
+                then.status(200).json_body(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": B256::ZERO,
+                }));
+            })
+            .await;
+        let submitter = TxSubmitter::default().with_private_relay(PrivateRelay::new(
+            relay.url("/").parse().unwrap(),
+            EthereumWallet::from(signer),
+            Duration::from_millis(500),
+        ));
+
+        let transfer = TransactionRequest::default().with_from(from).with_to(from);
+        let outcome = submitter.submit_private(&provider, transfer, None).await.unwrap();
+        accepted.assert_async().await;
+        assert!(matches!(outcome, TxOutcome::Confirmed(_)));
+    }
+}
@@ -12,11 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::BTreeMap};
 
-use alloy::primitives::{address, Address};
+use alloy::primitives::{address, Address, FixedBytes};
 use clap::Args;
 use derive_builder::Builder;
+use risc0_ethereum_contracts::selector::Selector;
+
+use crate::{
+    contracts::UNSPECIFIED_SELECTOR,
+    selector::{ProofType, SupportedSelectors},
+};
 
 pub use alloy_chains::NamedChain;
 
@@ -96,6 +102,185 @@ impl Deployment {
         let chain = NamedChain::try_from(chain_id.into()).ok()?;
         Self::from_chain(chain)
     }
+
+    /// Lookup the [Deployment] by network name, e.g. `base` or `base-sepolia`.
+    ///
+    /// Names are matched case-insensitively against the [DeploymentRegistry] of known deployments.
+    pub fn from_name(name: &str) -> Option<Deployment> {
+        DeploymentRegistry::default().get_by_name(name).map(|entry| entry.deployment.clone())
+    }
+}
+
+/// Entry in a [DeploymentRegistry].
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct RegistryEntry {
+    /// Name of the network, e.g. `base-sepolia`.
+    pub name: Cow<'static, str>,
+    /// Deployment of the Boundless Market on the network.
+    pub deployment: Deployment,
+    /// Selectors that are supported by the verifier router of the deployment.
+    ///
+    /// Selectors for inclusion proofs depend on the set builder image registered with the
+    /// [RiscZeroSetVerifier] and are added with [SupportedSelectors::with_set_builder_image_id].
+    ///
+    /// [RiscZeroSetVerifier]: https://github.com/risc0/risc0-ethereum/blob/main/contracts/src/RiscZeroSetVerifier.sol
+    pub supported_selectors: SupportedSelectors,
+}
+
+impl RegistryEntry {
+    /// Create a new [RegistryEntry], supporting the default selectors.
+    pub fn new(name: impl Into<Cow<'static, str>>, deployment: Deployment) -> Self {
+        Self { name: name.into(), deployment, supported_selectors: default_selectors() }
+    }
+
+    /// Set the selectors supported by the deployment.
+    pub fn with_supported_selectors(self, supported_selectors: SupportedSelectors) -> Self {
+        Self { supported_selectors, ..self }
+    }
+}
+
+/// Registry of known deployments of the Boundless Market, keyed by chain ID.
+///
+/// The [Default] registry contains the deployments maintained by the Boundless team. Entries can
+/// be overridden or added with [DeploymentRegistry::with_entry], e.g. to point to a local devnet
+/// or a fork.
+///
+/// # Examples
+/// ```
+/// use boundless_market::deployments::{DeploymentRegistry, NamedChain, RegistryEntry, BASE};
+///
+/// let registry = DeploymentRegistry::default();
+/// let base = registry.get_by_name("base").unwrap();
+/// assert_eq!(base.deployment.boundless_market_address, BASE.boundless_market_address);
+///
+/// // Override the order stream URL for Base.
+/// let mut deployment = BASE;
+/// deployment.order_stream_url = Some("http://localhost:8585".into());
+/// let registry =
+///     registry.with_entry(NamedChain::Base as u64, RegistryEntry::new("base", deployment));
+/// let base = registry.get(NamedChain::Base as u64).unwrap();
+/// assert_eq!(base.deployment.order_stream_url.as_deref(), Some("http://localhost:8585"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct DeploymentRegistry {
+    entries: BTreeMap<u64, RegistryEntry>,
+}
+
+impl Default for DeploymentRegistry {
+    fn default() -> Self {
+        Self::empty()
+            .with_entry(NamedChain::Sepolia as u64, RegistryEntry::new("sepolia", SEPOLIA))
+            .with_entry(NamedChain::Base as u64, RegistryEntry::new("base", BASE))
+            .with_entry(
+                NamedChain::BaseSepolia as u64,
+                RegistryEntry::new("base-sepolia", BASE_SEPOLIA),
+            )
+    }
+}
+
+impl DeploymentRegistry {
+    /// Create a registry without any entries.
+    pub fn empty() -> Self {
+        Self { entries: BTreeMap::new() }
+    }
+
+    /// Add an entry for a chain ID to the registry, taking ownership.
+    ///
+    /// Replaces any existing entry for the same chain ID.
+    pub fn with_entry(mut self, chain_id: u64, entry: RegistryEntry) -> Self {
+        self.insert(chain_id, entry);
+        self
+    }
+
+    /// Add an entry for a chain ID to the registry, returning the entry it replaced, if any.
+    pub fn insert(&mut self, chain_id: u64, entry: RegistryEntry) -> Option<RegistryEntry> {
+        self.entries.insert(chain_id, entry)
+    }
+
+    /// Lookup the entry for a chain ID.
+    pub fn get(&self, chain_id: u64) -> Option<&RegistryEntry> {
+        self.entries.get(&chain_id)
+    }
+
+    /// Lookup the entry for a network name. Names are matched case-insensitively.
+    pub fn get_by_name(&self, name: &str) -> Option<&RegistryEntry> {
+        self.entries.values().find(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Iterate over the entries of the registry, ordered by chain ID.
+    pub fn iter(&self) -> impl Iterator<Item = &RegistryEntry> {
+        self.entries.values()
+    }
+
+    /// Compare a deployment with the registry entry for its chain ID.
+    ///
+    /// Returns a description of each field that differs from the registry. Returns an empty list
+    /// if the deployment matches, or if there is no entry for the chain ID.
+    pub fn check(&self, chain_id: u64, deployment: &Deployment) -> Vec<String> {
+        let Some(expected) = self.get(chain_id).map(|entry| &entry.deployment) else {
+            return Vec::new();
+        };
+        let mut mismatches = Vec::new();
+
+        if deployment.boundless_market_address != expected.boundless_market_address {
+            mismatches.push(format!(
+                "boundless_market_address mismatch: configured={}, expected={}",
+                deployment.boundless_market_address, expected.boundless_market_address
+            ));
+        }
+
+        if deployment.set_verifier_address != expected.set_verifier_address {
+            mismatches.push(format!(
+                "set_verifier_address mismatch: configured={}, expected={}",
+                deployment.set_verifier_address, expected.set_verifier_address
+            ));
+        }
+
+        if let (Some(configured), Some(expected)) =
+            (deployment.verifier_router_address, expected.verifier_router_address)
+        {
+            if configured != expected {
+                mismatches.push(format!(
+                    "verifier_router_address mismatch: configured={configured}, expected={expected}"
+                ));
+            }
+        }
+
+        if let (Some(configured), Some(expected)) =
+            (deployment.stake_token_address, expected.stake_token_address)
+        {
+            if configured != expected {
+                mismatches.push(format!(
+                    "stake_token_address mismatch: configured={configured}, expected={expected}"
+                ));
+            }
+        }
+
+        if deployment.order_stream_url != expected.order_stream_url {
+            mismatches.push(format!(
+                "order_stream_url mismatch: configured={:?}, expected={:?}",
+                deployment.order_stream_url, expected.order_stream_url
+            ));
+        }
+
+        if let Some(configured) = deployment.chain_id {
+            if configured != chain_id {
+                mismatches.push(format!(
+                    "chain_id mismatch: configured={configured}, expected={chain_id}"
+                ));
+            }
+        }
+
+        mismatches
+    }
+}
+
+/// Selectors supported by the verifier routers of the known deployments.
+fn default_selectors() -> SupportedSelectors {
+    SupportedSelectors::new()
+        .with_selector(UNSPECIFIED_SELECTOR, ProofType::Any)
+        .with_selector(FixedBytes::from(Selector::Groth16V2_2 as u32), ProofType::Groth16)
 }
 
 // TODO(#654): Ensure consistency with deployment.toml and with docs
@@ -128,3 +313,74 @@ pub const BASE_SEPOLIA: Deployment = Deployment {
     stake_token_address: Some(address!("0x036CbD53842c5426634e7929541eC2318f3dCF7e")),
     order_stream_url: Some(Cow::Borrowed("https://base-sepolia.beboundless.xyz")),
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_lookup() {
+        let registry = DeploymentRegistry::default();
+        assert_eq!(registry.iter().count(), 3);
+
+        let entry = registry.get_by_name("Base-Sepolia").unwrap();
+        assert_eq!(entry.deployment.chain_id, Some(NamedChain::BaseSepolia as u64));
+        assert!(entry.supported_selectors.is_supported(UNSPECIFIED_SELECTOR));
+
+        let entry = registry.get(NamedChain::Sepolia as u64).unwrap();
+        assert_eq!(entry.name, "sepolia");
+        assert!(registry.get_by_name("holesky").is_none());
+
+        assert_eq!(
+            Deployment::from_name("base").unwrap().boundless_market_address,
+            BASE.boundless_market_address
+        );
+    }
+
+    #[test]
+    fn registry_override() {
+        let devnet = Deployment::builder()
+            .chain_id(31337u64)
+            .boundless_market_address(Address::repeat_byte(1))
+            .set_verifier_address(Address::repeat_byte(2))
+            .build()
+            .unwrap();
+        let mut base = BASE;
+        base.order_stream_url = None;
+
+        let registry = DeploymentRegistry::default()
+            .with_entry(31337, RegistryEntry::new("devnet", devnet.clone()))
+            .with_entry(
+                NamedChain::Base as u64,
+                RegistryEntry::new("base", base)
+                    .with_supported_selectors(SupportedSelectors::new()),
+            );
+
+        assert_eq!(registry.iter().count(), 4);
+        assert_eq!(
+            registry.get_by_name("devnet").unwrap().deployment.boundless_market_address,
+            devnet.boundless_market_address
+        );
+        let base_entry = registry.get(NamedChain::Base as u64).unwrap();
+        assert!(base_entry.deployment.order_stream_url.is_none());
+        assert!(!base_entry.supported_selectors.is_supported(UNSPECIFIED_SELECTOR));
+    }
+
+    #[test]
+    fn registry_check() {
+        let registry = DeploymentRegistry::default();
+        let chain_id = NamedChain::Base as u64;
+        assert!(registry.check(chain_id, &BASE).is_empty());
+        // No entry to compare against.
+        assert!(registry.check(31337, &BASE).is_empty());
+
+        let mut deployment = BASE;
+        deployment.boundless_market_address = Address::ZERO;
+        let mismatches = registry.check(chain_id, &deployment);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].starts_with("boundless_market_address mismatch"));
+
+        let mismatches = registry.check(NamedChain::Sepolia as u64, &BASE);
+        assert!(mismatches.iter().any(|m| m.starts_with("chain_id mismatch")));
+    }
+}
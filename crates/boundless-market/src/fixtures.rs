@@ -0,0 +1,319 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::ops::RangeInclusive;
+
+use alloy::primitives::{Address, U256};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use risc0_zkvm::sha::Digest;
+
+use crate::{
+    contracts::{Offer, Predicate, ProofRequest, RequestId, RequestInput, Requirements},
+    input::GuestEnv,
+};
+
+/// A band of offer prices, picked with probability proportional to its weight.
+#[derive(Clone, Debug)]
+pub struct PriceBand {
+    /// Range the min price of the offer is drawn from.
+    pub min_price: RangeInclusive<U256>,
+    /// Range the max price of the offer is drawn from. The max price is raised to the min price
+    /// if it is drawn lower.
+    pub max_price: RangeInclusive<U256>,
+    /// Relative weight of the band.
+    pub weight: u32,
+}
+
+/// An image requests can be made for, picked with probability proportional to its weight.
+///
+/// The distribution of image sizes in a corpus is controlled by weighting images of different
+/// sizes.
+#[derive(Clone, Debug)]
+pub struct FixtureImage {
+    /// ID of the image.
+    pub image_id: Digest,
+    /// URL the image is served from.
+    pub image_url: String,
+    /// Relative weight of the image.
+    pub weight: u32,
+}
+
+/// Distributions a [CorpusGenerator] draws requests from.
+#[derive(Clone, Debug)]
+pub struct CorpusConfig {
+    /// Seed of the generator; the same seed and config always generate the same corpus.
+    pub seed: u64,
+    /// Addresses requests are made from, picked uniformly.
+    pub requestors: Vec<Address>,
+    /// Images requests are made for.
+    pub images: Vec<FixtureImage>,
+    /// Price bands offers are drawn from.
+    pub price_bands: Vec<PriceBand>,
+    /// Bidding start of the first request, in seconds since the UNIX epoch.
+    pub bidding_start: u64,
+    /// Range of the delay between the bidding start of consecutive requests, in seconds.
+    pub bidding_start_interval: RangeInclusive<u64>,
+    /// Range of the offer timeout, in seconds.
+    pub timeout: RangeInclusive<u32>,
+    /// Range of the lock timeout, as a percent of the timeout.
+    pub lock_timeout_percent: RangeInclusive<u32>,
+    /// Range of the ramp up period, as a percent of the lock timeout.
+    pub ramp_up_percent: RangeInclusive<u32>,
+    /// Range of the lock stake.
+    pub lock_stake: RangeInclusive<U256>,
+    /// Range of the size of the inline input written to stdin, in bytes.
+    pub input_size: RangeInclusive<usize>,
+}
+
+impl CorpusConfig {
+    /// Config drawing every request for the given image from a single requestor, with a single
+    /// price band.
+    pub fn new(requestor: Address, image_id: impl Into<Digest>, image_url: &str) -> Self {
+        Self {
+            seed: 0,
+            requestors: vec![requestor],
+            images: vec![FixtureImage {
+                image_id: image_id.into(),
+                image_url: image_url.to_string(),
+                weight: 1,
+            }],
+            price_bands: vec![PriceBand {
+                min_price: U256::ZERO..=U256::from(1_000_000_000u64),
+                max_price: U256::from(1_000_000_000u64)..=U256::from(1_000_000_000_000u64),
+                weight: 1,
+            }],
+            bidding_start: crate::util::now_timestamp(),
+            bidding_start_interval: 0..=1,
+            timeout: 300..=3600,
+            lock_timeout_percent: 50..=100,
+            ramp_up_percent: 0..=50,
+            lock_stake: U256::ZERO..=U256::from(1_000_000_000_000_000u64),
+            input_size: 0..=1024,
+        }
+    }
+
+    /// Set the seed of the generator.
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// Set the addresses requests are made from.
+    pub fn with_requestors(self, requestors: Vec<Address>) -> Self {
+        Self { requestors, ..self }
+    }
+
+    /// Set the images requests are made for.
+    pub fn with_images(self, images: Vec<FixtureImage>) -> Self {
+        Self { images, ..self }
+    }
+
+    /// Set the price bands offers are drawn from.
+    pub fn with_price_bands(self, price_bands: Vec<PriceBand>) -> Self {
+        Self { price_bands, ..self }
+    }
+
+    /// Set the bidding start of the first request and the range of the delay between requests.
+    pub fn with_bidding_start(self, bidding_start: u64, interval: RangeInclusive<u64>) -> Self {
+        Self { bidding_start, bidding_start_interval: interval, ..self }
+    }
+
+    /// Set the range of the offer timeout, in seconds.
+    pub fn with_timeout(self, timeout: RangeInclusive<u32>) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Set the range of the lock timeout, as a percent of the timeout.
+    pub fn with_lock_timeout_percent(self, lock_timeout_percent: RangeInclusive<u32>) -> Self {
+        Self { lock_timeout_percent, ..self }
+    }
+
+    /// Set the range of the ramp up period, as a percent of the lock timeout.
+    pub fn with_ramp_up_percent(self, ramp_up_percent: RangeInclusive<u32>) -> Self {
+        Self { ramp_up_percent, ..self }
+    }
+
+    /// Set the range of the lock stake.
+    pub fn with_lock_stake(self, lock_stake: RangeInclusive<U256>) -> Self {
+        Self { lock_stake, ..self }
+    }
+
+    /// Set the range of the size of the inline input, in bytes.
+    pub fn with_input_size(self, input_size: RangeInclusive<usize>) -> Self {
+        Self { input_size, ..self }
+    }
+}
+
+/// Generates randomized but valid [ProofRequest] corpora, for load tests and backtests.
+///
+/// Requests are unsigned; sign them with [ProofRequest::sign_request] to submit them. Each
+/// requestor's requests use consecutive request indices, starting from 0.
+pub struct CorpusGenerator {
+    conf: CorpusConfig,
+    rng: StdRng,
+    bidding_start: u64,
+    next_index: Vec<u32>,
+}
+
+impl CorpusGenerator {
+    /// Create a generator drawing from the given config.
+    ///
+    /// Panics if the config has no requestors, images or price bands, or if their weights are
+    /// all zero.
+    pub fn new(conf: CorpusConfig) -> Self {
+        assert!(!conf.requestors.is_empty(), "corpus config has no requestors");
+        assert!(conf.images.iter().any(|image| image.weight > 0), "corpus config has no images");
+        assert!(
+            conf.price_bands.iter().any(|band| band.weight > 0),
+            "corpus config has no price bands"
+        );
+        Self {
+            rng: StdRng::seed_from_u64(conf.seed),
+            bidding_start: conf.bidding_start.max(1),
+            next_index: vec![0; conf.requestors.len()],
+            conf,
+        }
+    }
+
+    /// Generate the next request of the corpus.
+    pub fn next_request(&mut self) -> ProofRequest {
+        let requestor = self.rng.random_range(0..self.conf.requestors.len());
+        let index = self.next_index[requestor];
+        self.next_index[requestor] += 1;
+
+        let image = &self.conf.images
+            [pick_weighted(&mut self.rng, self.conf.images.iter().map(|image| image.weight))];
+        let band = &self.conf.price_bands
+            [pick_weighted(&mut self.rng, self.conf.price_bands.iter().map(|band| band.weight))];
+
+        let min_price = random_u256(&mut self.rng, &band.min_price);
+        let max_price =
+            random_u256(&mut self.rng, &band.max_price).max(min_price).max(U256::from(1));
+        let timeout = self.rng.random_range(self.conf.timeout.clone()).max(1);
+        let lock_timeout =
+            percent_of(timeout, self.rng.random_range(self.conf.lock_timeout_percent.clone()))
+                .clamp(1, timeout)
+                .max(timeout.saturating_sub((1 << 24) - 1));
+        let ramp_up_period =
+            percent_of(lock_timeout, self.rng.random_range(self.conf.ramp_up_percent.clone()))
+                .min(lock_timeout);
+
+        let mut stdin = vec![0u8; self.rng.random_range(self.conf.input_size.clone())];
+        self.rng.fill(&mut stdin[..]);
+        let input = GuestEnv::from_stdin(stdin).encode().expect("failed to encode input");
+
+        let request = ProofRequest::new(
+            RequestId::new(self.conf.requestors[requestor], index),
+            Requirements::new(image.image_id, Predicate::prefix_match(vec![])),
+            image.image_url.clone(),
+            RequestInput::inline(input),
+            Offer {
+                minPrice: min_price,
+                maxPrice: max_price,
+                biddingStart: self.bidding_start,
+                rampUpPeriod: ramp_up_period,
+                timeout,
+                lockTimeout: lock_timeout,
+                lockStake: random_u256(&mut self.rng, &self.conf.lock_stake),
+            },
+        );
+        self.bidding_start += self.rng.random_range(self.conf.bidding_start_interval.clone());
+        request
+    }
+
+    /// Generate the next `count` requests of the corpus.
+    pub fn generate(&mut self, count: usize) -> Vec<ProofRequest> {
+        (0..count).map(|_| self.next_request()).collect()
+    }
+}
+
+/// Index of an entry picked with probability proportional to its weight.
+fn pick_weighted(rng: &mut StdRng, weights: impl Iterator<Item = u32> + Clone) -> usize {
+    let total: u64 = weights.clone().map(u64::from).sum();
+    let mut pick = rng.random_range(0..total);
+    for (idx, weight) in weights.enumerate() {
+        if pick < u64::from(weight) {
+            return idx;
+        }
+        pick -= u64::from(weight);
+    }
+    unreachable!("pick is less than the total weight")
+}
+
+fn random_u256(rng: &mut StdRng, range: &RangeInclusive<U256>) -> U256 {
+    let (start, end) = (*range.start(), *range.end());
+    if end <= start {
+        return start;
+    }
+    let span = end - start;
+    if span == U256::MAX {
+        return U256::from_limbs(rng.random());
+    }
+    start + U256::from_limbs(rng.random()) % (span + U256::from(1))
+}
+
+fn percent_of(value: u32, percent: u32) -> u32 {
+    (u64::from(value) * u64::from(percent) / 100) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_valid_requests_within_bands() {
+        let cheap = PriceBand {
+            min_price: U256::from(1)..=U256::from(10),
+            max_price: U256::from(10)..=U256::from(100),
+            weight: 3,
+        };
+        let expensive = PriceBand {
+            min_price: U256::from(1_000)..=U256::from(2_000),
+            max_price: U256::from(5_000)..=U256::from(10_000),
+            weight: 1,
+        };
+        let conf = CorpusConfig::new(
+            Address::repeat_byte(1),
+            Digest::new([1; 8]),
+            "http://localhost/image",
+        )
+        .with_seed(42)
+        .with_requestors(vec![Address::repeat_byte(1), Address::repeat_byte(2)])
+        .with_price_bands(vec![cheap, expensive])
+        .with_timeout(100..=200)
+        .with_input_size(16..=64);
+
+        let corpus = CorpusGenerator::new(conf.clone()).generate(200);
+        for request in &corpus {
+            request.validate().unwrap();
+            assert!((100..=200).contains(&request.offer.timeout));
+            let max_price = request.offer.maxPrice;
+            assert!(
+                (U256::from(10)..=U256::from(100)).contains(&max_price)
+                    || (U256::from(5_000)..=U256::from(10_000)).contains(&max_price)
+            );
+        }
+        let cheap_count =
+            corpus.iter().filter(|request| request.offer.maxPrice <= U256::from(100)).count();
+        assert!(cheap_count > 100 && cheap_count < 200, "{cheap_count} cheap requests");
+
+        // Request IDs are unique
+        let mut ids: Vec<_> = corpus.iter().map(|request| request.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), corpus.len());
+
+        // The same seed generates the same corpus
+        assert_eq!(CorpusGenerator::new(conf).generate(200), corpus);
+    }
+}
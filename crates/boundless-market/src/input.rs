@@ -88,6 +88,13 @@ pub struct GuestEnv {
     /// be read. If the guest uses `env::read`, this should be encoded using the default RISC Zero
     /// codec. [GuestEnvBuilder::write] will encode the data given using the default codec.
     pub stdin: Vec<u8>,
+    /// URLs of the receipts of the assumptions of the guest.
+    ///
+    /// Guests that compose proofs verify receipts with `env::verify`, which adds an assumption to
+    /// their receipt. The prover fetches the bincode encoded [Receipt][risc0_zkvm::Receipt] from
+    /// each of these URLs and resolves the assumptions with them when proving.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assumptions: Vec<String>,
 }
 
 impl GuestEnv {
@@ -102,7 +109,7 @@ impl GuestEnv {
             return Err(Error::EmptyEncodedInput);
         }
         match Version::try_from(bytes[0])? {
-            Version::V0 => Ok(Self { stdin: bytes[1..].to_vec() }),
+            Version::V0 => Ok(Self::from_stdin(&bytes[1..])),
             Version::V1 => Ok(rmp_serde::from_read(&bytes[1..])?),
         }
     }
@@ -118,7 +125,7 @@ impl GuestEnv {
 
     /// Create a [GuestEnv] with `stdin` set to the contents of the given `bytes`.
     pub fn from_stdin(bytes: impl Into<Vec<u8>>) -> Self {
-        GuestEnv { stdin: bytes.into() }
+        GuestEnv { stdin: bytes.into(), assumptions: Vec::new() }
     }
 }
 
@@ -128,7 +135,14 @@ impl TryFrom<GuestEnv> for ExecutorEnv<'_> {
     /// Create an [ExecutorEnv], which can be used for execution and proving through the
     /// [risc0_zkvm] [Prover][risc0_zkvm::Prover] and [Executor][risc0_zkvm::Executor] traits, from
     /// the given [GuestEnv].
+    ///
+    /// Assumptions are given as receipt URLs, so they must be fetched and added to the builder by
+    /// the caller. Conversion of a [GuestEnv] with assumptions fails.
     fn try_from(env: GuestEnv) -> Result<Self, Self::Error> {
+        anyhow::ensure!(
+            env.assumptions.is_empty(),
+            "guest env has assumptions, which must be added to the executor env by the caller"
+        );
         ExecutorEnv::builder().write_slice(&env.stdin).build()
     }
 }
@@ -150,17 +164,21 @@ pub struct GuestEnvBuilder {
     ///
     /// See [GuestEnv::stdin]
     pub stdin: Vec<u8>,
+    /// URLs of the receipts of the assumptions of the guest.
+    ///
+    /// See [GuestEnv::assumptions]
+    pub assumptions: Vec<String>,
 }
 
 impl GuestEnvBuilder {
     /// Create a new input builder.
     pub fn new() -> Self {
-        Self { stdin: Vec::new() }
+        Self { stdin: Vec::new(), assumptions: Vec::new() }
     }
 
     /// Build the [GuestEnv] for inclusion in a proof request.
     pub fn build_env(self) -> GuestEnv {
-        GuestEnv { stdin: self.stdin }
+        GuestEnv { stdin: self.stdin, assumptions: self.assumptions }
     }
 
     /// Build the and encode [GuestEnv] for inclusion in a proof request.
@@ -224,6 +242,26 @@ impl GuestEnvBuilder {
         Self { stdin: input, ..self }
     }
 
+    /// Add an assumption, given as the URL of its receipt.
+    ///
+    /// The receipt must be the bincode encoded [Receipt][risc0_zkvm::Receipt] of a proof the guest
+    /// verifies with `env::verify`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use boundless_market::GuestEnv;
+    ///
+    /// let input = GuestEnv::builder()
+    ///     .write_slice(&[1u8, 2, 3])
+    ///     .with_assumption("https://example.com/receipt.bin");
+    /// ```
+    pub fn with_assumption(self, url: impl Into<String>) -> Self {
+        let mut assumptions = self.assumptions;
+        assumptions.push(url.into());
+        Self { assumptions, ..self }
+    }
+
     /// Write a frame.
     ///
     /// A frame contains a length header along with the payload. Reading a frame can be more
@@ -270,6 +308,14 @@ mod tests {
 
         let decoded_env = GuestEnv::decode(&env.encode()?)?;
         assert_eq!(env, decoded_env);
+
+        let env = GuestEnv::builder()
+            .write_slice(timestamp.as_bytes())
+            .with_assumption("https://example.com/receipt.bin")
+            .build_env();
+        let decoded_env = GuestEnv::decode(&env.encode()?)?;
+        assert_eq!(env, decoded_env);
+        assert_eq!(decoded_env.assumptions, vec!["https://example.com/receipt.bin".to_string()]);
         Ok(())
     }
 }
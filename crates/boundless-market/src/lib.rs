@@ -30,6 +30,13 @@ pub use alloy;
 #[cfg(not(target_os = "zkvm"))]
 pub mod balance_alerts_layer;
 
+/// Watch-only monitor for a requestor's market balance.
+///
+/// It compares the balance with the max price of open requests to warn before locks would
+/// start failing due to an insufficient balance.
+#[cfg(not(target_os = "zkvm"))]
+pub mod balance_monitor;
+
 /// Client module for interacting with the Boundless Market API.
 #[cfg(not(target_os = "zkvm"))]
 pub mod client;
@@ -51,6 +58,10 @@ pub mod deployments;
 #[cfg(not(target_os = "zkvm"))]
 pub use deployments::Deployment;
 
+/// Generators of randomized [ProofRequest] corpora for load tests and backtests.
+#[cfg(all(feature = "test-utils", not(target_os = "zkvm")))]
+pub mod fixtures;
+
 /// Input module for serializing input.
 #[cfg(not(target_os = "zkvm"))]
 pub mod input;
@@ -22,15 +22,118 @@ use alloy::{
     rpc::types::TransactionRequest,
     transports::{RpcError, TransportResult},
 };
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
-use tokio::sync::{Mutex, Semaphore};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+
+#[derive(Debug, Default)]
+struct AccountNonces {
+    /// Next nonce to assign, None until synced with the node.
+    next: Option<u64>,
+    /// Nonces of transactions sent by this provider that were given up on.
+    given_up: BTreeSet<u64>,
+}
+
+/// Nonces assigned by a [NonceProvider], shared with the services submitting its transactions.
+///
+/// Nonces are assigned from a local counter, synced with the pending nonce of the node, so that
+/// concurrent submissions get consecutive nonces even if the node is slow to report the
+/// transactions just sent. The counter is resynced with the node after a failed send, and after
+/// a transaction was given up on, so that its nonce is reused.
+///
+/// As a provider is connected to a single chain, so are its nonces.
+#[derive(Clone, Debug, Default)]
+pub struct NonceTracker {
+    account_semaphores: Arc<Mutex<HashMap<Address, Arc<Semaphore>>>>,
+    accounts: Arc<std::sync::Mutex<HashMap<Address, AccountNonces>>>,
+}
+
+impl NonceTracker {
+    /// Wait for the account to be free to send a transaction, from assigning its nonce until it is
+    /// sent.
+    async fn acquire(&self, address: Address) -> OwnedSemaphorePermit {
+        let semaphore = {
+            let mut semaphores = self.account_semaphores.lock().await;
+            semaphores.entry(address).or_insert_with(|| Arc::new(Semaphore::new(1))).clone()
+        };
+        semaphore.acquire_owned().await.unwrap()
+    }
+
+    /// Assign the next nonce of the account to a transaction sent with `send`, for senders other
+    /// than the provider, e.g. of raw transactions signed locally.
+    ///
+    /// `send` is given the nonce, and returns whether the transaction was sent.
+    pub async fn send_with<T, E, Fut>(
+        &self,
+        from: Address,
+        pending: u64,
+        send: impl FnOnce(u64) -> Fut,
+    ) -> Result<T, E>
+    where
+        Fut: std::future::Future<Output = Result<T, E>>,
+    {
+        let _permit = self.acquire(from).await;
+        let nonce = self.next(from, pending);
+        let res = send(nonce).await;
+        match &res {
+            Ok(_) => self.sent(from, nonce),
+            Err(_) => self.resync(from),
+        }
+        res
+    }
+
+    /// Nonce to assign to the next transaction of the account, given its pending nonce.
+    fn next(&self, from: Address, pending: u64) -> u64 {
+        let accounts = self.accounts.lock().unwrap();
+        // The node may not report the transactions that were just sent yet
+        accounts
+            .get(&from)
+            .and_then(|account| account.next)
+            .map_or(pending, |next| next.max(pending))
+    }
+
+    fn sent(&self, from: Address, nonce: u64) {
+        let mut accounts = self.accounts.lock().unwrap();
+        let account = accounts.entry(from).or_default();
+        account.next = Some(account.next.map_or(nonce + 1, |next| next.max(nonce + 1)));
+        account.given_up.remove(&nonce);
+    }
+
+    /// Resync the next nonce of the account with the node on the next send.
+    pub fn resync(&self, from: Address) {
+        if let Some(account) = self.accounts.lock().unwrap().get_mut(&from) {
+            account.next = None;
+        }
+    }
+
+    /// Record that the transaction with the given nonce, sent by this provider, was given up on,
+    /// and resync the next nonce of the account with the node.
+    pub fn give_up(&self, from: Address, nonce: u64) {
+        let mut accounts = self.accounts.lock().unwrap();
+        let account = accounts.entry(from).or_default();
+        account.next = None;
+        account.given_up.insert(nonce);
+    }
+
+    /// Nonces from `mined` up to `nonce` of transactions that this provider sent and that were
+    /// given up on, which hold up the transaction with `nonce`.
+    ///
+    /// Nonces of transactions sent by other processes are never returned.
+    pub fn given_up(&self, from: Address, mined: u64, nonce: u64) -> Vec<u64> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let Some(account) = accounts.get_mut(&from) else {
+            return Vec::new();
+        };
+        account.given_up = account.given_up.split_off(&mined);
+        account.given_up.range(..nonce).copied().collect()
+    }
+}
 
 /// A provider that manages nonces per account using semaphores.
 ///
 /// This provider exists to avoid nonce collisions when submitting transactions concurrently.
 /// It does so by holding a semaphore permit between fetching the pending nonce of the signer until
-/// the transaction is sent.
+/// the transaction is sent, and by assigning nonces with a [NonceTracker].
 #[derive(Clone, Debug)]
 pub struct NonceProvider<F, P>
 where
@@ -39,7 +142,7 @@ where
 {
     inner: Arc<FillProvider<F, P, Ethereum>>,
     wallet: EthereumWallet,
-    account_semaphores: Arc<Mutex<HashMap<Address, Arc<Semaphore>>>>,
+    nonces: NonceTracker,
 }
 
 impl<F, P> NonceProvider<F, P>
@@ -49,17 +152,12 @@ where
 {
     /// Construct a new provider with the inner filler and wallet.
     pub fn new(inner: FillProvider<F, P, Ethereum>, wallet: EthereumWallet) -> Self {
-        Self {
-            inner: Arc::new(inner),
-            wallet,
-            account_semaphores: Arc::new(Mutex::new(HashMap::new())),
-        }
+        Self { inner: Arc::new(inner), wallet, nonces: NonceTracker::default() }
     }
 
-    /// Get or create a semaphore for the given account address.
-    async fn get_account_semaphore(&self, address: Address) -> Arc<Semaphore> {
-        let mut semaphores = self.account_semaphores.lock().await;
-        semaphores.entry(address).or_insert_with(|| Arc::new(Semaphore::new(1))).clone()
+    /// Returns the tracker of the nonces assigned by this provider.
+    pub fn nonce_tracker(&self) -> NonceTracker {
+        self.nonces.clone()
     }
 }
 
@@ -84,21 +182,46 @@ where
         };
         request.set_from(from_address);
 
-        // Get semaphore for this account and acquire permit
-        let semaphore = self.get_account_semaphore(from_address).await;
-        let _permit = semaphore.acquire().await.unwrap();
-
-        // Fetch the pending nonce if not already set
-        if request.nonce.is_none() {
-            let pending_nonce = self.inner.get_transaction_count(from_address).pending().await?;
-            request.nonce = Some(pending_nonce);
-            tracing::trace!(
-                "NonceProvider::send_with_nonce_management - set nonce {} for address: {}",
-                pending_nonce,
-                from_address
-            );
+        // Acquire the permit of this account to send a transaction
+        let _permit = self.nonces.acquire(from_address).await;
+
+        // Assign the next nonce if not already set
+        let assigned = request.nonce.is_none();
+        let nonce = match request.nonce {
+            Some(nonce) => nonce,
+            None => {
+                let pending_nonce =
+                    self.inner.get_transaction_count(from_address).pending().await?;
+                let nonce = self.nonces.next(from_address, pending_nonce);
+                request.nonce = Some(nonce);
+                tracing::trace!(
+                    "NonceProvider::send_with_nonce_management - set nonce {} for address: {}",
+                    nonce,
+                    from_address
+                );
+                nonce
+            }
+        };
+
+        let res = self.send_filled(request).await;
+        match &res {
+            Ok(_) => self.nonces.sent(from_address, nonce),
+            Err(_) if assigned => self.nonces.resync(from_address),
+            Err(_) => {}
         }
+        res
+    }
+}
 
+impl<F, P> NonceProvider<F, P>
+where
+    F: TxFiller<Ethereum>,
+    P: Provider<Ethereum> + Send + Sync + std::fmt::Debug,
+{
+    async fn send_filled(
+        &self,
+        request: TransactionRequest,
+    ) -> TransportResult<PendingTransactionBuilder<Ethereum>> {
         let tx = self.inner.fill(request).await?;
 
         let builder = match tx {
@@ -134,3 +257,48 @@ where
         <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&self.wallet)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        consensus::Transaction as _,
+        node_bindings::Anvil,
+        providers::{
+            fillers::{ChainIdFiller, GasFiller},
+            ProviderBuilder,
+        },
+        signers::local::PrivateKeySigner,
+    };
+
+    #[tokio::test]
+    async fn assigns_consecutive_nonces() {
+        let anvil = Anvil::new().spawn();
+        let signer: PrivateKeySigner = anvil.keys()[0].clone().into();
+        let from = signer.address();
+        let base_provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .filler(ChainIdFiller::default())
+            .filler(GasFiller)
+            .connect_http(anvil.endpoint_url());
+        let provider = NonceProvider::new(base_provider, EthereumWallet::from(signer));
+        let nonces = provider.nonce_tracker();
+
+        let transfer = TransactionRequest::default().with_from(from).with_to(from);
+        let sends = (0..3).map(|_| provider.send_transaction(transfer.clone()));
+        let mut sent = Vec::new();
+        for pending_tx in futures_util::future::join_all(sends).await {
+            let tx_hash = *pending_tx.unwrap().tx_hash();
+            let tx = provider.get_transaction_by_hash(tx_hash).await.unwrap().unwrap();
+            sent.push(tx.nonce());
+        }
+        sent.sort();
+        assert_eq!(sent, [0, 1, 2]);
+
+        // Only the nonces of transactions given up on are returned, once not mined
+        nonces.give_up(from, 1);
+        assert_eq!(nonces.given_up(from, 0, 3), [1]);
+        assert!(nonces.given_up(from, 2, 3).is_empty());
+        assert!(nonces.given_up(Address::ZERO, 0, 3).is_empty());
+    }
+}
@@ -13,7 +13,9 @@
 // limitations under the License.
 
 use alloy::{
-    primitives::{Address, Signature, U256},
+    network::Ethereum,
+    primitives::{Address, Bytes, Signature, U256},
+    providers::Provider,
     signers::{Error as SignerErr, Signer},
 };
 use alloy_primitives::B256;
@@ -21,11 +23,23 @@ use alloy_sol_types::SolStruct;
 use anyhow::{Context, Result};
 use async_stream::stream;
 use chrono::{DateTime, Utc};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
 use futures_util::{SinkExt, Stream, StreamExt};
-use reqwest::Url;
+use reqwest::{header::HeaderMap, StatusCode, Url};
 use serde::{Deserialize, Serialize};
 use siwe::Message as SiweMsg;
-use std::pin::Pin;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display},
+    io::{Read, Write},
+    pin::Pin,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 use thiserror::Error;
 use time::OffsetDateTime;
 use tokio::net::TcpStream;
@@ -35,10 +49,21 @@ use tokio_tungstenite::{
 };
 use utoipa::ToSchema;
 
-use crate::contracts::{eip712_domain, ProofRequest, RequestError};
+use crate::contracts::{
+    boundless_market::{BoundlessMarketService, MarketError},
+    eip712_domain,
+    erc1271::SmartContractSigner,
+    ProofRequest, RequestError,
+};
 
 /// Order stream submission API path.
 pub const ORDER_SUBMISSION_PATH: &str = "/api/v1/submit_order";
+/// Order stream batch order submission API path.
+pub const ORDER_BATCH_SUBMISSION_PATH: &str = "/api/v1/submit_orders";
+/// Order stream order withdrawal API path.
+pub const ORDER_WITHDRAW_PATH: &str = "/api/v1/withdraw_order";
+/// Maximum number of orders submitted in a single batch.
+pub const MAX_ORDER_BATCH_SIZE: usize = 100;
 /// Order stream order list API path.
 pub const ORDER_LIST_PATH: &str = "/api/v1/orders";
 /// Order stream nonce API path.
@@ -47,6 +72,12 @@ pub const AUTH_GET_NONCE: &str = "/api/v1/nonce/";
 pub const HEALTH_CHECK: &str = "/api/v1/health";
 /// Order stream websocket path.
 pub const ORDER_WS_PATH: &str = "/ws/v1/orders";
+/// Header carrying an [OrderFilter] when connecting to the order stream websocket.
+pub const ORDER_FILTER_HEADER: &str = "X-Order-Filter";
+/// Header carrying a [ConsumerGroup] when connecting to the order stream websocket.
+pub const CONSUMER_GROUP_HEADER: &str = "X-Consumer-Group";
+/// Header selecting the [OrderEncoding] of orders pushed over the order stream websocket.
+pub const ORDER_ENCODING_HEADER: &str = "X-Order-Encoding";
 
 /// Error body for API responses
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -80,6 +111,264 @@ pub enum OrderError {
     RequestError(#[from] RequestError),
 }
 
+/// Error returned by the [OrderStreamClient]
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum OrderStreamErr {
+    #[error("authentication failed: {0}")]
+    /// The server rejected the authentication of the client.
+    Auth(String),
+    #[error("rate limited by the order stream server{}", fmt_retry_after(retry_after))]
+    /// The server is rate limiting the client.
+    RateLimited {
+        /// Delay before retrying requested by the server, if any
+        retry_after: Option<Duration>,
+    },
+    #[error("network error: {0:#}")]
+    /// The server could not be reached, or the connection to it failed.
+    Network(anyhow::Error),
+    #[error("protocol error: {0:#}")]
+    /// The server returned an error or an unexpected response.
+    Protocol(anyhow::Error),
+    #[error("validation error: {0:#}")]
+    /// The order, or the arguments of the call, are invalid.
+    Validation(anyhow::Error),
+    #[error("no order found")]
+    /// No order matched the request.
+    NotFound,
+}
+
+impl OrderStreamErr {
+    /// Copy of the error, reported for each order of a batch that failed as a whole.
+    fn duplicate(&self) -> Self {
+        match self {
+            Self::Auth(msg) => Self::Auth(msg.clone()),
+            Self::RateLimited { retry_after } => Self::RateLimited { retry_after: *retry_after },
+            Self::Network(err) => Self::Network(anyhow::anyhow!("{err:#}")),
+            Self::Protocol(err) => Self::Protocol(anyhow::anyhow!("{err:#}")),
+            Self::Validation(err) => Self::Validation(anyhow::anyhow!("{err:#}")),
+            Self::NotFound => Self::NotFound,
+        }
+    }
+}
+
+impl From<reqwest::Error> for OrderStreamErr {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_decode() {
+            Self::Protocol(err.into())
+        } else {
+            Self::Network(err.into())
+        }
+    }
+}
+
+impl From<url::ParseError> for OrderStreamErr {
+    fn from(err: url::ParseError) -> Self {
+        Self::Validation(err.into())
+    }
+}
+
+impl From<OrderError> for OrderStreamErr {
+    fn from(err: OrderError) -> Self {
+        Self::Validation(err.into())
+    }
+}
+
+impl From<RequestError> for OrderStreamErr {
+    fn from(err: RequestError) -> Self {
+        Self::Validation(err.into())
+    }
+}
+
+fn fmt_retry_after(retry_after: &Option<Duration>) -> String {
+    retry_after.map(|delay| format!(", retry after {delay:?}")).unwrap_or_default()
+}
+
+/// Parse the value of a `Retry-After` header, given in seconds.
+fn parse_retry_after(value: Option<&str>) -> Option<Duration> {
+    value?.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Convert an unsuccessful response of the order stream server into an error.
+async fn response_error(response: reqwest::Response) -> OrderStreamErr {
+    let status = response.status();
+    let retry_after = parse_retry_after(
+        response.headers().get(reqwest::header::RETRY_AFTER).and_then(|value| value.to_str().ok()),
+    );
+    let msg = match response.json::<serde_json::Value>().await {
+        Ok(json_body) => json_body["msg"].as_str().unwrap_or("Unknown server error").to_string(),
+        Err(_) => "Failed to read server error message".to_string(),
+    };
+    match status.as_u16() {
+        401 | 403 => OrderStreamErr::Auth(msg),
+        429 => OrderStreamErr::RateLimited { retry_after },
+        400..=499 => OrderStreamErr::Validation(anyhow::anyhow!("{status}: {msg}")),
+        _ => OrderStreamErr::Protocol(anyhow::anyhow!("{status}: {msg}")),
+    }
+}
+
+/// Headers giving the number of requests left in the server's current rate-limit window.
+const RATE_LIMIT_REMAINING_HEADERS: [&str; 2] = ["ratelimit-remaining", "x-ratelimit-remaining"];
+/// Headers giving the seconds until the server's current rate-limit window resets.
+const RATE_LIMIT_RESET_HEADERS: [&str; 2] = ["ratelimit-reset", "x-ratelimit-reset"];
+
+/// Value of the first of the given headers present in the response, parsed as an integer.
+fn header_u64(headers: &HeaderMap, names: &[&str]) -> Option<u64> {
+    names
+        .iter()
+        .find_map(|name| headers.get(*name))
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Rate of requests allowed by the [RateLimiter] of an [OrderStreamClient].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    /// Average number of requests sent per second. Zero disables the limit, though requests are
+    /// still held back when the server asks to.
+    pub requests_per_second: f64,
+    /// Number of requests that can be sent at once after being idle.
+    pub burst: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self { requests_per_second: 10.0, burst: 20 }
+    }
+}
+
+/// Snapshot of the state of the [RateLimiter] of an [OrderStreamClient].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimiterMetrics {
+    /// Number of requests that can be sent immediately
+    pub available_tokens: f64,
+    /// Time left before requests are sent again, if the server asked to back off
+    pub backoff_remaining: Option<Duration>,
+    /// Number of responses from the server rejecting a request as rate limited
+    pub rate_limited_total: u64,
+    /// Number of rate limited requests that were retried
+    pub retries_total: u64,
+}
+
+/// Token-bucket rate limiter shared by all endpoints of an [OrderStreamClient] and its clones.
+///
+/// Requests are also held back for as long as the server asks to, either with a `Retry-After`
+/// header on a 429 response, or by reporting an exhausted rate-limit window with the
+/// `RateLimit-Remaining` and `RateLimit-Reset` headers (or their `X-` prefixed variants).
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    state: Mutex<RateLimiterState>,
+}
+
+#[derive(Debug)]
+struct RateLimiterState {
+    tokens: f64,
+    refilled_at: Instant,
+    blocked_until: Option<Instant>,
+    rate_limited_total: u64,
+    retries_total: u64,
+}
+
+impl RateLimiter {
+    /// Create a limiter with a full bucket of `limit.burst` tokens.
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(RateLimiterState {
+                tokens: limit.burst as f64,
+                refilled_at: Instant::now(),
+                blocked_until: None,
+                rate_limited_total: 0,
+                retries_total: 0,
+            }),
+        }
+    }
+
+    /// Wait until a request may be sent, taking a token for it.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                self.refill(&mut state, now);
+                match state.blocked_until.filter(|until| *until > now) {
+                    Some(until) => until - now,
+                    None if self.limit.requests_per_second <= 0.0 => return,
+                    None if state.tokens >= 1.0 => {
+                        state.tokens -= 1.0;
+                        return;
+                    }
+                    None => Duration::from_secs_f64(
+                        (1.0 - state.tokens) / self.limit.requests_per_second,
+                    ),
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Hold back all requests for at least `delay`.
+    pub fn back_off(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut state = self.state.lock().unwrap();
+        state.blocked_until = state.blocked_until.max(Some(until));
+    }
+
+    /// Current state of the limiter.
+    pub fn metrics(&self) -> RateLimiterMetrics {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        self.refill(&mut state, now);
+        RateLimiterMetrics {
+            available_tokens: state.tokens,
+            backoff_remaining: state
+                .blocked_until
+                .filter(|until| *until > now)
+                .map(|until| until - now),
+            rate_limited_total: state.rate_limited_total,
+            retries_total: state.retries_total,
+        }
+    }
+
+    fn refill(&self, state: &mut RateLimiterState, now: Instant) {
+        let elapsed = now.saturating_duration_since(state.refilled_at).as_secs_f64();
+        state.tokens =
+            (state.tokens + elapsed * self.limit.requests_per_second).min(self.limit.burst as f64);
+        state.refilled_at = now;
+    }
+
+    /// Record a request rejected by the server as rate limited, backing off for `retry_after`.
+    fn record_rate_limited(&self, retry_after: Option<Duration>) {
+        self.state.lock().unwrap().rate_limited_total += 1;
+        if let Some(retry_after) = retry_after {
+            self.back_off(retry_after);
+        }
+    }
+
+    fn record_retry(&self) {
+        self.state.lock().unwrap().retries_total += 1;
+    }
+
+    /// Back off until the server's rate-limit window resets, if the response reports it is
+    /// exhausted.
+    fn observe_headers(&self, headers: &HeaderMap) {
+        if header_u64(headers, &RATE_LIMIT_REMAINING_HEADERS) != Some(0) {
+            return;
+        }
+        if let Some(reset) = header_u64(headers, &RATE_LIMIT_RESET_HEADERS) {
+            self.state.lock().unwrap().tokens = 0.0;
+            self.back_off(Duration::from_secs(reset));
+        }
+    }
+}
+
+/// Add up to half of `delay` at random, so that clients rate limited together do not all retry
+/// at the same time.
+fn with_jitter(delay: Duration) -> Duration {
+    delay + delay.mul_f64(rand::random::<f64>() / 2.0)
+}
+
 /// Order struct, containing a ProofRequest and its Signature
 ///
 /// The contents of this struct match the calldata of the `submitOrder` function in the `BoundlessMarket` contract.
@@ -95,6 +384,12 @@ pub struct Order {
     // TODO: This should not be Signature. It should be Bytes or Vec<u8>.
     #[schema(value_type = Object)]
     pub signature: Signature,
+    /// ERC-1271 signature, for requests with the smart contract signed flag set
+    ///
+    /// Set instead of `signature`, which is then unused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub contract_signature: Option<Bytes>,
 }
 
 /// Order data + order-stream id
@@ -116,6 +411,123 @@ pub struct Nonce {
     pub nonce: String,
 }
 
+/// Health of the order stream server, returned by [HEALTH_CHECK]
+///
+/// All fields are optional, as older servers return an empty body.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default, PartialEq)]
+pub struct HealthInfo {
+    /// Version of the server
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Number of provers connected over the websocket
+    #[serde(default)]
+    pub connected_provers: Option<usize>,
+    /// Time the last order was submitted, if any
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub last_order_at: Option<DateTime<Utc>>,
+    /// Time of the server when the health was checked
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub server_time: Option<DateTime<Utc>>,
+}
+
+impl HealthInfo {
+    /// Time from the last order to the health check, by the clock of the server.
+    ///
+    /// A lag growing beyond the usual interval between orders may indicate the server stopped
+    /// accepting orders.
+    pub fn order_lag(&self) -> Option<Duration> {
+        (self.server_time? - self.last_order_at?).to_std().ok()
+    }
+}
+
+/// Watchdog of the websocket streams of an [OrderStreamClient], reporting them degraded when no
+/// message, including pongs to the pings of the client, is received within a window.
+///
+/// Add it to the client with [OrderStreamClient::with_hook]. Clones share the same state. Unlike
+/// the check of pongs of the streams, which closes the connection, the watchdog also covers the
+/// time spent reconnecting, so that a stream failing to reconnect is reported.
+#[derive(Clone, Debug)]
+pub struct StreamWatchdog {
+    window: Duration,
+    started: Instant,
+    /// Milliseconds from `started` to the last message
+    last_message_ms: Arc<AtomicU64>,
+}
+
+impl StreamWatchdog {
+    /// Create a watchdog reporting the streams degraded after `window` without messages.
+    pub fn new(window: Duration) -> Self {
+        Self { window, started: Instant::now(), last_message_ms: Default::default() }
+    }
+
+    /// Time since the last message, or since the watchdog was created if none was received.
+    pub fn silence(&self) -> Duration {
+        let last_message = Duration::from_millis(self.last_message_ms.load(Ordering::Relaxed));
+        self.started.elapsed().saturating_sub(last_message)
+    }
+
+    /// Whether no message was received within the window.
+    pub fn is_degraded(&self) -> bool {
+        self.silence() > self.window
+    }
+
+    /// Resolve once no message was received within the window.
+    pub async fn degraded(&self) {
+        loop {
+            let silence = self.silence();
+            if silence > self.window {
+                return;
+            }
+            tokio::time::sleep(self.window - silence + Duration::from_millis(1)).await;
+        }
+    }
+}
+
+impl OrderStreamHook for StreamWatchdog {
+    fn on_ws_message(&self, _message: &tungstenite::Message) {
+        let elapsed = self.started.elapsed().as_millis() as u64;
+        self.last_message_ms.fetch_max(elapsed, Ordering::Relaxed);
+    }
+}
+
+/// Nonces pre-fetched from the order stream server, so that connections can authenticate without
+/// first fetching a nonce.
+///
+/// The server holds a single nonce per address, rotated on each authenticated connection attempt,
+/// so at most one nonce is pooled per address. Pooled nonces are only used once, and are dropped
+/// once older than the max age, as they may have been rotated by another connection.
+#[derive(Clone, Debug)]
+pub struct NoncePool {
+    max_age: Duration,
+    nonces: Arc<Mutex<HashMap<Address, (Nonce, Instant)>>>,
+}
+
+impl Default for NoncePool {
+    fn default() -> Self {
+        Self::new(NONCE_MAX_AGE)
+    }
+}
+
+impl NoncePool {
+    /// Create an empty pool, discarding nonces older than `max_age`.
+    pub fn new(max_age: Duration) -> Self {
+        Self { max_age, nonces: Default::default() }
+    }
+
+    /// Take the pooled nonce for the address, if there is one that has not expired.
+    pub fn take(&self, address: Address) -> Option<Nonce> {
+        let (nonce, fetched_at) = self.nonces.lock().unwrap().remove(&address)?;
+        (fetched_at.elapsed() < self.max_age).then_some(nonce)
+    }
+
+    /// Pool a nonce for the address, replacing any previous one.
+    pub fn put(&self, address: Address, nonce: Nonce) {
+        self.nonces.lock().unwrap().insert(address, (nonce, Instant::now()));
+    }
+}
+
 /// Response for submitting a new order
 #[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
 pub struct SubmitOrderRes {
@@ -126,13 +538,94 @@ pub struct SubmitOrderRes {
     pub request_id: U256,
 }
 
+/// Result of submitting one order of a batch
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct SubmitOrderResult {
+    /// Request ID of the order
+    #[schema(value_type = Object)]
+    pub request_id: U256,
+    /// Reason the order was rejected, if it was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Request to withdraw an order from the order stream, signed by the client of the request.
+///
+/// Withdrawn orders are no longer served to provers by the order stream. Note that this does not
+/// invalidate the signature of the request: provers that already received the order can still
+/// lock it on-chain.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct WithdrawOrder {
+    /// Request ID of the order
+    #[schema(value_type = Object)]
+    pub request_id: U256,
+    /// Request digest of the order
+    #[schema(value_type = Object)]
+    pub request_digest: B256,
+    /// EIP-191 signature of the [WithdrawOrder::message] by the client
+    #[schema(value_type = Object)]
+    pub signature: Signature,
+}
+
+impl WithdrawOrder {
+    /// Sign a request to withdraw the order with the given digest.
+    pub async fn new(
+        request_id: U256,
+        request_digest: B256,
+        signer: &impl Signer,
+    ) -> Result<Self, SignerErr> {
+        let signature = signer.sign_message(Self::message(request_digest).as_bytes()).await?;
+        Ok(Self { request_id, request_digest, signature })
+    }
+
+    /// Message signed to withdraw the order with the given digest.
+    ///
+    /// Distinct from the EIP-712 signature of the request, so that one can not be used as the
+    /// other.
+    pub fn message(request_digest: B256) -> String {
+        format!("Withdraw Boundless order {request_digest}")
+    }
+
+    /// Address that signed the withdrawal.
+    pub fn signer(&self) -> Result<Address, OrderError> {
+        self.signature
+            .recover_address_from_msg(Self::message(self.request_digest))
+            .map_err(|err| OrderError::InvalidSignature(err.into()))
+    }
+}
+
 impl Order {
     /// Create a new Order
     pub fn new(request: ProofRequest, request_digest: B256, signature: Signature) -> Self {
-        Self { request, request_digest, signature }
+        Self { request, request_digest, signature, contract_signature: None }
+    }
+
+    /// Create a new Order for a request signed by a smart contract, with its ERC-1271 signature.
+    pub fn new_smart_contract_signed(
+        request: ProofRequest,
+        request_digest: B256,
+        signature: impl Into<Bytes>,
+    ) -> Self {
+        Self {
+            request,
+            request_digest,
+            signature: Signature::new(U256::ZERO, U256::ZERO, false),
+            contract_signature: Some(signature.into()),
+        }
+    }
+
+    /// Signature of the request by its client, as submitted onchain.
+    pub fn client_signature(&self) -> Bytes {
+        match &self.contract_signature {
+            Some(signature) => signature.clone(),
+            None => self.signature.as_bytes().into(),
+        }
     }
 
     /// Validate the Order
+    ///
+    /// ERC-1271 signatures of requests signed by a smart contract can only be checked onchain,
+    /// so only their presence is checked, see [Self::validate_with_provider].
     pub fn validate(&self, market_address: Address, chain_id: u64) -> Result<(), OrderError> {
         self.request.validate()?;
         let domain = eip712_domain(market_address, chain_id);
@@ -140,6 +633,17 @@ impl Order {
         if hash != self.request_digest {
             return Err(OrderError::RequestError(RequestError::DigestMismatch));
         }
+        if self.request.is_smart_contract_signed() {
+            if self.contract_signature.is_none() {
+                return Err(OrderError::InvalidSignature(SignerErr::other(
+                    "missing ERC-1271 signature of smart contract signed request",
+                )));
+            }
+            return Ok(());
+        }
+        if self.contract_signature.is_some() {
+            return Err(OrderError::RequestError(RequestError::NotSmartContractSigned));
+        }
         self.request.verify_signature(
             &self.signature.as_bytes().into(),
             market_address,
@@ -147,6 +651,178 @@ impl Order {
         )?;
         Ok(())
     }
+
+    /// Validate the Order, checking the ERC-1271 signature of requests signed by a smart contract
+    /// by calling `isValidSignature` on the client with the given provider.
+    pub async fn validate_with_provider<P: Provider<Ethereum>>(
+        &self,
+        market_address: Address,
+        chain_id: u64,
+        provider: P,
+    ) -> Result<(), OrderError> {
+        self.validate(market_address, chain_id)?;
+        if let Some(signature) = &self.contract_signature {
+            self.request
+                .verify_erc1271_signature(signature, market_address, chain_id, provider)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Filter negotiated when connecting to the order stream websocket.
+///
+/// The server only pushes orders matching all of the set fields. Unset fields match any order.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrderFilter {
+    /// Minimum offer max price, in wei
+    #[schema(value_type = Option<String>)]
+    pub min_price: Option<U256>,
+    /// Maximum lock stake
+    #[schema(value_type = Option<String>)]
+    pub max_stake: Option<U256>,
+    /// Allowed image IDs
+    #[schema(value_type = Option<Vec<String>>)]
+    pub image_ids: Option<Vec<B256>>,
+    /// Allowed client addresses
+    #[schema(value_type = Option<Vec<String>>)]
+    pub client_addresses: Option<Vec<Address>>,
+}
+
+impl OrderFilter {
+    /// Whether the request matches the filter
+    pub fn matches(&self, request: &ProofRequest) -> bool {
+        self.min_price.is_none_or(|min_price| request.offer.maxPrice >= min_price)
+            && self.max_stake.is_none_or(|max_stake| request.offer.lockStake <= max_stake)
+            && self
+                .image_ids
+                .as_ref()
+                .is_none_or(|image_ids| image_ids.contains(&request.requirements.imageId))
+            && self
+                .client_addresses
+                .as_ref()
+                .is_none_or(|addresses| addresses.contains(&request.client_address()))
+    }
+
+    /// Whether the filter matches all orders
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Consumer group joined when connecting to the order stream websocket.
+///
+/// Each order is pushed to a single member of a group, the one with the fewest orders queued,
+/// so that a fleet of brokers sharing a group prices each order once. Members connect with
+/// distinct addresses, as only one connection per address is allowed. Connections outside of
+/// any group receive all orders.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConsumerGroup {
+    /// Name of the group
+    pub name: String,
+}
+
+impl ConsumerGroup {
+    /// Create a consumer group with the given name
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+/// Validation of the orders yielded by the streams of an [OrderStreamClient].
+///
+/// Orders are validated with [Order::validate] against the market address and chain ID of the
+/// client, checking the request, its digest and its signature. Invalid orders are reported to
+/// [OrderStreamHook::on_invalid_order].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OrderValidation {
+    /// Orders are yielded without being validated
+    #[default]
+    Disabled,
+    /// Invalid orders are reported and dropped
+    Drop,
+    /// Invalid orders are reported and yielded
+    Flag,
+}
+
+/// Counts of orders validated by the streams of an [OrderStreamClient], see [OrderValidation].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OrderValidationMetrics {
+    /// Number of orders validated
+    pub validated_total: u64,
+    /// Number of orders that failed validation
+    pub invalid_total: u64,
+}
+
+#[derive(Debug, Default)]
+struct OrderValidationCounters {
+    validated_total: AtomicU64,
+    invalid_total: AtomicU64,
+}
+
+/// Serialization format of orders pushed over the order stream websocket.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderFormat {
+    /// JSON, as returned by the HTTP API
+    #[default]
+    Json,
+    /// MessagePack, faster to decode than JSON
+    MessagePack,
+}
+
+/// Encoding of orders pushed over the order stream websocket.
+///
+/// Orders are pushed in text frames of JSON by default. With any other encoding, they are pushed
+/// in binary frames, optionally compressed with deflate (in the zlib format). The encoding is
+/// sent in the [ORDER_ENCODING_HEADER] as the name of the format, e.g. `msgpack`, followed by
+/// `+deflate` when compressed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OrderEncoding {
+    /// Serialization format of the orders
+    pub format: OrderFormat,
+    /// Whether each order is compressed with deflate
+    pub deflate: bool,
+}
+
+impl OrderEncoding {
+    /// Orders serialized with MessagePack and compressed with deflate.
+    pub const MSGPACK_DEFLATE: Self = Self { format: OrderFormat::MessagePack, deflate: true };
+
+    /// Whether orders are pushed in text frames of JSON, the default.
+    pub fn is_text(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Display for OrderEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.format {
+            OrderFormat::Json => write!(f, "json")?,
+            OrderFormat::MessagePack => write!(f, "msgpack")?,
+        }
+        if self.deflate {
+            write!(f, "+deflate")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for OrderEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let (format, deflate) = match value.trim().split_once('+') {
+            Some((format, "deflate")) => (format, true),
+            Some((_, compression)) => anyhow::bail!("unsupported compression: {compression}"),
+            None => (value.trim(), false),
+        };
+        let format = match format {
+            "json" => OrderFormat::Json,
+            "msgpack" => OrderFormat::MessagePack,
+            _ => anyhow::bail!("unsupported order format: {format}"),
+        };
+        Ok(Self { format, deflate })
+    }
 }
 
 /// Authentication message for connecting to order-stream websock
@@ -196,6 +872,34 @@ impl AuthMsg {
     }
 }
 
+/// Hook into the traffic of an [OrderStreamClient], e.g. for tracing, metrics or to add headers.
+///
+/// Hooks are called in the order they were added with [OrderStreamClient::with_hook], and apply
+/// to all the HTTP requests sent by the client, including retries but not the websocket
+/// handshake, and to the websocket messages received by the streams of the client. All methods
+/// default to doing nothing.
+pub trait OrderStreamHook: Debug + Send + Sync {
+    /// Called before each HTTP request is sent, and may modify it.
+    fn on_request(&self, _request: &mut reqwest::Request) {}
+
+    /// Called once the response to an HTTP request, or the error sending it, is received.
+    fn on_response(
+        &self,
+        _method: &reqwest::Method,
+        _url: &Url,
+        _response: Result<&reqwest::Response, &reqwest::Error>,
+        _elapsed: Duration,
+    ) {
+    }
+
+    /// Called on each message received over the websocket, before it is handled.
+    fn on_ws_message(&self, _message: &tungstenite::Message) {}
+
+    /// Called on each order failing validation, when enabled with
+    /// [OrderStreamClient::with_validation].
+    fn on_invalid_order(&self, _order: &OrderData, _err: &OrderError) {}
+}
+
 /// Client for interacting with the order stream server
 #[derive(Clone, Debug)]
 pub struct OrderStreamClient {
@@ -207,73 +911,399 @@ pub struct OrderStreamClient {
     pub boundless_market_address: Address,
     /// Chain ID of the network
     pub chain_id: u64,
+    /// Filter sent to the server when connecting to the websocket
+    pub filter: Option<OrderFilter>,
+    /// Consumer group joined when connecting to the websocket
+    pub consumer_group: Option<ConsumerGroup>,
+    /// Nonces pre-fetched for authenticating websocket connections
+    pub nonce_pool: NoncePool,
+    /// Rate limiter shared by all requests to the server
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Hooks called on requests, responses and websocket messages, see [OrderStreamHook]
+    pub hooks: Vec<Arc<dyn OrderStreamHook>>,
+    /// Encoding requested for orders pushed over the websocket
+    pub encoding: OrderEncoding,
+    /// Validation of the orders yielded by the streams of the client
+    pub validation: OrderValidation,
+    validation_counters: Arc<OrderValidationCounters>,
 }
 
 impl OrderStreamClient {
     /// Create a new client
     pub fn new(base_url: Url, boundless_market_address: Address, chain_id: u64) -> Self {
-        Self { client: reqwest::Client::new(), base_url, boundless_market_address, chain_id }
+        Self {
+            client: reqwest::Client::new(),
+            base_url,
+            boundless_market_address,
+            chain_id,
+            filter: None,
+            consumer_group: None,
+            nonce_pool: NoncePool::default(),
+            rate_limiter: Arc::new(RateLimiter::new(RateLimit::default())),
+            hooks: Vec::new(),
+            encoding: OrderEncoding::default(),
+            validation: OrderValidation::default(),
+            validation_counters: Default::default(),
+        }
+    }
+
+    /// Add a hook called on the requests, responses and websocket messages of the client.
+    ///
+    /// Hooks are called after those added before them.
+    pub fn with_hook(mut self, hook: impl OrderStreamHook + 'static) -> Self {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// Validate the orders yielded by the streams of the client, see [OrderValidation].
+    pub fn with_validation(self, validation: OrderValidation) -> Self {
+        Self { validation, ..self }
+    }
+
+    /// Stream the orders received on a socket connected with [Self::connect_async], calling
+    /// the hooks of the client on each message and validating orders as set with
+    /// [Self::with_validation].
+    pub fn order_stream(
+        &self,
+        socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    ) -> Pin<Box<dyn Stream<Item = OrderData> + Send>> {
+        let orders = order_stream_with_hooks(socket, self.hooks.clone());
+        if self.validation == OrderValidation::Disabled {
+            return orders;
+        }
+        let client = self.clone();
+        Box::pin(orders.filter(move |order| futures_util::future::ready(client.accept(order))))
+    }
+
+    /// Whether an order received from the server is yielded, validating it as set with
+    /// [Self::with_validation].
+    fn accept(&self, order: &OrderData) -> bool {
+        if self.validation == OrderValidation::Disabled {
+            return true;
+        }
+        self.validation_counters.validated_total.fetch_add(1, Ordering::Relaxed);
+        let Err(err) = order.order.validate(self.boundless_market_address, self.chain_id) else {
+            return true;
+        };
+        self.validation_counters.invalid_total.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!(
+            "Invalid order {} for request 0x{:x} received from order stream: {err}",
+            order.id,
+            order.order.request.id
+        );
+        for hook in &self.hooks {
+            hook.on_invalid_order(order, &err);
+        }
+        self.validation == OrderValidation::Flag
+    }
+
+    /// Send the request, calling the hooks around it.
+    async fn execute(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let mut request = request.build()?;
+        for hook in &self.hooks {
+            hook.on_request(&mut request);
+        }
+        let method = request.method().clone();
+        let url = request.url().clone();
+        let start = Instant::now();
+        let response = self.client.execute(request).await;
+        let elapsed = start.elapsed();
+        for hook in &self.hooks {
+            hook.on_response(&method, &url, response.as_ref(), elapsed);
+        }
+        response
+    }
+
+    /// Limit the rate of requests sent to the server, across all endpoints and clones of the
+    /// client.
+    pub fn with_rate_limit(self, limit: RateLimit) -> Self {
+        Self { rate_limiter: Arc::new(RateLimiter::new(limit)), ..self }
+    }
+
+    /// Current state of the rate limiter of the client.
+    pub fn rate_limit_metrics(&self) -> RateLimiterMetrics {
+        self.rate_limiter.metrics()
+    }
+
+    /// Counts of orders validated by the streams of the client and its clones.
+    pub fn validation_metrics(&self) -> OrderValidationMetrics {
+        OrderValidationMetrics {
+            validated_total: self.validation_counters.validated_total.load(Ordering::Relaxed),
+            invalid_total: self.validation_counters.invalid_total.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Send the request built by `request` once the [RateLimiter] allows it.
+    ///
+    /// While the server responds with 429 Too Many Requests, the request is retried up to
+    /// [RATE_LIMIT_MAX_RETRIES] times, after the delay asked by the server or an exponential
+    /// backoff, with jitter. The last response is returned, whatever its status.
+    async fn send(
+        &self,
+        request: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, OrderStreamErr> {
+        let mut backoff = RATE_LIMIT_INITIAL_BACKOFF;
+        let mut retries = 0;
+        loop {
+            self.rate_limiter.acquire().await;
+            let response = self.execute(request()).await?;
+            self.rate_limiter.observe_headers(response.headers());
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(response);
+            }
+
+            let retry_after = parse_retry_after(
+                response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok()),
+            );
+            let delay = with_jitter(retry_after.unwrap_or(backoff));
+            self.rate_limiter.record_rate_limited(Some(delay));
+            if retries >= RATE_LIMIT_MAX_RETRIES {
+                return Ok(response);
+            }
+            retries += 1;
+            self.rate_limiter.record_retry();
+            tracing::debug!(
+                "Rate limited by order stream at {}, retrying in {delay:?}",
+                response.url().path()
+            );
+            backoff = (backoff * 2).min(RATE_LIMIT_MAX_BACKOFF);
+        }
+    }
+
+    /// Only receive orders matching the given filter over the websocket.
+    ///
+    /// Filtering is done by the server, reducing bandwidth and the number of orders to evaluate.
+    /// Orders fetched over HTTP, such as by [Self::fetch_order], are not filtered, except by
+    /// [Self::polling_order_stream] which applies the filter on the client.
+    pub fn with_filter(self, filter: OrderFilter) -> Self {
+        Self { filter: Some(filter), ..self }
+    }
+
+    /// Share orders received over the websocket with the other members of the given group.
+    ///
+    /// The server pushes each order to only one member of the group. Orders polled with
+    /// [Self::polling_order_stream] are not shared.
+    pub fn with_consumer_group(self, consumer_group: ConsumerGroup) -> Self {
+        Self { consumer_group: Some(consumer_group), ..self }
+    }
+
+    /// Request orders pushed over the websocket in the given encoding.
+    ///
+    /// Binary and compressed encodings reduce the time spent decoding bursts of orders. Streams
+    /// returned by [order_stream] decode orders in any encoding.
+    pub fn with_encoding(self, encoding: OrderEncoding) -> Self {
+        Self { encoding, ..self }
+    }
+
+    /// Sign the proof request, returning the validated order.
+    async fn sign_order(
+        &self,
+        request: &ProofRequest,
+        signer: &impl Signer,
+    ) -> Result<Order, OrderStreamErr> {
+        let signature =
+            request.sign_request(signer, self.boundless_market_address, self.chain_id).await?;
+        let domain = eip712_domain(self.boundless_market_address, self.chain_id);
+        let request_digest = request.eip712_signing_hash(&domain.alloy_struct());
+        let order = Order::new(request.clone(), request_digest, signature);
+        order.validate(self.boundless_market_address, self.chain_id)?;
+        Ok(order)
+    }
+
+    /// Sign the proof request on behalf of a smart contract, returning the validated order.
+    async fn sign_smart_contract_order(
+        &self,
+        request: &ProofRequest,
+        signer: &SmartContractSigner,
+    ) -> Result<Order, OrderStreamErr> {
+        let signature =
+            signer.sign_request(request, self.boundless_market_address, self.chain_id).await?;
+        let request_digest = request.signing_hash(self.boundless_market_address, self.chain_id)?;
+        let order = Order::new_smart_contract_signed(request.clone(), request_digest, signature);
+        order.validate(self.boundless_market_address, self.chain_id)?;
+        Ok(order)
+    }
+
+    /// Submit a proof request to the order stream server
+    pub async fn submit_request(
+        &self,
+        request: &ProofRequest,
+        signer: &impl Signer,
+    ) -> Result<Order, OrderStreamErr> {
+        let order = self.sign_order(request, signer).await?;
+        self.submit_order(order).await
     }
 
-    /// Submit a proof request to the order stream server
-    pub async fn submit_request(
+    /// Submit a proof request signed by a smart contract to the order stream server.
+    ///
+    /// The request must have the smart contract signed flag set, see
+    /// [ProofRequest::with_smart_contract_signed_flag]. Its ERC-1271 signature is checked onchain
+    /// by the server.
+    pub async fn submit_smart_contract_signed_request(
         &self,
         request: &ProofRequest,
-        signer: &impl Signer,
-    ) -> Result<Order> {
+        signer: &SmartContractSigner,
+    ) -> Result<Order, OrderStreamErr> {
+        let order = self.sign_smart_contract_order(request, signer).await?;
+        self.submit_order(order).await
+    }
+
+    async fn submit_order(&self, order: Order) -> Result<Order, OrderStreamErr> {
         let url = self.base_url.join(ORDER_SUBMISSION_PATH)?;
-        let signature =
-            request.sign_request(signer, self.boundless_market_address, self.chain_id).await?;
-        let domain = eip712_domain(self.boundless_market_address, self.chain_id);
-        let request_digest = request.eip712_signing_hash(&domain.alloy_struct());
-        let order = Order { request: request.clone(), request_digest, signature };
-        order.validate(self.boundless_market_address, self.chain_id)?;
-        let order_json = serde_json::to_value(&order)?;
+        let order_json =
+            serde_json::to_value(&order).map_err(|err| OrderStreamErr::Protocol(err.into()))?;
         let response = self
-            .client
-            .post(url)
-            .header("Content-Type", "application/json")
-            .json(&order_json)
-            .send()
+            .send(|| {
+                self.client
+                    .post(url.clone())
+                    .header("Content-Type", "application/json")
+                    .json(&order_json)
+            })
             .await?;
 
         // Check for any errors in the response
-        if let Err(err) = response.error_for_status_ref() {
-            let error_message = match response.json::<serde_json::Value>().await {
-                Ok(json_body) => {
-                    json_body["msg"].as_str().unwrap_or("Unknown server error").to_string()
+        if !response.status().is_success() {
+            return Err(response_error(response).await);
+        }
+
+        Ok(order)
+    }
+
+    /// Withdraw an order from the order stream server, so that it is no longer served to provers.
+    ///
+    /// The signer must be the client of the request. Withdrawing does not invalidate the signed
+    /// request, provers that already received it can still lock it on-chain.
+    ///
+    /// Returns [OrderStreamErr::NotFound] if no order matches.
+    pub async fn withdraw_order(
+        &self,
+        request_id: U256,
+        request_digest: B256,
+        signer: &impl Signer,
+    ) -> Result<(), OrderStreamErr> {
+        let url = self.base_url.join(ORDER_WITHDRAW_PATH)?;
+        let withdraw = WithdrawOrder::new(request_id, request_digest, signer)
+            .await
+            .map_err(|err| OrderStreamErr::Validation(err.into()))?;
+        let response = self
+            .send(|| {
+                self.client
+                    .post(url.clone())
+                    .header("Content-Type", "application/json")
+                    .json(&withdraw)
+            })
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(OrderStreamErr::NotFound);
+        }
+        if !response.status().is_success() {
+            return Err(response_error(response).await);
+        }
+
+        Ok(())
+    }
+
+    /// Submit multiple proof requests to the order stream server, signed by the same signer.
+    ///
+    /// Orders are submitted in batches of up to [MAX_ORDER_BATCH_SIZE], each in a single HTTP
+    /// request. Returns the result of each request, in the given order: a request that fails to
+    /// sign or validate, is rejected by the server, or is part of a batch that fails to submit,
+    /// fails without affecting the others.
+    pub async fn submit_requests(
+        &self,
+        requests: &[ProofRequest],
+        signer: &impl Signer,
+    ) -> Result<Vec<Result<Order, OrderStreamErr>>, OrderStreamErr> {
+        let url = self.base_url.join(ORDER_BATCH_SUBMISSION_PATH)?;
+        let mut results = Vec::with_capacity(requests.len());
+        for chunk in requests.chunks(MAX_ORDER_BATCH_SIZE) {
+            let mut chunk_results = Vec::with_capacity(chunk.len());
+            for request in chunk {
+                chunk_results.push(self.sign_order(request, signer).await);
+            }
+            let orders: Vec<&Order> = chunk_results.iter().flatten().collect();
+            if !orders.is_empty() {
+                match self.submit_batch(&url, &orders).await {
+                    Ok(submitted) => {
+                        for (result, submitted) in
+                            chunk_results.iter_mut().filter(|res| res.is_ok()).zip(submitted)
+                        {
+                            if let Some(error) = submitted.error {
+                                *result = Err(OrderStreamErr::Validation(anyhow::anyhow!(error)));
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        for result in chunk_results.iter_mut().filter(|res| res.is_ok()) {
+                            *result = Err(err.duplicate());
+                        }
+                    }
                 }
-                Err(_) => "Failed to read server error message".to_string(),
-            };
+            }
+            results.extend(chunk_results);
+        }
+        Ok(results)
+    }
 
-            return Err(anyhow::Error::new(err).context(error_message));
+    /// Submit a batch of signed orders, returning the result of each.
+    async fn submit_batch(
+        &self,
+        url: &Url,
+        orders: &[&Order],
+    ) -> Result<Vec<SubmitOrderResult>, OrderStreamErr> {
+        let orders_json =
+            serde_json::to_value(orders).map_err(|err| OrderStreamErr::Protocol(err.into()))?;
+        let response = self
+            .send(|| {
+                self.client
+                    .post(url.clone())
+                    .header("Content-Type", "application/json")
+                    .json(&orders_json)
+            })
+            .await?;
+        if !response.status().is_success() {
+            return Err(response_error(response).await);
         }
 
-        Ok(order)
+        let results: Vec<SubmitOrderResult> = response.json().await?;
+        if results.len() != orders.len() {
+            return Err(OrderStreamErr::Protocol(anyhow::anyhow!(
+                "expected {} results for the batch, got {}",
+                orders.len(),
+                results.len()
+            )));
+        }
+        Ok(results)
     }
 
     /// Fetch an order from the order stream server.
     ///
     /// If multiple orders are found, the `request_digest` must be provided to select the correct order.
-    pub async fn fetch_order(&self, id: U256, request_digest: Option<B256>) -> Result<Order> {
+    ///
+    /// Returns [OrderStreamErr::NotFound] if no order matches.
+    pub async fn fetch_order(
+        &self,
+        id: U256,
+        request_digest: Option<B256>,
+    ) -> Result<Order, OrderStreamErr> {
         let url = self.base_url.join(&format!("{ORDER_LIST_PATH}/{id}"))?;
-        let response = self.client.get(url).send().await?;
+        let response = self.send(|| self.client.get(url.clone())).await?;
 
         if !response.status().is_success() {
-            let error_message = match response.json::<serde_json::Value>().await {
-                Ok(json_body) => {
-                    json_body["msg"].as_str().unwrap_or("Unknown server error").to_string()
-                }
-                Err(_) => "Failed to read server error message".to_string(),
-            };
-
-            return Err(anyhow::Error::msg(error_message));
+            return Err(response_error(response).await);
         }
 
         let order_data: Vec<OrderData> = response.json().await?;
         let orders: Vec<Order> = order_data.into_iter().map(|data| data.order).collect();
         if orders.is_empty() {
-            return Err(anyhow::Error::msg("No order found"));
+            return Err(OrderStreamErr::NotFound);
         } else if orders.len() == 1 {
             return Ok(orders[0].clone());
         }
@@ -284,49 +1314,295 @@ impl OrderStreamClient {
                         return Ok(order);
                     }
                 }
-                Err(anyhow::Error::msg("No order found"))
-            }
-            None => {
-                Err(anyhow::Error::msg("Multiple orders found, please provide a request digest"))
+                Err(OrderStreamErr::NotFound)
             }
+            None => Err(OrderStreamErr::Validation(anyhow::anyhow!(
+                "Multiple orders found, please provide a request digest"
+            ))),
+        }
+    }
+
+    /// Wait for a request submitted to the order stream to be fulfilled, returning its journal
+    /// and seal.
+    ///
+    /// Requests submitted offchain are fulfilled onchain, so this watches the fulfillment events
+    /// of the market of the client using the given provider, see
+    /// [BoundlessMarketService::watch_fulfillment]. Fails with [MarketError::TimeoutReached] if
+    /// the request is not fulfilled within `timeout`.
+    pub async fn wait_for_fulfillment<P>(
+        &self,
+        provider: P,
+        request_id: U256,
+        timeout: Duration,
+    ) -> Result<(Bytes, Bytes), MarketError>
+    where
+        P: Provider<Ethereum> + 'static + Clone,
+    {
+        BoundlessMarketService::new(self.boundless_market_address, provider, Address::ZERO)
+            .watch_fulfillment(request_id, timeout)
+            .await
+    }
+
+    /// Get the health of the order stream server.
+    ///
+    /// Fails if the server is unhealthy.
+    pub async fn health(&self) -> Result<HealthInfo, OrderStreamErr> {
+        let url = self.base_url.join(HEALTH_CHECK)?;
+        let response = self.send(|| self.client.get(url.clone())).await?;
+        if !response.status().is_success() {
+            return Err(response_error(response).await);
+        }
+        let body = response.bytes().await?;
+        if body.is_empty() {
+            return Ok(HealthInfo::default());
         }
+        serde_json::from_slice(&body).map_err(|err| OrderStreamErr::Protocol(err.into()))
     }
 
     /// Get the nonce from the order stream service for websocket auth
-    pub async fn get_nonce(&self, address: Address) -> Result<Nonce> {
+    pub async fn get_nonce(&self, address: Address) -> Result<Nonce, OrderStreamErr> {
         let url = self.base_url.join(AUTH_GET_NONCE)?.join(&address.to_string())?;
-        let res = self.client.get(url).send().await?;
+        let res = self.send(|| self.client.get(url.clone())).await?;
         if !res.status().is_success() {
-            anyhow::bail!("Http error {} fetching nonce", res.status())
+            return Err(response_error(res).await);
         }
         let nonce = res.json().await?;
 
         Ok(nonce)
     }
 
+    /// Fetch the nonce for the address into the [NoncePool], so that the next connection can
+    /// authenticate immediately.
+    pub async fn prefetch_nonce(&self, address: Address) -> Result<(), OrderStreamErr> {
+        let nonce = self.get_nonce(address).await?;
+        self.nonce_pool.put(address, nonce);
+        Ok(())
+    }
+
+    /// Pre-fetch the next nonce in the background, after a connection attempt rotated it.
+    fn spawn_nonce_prefetch(&self, address: Address) {
+        let client = self.clone();
+        tokio::spawn(async move {
+            if let Err(err) = client.prefetch_nonce(address).await {
+                tracing::debug!("Failed to pre-fetch order-stream nonce: {err:?}");
+            }
+        });
+    }
+
+    /// List orders with an order stream id of at least `offset`, up to `limit` orders.
+    ///
+    /// The server caps `limit` at 1000 orders per request.
+    pub async fn list_orders(
+        &self,
+        offset: i64,
+        limit: u64,
+    ) -> Result<Vec<OrderData>, OrderStreamErr> {
+        let mut url = self.base_url.join(ORDER_LIST_PATH)?;
+        url.query_pairs_mut()
+            .append_pair("offset", &offset.to_string())
+            .append_pair("limit", &limit.to_string());
+        let response = self.send(|| self.client.get(url.clone())).await?;
+        if !response.status().is_success() {
+            return Err(response_error(response).await);
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Return a stream of orders that survives disconnects from the order stream server.
+    ///
+    /// Unlike [order_stream], which ends on any socket error, this reconnects with exponential
+    /// backoff, re-authenticating with `signer` on each connection. After reconnecting, orders
+    /// submitted while disconnected are fetched from [ORDER_LIST_PATH], starting after the last
+    /// received [OrderData::id], so no orders are dropped. The stream never ends.
+    pub fn resilient_order_stream<S>(
+        &self,
+        signer: S,
+    ) -> Pin<Box<dyn Stream<Item = OrderData> + Send>>
+    where
+        S: Signer + Send + Sync + 'static,
+    {
+        let client = self.clone();
+        Box::pin(stream! {
+            let mut last_id: Option<i64> = None;
+            let mut backoff = RECONNECT_INITIAL_BACKOFF;
+            loop {
+                let socket = match client.connect_async(&signer).await {
+                    Ok(socket) => socket,
+                    Err(err) => {
+                        // Wait at least as long as the server asks to when rate limited.
+                        let delay = match err {
+                            OrderStreamErr::RateLimited { retry_after: Some(retry_after) } => {
+                                backoff.max(retry_after)
+                            }
+                            _ => backoff,
+                        };
+                        tracing::warn!("Failed to connect to order stream, retrying in {delay:?}: {err}");
+                        tokio::time::sleep(delay).await;
+                        backoff = next_backoff(backoff);
+                        continue;
+                    }
+                };
+
+                // The socket is connected before backfilling, so that orders submitted in the
+                // meantime are received on the socket. Those are deduplicated below.
+                let mut backfilled = HashSet::new();
+                if let Some(mut offset) = last_id.map(|id| id + 1) {
+                    loop {
+                        let orders = match client.list_orders(offset, ORDER_LIST_LIMIT).await {
+                            Ok(orders) => orders,
+                            Err(err) => {
+                                tracing::warn!("Failed to fetch orders missed while disconnected from order stream: {err}");
+                                break;
+                            }
+                        };
+                        let done = (orders.len() as u64) < ORDER_LIST_LIMIT;
+                        for order in orders {
+                            offset = offset.max(order.id + 1);
+                            last_id = last_id.max(Some(order.id));
+                            backfilled.insert(order.id);
+                            if client.accept(&order) {
+                                yield order;
+                            }
+                        }
+                        if done {
+                            break;
+                        }
+                    }
+                    if !backfilled.is_empty() {
+                        tracing::info!("Fetched {} orders missed while disconnected from order stream", backfilled.len());
+                    }
+                }
+
+                let mut orders = client.order_stream(socket);
+                while let Some(order) = orders.next().await {
+                    if backfilled.remove(&order.id) {
+                        continue;
+                    }
+                    // Only back off again if the connection fails before receiving any orders.
+                    backoff = RECONNECT_INITIAL_BACKOFF;
+                    last_id = last_id.max(Some(order.id));
+                    yield order;
+                }
+
+                tracing::warn!("Disconnected from order stream, reconnecting in {backoff:?}");
+                tokio::time::sleep(backoff).await;
+                backoff = next_backoff(backoff);
+            }
+        })
+    }
+
+    /// Return a stream of orders polled from [ORDER_LIST_PATH] every `poll_interval`, for use
+    /// where WebSocket connections to the order stream server are not possible.
+    ///
+    /// Orders with an [OrderData::id] greater than `since_id` are yielded, in order. If
+    /// `since_id` is not set, only orders created after the stream is started are yielded, as
+    /// with [Self::connect_async]. The [OrderFilter] set with [Self::with_filter] is applied on
+    /// the client. Failed polls are logged and retried, so the stream never ends.
+    pub fn polling_order_stream(
+        &self,
+        poll_interval: Duration,
+        since_id: Option<i64>,
+    ) -> Pin<Box<dyn Stream<Item = OrderData> + Send>> {
+        let client = self.clone();
+        Box::pin(stream! {
+            let started_at = Utc::now();
+            let mut last_id = since_id;
+            let mut interval = tokio::time::interval(poll_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let mut offset = last_id.map_or(0, |id| id + 1);
+                loop {
+                    let orders = match client.list_orders(offset, ORDER_LIST_LIMIT).await {
+                        Ok(orders) => orders,
+                        Err(err) => {
+                            tracing::warn!("Failed to poll orders from order stream, retrying in {poll_interval:?}: {err}");
+                            break;
+                        }
+                    };
+                    let done = (orders.len() as u64) < ORDER_LIST_LIMIT;
+                    for order in orders {
+                        offset = offset.max(order.id + 1);
+                        if last_id.is_some_and(|id| order.id <= id) {
+                            continue;
+                        }
+                        last_id = Some(order.id);
+                        if since_id.is_none() && order.created_at < started_at {
+                            continue;
+                        }
+                        let request = &order.order.request;
+                        if client.filter.as_ref().is_some_and(|filter| !filter.matches(request)) {
+                            continue;
+                        }
+                        if client.accept(&order) {
+                            yield order;
+                        }
+                    }
+                    if done {
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
     /// Return a WebSocket stream connected to the order stream server
     ///
-    /// An authentication message is sent to the server via the `X-Auth-Data` header.
+    /// An authentication message is sent to the server via the `X-Auth-Data` header, along with
+    /// the [OrderFilter] set with [Self::with_filter], if any, via the `X-Order-Filter` header.
     /// The authentication message must contain a valid claim of an address holding a (pre-configured)
     /// minimum balance on the boundless market in order to connect to the server.
     /// Only one connection per address is allowed.
+    ///
+    /// The nonce for the authentication message is taken from the [NoncePool] if one was
+    /// pre-fetched, and fetched from the server otherwise. After each attempt, the next nonce is
+    /// pre-fetched in the background so that reconnects can authenticate immediately.
     pub async fn connect_async(
         &self,
         signer: &impl Signer,
-    ) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
-        let nonce = self
-            .get_nonce(signer.address())
-            .await
-            .context("Failed to fetch nonce from order-stream")?;
+    ) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, OrderStreamErr> {
+        let address = signer.address();
+        if let Some(nonce) = self.nonce_pool.take(address) {
+            match self.connect_with_nonce(nonce, signer).await {
+                Err(OrderStreamErr::Auth(_)) => {
+                    tracing::debug!(
+                        "Pre-fetched order-stream nonce was rejected, fetching a new one"
+                    );
+                }
+                res => {
+                    self.spawn_nonce_prefetch(address);
+                    return res;
+                }
+            }
+        }
+
+        let nonce = self.get_nonce(address).await?;
+        let res = self.connect_with_nonce(nonce, signer).await;
+        self.spawn_nonce_prefetch(address);
+        res
+    }
 
-        let auth_msg = AuthMsg::new(nonce, &self.base_url, signer).await?;
+    async fn connect_with_nonce(
+        &self,
+        nonce: Nonce,
+        signer: &impl Signer,
+    ) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, OrderStreamErr> {
+        let auth_msg = AuthMsg::new(nonce, &self.base_url, signer).await.map_err(|err| {
+            OrderStreamErr::Auth(format!("failed to create auth message: {err:#}"))
+        })?;
 
         // Serialize the `AuthMsg` to JSON
-        let auth_json =
-            serde_json::to_string(&auth_msg).context("failed to serialize auth message")?;
+        let auth_json = serde_json::to_string(&auth_msg)
+            .context("failed to serialize auth message")
+            .map_err(OrderStreamErr::Protocol)?;
 
         // Construct the WebSocket URL
-        let host = self.base_url.host().context("missing host")?.to_string();
+        let host = self
+            .base_url
+            .host()
+            .context("missing host")
+            .map_err(OrderStreamErr::Validation)?
+            .to_string();
         // Select TLS vs not
         let ws_scheme = if self.base_url.scheme() == "https" { "wss" } else { "ws" };
 
@@ -336,41 +1612,165 @@ impl OrderStreamClient {
         };
 
         // Create the WebSocket request
-        let mut request =
-            ws_url.clone().into_client_request().context("failed to create request")?;
-        request
-            .headers_mut()
-            .insert("X-Auth-Data", auth_json.parse().context("failed to parse auth message")?);
+        let mut request = ws_url
+            .clone()
+            .into_client_request()
+            .context("failed to create request")
+            .map_err(OrderStreamErr::Validation)?;
+        request.headers_mut().insert(
+            "X-Auth-Data",
+            auth_json
+                .parse()
+                .context("failed to parse auth message")
+                .map_err(OrderStreamErr::Protocol)?,
+        );
+        if let Some(filter) = self.filter.as_ref().filter(|filter| !filter.is_empty()) {
+            let filter_json = serde_json::to_string(filter)
+                .context("failed to serialize order filter")
+                .map_err(OrderStreamErr::Validation)?;
+            request.headers_mut().insert(
+                ORDER_FILTER_HEADER,
+                filter_json
+                    .parse()
+                    .context("failed to parse order filter")
+                    .map_err(OrderStreamErr::Validation)?,
+            );
+        }
+        if let Some(consumer_group) = &self.consumer_group {
+            let group_json = serde_json::to_string(consumer_group)
+                .context("failed to serialize consumer group")
+                .map_err(OrderStreamErr::Validation)?;
+            request.headers_mut().insert(
+                CONSUMER_GROUP_HEADER,
+                group_json
+                    .parse()
+                    .context("failed to parse consumer group")
+                    .map_err(OrderStreamErr::Validation)?,
+            );
+        }
+
+        if !self.encoding.is_text() {
+            request.headers_mut().insert(
+                ORDER_ENCODING_HEADER,
+                self.encoding
+                    .to_string()
+                    .parse()
+                    .context("failed to parse order encoding")
+                    .map_err(OrderStreamErr::Validation)?,
+            );
+        }
 
         // Connect to the WebSocket server and return the socket
+        self.rate_limiter.acquire().await;
         let (socket, _) = match connect_async(request).await {
             Ok(res) => res,
-            Err(tokio_tungstenite::tungstenite::Error::Http(err)) => {
-                let http_err = if let Some(http_body) = err.body() {
-                    String::from_utf8_lossy(http_body)
+            Err(tungstenite::Error::Http(res)) => {
+                let http_err = if let Some(http_body) = res.body() {
+                    String::from_utf8_lossy(http_body).into_owned()
                 } else {
                     "Empty http error body".into()
                 };
-                anyhow::bail!(
-                    "Failed to connect to ws endpoint ({}): {} {}",
-                    ws_url,
-                    self.base_url,
-                    http_err
+                let retry_after = parse_retry_after(
+                    res.headers().get("retry-after").and_then(|value| value.to_str().ok()),
                 );
+                return Err(match res.status().as_u16() {
+                    401 | 403 => OrderStreamErr::Auth(http_err),
+                    429 => {
+                        self.rate_limiter.record_rate_limited(retry_after);
+                        OrderStreamErr::RateLimited { retry_after }
+                    }
+                    _ => OrderStreamErr::Protocol(
+                        anyhow::Error::new(tungstenite::Error::Http(res)).context(format!(
+                            "Failed to connect to ws endpoint ({}): {} {}",
+                            ws_url, self.base_url, http_err
+                        )),
+                    ),
+                });
             }
             Err(err) => {
-                anyhow::bail!(
-                    "Failed to connect to ws endpoint ({}): {} {}",
-                    ws_url,
-                    self.base_url,
-                    err
-                );
+                return Err(OrderStreamErr::Network(anyhow::Error::new(err).context(format!(
+                    "Failed to connect to ws endpoint ({}): {}",
+                    ws_url, self.base_url
+                ))));
             }
         };
         Ok(socket)
     }
 }
 
+/// Age after which a pre-fetched nonce is discarded instead of used.
+const NONCE_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+/// Number of orders fetched per request when backfilling after a reconnect.
+const ORDER_LIST_LIMIT: u64 = 1000;
+/// Delay before the first reconnect attempt of [OrderStreamClient::resilient_order_stream].
+const RECONNECT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// Maximum delay between reconnect attempts of [OrderStreamClient::resilient_order_stream].
+const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Number of times a request rate limited by the server is retried.
+const RATE_LIMIT_MAX_RETRIES: u32 = 5;
+/// Delay before retrying a rate limited request, if the server does not give one.
+const RATE_LIMIT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// Maximum delay before retrying a rate limited request, if the server does not give one.
+const RATE_LIMIT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+fn next_backoff(backoff: Duration) -> Duration {
+    (backoff * 2).min(RECONNECT_MAX_BACKOFF)
+}
+
+/// Decode an [OrderData] from the payload of a text order-stream websocket frame.
+pub fn decode_order(payload: &str) -> Result<OrderData, serde_json::Error> {
+    serde_json::from_str(payload)
+}
+
+/// Leading byte of zlib streams compressed with deflate at the default window size.
+const ZLIB_MAGIC: u8 = 0x78;
+/// Maximum size of a decompressed order frame.
+const MAX_DECOMPRESSED_ORDER_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Encode an order for a websocket frame in the given [OrderEncoding].
+pub fn encode_order<T: Serialize>(order: &T, encoding: OrderEncoding) -> Result<Vec<u8>> {
+    let payload = match encoding.format {
+        OrderFormat::Json => serde_json::to_vec(order)?,
+        OrderFormat::MessagePack => rmp_serde::to_vec_named(order)?,
+    };
+    if !encoding.deflate {
+        return Ok(payload);
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+    encoder.write_all(&payload)?;
+    Ok(encoder.finish()?)
+}
+
+/// Decode an [OrderData] from the payload of a binary order-stream websocket frame, in any
+/// [OrderEncoding].
+///
+/// The encoding is detected from the leading byte of the payload: zlib streams are decompressed
+/// first, JSON objects start with `{` and anything else is decoded as MessagePack.
+pub fn decode_order_frame(payload: &[u8]) -> Result<OrderData> {
+    if payload.first() == Some(&ZLIB_MAGIC) {
+        let mut decompressed = Vec::new();
+        ZlibDecoder::new(payload)
+            .take(MAX_DECOMPRESSED_ORDER_SIZE + 1)
+            .read_to_end(&mut decompressed)
+            .context("failed to decompress order")?;
+        anyhow::ensure!(
+            decompressed.len() as u64 <= MAX_DECOMPRESSED_ORDER_SIZE,
+            "decompressed order exceeds {MAX_DECOMPRESSED_ORDER_SIZE} bytes"
+        );
+        return decode_uncompressed_order_frame(&decompressed);
+    }
+    decode_uncompressed_order_frame(payload)
+}
+
+fn decode_uncompressed_order_frame(payload: &[u8]) -> Result<OrderData> {
+    if payload.first() == Some(&b'{') {
+        Ok(serde_json::from_slice(payload)?)
+    } else {
+        Ok(rmp_serde::from_slice(payload)?)
+    }
+}
+
 /// Stream of Order messages from a WebSocket
 ///
 /// This function takes a WebSocket stream and returns a stream of `Order` messages.
@@ -389,7 +1789,17 @@ impl OrderStreamClient {
 /// ```
 #[allow(clippy::type_complexity)]
 pub fn order_stream(
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+) -> Pin<Box<dyn Stream<Item = OrderData> + Send>> {
+    order_stream_with_hooks(socket, Vec::new())
+}
+
+/// Stream of Order messages from a WebSocket, calling the given hooks on each message
+///
+/// See [order_stream] and [OrderStreamHook::on_ws_message].
+pub fn order_stream_with_hooks(
     mut socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    hooks: Vec<Arc<dyn OrderStreamHook>>,
 ) -> Pin<Box<dyn Stream<Item = OrderData> + Send>> {
     Box::pin(stream! {
         // Create a ping interval - configurable via environment variable
@@ -415,9 +1825,14 @@ pub fn order_stream(
             tokio::select! {
                 // Handle incoming messages
                 msg_result = socket.next() => {
+                    if let Some(Ok(msg)) = &msg_result {
+                        for hook in &hooks {
+                            hook.on_ws_message(msg);
+                        }
+                    }
                     match msg_result {
                         Some(Ok(tungstenite::Message::Text(msg))) => {
-                            match serde_json::from_str::<OrderData>(&msg) {
+                            match decode_order(&msg) {
                                 Ok(order) => yield order,
                                 Err(err) => {
                                     tracing::warn!("Failed to parse order: {:?}", err);
@@ -425,6 +1840,15 @@ pub fn order_stream(
                                 }
                             }
                         }
+                        Some(Ok(tungstenite::Message::Binary(data))) => {
+                            match decode_order_frame(&data) {
+                                Ok(order) => yield order,
+                                Err(err) => {
+                                    tracing::warn!("Failed to parse binary order frame: {:?}", err);
+                                    continue;
+                                }
+                            }
+                        }
                         // Reply to Ping's inline
                         Some(Ok(tungstenite::Message::Ping(data))) => {
                             tracing::trace!("Responding to ping");
@@ -487,7 +1911,377 @@ pub fn order_stream(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::contracts::{
+        Offer, Predicate, PredicateType, RequestId, RequestInput, Requirements,
+    };
     use alloy::signers::local::LocalSigner;
+    use risc0_zkvm::sha::Digest;
+
+    async fn test_order_data(id: i64) -> OrderData {
+        let signer = LocalSigner::random();
+        let request = ProofRequest::new(
+            RequestId::new(signer.address(), id as u32),
+            Requirements::new(
+                Digest::ZERO,
+                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+            ),
+            "https://dev.null",
+            RequestInput::builder().write_slice(&[0x41; 1024]).build_inline().unwrap(),
+            Offer {
+                minPrice: U256::from(0),
+                maxPrice: U256::from(1),
+                biddingStart: 0,
+                timeout: 1000,
+                rampUpPeriod: 1,
+                lockTimeout: 1000,
+                lockStake: U256::from(0),
+            },
+        );
+        let domain = eip712_domain(Address::ZERO, 1);
+        let request_digest = request.eip712_signing_hash(&domain.alloy_struct());
+        let signature = request.sign_request(&signer, Address::ZERO, 1).await.unwrap();
+        OrderData {
+            id,
+            order: Order::new(request, request_digest, signature),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_watchdog() {
+        let watchdog = StreamWatchdog::new(Duration::from_millis(200));
+        assert!(!watchdog.is_degraded());
+
+        let message = tungstenite::Message::Pong(Default::default());
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        watchdog.on_ws_message(&message);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(!watchdog.is_degraded());
+
+        tokio::time::timeout(Duration::from_secs(1), watchdog.degraded()).await.unwrap();
+        assert!(watchdog.is_degraded());
+
+        // Clones share the state
+        watchdog.clone().on_ws_message(&message);
+        assert!(!watchdog.is_degraded());
+    }
+
+    #[test]
+    fn health_info_order_lag() {
+        let now = Utc::now();
+        let health = HealthInfo {
+            last_order_at: Some(now - chrono::Duration::seconds(30)),
+            server_time: Some(now),
+            ..Default::default()
+        };
+        assert_eq!(health.order_lag(), Some(Duration::from_secs(30)));
+        assert_eq!(HealthInfo::default().order_lag(), None);
+        assert_eq!(serde_json::from_str::<HealthInfo>("{}").unwrap(), HealthInfo::default());
+    }
+
+    #[tokio::test]
+    async fn smart_contract_signed_order() {
+        let order = test_order_data(1).await.order;
+        let json = serde_json::to_value(&order).unwrap();
+        assert!(json.get("contract_signature").is_none());
+        assert_eq!(order.client_signature(), Bytes::from(order.signature.as_bytes()));
+
+        let request = order.request.with_smart_contract_signed_flag();
+        let request_digest = request.signing_hash(Address::ZERO, 1).unwrap();
+        let order = Order::new_smart_contract_signed(request, request_digest, [0xaa; 8]);
+        let decoded: Order = serde_json::from_value(serde_json::to_value(&order).unwrap()).unwrap();
+        assert_eq!(decoded, order);
+        assert_eq!(decoded.client_signature().as_ref(), [0xaa; 8]);
+    }
+
+    #[tokio::test]
+    async fn decode_order_frame() {
+        let order_data = test_order_data(1).await;
+        let payload = serde_json::to_string(&order_data).unwrap();
+
+        let decoded = decode_order(&payload).unwrap();
+        assert_eq!(decoded.id, order_data.id);
+        assert_eq!(decoded.order, order_data.order);
+        assert_eq!(decoded.created_at, order_data.created_at);
+        decoded.order.validate(Address::ZERO, 1).unwrap();
+
+        assert!(decode_order(&payload[..payload.len() - 1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn withdraw_order_signer() {
+        let signer = LocalSigner::random();
+        let digest = B256::repeat_byte(1);
+        let withdraw = WithdrawOrder::new(U256::from(1), digest, &signer).await.unwrap();
+        assert_eq!(withdraw.signer().unwrap(), signer.address());
+
+        // The signature does not carry over to another order
+        let other = WithdrawOrder { request_digest: B256::repeat_byte(2), ..withdraw };
+        assert_ne!(other.signer().unwrap(), signer.address());
+    }
+
+    #[tokio::test]
+    async fn decode_binary_order_frames() {
+        let order_data = test_order_data(1).await;
+        let encodings = ["json", "json+deflate", "msgpack", "msgpack+deflate"];
+        for encoding in encodings {
+            let encoding: OrderEncoding = encoding.parse().unwrap();
+            assert_eq!(encoding.to_string().parse::<OrderEncoding>().unwrap(), encoding);
+
+            let payload = encode_order(&order_data, encoding).unwrap();
+            let decoded = super::decode_order_frame(&payload).unwrap();
+            assert_eq!(decoded.id, order_data.id, "{encoding}");
+            assert_eq!(decoded.order, order_data.order, "{encoding}");
+            assert_eq!(decoded.created_at, order_data.created_at, "{encoding}");
+        }
+        assert!("msgpack+gzip".parse::<OrderEncoding>().is_err());
+        assert!("cbor".parse::<OrderEncoding>().is_err());
+    }
+
+    #[tokio::test]
+    async fn order_filter_matches() {
+        let order = test_order_data(1).await.order;
+        let request = &order.request;
+        assert!(OrderFilter::default().matches(request));
+        assert!(OrderFilter::default().is_empty());
+
+        let matching = OrderFilter {
+            min_price: Some(request.offer.maxPrice),
+            max_stake: Some(request.offer.lockStake),
+            image_ids: Some(vec![request.requirements.imageId]),
+            client_addresses: Some(vec![request.client_address()]),
+        };
+        assert!(matching.matches(request));
+
+        let filters = [
+            OrderFilter {
+                min_price: Some(request.offer.maxPrice + U256::from(1)),
+                ..matching.clone()
+            },
+            OrderFilter { max_stake: Some(U256::ZERO), ..matching.clone() },
+            OrderFilter { image_ids: Some(vec![B256::repeat_byte(1)]), ..matching.clone() },
+            OrderFilter { client_addresses: Some(vec![]), ..matching.clone() },
+        ];
+        for filter in filters {
+            assert!(!filter.matches(request), "{filter:?} should not match");
+        }
+    }
+
+    #[tokio::test]
+    async fn polling_order_stream_resumes_from_cursor() {
+        let server = httpmock::MockServer::start_async().await;
+        let orders = [test_order_data(3).await, test_order_data(4).await, test_order_data(5).await];
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path(ORDER_LIST_PATH).query_param("offset", "3");
+            then.status(200).json_body_obj(&orders[..2]);
+        });
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path(ORDER_LIST_PATH).query_param("offset", "5");
+            then.status(200).json_body_obj(&orders[2..]);
+        });
+
+        let filter = OrderFilter {
+            client_addresses: Some(vec![
+                orders[0].order.request.client_address(),
+                orders[2].order.request.client_address(),
+            ]),
+            ..Default::default()
+        };
+        let client = OrderStreamClient::new(server.base_url().parse().unwrap(), Address::ZERO, 1)
+            .with_filter(filter);
+        let ids: Vec<i64> = client
+            .polling_order_stream(Duration::from_millis(10), Some(2))
+            .take(2)
+            .map(|order| order.id)
+            .collect()
+            .await;
+        assert_eq!(ids, vec![3, 5]);
+    }
+
+    #[tokio::test]
+    async fn classifies_server_errors() {
+        let server = httpmock::MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path(format!("{ORDER_LIST_PATH}/1"));
+            then.status(200).json_body(serde_json::json!([]));
+        });
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path(format!("{ORDER_LIST_PATH}/2"));
+            then.status(429).header("Retry-After", "5");
+        });
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path(format!("{ORDER_LIST_PATH}/3"));
+            then.status(401).json_body(serde_json::json!({"type": "Auth", "msg": "bad auth"}));
+        });
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path(format!("{ORDER_LIST_PATH}/4"));
+            then.status(500).body("not json");
+        });
+
+        let client = OrderStreamClient::new(server.base_url().parse().unwrap(), Address::ZERO, 1);
+        let fetch = |id: u64| client.fetch_order(U256::from(id), None);
+        assert!(matches!(fetch(1).await, Err(OrderStreamErr::NotFound)));
+        assert!(matches!(
+            fetch(2).await,
+            Err(OrderStreamErr::RateLimited { retry_after: Some(delay) }) if delay == Duration::from_secs(5)
+        ));
+        assert!(matches!(fetch(3).await, Err(OrderStreamErr::Auth(msg)) if msg == "bad auth"));
+        assert!(matches!(fetch(4).await, Err(OrderStreamErr::Protocol(_))));
+
+        let unreachable =
+            OrderStreamClient::new("http://127.0.0.1:1".parse().unwrap(), Address::ZERO, 1);
+        assert!(matches!(
+            unreachable.get_nonce(Address::ZERO).await,
+            Err(OrderStreamErr::Network(_))
+        ));
+    }
+
+    #[test]
+    fn reconnect_backoff_is_capped() {
+        let mut backoff = RECONNECT_INITIAL_BACKOFF;
+        for _ in 0..10 {
+            backoff = next_backoff(backoff);
+        }
+        assert_eq!(backoff, RECONNECT_MAX_BACKOFF);
+        assert_eq!(next_backoff(Duration::from_secs(2)), Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limited_requests() {
+        let server = httpmock::MockServer::start();
+        let limited = server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path(format!("{AUTH_GET_NONCE}{}", Address::ZERO));
+            then.status(429).header("Retry-After", "0").json_body(serde_json::json!({
+                "type": "RateLimited",
+                "msg": "too many requests"
+            }));
+        });
+        let client = OrderStreamClient::new(server.base_url().parse().unwrap(), Address::ZERO, 1)
+            .with_rate_limit(RateLimit { requests_per_second: 1000.0, burst: 2 });
+
+        assert!(matches!(
+            client.get_nonce(Address::ZERO).await,
+            Err(OrderStreamErr::RateLimited { retry_after: Some(_) })
+        ));
+        limited.assert_hits(RATE_LIMIT_MAX_RETRIES as usize + 1);
+        let metrics = client.rate_limit_metrics();
+        assert_eq!(metrics.rate_limited_total, RATE_LIMIT_MAX_RETRIES as u64 + 1);
+        assert_eq!(metrics.retries_total, RATE_LIMIT_MAX_RETRIES as u64);
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingHook {
+        responses: Mutex<Vec<(reqwest::Method, String, Option<u16>)>>,
+        invalid_orders: Mutex<Vec<i64>>,
+    }
+
+    impl OrderStreamHook for Arc<RecordingHook> {
+        fn on_request(&self, request: &mut reqwest::Request) {
+            request.headers_mut().insert("x-client-id", "test".parse().unwrap());
+        }
+
+        fn on_response(
+            &self,
+            method: &reqwest::Method,
+            url: &Url,
+            response: Result<&reqwest::Response, &reqwest::Error>,
+            _elapsed: Duration,
+        ) {
+            let status = response.ok().map(|response| response.status().as_u16());
+            self.responses.lock().unwrap().push((method.clone(), url.path().to_string(), status));
+        }
+
+        fn on_invalid_order(&self, order: &OrderData, _err: &OrderError) {
+            self.invalid_orders.lock().unwrap().push(order.id);
+        }
+    }
+
+    #[tokio::test]
+    async fn validates_polled_orders() {
+        // With the signature of another order
+        let mut invalid = test_order_data(1).await;
+        invalid.order.signature = test_order_data(3).await.order.signature;
+        let valid = test_order_data(2).await;
+        let server = httpmock::MockServer::start();
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path(ORDER_LIST_PATH);
+            then.status(200).json_body(serde_json::to_value([&invalid, &valid]).unwrap());
+        });
+
+        let hook = Arc::new(RecordingHook::default());
+        let base_client =
+            OrderStreamClient::new(Url::parse(&server.base_url()).unwrap(), Address::ZERO, 1)
+                .with_hook(hook.clone());
+        for (validation, yielded) in
+            [(OrderValidation::Drop, vec![2]), (OrderValidation::Flag, vec![1, 2])]
+        {
+            let client = base_client.clone().with_validation(validation);
+            let orders: Vec<_> = client
+                .polling_order_stream(Duration::from_secs(60), Some(0))
+                .take(yielded.len())
+                .map(|order| order.id)
+                .collect()
+                .await;
+            assert_eq!(orders, yielded);
+        }
+        assert_eq!(*hook.invalid_orders.lock().unwrap(), vec![1, 1]);
+        assert_eq!(
+            base_client.validation_metrics(),
+            OrderValidationMetrics { validated_total: 4, invalid_total: 2 }
+        );
+    }
+
+    #[tokio::test]
+    async fn calls_hooks_around_requests() {
+        let server = httpmock::MockServer::start();
+        let nonce = server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .path(format!("{AUTH_GET_NONCE}{}", Address::ZERO))
+                .header("x-client-id", "test");
+            then.status(200).json_body(serde_json::json!({ "nonce": "TEST_NONCE" }));
+        });
+        let hook = Arc::new(RecordingHook::default());
+        let client = OrderStreamClient::new(server.base_url().parse().unwrap(), Address::ZERO, 1)
+            .with_hook(hook.clone());
+
+        assert_eq!(client.get_nonce(Address::ZERO).await.unwrap().nonce, "TEST_NONCE");
+        nonce.assert_hits(1);
+        assert_eq!(
+            *hook.responses.lock().unwrap(),
+            [(reqwest::Method::GET, format!("{AUTH_GET_NONCE}{}", Address::ZERO), Some(200))]
+        );
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_backs_off_on_exhausted_window() {
+        let limiter = RateLimiter::new(RateLimit { requests_per_second: 1.0, burst: 1 });
+        limiter.acquire().await;
+        assert!(limiter.metrics().available_tokens < 1.0);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset", "30".parse().unwrap());
+        limiter.observe_headers(&headers);
+        let backoff = limiter.metrics().backoff_remaining.unwrap();
+        assert!(backoff > Duration::from_secs(29) && backoff <= Duration::from_secs(30));
+    }
+
+    #[test]
+    fn nonce_pool_takes_fresh_nonces_once() {
+        let address = Address::repeat_byte(1);
+        let nonce = Nonce { nonce: "TEST_NONCE".to_string() };
+
+        let pool = NoncePool::default();
+        assert!(pool.take(address).is_none());
+        pool.put(address, nonce.clone());
+        assert!(pool.take(Address::repeat_byte(2)).is_none());
+        assert_eq!(pool.take(address).unwrap().nonce, nonce.nonce);
+        assert!(pool.take(address).is_none());
+
+        let pool = NoncePool::new(Duration::ZERO);
+        pool.put(address, nonce);
+        assert!(pool.take(address).is_none());
+    }
 
     #[tokio::test]
     async fn auth_msg_verify() {
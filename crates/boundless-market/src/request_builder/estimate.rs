@@ -0,0 +1,319 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, fmt};
+
+use alloy::{
+    network::Ethereum,
+    primitives::{utils::format_ether, U256},
+    providers::Provider,
+};
+use anyhow::Context;
+use risc0_zkvm::{Digest, Journal};
+
+use super::{
+    preflight_layer::session_results, Adapt, Layer, OfferLayer, OfferLayerConfig, OfferParams,
+    RequestParams, StandardRequestBuilder,
+};
+use crate::contracts::{boundless_market::BoundlessMarketService, Offer, RequestId, Requirements};
+
+/// Number of blocks queried for logs at once.
+const LOG_QUERY_CHUNK_BLOCKS: u64 = 1_000;
+
+/// Maximum number of recent locks sampled.
+const MAX_SAMPLED_LOCKS: usize = 200;
+
+fn median<T: Ord + Copy>(values: &[T]) -> Option<T> {
+    let mut values = values.to_vec();
+    values.sort();
+    values.get(values.len() / 2).copied()
+}
+
+/// Recent activity of the market, sampled from the requests locked on chain.
+#[non_exhaustive]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MarketConditions {
+    /// Current gas price, in wei.
+    pub gas_price: u128,
+
+    /// Number of recently locked requests sampled.
+    pub sampled_locks: usize,
+
+    /// Median time in seconds from the start of bidding until recently locked requests were
+    /// locked.
+    pub median_lock_delay: Option<u64>,
+
+    /// Median lock timeout of recently locked requests, in seconds.
+    pub median_lock_timeout: Option<u32>,
+
+    /// Median timeout of recently locked requests, in seconds.
+    pub median_timeout: Option<u32>,
+}
+
+impl MarketConditions {
+    /// Samples the gas price and the requests locked in the last `lookback_blocks` blocks.
+    pub async fn sample<P>(
+        market: &BoundlessMarketService<P>,
+        lookback_blocks: u64,
+    ) -> anyhow::Result<Self>
+    where
+        P: Provider<Ethereum> + 'static + Clone,
+    {
+        let provider = market.instance().provider();
+        let gas_price = provider.get_gas_price().await.context("failed to get gas price")?;
+        let current_block =
+            provider.get_block_number().await.context("failed to get block number")?;
+
+        let mut locks = Vec::new();
+        let mut from_block = current_block.saturating_sub(lookback_blocks);
+        while from_block <= current_block {
+            let to_block = (from_block + LOG_QUERY_CHUNK_BLOCKS - 1).min(current_block);
+            let logs = market
+                .instance()
+                .RequestLocked_filter()
+                .from_block(from_block)
+                .to_block(to_block)
+                .query()
+                .await
+                .context("failed to query RequestLocked events")?;
+            locks.extend(logs);
+            from_block = to_block + 1;
+        }
+
+        let mut lock_delays = Vec::new();
+        let mut lock_timeouts = Vec::new();
+        let mut timeouts = Vec::new();
+        let mut block_timestamps = HashMap::new();
+        for (event, log) in locks.iter().rev().take(MAX_SAMPLED_LOCKS) {
+            let offer = &event.request.offer;
+            lock_timeouts.push(offer.lockTimeout);
+            timeouts.push(offer.timeout);
+
+            let lock_timestamp = match (log.block_timestamp, log.block_number) {
+                (Some(timestamp), _) => timestamp,
+                (None, Some(block_number)) => match block_timestamps.get(&block_number) {
+                    Some(timestamp) => *timestamp,
+                    None => {
+                        let timestamp = provider
+                            .get_block_by_number(block_number.into())
+                            .await
+                            .with_context(|| format!("failed to get block {block_number}"))?
+                            .with_context(|| format!("block {block_number} not found"))?
+                            .header
+                            .timestamp;
+                        block_timestamps.insert(block_number, timestamp);
+                        timestamp
+                    }
+                },
+                (None, None) => continue,
+            };
+            lock_delays.push(lock_timestamp.saturating_sub(offer.biddingStart));
+        }
+
+        Ok(Self {
+            gas_price,
+            sampled_locks: lock_timeouts.len(),
+            median_lock_delay: median(&lock_delays),
+            median_lock_timeout: median(&lock_timeouts),
+            median_timeout: median(&timeouts),
+        })
+    }
+
+    /// Adjusts the timeouts of the offer config to the market.
+    ///
+    /// The lock timeout is raised to the median lock timeout of recently locked requests, as
+    /// provers may not lock requests giving them less time than usual to prove. The timeout keeps
+    /// the configured time after the lock timeout, and leaves at least twice the median time it
+    /// took recently locked requests to be locked.
+    pub fn adjust(&self, config: &OfferLayerConfig) -> OfferLayerConfig {
+        let lock_timeout = config.lock_timeout.max(self.median_lock_timeout.unwrap_or_default());
+        let lock_delay = self.median_lock_delay.unwrap_or_default();
+        let lock_delay = u32::try_from(lock_delay.saturating_mul(2)).unwrap_or(u32::MAX);
+        let timeout = config
+            .timeout
+            .max(lock_timeout.saturating_add(config.timeout.saturating_sub(config.lock_timeout)))
+            .max(lock_timeout.saturating_add(lock_delay));
+        OfferLayerConfig { lock_timeout, timeout, ..config.clone() }
+    }
+}
+
+/// Estimated cost of a proof request, with the offer suggested for the current market.
+///
+/// Returned by [StandardRequestBuilder::estimate]. Use [CostEstimate::apply] to build a request
+/// with the suggested offer, without executing the program again.
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct CostEstimate {
+    /// Count of the RISC Zero execution cycles, from executing the program locally.
+    pub cycles: u64,
+
+    /// Image ID of the program.
+    pub image_id: Digest,
+
+    /// Journal resulting from executing the program locally.
+    pub journal: Journal,
+
+    /// Upper bound of the gas cost of locking and fulfilling the request, in wei.
+    pub gas_cost: U256,
+
+    /// Suggested offer, with prices for the estimated cycles and gas cost, and timeouts for the
+    /// market conditions.
+    pub offer: Offer,
+
+    /// Market conditions the offer was suggested for.
+    pub market: MarketConditions,
+}
+
+impl CostEstimate {
+    /// Sets the results of the local execution and the suggested offer on the parameters.
+    ///
+    /// The bidding start is kept from the parameters, so that unless set it is set when the request
+    /// is built.
+    pub fn apply(&self, params: RequestParams) -> RequestParams {
+        let offer =
+            OfferParams { bidding_start: params.offer.bidding_start, ..self.offer.clone().into() };
+        params
+            .with_cycles(self.cycles)
+            .with_image_id(self.image_id)
+            .with_journal(self.journal.clone())
+            .with_offer(offer)
+    }
+}
+
+impl fmt::Display for CostEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} cycles, price {} - {} ETH (gas cost up to {} ETH), lock timeout {}s, timeout {}s, from {} recent locks",
+            self.cycles,
+            format_ether(self.offer.minPrice),
+            format_ether(self.offer.maxPrice),
+            format_ether(self.gas_cost),
+            self.offer.lockTimeout,
+            self.offer.timeout,
+            self.market.sampled_locks,
+        )
+    }
+}
+
+impl<P, S> StandardRequestBuilder<P, S>
+where
+    P: Provider<Ethereum> + 'static + Clone,
+{
+    /// Estimates the cost of a request and suggests an offer for the current market.
+    ///
+    /// The program is executed locally with the given environment, unless the cycles and
+    /// journal are already set, and nothing is uploaded. Prices follow the per-cycle prices of
+    /// the [OfferLayerConfig] plus the gas cost at the current gas price, and timeouts are
+    /// adjusted to the requests recently locked on chain, see [MarketConditions::adjust]. Offer
+    /// fields set on the parameters are kept.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(
+    /// #     request_builder: boundless_market::request_builder::StandardRequestBuilder,
+    /// # ) -> anyhow::Result<()> {
+    /// # const ECHO_ELF: &[u8] = b"";
+    /// use boundless_market::request_builder::RequestBuilder;
+    ///
+    /// let params = request_builder.params().with_program(ECHO_ELF).with_stdin(b"hello!");
+    /// let estimate = request_builder.estimate(params.clone()).await?;
+    /// println!("{estimate}");
+    /// let request = request_builder.build(estimate.apply(params)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn estimate(&self, params: impl Into<RequestParams>) -> anyhow::Result<CostEstimate> {
+        let params: RequestParams = params.into();
+        let params = match (params.cycles, &params.journal) {
+            (Some(_), Some(_)) => params,
+            _ => {
+                let program = params.require_program().context("failed to estimate request")?;
+                let env = params.require_env().context("failed to estimate request")?;
+                let session_info = self.preflight_layer.process((program, env)).await?;
+                let (cycles, journal, image_id) = session_results(session_info)?;
+                params.with_cycles(cycles).with_journal(journal).with_image_id(image_id)
+            }
+        };
+        let params = params.process_with(&self.requirements_layer).await?;
+        let requirements: Requirements = params
+            .requirements
+            .clone()
+            .try_into()
+            .context("failed to construct requirements for estimate")?;
+        let cycles = params.require_cycles()?;
+        let image_id = match params.image_id {
+            Some(image_id) => image_id,
+            None => Digest::from(<[u8; 32]>::from(requirements.imageId)),
+        };
+        let request_id = match &params.request_id {
+            Some(request_id) => request_id.clone(),
+            None => RequestId::new(self.request_id_layer.boundless_market.caller(), 0),
+        };
+
+        let market = MarketConditions::sample(
+            &self.request_id_layer.boundless_market,
+            self.offer_layer.config.market_lookback_blocks,
+        )
+        .await?;
+        tracing::debug!("Sampled market conditions: {market:?}");
+        let offer_layer = OfferLayer::new(
+            self.offer_layer.provider.clone(),
+            market.adjust(&self.offer_layer.config),
+        );
+        let offer =
+            offer_layer.process((&requirements, &request_id, Some(cycles), &params.offer)).await?;
+        let gas_cost = offer_layer.estimate_gas_cost_upper_bound(
+            &requirements,
+            &request_id,
+            market.gas_price,
+        )?;
+
+        Ok(CostEstimate {
+            cycles,
+            image_id,
+            journal: params.require_journal()?.clone(),
+            gas_cost,
+            offer,
+            market,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adjusts_timeouts_to_market() {
+        let config = OfferLayerConfig::builder().lock_timeout(600).timeout(1200).build().unwrap();
+
+        // Without recent locks the config is kept
+        let adjusted = MarketConditions::default().adjust(&config);
+        assert_eq!((adjusted.lock_timeout, adjusted.timeout), (600, 1200));
+
+        // Lock timeouts are raised to the market, keeping the time after the lock timeout
+        let market = MarketConditions {
+            median_lock_timeout: Some(900),
+            median_lock_delay: Some(30),
+            ..Default::default()
+        };
+        let adjusted = market.adjust(&config);
+        assert_eq!((adjusted.lock_timeout, adjusted.timeout), (900, 1500));
+
+        // Slow locks leave more time to be locked
+        let market = MarketConditions { median_lock_delay: Some(900), ..Default::default() };
+        let adjusted = market.adjust(&config);
+        assert_eq!((adjusted.lock_timeout, adjusted.timeout), (600, 2400));
+    }
+}
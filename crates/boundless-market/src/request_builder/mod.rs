@@ -51,6 +51,8 @@ pub use offer_layer::{
 };
 mod finalizer;
 pub use finalizer::{Finalizer, FinalizerConfig, FinalizerConfigBuilder};
+mod estimate;
+pub use estimate::{CostEstimate, MarketConditions};
 
 /// A trait for building proof requests, used by the [Client][crate::Client].
 ///
@@ -706,6 +708,38 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn estimate_cost() -> anyhow::Result<()> {
+        let anvil = Anvil::new().spawn();
+        let test_ctx = create_test_ctx(&anvil).await.unwrap();
+        let storage = Arc::new(MockStorageProvider::start());
+        let market = BoundlessMarketService::new(
+            test_ctx.deployment.boundless_market_address,
+            test_ctx.customer_provider.clone(),
+            test_ctx.customer_signer.address(),
+        );
+
+        let request_builder = StandardRequestBuilder::builder()
+            .storage_layer(Some(storage))
+            .offer_layer(test_ctx.customer_provider.clone())
+            .request_id_layer(market)
+            .build()?;
+
+        let params = request_builder.params().with_program(ECHO_ELF).with_stdin(b"hello!");
+        let estimate = request_builder.estimate(params.clone()).await?;
+        assert!(estimate.cycles > 0);
+        assert_eq!(estimate.image_id, compute_image_id(ECHO_ELF)?);
+        assert_eq!(estimate.journal.bytes, b"hello!");
+        assert_eq!(estimate.market.sampled_locks, 0);
+        assert!(estimate.offer.maxPrice > estimate.gas_cost);
+
+        let request = request_builder.build(estimate.apply(params)).await?;
+        assert_eq!(request.offer.maxPrice, estimate.offer.maxPrice);
+        assert_eq!(request.offer.timeout, estimate.offer.timeout);
+        Ok(())
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn without_storage_provider() -> anyhow::Result<()> {
@@ -85,6 +85,11 @@ pub struct OfferLayerConfig {
     /// Supported proof types and their corresponding selectors.
     #[builder(setter(into), default)]
     pub supported_selectors: SupportedSelectors,
+
+    /// Number of recent blocks sampled for locked requests when estimating the cost of a
+    /// request, see [StandardRequestBuilder::estimate][super::StandardRequestBuilder::estimate].
+    #[builder(default = "1_000")]
+    pub market_lookback_blocks: u64,
 }
 
 #[non_exhaustive]
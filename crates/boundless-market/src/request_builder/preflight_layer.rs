@@ -17,7 +17,7 @@ use crate::contracts::{RequestInput, RequestInputType};
 use crate::input::GuestEnv;
 use crate::storage::fetch_url;
 use anyhow::{bail, ensure, Context};
-use risc0_zkvm::{default_executor, sha::Digestible, SessionInfo};
+use risc0_zkvm::{default_executor, sha::Digestible, Digest, Journal, SessionInfo};
 use url::Url;
 
 /// A layer that performs preflight execution of the guest program.
@@ -67,6 +67,28 @@ impl Layer<(&Url, &RequestInput)> for PreflightLayer {
     }
 }
 
+impl Layer<(&[u8], &GuestEnv)> for PreflightLayer {
+    type Output = SessionInfo;
+    type Error = anyhow::Error;
+
+    async fn process(&self, (program, env): (&[u8], &GuestEnv)) -> anyhow::Result<Self::Output> {
+        let session_info = default_executor().execute(env.clone().try_into()?, program)?;
+        Ok(session_info)
+    }
+}
+
+/// Returns the cycle count, journal and image ID of a preflight execution.
+pub(super) fn session_results(session_info: SessionInfo) -> anyhow::Result<(u64, Journal, Digest)> {
+    let cycles = session_info.segments.iter().map(|segment| 1 << segment.po2).sum::<u64>();
+    // NOTE: SessionInfo should have ReceiptClaim provided for recent versions of risc0_zkvm.
+    let image_id = session_info
+        .receipt_claim
+        .context("preflight execution did not provide ReceiptClaim")?
+        .pre
+        .digest();
+    Ok((cycles, session_info.journal, image_id))
+}
+
 impl Adapt<PreflightLayer> for RequestParams {
     type Output = RequestParams;
     type Error = anyhow::Error;
@@ -82,15 +104,7 @@ impl Adapt<PreflightLayer> for RequestParams {
         let input = self.require_request_input().context("failed to preflight request")?;
 
         let session_info = layer.process((program_url, input)).await?;
-        let cycles = session_info.segments.iter().map(|segment| 1 << segment.po2).sum::<u64>();
-        let journal = session_info.journal;
-
-        // NOTE: SessionInfo should have ReceiptClaim provided for recent versions of risc0_zkvm.
-        let preflight_image_id = session_info
-            .receipt_claim
-            .context("preflight execution did not provide ReceiptClaim")?
-            .pre
-            .digest();
+        let (cycles, journal, preflight_image_id) = session_results(session_info)?;
         if let Some(provided_image_id) = self.image_id {
             ensure!(provided_image_id == preflight_image_id, "provided image ID does not match the value calculated in preflight: {provided_image_id} != {preflight_image_id}");
         }
@@ -0,0 +1,228 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provider implementation for uploading programs and inputs to Google Cloud Storage.
+//!
+//! Uploads go through the S3-compatible XML API of Cloud Storage, authenticated with an HMAC key
+//! of a service account. See <https://cloud.google.com/storage/docs/interoperability>.
+
+use std::{env::VarError, fmt::Debug, result::Result::Ok, time::Duration};
+
+use async_trait::async_trait;
+use aws_sdk_s3::{
+    config::{Builder, Credentials, Region},
+    presigning::{PresigningConfig, PresigningConfigError},
+    primitives::ByteStream,
+    Error as S3Error,
+};
+use reqwest::Url;
+use sha2::{Digest as _, Sha256};
+use url::ParseError;
+
+use super::{StorageProvider, StorageProviderConfig};
+
+const DEFAULT_GCS_URL: &str = "https://storage.googleapis.com";
+
+/// Region used to sign requests, which Cloud Storage accepts regardless of the bucket location.
+const GCS_SIGNING_REGION: &str = "auto";
+
+#[derive(Clone, Debug)]
+/// Storage provider that uploads programs and inputs to a Google Cloud Storage bucket.
+///
+/// The bucket must already exist. Uploads are returned as presigned URLs valid for an hour, or,
+/// if presigning is disabled, as plain URLs that require the bucket to be publicly readable.
+pub struct GcsStorageProvider {
+    gcs_bucket: String,
+    gcs_url: Url,
+    client: aws_sdk_s3::Client,
+    presigned: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+/// Error type for the Google Cloud Storage provider.
+pub enum GcsStorageProviderError {
+    /// Error type for errors of the S3-compatible API.
+    ///
+    /// Inside a [Box] because [S3Error] is rather large.
+    #[error("GCS error: {0}")]
+    GcsError(#[from] Box<S3Error>),
+
+    /// Error type for presigning errors.
+    #[error("GCS presigning error: {0}")]
+    PresigningConfigError(#[from] PresigningConfigError),
+
+    /// Error type for environment variable errors.
+    #[error("environment variable error: {0}")]
+    EnvVar(#[from] VarError),
+
+    /// Error type for missing configuration parameters.
+    #[error("missing config parameter: {0}")]
+    Config(String),
+
+    /// Error type for URL parsing errors.
+    #[error("url parse error: {0}")]
+    UrlParseError(#[from] ParseError),
+
+    /// Error type for other errors.
+    #[error("{0}")]
+    Other(#[from] anyhow::Error),
+}
+
+impl GcsStorageProvider {
+    /// Creates a new GCS storage provider from the environment variables.
+    pub fn from_env() -> Result<Self, GcsStorageProviderError> {
+        let access_key = std::env::var("GCS_ACCESS_KEY")?;
+        let secret_key = std::env::var("GCS_SECRET_KEY")?;
+        let bucket = std::env::var("GCS_BUCKET")?;
+        let url = match std::env::var("GCS_URL") {
+            Ok(url) => url,
+            Err(VarError::NotPresent) => DEFAULT_GCS_URL.to_string(),
+            Err(e) => return Err(e.into()),
+        };
+        let presigned = std::env::var_os("GCS_NO_PRESIGNED").is_none();
+
+        Self::from_parts(access_key, secret_key, bucket, url, presigned)
+    }
+
+    /// Creates a new GCS storage provider from the given parts.
+    ///
+    /// The access and secret keys are those of an HMAC key of the service account to upload as.
+    pub fn from_parts(
+        access_key: String,
+        secret_key: String,
+        bucket: String,
+        url: String,
+        presigned: bool,
+    ) -> Result<Self, GcsStorageProviderError> {
+        let gcs_url = Url::parse(&url)?;
+        let cred = Credentials::new(access_key, secret_key, None, None, "loaded-from-custom-env");
+
+        let gcs_config = Builder::new()
+            .endpoint_url(url)
+            .credentials_provider(cred)
+            .behavior_version_latest()
+            .region(Region::new(GCS_SIGNING_REGION))
+            .force_path_style(true)
+            .build();
+
+        let client = aws_sdk_s3::Client::from_conf(gcs_config);
+
+        Ok(Self { gcs_bucket: bucket, gcs_url, client, presigned })
+    }
+
+    /// Creates a new GCS storage provider from the given configuration.
+    pub fn from_config(config: &StorageProviderConfig) -> Result<Self, GcsStorageProviderError> {
+        let access_key = config
+            .gcs_access_key
+            .clone()
+            .ok_or_else(|| GcsStorageProviderError::Config("gcs_access_key".to_string()))?;
+        let secret_key = config
+            .gcs_secret_key
+            .clone()
+            .ok_or_else(|| GcsStorageProviderError::Config("gcs_secret_key".to_string()))?;
+        let bucket = config
+            .gcs_bucket
+            .clone()
+            .ok_or_else(|| GcsStorageProviderError::Config("gcs_bucket".to_string()))?;
+        let url = config.gcs_url.clone().unwrap_or_else(|| DEFAULT_GCS_URL.to_string());
+        let presigned = config.gcs_use_presigned.unwrap_or(true);
+
+        Self::from_parts(access_key, secret_key, bucket, url, presigned)
+    }
+
+    async fn upload(
+        &self,
+        data: impl AsRef<[u8]>,
+        key: &str,
+    ) -> Result<Url, GcsStorageProviderError> {
+        let byte_stream = ByteStream::from(data.as_ref().to_vec());
+
+        self.client
+            .put_object()
+            .bucket(&self.gcs_bucket)
+            .key(key)
+            .body(byte_stream)
+            .send()
+            .await
+            .map_err(|e| Box::new(S3Error::from(e.into_service_error())))?;
+
+        if !self.presigned {
+            return Ok(self.gcs_url.join(&format!("{}/{key}", self.gcs_bucket))?);
+        }
+
+        let presigned_request = self
+            .client
+            .get_object()
+            .bucket(&self.gcs_bucket)
+            .key(key)
+            .presigned(PresigningConfig::expires_in(Duration::from_secs(3600))?)
+            .await
+            .map_err(|e| Box::new(S3Error::from(e.into_service_error())))?;
+
+        Ok(Url::parse(presigned_request.uri())?)
+    }
+}
+
+#[async_trait]
+impl StorageProvider for GcsStorageProvider {
+    type Error = GcsStorageProviderError;
+
+    async fn upload_program(&self, program: &[u8]) -> Result<Url, Self::Error> {
+        let image_id = risc0_zkvm::compute_image_id(program)?;
+        let key = format!("program/{image_id}");
+        self.upload(program, &key).await
+    }
+
+    async fn upload_input(&self, input: &[u8]) -> Result<Url, Self::Error> {
+        let digest = Sha256::digest(input);
+        let key = format!("input/{}", hex::encode(digest.as_slice()));
+        self.upload(input, &key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn uploads_to_bucket() {
+        let server = MockServer::start_async().await;
+        let input = b"hello";
+        let key = format!("input/{}", hex::encode(Sha256::digest(input)));
+        let put = server
+            .mock_async(|when, then| {
+                when.method(PUT).path(format!("/bucket/{key}"));
+                then.status(200);
+            })
+            .await;
+
+        let provider = GcsStorageProvider::from_parts(
+            "access".into(),
+            "secret".into(),
+            "bucket".into(),
+            server.base_url(),
+            false,
+        )
+        .unwrap();
+        let url = provider.upload_input(input).await.unwrap();
+        put.assert_async().await;
+        assert_eq!(url.as_str(), server.url(format!("/bucket/{key}")));
+
+        let provider = GcsStorageProvider { presigned: true, ..provider };
+        let url = provider.upload_input(input).await.unwrap();
+        assert_eq!(url.path(), format!("/bucket/{key}"));
+        assert!(url.query_pairs().any(|(name, _)| name == "X-Amz-Signature"));
+    }
+}
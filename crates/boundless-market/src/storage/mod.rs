@@ -25,12 +25,14 @@ use reqwest::Url;
 
 mod fetch;
 mod file;
+mod gcs;
 mod mock;
 mod pinata;
 mod s3;
 
 pub use fetch::fetch_url;
 pub use file::{TempFileStorageProvider, TempFileStorageProviderError};
+pub use gcs::{GcsStorageProvider, GcsStorageProviderError};
 pub use mock::{MockStorageError, MockStorageProvider};
 pub use pinata::{PinataStorageProvider, PinataStorageProviderError};
 pub use s3::{S3StorageProvider, S3StorageProviderError};
@@ -86,6 +88,8 @@ impl<S: StorageProvider + Sync + Send + ?Sized> StorageProvider for Arc<S> {
 pub enum StandardStorageProvider {
     /// S3 storage provider.
     S3(S3StorageProvider),
+    /// Google Cloud Storage provider.
+    Gcs(GcsStorageProvider),
     /// Pinata storage provider.
     Pinata(PinataStorageProvider),
     /// Temporary file storage provider, used for local testing.
@@ -102,6 +106,9 @@ pub enum StandardStorageProviderError {
     /// Error type for the S3 storage provider.
     #[error("S3 storage provider error")]
     S3(#[from] S3StorageProviderError),
+    /// Error type for the Google Cloud Storage provider.
+    #[error("GCS storage provider error")]
+    Gcs(#[from] GcsStorageProviderError),
     /// Error type for the Pinata storage provider.
     #[error("Pinata storage provider error")]
     Pinata(#[from] PinataStorageProviderError),
@@ -129,6 +136,8 @@ pub enum StorageProviderType {
     None,
     /// S3 storage provider.
     S3,
+    /// Google Cloud Storage provider.
+    Gcs,
     /// Pinata storage provider.
     Pinata,
     /// Temporary file storage provider.
@@ -142,15 +151,18 @@ pub enum StorageProviderType {
 #[non_exhaustive]
 #[derive(Clone, Default, Debug, Args, Builder)]
 pub struct StorageProviderConfig {
-    /// Storage provider to use [possible values: s3, pinata, file]
+    /// Storage provider to use [possible values: s3, gcs, pinata, file]
     ///
     /// - For 's3', the following options are required:
     ///   --s3-access-key, --s3-secret-key, --s3-bucket, --s3-url, --aws-region
+    /// - For 'gcs', the following options are required:
+    ///   --gcs-access-key, --gcs-secret-key, --gcs-bucket (optionally, you can specify --gcs-url)
     /// - For 'pinata', the following option is required:
     ///   --pinata-jwt (optionally, you can specify --pinata-api-url, --ipfs-gateway-url)
     /// - For 'file', no additional options are required (optionally, you can specify --file-path)    
     #[arg(long, env, value_enum, default_value = "none", default_value_ifs = [
         ("s3_access_key", ArgPredicate::IsPresent, "s3"),
+        ("gcs_access_key", ArgPredicate::IsPresent, "gcs"),
         ("pinata_jwt", ArgPredicate::IsPresent, "pinata"),
         ("file_path", ArgPredicate::IsPresent, "file")
     ])]
@@ -183,6 +195,28 @@ pub struct StorageProviderConfig {
     #[builder(setter(strip_option), default)]
     pub s3_use_presigned: Option<bool>,
 
+    // **GCS Storage Provider Options**
+    /// GCS HMAC access key
+    #[arg(long, env, required_if_eq("storage_provider", "gcs"))]
+    #[builder(setter(strip_option, into), default)]
+    pub gcs_access_key: Option<String>,
+    /// GCS HMAC secret key
+    #[arg(long, env, required_if_eq("storage_provider", "gcs"))]
+    #[builder(setter(strip_option, into), default)]
+    pub gcs_secret_key: Option<String>,
+    /// GCS bucket
+    #[arg(long, env, required_if_eq("storage_provider", "gcs"))]
+    #[builder(setter(strip_option, into), default)]
+    pub gcs_bucket: Option<String>,
+    /// GCS XML API URL, defaults to https://storage.googleapis.com
+    #[arg(long, env, requires("gcs_access_key"))]
+    #[builder(setter(strip_option, into), default)]
+    pub gcs_url: Option<String>,
+    /// Use presigned URLs for GCS
+    #[arg(long, env, requires("gcs_access_key"), default_value = "true")]
+    #[builder(setter(strip_option), default)]
+    pub gcs_use_presigned: Option<bool>,
+
     // **Pinata Storage Provider Options**
     /// Pinata JWT
     #[arg(long, env, required_if_eq("storage_provider", "pinata"))]
@@ -220,6 +254,11 @@ impl StorageProviderConfig {
             s3_url: None,
             s3_use_presigned: None,
             aws_region: None,
+            gcs_access_key: None,
+            gcs_secret_key: None,
+            gcs_bucket: None,
+            gcs_url: None,
+            gcs_use_presigned: None,
             pinata_jwt: None,
             pinata_api_url: None,
             ipfs_gateway_url: None,
@@ -235,6 +274,7 @@ impl StorageProvider for StandardStorageProvider {
     async fn upload_program(&self, program: &[u8]) -> Result<Url, Self::Error> {
         Ok(match self {
             Self::S3(provider) => provider.upload_program(program).await?,
+            Self::Gcs(provider) => provider.upload_program(program).await?,
             Self::Pinata(provider) => provider.upload_program(program).await?,
             Self::File(provider) => provider.upload_program(program).await?,
             #[cfg(feature = "test-utils")]
@@ -245,6 +285,7 @@ impl StorageProvider for StandardStorageProvider {
     async fn upload_input(&self, input: &[u8]) -> Result<Url, Self::Error> {
         Ok(match self {
             Self::S3(provider) => provider.upload_input(input).await?,
+            Self::Gcs(provider) => provider.upload_input(input).await?,
             Self::Pinata(provider) => provider.upload_input(input).await?,
             Self::File(provider) => provider.upload_input(input).await?,
             #[cfg(feature = "test-utils")]
@@ -258,7 +299,8 @@ impl StorageProvider for StandardStorageProvider {
 /// If the environment variable `RISC0_DEV_MODE` is set, a temporary file storage provider is used.
 /// Otherwise, the following environment variables are checked in order:
 /// - `PINATA_JWT`, `PINATA_API_URL`, `IPFS_GATEWAY_URL`: Pinata storage provider;
-/// - `S3_ACCESS`, `S3_SECRET`, `S3_BUCKET`, `S3_URL`, `AWS_REGION`: S3 storage provider.
+/// - `S3_ACCESS`, `S3_SECRET`, `S3_BUCKET`, `S3_URL`, `AWS_REGION`: S3 storage provider;
+/// - `GCS_ACCESS_KEY`, `GCS_SECRET_KEY`, `GCS_BUCKET`, `GCS_URL`: Google Cloud Storage provider.
 pub fn storage_provider_from_env() -> Result<StandardStorageProvider, StandardStorageProviderError>
 {
     if is_dev_mode() {
@@ -273,6 +315,10 @@ pub fn storage_provider_from_env() -> Result<StandardStorageProvider, StandardSt
         return Ok(StandardStorageProvider::S3(provider));
     }
 
+    if let Ok(provider) = GcsStorageProvider::from_env() {
+        return Ok(StandardStorageProvider::Gcs(provider));
+    }
+
     Err(StandardStorageProviderError::NoProvider)
 }
 
@@ -285,6 +331,10 @@ pub fn storage_provider_from_config(
             let provider = S3StorageProvider::from_config(config)?;
             Ok(StandardStorageProvider::S3(provider))
         }
+        StorageProviderType::Gcs => {
+            let provider = GcsStorageProvider::from_config(config)?;
+            Ok(StandardStorageProvider::Gcs(provider))
+        }
         StorageProviderType::Pinata => {
             let provider = PinataStorageProvider::from_config(config)?;
             Ok(StandardStorageProvider::Pinata(provider))
@@ -19,8 +19,9 @@ use alloy::{
     sol_types::eip712_domain,
 };
 use boundless_market::{
+    balance_monitor::{BalanceStatus, RequestorBalanceMonitor},
     contracts::{
-        boundless_market::{FulfillmentTx, UnlockedRequest},
+        boundless_market::{FulfillmentTx, MarketError, UnlockedRequest},
         hit_points::default_allowance,
         AssessorReceipt, Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestStatus,
         Requirements,
@@ -29,6 +30,7 @@ use boundless_market::{
 };
 use boundless_market_test_utils::{create_test_ctx, mock_singleton, TestCtx, ECHO_ID};
 use risc0_zkvm::sha::Digest;
+use std::time::Duration;
 use tracing_test::traced_test;
 
 fn now_timestamp() -> u64 {
@@ -153,6 +155,40 @@ async fn test_submit_request() {
     assert!(log.requestId == request_id);
 }
 
+#[tokio::test]
+#[traced_test]
+async fn test_requestor_balance_monitor() {
+    // Setup anvil
+    let anvil = Anvil::new().spawn();
+
+    let ctx = create_test_ctx(&anvil).await.unwrap();
+    let monitor =
+        RequestorBalanceMonitor::new(ctx.customer_market.clone(), ctx.customer_signer.address());
+
+    let request = new_request(1, &ctx).await;
+    let max_price = request.offer.maxPrice;
+
+    // No deposit: the tracked request is not covered
+    monitor.track(&request);
+    let report = monitor.check().await.unwrap();
+    assert_eq!(report.committed, max_price);
+    assert_eq!(report.open_requests, 1);
+    assert_eq!(report.status, BalanceStatus::Insufficient);
+    assert!(logs_contain("does not cover the max price"));
+
+    // Deposit exactly the max price: covered, but within the warning margin
+    ctx.customer_market.deposit(max_price).await.unwrap();
+    let monitor = monitor.with_warn_margin(U256::from(1));
+    let report = monitor.check().await.unwrap();
+    assert_eq!(report.balance, max_price);
+    assert_eq!(report.status, BalanceStatus::Low);
+
+    monitor.untrack(request.id);
+    let report = monitor.check().await.unwrap();
+    assert_eq!(report.committed, U256::ZERO);
+    assert_eq!(report.status, BalanceStatus::Sufficient);
+}
+
 #[tokio::test]
 #[traced_test]
 async fn test_e2e() {
@@ -286,6 +322,66 @@ async fn test_e2e_merged_submit_fulfill() {
     assert_eq!(seal, fulfillments[0].seal);
 }
 
+#[tokio::test]
+async fn test_e2e_watch_fulfillment() {
+    // Setup anvil
+    let anvil = Anvil::new().spawn();
+    let ctx = create_test_ctx(&anvil).await.unwrap();
+
+    let eip712_domain = eip712_domain! {
+        name: "IBoundlessMarket",
+        version: "1",
+        chain_id: anvil.chain_id(),
+        verifying_contract: *ctx.customer_market.instance().address(),
+    };
+
+    let request = new_request(1, &ctx).await;
+    let request_id =
+        ctx.customer_market.submit_request(&request, &ctx.customer_signer).await.unwrap();
+
+    let logs = ctx.customer_market.instance().RequestSubmitted_filter().query().await.unwrap();
+    let (event, _) = logs.first().unwrap();
+    let request = &event.request;
+    let customer_sig = event.clientSignature.clone();
+
+    let deposit = default_allowance();
+    ctx.prover_market.deposit_stake_with_permit(deposit, &ctx.prover_signer).await.unwrap();
+    ctx.prover_market.lock_request(request, customer_sig, None).await.unwrap();
+
+    // Not fulfilled yet
+    let err = ctx
+        .customer_market
+        .watch_fulfillment(request_id, Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MarketError::TimeoutReached(_)));
+
+    let (root, set_verifier_seal, fulfillment, assessor_seal) =
+        mock_singleton(request, eip712_domain, ctx.prover_signer.address());
+    let assessor_fill = AssessorReceipt {
+        seal: assessor_seal,
+        selectors: vec![],
+        prover: ctx.prover_signer.address(),
+        callbacks: vec![],
+    };
+    let fulfill = async {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        ctx.prover_market
+            .fulfill(FulfillmentTx::new(vec![fulfillment.clone()], assessor_fill).with_submit_root(
+                ctx.deployment.set_verifier_address,
+                root,
+                set_verifier_seal,
+            ))
+            .await
+            .unwrap();
+    };
+    let watch = ctx.customer_market.watch_fulfillment(request_id, Duration::from_secs(30));
+    let ((journal, seal), _) = tokio::join!(async { watch.await.unwrap() }, fulfill);
+
+    assert_eq!(journal, fulfillment.journal);
+    assert_eq!(seal, fulfillment.seal);
+}
+
 #[tokio::test]
 async fn test_e2e_price_and_fulfill_batch() {
     // Setup anvil
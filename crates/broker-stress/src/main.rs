@@ -28,16 +28,12 @@ use alloy::{
 use anyhow::{Context, Result};
 use axum::{routing::get, Router};
 use boundless_market::{
-    contracts::{
-        hit_points::default_allowance, Offer, Predicate, PredicateType, ProofRequest, RequestId,
-        RequestInput, RequestInputType, Requirements,
-    },
-    input::GuestEnv,
+    contracts::{hit_points::default_allowance, RequestId},
+    fixtures::{CorpusConfig, CorpusGenerator, PriceBand},
 };
 use boundless_market_test_utils::{create_test_ctx_with_rpc_url, TestCtx, ECHO_ELF, ECHO_ID};
 use broker::test_utils::BrokerBuilder;
 use clap::Parser;
-use rand::{rngs::StdRng, Rng, SeedableRng};
 use risc0_zkp::core::digest::Digest;
 use tempfile::NamedTempFile;
 use tokio::{
@@ -80,40 +76,31 @@ async fn request_spawner<P: Provider>(
     args: StressTestArgs,
     spawner_id: u32,
 ) -> Result<()> {
-    let mut r = StdRng::seed_from_u64(args.rng_seed + u64::from(spawner_id));
+    let min_price = U256::from(20000000000000u64);
+    let max_price = U256::from(40000000000000u64);
+    let conf = CorpusConfig::new(ctx.customer_signer.address(), Digest::from(ECHO_ID), program_url)
+        .with_seed(args.rng_seed + u64::from(spawner_id))
+        .with_price_bands(vec![PriceBand {
+            min_price: min_price..=min_price,
+            max_price: max_price..=max_price,
+            weight: 1,
+        }])
+        .with_timeout(100..=100)
+        .with_lock_timeout_percent(100..=100)
+        .with_ramp_up_percent(1..=1)
+        .with_lock_stake(U256::from(10)..=U256::from(10))
+        .with_input_size(1..=31);
+    let mut generator = CorpusGenerator::new(conf);
 
     while !shutdown.load(Ordering::Relaxed) {
-        let request = ProofRequest::new(
-            RequestId::new(
-                ctx.customer_signer.address(),
-                ctx.customer_market.index_from_nonce().await?,
-            ),
-            Requirements::new(
-                Digest::from(ECHO_ID),
-                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
-            ),
-            program_url,
-            RequestInput {
-                inputType: RequestInputType::Inline,
-                data: GuestEnv::builder()
-                    .write_slice(&vec![0x41u8; r.random_range(1..32)])
-                    .build_vec()
-                    .unwrap()
-                    .into(),
-            },
-            Offer {
-                minPrice: U256::from(20000000000000u64),
-                maxPrice: U256::from(40000000000000u64),
-                biddingStart: SystemTime::now()
-                    .duration_since(SystemTime::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-                timeout: 100,
-                lockTimeout: 100,
-                rampUpPeriod: 1,
-                lockStake: U256::from(10),
-            },
+        // Requests are submitted live, so take the index from the market and start bidding now
+        let mut request = generator.next_request();
+        request.id = RequestId::u256(
+            ctx.customer_signer.address(),
+            ctx.customer_market.index_from_nonce().await?,
         );
+        request.offer.biddingStart =
+            SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
 
         ctx.customer_market.submit_request(&request, &ctx.customer_signer).await?;
         tracing::info!("Spawner {} submitted request {}", spawner_id, request.id);
@@ -0,0 +1,110 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of the per-order pricing hot path of the broker.
+//!
+//! Run with `cargo bench -p broker --features bench --bench pricing`. To compare a change against
+//! a baseline, save the baseline on the base commit and compare on the change:
+//!
+//! ```sh
+//! cargo bench -p broker --features bench --bench pricing -- --save-baseline main
+//! cargo bench -p broker --features bench --bench pricing -- --baseline main
+//! ```
+
+use std::hint::black_box;
+
+use alloy::primitives::Address;
+use broker::{
+    bench::{self, SelectorRegistry, SyntheticOrder},
+    config::{ConfigLock, MarketConf},
+};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+/// Bidding start of the synthetic orders, far enough in the past to be stable between runs.
+const BIDDING_START: u64 = 1_700_000_000;
+
+/// Number of orders priced per iteration.
+const BATCH_SIZE: u32 = 100;
+
+fn orders() -> Vec<SyntheticOrder> {
+    (0..BATCH_SIZE).map(|i| SyntheticOrder::new(i, BIDDING_START)).collect()
+}
+
+fn static_checks(c: &mut Criterion) {
+    let orders = orders();
+    let market = MarketConf {
+        deny_requestor_addresses: Some((0..32).map(|i| Address::with_last_byte(200 + i)).collect()),
+        ..Default::default()
+    };
+    let now = BIDDING_START + 10;
+
+    let mut group = c.benchmark_group("static_checks");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    group.bench_function("default", |b| {
+        let market = MarketConf::default();
+        b.iter(|| orders.iter().filter(|order| order.is_skipped(black_box(&market), now)).count())
+    });
+    group.bench_function("deny_list", |b| {
+        b.iter(|| orders.iter().filter(|order| order.is_skipped(black_box(&market), now)).count())
+    });
+    group.finish();
+}
+
+fn digest(c: &mut Criterion) {
+    let orders = orders();
+
+    let mut group = c.benchmark_group("digest");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    group.bench_function("order_id", |b| {
+        b.iter(|| orders.iter().map(|order| black_box(order.order_id())).count())
+    });
+    group.finish();
+}
+
+fn gas_math(c: &mut Criterion) {
+    let orders = orders();
+    let config = ConfigLock::default();
+    let selectors = SelectorRegistry::default();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut group = c.benchmark_group("gas_math");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    group.bench_function("lock_and_fulfill", |b| {
+        b.to_async(&runtime).iter(|| async {
+            for order in &orders {
+                black_box(order.gas_estimate(&config, &selectors).await.unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let orders = orders();
+    let abi: Vec<_> = orders.iter().map(SyntheticOrder::abi_encoded).collect();
+    let json: Vec<_> = orders.iter().map(SyntheticOrder::json_encoded).collect();
+
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    group.bench_function("abi", |b| {
+        b.iter(|| abi.iter().map(|data| bench::decode_abi(black_box(data)).unwrap()).count())
+    });
+    group.bench_function("json", |b| {
+        b.iter(|| json.iter().map(|data| bench::decode_json(black_box(data)).unwrap()).count())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, static_checks, digest, gas_math, decode);
+criterion_main!(benches);
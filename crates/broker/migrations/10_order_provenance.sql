@@ -0,0 +1,7 @@
+CREATE TABLE order_provenance (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    request_id TEXT NOT NULL,
+    data JSONB
+);
+
+CREATE INDEX order_provenance_request_id ON order_provenance (request_id, id);
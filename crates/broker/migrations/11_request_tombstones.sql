@@ -0,0 +1,5 @@
+CREATE TABLE request_tombstones (
+    digest TEXT PRIMARY KEY,
+    reason JSONB NOT NULL,
+    expires_at BIGINT NOT NULL
+);
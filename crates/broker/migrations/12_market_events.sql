@@ -0,0 +1,13 @@
+CREATE TABLE market_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    log_index BIGINT NOT NULL,
+    data JSONB,
+    UNIQUE (chain_id, block_number, log_index)
+);
+
+CREATE TABLE indexer_checkpoints (
+    chain_id BIGINT PRIMARY KEY,
+    block_number BIGINT NOT NULL
+);
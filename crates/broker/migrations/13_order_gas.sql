@@ -0,0 +1,7 @@
+CREATE TABLE order_gas (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id TEXT NOT NULL,
+    data JSONB
+);
+
+CREATE INDEX order_gas_order_id ON order_gas (order_id, id);
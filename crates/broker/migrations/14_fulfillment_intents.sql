@@ -0,0 +1,5 @@
+CREATE TABLE fulfillment_intents (
+    request_id TEXT PRIMARY KEY,
+    created_at BIGINT NOT NULL,
+    data JSONB
+);
@@ -0,0 +1,6 @@
+CREATE INDEX orders_status_updated_at ON orders (data->>'status', data->>'updated_at', id);
+CREATE INDEX orders_client_updated_at ON orders (
+    substr(substr('0000000000000000000000000000000000000000000000000000000000000000' || substr(data->>'$.request.id', 3), -48), 1, 40),
+    data->>'updated_at',
+    id
+);
@@ -0,0 +1,4 @@
+CREATE TABLE shadow_pricing (
+    id TEXT PRIMARY KEY,
+    data JSONB
+);
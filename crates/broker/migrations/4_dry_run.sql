@@ -0,0 +1,4 @@
+CREATE TABLE dry_run_decisions (
+    id TEXT PRIMARY KEY,
+    data JSONB
+);
@@ -0,0 +1,10 @@
+CREATE TABLE client_stats (
+    address TEXT PRIMARY KEY,
+    preflights INTEGER NOT NULL DEFAULT 0,
+    guest_panics INTEGER NOT NULL DEFAULT 0,
+    journal_bytes INTEGER NOT NULL DEFAULT 0,
+    locks INTEGER NOT NULL DEFAULT 0,
+    payment_failures INTEGER NOT NULL DEFAULT 0,
+    fulfilled INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0
+);
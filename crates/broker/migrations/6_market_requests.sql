@@ -0,0 +1,4 @@
+CREATE TABLE market_requests (
+    id TEXT PRIMARY KEY,
+    data JSONB
+);
@@ -0,0 +1,5 @@
+CREATE TABLE fulfillment_gas (
+    id TEXT PRIMARY KEY,
+    gas_estimate INTEGER NOT NULL,
+    gas_used INTEGER NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE image_cycles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    image_id TEXT NOT NULL,
+    total_cycles INTEGER NOT NULL
+);
+
+CREATE INDEX image_cycles_image_id ON image_cycles (image_id, id);
@@ -0,0 +1,7 @@
+CREATE TABLE pricing_audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    request_id TEXT NOT NULL,
+    data JSONB
+);
+
+CREATE INDEX pricing_audit_request_id ON pricing_audit (request_id, id);
@@ -0,0 +1,1019 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Operator-facing HTTP API for inspecting and intervening in the broker at runtime.
+//!
+//! All routes require an `Authorization: Bearer <token>` header matching the configured token.
+//!
+//! The API stays up while the broker drains, so that operators can follow the remaining
+//! committed orders before the process exits.
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use alloy::primitives::{Address, B256, U256};
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{
+    net::TcpListener,
+    sync::{broadcast::error::RecvError, mpsc, oneshot},
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::ConfigLock,
+    db::{DbError, DbObj, OrderQuery, COMMITTED_ORDER_STATUSES},
+    errors::CodedError,
+    events::EventBus,
+    impl_coded_debug,
+    order_monitor::{ManualLockRequest, OrderMonitorErr, PricedOrders},
+    order_picker::CancelPricingRequest,
+    task::{RetryRes, RetryTask, SupervisorErr},
+    DryRunRecord, FulfillmentType, Order, OrderGasRecord, OrderRequest, OrderStatus,
+    ShadowPricingRecord, SkipReason,
+};
+
+/// Interval at which a draining broker checks whether its committed orders have settled.
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Number of orders listed if no limit is given.
+const DEFAULT_ORDER_LIST_LIMIT: u32 = 100;
+
+#[derive(Error)]
+pub enum AdminErr {
+    #[error("{code} Failed to bind admin API to {0}: {1}", code = self.code())]
+    BindFailed(SocketAddr, std::io::Error),
+
+    #[error("{code} Admin API server error: {0}", code = self.code())]
+    ServeFailed(std::io::Error),
+}
+
+impl_coded_debug!(AdminErr);
+
+impl CodedError for AdminErr {
+    fn code(&self) -> &str {
+        match self {
+            AdminErr::BindFailed(..) => "[B-ADM-001]",
+            AdminErr::ServeFailed(_) => "[B-ADM-002]",
+        }
+    }
+}
+
+/// Drain mode of the broker, toggled through the admin API.
+///
+/// While draining, the broker commits to no new orders, and starts a graceful shutdown once all
+/// committed orders are fulfilled or have failed.
+#[derive(Default)]
+pub(crate) struct DrainMode {
+    draining: AtomicBool,
+    shutdown_token: CancellationToken,
+}
+
+pub(crate) type DrainModeObj = Arc<DrainMode>;
+
+impl DrainMode {
+    pub(crate) fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    /// Resolves once the broker is drained and should shut down.
+    pub(crate) async fn drained(&self) {
+        self.shutdown_token.cancelled().await
+    }
+
+    /// Start or stop draining. Draining cannot be stopped once the broker is shutting down.
+    fn set_draining(&self, draining: bool) -> bool {
+        if !draining && self.shutdown_token.is_cancelled() {
+            return false;
+        }
+        self.draining.store(draining, Ordering::Relaxed);
+        true
+    }
+
+    /// Start the shutdown if draining and no committed orders are left.
+    async fn check_drained(&self, db: &DbObj) -> Result<(), DbError> {
+        if self.is_draining()
+            && !self.shutdown_token.is_cancelled()
+            && db.count_orders(&committed_orders()).await? == 0
+        {
+            tracing::info!("All committed orders settled, broker is drained");
+            self.shutdown_token.cancel();
+        }
+        Ok(())
+    }
+}
+
+async fn watch_drain(drain_mode: DrainModeObj, db: DbObj) {
+    let mut interval = tokio::time::interval(DRAIN_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = drain_mode.check_drained(&db).await {
+            tracing::warn!("Failed to check whether the broker is drained: {err}");
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LockResponse {
+    order_id: String,
+    lock_price: U256,
+}
+
+#[derive(Serialize)]
+struct DrainResponse {
+    draining: bool,
+    committed_orders: usize,
+}
+
+#[derive(Serialize)]
+struct CancelPricingResponse {
+    order_id: String,
+    cancelled: bool,
+}
+
+#[derive(Serialize)]
+struct TombstoneResponse {
+    digest: B256,
+    reason: SkipReason,
+}
+
+#[derive(Serialize)]
+struct CapacityResponse {
+    max_concurrent_proofs: Option<u32>,
+    max_concurrent_preflights: u32,
+}
+
+/// Capacity overrides, unset values are left unchanged.
+#[derive(Deserialize)]
+struct CapacityUpdate {
+    max_concurrent_proofs: Option<u32>,
+    max_concurrent_preflights: Option<u32>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+enum OrderListStatus {
+    /// Priced orders waiting to be locked and/or proven
+    Pending,
+    /// Orders the broker committed to and has not fulfilled yet
+    Locked,
+    /// Fulfilled orders
+    Fulfilled,
+    /// Orders that failed after being committed to
+    Failed,
+    /// Orders skipped when pricing
+    Skipped,
+}
+
+#[derive(Deserialize)]
+struct OrderListQuery {
+    status: OrderListStatus,
+    limit: Option<u32>,
+    /// Number of matching orders to skip, to page through the list
+    offset: Option<u32>,
+    fulfillment_type: Option<FulfillmentType>,
+    client: Option<Address>,
+    /// UNIX timestamp the orders were last updated at or after
+    since: Option<i64>,
+    /// UNIX timestamp the orders were last updated before
+    until: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct OrderSummary {
+    order_id: String,
+    request_id: U256,
+    client: Address,
+    fulfillment_type: FulfillmentType,
+    /// Status in the DB, or `Priced` while waiting to be locked and/or proven
+    status: String,
+    total_cycles: Option<u64>,
+    target_timestamp: Option<u64>,
+    expire_timestamp: Option<u64>,
+    lock_price: Option<U256>,
+    skip_reason: Option<SkipReason>,
+    error_msg: Option<String>,
+}
+
+impl From<&Order> for OrderSummary {
+    fn from(order: &Order) -> Self {
+        Self {
+            order_id: order.id(),
+            request_id: order.request.id,
+            client: order.request.client_address(),
+            fulfillment_type: order.fulfillment_type,
+            status: format!("{:?}", order.status),
+            total_cycles: order.total_cycles,
+            target_timestamp: order.target_timestamp,
+            expire_timestamp: order.expire_timestamp,
+            lock_price: order.lock_price,
+            skip_reason: order.skip_reason,
+            error_msg: order.error_msg.clone(),
+        }
+    }
+}
+
+impl From<&OrderRequest> for OrderSummary {
+    fn from(order: &OrderRequest) -> Self {
+        Self {
+            order_id: order.id(),
+            request_id: order.request.id,
+            client: order.request.client_address(),
+            fulfillment_type: order.fulfillment_type,
+            status: "Priced".into(),
+            total_cycles: order.total_cycles,
+            target_timestamp: order.target_timestamp,
+            expire_timestamp: order.expire_timestamp,
+            lock_price: None,
+            skip_reason: None,
+            error_msg: None,
+        }
+    }
+}
+
+/// An order along with the records of how it was priced.
+#[derive(Serialize)]
+struct OrderDetails {
+    #[serde(flatten)]
+    order: OrderSummary,
+    dry_run: Option<DryRunRecord>,
+    shadow_pricing: Option<ShadowPricingRecord>,
+}
+
+/// The gas spent on the transactions of an order, and its totals.
+#[derive(Serialize)]
+struct OrderGas {
+    records: Vec<OrderGasRecord>,
+    gas_used: u64,
+    gas_cost: U256,
+}
+
+#[derive(Clone)]
+struct AdminState {
+    token: String,
+    db: DbObj,
+    config: ConfigLock,
+    drain_mode: DrainModeObj,
+    manual_lock_tx: Option<mpsc::Sender<ManualLockRequest>>,
+    cancel_pricing_tx: Option<mpsc::Sender<CancelPricingRequest>>,
+    priced_orders: Option<PricedOrders>,
+    events: Option<EventBus>,
+}
+
+/// Parse the bearer token of the admin API, rejecting blank tokens, which any request would match.
+pub(crate) fn parse_token(token: &str) -> Result<String, String> {
+    if token.trim().is_empty() {
+        return Err("admin token must not be empty".into());
+    }
+    Ok(token.to_string())
+}
+
+impl AdminState {
+    fn is_authorized(&self, headers: &HeaderMap) -> bool {
+        let Some(token) = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+        // Compare in constant time, to avoid leaking the token through response timing.
+        token.len() == self.token.len()
+            && token.bytes().zip(self.token.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+}
+
+#[derive(Clone)]
+pub struct AdminServer {
+    listen_addr: SocketAddr,
+    state: AdminState,
+}
+
+impl AdminServer {
+    /// Create the admin API server, toggling the given drain mode.
+    pub(crate) fn new(
+        listen_addr: SocketAddr,
+        token: String,
+        db: DbObj,
+        config: ConfigLock,
+        drain_mode: DrainModeObj,
+    ) -> Self {
+        let state = AdminState {
+            token,
+            db,
+            config,
+            drain_mode,
+            manual_lock_tx: None,
+            cancel_pricing_tx: None,
+            priced_orders: None,
+            events: None,
+        };
+        Self { listen_addr, state }
+    }
+
+    /// Forward manual lock requests to the order monitor.
+    pub(crate) fn with_manual_lock_tx(
+        self,
+        manual_lock_tx: mpsc::Sender<ManualLockRequest>,
+    ) -> Self {
+        Self { state: AdminState { manual_lock_tx: Some(manual_lock_tx), ..self.state }, ..self }
+    }
+
+    /// Forward requests to cancel pricing to the order picker.
+    pub(crate) fn with_cancel_pricing_tx(
+        self,
+        cancel_pricing_tx: mpsc::Sender<CancelPricingRequest>,
+    ) -> Self {
+        Self {
+            state: AdminState { cancel_pricing_tx: Some(cancel_pricing_tx), ..self.state },
+            ..self
+        }
+    }
+
+    /// List the priced orders waiting in the order monitor.
+    pub(crate) fn with_priced_orders(self, priced_orders: PricedOrders) -> Self {
+        Self { state: AdminState { priced_orders: Some(priced_orders), ..self.state }, ..self }
+    }
+
+    /// Stream the events of the given bus.
+    pub(crate) fn with_events(self, events: EventBus) -> Self {
+        Self { state: AdminState { events: Some(events), ..self.state }, ..self }
+    }
+
+    fn router(&self) -> Router {
+        let state = Arc::new(self.state.clone());
+        Router::new()
+            .route("/admin/orders", get(list_orders))
+            .route("/admin/orders/{order_id}", get(order_details))
+            .route("/admin/orders/{order_id}/gas", get(order_gas))
+            .route("/admin/orders/{order_id}/lock", post(lock_order))
+            .route("/admin/orders/{order_id}/pricing", delete(cancel_pricing))
+            .route("/admin/requests/{request_id}", get(market_request))
+            .route("/admin/requests/{request_id}/pricing", get(pricing_audit))
+            .route("/admin/requests/{request_id}/provenance", get(order_provenance))
+            .route("/admin/tombstones/{digest}", get(tombstone).delete(clear_tombstone))
+            .route("/admin/capacity", get(capacity).put(update_capacity))
+            .route("/admin/drain", get(drain_status).post(drain).delete(stop_drain))
+            .route("/admin/events", get(stream_events))
+            .route_layer(middleware::from_fn_with_state(state.clone(), require_token))
+            .with_state(state)
+    }
+}
+
+async fn require_token(
+    State(state): State<Arc<AdminState>>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.is_authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    next.run(request).await
+}
+
+fn internal_error(err: impl std::fmt::Display) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+}
+
+/// Stream the broker events as server-sent events, named after the event, with a JSON payload.
+async fn stream_events(State(state): State<Arc<AdminState>>) -> Response {
+    let Some(events) = &state.events else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Event bus is not available").into_response();
+    };
+
+    let stream = futures_util::stream::unfold(events.subscribe(), |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    let sse = SseEvent::default().event(event.name()).json_data(&event);
+                    return Some((sse, rx));
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("Admin API event stream missed {missed} broker events");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default()).into_response()
+}
+
+/// Query of all the orders committed to and not fulfilled yet.
+fn committed_orders() -> OrderQuery {
+    OrderQuery { statuses: COMMITTED_ORDER_STATUSES.to_vec(), ..Default::default() }
+}
+
+/// List a page of pending, locked, fulfilled, failed or skipped orders, optionally filtered by
+/// fulfillment type, client and update time. Orders in the DB are listed most recently updated
+/// first.
+async fn list_orders(
+    State(state): State<Arc<AdminState>>,
+    Query(query): Query<OrderListQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(DEFAULT_ORDER_LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    let statuses = match query.status {
+        // Priced orders are kept in memory, and have not been updated since pricing
+        OrderListStatus::Pending => {
+            let orders: Vec<OrderSummary> = state
+                .priced_orders
+                .iter()
+                .flat_map(|priced_orders| priced_orders.list())
+                .filter(|order| {
+                    query.fulfillment_type.is_none_or(|ty| order.fulfillment_type == ty)
+                })
+                .filter(|order| {
+                    query.client.is_none_or(|client| order.request.client_address() == client)
+                })
+                .skip(offset as usize)
+                .take(limit as usize)
+                .map(|order| OrderSummary::from(order.as_ref()))
+                .collect();
+            return Json(orders).into_response();
+        }
+        OrderListStatus::Locked => COMMITTED_ORDER_STATUSES.to_vec(),
+        OrderListStatus::Fulfilled => vec![OrderStatus::Done],
+        OrderListStatus::Failed => vec![OrderStatus::Failed],
+        OrderListStatus::Skipped => vec![OrderStatus::Skipped],
+    };
+    let order_query = OrderQuery {
+        statuses,
+        fulfillment_type: query.fulfillment_type,
+        client: query.client,
+        updated_since: query.since,
+        updated_before: query.until,
+        offset,
+        limit,
+    };
+    match state.db.query_orders(&order_query).await {
+        Ok(orders) => {
+            Json(orders.iter().map(OrderSummary::from).collect::<Vec<_>>()).into_response()
+        }
+        Err(err) => internal_error(err),
+    }
+}
+
+/// An order and its pricing decision, as stored once it was committed to or skipped, or while it
+/// waits to be locked.
+async fn order_details(
+    State(state): State<Arc<AdminState>>,
+    Path(order_id): Path<String>,
+) -> Response {
+    let order = match state.db.get_order(&order_id).await {
+        Ok(Some(order)) => OrderSummary::from(&order),
+        Ok(None) => {
+            let priced = match &state.priced_orders {
+                Some(priced_orders) => priced_orders.get(&order_id).await,
+                None => None,
+            };
+            match priced {
+                Some(order) => OrderSummary::from(order.as_ref()),
+                None => return (StatusCode::NOT_FOUND, "Unknown order").into_response(),
+            }
+        }
+        Err(err) => return internal_error(err),
+    };
+    let dry_run = match state.db.get_dry_run_record(&order_id).await {
+        Ok(record) => record,
+        Err(err) => return internal_error(err),
+    };
+    let shadow_pricing = match state.db.get_shadow_pricing_record(&order_id).await {
+        Ok(record) => record,
+        Err(err) => return internal_error(err),
+    };
+    Json(OrderDetails { order, dry_run, shadow_pricing }).into_response()
+}
+
+/// The gas spent on the lock and fulfillment transactions of an order, oldest first.
+async fn order_gas(State(state): State<Arc<AdminState>>, Path(order_id): Path<String>) -> Response {
+    let records = match state.db.get_order_gas_records(&order_id).await {
+        Ok(records) => records,
+        Err(err) => return internal_error(err),
+    };
+    let gas_used = records.iter().map(|record| record.gas_used).sum();
+    let gas_cost = records.iter().fold(U256::ZERO, |cost, record| cost + record.gas_cost);
+    Json(OrderGas { records, gas_used, gas_cost }).into_response()
+}
+
+/// The view of a market request indexed from market events.
+async fn market_request(
+    State(state): State<Arc<AdminState>>,
+    Path(request_id): Path<String>,
+) -> Response {
+    let Ok(request_id) = request_id.parse::<U256>() else {
+        return (StatusCode::BAD_REQUEST, "Invalid request ID").into_response();
+    };
+    match state.db.get_market_request(request_id).await {
+        Ok(Some(request)) => Json(request).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Unknown request").into_response(),
+        Err(err) => internal_error(err),
+    }
+}
+
+/// The audit log of every time an order of the request was priced, oldest first.
+async fn pricing_audit(
+    State(state): State<Arc<AdminState>>,
+    Path(request_id): Path<String>,
+) -> Response {
+    let Ok(request_id) = request_id.parse::<U256>() else {
+        return (StatusCode::BAD_REQUEST, "Invalid request ID").into_response();
+    };
+    match state.db.get_pricing_audit_records(request_id).await {
+        Ok(records) => Json(records).into_response(),
+        Err(err) => internal_error(err),
+    }
+}
+
+/// Where and when every order of the request was received, with its signature, oldest first.
+async fn order_provenance(
+    State(state): State<Arc<AdminState>>,
+    Path(request_id): Path<String>,
+) -> Response {
+    let Ok(request_id) = request_id.parse::<U256>() else {
+        return (StatusCode::BAD_REQUEST, "Invalid request ID").into_response();
+    };
+    match state.db.get_order_provenance_records(request_id).await {
+        Ok(records) => Json(records).into_response(),
+        Err(err) => internal_error(err),
+    }
+}
+
+/// Cancel pricing of an order that is queued or being priced. The order is skipped.
+async fn cancel_pricing(
+    State(state): State<Arc<AdminState>>,
+    Path(order_id): Path<String>,
+) -> Response {
+    let Some(cancel_pricing_tx) = &state.cancel_pricing_tx else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Order picker is not running").into_response();
+    };
+
+    tracing::info!("Admin API requested cancelling pricing of order {order_id}");
+    let (reply, reply_rx) = oneshot::channel();
+    let request = CancelPricingRequest { order_id: order_id.clone(), reply };
+    if cancel_pricing_tx.send(request).await.is_err() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Order picker is not running").into_response();
+    }
+
+    match reply_rx.await {
+        Ok(true) => Json(CancelPricingResponse { order_id, cancelled: true }).into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, "Order is not being priced").into_response(),
+        Err(_) => (StatusCode::SERVICE_UNAVAILABLE, "Order picker stopped before cancelling")
+            .into_response(),
+    }
+}
+
+/// The reason the request with the given digest was permanently skipped, if it is tombstoned.
+async fn tombstone(State(state): State<Arc<AdminState>>, Path(digest): Path<String>) -> Response {
+    let Ok(digest) = digest.parse::<B256>() else {
+        return (StatusCode::BAD_REQUEST, "Invalid request digest").into_response();
+    };
+    match state.db.get_request_tombstone(digest).await {
+        Ok(Some(reason)) => Json(TombstoneResponse { digest, reason }).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Request is not tombstoned").into_response(),
+        Err(err) => internal_error(err),
+    }
+}
+
+/// Clear the tombstone of a request, so that re-broadcasts of the request are priced again.
+async fn clear_tombstone(
+    State(state): State<Arc<AdminState>>,
+    Path(digest): Path<String>,
+) -> Response {
+    let Ok(digest) = digest.parse::<B256>() else {
+        return (StatusCode::BAD_REQUEST, "Invalid request digest").into_response();
+    };
+    match state.db.clear_request_tombstone(digest).await {
+        Ok(true) => {
+            tracing::info!("Admin API cleared tombstone of request {digest}");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => (StatusCode::NOT_FOUND, "Request is not tombstoned").into_response(),
+        Err(err) => internal_error(err),
+    }
+}
+
+fn capacity_response(config: &ConfigLock) -> Response {
+    match config.lock_all() {
+        Ok(config) => Json(CapacityResponse {
+            max_concurrent_proofs: config.market.max_concurrent_proofs,
+            max_concurrent_preflights: config.market.max_concurrent_preflights,
+        })
+        .into_response(),
+        Err(err) => internal_error(err),
+    }
+}
+
+/// Current proving and pricing concurrency limits.
+async fn capacity(State(state): State<Arc<AdminState>>) -> Response {
+    capacity_response(&state.config)
+}
+
+/// Override the proving and pricing concurrency limits until the config file is next reloaded.
+async fn update_capacity(
+    State(state): State<Arc<AdminState>>,
+    Json(update): Json<CapacityUpdate>,
+) -> Response {
+    if update.max_concurrent_proofs == Some(0) || update.max_concurrent_preflights == Some(0) {
+        return (StatusCode::BAD_REQUEST, "Capacity must be at least 1").into_response();
+    }
+
+    {
+        let mut config = match state.config.load_write() {
+            Ok(config) => config,
+            Err(err) => return internal_error(err),
+        };
+        if let Some(max_concurrent_proofs) = update.max_concurrent_proofs {
+            tracing::info!("Admin API set max_concurrent_proofs to {max_concurrent_proofs}");
+            config.market.max_concurrent_proofs = Some(max_concurrent_proofs);
+        }
+        if let Some(max_concurrent_preflights) = update.max_concurrent_preflights {
+            tracing::info!(
+                "Admin API set max_concurrent_preflights to {max_concurrent_preflights}"
+            );
+            config.market.max_concurrent_preflights = max_concurrent_preflights;
+        }
+    }
+    capacity_response(&state.config)
+}
+
+async fn drain_response(state: &AdminState) -> Response {
+    match state.db.count_orders(&committed_orders()).await {
+        Ok(count) => Json(DrainResponse {
+            draining: state.drain_mode.is_draining(),
+            committed_orders: count as usize,
+        })
+        .into_response(),
+        Err(err) => internal_error(err),
+    }
+}
+
+/// Stop committing to new orders, and exit once all committed orders are fulfilled or have
+/// failed.
+async fn drain(State(state): State<Arc<AdminState>>) -> Response {
+    if !state.drain_mode.is_draining() {
+        tracing::info!("Admin API requested drain");
+        state.drain_mode.set_draining(true);
+    }
+    if let Err(err) = state.drain_mode.check_drained(&state.db).await {
+        return internal_error(err);
+    }
+    drain_response(&state).await
+}
+
+/// Resume committing to new orders, unless the broker is already shutting down.
+async fn stop_drain(State(state): State<Arc<AdminState>>) -> Response {
+    if state.drain_mode.is_draining() {
+        if !state.drain_mode.set_draining(false) {
+            return (StatusCode::CONFLICT, "Broker is drained and shutting down").into_response();
+        }
+        tracing::info!("Admin API stopped drain");
+    }
+    drain_response(&state).await
+}
+
+/// Whether the broker is draining, and the number of committed orders left.
+async fn drain_status(State(state): State<Arc<AdminState>>) -> Response {
+    drain_response(&state).await
+}
+
+/// Lock a priced order now instead of waiting for its target timestamp.
+async fn lock_order(
+    State(state): State<Arc<AdminState>>,
+    Path(order_id): Path<String>,
+) -> Response {
+    let Some(manual_lock_tx) = &state.manual_lock_tx else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Order monitor is not running").into_response();
+    };
+
+    tracing::info!("Admin API requested manual lock of order {order_id}");
+    let (reply, reply_rx) = oneshot::channel();
+    let request = ManualLockRequest { order_id: order_id.clone(), reply };
+    if manual_lock_tx.send(request).await.is_err() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Order monitor is not running").into_response();
+    }
+
+    match reply_rx.await {
+        Ok(Ok(lock_price)) => Json(LockResponse { order_id, lock_price }).into_response(),
+        Ok(Err(err)) => {
+            tracing::warn!("Manual lock of order {order_id} failed: {err}");
+            let status = match err {
+                OrderMonitorErr::UnknownOrder(_) => StatusCode::NOT_FOUND,
+                OrderMonitorErr::AlreadyLocked
+                | OrderMonitorErr::ClaimedByReplica
+                | OrderMonitorErr::CompetingLock(_)
+                | OrderMonitorErr::Draining
+                | OrderMonitorErr::DryRun
+                | OrderMonitorErr::Standby
+                | OrderMonitorErr::LockExpired
+                | OrderMonitorErr::InsufficientBalance
+                | OrderMonitorErr::RequestorInsufficientBalance(_) => StatusCode::CONFLICT,
+                _ => StatusCode::BAD_GATEWAY,
+            };
+            (status, err.to_string()).into_response()
+        }
+        Err(_) => (StatusCode::SERVICE_UNAVAILABLE, "Order monitor stopped before locking")
+            .into_response(),
+    }
+}
+
+impl RetryTask for AdminServer {
+    type Error = AdminErr;
+
+    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
+        let listen_addr = self.listen_addr;
+        let router = self.router();
+        let drain_mode = self.state.drain_mode.clone();
+        let db = self.state.db.clone();
+        Box::pin(async move {
+            let listener = TcpListener::bind(listen_addr)
+                .await
+                .map_err(|err| SupervisorErr::Recover(AdminErr::BindFailed(listen_addr, err)))?;
+            tracing::info!("Admin API listening on {listen_addr}");
+            let drain_watcher = tokio::spawn(watch_drain(drain_mode, db));
+            let res = axum::serve(listener, router)
+                .with_graceful_shutdown(cancel_token.cancelled_owned())
+                .await;
+            drain_watcher.abort();
+            res.map_err(|err| SupervisorErr::Recover(AdminErr::ServeFailed(err)))?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::SqliteDb;
+
+    const TOKEN: &str = "test-token";
+
+    struct TestServer {
+        url: String,
+        manual_lock_rx: mpsc::Receiver<ManualLockRequest>,
+        cancel_pricing_rx: mpsc::Receiver<CancelPricingRequest>,
+        db: DbObj,
+        config: ConfigLock,
+        drain_mode: DrainModeObj,
+    }
+
+    async fn spawn_server() -> TestServer {
+        let (manual_lock_tx, manual_lock_rx) = mpsc::channel(1);
+        let (cancel_pricing_tx, cancel_pricing_rx) = mpsc::channel(1);
+        let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
+        let config = ConfigLock::default();
+        let drain_mode = DrainModeObj::default();
+        let server = AdminServer::new(
+            ([127, 0, 0, 1], 0).into(),
+            TOKEN.into(),
+            db.clone(),
+            config.clone(),
+            drain_mode.clone(),
+        )
+        .with_manual_lock_tx(manual_lock_tx)
+        .with_cancel_pricing_tx(cancel_pricing_tx);
+        let listener = TcpListener::bind(server.listen_addr).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = server.router();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        TestServer {
+            url: format!("http://{addr}"),
+            manual_lock_rx,
+            cancel_pricing_rx,
+            db,
+            config,
+            drain_mode,
+        }
+    }
+
+    async fn json(res: reqwest::Response) -> serde_json::Value {
+        serde_json::from_str(&res.text().await.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn blank_token_rejected() {
+        assert!(parse_token("").is_err());
+        assert!(parse_token(" \t").is_err());
+        assert_eq!(parse_token(TOKEN).unwrap(), TOKEN);
+    }
+
+    #[tokio::test]
+    async fn routes_require_token() {
+        let server = spawn_server().await;
+        let url = &server.url;
+        let client = reqwest::Client::new();
+
+        let res = client.post(format!("{url}/admin/orders/0x1/lock")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .post(format!("{url}/admin/orders/0x1/lock"))
+            .bearer_auth("wrong-token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // Checked before the query is parsed
+        let res = client.get(format!("{url}/admin/orders?status=bogus")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn lock_forwards_to_order_monitor() {
+        let TestServer { url, mut manual_lock_rx, .. } = spawn_server().await;
+        tokio::spawn(async move {
+            while let Some(request) = manual_lock_rx.recv().await {
+                let res = match request.order_id.as_str() {
+                    "0x1" => Ok(U256::from(100)),
+                    "0x2" => Err(OrderMonitorErr::AlreadyLocked),
+                    id => Err(OrderMonitorErr::UnknownOrder(id.into())),
+                };
+                request.reply.send(res).unwrap();
+            }
+        });
+        let client = reqwest::Client::new();
+        let lock = |id: &str| {
+            client.post(format!("{url}/admin/orders/{id}/lock")).bearer_auth(TOKEN).send()
+        };
+
+        let res = lock("0x1").await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = json(res).await;
+        assert_eq!(body["order_id"], "0x1");
+        assert_eq!(body["lock_price"], "0x64");
+
+        assert_eq!(lock("0x2").await.unwrap().status(), StatusCode::CONFLICT);
+        assert_eq!(lock("0x3").await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn inspects_orders_and_cancels_pricing() {
+        let TestServer { url, mut cancel_pricing_rx, .. } = spawn_server().await;
+        tokio::spawn(async move {
+            while let Some(request) = cancel_pricing_rx.recv().await {
+                request.reply.send(request.order_id == "0x1").unwrap();
+            }
+        });
+        let client = reqwest::Client::new();
+
+        let filters = "fulfillment_type=LockAndFulfill&client=0x0000000000000000000000000000000000000001&since=0&until=100&offset=1";
+        for status in ["pending", "locked", "fulfilled", "failed", "skipped"] {
+            let res = client
+                .get(format!("{url}/admin/orders?status={status}&{filters}"))
+                .bearer_auth(TOKEN)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(json(res).await, serde_json::json!([]));
+        }
+        let res =
+            client.get(format!("{url}/admin/orders/0x1")).bearer_auth(TOKEN).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let request =
+            |id: &str| client.get(format!("{url}/admin/requests/{id}")).bearer_auth(TOKEN).send();
+        assert_eq!(request("0x1").await.unwrap().status(), StatusCode::NOT_FOUND);
+        assert_eq!(request("bogus").await.unwrap().status(), StatusCode::BAD_REQUEST);
+        let res = request("0x1/pricing").await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(json(res).await, serde_json::json!([]));
+        assert_eq!(request("bogus/pricing").await.unwrap().status(), StatusCode::BAD_REQUEST);
+        let res = request("0x1/provenance").await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(json(res).await, serde_json::json!([]));
+        let res = client
+            .get(format!("{url}/admin/orders/0x1/gas"))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            json(res).await,
+            serde_json::json!({ "records": [], "gas_used": 0, "gas_cost": "0x0" })
+        );
+
+        let cancel = |id: &str| {
+            client.delete(format!("{url}/admin/orders/{id}/pricing")).bearer_auth(TOKEN).send()
+        };
+        let res = cancel("0x1").await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(json(res).await["cancelled"], true);
+        assert_eq!(cancel("0x2").await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn clears_tombstones() {
+        let TestServer { url, db, .. } = spawn_server().await;
+        let client = reqwest::Client::new();
+        let digest = B256::repeat_byte(1);
+        db.add_request_tombstone(digest, SkipReason::ImageDenied, u64::MAX).await.unwrap();
+
+        let tombstone_url = format!("{url}/admin/tombstones/{digest}");
+        let res = client.get(&tombstone_url).bearer_auth(TOKEN).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(json(res).await["reason"], "image_denied");
+
+        let clear = || client.delete(&tombstone_url).bearer_auth(TOKEN).send();
+        assert_eq!(clear().await.unwrap().status(), StatusCode::NO_CONTENT);
+        assert_eq!(clear().await.unwrap().status(), StatusCode::NOT_FOUND);
+        assert_eq!(db.get_request_tombstone(digest).await.unwrap(), None);
+
+        let res = client.delete(format!("{url}/admin/tombstones/bogus")).bearer_auth(TOKEN).send();
+        assert_eq!(res.await.unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn overrides_capacity() {
+        let TestServer { url, config, .. } = spawn_server().await;
+        let client = reqwest::Client::new();
+
+        let res = client
+            .put(format!("{url}/admin/capacity"))
+            .bearer_auth(TOKEN)
+            .json(&serde_json::json!({ "max_concurrent_proofs": 0 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res = client
+            .put(format!("{url}/admin/capacity"))
+            .bearer_auth(TOKEN)
+            .json(&serde_json::json!({ "max_concurrent_proofs": 3 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = json(res).await;
+        assert_eq!(body["max_concurrent_proofs"], 3);
+        let config = config.lock_all().unwrap();
+        assert_eq!(config.market.max_concurrent_proofs, Some(3));
+        assert_eq!(body["max_concurrent_preflights"], config.market.max_concurrent_preflights);
+    }
+
+    #[tokio::test]
+    async fn toggles_drain_mode() {
+        let TestServer { url, drain_mode, .. } = spawn_server().await;
+        let client = reqwest::Client::new();
+
+        let res = client.post(format!("{url}/admin/drain")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert!(!drain_mode.is_draining());
+
+        let res = client.get(format!("{url}/admin/drain")).bearer_auth(TOKEN).send().await.unwrap();
+        assert_eq!(json(res).await["draining"], false);
+
+        // Drained immediately, as there are no committed orders
+        let res =
+            client.post(format!("{url}/admin/drain")).bearer_auth(TOKEN).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = json(res).await;
+        assert_eq!(body["draining"], true);
+        assert_eq!(body["committed_orders"], 0);
+        tokio::time::timeout(Duration::from_secs(1), drain_mode.drained()).await.unwrap();
+
+        let res =
+            client.delete(format!("{url}/admin/drain")).bearer_auth(TOKEN).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        assert!(drain_mode.is_draining());
+    }
+
+    #[test]
+    fn drain_can_stop_before_drained() {
+        let drain_mode = DrainMode::default();
+        assert!(drain_mode.set_draining(true));
+        assert!(drain_mode.is_draining());
+        assert!(drain_mode.set_draining(false));
+        assert!(!drain_mode.is_draining());
+    }
+}
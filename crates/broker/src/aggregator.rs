@@ -283,6 +283,7 @@ impl AggregatorService {
             conf_batch_fees,
             conf_max_journal_bytes,
             conf_max_concurrent_proofs,
+            conf_fulfill_window,
         ) = {
             let config = self.config.lock_all().context("Failed to lock config")?;
 
@@ -299,6 +300,7 @@ impl AggregatorService {
                 batch_max_fees,
                 config.batcher.batch_max_journal_bytes,
                 config.market.max_concurrent_proofs,
+                config.batcher.fulfill_window.clone(),
             )
         };
 
@@ -378,6 +380,30 @@ impl AggregatorService {
             }
         }
 
+        // Finalize once the fulfillment window of the batch ends, or it holds enough orders.
+        if let Some(window) = conf_fulfill_window {
+            if batch_size >= window.max_orders as usize {
+                tracing::debug!(
+                    "Finalizing batch {batch_id}: fulfillment window order limit hit {} - {}",
+                    batch_size,
+                    window.max_orders
+                );
+                return Ok(true);
+            }
+            if let Some(window_start) = batch.window_start {
+                let window_secs = now_timestamp().saturating_sub(window_start);
+                if window_secs >= window.max_secs {
+                    tracing::debug!(
+                        "Finalizing batch {batch_id}: fulfillment window ended {} - {}",
+                        window_secs,
+                        window.max_secs
+                    );
+                    return Ok(true);
+                }
+            }
+            tracing::debug!("Batch {batch_id} within its fulfillment window");
+        }
+
         // Finalize whenever a batch hits the target fee total.
         if let Some(batch_target_fees) = conf_batch_fees {
             let fees =
@@ -783,6 +809,7 @@ mod tests {
             request: order_request,
             image_id: Some(image_id_str.clone()),
             input_id: Some(input_id.clone()),
+            assumption_ids: None,
             proof_id: Some(proof_res_1.id),
             compressed_proof_id: None,
             expire_timestamp: Some(now_timestamp() + 100),
@@ -790,6 +817,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -830,6 +859,7 @@ mod tests {
             request: order_request,
             image_id: Some(image_id_str),
             input_id: Some(input_id),
+            assumption_ids: None,
             proof_id: Some(proof_res_2.id),
             compressed_proof_id: None,
             expire_timestamp: Some(now_timestamp() + 100),
@@ -837,6 +867,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -944,6 +976,7 @@ mod tests {
             target_timestamp: None,
             image_id: Some(image_id_str.clone()),
             input_id: Some(input_id.clone()),
+            assumption_ids: None,
             proof_id: Some(proof_res_1.id),
             compressed_proof_id: None,
             expire_timestamp: Some(order_request.expires_at()),
@@ -951,6 +984,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             request: order_request,
             boundless_market_address: Address::ZERO,
             chain_id,
@@ -1006,6 +1041,7 @@ mod tests {
             target_timestamp: None,
             image_id: Some(image_id_str),
             input_id: Some(input_id),
+            assumption_ids: None,
             proof_id: Some(proof_res_2.id),
             compressed_proof_id: None,
             expire_timestamp: Some(order_request.expires_at()),
@@ -1013,6 +1049,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             request: order_request,
             boundless_market_address: Address::ZERO,
             chain_id,
@@ -1118,6 +1156,7 @@ mod tests {
             request: order_request,
             image_id: Some(image_id_str.clone()),
             input_id: Some(input_id.clone()),
+            assumption_ids: None,
             proof_id: Some(proof_res.id),
             compressed_proof_id: None,
             expire_timestamp: Some(now_timestamp() + 100),
@@ -1125,6 +1164,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -1232,6 +1273,7 @@ mod tests {
             request: order_request,
             image_id: Some(image_id_str.clone()),
             input_id: Some(input_id.clone()),
+            assumption_ids: None,
             proof_id: Some(proof_res.id),
             compressed_proof_id: None,
             expire_timestamp: Some(now_timestamp() + 100),
@@ -1239,6 +1281,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -1354,6 +1398,7 @@ mod tests {
             request: order_request.clone(),
             image_id: Some(image_id_str.clone()),
             input_id: Some(input_id.clone()),
+            assumption_ids: None,
             proof_id: Some(proof_res.clone().id),
             compressed_proof_id: None,
             expire_timestamp: Some(now_timestamp() + 1000),
@@ -1361,6 +1406,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -1393,6 +1440,7 @@ mod tests {
             request: order_request_2,
             image_id: Some(image_id_str.clone()),
             input_id: Some(input_id.clone()),
+            assumption_ids: None,
             proof_id: Some(proof_res.id),
             compressed_proof_id: None,
             expire_timestamp: Some(now_timestamp() + 1000),
@@ -1400,6 +1448,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -1465,6 +1515,7 @@ mod tests {
             ),
             image_id: None,
             input_id: None,
+            assumption_ids: None,
             proof_id: None,
             compressed_proof_id: None,
             expire_timestamp: Some(current_time - 100),
@@ -1472,6 +1523,8 @@ mod tests {
             lock_price: Some(U256::from(1)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: Address::ZERO,
             chain_id: 1,
             total_cycles: None,
@@ -1506,6 +1559,7 @@ mod tests {
             ),
             image_id: None,
             input_id: None,
+            assumption_ids: None,
             proof_id: None,
             compressed_proof_id: None,
             expire_timestamp: Some(current_time + 100),
@@ -1513,6 +1567,8 @@ mod tests {
             lock_price: Some(U256::from(1)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: Address::ZERO,
             chain_id: 1,
             total_cycles: None,
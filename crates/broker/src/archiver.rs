@@ -0,0 +1,508 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Archival of closed orders to object storage.
+//!
+//! Orders that reached a terminal state (Done, Failed or Skipped) and have not been touched within
+//! the configured retention period are written to an S3 bucket and then removed from the local
+//! database. When finality is tracked, fulfilled orders are only archived once their fulfillment
+//! is final. Each archival pass writes pairs of objects:
+//!
+//! * `{prefix}/orders/{YYYY}/{MM}/{DD}/{name}.jsonl.gz`: gzip compressed JSON lines, one
+//!   [ArchivedOrder] per line. Each line holds the full order row, including the failure or skip
+//!   reason, and optionally the receipts fetched from the prover.
+//! * `{prefix}/index/{YYYY}/{MM}/{DD}/{name}.json`: an uncompressed [ArchiveIndex] listing the
+//!   orders contained in the data object, so archives can be searched without downloading them.
+
+use std::{io::Write, sync::Arc, time::Duration};
+
+use alloy::primitives::{Bytes, U256};
+use anyhow::Context;
+use async_trait::async_trait;
+use aws_sdk_s3::{primitives::ByteStream, Client as S3Client};
+use chrono::{DateTime, Utc};
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{ConfigErr, ConfigLock},
+    db::{DbError, DbObj},
+    errors::CodedError,
+    provers::ProverObj,
+    storage::load_aws_config,
+    task::{RetryRes, RetryTask, SupervisorErr},
+    Order, OrderStatus,
+};
+
+#[derive(Error, Debug)]
+pub enum ArchiverErr {
+    #[error("{code} DB error: {0}", code = self.code())]
+    DbError(#[from] DbError),
+
+    #[error("{code} Config error {0}", code = self.code())]
+    ConfigReadErr(#[from] ConfigErr),
+
+    #[error("{code} Failed to upload archive object: {0:?}", code = self.code())]
+    UploadFailed(anyhow::Error),
+
+    #[error("{code} Unexpected error: {0:?}", code = self.code())]
+    UnexpectedErr(#[from] anyhow::Error),
+}
+
+impl CodedError for ArchiverErr {
+    fn code(&self) -> &str {
+        match self {
+            ArchiverErr::DbError(_) => "[B-ARC-001]",
+            ArchiverErr::ConfigReadErr(_) => "[B-ARC-002]",
+            ArchiverErr::UploadFailed(_) => "[B-ARC-003]",
+            ArchiverErr::UnexpectedErr(_) => "[B-ARC-500]",
+        }
+    }
+}
+
+/// Destination for archive objects.
+#[async_trait]
+pub(crate) trait ArchiveStore: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> anyhow::Result<()>;
+}
+
+/// [ArchiveStore] backed by an S3 (or S3-compatible) bucket.
+pub(crate) struct S3ArchiveStore {
+    bucket: String,
+    client: S3Client,
+}
+
+impl S3ArchiveStore {
+    pub(crate) async fn new(bucket: String) -> anyhow::Result<Self> {
+        let config = load_aws_config(Some(3)).await.context("Failed to load AWS config")?;
+        Ok(Self { bucket, client: S3Client::new(&config) })
+    }
+}
+
+#[async_trait]
+impl ArchiveStore for S3ArchiveStore {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> anyhow::Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(ByteStream::from(data))
+            .send()
+            .await
+            .with_context(|| format!("Failed to put s3://{}/{key}", self.bucket))?;
+        Ok(())
+    }
+}
+
+/// A single archived order, one per line of the data object.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ArchivedOrder {
+    pub id: String,
+    pub order: Order,
+    /// bincode serialized STARK receipt, if requested and still available from the prover
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<Bytes>,
+    /// Compressed (Groth16) receipt, if requested and still available from the prover
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compressed_receipt: Option<Bytes>,
+}
+
+/// Index entry describing one order within an archive data object.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ArchiveIndexEntry {
+    pub id: String,
+    pub request_id: U256,
+    pub status: OrderStatus,
+    pub updated_at: i64,
+    /// Zero based line number of the order within the decompressed data object
+    pub line: usize,
+}
+
+/// Index object written next to each archive data object.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ArchiveIndex {
+    pub data_key: String,
+    pub created_at: DateTime<Utc>,
+    pub orders: Vec<ArchiveIndexEntry>,
+}
+
+#[derive(Clone)]
+pub struct ArchiverTask {
+    db: DbObj,
+    config: ConfigLock,
+    prover: ProverObj,
+    store: Arc<dyn ArchiveStore>,
+    finalized_block: Option<watch::Receiver<u64>>,
+}
+
+impl ArchiverTask {
+    pub(crate) fn new(
+        db: DbObj,
+        config: ConfigLock,
+        prover: ProverObj,
+        store: Arc<dyn ArchiveStore>,
+    ) -> Self {
+        Self { db, config, prover, store, finalized_block: None }
+    }
+
+    /// Only archive fulfilled orders once the block they were fulfilled in is at or below the
+    /// given finalized block.
+    pub(crate) fn with_finalized_block(self, finalized_block: watch::Receiver<u64>) -> Self {
+        Self { finalized_block: Some(finalized_block), ..self }
+    }
+
+    /// Returns the leading orders of the batch whose fulfillment, if any, is final.
+    ///
+    /// Orders are archived oldest first, so the batch is cut at the first order fulfilled in a
+    /// block that is not final yet, and archived on a later pass.
+    async fn take_final(&self, orders: Vec<Order>) -> Result<Vec<Order>, ArchiverErr> {
+        let Some(finalized_block) = &self.finalized_block else {
+            return Ok(orders);
+        };
+        let finalized_block = *finalized_block.borrow();
+        let mut final_orders = Vec::with_capacity(orders.len());
+        for order in orders {
+            if order.status == OrderStatus::Done {
+                let fulfilled_block = self.db.get_request_fulfilled_block(order.request.id).await?;
+                if let Some(block) = fulfilled_block.filter(|block| *block > finalized_block) {
+                    tracing::debug!(
+                        "Holding back archival of order {}, fulfilled in block {block} above finalized block {finalized_block}",
+                        order.id()
+                    );
+                    break;
+                }
+            }
+            final_orders.push(order);
+        }
+        Ok(final_orders)
+    }
+
+    /// Fetch the receipts of a completed order from the prover.
+    ///
+    /// Failures are logged and ignored, the prover may have already purged the proof.
+    async fn fetch_receipts(&self, order: &Order) -> (Option<Bytes>, Option<Bytes>) {
+        if order.status != OrderStatus::Done {
+            return (None, None);
+        }
+
+        let mut receipt = None;
+        if let Some(proof_id) = order.proof_id.as_ref() {
+            match self.prover.get_receipt(proof_id).await {
+                Ok(Some(res)) => match bincode::serialize(&res) {
+                    Ok(bytes) => receipt = Some(bytes.into()),
+                    Err(err) => {
+                        tracing::warn!(
+                            "Failed to serialize receipt for order {}: {err}",
+                            order.id()
+                        )
+                    }
+                },
+                Ok(None) => {}
+                Err(err) => {
+                    tracing::debug!("Receipt for order {} unavailable: {err}", order.id())
+                }
+            }
+        }
+
+        let mut compressed_receipt = None;
+        if let Some(proof_id) = order.compressed_proof_id.as_ref() {
+            match self.prover.get_compressed_receipt(proof_id).await {
+                Ok(res) => compressed_receipt = res.map(Bytes::from),
+                Err(err) => {
+                    tracing::debug!(
+                        "Compressed receipt for order {} unavailable: {err}",
+                        order.id()
+                    )
+                }
+            }
+        }
+
+        (receipt, compressed_receipt)
+    }
+
+    /// Encode a batch of orders into the gzip compressed data object and its index.
+    fn encode_batch(
+        records: &[ArchivedOrder],
+        data_key: &str,
+    ) -> Result<(Vec<u8>, ArchiveIndex), ArchiverErr> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let mut entries = Vec::with_capacity(records.len());
+        for (line, record) in records.iter().enumerate() {
+            serde_json::to_writer(&mut encoder, record).context("Failed to serialize order")?;
+            encoder.write_all(b"\n").context("Failed to compress archive")?;
+            entries.push(ArchiveIndexEntry {
+                id: record.id.clone(),
+                request_id: record.order.request.id,
+                status: record.order.status,
+                updated_at: record.order.updated_at.timestamp(),
+                line,
+            });
+        }
+        let data = encoder.finish().context("Failed to compress archive")?;
+
+        Ok((
+            data,
+            ArchiveIndex {
+                data_key: data_key.to_string(),
+                created_at: Utc::now(),
+                orders: entries,
+            },
+        ))
+    }
+
+    /// Run a single archival pass, returning the number of orders archived.
+    async fn archive_closed_orders(&self) -> Result<usize, ArchiverErr> {
+        let (prefix, retention_secs, batch_size, include_receipts) = {
+            let config = self.config.lock_all()?;
+            (
+                config.archive.prefix.trim_end_matches('/').to_string(),
+                config.archive.retention_secs,
+                config.archive.batch_size.max(1),
+                config.archive.include_receipts,
+            )
+        };
+        let cutoff =
+            Utc::now().timestamp().saturating_sub(retention_secs.try_into().unwrap_or(i64::MAX));
+
+        let mut total = 0;
+        loop {
+            let orders = self.db.get_archivable_orders(cutoff, batch_size).await?;
+            let orders = self.take_final(orders).await?;
+            if orders.is_empty() {
+                break;
+            }
+            let count = orders.len();
+
+            let mut records = Vec::with_capacity(count);
+            for order in orders {
+                let (receipt, compressed_receipt) =
+                    if include_receipts { self.fetch_receipts(&order).await } else { (None, None) };
+                records.push(ArchivedOrder { id: order.id(), order, receipt, compressed_receipt });
+            }
+
+            // Orders are sorted by updated_at, so the first record dates the object.
+            let oldest = records[0].order.updated_at;
+            let name = format!("{}-{}", oldest.timestamp(), uuid::Uuid::new_v4());
+            let date_path = oldest.format("%Y/%m/%d");
+            let data_key = format!("{prefix}/orders/{date_path}/{name}.jsonl.gz");
+            let index_key = format!("{prefix}/index/{date_path}/{name}.json");
+
+            let (data, index) = Self::encode_batch(&records, &data_key)?;
+            let index = serde_json::to_vec(&index).context("Failed to serialize archive index")?;
+
+            self.store
+                .put(&data_key, data, "application/gzip")
+                .await
+                .map_err(ArchiverErr::UploadFailed)?;
+            self.store
+                .put(&index_key, index, "application/json")
+                .await
+                .map_err(ArchiverErr::UploadFailed)?;
+
+            // Only delete locally once both objects are durably stored.
+            let ids: Vec<&str> = records.iter().map(|r| r.id.as_str()).collect();
+            self.db.delete_orders(&ids).await?;
+            tracing::debug!("Archived {count} orders to {data_key}");
+
+            total += count;
+            // Also stops once the batch was cut at an order that is not final
+            if count < batch_size as usize {
+                break;
+            }
+        }
+
+        Ok(total)
+    }
+
+    async fn run_archiver_loop(&self, cancel_token: CancellationToken) -> Result<(), ArchiverErr> {
+        loop {
+            let interval = {
+                let config = self.config.lock_all()?;
+                config.archive.interval_secs
+            };
+
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(interval.into())) => {},
+                _ = cancel_token.cancelled() => {
+                    tracing::info!("Archiver task received cancellation, shutting down gracefully");
+                    return Ok(());
+                }
+            }
+
+            match self.archive_closed_orders().await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("[B-ARC-100] Archived {count} closed orders"),
+                Err(err) => tracing::warn!("Error archiving closed orders: {err}"),
+            }
+        }
+    }
+}
+
+impl RetryTask for ArchiverTask {
+    type Error = ArchiverErr;
+
+    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
+        let this = self.clone();
+        Box::pin(async move {
+            this.run_archiver_loop(cancel_token).await.map_err(SupervisorErr::Recover)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::SqliteDb, provers::DefaultProver, FulfillmentType, OrderRequest};
+    use alloy::primitives::Address;
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput, RequestInputType,
+        Requirements,
+    };
+    use flate2::read::GzDecoder;
+    use risc0_zkvm::sha::Digest;
+    use sqlx::SqlitePool;
+    use std::{io::Read, sync::Mutex};
+
+    #[derive(Default)]
+    struct MemoryStore {
+        objects: Mutex<Vec<(String, Vec<u8>)>>,
+    }
+
+    #[async_trait]
+    impl ArchiveStore for MemoryStore {
+        async fn put(&self, key: &str, data: Vec<u8>, _content_type: &str) -> anyhow::Result<()> {
+            self.objects.lock().unwrap().push((key.to_string(), data));
+            Ok(())
+        }
+    }
+
+    fn create_order(id: u32, status: OrderStatus, age_secs: i64) -> Order {
+        let mut order = OrderRequest::new(
+            ProofRequest::new(
+                RequestId::new(Address::ZERO, id),
+                Requirements::new(
+                    Digest::ZERO,
+                    Predicate {
+                        predicateType: PredicateType::PrefixMatch,
+                        data: Default::default(),
+                    },
+                ),
+                "http://risczero.com",
+                RequestInput { inputType: RequestInputType::Inline, data: "".into() },
+                Offer {
+                    minPrice: U256::from(1),
+                    maxPrice: U256::from(2),
+                    biddingStart: 0,
+                    timeout: 100,
+                    lockTimeout: 100,
+                    rampUpPeriod: 1,
+                    lockStake: U256::from(0),
+                },
+            ),
+            Bytes::new(),
+            FulfillmentType::LockAndFulfill,
+            Address::ZERO,
+            1,
+        )
+        .to_order(status);
+        order.updated_at = Utc::now() - chrono::Duration::seconds(age_secs);
+        order
+    }
+
+    #[sqlx::test]
+    async fn archives_and_deletes_closed_orders(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let config = ConfigLock::default();
+        {
+            let mut config = config.load_write().unwrap();
+            config.archive.retention_secs = 100;
+            config.archive.batch_size = 1;
+        }
+        let store = Arc::new(MemoryStore::default());
+        let archiver =
+            ArchiverTask::new(db.clone(), config, Arc::new(DefaultProver::new()), store.clone());
+
+        let done = create_order(1, OrderStatus::Done, 1000);
+        let failed = create_order(2, OrderStatus::Failed, 500);
+        let recent = create_order(3, OrderStatus::Done, 10);
+        let active = create_order(4, OrderStatus::Proving, 1000);
+        for order in [&done, &failed, &recent, &active] {
+            db.add_order(order).await.unwrap();
+        }
+
+        assert_eq!(archiver.archive_closed_orders().await.unwrap(), 2);
+
+        assert!(db.get_order(&done.id()).await.unwrap().is_none());
+        assert!(db.get_order(&failed.id()).await.unwrap().is_none());
+        assert!(db.get_order(&recent.id()).await.unwrap().is_some());
+        assert!(db.get_order(&active.id()).await.unwrap().is_some());
+
+        // batch_size of 1 results in a data and index object per order
+        let objects = store.objects.lock().unwrap();
+        assert_eq!(objects.len(), 4);
+        let (data_key, data) = &objects[0];
+        let (index_key, index) = &objects[1];
+        assert!(data_key.starts_with("broker-archive/orders/"));
+        assert!(data_key.ends_with(".jsonl.gz"));
+        assert!(index_key.starts_with("broker-archive/index/"));
+
+        let mut decoded = String::new();
+        GzDecoder::new(data.as_slice()).read_to_string(&mut decoded).unwrap();
+        let record: ArchivedOrder = serde_json::from_str(decoded.lines().next().unwrap()).unwrap();
+        assert_eq!(record.id, done.id());
+        assert_eq!(record.order.status, OrderStatus::Done);
+
+        let index: ArchiveIndex = serde_json::from_slice(index).unwrap();
+        assert_eq!(&index.data_key, data_key);
+        assert_eq!(index.orders.len(), 1);
+        assert_eq!(index.orders[0].id, done.id());
+        assert_eq!(index.orders[0].request_id, done.request.id);
+    }
+
+    #[sqlx::test]
+    async fn holds_back_orders_until_final(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let config = ConfigLock::default();
+        config.load_write().unwrap().archive.retention_secs = 100;
+        let (finalized_tx, finalized_rx) = watch::channel(10);
+        let store = Arc::new(MemoryStore::default());
+        let archiver =
+            ArchiverTask::new(db.clone(), config, Arc::new(DefaultProver::new()), store.clone())
+                .with_finalized_block(finalized_rx);
+
+        let final_order = create_order(1, OrderStatus::Done, 1000);
+        let pending = create_order(2, OrderStatus::Done, 900);
+        let failed = create_order(3, OrderStatus::Failed, 800);
+        for order in [&final_order, &pending, &failed] {
+            db.add_order(order).await.unwrap();
+        }
+        db.set_request_fulfilled(final_order.request.id, 10).await.unwrap();
+        db.set_request_fulfilled(pending.request.id, 11).await.unwrap();
+
+        // Archival stops at the first order fulfilled above the finalized block
+        assert_eq!(archiver.archive_closed_orders().await.unwrap(), 1);
+        assert!(db.get_order(&final_order.id()).await.unwrap().is_none());
+        assert!(db.get_order(&pending.id()).await.unwrap().is_some());
+        assert!(db.get_order(&failed.id()).await.unwrap().is_some());
+
+        finalized_tx.send_replace(11);
+        assert_eq!(archiver.archive_closed_orders().await.unwrap(), 2);
+        assert!(db.get_order(&pending.id()).await.unwrap().is_none());
+    }
+}
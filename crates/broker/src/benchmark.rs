@@ -0,0 +1,236 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Calibration of the proving throughput and gas estimates, for `broker benchmark`.
+//!
+//! The loop guest is preflighted and proven through the configured prover backend at a few cycle
+//! counts, measuring the achieved throughput. The gas estimates are derived from the gas the
+//! broker recorded for its past lock and fulfillment transactions.
+
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use guest_util::{LOOP_ELF, LOOP_ID};
+use risc0_zkvm::sha::Digest;
+
+use crate::{
+    now_timestamp,
+    provers::{encode_input, ProverObj},
+    GasTxKind, OrderGasRecord,
+};
+
+/// Minimum number of recorded transactions of a kind to calibrate its gas estimate from.
+const MIN_GAS_SAMPLES: usize = 10;
+
+/// Percentile of the gas used by recorded transactions taken as the estimate, so that most
+/// transactions fit within it.
+const GAS_ESTIMATE_PERCENTILE: usize = 90;
+
+/// Throughput achieved running the loop guest once.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BenchmarkRun {
+    pub(crate) total_cycles: u64,
+    pub(crate) preflight_secs: f64,
+    pub(crate) prove_secs: f64,
+}
+
+impl BenchmarkRun {
+    pub(crate) fn preflight_khz(&self) -> f64 {
+        khz(self.total_cycles, self.preflight_secs)
+    }
+
+    pub(crate) fn prove_khz(&self) -> f64 {
+        khz(self.total_cycles, self.prove_secs)
+    }
+}
+
+fn khz(cycles: u64, secs: f64) -> f64 {
+    if secs > 0.0 {
+        cycles as f64 / secs / 1_000.0
+    } else {
+        0.0
+    }
+}
+
+/// Preflight and prove the loop guest for each of the given numbers of mcycles, one at a time.
+pub(crate) async fn run_loop_guest(
+    prover: &ProverObj,
+    mcycles: &[u64],
+) -> Result<Vec<BenchmarkRun>> {
+    let image_id = Digest::from(LOOP_ID).to_string();
+    prover
+        .upload_image(&image_id, LOOP_ELF.to_vec())
+        .await
+        .context("Failed to upload the loop guest")?;
+
+    let mut runs = Vec::with_capacity(mcycles.len());
+    for (idx, mcycles) in mcycles.iter().enumerate() {
+        // The nonce makes the input unique, so that backends do not reuse earlier results
+        let nonce = now_timestamp() + idx as u64;
+        let input = encode_input(&(mcycles * 1_000_000, nonce))?;
+        let input_id = prover.upload_input(input).await.context("Failed to upload input")?;
+
+        let start = Instant::now();
+        prover
+            .preflight(&image_id, &input_id, vec![], None, &format!("benchmark-{idx}"))
+            .await
+            .with_context(|| format!("Failed to preflight {mcycles} mcycles"))?;
+        let preflight_secs = start.elapsed().as_secs_f64();
+
+        let start = Instant::now();
+        let proof = prover
+            .prove_and_monitor_stark(&image_id, &input_id, vec![])
+            .await
+            .with_context(|| format!("Failed to prove {mcycles} mcycles"))?;
+        let prove_secs = start.elapsed().as_secs_f64();
+
+        if let Err(err) = prover.delete_input(&input_id).await {
+            tracing::warn!("Failed to delete benchmark input {input_id}: {err}");
+        }
+        let run =
+            BenchmarkRun { total_cycles: proof.stats.total_cycles, preflight_secs, prove_secs };
+        tracing::info!(
+            "Proved {} cycles at {:.0} kHz, preflight at {:.0} kHz",
+            run.total_cycles,
+            run.prove_khz(),
+            run.preflight_khz()
+        );
+        runs.push(run);
+    }
+    Ok(runs)
+}
+
+/// Market config values calibrated by the benchmark.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Calibration {
+    pub(crate) peak_prove_khz: Option<u64>,
+    pub(crate) lockin_gas_estimate: Option<u64>,
+    pub(crate) fulfill_gas_estimate: Option<u64>,
+}
+
+impl Calibration {
+    /// Calibrate from the benchmark runs and the recorded gas of recent transactions.
+    ///
+    /// Small proofs underuse a proving cluster, so the fastest run is taken as its peak.
+    pub(crate) fn new(runs: &[BenchmarkRun], gas_records: &[OrderGasRecord]) -> Self {
+        let peak_prove_khz = runs
+            .iter()
+            .map(BenchmarkRun::prove_khz)
+            .filter(|khz| *khz >= 1.0)
+            .max_by(f64::total_cmp)
+            .map(|khz| khz as u64);
+        Self {
+            peak_prove_khz,
+            lockin_gas_estimate: gas_estimate(gas_records, GasTxKind::Lock),
+            fulfill_gas_estimate: gas_estimate(gas_records, GasTxKind::Fulfill),
+        }
+    }
+
+    /// Calibrated market config fields and their values.
+    pub(crate) fn values(&self) -> Vec<(&'static str, u64)> {
+        [
+            ("peak_prove_khz", self.peak_prove_khz),
+            ("lockin_gas_estimate", self.lockin_gas_estimate),
+            ("fulfill_gas_estimate", self.fulfill_gas_estimate),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| Some((field, value?)))
+        .collect()
+    }
+}
+
+fn gas_estimate(records: &[OrderGasRecord], kind: GasTxKind) -> Option<u64> {
+    let mut gas: Vec<u64> =
+        records.iter().filter(|record| record.kind == kind).map(|record| record.gas_used).collect();
+    if gas.len() < MIN_GAS_SAMPLES {
+        return None;
+    }
+    gas.sort_unstable();
+    Some(gas[(gas.len() - 1) * GAS_ESTIMATE_PERCENTILE / 100])
+}
+
+/// Set the given fields of the `[market]` table of the TOML config, keeping the rest as is.
+///
+/// Fields already set are replaced in place, the others are added at the top of the table.
+pub(crate) fn set_market_values(config: &str, values: &[(&str, u64)]) -> Result<String> {
+    let mut lines: Vec<String> = config.lines().map(str::to_string).collect();
+    let Some(market_idx) = lines.iter().position(|line| line.trim() == "[market]") else {
+        bail!("Config has no [market] table");
+    };
+    let market_end = lines[market_idx + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |idx| market_idx + 1 + idx);
+
+    let mut missing = Vec::new();
+    for (field, value) in values {
+        let existing = (market_idx + 1..market_end).find(|idx| {
+            lines[*idx]
+                .trim_start()
+                .strip_prefix(field)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        });
+        match existing {
+            Some(idx) => lines[idx] = format!("{field} = {value}"),
+            None => missing.push(format!("{field} = {value}")),
+        }
+    }
+    lines.splice(market_idx + 1..market_idx + 1, missing);
+
+    let mut updated = lines.join("\n");
+    if config.ends_with('\n') {
+        updated.push('\n');
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{FixedBytes, U256};
+    use chrono::Utc;
+
+    #[test]
+    fn calibrates_and_writes_config() {
+        let runs = [
+            BenchmarkRun { total_cycles: 4_000_000, preflight_secs: 1.0, prove_secs: 10.0 },
+            BenchmarkRun { total_cycles: 16_000_000, preflight_secs: 4.0, prove_secs: 20.0 },
+        ];
+        let record = |kind, gas_used| OrderGasRecord {
+            order_id: "a".into(),
+            kind,
+            tx_hash: FixedBytes::ZERO,
+            gas_used,
+            gas_cost: U256::ZERO,
+            created_at: Utc::now(),
+        };
+        let mut records: Vec<_> = (1..=20).map(|i| record(GasTxKind::Lock, i * 10_000)).collect();
+        records.push(record(GasTxKind::Fulfill, 300_000));
+
+        let calibration = Calibration::new(&runs, &records);
+        assert_eq!(runs[1].preflight_khz(), 4_000.0);
+        assert_eq!(calibration.peak_prove_khz, Some(800));
+        assert_eq!(calibration.lockin_gas_estimate, Some(180_000));
+        // Too few fulfillments to calibrate from
+        assert_eq!(calibration.fulfill_gas_estimate, None);
+
+        let config = "[market]\n# Peak\npeak_prove_khz = 500\nmcycle_price = \"0.1\"\n\n[prover]\nlockin_gas_estimate = 1\n";
+        let updated = set_market_values(config, &calibration.values()).unwrap();
+        assert_eq!(
+            updated,
+            "[market]\nlockin_gas_estimate = 180000\n# Peak\npeak_prove_khz = 800\nmcycle_price = \"0.1\"\n\n[prover]\nlockin_gas_estimate = 1\n"
+        );
+        set_market_values("[prover]\n", &calibration.values()).unwrap_err();
+    }
+}
@@ -13,10 +13,13 @@
 // limitations under the License.
 
 use alloy::{
+    network::Ethereum,
     primitives::utils::parse_ether,
-    providers::{fillers::ChainIdFiller, network::EthereumWallet, ProviderBuilder, WalletProvider},
+    providers::{
+        fillers::ChainIdFiller, network::EthereumWallet, Provider, ProviderBuilder, WalletProvider,
+    },
     rpc::client::RpcClient,
-    transports::layers::RetryBackoffLayer,
+    transports::{layers::RetryBackoffLayer, utils::guess_local_url},
 };
 use anyhow::{Context, Result};
 use boundless_market::{
@@ -25,9 +28,10 @@ use boundless_market::{
     dynamic_gas_filler::DynamicGasFiller,
     nonce_layer::NonceProvider,
 };
-use broker::{Args, Broker, Config, CustomRetryPolicy};
+use broker::{Args, Broker, Command, Config, ConfigCommand, CustomRetryPolicy, TieredTransport};
 use clap::Parser;
 use tracing_subscriber::fmt::format::FmtSpan;
+use url::Url;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -49,25 +53,89 @@ async fn main() -> Result<()> {
     }
 
     let wallet = EthereumWallet::from(args.private_key.clone());
+    let provider =
+        build_provider(&args, &config, &wallet, args.rpc_url.clone(), args.read_rpc_url.clone())?;
+    let broker = Broker::new(args.clone(), provider.clone())
+        .await?
+        .with_nonce_tracker(provider.nonce_tracker());
+    if let Some(Command::Config(ConfigCommand::Lint { lookback_blocks, benchmark_khz })) =
+        args.command
+    {
+        return broker.lint_config(lookback_blocks, benchmark_khz).await;
+    }
+    if let Some(Command::ExportDataset { output, since }) = &args.command {
+        return broker.export_dataset(output, *since).await;
+    }
+    if let Some(Command::ExportState { output }) = &args.command {
+        return broker.export_state(output).await;
+    }
+    if let Some(Command::ImportState { input, keep_proof_ids }) = &args.command {
+        return broker.import_state(input, *keep_proof_ids).await;
+    }
+    if let Some(Command::Benchmark { mcycles, gas_samples, write }) = &args.command {
+        return broker.benchmark(mcycles, *gas_samples, *write).await;
+    }
+
+    // TODO: Move this code somewhere else / monitor our balanceOf and top it up as needed
+    if let Some(deposit_amount) = args.deposit_amount.as_ref() {
+        let boundless_market = BoundlessMarketService::new(
+            broker.deployment().boundless_market_address,
+            provider.clone(),
+            provider.default_signer_address(),
+        );
+
+        tracing::info!("pre-depositing {deposit_amount} stake tokens into the market contract");
+        boundless_market
+            .deposit_stake_with_permit(*deposit_amount, &args.private_key)
+            .await
+            .context("Failed to deposit to market")?;
+    }
+
+    // Await broker shutdown before returning from main
+    broker.start_service().await.context("Broker service failed")?;
 
+    Ok(())
+}
+
+/// Build the provider used to interact with the chain at the given RPC URL.
+///
+/// With a read RPC URL, calls other than sending and tracking transactions are routed to it.
+fn build_provider(
+    args: &Args,
+    config: &Config,
+    wallet: &EthereumWallet,
+    rpc_url: Url,
+    read_rpc_url: Option<Url>,
+) -> Result<impl Provider<Ethereum> + WalletProvider + Clone + 'static> {
     let retry_layer = RetryBackoffLayer::new_with_policy(
         args.rpc_retry_max,
         args.rpc_retry_backoff,
         args.rpc_retry_cu,
         CustomRetryPolicy,
     );
-    let client = RpcClient::builder().layer(retry_layer).http(args.rpc_url.clone());
+    let client = match read_rpc_url {
+        Some(read_rpc_url) => {
+            tracing::info!("Routing RPC reads to {read_rpc_url}, transactions to {rpc_url}");
+            let is_local = guess_local_url(&rpc_url);
+            RpcClient::builder()
+                .layer(retry_layer)
+                .transport(TieredTransport::new(rpc_url, read_rpc_url), is_local)
+        }
+        None => RpcClient::builder().layer(retry_layer).http(rpc_url),
+    };
     let balance_alerts_layer = BalanceAlertLayer::new(BalanceAlertConfig {
         watch_address: wallet.default_signer().address(),
         warn_threshold: config
             .market
             .balance_warn_threshold
-            .map(|s| parse_ether(&s))
+            .as_deref()
+            .map(parse_ether)
             .transpose()?,
         error_threshold: config
             .market
             .balance_error_threshold
-            .map(|s| parse_ether(&s))
+            .as_deref()
+            .map(parse_ether)
             .transpose()?,
     });
 
@@ -85,26 +153,5 @@ async fn main() -> Result<()> {
         .layer(balance_alerts_layer)
         .connect_client(client);
 
-    let provider = NonceProvider::new(base_provider, wallet.clone());
-    let broker = Broker::new(args.clone(), provider.clone()).await?;
-
-    // TODO: Move this code somewhere else / monitor our balanceOf and top it up as needed
-    if let Some(deposit_amount) = args.deposit_amount.as_ref() {
-        let boundless_market = BoundlessMarketService::new(
-            broker.deployment().boundless_market_address,
-            provider.clone(),
-            provider.default_signer_address(),
-        );
-
-        tracing::info!("pre-depositing {deposit_amount} stake tokens into the market contract");
-        boundless_market
-            .deposit_stake_with_permit(*deposit_amount, &args.private_key)
-            .await
-            .context("Failed to deposit to market")?;
-    }
-
-    // Await broker shutdown before returning from main
-    broker.start_service().await.context("Broker service failed")?;
-
-    Ok(())
+    Ok(NonceProvider::new(base_provider, wallet.clone()))
 }
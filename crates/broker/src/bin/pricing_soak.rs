@@ -0,0 +1,89 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Soak test of the pricing hot path of the broker over synthetic orders.
+//!
+//! Runs the static checks, digest computation, gas math and decoding of every order, then reports
+//! the throughput and latency percentiles. Run with
+//! `cargo run --release -p broker --features bench --bin pricing-soak`.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{ensure, Result};
+use broker::{
+    bench::{self, SelectorRegistry, SyntheticOrder},
+    config::{ConfigLock, MarketConf},
+};
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Number of synthetic orders priced
+    #[clap(long, default_value_t = 10_000)]
+    orders: u32,
+
+    /// Number of times the orders are priced
+    #[clap(long, default_value_t = 3)]
+    rounds: u32,
+}
+
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    sorted[(sorted.len() * percent / 100).min(sorted.len() - 1)]
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    ensure!(args.orders > 0 && args.rounds > 0, "orders and rounds must be greater than 0");
+
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+    let orders: Vec<_> = (0..args.orders).map(|i| SyntheticOrder::new(i, now)).collect();
+    let market = MarketConf::default();
+    let config = ConfigLock::default();
+    let selectors = SelectorRegistry::default();
+
+    let mut latencies = Vec::with_capacity((args.orders * args.rounds) as usize);
+    let mut skipped = 0;
+    let started = Instant::now();
+    for _ in 0..args.rounds {
+        for order in &orders {
+            let order_started = Instant::now();
+            let decoded = bench::decode_json(&order.json_encoded())?;
+            ensure!(decoded == bench::decode_abi(&order.abi_encoded())?, "decoded ids differ");
+            if order.is_skipped(&market, now) {
+                skipped += 1;
+                latencies.push(order_started.elapsed());
+                continue;
+            }
+            std::hint::black_box(order.order_id());
+            std::hint::black_box(order.gas_estimate(&config, &selectors).await?);
+            latencies.push(order_started.elapsed());
+        }
+    }
+    let elapsed = started.elapsed();
+
+    latencies.sort_unstable();
+    let priced = latencies.len();
+    println!("priced {priced} orders ({skipped} skipped) in {elapsed:?}");
+    println!("throughput: {:.0} orders/s", priced as f64 / elapsed.as_secs_f64());
+    println!(
+        "latency: p50 {:?}, p99 {:?}, max {:?}",
+        percentile(&latencies, 50),
+        percentile(&latencies, 99),
+        latencies[priced - 1]
+    );
+
+    Ok(())
+}
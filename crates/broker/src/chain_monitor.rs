@@ -14,19 +14,38 @@
 
 use alloy_chains::NamedChain;
 use std::{
-    sync::Arc,
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
-use tokio::sync::{watch, Notify, RwLock};
+use tokio::sync::{broadcast, watch, Notify, RwLock};
 use tokio_util::sync::CancellationToken;
 
-use alloy::{eips::BlockNumberOrTag, providers::Provider};
+use alloy::{
+    consensus::Transaction as _,
+    eips::BlockNumberOrTag,
+    network::TransactionResponse,
+    primitives::{Address, B256, U256},
+    providers::Provider,
+    rpc::types::Transaction,
+    sol_types::SolCall,
+};
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use boundless_market::contracts::IBoundlessMarket;
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use serde::Deserialize;
 use thiserror::Error;
+use url::Url;
 
 use crate::{
+    config::{GasOracleConf, GasSpikeConf},
     errors::CodedError,
     impl_coded_debug,
+    metrics::MetricsObj,
     task::{RetryRes, RetryTask, SupervisorErr},
 };
 
@@ -49,35 +68,599 @@ impl CodedError for ChainMonitorErr {
     }
 }
 
+/// Maximum number of blocks that can be queried with `eth_feeHistory`.
+const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+/// Percentile of the per-block priority fee rewards queried from the fee history.
+const PRIORITY_FEE_REWARD_PERCENTILE: f64 = 50.0;
+
+/// Timeout for requests to an external gas oracle.
+const EXTERNAL_ORACLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval at which the mempool is polled for pending transactions.
+const MEMPOOL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Time after which a pending lock transaction is forgotten, as it was either included or dropped.
+const PENDING_LOCK_TTL: Duration = Duration::from_secs(60);
+
+/// Interval between checks of the chain head while waiting for confirmations.
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of chain heads tracked to detect reorgs. Reorgs replacing only older blocks are missed.
+const REORG_TRACKED_HEADS: usize = 128;
+
+/// Capacity of the channel reorgs are broadcast on.
+const REORG_CHANNEL_CAPACITY: usize = 16;
+
 #[derive(Clone, Debug, Copy)]
 pub(crate) struct ChainHead {
     pub block_number: u64,
     pub block_timestamp: u64,
 }
 
+/// Reorg of the chain, detected when a chain head seen before was replaced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ChainReorg {
+    /// First block that may have been replaced. Chain heads are not seen at every block, so the
+    /// actual fork may be after it.
+    pub fork_block: u64,
+}
+
+/// Fees projected for a transaction, in wei per gas.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub(crate) struct GasFees {
+    /// Maximum total fee per gas, used to estimate gas costs
+    pub max_fee_per_gas: u128,
+    /// Priority fee per gas, or 0 when the oracle does not estimate priority fees
+    #[serde(default)]
+    pub max_priority_fee_per_gas: u128,
+}
+
+/// Source of gas fee projections for pricing orders and sending transactions.
+#[async_trait]
+pub(crate) trait GasOracle: Send + Sync {
+    /// Projects the fees for transactions sent within the next `window_secs`, given the latest
+    /// block and its gas price as cached by the chain monitor.
+    async fn estimate_fees(
+        &self,
+        block_number: u64,
+        gas_price: u128,
+        window_secs: u64,
+    ) -> Result<GasFees>;
+}
+
+pub(crate) type GasOracleObj = Arc<dyn GasOracle>;
+
+/// Uses the current gas price reported by `eth_gasPrice`, regardless of the window.
+pub(crate) struct LegacyGasOracle;
+
+#[async_trait]
+impl GasOracle for LegacyGasOracle {
+    async fn estimate_fees(
+        &self,
+        _block_number: u64,
+        gas_price: u128,
+        _window_secs: u64,
+    ) -> Result<GasFees> {
+        Ok(GasFees { max_fee_per_gas: gas_price, max_priority_fee_per_gas: 0 })
+    }
+}
+
+/// Fee history of the blocks up to `block_number`, oldest first.
+struct FeeHistory {
+    block_number: u64,
+    /// Base fees of the blocks, and of the block after `block_number` last
+    base_fees: Vec<u128>,
+    /// Median priority fees of the blocks
+    rewards: Vec<u128>,
+}
+
+impl FeeHistory {
+    /// Base fees and rewards of the last `blocks` blocks, if covered by the history.
+    fn last(&self, blocks: u64) -> Option<(&[u128], &[u128])> {
+        let blocks = usize::try_from(blocks).ok()?;
+        let base_fees = self.base_fees.get(self.base_fees.len().checked_sub(blocks + 1)?..)?;
+        let rewards = self.rewards.get(self.rewards.len().saturating_sub(blocks)..)?;
+        Some((base_fees, rewards))
+    }
+}
+
+/// Projects EIP-1559 fees from the fee history of the blocks in the last `window_secs`, using
+/// recent fees as a proxy for the fees over the coming window.
+///
+/// The fee history is queried at most once per block, unless a longer window is requested.
+pub(crate) struct Eip1559GasOracle<P> {
+    provider: Arc<P>,
+    block_time: Duration,
+    base_fee_percentile: u8,
+    fee_history: Mutex<Option<FeeHistory>>,
+}
+
+#[async_trait]
+impl<P: Provider + 'static> GasOracle for Eip1559GasOracle<P> {
+    async fn estimate_fees(
+        &self,
+        block_number: u64,
+        _gas_price: u128,
+        window_secs: u64,
+    ) -> Result<GasFees> {
+        let blocks =
+            (window_secs / self.block_time.as_secs().max(1)).clamp(1, MAX_FEE_HISTORY_BLOCKS);
+        {
+            let fee_history = self.fee_history.lock().unwrap();
+            if let Some((base_fees, rewards)) = fee_history
+                .as_ref()
+                .filter(|history| history.block_number == block_number)
+                .and_then(|history| history.last(blocks))
+            {
+                return project_eip1559_fees(base_fees, rewards, self.base_fee_percentile)
+                    .context("empty fee history");
+            }
+        }
+
+        let history = self
+            .provider
+            .get_fee_history(
+                blocks,
+                BlockNumberOrTag::Number(block_number),
+                &[PRIORITY_FEE_REWARD_PERCENTILE],
+            )
+            .await
+            .context("failed to get fee history")?;
+        let rewards: Vec<u128> = history
+            .reward
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|reward| reward.first().copied())
+            .collect();
+        let fees =
+            project_eip1559_fees(&history.base_fee_per_gas, &rewards, self.base_fee_percentile)
+                .context("empty fee history")?;
+        *self.fee_history.lock().unwrap() =
+            Some(FeeHistory { block_number, base_fees: history.base_fee_per_gas, rewards });
+        Ok(fees)
+    }
+}
+
+/// Returns the value at the given percentile of the values.
+fn percentile(values: &[u128], percentile: u8) -> Option<u128> {
+    let mut values = values.to_vec();
+    values.sort_unstable();
+    let index = (values.len().checked_sub(1)?) * usize::from(percentile.min(100)) / 100;
+    values.get(index).copied()
+}
+
+/// Projects fees from the base fees and per-block priority fees of a fee history.
+///
+/// The base fee is the given percentile of the historical base fees, but at least the base fee
+/// of the next block, which is the last entry of the history. The priority fee is the median of
+/// the per-block priority fees.
+fn project_eip1559_fees(
+    base_fees: &[u128],
+    rewards: &[u128],
+    base_fee_percentile: u8,
+) -> Option<GasFees> {
+    let next_base_fee = *base_fees.last()?;
+    let base_fee = percentile(base_fees, base_fee_percentile)?.max(next_base_fee);
+    let priority_fee = percentile(rewards, 50).unwrap_or_default();
+    Some(GasFees {
+        max_fee_per_gas: base_fee + priority_fee,
+        max_priority_fee_per_gas: priority_fee,
+    })
+}
+
+/// Fetches fee projections from an external endpoint.
+///
+/// The endpoint is queried with a `window_secs` parameter, and responds with a JSON object with
+/// `max_fee_per_gas` and optionally `max_priority_fee_per_gas`, in wei.
+pub(crate) struct ExternalGasOracle {
+    client: reqwest::Client,
+    url: Url,
+}
+
+#[async_trait]
+impl GasOracle for ExternalGasOracle {
+    async fn estimate_fees(
+        &self,
+        _block_number: u64,
+        _gas_price: u128,
+        window_secs: u64,
+    ) -> Result<GasFees> {
+        let mut url = self.url.clone();
+        url.query_pairs_mut().append_pair("window_secs", &window_secs.to_string());
+        self.client
+            .get(url)
+            .timeout(EXTERNAL_ORACLE_TIMEOUT)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .with_context(|| format!("failed to query gas oracle {}", self.url))?
+            .json()
+            .await
+            .with_context(|| format!("invalid response from gas oracle {}", self.url))
+    }
+}
+
+/// Build the gas oracle for the given config.
+pub(crate) async fn gas_oracle_from_conf<P: Provider + 'static>(
+    conf: &GasOracleConf,
+    provider: Arc<P>,
+) -> Result<GasOracleObj> {
+    Ok(match conf {
+        GasOracleConf::Legacy => Arc::new(LegacyGasOracle),
+        GasOracleConf::Eip1559 { base_fee_percentile } => {
+            let chain_id = provider.get_chain_id().await.context("failed to get chain ID")?;
+            let block_time = NamedChain::try_from(chain_id)
+                .ok()
+                .and_then(|chain| chain.average_blocktime_hint())
+                .unwrap_or(Duration::from_secs(2));
+            Arc::new(Eip1559GasOracle {
+                provider,
+                block_time,
+                base_fee_percentile: *base_fee_percentile,
+                fee_history: Default::default(),
+            })
+        }
+        GasOracleConf::External { url } => {
+            Arc::new(ExternalGasOracle { client: reqwest::Client::new(), url: url.clone() })
+        }
+    })
+}
+
+/// Lock transaction of another account seen in the mempool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PendingLock {
+    pub sender: Address,
+    /// Priority fee per gas bid by the transaction, or its gas price for legacy transactions
+    pub priority_fee_per_gas: u128,
+}
+
+/// Returns the ID of the request locked by a call to the market, if it is a lock call.
+fn decode_lock_request_id(to: Option<Address>, market: Address, input: &[u8]) -> Option<U256> {
+    if to != Some(market) {
+        return None;
+    }
+    if let Ok(call) = IBoundlessMarket::lockRequestCall::abi_decode(input) {
+        Some(call.request.id)
+    } else if let Ok(call) = IBoundlessMarket::lockRequestWithSignatureCall::abi_decode(input) {
+        Some(call.request.id)
+    } else {
+        None
+    }
+}
+
+/// Rapid increase of the gas price, reported by the chain monitor while it lasts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct GasSpike {
+    /// Increase of the gas price over its lowest price in the window, in percent
+    pub pct: u64,
+    /// Window the increase was measured over
+    pub window: Duration,
+}
+
+/// Detects gas spikes from the gas prices seen over the configured window.
+struct GasSpikeDetector {
+    conf: GasSpikeConf,
+    samples: VecDeque<(Instant, u128)>,
+}
+
+impl GasSpikeDetector {
+    fn new(conf: GasSpikeConf) -> Self {
+        Self { conf, samples: VecDeque::new() }
+    }
+
+    /// Record the gas price, returning the spike it is part of, if any.
+    fn record(&mut self, now: Instant, gas_price: u128) -> Option<GasSpike> {
+        let window = Duration::from_secs(self.conf.window_secs);
+        while self.samples.front().is_some_and(|(at, _)| now.duration_since(*at) > window) {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((now, gas_price));
+
+        let lowest = self.samples.iter().map(|(_, price)| *price).min().unwrap_or(gas_price);
+        if lowest == 0 {
+            return None;
+        }
+        let pct = u64::try_from(gas_price.saturating_sub(lowest).saturating_mul(100) / lowest)
+            .unwrap_or(u64::MAX);
+        (pct >= self.conf.threshold_percent).then_some(GasSpike { pct, window })
+    }
+}
+
+/// Market watched in the mempool, and the sender of the broker's own lock transactions.
+#[derive(Clone, Copy)]
+struct MempoolWatch {
+    market: Address,
+    own_sender: Address,
+}
+
+/// How the finalized block is determined.
+#[derive(Clone, Copy)]
+enum Finality {
+    /// The `finalized` block reported by the node
+    Tag,
+    /// The block this many blocks below the chain head
+    Depth(u64),
+}
+
 #[derive(Clone)]
 pub struct ChainMonitorService<P> {
     provider: Arc<P>,
     gas_price: watch::Sender<u128>,
+    gas_oracle: GasOracleObj,
     update_notifier: Arc<Notify>,
     next_update: Arc<RwLock<Instant>>,
     head_update: watch::Sender<ChainHead>,
+    mempool_watch: Option<MempoolWatch>,
+    pending_locks: Arc<Mutex<HashMap<U256, (Instant, PendingLock)>>>,
+    gas_spike_detector: Option<Arc<Mutex<GasSpikeDetector>>>,
+    gas_spike: watch::Sender<Option<GasSpike>>,
+    finality: Option<Finality>,
+    finalized_block: watch::Sender<u64>,
+    /// Hashes of the last chain heads seen, by block number
+    recent_heads: Arc<Mutex<BTreeMap<u64, B256>>>,
+    reorgs: broadcast::Sender<ChainReorg>,
+    metrics: MetricsObj,
 }
 
-impl<P: Provider> ChainMonitorService<P> {
+impl<P: Provider + 'static> ChainMonitorService<P> {
     pub async fn new(provider: Arc<P>) -> Result<Self> {
         let (gas_price, _) = watch::channel(0);
         let (head_update, _) = watch::channel(ChainHead { block_number: 0, block_timestamp: 0 });
+        let (gas_spike, _) = watch::channel(None);
+        let (finalized_block, _) = watch::channel(0);
+        let (reorgs, _) = broadcast::channel(REORG_CHANNEL_CAPACITY);
 
         Ok(Self {
+            gas_oracle: Arc::new(LegacyGasOracle),
             provider,
             gas_price,
             update_notifier: Arc::new(Notify::new()),
             next_update: Arc::new(RwLock::new(Instant::now())),
             head_update,
+            mempool_watch: None,
+            pending_locks: Default::default(),
+            gas_spike_detector: None,
+            gas_spike,
+            finality: None,
+            finalized_block,
+            recent_heads: Default::default(),
+            reorgs,
+            metrics: Default::default(),
         })
     }
 
+    /// Use the given oracle to project gas fees, instead of the current `eth_gasPrice`.
+    pub(crate) fn with_gas_oracle(self, gas_oracle: GasOracleObj) -> Self {
+        Self { gas_oracle, ..self }
+    }
+
+    /// Watch the mempool for lock transactions sent to the market by accounts other than
+    /// `own_sender`.
+    pub(crate) fn with_mempool_watch(self, market: Address, own_sender: Address) -> Self {
+        Self { mempool_watch: Some(MempoolWatch { market, own_sender }), ..self }
+    }
+
+    /// Report gas spikes, as configured, from the gas price seen on each update.
+    pub(crate) fn with_gas_spike(self, conf: GasSpikeConf) -> Self {
+        Self { gas_spike_detector: Some(Arc::new(Mutex::new(GasSpikeDetector::new(conf)))), ..self }
+    }
+
+    /// Track the finalized block on each update, `finality_depth` blocks below the chain head or,
+    /// if unset, as reported by the node.
+    pub(crate) fn with_finality(self, finality_depth: Option<u64>) -> Self {
+        let finality = finality_depth.map_or(Finality::Tag, Finality::Depth);
+        Self { finality: Some(finality), ..self }
+    }
+
+    /// Record gas spikes in the given metrics.
+    pub(crate) fn with_metrics(self, metrics: MetricsObj) -> Self {
+        Self { metrics, ..self }
+    }
+
+    /// Returns the ongoing gas spike, if any, triggering an update if enough time has passed.
+    ///
+    /// Always `None` when gas spikes are not detected.
+    pub(crate) async fn current_gas_spike(&self) -> Result<Option<GasSpike>> {
+        if self.gas_spike_detector.is_none() {
+            return Ok(None);
+        }
+        self.current_gas_price().await?;
+        Ok(*self.gas_spike.borrow())
+    }
+
+    fn record_gas_price(&self, chain_id: u64, gas_price: u128) {
+        let Some(detector) = &self.gas_spike_detector else {
+            return;
+        };
+        let spike = detector.lock().unwrap().record(Instant::now(), gas_price);
+        let changed = self.gas_spike.send_if_modified(|current| {
+            let changed = current.is_some() != spike.is_some();
+            *current = spike;
+            changed
+        });
+        if !changed {
+            return;
+        }
+        match spike {
+            Some(spike) => tracing::warn!(
+                "Gas spike on chain {chain_id}: gas price up {}% over the last {}s",
+                spike.pct,
+                spike.window.as_secs()
+            ),
+            None => tracing::info!("Gas price on chain {chain_id} settled after a spike"),
+        }
+        self.metrics.record_gas_spike(chain_id, spike);
+    }
+
+    /// Subscribe to the finalized block number, updated along with the chain head.
+    ///
+    /// Stays at 0 when finality is not tracked.
+    pub(crate) fn subscribe_finalized_block(&self) -> watch::Receiver<u64> {
+        self.finalized_block.subscribe()
+    }
+
+    /// Subscribe to the reorgs detected on each update.
+    pub(crate) fn subscribe_reorgs(&self) -> broadcast::Receiver<ChainReorg> {
+        self.reorgs.subscribe()
+    }
+
+    /// Check the chain heads seen before against the chain ending in the given head, and track
+    /// the head. Returns the reorg that replaced any of the heads seen before.
+    ///
+    /// Only called while updating the chain head, so the tracked heads are not updated
+    /// concurrently.
+    async fn track_head(
+        &self,
+        number: u64,
+        hash: B256,
+        parent_hash: B256,
+    ) -> Result<Option<ChainReorg>> {
+        let tracked: Vec<(u64, B256)> = {
+            let recent_heads = self.recent_heads.lock().unwrap();
+            recent_heads.iter().rev().map(|(number, hash)| (*number, *hash)).collect()
+        };
+
+        // Walk back from the most recent head seen to the last one still on the chain
+        let (mut replaced, mut ancestor) = (None, None);
+        for (tracked_number, tracked_hash) in tracked {
+            let canonical_hash = if tracked_number > number {
+                None
+            } else if tracked_number == number {
+                Some(hash)
+            } else if tracked_number + 1 == number {
+                Some(parent_hash)
+            } else {
+                let block = self
+                    .provider
+                    .get_block_by_number(tracked_number.into())
+                    .await
+                    .with_context(|| format!("failed to fetch block {tracked_number}"))?;
+                block.map(|block| block.header.hash)
+            };
+            if canonical_hash == Some(tracked_hash) {
+                ancestor = Some(tracked_number);
+                break;
+            }
+            replaced = Some(tracked_number);
+        }
+        let reorg = replaced.map(|replaced| ChainReorg {
+            fork_block: ancestor.map_or(replaced, |ancestor| ancestor + 1),
+        });
+
+        let mut recent_heads = self.recent_heads.lock().unwrap();
+        if let Some(reorg) = reorg {
+            recent_heads.retain(|tracked_number, _| *tracked_number < reorg.fork_block);
+        }
+        recent_heads.insert(number, hash);
+        while recent_heads.len() > REORG_TRACKED_HEADS {
+            recent_heads.pop_first();
+        }
+        Ok(reorg)
+    }
+
+    /// Track the new chain head, broadcasting the reorg it reveals, if any.
+    async fn check_reorg(&self, chain_id: u64, number: u64, hash: B256, parent_hash: B256) {
+        match self.track_head(number, hash, parent_hash).await {
+            Ok(Some(reorg)) => {
+                tracing::warn!(
+                    "Reorg on chain {chain_id} from block {}, new head {number}",
+                    reorg.fork_block
+                );
+                self.metrics.record_chain_reorg();
+                // Fails only when no service is subscribed
+                let _ = self.reorgs.send(reorg);
+            }
+            Ok(None) => {}
+            Err(err) => tracing::warn!("Failed to check chain {chain_id} for reorgs: {err:?}"),
+        }
+    }
+
+    /// Wait until the given block has at least `confirmations` confirmations, returning the
+    /// chain head then. The block itself counts as the first confirmation.
+    pub(crate) async fn wait_for_confirmations(
+        &self,
+        block_number: u64,
+        confirmations: u64,
+    ) -> Result<u64> {
+        loop {
+            let head = self.current_block_number().await?;
+            if head.saturating_add(1) >= block_number.saturating_add(confirmations) {
+                return Ok(head);
+            }
+            tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn update_finalized_block(&self, head: u64) {
+        let finalized = match self.finality {
+            None => return,
+            Some(Finality::Depth(depth)) => head.saturating_sub(depth),
+            Some(Finality::Tag) => {
+                match self.provider.get_block_by_number(BlockNumberOrTag::Finalized).await {
+                    Ok(Some(block)) => block.header.number,
+                    Ok(None) => {
+                        tracing::warn!("Failed to fetch finalized block: no block in response");
+                        return;
+                    }
+                    Err(err) => {
+                        tracing::warn!("Failed to fetch finalized block: {err}");
+                        return;
+                    }
+                }
+            }
+        };
+        self.finalized_block.send_if_modified(|current| {
+            let changed = finalized > *current;
+            *current = (*current).max(finalized);
+            changed
+        });
+    }
+
+    /// Returns the lock transaction of another account for the request seen pending in the
+    /// mempool, if any.
+    pub(crate) fn competing_lock(&self, request_id: U256) -> Option<PendingLock> {
+        let pending_locks = self.pending_locks.lock().unwrap();
+        let (seen_at, lock) = pending_locks.get(&request_id)?;
+        (seen_at.elapsed() < PENDING_LOCK_TTL).then_some(*lock)
+    }
+
+    fn record_pending_locks(&self, locks: impl IntoIterator<Item = (U256, PendingLock)>) {
+        let mut pending_locks = self.pending_locks.lock().unwrap();
+        pending_locks.retain(|_, (seen_at, _)| seen_at.elapsed() < PENDING_LOCK_TTL);
+        for (request_id, lock) in locks {
+            tracing::debug!(
+                "Pending lock of request 0x{request_id:x} from {} seen in the mempool",
+                lock.sender
+            );
+            pending_locks.insert(request_id, (Instant::now(), lock));
+        }
+    }
+
+    fn record_pending_txs(&self, txs: &[Transaction]) {
+        let Some(watch) = self.mempool_watch else {
+            return;
+        };
+        self.record_pending_locks(
+            txs.iter().filter(|tx| tx.from() != watch.own_sender).filter_map(|tx| {
+                let request_id = decode_lock_request_id(tx.to(), watch.market, tx.input())?;
+                let lock = PendingLock {
+                    sender: tx.from(),
+                    priority_fee_per_gas: tx.priority_fee_or_price(),
+                };
+                Some((request_id, lock))
+            }),
+        );
+    }
+
+    /// Returns the gas fees projected by the gas oracle for transactions sent within the next
+    /// `window_secs`, from the chain head and gas price cached since the last update.
+    pub(crate) async fn projected_gas_fees(&self, window_secs: u64) -> Result<GasFees> {
+        let head = self.current_chain_head().await?;
+        let gas_price = self.current_gas_price().await?;
+        self.gas_oracle.estimate_fees(head.block_number, gas_price, window_secs).await
+    }
+
     /// Returns the latest block number, triggering an update if enough time has passed
     pub async fn current_block_number(&self) -> Result<u64> {
         self.current_chain_head().await.map(|head| head.block_number)
@@ -135,8 +718,32 @@ where
                 .map(|block_time| block_time.mul_f32(0.6))
                 .unwrap_or(Duration::from_secs(2));
 
+            let mut pending_txs: BoxStream<'static, Vec<Transaction>> = match self_clone
+                .mempool_watch
+            {
+                Some(_) => match self_clone.provider.watch_full_pending_transactions().await {
+                    Ok(poller) => {
+                        poller.with_poll_interval(MEMPOOL_POLL_INTERVAL).into_stream().boxed()
+                    }
+                    Err(err) => {
+                        tracing::warn!("Failed to watch the mempool, competing lock transactions will not be detected: {err}");
+                        stream::pending().boxed()
+                    }
+                },
+                None => stream::pending().boxed(),
+            };
+
             loop {
                 tokio::select! {
+                    txs = pending_txs.next() => {
+                        match txs {
+                            Some(txs) => self_clone.record_pending_txs(&txs),
+                            None => {
+                                tracing::warn!("Mempool watch stopped, competing lock transactions will not be detected");
+                                pending_txs = stream::pending().boxed();
+                            }
+                        }
+                    }
                     // Wait for notification or handle cancellation
                     _ = self_clone.update_notifier.notified() => {
                         // Needs update, lock next update value to avoid unnecessary notifications.
@@ -159,12 +766,17 @@ where
                             block_number: block.header.number,
                             block_timestamp: block.header.timestamp,
                         };
+                        self_clone
+                            .check_reorg(chain_id, head.block_number, block.header.hash, block.header.parent_hash)
+                            .await;
+                        self_clone.update_finalized_block(head.block_number).await;
                         let _ = self_clone.head_update.send_replace(head);
 
                         let gas_price = gas_price_res
                             .context("failed to get gas price")
                             .map_err(ChainMonitorErr::RpcErr)
                             .map_err(SupervisorErr::Recover)?;
+                        self_clone.record_gas_price(chain_id, gas_price);
                         let _ = self_clone.gas_price.send_replace(gas_price);
 
                         // Set timestamp for next update
@@ -191,9 +803,113 @@ mod tests {
         providers::{ext::AnvilApi, ProviderBuilder},
         signers::local::PrivateKeySigner,
     };
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput, RequestInputType,
+        Requirements,
+    };
+    use risc0_zkvm::sha::Digest;
 
     use super::*;
 
+    #[test]
+    fn eip1559_fee_projection() {
+        assert_eq!(project_eip1559_fees(&[], &[], 90), None);
+
+        // The next block's base fee is used if it is above the percentile
+        let fees = project_eip1559_fees(&[10, 20, 30, 40], &[1, 2, 3], 50).unwrap();
+        assert_eq!(fees, GasFees { max_fee_per_gas: 42, max_priority_fee_per_gas: 2 });
+
+        let fees = project_eip1559_fees(&[10, 50, 30, 20, 40, 15], &[], 90).unwrap();
+        assert_eq!(fees, GasFees { max_fee_per_gas: 40, max_priority_fee_per_gas: 0 });
+
+        let fees = project_eip1559_fees(&[10, 50, 30, 20, 40, 15], &[5], 100).unwrap();
+        assert_eq!(fees, GasFees { max_fee_per_gas: 55, max_priority_fee_per_gas: 5 });
+    }
+
+    #[test]
+    fn cached_fee_history_windows() {
+        let history = FeeHistory {
+            block_number: 10,
+            base_fees: vec![10, 20, 30, 40],
+            rewards: vec![1, 2, 3],
+        };
+        // Shorter windows are served from the last blocks of the cached history
+        assert_eq!(history.last(1), Some((&[30, 40][..], &[3][..])));
+        assert_eq!(history.last(3), Some((&[10, 20, 30, 40][..], &[1, 2, 3][..])));
+        // Longer windows are queried again
+        assert_eq!(history.last(4), None);
+    }
+
+    #[tokio::test]
+    async fn tracks_competing_locks() {
+        let market = Address::repeat_byte(1);
+        let request = ProofRequest::new(
+            RequestId::new(Address::repeat_byte(2), 1),
+            Requirements::new(
+                Digest::ZERO,
+                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+            ),
+            "http://risczero.com",
+            RequestInput { inputType: RequestInputType::Inline, data: "".into() },
+            Offer {
+                minPrice: U256::ZERO,
+                maxPrice: U256::from(1),
+                biddingStart: 100,
+                timeout: 100,
+                lockTimeout: 100,
+                rampUpPeriod: 1,
+                lockStake: U256::ZERO,
+            },
+        );
+        let input = IBoundlessMarket::lockRequestCall {
+            request: request.clone(),
+            clientSignature: Default::default(),
+        }
+        .abi_encode();
+        assert_eq!(decode_lock_request_id(Some(market), market, &input), Some(request.id));
+        assert_eq!(decode_lock_request_id(Some(Address::ZERO), market, &input), None);
+        assert_eq!(decode_lock_request_id(Some(market), market, &[0; 4]), None);
+
+        let provider =
+            Arc::new(ProviderBuilder::new().connect_http("http://localhost:8545".parse().unwrap()));
+        let chain_monitor = ChainMonitorService::new(provider)
+            .await
+            .unwrap()
+            .with_mempool_watch(market, Address::repeat_byte(3));
+        assert_eq!(chain_monitor.competing_lock(request.id), None);
+
+        let lock = PendingLock { sender: Address::repeat_byte(4), priority_fee_per_gas: 10 };
+        chain_monitor.record_pending_locks([(request.id, lock)]);
+        assert_eq!(chain_monitor.competing_lock(request.id), Some(lock));
+        assert_eq!(chain_monitor.competing_lock(U256::from(1)), None);
+
+        // Forgotten once expired
+        chain_monitor.pending_locks.lock().unwrap().get_mut(&request.id).unwrap().0 =
+            Instant::now() - PENDING_LOCK_TTL;
+        assert_eq!(chain_monitor.competing_lock(request.id), None);
+    }
+
+    #[test]
+    fn gas_spike_detector() {
+        let mut detector = GasSpikeDetector::new(GasSpikeConf {
+            threshold_percent: 50,
+            window_secs: 60,
+            submit_deadline_margin_secs: 600,
+        });
+        let start = Instant::now();
+        assert_eq!(detector.record(start, 100), None);
+        assert_eq!(detector.record(start + Duration::from_secs(10), 140), None);
+
+        let spike = detector.record(start + Duration::from_secs(20), 150).unwrap();
+        assert_eq!(spike, GasSpike { pct: 50, window: Duration::from_secs(60) });
+
+        // Still a spike while the lowest price is within the window
+        assert!(detector.record(start + Duration::from_secs(50), 160).is_some());
+
+        // Cleared once the price the increase was measured from leaves the window
+        assert_eq!(detector.record(start + Duration::from_secs(75), 160), None);
+    }
+
     #[tokio::test]
     async fn chain_monitor_smoke_test() {
         // Using an unknown chain ID to use default 2s polling time.
@@ -227,4 +943,68 @@ mod tests {
         let block = chain_monitor.current_block_number().await.unwrap();
         assert_eq!(block, NUM_BLOCKS);
     }
+
+    #[tokio::test]
+    async fn detects_reorgs() {
+        let anvil = Anvil::new().chain_id(888833888).spawn();
+        let provider = Arc::new(ProviderBuilder::new().connect(&anvil.endpoint()).await.unwrap());
+
+        let chain_monitor = Arc::new(ChainMonitorService::new(provider.clone()).await.unwrap());
+        tokio::spawn(chain_monitor.spawn(CancellationToken::new()));
+        let mut reorgs = chain_monitor.subscribe_reorgs();
+        let update = || async {
+            *chain_monitor.next_update.write().await = Instant::now();
+            chain_monitor.current_block_number().await.unwrap()
+        };
+
+        provider.anvil_mine(Some(2), None).await.unwrap();
+        assert_eq!(update().await, 2);
+        let snapshot = provider.evm_snapshot().await.unwrap();
+        provider.anvil_mine(Some(3), Some(2)).await.unwrap();
+        assert_eq!(update().await, 5);
+
+        // Blocks 3 to 5 are replaced by a longer chain, with blocks at other timestamps
+        assert!(provider.evm_revert(snapshot).await.unwrap());
+        provider.anvil_mine(Some(4), Some(100)).await.unwrap();
+        assert_eq!(update().await, 6);
+        assert_eq!(reorgs.try_recv().unwrap(), ChainReorg { fork_block: 3 });
+
+        // Extending the chain is not a reorg
+        provider.anvil_mine(Some(1), None).await.unwrap();
+        assert_eq!(update().await, 7);
+        assert!(reorgs.try_recv().is_err());
+        assert!(chain_monitor.metrics.encode().contains("broker_chain_reorgs_total 1\n"));
+    }
+
+    #[tokio::test]
+    async fn waits_for_confirmations() {
+        let anvil = Anvil::new().chain_id(888833888).spawn();
+        let provider = Arc::new(ProviderBuilder::new().connect(&anvil.endpoint()).await.unwrap());
+
+        let chain_monitor = Arc::new(
+            ChainMonitorService::new(provider.clone()).await.unwrap().with_finality(Some(4)),
+        );
+        tokio::spawn(chain_monitor.spawn(CancellationToken::new()));
+        let finalized = chain_monitor.subscribe_finalized_block();
+
+        // A transaction included in block 2 has 3 confirmations once the head reaches block 4
+        provider.anvil_mine(Some(2), None).await.unwrap();
+        let miner = {
+            let provider = provider.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                provider.anvil_mine(Some(2), None).await.unwrap();
+            })
+        };
+        let head = chain_monitor.wait_for_confirmations(2, 3).await.unwrap();
+        assert_eq!(head, 4);
+        miner.await.unwrap();
+        assert_eq!(*finalized.borrow(), 0);
+
+        // Blocks 4 below the head are final
+        provider.anvil_mine(Some(6), None).await.unwrap();
+        *chain_monitor.next_update.write().await = Instant::now();
+        assert_eq!(chain_monitor.current_block_number().await.unwrap(), 10);
+        assert_eq!(*finalized.borrow(), 6);
+    }
 }
@@ -13,12 +13,12 @@
 // limitations under the License.
 
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     path::{Path, PathBuf},
     sync::{Arc, RwLock},
 };
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, B256, U256};
 use anyhow::{Context, Result};
 use notify::{EventKind, Watcher};
 use serde::{Deserialize, Serialize};
@@ -28,6 +28,7 @@ use tokio::{
     task::JoinHandle,
     time::{timeout, Duration},
 };
+use url::Url;
 
 use crate::{errors::CodedError, impl_coded_debug};
 
@@ -57,6 +58,11 @@ mod defaults {
         250_000
     }
 
+    pub const fn assumption_cycles() -> u64 {
+        // One 1M cycle recursion segment resolving the assumption
+        1 << 20
+    }
+
     pub const fn additional_proof_cycles() -> u64 {
         // 2 mcycles for assessor + 270k cycles for set builder by default
         2_000_000 + 270_000
@@ -66,6 +72,30 @@ mod defaults {
         2
     }
 
+    pub const fn txn_max_resubmissions() -> u32 {
+        2
+    }
+
+    pub const fn txn_simulate() -> bool {
+        true
+    }
+
+    pub const fn txn_max_queued() -> usize {
+        32
+    }
+
+    pub const fn fulfillment_intent_stale_secs() -> u64 {
+        900
+    }
+
+    pub const fn fulfill_window_max_secs() -> u64 {
+        300
+    }
+
+    pub const fn fulfill_window_max_orders() -> u32 {
+        10
+    }
+
     pub const fn reaper_interval_secs() -> u32 {
         60
     }
@@ -77,6 +107,237 @@ mod defaults {
     pub const fn max_concurrent_preflights() -> u32 {
         4
     }
+
+    pub fn archive_prefix() -> String {
+        "broker-archive".to_string()
+    }
+
+    pub const fn archive_retention_secs() -> u64 {
+        // 7 days
+        7 * 24 * 60 * 60
+    }
+
+    pub const fn archive_interval_secs() -> u32 {
+        3600
+    }
+
+    pub const fn archive_batch_size() -> u32 {
+        500
+    }
+
+    pub const fn skipped_order_reevaluation_interval_secs() -> u64 {
+        30
+    }
+
+    pub const fn base_fee_percentile() -> u8 {
+        90
+    }
+
+    pub const fn duty_cycle_window_secs() -> u64 {
+        3600
+    }
+
+    pub const fn client_reputation_min_orders() -> u64 {
+        10
+    }
+
+    pub const fn client_reputation_min_score_percent() -> u64 {
+        25
+    }
+
+    pub const fn scale_exec_limits() -> bool {
+        true
+    }
+
+    pub const fn gas_dip_percent() -> u64 {
+        10
+    }
+
+    pub const fn gas_dip_max_wait_secs() -> u64 {
+        120
+    }
+
+    pub const fn uncontested_lookback_secs() -> u64 {
+        3600
+    }
+
+    pub const fn self_throttle_window_secs() -> u64 {
+        3600
+    }
+
+    pub const fn self_throttle_adjust_interval_secs() -> u64 {
+        900
+    }
+
+    pub const fn self_throttle_min_samples() -> u64 {
+        10
+    }
+
+    pub const fn max_lock_loss_percent() -> u64 {
+        90
+    }
+
+    pub const fn max_deadline_miss_percent() -> u64 {
+        10
+    }
+
+    pub const fn self_throttle_max_level() -> u32 {
+        3
+    }
+
+    pub const fn self_throttle_step_percent() -> u64 {
+        25
+    }
+
+    pub const fn min_concurrent_preflights() -> u32 {
+        1
+    }
+
+    pub const fn max_adaptive_preflights() -> u32 {
+        16
+    }
+
+    pub const fn max_preflight_cpu_percent() -> u64 {
+        90
+    }
+
+    pub const fn max_preflight_memory_percent() -> u64 {
+        90
+    }
+
+    pub const fn adaptive_preflights_adjust_interval_secs() -> u64 {
+        15
+    }
+
+    pub const fn tiny_order_gas_snapshot_secs() -> u64 {
+        30
+    }
+
+    pub const fn tiny_order_skip_batch_size() -> usize {
+        50
+    }
+
+    pub const fn lock_race_bump_percent() -> u64 {
+        10
+    }
+
+    pub const fn exec_limit_percentile() -> u8 {
+        99
+    }
+
+    pub const fn exec_limit_min_samples() -> u32 {
+        20
+    }
+
+    pub const fn exec_limit_max_samples() -> u32 {
+        500
+    }
+
+    pub fn lock_expired_min_profit() -> String {
+        "0".to_string()
+    }
+
+    pub const fn lock_expired_win_percent() -> u64 {
+        100
+    }
+
+    pub const fn speculative_prove_min_miss_percent() -> u64 {
+        50
+    }
+
+    pub const fn requestor_rate_limit_burst() -> u64 {
+        10
+    }
+
+    pub const fn requestor_rate_limit_expiry_margin_secs() -> u64 {
+        300
+    }
+
+    pub const fn lock_confirmations() -> u64 {
+        1
+    }
+
+    pub const fn fulfillment_confirmations() -> u64 {
+        1
+    }
+
+    pub const fn gas_spike_threshold_percent() -> u64 {
+        50
+    }
+
+    pub const fn gas_spike_window_secs() -> u64 {
+        120
+    }
+
+    pub const fn gas_spike_submit_deadline_margin_secs() -> u64 {
+        600
+    }
+
+    pub const fn deadline_margin_base_secs() -> u64 {
+        60
+    }
+
+    pub const fn deadline_margin_safety_percent() -> u64 {
+        150
+    }
+
+    pub const fn prover_backend_weight() -> u32 {
+        1
+    }
+
+    pub const fn order_stream_watchdog_secs() -> u64 {
+        120
+    }
+
+    pub const fn request_tombstone_ttl_secs() -> u64 {
+        // 24 hours
+        86_400
+    }
+
+    pub const fn signature_verify_batch_size() -> usize {
+        64
+    }
+
+    pub const fn order_claim_lease_secs() -> u64 {
+        60 * 60
+    }
+
+    pub const fn pricing_webhook_batch_size() -> usize {
+        50
+    }
+
+    pub const fn pricing_webhook_flush_interval_secs() -> u64 {
+        5
+    }
+
+    pub const fn event_webhook_batch_size() -> usize {
+        50
+    }
+
+    pub const fn event_webhook_flush_interval_secs() -> u64 {
+        5
+    }
+
+    pub const fn private_relay_fallback_secs() -> u64 {
+        // Flashbots Protect typically includes transactions within a few blocks
+        36
+    }
+
+    pub const fn groth16_workers() -> usize {
+        1
+    }
+
+    pub const fn pinned_image_check_secs() -> u64 {
+        600
+    }
+
+    pub const fn image_cache_max_size_mb() -> u64 {
+        10_240
+    }
+
+    pub const fn prover_backend_failover_backoff_secs() -> u64 {
+        60
+    }
 }
 
 /// Order pricing priority mode for determining which orders to price first
@@ -113,6 +374,554 @@ impl Default for OrderCommitmentPriority {
     }
 }
 
+/// Strategy for when to fulfill orders in the window after another prover's lock has expired
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum LockExpiredStrategyConf {
+    /// Start proving as soon as the lock expires
+    Immediate,
+    /// Wait for the gas price to dip below its recent average before starting
+    GasDip {
+        /// Percentage below the recent average gas price that is considered a dip
+        #[serde(default = "defaults::gas_dip_percent")]
+        dip_percent: u64,
+        /// Maximum time to wait for a dip after the lock expires, after which proving starts
+        /// regardless of the gas price
+        #[serde(default = "defaults::gas_dip_max_wait_secs")]
+        max_wait_secs: u64,
+    },
+    /// Only race for fulfillment if no other prover has recently fulfilled a lock-expired order
+    Uncontested {
+        /// Window in which competing lock-expired fulfillments are counted
+        #[serde(default = "defaults::uncontested_lookback_secs")]
+        lookback_secs: u64,
+    },
+}
+
+impl Default for LockExpiredStrategyConf {
+    fn default() -> Self {
+        Self::Immediate
+    }
+}
+
+/// Source of gas fee projections used when pricing orders and sending lock transactions
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum GasOracleConf {
+    /// Use the current gas price reported by `eth_gasPrice`
+    Legacy,
+    /// Project EIP-1559 fees from the fee history over the order's fulfillment window
+    Eip1559 {
+        /// Percentile of the base fees over the window that is projected
+        #[serde(default = "defaults::base_fee_percentile")]
+        base_fee_percentile: u8,
+    },
+    /// Query fee projections from an external endpoint
+    External {
+        /// URL of the endpoint, queried with a `window_secs` parameter
+        url: Url,
+    },
+}
+
+impl Default for GasOracleConf {
+    fn default() -> Self {
+        Self::Legacy
+    }
+}
+
+/// Limit on the share of time the prover spends proving, e.g. for power or thermal limits
+///
+/// The effective limit is the lower of `max_duty_cycle_percent` and
+/// `max_power_watts / prover_power_watts`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct DutyCycleConf {
+    /// Maximum share of each window that the prover may spend proving (in percent)
+    #[serde(default)]
+    pub max_duty_cycle_percent: Option<u64>,
+    /// Maximum sustained power draw of the prover, averaged over each window (in watts)
+    #[serde(default)]
+    pub max_power_watts: Option<u64>,
+    /// Power draw of the prover while proving (in watts)
+    #[serde(default)]
+    pub prover_power_watts: Option<u64>,
+    /// Length of the rolling window the duty cycle is measured over (in seconds)
+    #[serde(default = "defaults::duty_cycle_window_secs")]
+    pub window_secs: u64,
+    /// Hours of the day (UTC, 0-23) during which the limit applies
+    ///
+    /// The limit applies at all times if empty.
+    #[serde(default)]
+    pub peak_hours: Vec<u8>,
+}
+
+/// Scoring of requestors by their history with the broker
+///
+/// Scores are derived from the fulfillment success rate, the frequency of guest panics in
+/// preflight, the payment reliability at lock time and, optionally, the average journal size of
+/// the requestor's orders.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ClientReputationConf {
+    /// Number of preflighted orders from a requestor before it is scored
+    #[serde(default = "defaults::client_reputation_min_orders")]
+    pub min_orders: u64,
+    /// Score (in percent) below which orders from the requestor are skipped
+    #[serde(default = "defaults::client_reputation_min_score_percent")]
+    pub min_score_percent: u64,
+    /// Scale the preflight execution limit of orders by the requestor's score
+    #[serde(default = "defaults::scale_exec_limits")]
+    pub scale_exec_limits: bool,
+    /// Average journal size (in bytes) above which the score of a requestor is reduced
+    #[serde(default)]
+    pub max_average_journal_bytes: Option<u64>,
+}
+
+/// Automatic tightening of order intake when the broker falls behind
+///
+/// Over each `window_secs`, the share of lock attempts lost to other provers and the share of
+/// committed orders that missed their deadline are measured. When either exceeds its limit, the
+/// throttle level is raised by one, and when both are back under half their limit it is lowered
+/// by one, at most once per `adjust_interval_secs`. Each level raises the minimum mcycle prices by
+/// `price_step_percent` and lowers `max_concurrent_proofs` by `concurrency_step_percent`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct SelfThrottleConf {
+    /// Window (in seconds) over which lock and fulfillment outcomes are measured
+    #[serde(default = "defaults::self_throttle_window_secs")]
+    pub window_secs: u64,
+    /// Minimum time (in seconds) between two adjustments of the throttle level
+    #[serde(default = "defaults::self_throttle_adjust_interval_secs")]
+    pub adjust_interval_secs: u64,
+    /// Number of outcomes in the window before a rate is considered
+    #[serde(default = "defaults::self_throttle_min_samples")]
+    pub min_samples: u64,
+    /// Share (in percent) of lock attempts lost above which intake is tightened
+    #[serde(default = "defaults::max_lock_loss_percent")]
+    pub max_lock_loss_percent: u64,
+    /// Share (in percent) of committed orders missing their deadline above which intake is
+    /// tightened
+    #[serde(default = "defaults::max_deadline_miss_percent")]
+    pub max_deadline_miss_percent: u64,
+    /// Maximum throttle level
+    #[serde(default = "defaults::self_throttle_max_level")]
+    pub max_level: u32,
+    /// Increase (in percent) of the minimum mcycle prices per level
+    #[serde(default = "defaults::self_throttle_step_percent")]
+    pub price_step_percent: u64,
+    /// Decrease (in percent) of `max_concurrent_proofs` per level
+    #[serde(default = "defaults::self_throttle_step_percent")]
+    pub concurrency_step_percent: u64,
+}
+
+/// Adaptive number of concurrent preflights, replacing `max_concurrent_preflights`
+///
+/// Every `adjust_interval_secs`, the CPU utilization (load average per core) and memory
+/// utilization of the host, and the median duration of the preflights completed since, are
+/// compared to their limits. When any exceeds its limit, the number of concurrent preflights is
+/// lowered by a quarter, and when all are under their limit it is raised by one, within `min` and
+/// `max`. The utilization of the host is only measured on Linux. Starts from
+/// `max_concurrent_preflights`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct AdaptivePreflightConf {
+    /// Minimum number of concurrent preflights
+    #[serde(default = "defaults::min_concurrent_preflights")]
+    pub min: u32,
+    /// Maximum number of concurrent preflights
+    #[serde(default = "defaults::max_adaptive_preflights")]
+    pub max: u32,
+    /// CPU utilization (in percent) above which concurrency is lowered
+    #[serde(default = "defaults::max_preflight_cpu_percent")]
+    pub max_cpu_percent: u64,
+    /// Memory utilization (in percent) above which concurrency is lowered
+    #[serde(default = "defaults::max_preflight_memory_percent")]
+    pub max_memory_percent: u64,
+    /// Optional median preflight duration (in seconds) above which concurrency is lowered
+    #[serde(default)]
+    pub max_preflight_secs: Option<u64>,
+    /// Minimum time (in seconds) between two adjustments
+    #[serde(default = "defaults::adaptive_preflights_adjust_interval_secs")]
+    pub adjust_interval_secs: u64,
+}
+
+/// Fast path for pricing tiny orders, whose offer max price is below `max_price`
+///
+/// Tiny orders are priced with a gas price snapshot taken at most `gas_snapshot_secs` ago, and
+/// without checking the gas and stake balances reserved for other orders. Gas balance is still
+/// checked before locking. Skipped tiny orders are written to the DB in batches of
+/// `skip_batch_size`, or every few seconds.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct TinyOrderConf {
+    /// Offer max price (in the native token) below which an order is tiny, e.g. "0.00001"
+    pub max_price: String,
+    /// Maximum age (in seconds) of the gas price snapshot used to price tiny orders
+    #[serde(default = "defaults::tiny_order_gas_snapshot_secs")]
+    pub gas_snapshot_secs: u64,
+    /// Number of skipped tiny orders written to the DB at once
+    #[serde(default = "defaults::tiny_order_skip_batch_size")]
+    pub skip_batch_size: usize,
+}
+
+/// Action taken when a competing lock transaction is seen in the mempool
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LockRaceAction {
+    /// Do not send the lock transaction
+    Cancel,
+    /// Send the lock transaction with a priority fee above the competing transaction
+    Bump,
+}
+
+impl Default for LockRaceAction {
+    fn default() -> Self {
+        Self::Cancel
+    }
+}
+
+/// Mempool monitoring for lock transactions of other provers.
+///
+/// Pending `lockRequest` transactions sent to the market by other accounts are tracked, and an
+/// order about to be locked by the broker is abandoned or locked with a higher priority fee when
+/// a competing transaction for it is pending. Requires an RPC endpoint that supports pending
+/// transaction filters. Lock transactions that were already sent are not replaced.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct LockRaceWatchConf {
+    /// Action taken when a competing lock transaction is pending
+    #[serde(default)]
+    pub action: LockRaceAction,
+    /// Percentage above the priority fee of the competing transaction to bid when bumping
+    #[serde(default = "defaults::lock_race_bump_percent")]
+    pub bump_percent: u64,
+    /// Maximum priority gas (in wei) to add when bumping, above which the lock is abandoned
+    #[serde(default)]
+    pub max_priority_gas: Option<u64>,
+}
+
+/// Margin required before the deadline of an order, scaled by its estimated cycles.
+///
+/// The margin is `base_secs` plus the time to prove the order at the proving throughput, scaled
+/// by `safety_percent`. Before preflight, or while the throughput is unknown, only `base_secs` is
+/// required.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct DeadlineMarginConf {
+    /// Seconds required before the deadline regardless of the size of the order
+    #[serde(default = "defaults::deadline_margin_base_secs")]
+    pub base_secs: u64,
+    /// Percentage of the estimated proving time added to the margin
+    #[serde(default = "defaults::deadline_margin_safety_percent")]
+    pub safety_percent: u64,
+}
+
+impl DeadlineMarginConf {
+    /// Seconds required before the deadline of an order with the given cycles, if known.
+    pub fn margin_secs(&self, total_cycles: Option<u64>, prove_khz: Option<u64>) -> u64 {
+        let prove_secs = match (total_cycles, prove_khz) {
+            (Some(cycles), Some(khz)) if khz > 0 => cycles.div_ceil(khz.saturating_mul(1_000)),
+            _ => 0,
+        };
+        self.base_secs.saturating_add(prove_secs.saturating_mul(self.safety_percent) / 100)
+    }
+}
+
+/// Preflight exec limits learned from the cycles used by past executions of each image.
+///
+/// Once `min_samples` executions of an image were recorded, the exec limit of its orders is
+/// capped at the `percentile` of the latest `max_samples` cycle counts. Orders using more cycles
+/// than that are skipped without running the full preflight.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ExecLimitLearningConf {
+    /// Percentile (1 to 100) of past cycle counts used as the exec limit
+    #[serde(default = "defaults::exec_limit_percentile")]
+    pub percentile: u8,
+    /// Number of executions of an image recorded before its exec limit is capped
+    #[serde(default = "defaults::exec_limit_min_samples")]
+    pub min_samples: u32,
+    /// Number of the latest executions of each image that are kept
+    #[serde(default = "defaults::exec_limit_max_samples")]
+    pub max_samples: u32,
+}
+
+/// Circuit breaker for rapid gas price increases.
+///
+/// The chain monitor reports a gas spike while the gas price is at least `threshold_percent`
+/// above its lowest price over the last `window_secs`. During a spike, orders to lock are not
+/// priced, and batches are only submitted once their deadline is within
+/// `submit_deadline_margin_secs`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct GasSpikeConf {
+    /// Increase of the gas price over its lowest price in the window that is a spike, in percent
+    #[serde(default = "defaults::gas_spike_threshold_percent")]
+    pub threshold_percent: u64,
+    /// Window the increase is measured over (in seconds)
+    #[serde(default = "defaults::gas_spike_window_secs")]
+    pub window_secs: u64,
+    /// Batches with a deadline further out than this are not submitted during a spike (in
+    /// seconds)
+    #[serde(default = "defaults::gas_spike_submit_deadline_margin_secs")]
+    pub submit_deadline_margin_secs: u64,
+}
+
+/// Profitability model for orders fulfilled after another prover's lock has expired.
+///
+/// The share of the slashed stake expected to be won against other provers must cover the cost
+/// of proving at `mcycle_price_stake_token`, the gas to fulfill converted to stake tokens at
+/// `native_token_price`, and `min_profit`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct LockExpiredPricingConf {
+    /// Price of one native token (e.g. ETH) in stake tokens
+    pub native_token_price: String,
+    /// Expected share of lock-expired fulfillments won against other provers, in percent
+    #[serde(default = "defaults::lock_expired_win_percent")]
+    pub win_percent: u64,
+    /// Minimum expected profit to fulfill an order, in stake tokens
+    #[serde(default = "defaults::lock_expired_min_profit")]
+    pub min_profit: String,
+}
+
+/// Token-bucket rate limit on the orders of each requestor that are priced
+///
+/// Each requestor can have up to `burst` orders priced at once, refilled at `orders_per_minute`.
+/// Orders beyond that are deferred rather than skipped, and priced once the bucket refills or
+/// within `expiry_margin_secs` of their deadline, whichever comes first.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct RequestorRateLimitConf {
+    /// Rate at which the bucket of each requestor refills, in orders per minute
+    pub orders_per_minute: u64,
+    /// Number of orders of a requestor that can be priced at once
+    #[serde(default = "defaults::requestor_rate_limit_burst")]
+    pub burst: u64,
+    /// Orders within this many seconds of their deadline are priced regardless of the limit
+    #[serde(default = "defaults::requestor_rate_limit_expiry_margin_secs")]
+    pub expiry_margin_secs: u64,
+}
+
+/// Confirmations required of the broker's transactions before acting on them
+///
+/// A transaction included in the latest block has one confirmation. Locked orders are proven
+/// once their lock transaction has `lock` confirmations, and fulfilled orders are marked done
+/// once their fulfillment transaction has `fulfillment` confirmations. Closed orders are only
+/// archived once their fulfillment is final, either `finality_depth` blocks deep or, when
+/// unset, at or below the `finalized` block reported by the node.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ConfirmationConf {
+    /// Confirmations of a lock transaction before proving the order
+    #[serde(default = "defaults::lock_confirmations")]
+    pub lock: u64,
+    /// Confirmations of a fulfillment transaction before its orders are marked done
+    #[serde(default = "defaults::fulfillment_confirmations")]
+    pub fulfillment: u64,
+    /// Depth at which blocks are final, instead of the `finalized` block of the node
+    #[serde(default)]
+    pub finality_depth: Option<u64>,
+}
+
+/// Webhook notified of pricing decisions
+///
+/// Summaries of pricing decisions are POSTed to `url` as a JSON array, once `batch_size`
+/// decisions are queued or `flush_interval_secs` after the first of them. Delivery is best
+/// effort: batches are not retried, and decisions are dropped while the queue is full. When a
+/// `secret` is set, each request carries an `X-Webhook-Timestamp` header and an
+/// `X-Webhook-Signature` header of `sha256=` followed by the hex HMAC-SHA256 of
+/// `{timestamp}.{body}` keyed with the secret.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct PricingWebhookConf {
+    /// URL the pricing decisions are POSTed to
+    pub url: Url,
+    /// Secret the payloads are signed with
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Maximum number of pricing decisions sent at once
+    #[serde(default = "defaults::pricing_webhook_batch_size")]
+    pub batch_size: usize,
+    /// Maximum number of seconds a pricing decision is queued before being sent
+    #[serde(default = "defaults::pricing_webhook_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+}
+
+/// Webhook the order events of the broker are forwarded to, e.g. to alert operators
+///
+/// Events about the orders of the broker, from being received to being fulfilled, skipped or
+/// failed, are POSTed to `url` as a JSON array, once `batch_size` events are buffered or
+/// `flush_interval_secs` after the first of them. On-chain state changes of other requests are
+/// not forwarded. Balances are forwarded once when they drop below the thresholds, until topped
+/// up. Delivery is best effort, and requests are signed as for the [PricingWebhookConf].
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct EventWebhookConf {
+    /// URL the events are POSTed to
+    pub url: Url,
+    /// Secret the payloads are signed with
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Maximum number of events sent at once
+    #[serde(default = "defaults::event_webhook_batch_size")]
+    pub batch_size: usize,
+    /// Maximum number of seconds an event is buffered before being sent
+    #[serde(default = "defaults::event_webhook_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+    /// Order events forwarded, e.g. `order_locked`, `order_fulfilled`, `order_skipped` and
+    /// `order_failed`
+    ///
+    /// All order events are forwarded when empty.
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Skip reasons `order_skipped` events are forwarded for, e.g. `insufficient_stake`
+    ///
+    /// All skipped orders are forwarded when empty.
+    #[serde(default)]
+    pub skip_reasons: Vec<String>,
+    /// Forward the `gas_balance` of the signer when it drops below this, in the native token
+    #[serde(default)]
+    pub gas_balance_below: Option<String>,
+    /// Forward the `stake_balance` of the prover when it drops below this, in stake tokens
+    #[serde(default)]
+    pub stake_balance_below: Option<String>,
+}
+
+/// Gas and latency profile of the selectors orders can require
+///
+/// Unset fields keep the defaults of the proof type of the selector: Groth16 proofs cost
+/// `groth16_verify_gas_estimate` to verify and take time to wrap after proving, while other
+/// proofs cost nothing on top of `fulfill_gas_estimate`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct SelectorProfileConf {
+    /// Selector as 4 byte hex, or proof type (`groth16`, `inclusion` or `any`) to profile all
+    /// selectors of that type
+    pub selector: String,
+    /// Gas to verify the proof, on top of `fulfill_gas_estimate`
+    #[serde(default)]
+    pub fulfill_gas: Option<u64>,
+    /// Seconds to post-process the proof after proving, e.g. to wrap it in Groth16
+    #[serde(default)]
+    pub post_processing_secs: Option<u64>,
+    /// Whether the proving hardware can produce the proof; orders requiring it are skipped if not
+    #[serde(default)]
+    pub supported: Option<bool>,
+}
+
+/// Private relay lock transactions are sent to, such as Flashbots Protect
+///
+/// Lock transactions sent through the relay are not visible to competing provers watching the
+/// public mempool, so they can't be front-run.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct PrivateRelayConf {
+    /// RPC URL of the relay, accepting `eth_sendRawTransaction`
+    pub url: Url,
+    /// Seconds after which a lock transaction not included through the relay is sent to the
+    /// public mempool
+    #[serde(default = "defaults::private_relay_fallback_secs")]
+    pub fallback_secs: u64,
+}
+
+/// Claims on requests shared by broker replicas
+///
+/// Replicas sharing a wallet claim each request in the database at `db_url` before pricing it,
+/// and skip requests claimed by another replica. The database is either Postgres
+/// (`postgres://...`) or a SQLite file on a volume shared by the replicas. Claims are leased
+/// for `lease_secs` and extended while the replica holding them keeps handling the request, so
+/// that requests claimed by a replica that stopped are picked up by the others once expired.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct OrderClaimsConf {
+    /// URL of the claims database
+    pub db_url: String,
+    /// ID of this replica, unique among the replicas
+    ///
+    /// If not set, a random ID is generated on startup, so that claims held before a restart
+    /// are only released once their lease expires.
+    #[serde(default)]
+    pub replica_id: Option<String>,
+    /// Seconds a claim is held for
+    #[serde(default = "defaults::order_claim_lease_secs")]
+    pub lease_secs: u64,
+    /// Seconds the leadership lease is held for, enabling active/standby failover
+    ///
+    /// If set, only the replica holding the leadership lease locks orders. The others are on
+    /// standby: they follow the market and price orders, but do not lock them until they take
+    /// over the lease, once the leader stopped renewing it. The new leader then resumes proving
+    /// the orders locked by the previous one.
+    #[serde(default)]
+    pub leader_lease_secs: Option<u64>,
+}
+
+/// Tier of requestors whose orders are priced and prioritized apart from anonymous traffic,
+/// e.g. requestors with a service level agreement
+///
+/// Unset fields fall back to the market config. Requestors listed in several tiers belong to
+/// the first of them.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct RequestorTierConf {
+    /// Name of the tier, used in logs
+    pub name: String,
+    /// Requestor addresses in the tier
+    pub requestors: Vec<Address>,
+    /// Mega-cycle price of orders from the tier, in the native token, overriding `mcycle_price`
+    #[serde(default)]
+    pub mcycle_price: Option<String>,
+    /// Mega-cycle price of orders from the tier, in stake tokens, overriding
+    /// `mcycle_price_stake_token`
+    #[serde(default)]
+    pub mcycle_price_stake_token: Option<String>,
+    /// Max stake to lock orders from the tier, overriding `max_stake`
+    #[serde(default)]
+    pub max_stake: Option<String>,
+    /// Whether orders from the tier bypass the mcycle limit and max input size, as orders from
+    /// `priority_requestor_addresses` do
+    #[serde(default)]
+    pub skip_exec_limits: bool,
+    /// Priority of orders from the tier
+    ///
+    /// Orders are priced and committed to in order of the priority of their tier, after orders
+    /// from `priority_requestor_addresses` and before the priority of their tags. Orders from
+    /// requestors in no tier have priority 0.
+    #[serde(default)]
+    pub priority: u32,
+}
+
+impl RequestorTierConf {
+    /// Tier of the requestor, if it is in any.
+    pub fn find(tiers: &[Self], client: Address) -> Option<&Self> {
+        tiers.iter().find(|tier| tier.requestors.contains(&client))
+    }
+}
+
+/// Named tag applied to orders matching all of the given predicates
+///
+/// Unset predicates match any order. Tags are used to prioritize orders, limit how many orders
+/// of a kind are proven concurrently, and label orders in logs.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct OrderTagConf {
+    /// Name of the tag
+    pub name: String,
+    /// Requestor addresses to match
+    #[serde(default)]
+    pub requestors: Option<Vec<Address>>,
+    /// Image IDs to match
+    #[serde(default)]
+    pub image_ids: Option<Vec<B256>>,
+    /// Minimum offer max price to match, denominated in the native token (e.g. ETH)
+    #[serde(default)]
+    pub min_price: Option<String>,
+    /// Maximum offer max price to match, denominated in the native token (e.g. ETH)
+    #[serde(default)]
+    pub max_price: Option<String>,
+    /// Priority of orders with this tag
+    ///
+    /// Orders are priced and committed to in order of the highest priority of their tags, after
+    /// orders from `priority_requestor_addresses` and the priority of their requestor tier.
+    /// Untagged orders have priority 0.
+    #[serde(default)]
+    pub priority: u32,
+    /// Maximum number of orders with this tag to be proving concurrently
+    #[serde(default)]
+    pub max_concurrent_proofs: Option<u32>,
+}
+
+/// Alternate locations for image and input artifacts under a URL prefix
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ArtifactMirrorConf {
+    /// URL prefix of the original location, e.g. `https://gateway.pinata.cloud/ipfs/`
+    pub prefix: String,
+    /// Prefixes substituted for `prefix` to build mirror URLs, tried in order when the original
+    /// location fails
+    pub mirrors: Vec<String>,
+}
+
 /// All configuration related to markets mechanics
 #[derive(Debug, Deserialize, Serialize)]
 #[non_exhaustive]
@@ -153,11 +962,14 @@ pub struct MarketConf {
     ///
     /// Used to estimate proving capacity and accept only as much work as the prover can handle. Estimates
     /// can be derived from benchmarking using Bento CLI or from data based on fulfilling market orders.
+    /// Once proofs have completed, the order picker uses the observed proving throughput instead,
+    /// counting concurrent proofs together.
     pub peak_prove_khz: Option<u64>,
     /// Min seconds left before the deadline to consider bidding on a request.
     ///
     /// If there is not enough time left before the deadline, the prover may not be able to complete
-    /// proving of the request and finalize the batch for publishing before expiration.
+    /// proving of the request and finalize the batch for publishing before expiration. Replaced by
+    /// `deadline_margin` when set.
     pub min_deadline: u64,
     /// On startup, the number of blocks to look back for possible open orders.
     pub lookback_blocks: u64,
@@ -165,6 +977,13 @@ pub struct MarketConf {
     ///
     /// Requests that require a higher stake than this will not be considered.
     pub max_stake: String,
+    /// Expected decimals of the staking token
+    ///
+    /// Stake amounts are parsed and formatted with the decimals read from the token on startup.
+    /// When set, the broker refuses to start if the token reports different decimals, guarding
+    /// stake amounts configured for another deployment's token.
+    #[serde(default)]
+    pub stake_token_decimals: Option<u8>,
     /// Optional allow list for customer address.
     ///
     /// If enabled, all requests from clients not in the allow list are skipped.
@@ -173,6 +992,17 @@ pub struct MarketConf {
     ///
     /// If enabled, all requests from clients in the deny list are skipped.
     pub deny_requestor_addresses: Option<HashSet<Address>>,
+    /// Optional allow list for guest image IDs.
+    ///
+    /// If enabled, all requests for images not in the allow list are skipped.
+    #[serde(default)]
+    pub allow_image_ids: Option<HashSet<B256>>,
+    /// Optional deny list for guest image IDs.
+    ///
+    /// If enabled, all requests for images in the deny list are skipped, e.g. guests known to
+    /// frequently panic or hit session limits.
+    #[serde(default)]
+    pub deny_image_ids: Option<HashSet<B256>>,
     /// lockRequest priority gas
     ///
     /// Optional additional gas to add to the transaction for lockinRequest, good
@@ -201,11 +1031,30 @@ pub struct MarketConf {
     /// conservative default will be used.
     #[serde(default = "defaults::groth16_verify_gas_estimate")]
     pub groth16_verify_gas_estimate: u64,
+    /// Gas and latency profiles of selectors, see [SelectorProfileConf]
+    ///
+    /// Profiles of a selector override profiles of its proof type.
+    #[serde(default)]
+    pub selector_profiles: Vec<SelectorProfileConf>,
+    /// Maximum share of the price of an order that its gas may cost, in percent
+    ///
+    /// The gas spent by each order on its lock and fulfillment transactions is recorded. Locks
+    /// and fulfillment attempts whose cost, added to the gas already spent by the order, would
+    /// exceed this share of its price are aborted, unless aborting would forfeit the lock stake.
+    /// If not set, gas spending is recorded but not limited.
+    #[serde(default)]
+    pub gas_budget_percent: Option<u64>,
     /// Additional cycles to be proven for each order.
     ///
     /// This is currently the sum of the cycles for the assessor and set builder.
     #[serde(default = "defaults::additional_proof_cycles")]
     pub additional_proof_cycles: u64,
+    /// Cycles added to the preflight cycle count of an order for each of its assumptions.
+    ///
+    /// Orders composing proofs have the assumptions of their receipts resolved when proving, which
+    /// is not part of the preflight execution.
+    #[serde(default = "defaults::assumption_cycles")]
+    pub assumption_cycles: u64,
     /// Optional balance warning threshold (in native token)
     ///
     /// If the submitter balance drops below this the broker will issue warning logs
@@ -236,6 +1085,25 @@ pub struct MarketConf {
     /// Used to limit pricing tasks spawned to prevent overwhelming the system
     #[serde(default = "defaults::max_concurrent_preflights")]
     pub max_concurrent_preflights: u32,
+    /// Window to collect preflights in before submitting them to the prover as one batch (in ms)
+    ///
+    /// Orders selected for pricing together are preflighted in a single batch, of at most
+    /// `max_concurrent_preflights` executions, to amortize session setup on the prover. Set to 0
+    /// to submit each preflight on its own (default).
+    #[serde(default)]
+    pub preflight_batch_window_ms: u64,
+    /// Optional adaptive number of concurrent preflights, see [AdaptivePreflightConf]
+    ///
+    /// When set, the number of orders priced concurrently follows the load of the host and the
+    /// duration of preflights, instead of `max_concurrent_preflights`.
+    #[serde(default)]
+    pub adaptive_preflights: Option<AdaptivePreflightConf>,
+    /// Maximum number of URL inputs to prefetch concurrently
+    ///
+    /// Inputs of new orders are downloaded, validated and cached while the orders wait to be
+    /// priced. Set to 0 to only fetch inputs when preflighting (default). Read on startup.
+    #[serde(default)]
+    pub input_prefetch_concurrency: u32,
     /// Order pricing priority mode
     ///
     /// Determines how orders are prioritized for pricing. Options:
@@ -251,6 +1119,281 @@ pub struct MarketConf {
     /// - "shortest_expiry": Process orders by shortest expiry first (lock expiry for lock-and-fulfill orders, request expiry for others)
     #[serde(default, alias = "expired_order_fulfillment_priority")]
     pub order_commitment_priority: OrderCommitmentPriority,
+    /// Speculative proving window for lock-expired orders (in seconds)
+    ///
+    /// When the prover has no committed orders, orders that will become available to fulfill
+    /// after their lock expires are started early if their lock expires within this window.
+    /// The proof is held back until the lock has expired and is discarded if the original
+    /// locker fulfills the request first. Set to 0 to disable (default).
+    #[serde(default)]
+    pub speculative_prove_window_secs: u64,
+    /// Minimum share (in percent) of indexed locks the locker did not fulfill for its orders to
+    /// be proven speculatively
+    ///
+    /// Only orders that are likely to become available are proven speculatively: those of
+    /// lockers that missed at least this share of their indexed locks, being slashed or fulfilled
+    /// by another prover. Lockers with too few indexed locks are not proven speculatively.
+    #[serde(default = "defaults::speculative_prove_min_miss_percent")]
+    pub speculative_prove_min_miss_percent: u64,
+    /// Strategy for when to fulfill orders after another prover's lock has expired
+    ///
+    /// - "immediate": Start proving as soon as the lock expires (default)
+    /// - "gas_dip": Wait up to `max_wait_secs` for the gas price to drop `dip_percent` below
+    ///   its recent average
+    /// - "uncontested": Skip the order if other provers fulfilled lock-expired orders within
+    ///   the last `lookback_secs`
+    #[serde(default)]
+    pub lock_expired_strategy: LockExpiredStrategyConf,
+    /// Named order tags, see [OrderTagConf]
+    ///
+    /// An order can match multiple tags.
+    #[serde(default)]
+    pub order_tags: Vec<OrderTagConf>,
+    /// Optional shadow pricing profile
+    ///
+    /// When set, every order that reaches price evaluation is priced a second time using these
+    /// overrides. The shadow decision is never acted on; it is logged and recorded in the DB next
+    /// to the live decision so candidate pricing changes can be compared against live traffic.
+    #[serde(default)]
+    pub shadow_pricing: Option<ShadowPricingConf>,
+    /// Dry-run mode
+    ///
+    /// When enabled, orders are priced and preflighted as usual, but are never locked or
+    /// fulfilled. Orders that would have been locked or proven are recorded in the DB along with
+    /// their estimated profit, so pricing config can be evaluated against live traffic before
+    /// committing stake.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Record an audit log of every pricing decision
+    ///
+    /// When enabled, the inputs considered when pricing each order (gas price, balances, exec
+    /// limit, preflight cycles and mcycle prices) are recorded in the DB along with the outcome,
+    /// and can be queried by request ID through the admin API.
+    #[serde(default)]
+    pub pricing_audit: bool,
+    /// Mirrors for image and input URLs, see [ArtifactMirrorConf]
+    ///
+    /// Mirrors are tried in addition to any given by the request itself, in order of their
+    /// recent fetch failures.
+    #[serde(default)]
+    pub artifact_mirrors: Vec<ArtifactMirrorConf>,
+    /// Interval at which the prover's balances and proving queue are checked to re-queue skipped
+    /// orders (in seconds)
+    ///
+    /// Orders skipped for insufficient gas, stake or proving capacity are priced again when the
+    /// respective condition improves, until their lock expires. Set to 0 to disable.
+    #[serde(default = "defaults::skipped_order_reevaluation_interval_secs")]
+    pub skipped_order_reevaluation_interval_secs: u64,
+    /// Source of gas fee projections
+    ///
+    /// - "legacy": Use the current `eth_gasPrice` (default)
+    /// - "eip1559": Project fees from the fee history, using `base_fee_percentile` of the base
+    ///   fees over the order's fulfillment window plus the median priority fee
+    /// - "external": Query projections from `url`
+    ///
+    /// Read on startup.
+    #[serde(default)]
+    pub gas_oracle: GasOracleConf,
+    /// Optional duty cycle limit, see [DutyCycleConf]
+    ///
+    /// While the limit applies, orders that can still be completed after waiting out the window
+    /// are not locked or proven if the projected duty cycle would exceed the limit. Orders that
+    /// can not wait are committed to regardless.
+    #[serde(default)]
+    pub duty_cycle: Option<DutyCycleConf>,
+    /// Optional requestor reputation scoring, see [ClientReputationConf]
+    ///
+    /// Statistics of each requestor are recorded regardless. When set, orders from requestors
+    /// with a low score are skipped, and the execution limit of other orders is reduced in line
+    /// with the score. Does not apply to `priority_requestor_addresses`.
+    #[serde(default)]
+    pub client_reputation: Option<ClientReputationConf>,
+    /// Optional maximum risk score, from 0 to 100, of orders to lock
+    ///
+    /// When set, the risk of locking each order is scored after preflight from the time left to
+    /// prove it, the proving queue, the requestor's failed orders and the lock stake, and orders
+    /// scoring higher are skipped. The score components are recorded in the pricing audit log.
+    #[serde(default)]
+    pub max_risk_score: Option<u64>,
+    /// Optional self-throttling when falling behind, see [SelfThrottleConf]
+    ///
+    /// Outcomes are recorded regardless. When set, the minimum prices and the maximum number of
+    /// concurrent proofs are adjusted from the recent rate of lost lock races and missed
+    /// deadlines.
+    #[serde(default)]
+    pub self_throttle: Option<SelfThrottleConf>,
+    /// Optional interval (in seconds) at which to poll the order stream for new orders
+    ///
+    /// When set, orders are polled from the order stream REST API instead of received over a
+    /// WebSocket, for brokers behind proxies that block WebSocket connections. Read on startup.
+    #[serde(default)]
+    pub order_stream_poll_interval_secs: Option<u64>,
+    /// Optional consumer group to join on the order stream
+    ///
+    /// Each order is pushed over the WebSocket to only one broker of the group, so that a fleet
+    /// of brokers does not price the same orders. Each broker must connect with its own address.
+    /// Read on startup.
+    #[serde(default)]
+    pub order_stream_consumer_group: Option<String>,
+    /// Validate orders received from the order stream before pricing them
+    ///
+    /// Orders whose request, digest or signature are invalid for the market and chain are
+    /// dropped and counted in the metrics. Read on startup.
+    #[serde(default)]
+    pub order_stream_validation: bool,
+    /// Seconds without any message or pong from the order stream before it is degraded
+    ///
+    /// A degraded stream is reported to the supervisor, which restarts the offchain market
+    /// monitor. Not applied when polling the order stream. Set to 0 to disable. Read on startup.
+    #[serde(default = "defaults::order_stream_watchdog_secs")]
+    pub order_stream_watchdog_secs: u64,
+    /// Seconds a request skipped for a permanent reason is remembered (default 24 hours)
+    ///
+    /// Requests skipped for an unsupported selector, a denied image or a predicate the journal
+    /// does not match are tombstoned by the digest of the request, so that re-broadcasts of the
+    /// request are skipped without pricing them again. Tombstones can be cleared with the admin
+    /// API. Set to 0 to disable tombstones.
+    #[serde(default = "defaults::request_tombstone_ttl_secs")]
+    pub request_tombstone_ttl_secs: u64,
+    /// Number of workers verifying the signatures of orders from the order stream
+    ///
+    /// Defaults to the number of available CPUs.
+    #[serde(default)]
+    pub signature_verify_workers: Option<usize>,
+    /// Maximum number of queued orders from the order stream whose signatures are verified
+    /// together, split across the workers
+    #[serde(default = "defaults::signature_verify_batch_size")]
+    pub signature_verify_batch_size: usize,
+    /// Optional fast path for tiny orders, see [TinyOrderConf]
+    ///
+    /// Reduces the per-order overhead of the long tail of dust orders, most of which are skipped.
+    #[serde(default)]
+    pub tiny_orders: Option<TinyOrderConf>,
+    /// Optional mempool monitoring for competing lock transactions, see [LockRaceWatchConf]
+    ///
+    /// Whether the mempool is watched is read on startup, the rest of the config is read live.
+    #[serde(default)]
+    pub lock_race_watch: Option<LockRaceWatchConf>,
+    /// Optional deadline margin scaled by the estimated cycles of orders, see [DeadlineMarginConf]
+    ///
+    /// When set, replaces `min_deadline`, so that small orders are not rejected by a margin sized
+    /// for large ones, and large orders are not accepted with too little time to prove them.
+    #[serde(default)]
+    pub deadline_margin: Option<DeadlineMarginConf>,
+    /// Optional exec limits learned from the cycles of past executions, see
+    /// [ExecLimitLearningConf]
+    ///
+    /// Cycle counts are only recorded while set. Not applied to orders from
+    /// `priority_requestor_addresses`.
+    #[serde(default)]
+    pub exec_limit_learning: Option<ExecLimitLearningConf>,
+    /// Optional gas spike circuit breaker, see [GasSpikeConf]
+    ///
+    /// Read on startup.
+    #[serde(default)]
+    pub gas_spike: Option<GasSpikeConf>,
+    /// Optional profitability model for lock-expired orders, see [LockExpiredPricingConf]
+    ///
+    /// When unset, the full stake reward is expected to be paid out and the gas to fulfill is
+    /// not accounted for.
+    #[serde(default)]
+    pub lock_expired_pricing: Option<LockExpiredPricingConf>,
+    /// Optional rate limit on the orders priced per requestor, see [RequestorRateLimitConf]
+    ///
+    /// Keeps a single requestor flooding the market from taking up all preflight capacity. Does
+    /// not apply to `priority_requestor_addresses`.
+    #[serde(default)]
+    pub requestor_rate_limit: Option<RequestorRateLimitConf>,
+    /// Optional confirmation requirements of lock and fulfillment transactions, see
+    /// [ConfirmationConf]
+    ///
+    /// When unset, transactions are acted on once included in a block, and archival does not
+    /// wait for finality. Whether finality is tracked is read on startup, the rest of the config
+    /// is read live.
+    #[serde(default)]
+    pub confirmations: Option<ConfirmationConf>,
+    /// Optional webhook notified of pricing decisions, see [PricingWebhookConf]
+    ///
+    /// Read on startup.
+    #[serde(default)]
+    pub pricing_webhook: Option<PricingWebhookConf>,
+    /// Optional webhook the order events are forwarded to, see [EventWebhookConf]
+    ///
+    /// Read on startup.
+    #[serde(default)]
+    pub event_webhook: Option<EventWebhookConf>,
+    /// Optional private relay lock transactions are sent through, see [PrivateRelayConf]
+    ///
+    /// Read on startup.
+    #[serde(default)]
+    pub lock_private_relay: Option<PrivateRelayConf>,
+    /// Optional claims on requests shared with other broker replicas, see [OrderClaimsConf]
+    ///
+    /// Read on startup.
+    #[serde(default)]
+    pub order_claims: Option<OrderClaimsConf>,
+    /// Tiers of requestors with their own pricing and priority, see [RequestorTierConf]
+    #[serde(default)]
+    pub requestor_tiers: Vec<RequestorTierConf>,
+}
+
+impl MarketConf {
+    /// Gas an order may cost, in the native token, given its price, see `gas_budget_percent`.
+    pub fn gas_budget(&self, price: U256) -> Option<U256> {
+        self.gas_budget_percent.map(|percent| price * U256::from(percent) / U256::from(100))
+    }
+
+    /// Seconds required before the deadline of an order with the given cycles, if known.
+    pub fn deadline_margin_secs(&self, total_cycles: Option<u64>, prove_khz: Option<u64>) -> u64 {
+        match &self.deadline_margin {
+            Some(conf) => conf.margin_secs(total_cycles, prove_khz),
+            None => self.min_deadline,
+        }
+    }
+
+    /// Whether orders from the requestor bypass the mcycle limit and max input size, as a
+    /// priority requestor or through its tier.
+    pub fn skips_exec_limits(&self, client: Address) -> bool {
+        self.priority_requestor_addresses
+            .as_ref()
+            .is_some_and(|addresses| addresses.contains(&client))
+            || RequestorTierConf::find(&self.requestor_tiers, client)
+                .is_some_and(|tier| tier.skip_exec_limits)
+    }
+
+    /// Mega-cycle price of orders from the requestor, in the native token.
+    pub fn mcycle_price_for(&self, client: Address) -> &str {
+        RequestorTierConf::find(&self.requestor_tiers, client)
+            .and_then(|tier| tier.mcycle_price.as_deref())
+            .unwrap_or(&self.mcycle_price)
+    }
+
+    /// Mega-cycle price of orders from the requestor, in stake tokens.
+    pub fn mcycle_price_stake_token_for(&self, client: Address) -> &str {
+        RequestorTierConf::find(&self.requestor_tiers, client)
+            .and_then(|tier| tier.mcycle_price_stake_token.as_deref())
+            .unwrap_or(&self.mcycle_price_stake_token)
+    }
+
+    /// Max stake to lock orders from the requestor.
+    pub fn max_stake_for(&self, client: Address) -> &str {
+        RequestorTierConf::find(&self.requestor_tiers, client)
+            .and_then(|tier| tier.max_stake.as_deref())
+            .unwrap_or(&self.max_stake)
+    }
+}
+
+/// Overrides applied when pricing orders with the shadow profile.
+///
+/// Unset fields fall back to the live market config.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct ShadowPricingConf {
+    /// Candidate mega-cycle price, denominated in the native token (e.g. ETH).
+    pub mcycle_price: Option<String>,
+    /// Candidate mega-cycle price, denominated in the Boundless staking token.
+    pub mcycle_price_stake_token: Option<String>,
+    /// Candidate max cycles (in mcycles)
+    pub max_mcycle_limit: Option<u64>,
 }
 
 impl Default for MarketConf {
@@ -268,15 +1411,21 @@ impl Default for MarketConf {
             min_deadline: 120, // 2 mins
             lookback_blocks: 100,
             max_stake: "0.1".to_string(),
+            stake_token_decimals: None,
             allow_client_addresses: None,
             deny_requestor_addresses: None,
+            allow_image_ids: None,
+            deny_image_ids: None,
             lockin_priority_gas: None,
             max_file_size: 50_000_000,
             max_fetch_retries: Some(2),
             lockin_gas_estimate: defaults::lockin_gas_estimate(),
             fulfill_gas_estimate: defaults::fulfill_gas_estimate(),
+            gas_budget_percent: None,
             groth16_verify_gas_estimate: defaults::groth16_verify_gas_estimate(),
+            selector_profiles: Vec::new(),
             additional_proof_cycles: defaults::additional_proof_cycles(),
+            assumption_cycles: defaults::assumption_cycles(),
             balance_warn_threshold: None,
             balance_error_threshold: None,
             stake_balance_warn_threshold: None,
@@ -284,12 +1433,72 @@ impl Default for MarketConf {
             max_concurrent_proofs: None,
             cache_dir: None,
             max_concurrent_preflights: defaults::max_concurrent_preflights(),
+            preflight_batch_window_ms: 0,
+            adaptive_preflights: None,
+            input_prefetch_concurrency: 0,
             order_pricing_priority: OrderPricingPriority::default(),
             order_commitment_priority: OrderCommitmentPriority::default(),
+            speculative_prove_window_secs: 0,
+            speculative_prove_min_miss_percent: defaults::speculative_prove_min_miss_percent(),
+            lock_expired_strategy: LockExpiredStrategyConf::default(),
+            order_tags: Vec::new(),
+            shadow_pricing: None,
+            dry_run: false,
+            pricing_audit: false,
+            artifact_mirrors: Vec::new(),
+            skipped_order_reevaluation_interval_secs:
+                defaults::skipped_order_reevaluation_interval_secs(),
+            gas_oracle: GasOracleConf::default(),
+            duty_cycle: None,
+            client_reputation: None,
+            max_risk_score: None,
+            self_throttle: None,
+            order_stream_poll_interval_secs: None,
+            order_stream_consumer_group: None,
+            order_stream_validation: false,
+            order_stream_watchdog_secs: defaults::order_stream_watchdog_secs(),
+            request_tombstone_ttl_secs: defaults::request_tombstone_ttl_secs(),
+            signature_verify_workers: None,
+            signature_verify_batch_size: defaults::signature_verify_batch_size(),
+            tiny_orders: None,
+            lock_race_watch: None,
+            deadline_margin: None,
+            exec_limit_learning: None,
+            gas_spike: None,
+            lock_expired_pricing: None,
+            requestor_rate_limit: None,
+            confirmations: None,
+            pricing_webhook: None,
+            event_webhook: None,
+            lock_private_relay: None,
+            order_claims: None,
+            requestor_tiers: Vec::new(),
         }
     }
 }
 
+/// Image downloaded at startup and kept in the prover, see [ProverConf::pinned_images]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct PinnedImageConf {
+    /// URL of the image, fetched like request image URLs
+    pub url: String,
+    /// Expected image ID, checked against the downloaded image
+    ///
+    /// If set, the image is not downloaded when the prover already has it.
+    #[serde(default)]
+    pub image_id: Option<String>,
+}
+
+/// On-disk cache of guest images, see [ProverConf::image_cache]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ImageCacheConf {
+    /// Directory to store the images in
+    pub dir: PathBuf,
+    /// Size of the cache (in MiB) above which the least recently used images are evicted
+    #[serde(default = "defaults::image_cache_max_size_mb")]
+    pub max_size_mb: u64,
+}
+
 /// All configuration related to prover (bonsai / Bento) mechanics
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ProverConf {
@@ -342,6 +1551,37 @@ pub struct ProverConf {
     /// If not set, it defaults to 30 seconds.
     #[serde(default = "defaults::reaper_grace_period_secs")]
     pub reaper_grace_period_secs: u32,
+    /// Max number of orders proven at once
+    ///
+    /// Orders waiting to be proven are started by urgency, so that orders nearest their deadline
+    /// are proven first. If the prover supports checkpointing, a running proof with time to spare
+    /// is suspended to start an order that would otherwise miss its deadline. If not set, all
+    /// committed orders are proven at once.
+    #[serde(default)]
+    pub max_active_proofs: Option<u32>,
+    /// Number of proofs wrapped into Groth16 at once
+    ///
+    /// Orders requiring a Groth16 proof are wrapped after proving on a dedicated pool of workers.
+    /// The observed wrapping time, including the wait for a worker, is accounted for when
+    /// checking that orders can be fulfilled before their deadline.
+    #[serde(default = "defaults::groth16_workers")]
+    pub groth16_workers: usize,
+    /// Images of popular applications to download, verify and upload to the prover at startup
+    ///
+    /// Pinned images are checked again every `pinned_image_check_secs` and uploaded again if the
+    /// prover no longer has them, so that the first orders for these images do not wait on a
+    /// download.
+    #[serde(default)]
+    pub pinned_images: Vec<PinnedImageConf>,
+    /// Interval at which pinned images are checked in the prover (in seconds)
+    #[serde(default = "defaults::pinned_image_check_secs")]
+    pub pinned_image_check_secs: u64,
+    /// Optional on-disk cache of guest images, see [ImageCacheConf]
+    ///
+    /// Downloaded images are stored by image ID and verified when read, so that images the
+    /// prover no longer has are uploaded again without downloading them. Read on startup.
+    #[serde(default)]
+    pub image_cache: Option<ImageCacheConf>,
 }
 
 impl Default for ProverConf {
@@ -359,11 +1599,37 @@ impl Default for ProverConf {
             max_critical_task_retries: None,
             reaper_interval_secs: defaults::reaper_interval_secs(),
             reaper_grace_period_secs: defaults::reaper_grace_period_secs(),
+            max_active_proofs: None,
+            groth16_workers: defaults::groth16_workers(),
+            pinned_images: Vec::new(),
+            pinned_image_check_secs: defaults::pinned_image_check_secs(),
+            image_cache: None,
         }
     }
 }
 
+/// Window that proven orders are held in before their batch is fulfilled, to share the cost of
+/// the fulfillment transaction.
+///
+/// The window opens when the first proofs are added to a batch. The batch is fulfilled once
+/// `max_secs` have passed since, or once it holds `max_orders` orders, whichever comes first.
+/// Batches with an order within `block_deadline_buffer_secs` of its deadline are fulfilled
+/// regardless of the window.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct FulfillWindowConf {
+    /// Max time (in seconds) proven orders are held, from the first proofs added to the batch
+    #[serde(default = "defaults::fulfill_window_max_secs")]
+    pub max_secs: u64,
+    /// Number of orders after which the batch is fulfilled without waiting for the window to end
+    #[serde(default = "defaults::fulfill_window_max_orders")]
+    pub max_orders: u32,
+}
+
 /// All configuration related to batching / aggregation
+///
+/// Proofs are held in a batch until the first of `batch_max_time`, `min_batch_size`,
+/// `batch_max_journal_bytes`, `batch_max_fees` or the end of the `fulfill_window` is reached, or
+/// until an order in the batch is within `block_deadline_buffer_secs` of its deadline.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct BatcherConfig {
     /// Max batch duration before publishing (in seconds)
@@ -383,6 +1649,24 @@ pub struct BatcherConfig {
     pub block_deadline_buffer_secs: u64,
     /// Timeout, in seconds for transaction confirmations
     pub txn_timeout: Option<u64>,
+    /// Number of times a lock or fulfill transaction that is not confirmed within `txn_timeout`
+    /// is resubmitted with bumped fees
+    #[serde(default = "defaults::txn_max_resubmissions")]
+    pub txn_max_resubmissions: u32,
+    /// Whether to simulate lock and fulfill transactions before sending them, so that
+    /// transactions that would revert fail without spending gas
+    #[serde(default = "defaults::txn_simulate")]
+    pub txn_simulate: bool,
+    /// Max fee per gas, in wei, paid by lock and fulfill transactions, including resubmissions
+    pub txn_max_fee_per_gas: Option<u64>,
+    /// Max number of lock and fulfill transactions in flight at once
+    ///
+    /// Transactions beyond it wait to be sent, unlimited if unset
+    pub txn_max_in_flight: Option<usize>,
+    /// Max number of lock and fulfill transactions waiting to be sent, beyond which they fail
+    /// rather than being queued
+    #[serde(default = "defaults::txn_max_queued")]
+    pub txn_max_queued: usize,
     /// Polling time, in milliseconds
     ///
     /// The time between polls for new orders to aggregate and how often to check for batch finalize
@@ -400,6 +1684,18 @@ pub struct BatcherConfig {
     /// Number of attempts to make to submit a batch before abandoning
     #[serde(default = "defaults::max_submission_attempts")]
     pub max_submission_attempts: u32,
+    /// Age, in seconds, after which the intent to fulfill a request that is not fulfilled on
+    /// chain is considered abandoned
+    ///
+    /// An intent is recorded in the broker DB before a fulfillment transaction is sent, and
+    /// blocks sending another fulfillment of the request, including after a restart of the
+    /// broker, until it is abandoned. It should exceed the time to confirm a transaction with all
+    /// its resubmissions.
+    #[serde(default = "defaults::fulfillment_intent_stale_secs")]
+    pub fulfillment_intent_stale_secs: u64,
+    /// Optional window fulfillments are held in, see [FulfillWindowConf]
+    #[serde(default)]
+    pub fulfill_window: Option<FulfillWindowConf>,
 }
 
 impl Default for BatcherConfig {
@@ -411,14 +1707,153 @@ impl Default for BatcherConfig {
             batch_max_fees: None,
             block_deadline_buffer_secs: 120,
             txn_timeout: None,
+            txn_max_resubmissions: defaults::txn_max_resubmissions(),
+            txn_simulate: defaults::txn_simulate(),
+            txn_max_fee_per_gas: None,
+            txn_max_in_flight: None,
+            txn_max_queued: defaults::txn_max_queued(),
             batch_poll_time_ms: Some(1000),
             single_txn_fulfill: false,
             withdraw: false,
             max_submission_attempts: defaults::max_submission_attempts(),
+            fulfillment_intent_stale_secs: defaults::fulfillment_intent_stale_secs(),
+            fulfill_window: None,
+        }
+    }
+}
+
+/// Archival of closed orders to object storage
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ArchiveConf {
+    /// S3 bucket to archive closed orders into
+    ///
+    /// Archival is disabled when unset. Credentials are resolved from the default AWS credential
+    /// chain; S3-compatible stores (e.g. minio) can be targeted by setting `AWS_ENDPOINT_URL`.
+    pub bucket: Option<String>,
+    /// Key prefix for all archive objects written to the bucket
+    #[serde(default = "defaults::archive_prefix")]
+    pub prefix: String,
+    /// Retention period for closed orders (in seconds)
+    ///
+    /// Orders that are Done, Failed or Skipped and have not been updated within this period are
+    /// uploaded to the archive and deleted from the local database.
+    #[serde(default = "defaults::archive_retention_secs")]
+    pub retention_secs: u64,
+    /// Interval between archival passes (in seconds)
+    #[serde(default = "defaults::archive_interval_secs")]
+    pub interval_secs: u32,
+    /// Max number of orders written to a single archive object
+    #[serde(default = "defaults::archive_batch_size")]
+    pub batch_size: u32,
+    /// Fetch the receipts of completed orders from the prover and store them alongside the order
+    #[serde(default)]
+    pub include_receipts: bool,
+}
+
+impl Default for ArchiveConf {
+    fn default() -> Self {
+        Self {
+            bucket: None,
+            prefix: defaults::archive_prefix(),
+            retention_secs: defaults::archive_retention_secs(),
+            interval_secs: defaults::archive_interval_secs(),
+            batch_size: defaults::archive_batch_size(),
+            include_receipts: false,
         }
     }
 }
 
+/// Overrides of the restart policy of supervised tasks
+///
+/// Unset fields keep the built-in policy of the task, which restarts critical tasks faster.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RestartPolicyConf {
+    /// Milliseconds before the first restart of a failed task
+    #[serde(default)]
+    pub initial_delay_ms: Option<u64>,
+    /// Multiplier applied to the delay after each restart
+    #[serde(default)]
+    pub backoff_multiplier: Option<f64>,
+    /// Maximum milliseconds between restarts, regardless of backoff
+    #[serde(default)]
+    pub max_delay_ms: Option<u64>,
+    /// Maximum restarts of a task within `restart_window_secs`, after which the broker exits
+    #[serde(default)]
+    pub max_restarts: Option<u32>,
+    /// Seconds over which restarts are counted for `max_restarts`, 600 by default
+    #[serde(default)]
+    pub restart_window_secs: Option<u64>,
+}
+
+/// Restart policy of the tasks supervised by the broker
+///
+/// Read each time a task exits, so that changes apply without a restart.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+pub struct SupervisorConf {
+    /// Overrides for all tasks
+    #[serde(flatten)]
+    pub restart: RestartPolicyConf,
+    /// Overrides for individual tasks by name, e.g. `order_picker` or `market_monitor`, taking
+    /// precedence over those for all tasks
+    ///
+    /// Task names are logged with each restart.
+    #[serde(default)]
+    pub tasks: BTreeMap<String, RestartPolicyConf>,
+}
+
+/// Kind of a prover backend
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProverBackendKind {
+    /// Prove in-process with the local zkVM
+    Local,
+    /// A Bento cluster
+    Bento,
+    /// The Bonsai proving service
+    Bonsai,
+}
+
+/// Prover backend of the prover pool
+///
+/// Preflights and proofs are routed to the backend with the fewest jobs in flight relative to
+/// its weight, among the available backends within their cycle limits. When a backend errors
+/// or times out, the job fails over to the next backend, and the failed backend is avoided for
+/// `failover_backoff_secs`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProverBackendConf {
+    /// Name of the backend, used in proof IDs and logs
+    ///
+    /// Renaming a backend orphans the proofs in progress on it.
+    pub name: String,
+    /// Kind of the backend
+    pub kind: ProverBackendKind,
+    /// API URL of Bento or Bonsai backends
+    #[serde(default)]
+    pub url: Option<Url>,
+    /// API key of Bonsai backends
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Relative share of jobs routed to the backend
+    #[serde(default = "defaults::prover_backend_weight")]
+    pub weight: u32,
+    /// Only route proofs of at least this many cycles to the backend
+    #[serde(default)]
+    pub min_cycles: Option<u64>,
+    /// Only route proofs of at most this many cycles to the backend
+    #[serde(default)]
+    pub max_cycles: Option<u64>,
+    /// Max preflights and proofs in flight on the backend
+    #[serde(default)]
+    pub max_concurrent: Option<u32>,
+    /// Time after which a preflight or proof submission on the backend is abandoned and failed
+    /// over (in seconds)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Time a failed backend is avoided for (in seconds)
+    #[serde(default = "defaults::prover_backend_failover_backoff_secs")]
+    pub failover_backoff_secs: u64,
+}
+
 /// Top level config for the broker service
 #[derive(Deserialize, Serialize, Default, Debug)]
 pub struct Config {
@@ -428,6 +1863,17 @@ pub struct Config {
     pub prover: ProverConf,
     /// Aggregation batch configs
     pub batcher: BatcherConfig,
+    /// Closed order archival configs
+    #[serde(default)]
+    pub archive: ArchiveConf,
+    /// Restart policy of supervised tasks
+    #[serde(default)]
+    pub supervisor: SupervisorConf,
+    /// Prover backends to delegate preflights and proofs to
+    ///
+    /// When empty, the single backend selected by the broker arguments is used.
+    #[serde(default)]
+    pub provers: Vec<ProverBackendConf>,
 }
 
 impl Config {
@@ -481,7 +1927,9 @@ impl ConfigLock {
         self.config.read().map_err(|_| ConfigErr::LockFailed)
     }
 
-    #[cfg(test)]
+    /// Write access to the config, e.g. to override values at runtime.
+    ///
+    /// Overrides are lost when the config file changes and is reloaded.
     pub fn load_write(&self) -> Result<std::sync::RwLockWriteGuard<Config>, ConfigErr> {
         self.config.write().map_err(|_| ConfigErr::LockFailed)
     }
@@ -630,6 +2078,11 @@ allow_client_addresses = ["0x0000000000000000000000000000000000000000"]
 deny_requestor_addresses = ["0x0000000000000000000000000000000000000000"]
 lockin_priority_gas = 100
 max_mcycle_limit = 10
+speculative_prove_window_secs = 60
+speculative_prove_min_miss_percent = 25
+
+[market.shadow_pricing]
+mcycle_price = "0.2"
 
 [prover]
 status_poll_retry_count = 2
@@ -647,7 +2100,12 @@ block_deadline_buffer_secs = 120
 txn_timeout = 45
 batch_poll_time_ms = 1200
 single_txn_fulfill = true
-withdraw = true"#;
+withdraw = true
+
+[archive]
+bucket = "broker-archive-bucket"
+retention_secs = 86400
+include_receipts = true"#;
 
     const BAD_CONFIG: &str = r#"
 [market]
@@ -674,6 +2132,8 @@ error = ?"#;
         assert_eq!(config.market.max_stake, "0.1");
         assert_eq!(config.market.max_file_size, 50_000_000);
         assert_eq!(config.market.lockin_priority_gas, None);
+        assert_eq!(config.market.speculative_prove_window_secs, 0);
+        assert_eq!(config.market.speculative_prove_min_miss_percent, 50);
 
         assert_eq!(config.prover.status_poll_ms, 1000);
         assert_eq!(config.prover.status_poll_retry_count, 3);
@@ -691,6 +2151,56 @@ error = ?"#;
         assert_eq!(config.batcher.block_deadline_buffer_secs, 120);
         assert_eq!(config.batcher.txn_timeout, None);
         assert_eq!(config.batcher.batch_poll_time_ms, None);
+
+        assert_eq!(config.archive.bucket, None);
+        assert_eq!(config.archive.retention_secs, defaults::archive_retention_secs());
+    }
+
+    #[test]
+    fn deadline_margin_scales_with_cycles() {
+        let mut market = MarketConf { min_deadline: 300, ..Default::default() };
+        assert_eq!(market.deadline_margin_secs(Some(1_000_000_000), Some(1_000)), 300);
+
+        market.deadline_margin = Some(DeadlineMarginConf { base_secs: 60, safety_percent: 150 });
+        assert_eq!(market.deadline_margin_secs(None, Some(1_000)), 60);
+        assert_eq!(market.deadline_margin_secs(Some(1_000_000_000), None), 60);
+        // 1000 seconds at 1 MHz, with 50% headroom
+        assert_eq!(market.deadline_margin_secs(Some(1_000_000_000), Some(1_000)), 1_560);
+        assert_eq!(market.deadline_margin_secs(Some(1_000_000), Some(1_000)), 61);
+    }
+
+    #[test]
+    fn requestor_tiers_override_market() {
+        let enterprise = Address::repeat_byte(1);
+        let priority = Address::repeat_byte(2);
+        let anonymous = Address::repeat_byte(3);
+        let market = MarketConf {
+            mcycle_price: "0.1".into(),
+            mcycle_price_stake_token: "0.1".into(),
+            max_stake: "0.1".into(),
+            priority_requestor_addresses: Some(vec![priority]),
+            requestor_tiers: vec![RequestorTierConf {
+                name: "enterprise".into(),
+                requestors: vec![enterprise],
+                mcycle_price: Some("0.05".into()),
+                mcycle_price_stake_token: None,
+                max_stake: Some("1".into()),
+                skip_exec_limits: true,
+                priority: 10,
+            }],
+            ..Default::default()
+        };
+
+        let tier = RequestorTierConf::find(&market.requestor_tiers, enterprise).unwrap();
+        assert_eq!((tier.name.as_str(), tier.priority), ("enterprise", 10));
+        assert_eq!(market.mcycle_price_for(enterprise), "0.05");
+        assert_eq!(market.mcycle_price_stake_token_for(enterprise), "0.1");
+        assert_eq!(market.max_stake_for(enterprise), "1");
+        assert_eq!(market.mcycle_price_for(anonymous), "0.1");
+        assert_eq!(market.max_stake_for(anonymous), "0.1");
+        assert!(market.skips_exec_limits(enterprise));
+        assert!(market.skips_exec_limits(priority));
+        assert!(!market.skips_exec_limits(anonymous));
     }
 
     #[tokio::test]
@@ -739,6 +2249,11 @@ error = ?"#;
             assert_eq!(config.market.lockin_priority_gas, Some(100));
             assert_eq!(config.market.max_fetch_retries, Some(10));
             assert_eq!(config.market.max_mcycle_limit, Some(10));
+            assert_eq!(config.market.speculative_prove_window_secs, 60);
+            assert_eq!(config.market.speculative_prove_min_miss_percent, 25);
+            let shadow = config.market.shadow_pricing.as_ref().unwrap();
+            assert_eq!(shadow.mcycle_price, Some("0.2".into()));
+            assert_eq!(shadow.mcycle_price_stake_token, None);
             assert_eq!(config.prover.status_poll_ms, 1000);
             assert_eq!(config.prover.status_poll_retry_count, 2);
             assert_eq!(config.prover.req_retry_count, 1);
@@ -751,6 +2266,10 @@ error = ?"#;
             assert_eq!(config.batcher.min_batch_size, Some(3));
             assert!(config.batcher.single_txn_fulfill);
             assert!(config.batcher.withdraw);
+            assert_eq!(config.archive.bucket, Some("broker-archive-bucket".into()));
+            assert_eq!(config.archive.prefix, "broker-archive");
+            assert_eq!(config.archive.retention_secs, 86400);
+            assert!(config.archive.include_receipts);
         }
         tracing::debug!("closing...");
     }
@@ -0,0 +1,371 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks of the market config against recent market activity, for `broker config lint`.
+
+use std::{collections::HashMap, fmt};
+
+use alloy::{
+    network::Ethereum,
+    primitives::{
+        utils::{format_ether, parse_ether, parse_units},
+        Address, U256,
+    },
+    providers::Provider,
+    rpc::types::Filter,
+    sol_types::SolEvent,
+};
+use anyhow::{Context, Result};
+use boundless_market::contracts::IBoundlessMarket;
+
+use crate::{
+    config::MarketConf,
+    db::DbObj,
+    indexer::{decode_log, MarketEvent},
+    FulfillmentType, OrderRequest,
+};
+
+/// Number of blocks queried for logs at once.
+const LOG_QUERY_CHUNK_BLOCKS: u64 = 1_000;
+
+/// Maximum number of recent locks sampled.
+const MAX_SAMPLED_LOCKS: usize = 500;
+
+/// Minimum number of lock prices required to compare `mcycle_price` against.
+const MIN_PRICE_SAMPLES: usize = 5;
+
+/// Factor above the median clearing price at which `mcycle_price` is reported.
+const MCYCLE_PRICE_WARN_FACTOR: u64 = 2;
+
+/// Relative difference between `peak_prove_khz` and the benchmark at which it is reported.
+const PROVE_KHZ_WARN_PERCENT: u64 = 25;
+
+/// Recent market activity the config is checked against.
+#[derive(Debug, Default)]
+pub(crate) struct MarketSample {
+    /// Lock timeouts of recently locked requests, in seconds
+    pub(crate) lock_timeouts: Vec<u64>,
+    /// Lock prices per mcycle of recently locked requests that the broker has preflighted
+    pub(crate) mcycle_prices: Vec<U256>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LintWarning {
+    pub(crate) severity: Severity,
+    pub(crate) field: &'static str,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        write!(f, "{severity}: market.{}: {}", self.field, self.message)
+    }
+}
+
+fn median<T: Ord + Copy>(values: &[T]) -> Option<T> {
+    let mut values = values.to_vec();
+    values.sort();
+    values.get(values.len() / 2).copied()
+}
+
+/// Collect the lock timeouts and clearing prices of requests locked in the last `lookback_blocks`.
+///
+/// Clearing prices per mcycle are only known for requests the broker has preflighted, using the
+/// cycle count recorded in the DB.
+pub(crate) async fn sample_market<P: Provider<Ethereum>>(
+    provider: &P,
+    db: &DbObj,
+    market_addr: Address,
+    chain_id: u64,
+    lookback_blocks: u64,
+) -> Result<MarketSample> {
+    let current_block = provider.get_block_number().await.context("Failed to get block number")?;
+    let mut logs = Vec::new();
+    let mut from_block = current_block.saturating_sub(lookback_blocks);
+    while from_block <= current_block {
+        let to_block = (from_block + LOG_QUERY_CHUNK_BLOCKS - 1).min(current_block);
+        let filter = Filter::new()
+            .event_signature(IBoundlessMarket::RequestLocked::SIGNATURE_HASH)
+            .from_block(from_block)
+            .to_block(to_block)
+            .address(market_addr);
+        logs.extend(provider.get_logs(&filter).await.context("Failed to get logs")?);
+        from_block = to_block + 1;
+    }
+    tracing::debug!("Found {} locked requests in the past {lookback_blocks} blocks", logs.len());
+
+    let mut sample = MarketSample::default();
+    let mut block_timestamps = HashMap::new();
+    for log in logs.iter().rev().take(MAX_SAMPLED_LOCKS) {
+        let (request, client_signature) = match decode_log(log) {
+            Ok(Some((_, MarketEvent::Locked { request, client_signature, .. }))) => {
+                (request, client_signature)
+            }
+            Ok(_) => continue,
+            Err(err) => {
+                tracing::warn!("Failed to decode RequestLocked log: {err:?}");
+                continue;
+            }
+        };
+        sample.lock_timeouts.push(request.offer.lockTimeout.into());
+
+        let order = OrderRequest::new(
+            request.as_ref().clone(),
+            client_signature,
+            FulfillmentType::LockAndFulfill,
+            market_addr,
+            chain_id,
+        );
+        let Some(total_cycles) =
+            db.get_order(&order.id()).await?.and_then(|order| order.total_cycles)
+        else {
+            continue;
+        };
+        let Some(block_number) = log.block_number else {
+            continue;
+        };
+        let lock_timestamp = match block_timestamps.get(&block_number) {
+            Some(timestamp) => *timestamp,
+            None => {
+                let timestamp = provider
+                    .get_block_by_number(block_number.into())
+                    .await
+                    .with_context(|| format!("Failed to get block {block_number}"))?
+                    .with_context(|| format!("Block {block_number} not found"))?
+                    .header
+                    .timestamp;
+                block_timestamps.insert(block_number, timestamp);
+                timestamp
+            }
+        };
+        let lock_price = request.offer.price_at(lock_timestamp)?;
+        let mcycles = total_cycles.div_ceil(1_000_000).max(1);
+        sample.mcycle_prices.push(lock_price / U256::from(mcycles));
+    }
+    Ok(sample)
+}
+
+/// Check the market config against the sampled market activity and an optional benchmarked
+/// proving throughput, returning warnings with the most severe first.
+///
+/// Stake token amounts are parsed with the decimals of the stake token, as when pricing orders.
+pub(crate) fn lint_market_config(
+    config: &MarketConf,
+    sample: &MarketSample,
+    benchmark_khz: Option<u64>,
+    stake_token_decimals: u8,
+) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let mut warn =
+        |severity, field, message: String| warnings.push(LintWarning { severity, field, message });
+
+    if sample.lock_timeouts.is_empty() {
+        warn(
+            Severity::Info,
+            "min_deadline",
+            "no recently locked requests found to check against their lock timeouts".into(),
+        );
+    } else {
+        let min_deadline = config.deadline_margin_secs(None, None);
+        let below = sample.lock_timeouts.iter().filter(|&&t| t <= min_deadline).count();
+        if below * 2 > sample.lock_timeouts.len() {
+            warn(
+                Severity::Warning,
+                "min_deadline",
+                format!(
+                    "{}s is at least the lock timeout of {below} of {} recently locked requests (median {}s); these orders are never locked",
+                    min_deadline,
+                    sample.lock_timeouts.len(),
+                    median(&sample.lock_timeouts).unwrap_or_default(),
+                ),
+            );
+        }
+    }
+
+    match parse_ether(&config.mcycle_price) {
+        Err(err) => warn(Severity::Error, "mcycle_price", format!("invalid price: {err}")),
+        Ok(mcycle_price) if sample.mcycle_prices.len() >= MIN_PRICE_SAMPLES => {
+            let clearing_price = median(&sample.mcycle_prices).unwrap_or_default();
+            if mcycle_price > clearing_price * U256::from(MCYCLE_PRICE_WARN_FACTOR) {
+                warn(
+                    Severity::Warning,
+                    "mcycle_price",
+                    format!(
+                        "{} is more than {MCYCLE_PRICE_WARN_FACTOR}x the median clearing price of {} per mcycle over {} recent locks",
+                        config.mcycle_price,
+                        format_ether(clearing_price),
+                        sample.mcycle_prices.len(),
+                    ),
+                );
+            }
+        }
+        Ok(_) => warn(
+            Severity::Info,
+            "mcycle_price",
+            format!(
+                "only {} recent locks with known cycle counts, need {MIN_PRICE_SAMPLES} to compare against clearing prices",
+                sample.mcycle_prices.len()
+            ),
+        ),
+    }
+
+    match (config.peak_prove_khz, benchmark_khz) {
+        (Some(peak_prove_khz), Some(benchmark_khz)) => {
+            let diff = peak_prove_khz.abs_diff(benchmark_khz);
+            if diff * 100 > benchmark_khz * PROVE_KHZ_WARN_PERCENT {
+                warn(
+                    Severity::Warning,
+                    "peak_prove_khz",
+                    format!(
+                        "{peak_prove_khz} differs from the benchmarked {benchmark_khz} kHz by more than {PROVE_KHZ_WARN_PERCENT}%"
+                    ),
+                );
+            }
+        }
+        (None, Some(benchmark_khz)) => warn(
+            Severity::Warning,
+            "peak_prove_khz",
+            format!(
+                "not set; set it to the benchmarked {benchmark_khz} kHz to skip orders that can not be proven before their deadline"
+            ),
+        ),
+        (None, None) => warn(
+            Severity::Info,
+            "peak_prove_khz",
+            "not set; orders are not checked against their deadline until proofs have completed"
+                .into(),
+        ),
+        (Some(_), None) => {}
+    }
+
+    for tier in &config.requestor_tiers {
+        let amounts = [
+            ("mcycle_price", &tier.mcycle_price, 18),
+            ("mcycle_price_stake_token", &tier.mcycle_price_stake_token, stake_token_decimals),
+            ("max_stake", &tier.max_stake, stake_token_decimals),
+        ];
+        for (name, amount, decimals) in amounts {
+            let Some(amount) = amount else { continue };
+            if let Err(err) = parse_units(amount, decimals) {
+                warn(
+                    Severity::Error,
+                    "requestor_tiers",
+                    format!("tier {}: invalid {name} {amount}: {err}", tier.name),
+                );
+            }
+        }
+    }
+
+    warnings.sort_by_key(|warning| warning.severity);
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RequestorTierConf;
+
+    fn fields(warnings: &[LintWarning], severity: Severity) -> Vec<&'static str> {
+        warnings.iter().filter(|w| w.severity == severity).map(|w| w.field).collect()
+    }
+
+    #[test]
+    fn warns_on_min_deadline_above_lock_timeouts() {
+        let config = MarketConf { min_deadline: 300, ..Default::default() };
+        let sample = MarketSample { lock_timeouts: vec![120, 200, 600], ..Default::default() };
+        let warnings = lint_market_config(&config, &sample, None, 18);
+        assert_eq!(fields(&warnings, Severity::Warning), ["min_deadline"]);
+
+        let sample = MarketSample { lock_timeouts: vec![120, 600, 600], ..Default::default() };
+        let warnings = lint_market_config(&config, &sample, None, 18);
+        assert!(fields(&warnings, Severity::Warning).is_empty());
+    }
+
+    #[test]
+    fn warns_on_mcycle_price_above_clearing_price() {
+        let sample = MarketSample {
+            lock_timeouts: vec![600],
+            mcycle_prices: vec![parse_ether("0.000001").unwrap(); MIN_PRICE_SAMPLES],
+        };
+        let config = MarketConf { mcycle_price: "0.00001".into(), ..Default::default() };
+        let warnings = lint_market_config(&config, &sample, None, 18);
+        assert_eq!(fields(&warnings, Severity::Warning), ["mcycle_price"]);
+
+        let config = MarketConf { mcycle_price: "0.000002".into(), ..Default::default() };
+        let warnings = lint_market_config(&config, &sample, None, 18);
+        assert!(fields(&warnings, Severity::Warning).is_empty());
+
+        let config = MarketConf { mcycle_price: "abc".into(), ..Default::default() };
+        let warnings = lint_market_config(&config, &sample, None, 18);
+        assert_eq!(fields(&warnings, Severity::Error), ["mcycle_price"]);
+        // Errors are reported first
+        assert_eq!(warnings[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn warns_on_peak_prove_khz_far_from_benchmark() {
+        let sample = MarketSample { lock_timeouts: vec![600], ..Default::default() };
+        let config = MarketConf { peak_prove_khz: Some(100), ..Default::default() };
+        let warnings = lint_market_config(&config, &sample, Some(200), 18);
+        assert_eq!(fields(&warnings, Severity::Warning), ["peak_prove_khz"]);
+
+        let warnings = lint_market_config(&config, &sample, Some(110), 18);
+        assert!(fields(&warnings, Severity::Warning).is_empty());
+
+        let config = MarketConf { peak_prove_khz: None, ..Default::default() };
+        let warnings = lint_market_config(&config, &sample, Some(110), 18);
+        assert_eq!(fields(&warnings, Severity::Warning), ["peak_prove_khz"]);
+    }
+
+    #[test]
+    fn errors_on_invalid_tier_amounts() {
+        let sample = MarketSample { lock_timeouts: vec![600], ..Default::default() };
+        let tier = RequestorTierConf {
+            name: "enterprise".into(),
+            requestors: vec![],
+            mcycle_price: Some("0.05".into()),
+            mcycle_price_stake_token: Some("0.1".into()),
+            max_stake: Some("1".into()),
+            skip_exec_limits: false,
+            priority: 0,
+        };
+        let config = MarketConf { requestor_tiers: vec![tier.clone()], ..Default::default() };
+        let warnings = lint_market_config(&config, &sample, None, 6);
+        assert!(fields(&warnings, Severity::Error).is_empty());
+
+        let config = MarketConf {
+            requestor_tiers: vec![RequestorTierConf {
+                mcycle_price: Some("abc".into()),
+                max_stake: Some("1,5".into()),
+                ..tier
+            }],
+            ..Default::default()
+        };
+        let warnings = lint_market_config(&config, &sample, None, 6);
+        assert_eq!(fields(&warnings, Severity::Error), ["requestor_tiers", "requestor_tiers"]);
+        assert!(warnings[0].message.starts_with("tier enterprise: invalid mcycle_price abc"));
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exec limits learned from the cycles used by past executions of each image.
+
+use boundless_market::contracts::ProofRequest;
+
+use crate::{
+    config::ExecLimitLearningConf,
+    db::{DbError, DbObj},
+};
+
+/// Key of the image of the request in the cycle history.
+pub(crate) fn image_key(request: &ProofRequest) -> String {
+    request.requirements.imageId.to_string()
+}
+
+/// Cycle count at the configured percentile of the samples, if there are enough of them.
+pub(crate) fn learned_limit(samples: &[u64], conf: &ExecLimitLearningConf) -> Option<u64> {
+    if samples.is_empty() || samples.len() < conf.min_samples as usize {
+        return None;
+    }
+    let mut samples = samples.to_vec();
+    samples.sort_unstable();
+    let percentile = conf.percentile.clamp(1, 100) as usize;
+    let idx = (samples.len() * percentile).div_ceil(100).saturating_sub(1);
+    Some(samples[idx])
+}
+
+/// Exec limit learned for the image of the request, if enough executions were recorded.
+pub(crate) async fn exec_limit(
+    db: &DbObj,
+    request: &ProofRequest,
+    conf: &ExecLimitLearningConf,
+) -> Result<Option<u64>, DbError> {
+    let samples = db.get_image_cycles(&image_key(request)).await?;
+    Ok(learned_limit(&samples, conf))
+}
+
+/// Record the cycles used by an execution of the image of the request.
+pub(crate) async fn record(
+    db: &DbObj,
+    request: &ProofRequest,
+    total_cycles: u64,
+    conf: &ExecLimitLearningConf,
+) -> Result<(), DbError> {
+    db.add_image_cycles(&image_key(request), total_cycles, conf.max_samples).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::SqliteDb;
+    use alloy::primitives::{Address, U256};
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, RequestId, RequestInput, RequestInputType, Requirements,
+    };
+    use risc0_zkvm::sha::Digest;
+    use sqlx::SqlitePool;
+    use std::sync::Arc;
+
+    fn request(image_id: Digest) -> ProofRequest {
+        ProofRequest::new(
+            RequestId::new(Address::repeat_byte(1), 1),
+            Requirements::new(
+                image_id,
+                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+            ),
+            "http://risczero.com",
+            RequestInput { inputType: RequestInputType::Inline, data: "".into() },
+            Offer {
+                minPrice: U256::ZERO,
+                maxPrice: U256::from(1),
+                biddingStart: 0,
+                timeout: 100,
+                lockTimeout: 100,
+                rampUpPeriod: 1,
+                lockStake: U256::ZERO,
+            },
+        )
+    }
+
+    #[sqlx::test]
+    async fn learns_exec_limit_per_image(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let conf = ExecLimitLearningConf { percentile: 90, min_samples: 5, max_samples: 10 };
+        let image = request(Digest::new([1; 8]));
+        let other_image = request(Digest::new([2; 8]));
+
+        for cycles in 1..=4 {
+            record(&db, &image, cycles * 1_000, &conf).await.unwrap();
+        }
+        assert_eq!(exec_limit(&db, &image, &conf).await.unwrap(), None);
+
+        // Only the latest max_samples samples are kept
+        for cycles in 5..=20 {
+            record(&db, &image, cycles * 1_000, &conf).await.unwrap();
+        }
+        record(&db, &other_image, 1_000_000, &conf).await.unwrap();
+        let mut samples = db.get_image_cycles(&image_key(&image)).await.unwrap();
+        samples.sort_unstable();
+        assert_eq!(samples, (11..=20).map(|cycles| cycles * 1_000).collect::<Vec<_>>());
+
+        assert_eq!(exec_limit(&db, &image, &conf).await.unwrap(), Some(19_000));
+        assert_eq!(exec_limit(&db, &other_image, &conf).await.unwrap(), None);
+
+        let all = ExecLimitLearningConf { percentile: 100, ..conf };
+        assert_eq!(learned_limit(&samples, &all), Some(20_000));
+    }
+}
@@ -0,0 +1,499 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Labeled dataset of pricing decisions, for `broker export-dataset`.
+//!
+//! Each closed (Done, Failed or Skipped) order still in the broker DB is joined with its shadow
+//! pricing and dry-run records, the gas used to fulfill it, and the indexed view of the market
+//! request, and written as one row of a Parquet file. Orders removed by the archiver are not
+//! exported.
+//!
+//! # Schema
+//!
+//! The schema version is stored under the [SCHEMA_VERSION_KEY] key of the Parquet file metadata.
+//! Columns are only ever added within a version. Amounts in wei are decimal strings, as they do
+//! not fit in 64 bits. Nullable columns are marked with `?`.
+//!
+//! | Column                 | Type    | Description |
+//! |------------------------|---------|-------------|
+//! | `order_id`             | utf8    | ID of the order in the broker DB |
+//! | `request_id`           | utf8    | Request ID, as 0x-prefixed hex |
+//! | `client`               | utf8    | Address of the requestor |
+//! | `chain_id`             | uint64  | Chain the request was made on |
+//! | `fulfillment_type`     | utf8    | `lock_and_fulfill`, `fulfill_after_lock_expire` or `fulfill_without_locking` |
+//! | `decision`             | utf8    | Live pricing decision: `lock`, `prove_after_lock_expire` or `skip` |
+//! | `shadow_decision`      | utf8?   | Decision of the shadow pricing config, if enabled |
+//! | `dry_run_decision`     | utf8?   | Decision taken in dry-run mode, if enabled |
+//! | `skip_reason`          | utf8?   | Reason the order was skipped |
+//! | `min_price`            | utf8    | Offer minimum price, in wei |
+//! | `max_price`            | utf8    | Offer maximum price, in wei |
+//! | `lock_stake`           | utf8    | Offer lock stake, in stake token base units |
+//! | `bidding_start`        | uint64  | Offer bidding start, UNIX timestamp |
+//! | `ramp_up_period`       | uint64  | Offer ramp-up period, in seconds |
+//! | `lock_timeout`         | uint64  | Offer lock timeout, in seconds |
+//! | `timeout`              | uint64  | Offer timeout, in seconds |
+//! | `total_cycles`         | uint64? | Cycles measured by preflight |
+//! | `lock_price`           | utf8?   | Price the broker locked the order at, in wei |
+//! | `estimated_reward`     | utf8?   | Expected payment recorded in dry-run mode |
+//! | `estimated_gas_cost`   | utf8?   | Estimated gas cost recorded in dry-run mode, in wei |
+//! | `fulfill_gas_estimate` | uint64? | Configured `fulfill_gas_estimate` when the order was fulfilled |
+//! | `fulfill_gas_used`     | uint64? | Share of the gas used by the fulfillment transaction |
+//! | `submitted_block`      | uint64? | Block the request was submitted on-chain at |
+//! | `locker`               | utf8?   | Prover that locked the request |
+//! | `locked_block`         | uint64? | Block the request was locked at |
+//! | `fulfiller`            | utf8?   | Prover that fulfilled the request |
+//! | `fulfilled_block`      | uint64? | Block the request was fulfilled at |
+//! | `lock_race`            | utf8?   | `won`, `lost` or `unlocked`, if the request was indexed |
+//! | `updated_at`           | int64   | Last update of the order, UNIX timestamp |
+//! | `label`                | utf8    | `fulfilled`, `failed`, `lock_lost` or `skipped` |
+
+use std::{fs::File, path::Path, sync::Arc};
+
+use alloy::primitives::Address;
+use anyhow::{Context, Result};
+use arrow::{
+    array::{ArrayRef, Int64Array, StringArray, UInt64Array},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    record_batch::RecordBatch,
+};
+use parquet::{
+    arrow::ArrowWriter,
+    file::{metadata::KeyValue, properties::WriterProperties},
+};
+
+use crate::{
+    db::DbObj, indexer::MarketRequest, DryRunRecord, FulfillmentType, Order, OrderStatus,
+    PricingDecision, ShadowPricingRecord, SkipReason,
+};
+
+/// Parquet metadata key holding the schema version.
+pub(crate) const SCHEMA_VERSION_KEY: &str = "boundless.dataset.schema_version";
+
+/// Version of the dataset schema, bumped whenever a column is removed or changes meaning.
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+
+/// Number of orders read from the DB and written to the file at once.
+const PAGE_SIZE: u32 = 1_000;
+
+/// Outcome used as the training label of an order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Label {
+    Fulfilled,
+    Failed,
+    /// The broker failed to lock the order as another prover locked the request first
+    LockLost,
+    Skipped,
+}
+
+impl Label {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Label::Fulfilled => "fulfilled",
+            Label::Failed => "failed",
+            Label::LockLost => "lock_lost",
+            Label::Skipped => "skipped",
+        }
+    }
+}
+
+/// Whether the broker won the race to lock a request it saw on-chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LockRace {
+    Won,
+    Lost,
+    Unlocked,
+}
+
+impl LockRace {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LockRace::Won => "won",
+            LockRace::Lost => "lost",
+            LockRace::Unlocked => "unlocked",
+        }
+    }
+}
+
+/// Row of the dataset, see the module docs for the meaning of each column.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DatasetRow {
+    pub(crate) order_id: String,
+    pub(crate) request_id: String,
+    pub(crate) client: String,
+    pub(crate) chain_id: u64,
+    pub(crate) fulfillment_type: &'static str,
+    pub(crate) decision: &'static str,
+    pub(crate) shadow_decision: Option<&'static str>,
+    pub(crate) dry_run_decision: Option<&'static str>,
+    pub(crate) skip_reason: Option<&'static str>,
+    pub(crate) min_price: String,
+    pub(crate) max_price: String,
+    pub(crate) lock_stake: String,
+    pub(crate) bidding_start: u64,
+    pub(crate) ramp_up_period: u64,
+    pub(crate) lock_timeout: u64,
+    pub(crate) timeout: u64,
+    pub(crate) total_cycles: Option<u64>,
+    pub(crate) lock_price: Option<String>,
+    pub(crate) estimated_reward: Option<String>,
+    pub(crate) estimated_gas_cost: Option<String>,
+    pub(crate) fulfill_gas_estimate: Option<u64>,
+    pub(crate) fulfill_gas_used: Option<u64>,
+    pub(crate) submitted_block: Option<u64>,
+    pub(crate) locker: Option<String>,
+    pub(crate) locked_block: Option<u64>,
+    pub(crate) fulfiller: Option<String>,
+    pub(crate) fulfilled_block: Option<u64>,
+    pub(crate) lock_race: Option<LockRace>,
+    pub(crate) updated_at: i64,
+    pub(crate) label: Label,
+}
+
+/// Records joined with a closed order to build its row.
+pub(crate) struct OrderRecords {
+    pub(crate) shadow: Option<ShadowPricingRecord>,
+    pub(crate) dry_run: Option<DryRunRecord>,
+    /// Fulfillment gas estimate and gas used
+    pub(crate) fulfillment_gas: Option<(u64, u64)>,
+    pub(crate) market_request: Option<MarketRequest>,
+}
+
+fn decision_name(decision: &PricingDecision) -> &'static str {
+    match decision {
+        PricingDecision::Lock { .. } => "lock",
+        PricingDecision::ProveAfterLockExpire => "prove_after_lock_expire",
+        PricingDecision::Skip => "skip",
+    }
+}
+
+impl DatasetRow {
+    /// Build the row of a closed order, returning `None` for orders that are still in progress.
+    pub(crate) fn new(order: &Order, records: OrderRecords, prover: Address) -> Option<Self> {
+        let (fulfillment_type, decision) = match order.fulfillment_type {
+            FulfillmentType::LockAndFulfill => ("lock_and_fulfill", "lock"),
+            FulfillmentType::FulfillAfterLockExpire => {
+                ("fulfill_after_lock_expire", "prove_after_lock_expire")
+            }
+            FulfillmentType::FulfillWithoutLocking => {
+                ("fulfill_without_locking", "prove_after_lock_expire")
+            }
+        };
+        let lock_race = records.market_request.as_ref().map(|request| match request.locker {
+            Some(locker) if locker == prover => LockRace::Won,
+            Some(_) => LockRace::Lost,
+            None => LockRace::Unlocked,
+        });
+        let (label, decision) = match order.status {
+            OrderStatus::Done => (Label::Fulfilled, decision),
+            OrderStatus::Failed
+                if order.fulfillment_type == FulfillmentType::LockAndFulfill
+                    && lock_race == Some(LockRace::Lost) =>
+            {
+                (Label::LockLost, decision)
+            }
+            OrderStatus::Failed => (Label::Failed, decision),
+            OrderStatus::Skipped => (Label::Skipped, "skip"),
+            _ => return None,
+        };
+        let offer = &order.request.offer;
+        let market_request = records.market_request.as_ref();
+        Some(Self {
+            order_id: order.id(),
+            request_id: format!("0x{:x}", order.request.id),
+            client: order.request.client_address().to_string(),
+            chain_id: order.chain_id,
+            fulfillment_type,
+            decision,
+            shadow_decision: records.shadow.as_ref().map(|record| decision_name(&record.shadow)),
+            dry_run_decision: records
+                .dry_run
+                .as_ref()
+                .map(|record| decision_name(&record.decision)),
+            skip_reason: order.skip_reason.as_ref().map(SkipReason::as_str),
+            min_price: offer.minPrice.to_string(),
+            max_price: offer.maxPrice.to_string(),
+            lock_stake: offer.lockStake.to_string(),
+            bidding_start: offer.biddingStart,
+            ramp_up_period: offer.rampUpPeriod as u64,
+            lock_timeout: offer.lockTimeout as u64,
+            timeout: offer.timeout as u64,
+            total_cycles: order.total_cycles,
+            lock_price: order.lock_price.map(|price| price.to_string()),
+            estimated_reward: records.dry_run.as_ref().map(|record| record.reward.to_string()),
+            estimated_gas_cost: records.dry_run.as_ref().map(|record| record.gas_cost.to_string()),
+            fulfill_gas_estimate: records.fulfillment_gas.map(|(estimate, _)| estimate),
+            fulfill_gas_used: records.fulfillment_gas.map(|(_, used)| used),
+            submitted_block: market_request.and_then(|request| request.submitted_block),
+            locker: market_request.and_then(|request| request.locker).map(|a| a.to_string()),
+            locked_block: market_request.and_then(|request| request.locked_block),
+            fulfiller: market_request.and_then(|request| request.fulfiller).map(|a| a.to_string()),
+            fulfilled_block: market_request.and_then(|request| request.fulfilled_block),
+            lock_race,
+            updated_at: order.updated_at.timestamp(),
+            label,
+        })
+    }
+}
+
+/// Arrow schema of the dataset, see the module docs.
+pub(crate) fn schema() -> SchemaRef {
+    let field = |name, data_type, nullable| Field::new(name, data_type, nullable);
+    Arc::new(Schema::new(vec![
+        field("order_id", DataType::Utf8, false),
+        field("request_id", DataType::Utf8, false),
+        field("client", DataType::Utf8, false),
+        field("chain_id", DataType::UInt64, false),
+        field("fulfillment_type", DataType::Utf8, false),
+        field("decision", DataType::Utf8, false),
+        field("shadow_decision", DataType::Utf8, true),
+        field("dry_run_decision", DataType::Utf8, true),
+        field("skip_reason", DataType::Utf8, true),
+        field("min_price", DataType::Utf8, false),
+        field("max_price", DataType::Utf8, false),
+        field("lock_stake", DataType::Utf8, false),
+        field("bidding_start", DataType::UInt64, false),
+        field("ramp_up_period", DataType::UInt64, false),
+        field("lock_timeout", DataType::UInt64, false),
+        field("timeout", DataType::UInt64, false),
+        field("total_cycles", DataType::UInt64, true),
+        field("lock_price", DataType::Utf8, true),
+        field("estimated_reward", DataType::Utf8, true),
+        field("estimated_gas_cost", DataType::Utf8, true),
+        field("fulfill_gas_estimate", DataType::UInt64, true),
+        field("fulfill_gas_used", DataType::UInt64, true),
+        field("submitted_block", DataType::UInt64, true),
+        field("locker", DataType::Utf8, true),
+        field("locked_block", DataType::UInt64, true),
+        field("fulfiller", DataType::Utf8, true),
+        field("fulfilled_block", DataType::UInt64, true),
+        field("lock_race", DataType::Utf8, true),
+        field("updated_at", DataType::Int64, false),
+        field("label", DataType::Utf8, false),
+    ]))
+}
+
+/// Convert rows into a record batch of the dataset [schema].
+pub(crate) fn to_record_batch(rows: &[DatasetRow]) -> Result<RecordBatch> {
+    fn strings<'a>(values: impl Iterator<Item = Option<&'a str>>) -> ArrayRef {
+        Arc::new(StringArray::from_iter(values))
+    }
+    fn numbers(values: impl Iterator<Item = Option<u64>>) -> ArrayRef {
+        Arc::new(UInt64Array::from_iter(values))
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        strings(rows.iter().map(|r| Some(r.order_id.as_str()))),
+        strings(rows.iter().map(|r| Some(r.request_id.as_str()))),
+        strings(rows.iter().map(|r| Some(r.client.as_str()))),
+        numbers(rows.iter().map(|r| Some(r.chain_id))),
+        strings(rows.iter().map(|r| Some(r.fulfillment_type))),
+        strings(rows.iter().map(|r| Some(r.decision))),
+        strings(rows.iter().map(|r| r.shadow_decision)),
+        strings(rows.iter().map(|r| r.dry_run_decision)),
+        strings(rows.iter().map(|r| r.skip_reason)),
+        strings(rows.iter().map(|r| Some(r.min_price.as_str()))),
+        strings(rows.iter().map(|r| Some(r.max_price.as_str()))),
+        strings(rows.iter().map(|r| Some(r.lock_stake.as_str()))),
+        numbers(rows.iter().map(|r| Some(r.bidding_start))),
+        numbers(rows.iter().map(|r| Some(r.ramp_up_period))),
+        numbers(rows.iter().map(|r| Some(r.lock_timeout))),
+        numbers(rows.iter().map(|r| Some(r.timeout))),
+        numbers(rows.iter().map(|r| r.total_cycles)),
+        strings(rows.iter().map(|r| r.lock_price.as_deref())),
+        strings(rows.iter().map(|r| r.estimated_reward.as_deref())),
+        strings(rows.iter().map(|r| r.estimated_gas_cost.as_deref())),
+        numbers(rows.iter().map(|r| r.fulfill_gas_estimate)),
+        numbers(rows.iter().map(|r| r.fulfill_gas_used)),
+        numbers(rows.iter().map(|r| r.submitted_block)),
+        strings(rows.iter().map(|r| r.locker.as_deref())),
+        numbers(rows.iter().map(|r| r.locked_block)),
+        strings(rows.iter().map(|r| r.fulfiller.as_deref())),
+        numbers(rows.iter().map(|r| r.fulfilled_block)),
+        strings(rows.iter().map(|r| r.lock_race.as_ref().map(LockRace::as_str))),
+        Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.updated_at))),
+        strings(rows.iter().map(|r| Some(r.label.as_str()))),
+    ];
+    RecordBatch::try_new(schema(), columns).context("Failed to build record batch")
+}
+
+async fn order_records(db: &DbObj, order: &Order) -> Result<OrderRecords> {
+    let order_id = order.id();
+    Ok(OrderRecords {
+        shadow: db.get_shadow_pricing_record(&order_id).await?,
+        dry_run: db.get_dry_run_record(&order_id).await?,
+        fulfillment_gas: db.get_fulfillment_gas(&order_id).await?,
+        market_request: db.get_market_request(order.request.id).await?,
+    })
+}
+
+/// Write the dataset of orders closed at or after `updated_since` to a Parquet file, returning the
+/// number of rows written.
+///
+/// `prover` is the address the broker locks orders with, used to tell lock races won and lost.
+pub(crate) async fn export_dataset(
+    db: &DbObj,
+    prover: Address,
+    updated_since: i64,
+    path: &Path,
+) -> Result<usize> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let props = WriterProperties::builder()
+        .set_key_value_metadata(Some(vec![KeyValue::new(
+            SCHEMA_VERSION_KEY.to_string(),
+            SCHEMA_VERSION.to_string(),
+        )]))
+        .build();
+    let mut writer = ArrowWriter::try_new(file, schema(), Some(props))
+        .context("Failed to create Parquet writer")?;
+
+    let mut offset = 0;
+    let mut num_rows = 0;
+    loop {
+        let orders = db
+            .get_closed_orders(updated_since, offset, PAGE_SIZE)
+            .await
+            .context("Failed to get closed orders")?;
+        let mut rows = Vec::with_capacity(orders.len());
+        for order in &orders {
+            let records = order_records(db, order)
+                .await
+                .with_context(|| format!("Failed to get records of order {}", order.id()))?;
+            rows.extend(DatasetRow::new(order, records, prover));
+        }
+        if !rows.is_empty() {
+            writer.write(&to_record_batch(&rows)?).context("Failed to write rows")?;
+            num_rows += rows.len();
+        }
+        if orders.len() < PAGE_SIZE as usize {
+            break;
+        }
+        offset += PAGE_SIZE;
+    }
+    writer.close().context("Failed to finish Parquet file")?;
+    Ok(num_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::SqliteDb,
+        indexer::{MarketEvent, MarketIndexer},
+        OrderRequest,
+    };
+    use alloy::primitives::{Bytes, U256};
+    use arrow::array::AsArray;
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput, RequestInputType,
+        Requirements,
+    };
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use risc0_zkvm::sha::Digest;
+    use sqlx::SqlitePool;
+
+    fn order_request(idx: u32) -> OrderRequest {
+        let request = ProofRequest::new(
+            RequestId::new(Address::repeat_byte(1), idx),
+            Requirements::new(
+                Digest::ZERO,
+                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+            ),
+            "http://risczero.com",
+            RequestInput { inputType: RequestInputType::Inline, data: "".into() },
+            Offer {
+                minPrice: U256::from(1),
+                maxPrice: U256::from(2),
+                biddingStart: 100,
+                timeout: 100,
+                lockTimeout: 50,
+                rampUpPeriod: 1,
+                lockStake: U256::from(3),
+            },
+        );
+        OrderRequest::new(request, Bytes::new(), FulfillmentType::LockAndFulfill, Address::ZERO, 1)
+    }
+
+    #[sqlx::test]
+    async fn exports_labeled_orders(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let indexer = MarketIndexer::new(db.clone());
+        let prover = Address::repeat_byte(2);
+        let other_prover = Address::repeat_byte(3);
+
+        let mut fulfilled = order_request(1).to_proving_order(U256::from(2));
+        fulfilled.status = OrderStatus::Done;
+        fulfilled.total_cycles = Some(1_000);
+        db.add_order(&fulfilled).await.unwrap();
+        db.set_fulfillment_gas(&[&fulfilled.id()], 300_000, 250_000).await.unwrap();
+        let locked = MarketEvent::Locked {
+            prover,
+            request: Box::new(fulfilled.request.clone()),
+            client_signature: Bytes::new(),
+        };
+        indexer.index(fulfilled.request.id, 10, &locked).await.unwrap();
+
+        let mut lost = order_request(2).to_proving_order(U256::from(2));
+        lost.status = OrderStatus::Failed;
+        db.add_order(&lost).await.unwrap();
+        let locked = MarketEvent::Locked {
+            prover: other_prover,
+            request: Box::new(lost.request.clone()),
+            client_signature: Bytes::new(),
+        };
+        indexer.index(lost.request.id, 11, &locked).await.unwrap();
+
+        db.insert_skipped_request(&order_request(3), SkipReason::PriceTooLow).await.unwrap();
+        // Still in progress, not exported
+        db.insert_accepted_request(&order_request(4), U256::from(2)).await.unwrap();
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let num_rows = export_dataset(&db, prover, 0, file.path()).await.unwrap();
+        assert_eq!(num_rows, 3);
+
+        let reader =
+            ParquetRecordBatchReaderBuilder::try_new(File::open(file.path()).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata().key_value_metadata().unwrap();
+        assert!(metadata.iter().any(|kv| kv.key == SCHEMA_VERSION_KEY
+            && kv.value.as_deref() == Some(SCHEMA_VERSION.to_string().as_str())));
+        let batches: Vec<RecordBatch> = reader.build().unwrap().map(Result::unwrap).collect();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+
+        let column = |name| batch.column_by_name(name).unwrap().as_string::<i32>();
+        let mut rows: Vec<_> = (0..batch.num_rows())
+            .map(|i| {
+                let cell = |name| {
+                    let column = column(name);
+                    column.is_valid(i).then(|| column.value(i).to_string())
+                };
+                (cell("request_id"), cell("label"), cell("lock_race"), cell("skip_reason"))
+            })
+            .collect();
+        rows.sort();
+        let request_id = |idx| Some(format!("0x{:x}", order_request(idx).request.id));
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(
+            rows,
+            vec![
+                (request_id(1), some("fulfilled"), some("won"), None),
+                (request_id(2), some("lock_lost"), some("lost"), None),
+                (request_id(3), some("skipped"), None, some("price_too_low")),
+            ]
+        );
+        let gas_used = batch.column_by_name("fulfill_gas_used").unwrap();
+        assert_eq!(gas_used.as_primitive::<arrow::datatypes::UInt64Type>().value(0), 250_000);
+        assert_eq!(gas_used.null_count(), 2);
+    }
+}
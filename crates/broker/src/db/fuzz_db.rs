@@ -108,6 +108,7 @@ fn generate_test_order(request_id: u32) -> Order {
         ),
         image_id: None,
         input_id: None,
+        assumption_ids: None,
         proof_id: Some(format!("proof_{request_id}")),
         compressed_proof_id: Some(format!("compressed_proof_{request_id}")),
         expire_timestamp: Some(1000),
@@ -115,6 +116,8 @@ fn generate_test_order(request_id: u32) -> Order {
         lock_price: Some(U256::from(10)),
         fulfillment_type: FulfillmentType::LockAndFulfill,
         error_msg: None,
+        skip_reason: None,
+        pricing_checkpoint: None,
         boundless_market_address: Address::ZERO,
         chain_id: 1,
         total_cycles: None,
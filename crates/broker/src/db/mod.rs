@@ -12,21 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{default::Default, str::FromStr, sync::Arc};
+use std::{default::Default, path::Path, str::FromStr, sync::Arc};
 
-use alloy::primitives::{ruint::ParseError as RuintParseErr, Bytes, B256, U256};
+use alloy::primitives::{ruint::ParseError as RuintParseErr, Address, Bytes, B256, U256};
 use async_trait::async_trait;
 use chrono::Utc;
 use sqlx::{
     sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions},
-    Row,
+    QueryBuilder, Row, Sqlite,
 };
 use thiserror::Error;
 
 use crate::{
     errors::{impl_coded_debug, CodedError},
-    AggregationState, Batch, BatchStatus, FulfillmentType, Order, OrderRequest, OrderStatus,
-    ProofRequest,
+    indexer::{LockerStats, MarketEventRecord, MarketRequest},
+    reputation::{ClientEvent, ClientStats},
+    AggregationState, Batch, BatchStatus, DryRunRecord, FulfillmentIntent, FulfillmentType, Order,
+    OrderGasRecord, OrderProvenanceRecord, OrderRequest, OrderStatus, PricingAuditRecord,
+    ProofRequest, ShadowPricingRecord, SkipReason, StateManifest,
 };
 use tracing::instrument;
 
@@ -78,8 +81,8 @@ pub enum DbError {
     #[error("{code} Invalid max connection env var value", code = self.code())]
     MaxConnEnvVar(#[from] std::num::ParseIntError),
 
-    #[error("{code} Duplicate order id accepted {0}", code = self.code())]
-    DuplicateOrderId(String),
+    #[error("{code} State import failed: {0:#}", code = self.code())]
+    StateImport(anyhow::Error),
 }
 
 impl_coded_debug!(DbError);
@@ -122,9 +125,77 @@ pub struct AggregationOrder {
     pub fee: U256,
 }
 
+/// Filters and page of an order query, see [BrokerDb::query_orders].
+#[derive(Clone, Debug, Default)]
+pub struct OrderQuery {
+    /// Statuses of the orders, any status if empty
+    pub statuses: Vec<OrderStatus>,
+    pub fulfillment_type: Option<FulfillmentType>,
+    /// Client address of the requests
+    pub client: Option<Address>,
+    /// UNIX timestamp the orders were last updated at or after
+    pub updated_since: Option<i64>,
+    /// UNIX timestamp the orders were last updated before
+    pub updated_before: Option<i64>,
+    /// Number of matching orders to skip
+    pub offset: u32,
+    /// Max number of orders to return
+    pub limit: u32,
+}
+
+/// Statuses of the orders committed to and not fulfilled yet, see [BrokerDb::get_committed_orders].
+pub const COMMITTED_ORDER_STATUSES: [OrderStatus; 6] = [
+    OrderStatus::PendingProving,
+    OrderStatus::Proving,
+    OrderStatus::PendingAgg,
+    OrderStatus::Aggregating,
+    OrderStatus::SkipAggregation,
+    OrderStatus::PendingSubmission,
+];
+
+/// SQL expression of the client address of an order, in lowercase hex without `0x` prefix.
+///
+/// The client address is stored in bits 32 to 191 of the request ID, which is serialized as hex
+/// without leading zeros. Must match the expression of the `orders_client_updated_at` index.
+const ORDER_CLIENT_SQL: &str = "substr(substr('0000000000000000000000000000000000000000000000000000000000000000' || substr(data->>'$.request.id', 3), -48), 1, 40)";
+
+/// Append the `WHERE` clause of the filters of the query.
+fn push_order_filters(builder: &mut QueryBuilder<'_, Sqlite>, query: &OrderQuery) {
+    builder.push(" WHERE TRUE");
+    if !query.statuses.is_empty() {
+        builder.push(" AND data->>'status' IN (");
+        let mut statuses = builder.separated(", ");
+        for status in &query.statuses {
+            statuses.push_bind(*status);
+        }
+        statuses.push_unseparated(")");
+    }
+    if let Some(fulfillment_type) = query.fulfillment_type {
+        builder.push(" AND data->>'fulfillment_type' = ").push_bind(fulfillment_type);
+    }
+    if let Some(client) = query.client {
+        builder.push(format!(" AND {ORDER_CLIENT_SQL} = ")).push_bind(hex::encode(client));
+    }
+    if let Some(updated_since) = query.updated_since {
+        builder.push(" AND data->>'updated_at' >= ").push_bind(updated_since);
+    }
+    if let Some(updated_before) = query.updated_before {
+        builder.push(" AND data->>'updated_at' < ").push_bind(updated_before);
+    }
+}
+
 #[async_trait]
 pub trait BrokerDb {
-    async fn insert_skipped_request(&self, order_request: &OrderRequest) -> Result<(), DbError>;
+    async fn insert_skipped_request(
+        &self,
+        order_request: &OrderRequest,
+        reason: SkipReason,
+    ) -> Result<(), DbError>;
+    /// Insert skipped orders in a single transaction, ignoring orders already in the DB.
+    async fn insert_skipped_requests(
+        &self,
+        skipped: &[(OrderRequest, SkipReason)],
+    ) -> Result<(), DbError>;
     async fn insert_accepted_request(
         &self,
         order_request: &OrderRequest,
@@ -146,7 +217,50 @@ pub trait BrokerDb {
         &self,
         grace_period_secs: i64,
     ) -> Result<Vec<Order>, DbError>;
+    /// Returns up to `limit` closed (Done, Failed or Skipped) orders last updated before the
+    /// given UNIX timestamp, oldest first.
+    async fn get_archivable_orders(
+        &self,
+        updated_before: i64,
+        limit: u32,
+    ) -> Result<Vec<Order>, DbError>;
+    /// Returns up to `limit` closed (Done, Failed or Skipped) orders last updated at or after the
+    /// given UNIX timestamp, oldest first, skipping the first `offset` of them.
+    async fn get_closed_orders(
+        &self,
+        updated_since: i64,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<Order>, DbError>;
+    /// Deletes the given orders, returning the number of rows removed.
+    async fn delete_orders(&self, ids: &[&str]) -> Result<u64, DbError>;
+    /// Returns the number of skipped orders per skip reason, most frequent first, optionally
+    /// limited to orders skipped at or after the given UNIX timestamp.
+    ///
+    /// Orders skipped before skip reasons were recorded are not counted.
+    async fn get_skip_reason_counts(
+        &self,
+        updated_since: Option<i64>,
+    ) -> Result<Vec<(SkipReason, u64)>, DbError>;
+    /// Returns up to `limit` orders skipped for the given reason, most recent first.
+    async fn get_skipped_orders(
+        &self,
+        reason: SkipReason,
+        limit: u32,
+    ) -> Result<Vec<Order>, DbError>;
+    /// Returns up to `limit` orders with the given status, most recently updated first.
+    async fn get_orders_by_status(
+        &self,
+        status: OrderStatus,
+        limit: u32,
+    ) -> Result<Vec<Order>, DbError>;
+    /// Returns the page of orders matching the filters of the query, most recently updated first.
+    async fn query_orders(&self, query: &OrderQuery) -> Result<Vec<Order>, DbError>;
+    /// Returns the number of orders matching the filters of the query, regardless of its page.
+    async fn count_orders(&self, query: &OrderQuery) -> Result<u64, DbError>;
     async fn get_proving_order(&self) -> Result<Option<Order>, DbError>;
+    /// Set the given order from PendingProving to Proving, returning it if it was pending.
+    async fn claim_proving_order(&self, id: &str) -> Result<Option<Order>, DbError>;
     async fn get_active_proofs(&self) -> Result<Vec<Order>, DbError>;
     async fn set_order_proof_id(&self, order_id: &str, proof_id: &str) -> Result<(), DbError>;
     async fn set_order_compressed_proof_id(
@@ -169,6 +283,8 @@ pub trait BrokerDb {
     ) -> Result<(), DbError>;
     // Checks the fulfillment table for the given request_id
     async fn is_request_fulfilled(&self, request_id: U256) -> Result<bool, DbError>;
+    /// Returns the block the given request was fulfilled in, if recorded.
+    async fn get_request_fulfilled_block(&self, request_id: U256) -> Result<Option<u64>, DbError>;
     async fn set_request_locked(
         &self,
         request_id: U256,
@@ -179,6 +295,129 @@ pub trait BrokerDb {
     async fn is_request_locked(&self, request_id: U256) -> Result<bool, DbError>;
     // Checks the locked table for the given request_id
     async fn get_request_locked(&self, request_id: U256) -> Result<Option<(String, u64)>, DbError>;
+    /// Remove the fulfillment of the given request, e.g. after it was reorged out.
+    async fn clear_request_fulfilled(&self, request_id: U256) -> Result<(), DbError>;
+    /// Remove the lock of the given request, e.g. after it was reorged out.
+    async fn clear_request_locked(&self, request_id: U256) -> Result<(), DbError>;
+    /// Record the live and shadow pricing decisions for an order, replacing any previous record.
+    async fn set_shadow_pricing_record(&self, record: &ShadowPricingRecord) -> Result<(), DbError>;
+    async fn get_shadow_pricing_record(
+        &self,
+        order_id: &str,
+    ) -> Result<Option<ShadowPricingRecord>, DbError>;
+    /// Record a pricing decision that was not acted on in dry-run mode, replacing any previous
+    /// record for the order.
+    async fn set_dry_run_record(&self, record: &DryRunRecord) -> Result<(), DbError>;
+    async fn get_dry_run_record(&self, order_id: &str) -> Result<Option<DryRunRecord>, DbError>;
+    /// Record the configured fulfillment gas estimate of the orders along with their share of the
+    /// gas used by the fulfillment transaction.
+    async fn set_fulfillment_gas(
+        &self,
+        order_ids: &[&str],
+        gas_estimate: u64,
+        gas_used: u64,
+    ) -> Result<(), DbError>;
+    /// Returns the gas estimate and gas used recorded for the order.
+    async fn get_fulfillment_gas(&self, order_id: &str) -> Result<Option<(u64, u64)>, DbError>;
+    /// Append records of the gas spent by orders on a transaction to their gas ledgers.
+    async fn add_order_gas_records(&self, records: &[OrderGasRecord]) -> Result<(), DbError>;
+    /// Returns the gas ledger of the order, oldest first.
+    async fn get_order_gas_records(&self, order_id: &str) -> Result<Vec<OrderGasRecord>, DbError>;
+    /// Returns the most recent gas records of all orders, newest first.
+    async fn get_recent_gas_records(&self, limit: u32) -> Result<Vec<OrderGasRecord>, DbError>;
+    /// Returns the recorded intents to fulfill the given requests.
+    async fn get_fulfillment_intents(
+        &self,
+        request_ids: &[U256],
+    ) -> Result<Vec<FulfillmentIntent>, DbError>;
+    /// Record the intents to fulfill their requests, all or none.
+    ///
+    /// Intents recorded before `stale_before` (in seconds since the epoch) are replaced. Returns
+    /// false, recording none, if any request has a more recent intent.
+    async fn claim_fulfillment_intents(
+        &self,
+        intents: &[FulfillmentIntent],
+        stale_before: u64,
+    ) -> Result<bool, DbError>;
+    /// Record the hash of the transaction that fulfilled the requests in their intents.
+    async fn set_fulfillment_intents_tx(
+        &self,
+        request_ids: &[U256],
+        tx_hash: B256,
+    ) -> Result<(), DbError>;
+    /// Remove the intents to fulfill the requests, allowing them to be fulfilled again.
+    async fn release_fulfillment_intents(&self, request_ids: &[U256]) -> Result<(), DbError>;
+    /// Add a sample of the cycles used by an execution of the image, keeping only the latest
+    /// `max_samples` samples of the image.
+    async fn add_image_cycles(
+        &self,
+        image_id: &str,
+        total_cycles: u64,
+        max_samples: u32,
+    ) -> Result<(), DbError>;
+    /// Returns the latest cycle samples recorded for the image.
+    async fn get_image_cycles(&self, image_id: &str) -> Result<Vec<u64>, DbError>;
+    /// Append a record of the inputs and outcome of pricing an order to the audit log.
+    async fn add_pricing_audit_record(&self, record: &PricingAuditRecord) -> Result<(), DbError>;
+    /// Returns the audit log of every time an order of the request was priced, oldest first.
+    async fn get_pricing_audit_records(
+        &self,
+        request_id: U256,
+    ) -> Result<Vec<PricingAuditRecord>, DbError>;
+    /// Append a record of where and when an order was received.
+    async fn add_order_provenance_record(
+        &self,
+        record: &OrderProvenanceRecord,
+    ) -> Result<(), DbError>;
+    /// Returns the provenance of every order of the request received, oldest first.
+    async fn get_order_provenance_records(
+        &self,
+        request_id: U256,
+    ) -> Result<Vec<OrderProvenanceRecord>, DbError>;
+    /// Append an event to the market event log. Returns whether it was not in the log yet.
+    async fn add_market_event(&self, record: &MarketEventRecord) -> Result<bool, DbError>;
+    /// Returns the events logged on the chain from the given block on, in chain order.
+    async fn get_market_events(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+    ) -> Result<Vec<MarketEventRecord>, DbError>;
+    /// Returns the last block of the chain whose events were broadcast, if any.
+    async fn get_indexer_checkpoint(&self, chain_id: u64) -> Result<Option<u64>, DbError>;
+    /// Move the checkpoint of the chain forward to the given block, if it is not already past it.
+    async fn set_indexer_checkpoint(&self, chain_id: u64, block_number: u64)
+        -> Result<(), DbError>;
+    /// Remove the events logged on the chain from the given block on, returning them in chain
+    /// order, and move the checkpoint of the chain back to the block before, if it is past it.
+    async fn revert_market_events(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+    ) -> Result<Vec<MarketEventRecord>, DbError>;
+    /// Record that the request with the given signing hash was skipped for a permanent reason,
+    /// replacing any previous tombstone of the request. Expired tombstones are pruned.
+    async fn add_request_tombstone(
+        &self,
+        digest: B256,
+        reason: SkipReason,
+        expires_at: u64,
+    ) -> Result<(), DbError>;
+    /// Returns the skip reason of the tombstone of the request, if it has not expired.
+    async fn get_request_tombstone(&self, digest: B256) -> Result<Option<SkipReason>, DbError>;
+    /// Remove the tombstone of the request. Returns whether there was one.
+    async fn clear_request_tombstone(&self, digest: B256) -> Result<bool, DbError>;
+    /// Store the indexed view of a market request, replacing any previous view.
+    async fn set_market_request(&self, request: &MarketRequest) -> Result<(), DbError>;
+    async fn get_market_request(&self, request_id: U256) -> Result<Option<MarketRequest>, DbError>;
+    /// Returns the outcomes of the indexed market requests locked by the prover.
+    async fn get_locker_stats(&self, locker: Address) -> Result<LockerStats, DbError>;
+    /// Add an event to the statistics of the requestor.
+    ///
+    /// Orders being fulfilled or failing are recorded when their status is set.
+    async fn record_client_event(&self, client: Address, event: ClientEvent)
+        -> Result<(), DbError>;
+    /// Returns the statistics of the requestor, all zero if none were recorded.
+    async fn get_client_stats(&self, client: Address) -> Result<ClientStats, DbError>;
     /// Update a batch with the results of an aggregation step.
     ///
     /// Sets the aggreagtion state, and adds the given orders to the batch, updating the batch fees
@@ -191,6 +430,17 @@ pub trait BrokerDb {
         assessor_proof_id: Option<String>,
     ) -> Result<(), DbError>;
     async fn get_batch(&self, batch_id: usize) -> Result<Batch, DbError>;
+    /// Write a consistent snapshot of the whole DB, along with the manifest, to a new SQLite
+    /// file at `path`.
+    async fn export_state(&self, path: &Path, manifest: &StateManifest) -> Result<(), DbError>;
+    /// Copy a snapshot written by [BrokerDb::export_state] into the DB, which must not have any
+    /// orders or batches yet.
+    ///
+    /// If `reset_proofs` is set, committed orders are set back to pending proving and their
+    /// proof IDs cleared, and unsubmitted batches are failed, as the proofs are held by the
+    /// prover the snapshot was taken next to.
+    async fn import_state(&self, path: &Path, reset_proofs: bool)
+        -> Result<StateManifest, DbError>;
 
     #[cfg(test)]
     async fn add_order(&self, order: &Order) -> Result<(), DbError>;
@@ -236,6 +486,67 @@ impl SqliteDb {
         Ok(Self { pool })
     }
 
+    /// Replace the contents of the tables of the DB with those of the attached `snapshot` DB.
+    async fn copy_snapshot(
+        conn: &mut sqlx::SqliteConnection,
+        reset_proofs: bool,
+    ) -> Result<(), DbError> {
+        let tables: Vec<String> = sqlx::query_scalar(
+            r#"SELECT name FROM snapshot.sqlite_master
+               WHERE type = 'table'
+                 AND name NOT LIKE 'sqlite_%'
+                 AND name NOT IN ('_sqlx_migrations', 'state_manifest')"#,
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut txn = sqlx::Connection::begin(&mut *conn).await?;
+        for table in tables {
+            sqlx::query(&format!(r#"DELETE FROM main."{table}""#)).execute(&mut *txn).await?;
+            sqlx::query(&format!(r#"INSERT INTO main."{table}" SELECT * FROM snapshot."{table}""#))
+                .execute(&mut *txn)
+                .await?;
+        }
+
+        if reset_proofs {
+            sqlx::query(
+                r#"
+                UPDATE main.orders
+                SET data = json_set(data,
+                    '$.status', $1,
+                    '$.proof_id', NULL,
+                    '$.compressed_proof_id', NULL,
+                    '$.image_id', NULL,
+                    '$.input_id', NULL)
+                WHERE data->>'status' IN ($2, $3, $4, $5, $6)"#,
+            )
+            .bind(OrderStatus::PendingProving)
+            .bind(OrderStatus::Proving)
+            .bind(OrderStatus::PendingAgg)
+            .bind(OrderStatus::Aggregating)
+            .bind(OrderStatus::SkipAggregation)
+            .bind(OrderStatus::PendingSubmission)
+            .execute(&mut *txn)
+            .await?;
+
+            sqlx::query(
+                r#"
+                UPDATE main.batches
+                SET data = json_set(data, '$.status', $1, '$.error_msg', $2)
+                WHERE data->>'status' NOT IN ($3, $4)"#,
+            )
+            .bind(BatchStatus::Failed)
+            .bind("Proofs reset on state import")
+            .bind(BatchStatus::Submitted)
+            .bind(BatchStatus::Failed)
+            .execute(&mut *txn)
+            .await?;
+        }
+
+        txn.commit().await?;
+        Ok(())
+    }
+
     async fn new_batch(&self) -> Result<usize, DbError> {
         let batch = Batch { start_time: Utc::now(), ..Default::default() };
 
@@ -264,9 +575,27 @@ impl SqliteDb {
         Ok(())
     }
 
+    /// Record the outcome of an order in the statistics of its requestor.
+    ///
+    /// Failing to record the outcome does not fail the status update of the order.
+    async fn record_order_outcome(&self, id: &str, event: ClientEvent) {
+        let res = match self.get_order(id).await {
+            Ok(Some(order)) => {
+                self.record_client_event(order.request.client_address(), event).await
+            }
+            Ok(None) => Ok(()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = res {
+            tracing::warn!("Failed to record {event:?} of order {id} in client stats: {err}");
+        }
+    }
+
     /// Insert an accepted order, overwriting only if the existing order is skipped.
-    /// Returns true if inserted/updated, false if ignored due to existing non-skipped order.
-    async fn insert_accepted_order(&self, order: &Order) -> Result<(), DbError> {
+    ///
+    /// Idempotent: if the order was already accepted, e.g. due to a replayed event or a restart,
+    /// the existing order is left untouched and returned.
+    async fn insert_accepted_order(&self, order: Order) -> Result<Order, DbError> {
         let result = sqlx::query(
             r#"INSERT INTO orders (id, data) VALUES ($1, $2) 
                ON CONFLICT(id) DO UPDATE SET 
@@ -279,10 +608,11 @@ impl SqliteDb {
         .await?;
 
         if result.rows_affected() == 0 {
-            return Err(DbError::DuplicateOrderId(order.id()));
+            tracing::debug!("Order {} already accepted, keeping existing order", order.id());
+            return self.get_order(&order.id()).await?.ok_or(DbError::OrderNotFound(order.id()));
         }
 
-        Ok(())
+        Ok(order)
     }
 }
 
@@ -300,6 +630,55 @@ struct DbBatch {
     data: Batch,
 }
 
+#[derive(sqlx::FromRow)]
+struct DbShadowPricingRecord {
+    #[allow(dead_code)]
+    id: String,
+    #[sqlx(json)]
+    data: ShadowPricingRecord,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbDryRunRecord {
+    #[allow(dead_code)]
+    id: String,
+    #[sqlx(json)]
+    data: DryRunRecord,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbMarketRequest {
+    #[allow(dead_code)]
+    id: String,
+    #[sqlx(json)]
+    data: MarketRequest,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbClientStats {
+    preflights: i64,
+    guest_panics: i64,
+    journal_bytes: i64,
+    locks: i64,
+    payment_failures: i64,
+    fulfilled: i64,
+    failed: i64,
+}
+
+impl From<DbClientStats> for ClientStats {
+    fn from(row: DbClientStats) -> Self {
+        Self {
+            preflights: row.preflights as u64,
+            guest_panics: row.guest_panics as u64,
+            journal_bytes: row.journal_bytes as u64,
+            locks: row.locks as u64,
+            payment_failures: row.payment_failures as u64,
+            fulfilled: row.fulfilled as u64,
+            failed: row.failed as u64,
+        }
+    }
+}
+
 #[derive(sqlx::FromRow)]
 struct DbLockedRequest {
     #[allow(dead_code)]
@@ -317,8 +696,30 @@ impl BrokerDb for SqliteDb {
     }
 
     #[instrument(level = "trace", skip_all, fields(id = %format!("{}", order_request.id())))]
-    async fn insert_skipped_request(&self, order_request: &OrderRequest) -> Result<(), DbError> {
-        self.insert_order_ignore_duplicates(&order_request.to_skipped_order()).await
+    async fn insert_skipped_request(
+        &self,
+        order_request: &OrderRequest,
+        reason: SkipReason,
+    ) -> Result<(), DbError> {
+        self.insert_order_ignore_duplicates(&order_request.to_skipped_order(reason)).await
+    }
+
+    #[instrument(level = "trace", skip_all, fields(count = skipped.len()))]
+    async fn insert_skipped_requests(
+        &self,
+        skipped: &[(OrderRequest, SkipReason)],
+    ) -> Result<(), DbError> {
+        let mut txn = self.pool.begin().await?;
+        for (order_request, reason) in skipped {
+            let order = order_request.to_skipped_order(*reason);
+            sqlx::query("INSERT INTO orders (id, data) VALUES ($1, $2) ON CONFLICT(id) DO NOTHING")
+                .bind(order.id())
+                .bind(sqlx::types::Json(&order))
+                .execute(&mut *txn)
+                .await?;
+        }
+        txn.commit().await?;
+        Ok(())
     }
 
     #[instrument(level = "trace", skip_all, fields(id = %format!("{}", order_request.id())))]
@@ -327,9 +728,7 @@ impl BrokerDb for SqliteDb {
         order_request: &OrderRequest,
         lock_price: U256,
     ) -> Result<Order, DbError> {
-        let order = order_request.to_proving_order(lock_price);
-        self.insert_accepted_order(&order).await?;
-        Ok(order)
+        self.insert_accepted_order(order_request.to_proving_order(lock_price)).await
     }
 
     #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
@@ -411,6 +810,7 @@ impl BrokerDb for SqliteDb {
         if res.rows_affected() == 0 {
             return Err(DbError::OrderNotFound(id.to_string()));
         }
+        self.record_order_outcome(id, ClientEvent::Failed).await;
 
         Ok(())
     }
@@ -436,6 +836,7 @@ impl BrokerDb for SqliteDb {
         if res.rows_affected() == 0 {
             return Err(DbError::OrderNotFound(id.to_string()));
         }
+        self.record_order_outcome(id, ClientEvent::Fulfilled).await;
 
         Ok(())
     }
@@ -481,6 +882,166 @@ impl BrokerDb for SqliteDb {
         Ok(orders.into_iter().map(|db_order| db_order.data).collect())
     }
 
+    #[instrument(level = "trace", skip(self))]
+    async fn get_archivable_orders(
+        &self,
+        updated_before: i64,
+        limit: u32,
+    ) -> Result<Vec<Order>, DbError> {
+        let orders: Vec<DbOrder> = sqlx::query_as(
+            r#"
+            SELECT * FROM orders
+                WHERE data->>'status' IN ($1, $2, $3)
+                AND data->>'updated_at' < $4
+                ORDER BY data->>'updated_at' ASC
+                LIMIT $5"#,
+        )
+        .bind(OrderStatus::Done)
+        .bind(OrderStatus::Failed)
+        .bind(OrderStatus::Skipped)
+        .bind(updated_before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders.into_iter().map(|x| x.data).collect())
+    }
+
+    async fn get_closed_orders(
+        &self,
+        updated_since: i64,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<Order>, DbError> {
+        let orders: Vec<DbOrder> = sqlx::query_as(
+            r#"
+            SELECT * FROM orders
+                WHERE data->>'status' IN ($1, $2, $3)
+                AND data->>'updated_at' >= $4
+                ORDER BY data->>'updated_at' ASC, id ASC
+                LIMIT $5 OFFSET $6"#,
+        )
+        .bind(OrderStatus::Done)
+        .bind(OrderStatus::Failed)
+        .bind(OrderStatus::Skipped)
+        .bind(updated_since)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders.into_iter().map(|x| x.data).collect())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(count = ids.len()))]
+    async fn delete_orders(&self, ids: &[&str]) -> Result<u64, DbError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let placeholders = std::iter::repeat_n("?", ids.len()).collect::<Vec<_>>().join(", ");
+        let query = format!("DELETE FROM orders WHERE id IN ({placeholders})");
+
+        let mut q = sqlx::query(&query);
+        for id in ids {
+            q = q.bind(id);
+        }
+        let res = q.execute(&self.pool).await?;
+        Ok(res.rows_affected())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_skip_reason_counts(
+        &self,
+        updated_since: Option<i64>,
+    ) -> Result<Vec<(SkipReason, u64)>, DbError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT data->>'skip_reason' AS reason, COUNT(*) AS count FROM orders
+                WHERE data->>'status' = $1
+                AND data->>'skip_reason' IS NOT NULL
+                AND data->>'updated_at' >= $2
+                GROUP BY reason
+                ORDER BY count DESC, reason ASC"#,
+        )
+        .bind(OrderStatus::Skipped)
+        .bind(updated_since.unwrap_or(0))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| -> Result<_, DbError> {
+                let reason: String = row.try_get("reason")?;
+                let count: i64 = row.try_get("count")?;
+                let reason = serde_json::from_value(serde_json::Value::String(reason))?;
+                Ok((reason, count as u64))
+            })
+            .collect()
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_skipped_orders(
+        &self,
+        reason: SkipReason,
+        limit: u32,
+    ) -> Result<Vec<Order>, DbError> {
+        let orders: Vec<DbOrder> = sqlx::query_as(
+            r#"
+            SELECT * FROM orders
+                WHERE data->>'status' = $1
+                AND data->>'skip_reason' = $2
+                ORDER BY data->>'updated_at' DESC
+                LIMIT $3"#,
+        )
+        .bind(OrderStatus::Skipped)
+        .bind(reason.as_str())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders.into_iter().map(|x| x.data).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_orders_by_status(
+        &self,
+        status: OrderStatus,
+        limit: u32,
+    ) -> Result<Vec<Order>, DbError> {
+        let orders: Vec<DbOrder> = sqlx::query_as(
+            r#"
+            SELECT * FROM orders
+                WHERE data->>'status' = $1
+                ORDER BY data->>'updated_at' DESC
+                LIMIT $2"#,
+        )
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders.into_iter().map(|x| x.data).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn query_orders(&self, query: &OrderQuery) -> Result<Vec<Order>, DbError> {
+        let mut builder = QueryBuilder::new("SELECT * FROM orders");
+        push_order_filters(&mut builder, query);
+        builder.push(" ORDER BY data->>'updated_at' DESC, id DESC LIMIT ").push_bind(query.limit);
+        builder.push(" OFFSET ").push_bind(query.offset);
+        let orders: Vec<DbOrder> = builder.build_query_as().fetch_all(&self.pool).await?;
+
+        Ok(orders.into_iter().map(|x| x.data).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn count_orders(&self, query: &OrderQuery) -> Result<u64, DbError> {
+        let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM orders");
+        push_order_filters(&mut builder, query);
+        let count: i64 = builder.build_query_scalar().fetch_one(&self.pool).await?;
+
+        Ok(count as u64)
+    }
+
     #[instrument(level = "trace", skip_all)]
     async fn get_proving_order(&self) -> Result<Option<Order>, DbError> {
         let elm: Option<DbOrder> = sqlx::query_as(
@@ -508,6 +1069,26 @@ impl BrokerDb for SqliteDb {
         Ok(Some(order.data))
     }
 
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
+    async fn claim_proving_order(&self, id: &str) -> Result<Option<Order>, DbError> {
+        let elm: Option<DbOrder> = sqlx::query_as(
+            r#"
+            UPDATE orders
+            SET data = json_set(json_set(data, '$.status', $1), '$.updated_at', $2)
+            WHERE id = $3 AND data->>'status' = $4
+            RETURNING *
+            "#,
+        )
+        .bind(OrderStatus::Proving)
+        .bind(Utc::now().timestamp())
+        .bind(id)
+        .bind(OrderStatus::PendingProving)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(elm.map(|order| order.data))
+    }
+
     #[instrument(level = "trace", skip_all)]
     async fn get_active_proofs(&self) -> Result<Vec<Order>, DbError> {
         let orders: Vec<DbOrder> =
@@ -842,22 +1423,28 @@ impl BrokerDb for SqliteDb {
         let db_fees = U256::from_str(&db_fees)?;
         let new_fees = orders.iter().fold(db_fees, |sum, order| sum + order.fee);
 
-        // Update the batch fees, deadline, and aggregation state.
+        // The fulfillment window opens with the first orders added to the batch
+        let window_start = (!orders.is_empty()).then(|| crate::now_timestamp() as i64);
+
+        // Update the batch fees, deadline, fulfillment window, and aggregation state.
         let res = sqlx::query(
             r#"
             UPDATE batches
             SET
                 data = json_set(
+                       json_set(
                        json_set(
                        json_set(data,
                        '$.deadline', $1),
                        '$.fees', $2),
-                       '$.aggregation_state', json($3))
+                       '$.window_start', COALESCE(data->>'window_start', $3)),
+                       '$.aggregation_state', json($4))
             WHERE
-                id = $4"#,
+                id = $5"#,
         )
         .bind(new_deadline)
         .bind(format!("0x{new_fees:x}"))
+        .bind(window_start)
         .bind(sqlx::types::Json(aggreagtion_state))
         .bind(batch_id as i64)
         .execute(&mut *txn)
@@ -955,15 +1542,20 @@ impl BrokerDb for SqliteDb {
         request_id: U256,
         block_number: u64,
     ) -> Result<(), DbError> {
-        sqlx::query(
+        let result = sqlx::query(
             r#"
-            INSERT INTO fulfilled_requests (id, block_number) VALUES ($1, $2)"#,
+            INSERT INTO fulfilled_requests (id, block_number) VALUES ($1, $2)
+            ON CONFLICT(id) DO NOTHING"#,
         )
         .bind(format!("0x{request_id:x}"))
         .bind(block_number as i64)
         .execute(&self.pool)
         .await?;
 
+        if result.rows_affected() == 0 {
+            tracing::debug!("Request 0x{request_id:x} already marked as fulfilled");
+        }
+
         Ok(())
     }
 
@@ -977,6 +1569,17 @@ impl BrokerDb for SqliteDb {
         Ok(res.is_some())
     }
 
+    #[instrument(level = "trace", skip(self))]
+    async fn get_request_fulfilled_block(&self, request_id: U256) -> Result<Option<u64>, DbError> {
+        let block_number: Option<i64> =
+            sqlx::query_scalar(r#"SELECT block_number FROM fulfilled_requests WHERE id = $1"#)
+                .bind(format!("0x{request_id:x}"))
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(block_number.map(|block_number| block_number as u64))
+    }
+
     #[instrument(level = "trace", skip(self))]
     async fn set_request_locked(
         &self,
@@ -984,8 +1587,9 @@ impl BrokerDb for SqliteDb {
         locker: &str,
         block_number: u64,
     ) -> Result<(), DbError> {
-        sqlx::query(
-            r#"INSERT INTO locked_requests (id, locker, block_number) VALUES ($1, $2, $3)"#,
+        let result = sqlx::query(
+            r#"INSERT INTO locked_requests (id, locker, block_number) VALUES ($1, $2, $3)
+               ON CONFLICT(id) DO NOTHING"#,
         )
         .bind(format!("0x{request_id:x}"))
         .bind(locker)
@@ -993,6 +1597,10 @@ impl BrokerDb for SqliteDb {
         .execute(&self.pool)
         .await?;
 
+        if result.rows_affected() == 0 {
+            tracing::debug!("Request 0x{request_id:x} already marked as locked");
+        }
+
         Ok(())
     }
 
@@ -1017,25 +1625,579 @@ impl BrokerDb for SqliteDb {
         Ok(res.map(|r| (r.locker, r.block_number)))
     }
 
-    #[cfg(test)]
-    async fn add_batch(&self, batch_id: usize, batch: Batch) -> Result<(), DbError> {
-        let res = sqlx::query("INSERT INTO batches (id, data) VALUES ($1, $2)")
-            .bind(batch_id as i64)
-            .bind(sqlx::types::Json(batch))
+    #[instrument(level = "trace", skip(self))]
+    async fn clear_request_fulfilled(&self, request_id: U256) -> Result<(), DbError> {
+        sqlx::query(r#"DELETE FROM fulfilled_requests WHERE id = $1"#)
+            .bind(format!("0x{request_id:x}"))
             .execute(&self.pool)
             .await?;
 
-        if res.rows_affected() == 0 {
-            return Err(DbError::BatchInsertFailure(batch_id));
-        }
-
         Ok(())
     }
 
-    #[cfg(test)]
-    async fn set_batch_status(&self, batch_id: usize, status: BatchStatus) -> Result<(), DbError> {
-        let res = sqlx::query(
-            r#"
+    #[instrument(level = "trace", skip(self))]
+    async fn clear_request_locked(&self, request_id: U256) -> Result<(), DbError> {
+        sqlx::query(r#"DELETE FROM locked_requests WHERE id = $1"#)
+            .bind(format!("0x{request_id:x}"))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{}", record.order_id)))]
+    async fn set_shadow_pricing_record(&self, record: &ShadowPricingRecord) -> Result<(), DbError> {
+        sqlx::query(
+            r#"INSERT INTO shadow_pricing (id, data) VALUES ($1, $2)
+               ON CONFLICT(id) DO UPDATE SET data = excluded.data"#,
+        )
+        .bind(&record.order_id)
+        .bind(sqlx::types::Json(record))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_shadow_pricing_record(
+        &self,
+        order_id: &str,
+    ) -> Result<Option<ShadowPricingRecord>, DbError> {
+        let record: Option<DbShadowPricingRecord> =
+            sqlx::query_as("SELECT * FROM shadow_pricing WHERE id = $1")
+                .bind(order_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(record.map(|x| x.data))
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{}", record.order_id)))]
+    async fn set_dry_run_record(&self, record: &DryRunRecord) -> Result<(), DbError> {
+        sqlx::query(
+            r#"INSERT INTO dry_run_decisions (id, data) VALUES ($1, $2)
+               ON CONFLICT(id) DO UPDATE SET data = excluded.data"#,
+        )
+        .bind(&record.order_id)
+        .bind(sqlx::types::Json(record))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_dry_run_record(&self, order_id: &str) -> Result<Option<DryRunRecord>, DbError> {
+        let record: Option<DbDryRunRecord> =
+            sqlx::query_as("SELECT * FROM dry_run_decisions WHERE id = $1")
+                .bind(order_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(record.map(|x| x.data))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn record_client_event(
+        &self,
+        client: Address,
+        event: ClientEvent,
+    ) -> Result<(), DbError> {
+        let delta = ClientStats::from(event);
+        sqlx::query(
+            r#"INSERT INTO client_stats
+                   (address, preflights, guest_panics, journal_bytes, locks, payment_failures, fulfilled, failed)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+               ON CONFLICT(address) DO UPDATE SET
+                   preflights = preflights + excluded.preflights,
+                   guest_panics = guest_panics + excluded.guest_panics,
+                   journal_bytes = journal_bytes + excluded.journal_bytes,
+                   locks = locks + excluded.locks,
+                   payment_failures = payment_failures + excluded.payment_failures,
+                   fulfilled = fulfilled + excluded.fulfilled,
+                   failed = failed + excluded.failed"#,
+        )
+        .bind(client.to_string())
+        .bind(delta.preflights as i64)
+        .bind(delta.guest_panics as i64)
+        .bind(delta.journal_bytes as i64)
+        .bind(delta.locks as i64)
+        .bind(delta.payment_failures as i64)
+        .bind(delta.fulfilled as i64)
+        .bind(delta.failed as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_client_stats(&self, client: Address) -> Result<ClientStats, DbError> {
+        let stats: Option<DbClientStats> =
+            sqlx::query_as("SELECT * FROM client_stats WHERE address = $1")
+                .bind(client.to_string())
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(stats.map(ClientStats::from).unwrap_or_default())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(count = order_ids.len()))]
+    async fn set_fulfillment_gas(
+        &self,
+        order_ids: &[&str],
+        gas_estimate: u64,
+        gas_used: u64,
+    ) -> Result<(), DbError> {
+        let mut txn = self.pool.begin().await?;
+        for order_id in order_ids {
+            sqlx::query(
+                r#"INSERT INTO fulfillment_gas (id, gas_estimate, gas_used) VALUES ($1, $2, $3)
+                   ON CONFLICT(id) DO UPDATE
+                   SET gas_estimate = excluded.gas_estimate, gas_used = excluded.gas_used"#,
+            )
+            .bind(order_id)
+            .bind(gas_estimate as i64)
+            .bind(gas_used as i64)
+            .execute(&mut *txn)
+            .await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_fulfillment_gas(&self, order_id: &str) -> Result<Option<(u64, u64)>, DbError> {
+        let gas: Option<(i64, i64)> =
+            sqlx::query_as("SELECT gas_estimate, gas_used FROM fulfillment_gas WHERE id = $1")
+                .bind(order_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(gas.map(|(estimate, used)| (estimate as u64, used as u64)))
+    }
+
+    #[instrument(level = "trace", skip_all, fields(count = records.len()))]
+    async fn add_order_gas_records(&self, records: &[OrderGasRecord]) -> Result<(), DbError> {
+        let mut txn = self.pool.begin().await?;
+        for record in records {
+            sqlx::query("INSERT INTO order_gas (order_id, data) VALUES ($1, $2)")
+                .bind(&record.order_id)
+                .bind(sqlx::types::Json(record))
+                .execute(&mut *txn)
+                .await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_order_gas_records(&self, order_id: &str) -> Result<Vec<OrderGasRecord>, DbError> {
+        let records: Vec<(sqlx::types::Json<OrderGasRecord>,)> =
+            sqlx::query_as("SELECT data FROM order_gas WHERE order_id = $1 ORDER BY id")
+                .bind(order_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(records.into_iter().map(|(record,)| record.0).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_recent_gas_records(&self, limit: u32) -> Result<Vec<OrderGasRecord>, DbError> {
+        let records: Vec<(sqlx::types::Json<OrderGasRecord>,)> =
+            sqlx::query_as("SELECT data FROM order_gas ORDER BY id DESC LIMIT $1")
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(records.into_iter().map(|(record,)| record.0).collect())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(count = request_ids.len()))]
+    async fn get_fulfillment_intents(
+        &self,
+        request_ids: &[U256],
+    ) -> Result<Vec<FulfillmentIntent>, DbError> {
+        let mut intents = vec![];
+        for request_id in request_ids {
+            let intent: Option<(sqlx::types::Json<FulfillmentIntent>,)> =
+                sqlx::query_as("SELECT data FROM fulfillment_intents WHERE request_id = $1")
+                    .bind(format!("0x{request_id:x}"))
+                    .fetch_optional(&self.pool)
+                    .await?;
+            intents.extend(intent.map(|(intent,)| intent.0));
+        }
+        Ok(intents)
+    }
+
+    #[instrument(level = "trace", skip_all, fields(count = intents.len()))]
+    async fn claim_fulfillment_intents(
+        &self,
+        intents: &[FulfillmentIntent],
+        stale_before: u64,
+    ) -> Result<bool, DbError> {
+        let mut txn = self.pool.begin().await?;
+        for intent in intents {
+            let res = sqlx::query(
+                r#"INSERT INTO fulfillment_intents (request_id, created_at, data) VALUES ($1, $2, $3)
+                   ON CONFLICT(request_id) DO UPDATE
+                   SET created_at = excluded.created_at, data = excluded.data
+                   WHERE fulfillment_intents.created_at < $4"#,
+            )
+            .bind(format!("0x{:x}", intent.request_id))
+            .bind(intent.created_at.timestamp())
+            .bind(sqlx::types::Json(intent))
+            .bind(stale_before as i64)
+            .execute(&mut *txn)
+            .await?;
+            if res.rows_affected() == 0 {
+                // Dropping the transaction rolls back the intents recorded so far
+                return Ok(false);
+            }
+        }
+        txn.commit().await?;
+        Ok(true)
+    }
+
+    #[instrument(level = "trace", skip_all, fields(count = request_ids.len(), tx_hash = %tx_hash))]
+    async fn set_fulfillment_intents_tx(
+        &self,
+        request_ids: &[U256],
+        tx_hash: B256,
+    ) -> Result<(), DbError> {
+        let mut txn = self.pool.begin().await?;
+        for request_id in request_ids {
+            sqlx::query(
+                r#"UPDATE fulfillment_intents SET data = json_set(data, '$.tx_hash', $1)
+                   WHERE request_id = $2"#,
+            )
+            .bind(tx_hash.to_string())
+            .bind(format!("0x{request_id:x}"))
+            .execute(&mut *txn)
+            .await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(count = request_ids.len()))]
+    async fn release_fulfillment_intents(&self, request_ids: &[U256]) -> Result<(), DbError> {
+        let mut txn = self.pool.begin().await?;
+        for request_id in request_ids {
+            sqlx::query("DELETE FROM fulfillment_intents WHERE request_id = $1")
+                .bind(format!("0x{request_id:x}"))
+                .execute(&mut *txn)
+                .await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn add_image_cycles(
+        &self,
+        image_id: &str,
+        total_cycles: u64,
+        max_samples: u32,
+    ) -> Result<(), DbError> {
+        let mut txn = self.pool.begin().await?;
+        sqlx::query("INSERT INTO image_cycles (image_id, total_cycles) VALUES ($1, $2)")
+            .bind(image_id)
+            .bind(total_cycles as i64)
+            .execute(&mut *txn)
+            .await?;
+        sqlx::query(
+            r#"DELETE FROM image_cycles
+               WHERE image_id = $1 AND id NOT IN
+                   (SELECT id FROM image_cycles WHERE image_id = $1 ORDER BY id DESC LIMIT $2)"#,
+        )
+        .bind(image_id)
+        .bind(max_samples)
+        .execute(&mut *txn)
+        .await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_image_cycles(&self, image_id: &str) -> Result<Vec<u64>, DbError> {
+        let samples: Vec<(i64,)> =
+            sqlx::query_as("SELECT total_cycles FROM image_cycles WHERE image_id = $1")
+                .bind(image_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(samples.into_iter().map(|(cycles,)| cycles as u64).collect())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{}", record.order_id)))]
+    async fn add_pricing_audit_record(&self, record: &PricingAuditRecord) -> Result<(), DbError> {
+        sqlx::query("INSERT INTO pricing_audit (request_id, data) VALUES ($1, $2)")
+            .bind(format!("0x{:x}", record.request_id))
+            .bind(sqlx::types::Json(record))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self), fields(request_id = %format!("0x{:x}", request_id)))]
+    async fn get_pricing_audit_records(
+        &self,
+        request_id: U256,
+    ) -> Result<Vec<PricingAuditRecord>, DbError> {
+        let records: Vec<(sqlx::types::Json<PricingAuditRecord>,)> =
+            sqlx::query_as("SELECT data FROM pricing_audit WHERE request_id = $1 ORDER BY id")
+                .bind(format!("0x{:x}", request_id))
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(records.into_iter().map(|(record,)| record.0).collect())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{}", record.order_id)))]
+    async fn add_order_provenance_record(
+        &self,
+        record: &OrderProvenanceRecord,
+    ) -> Result<(), DbError> {
+        sqlx::query("INSERT INTO order_provenance (request_id, data) VALUES ($1, $2)")
+            .bind(format!("0x{:x}", record.request_id))
+            .bind(sqlx::types::Json(record))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self), fields(request_id = %format!("0x{:x}", request_id)))]
+    async fn get_order_provenance_records(
+        &self,
+        request_id: U256,
+    ) -> Result<Vec<OrderProvenanceRecord>, DbError> {
+        let records: Vec<(sqlx::types::Json<OrderProvenanceRecord>,)> =
+            sqlx::query_as("SELECT data FROM order_provenance WHERE request_id = $1 ORDER BY id")
+                .bind(format!("0x{:x}", request_id))
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(records.into_iter().map(|(record,)| record.0).collect())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("0x{:x}", record.request_id)))]
+    async fn add_market_event(&self, record: &MarketEventRecord) -> Result<bool, DbError> {
+        let res = sqlx::query(
+            r#"INSERT INTO market_events (chain_id, block_number, log_index, data)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT(chain_id, block_number, log_index) DO NOTHING"#,
+        )
+        .bind(record.chain_id as i64)
+        .bind(record.block_number as i64)
+        .bind(record.log_index as i64)
+        .bind(sqlx::types::Json(record))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_market_events(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+    ) -> Result<Vec<MarketEventRecord>, DbError> {
+        let records: Vec<(sqlx::types::Json<MarketEventRecord>,)> = sqlx::query_as(
+            r#"SELECT data FROM market_events
+               WHERE chain_id = $1 AND block_number >= $2
+               ORDER BY block_number, log_index"#,
+        )
+        .bind(chain_id as i64)
+        .bind(from_block as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records.into_iter().map(|(record,)| record.0).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_indexer_checkpoint(&self, chain_id: u64) -> Result<Option<u64>, DbError> {
+        let block_number: Option<i64> =
+            sqlx::query_scalar("SELECT block_number FROM indexer_checkpoints WHERE chain_id = $1")
+                .bind(chain_id as i64)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(block_number.map(|block_number| block_number as u64))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn set_indexer_checkpoint(
+        &self,
+        chain_id: u64,
+        block_number: u64,
+    ) -> Result<(), DbError> {
+        sqlx::query(
+            r#"INSERT INTO indexer_checkpoints (chain_id, block_number) VALUES ($1, $2)
+               ON CONFLICT(chain_id) DO UPDATE
+               SET block_number = MAX(block_number, excluded.block_number)"#,
+        )
+        .bind(chain_id as i64)
+        .bind(block_number as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn revert_market_events(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+    ) -> Result<Vec<MarketEventRecord>, DbError> {
+        let mut txn = self.pool.begin().await?;
+        let records: Vec<(sqlx::types::Json<MarketEventRecord>,)> = sqlx::query_as(
+            r#"SELECT data FROM market_events
+               WHERE chain_id = $1 AND block_number >= $2
+               ORDER BY block_number, log_index"#,
+        )
+        .bind(chain_id as i64)
+        .bind(from_block as i64)
+        .fetch_all(&mut *txn)
+        .await?;
+        sqlx::query("DELETE FROM market_events WHERE chain_id = $1 AND block_number >= $2")
+            .bind(chain_id as i64)
+            .bind(from_block as i64)
+            .execute(&mut *txn)
+            .await?;
+        sqlx::query(
+            r#"UPDATE indexer_checkpoints SET block_number = MIN(block_number, $2)
+               WHERE chain_id = $1"#,
+        )
+        .bind(chain_id as i64)
+        .bind(from_block.saturating_sub(1) as i64)
+        .execute(&mut *txn)
+        .await?;
+        txn.commit().await?;
+
+        Ok(records.into_iter().map(|(record,)| record.0).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn add_request_tombstone(
+        &self,
+        digest: B256,
+        reason: SkipReason,
+        expires_at: u64,
+    ) -> Result<(), DbError> {
+        let mut txn = self.pool.begin().await?;
+        sqlx::query("DELETE FROM request_tombstones WHERE expires_at <= $1")
+            .bind(crate::now_timestamp() as i64)
+            .execute(&mut *txn)
+            .await?;
+        sqlx::query(
+            r#"INSERT INTO request_tombstones (digest, reason, expires_at) VALUES ($1, $2, $3)
+               ON CONFLICT(digest) DO UPDATE
+               SET reason = excluded.reason, expires_at = excluded.expires_at"#,
+        )
+        .bind(digest.to_string())
+        .bind(sqlx::types::Json(reason))
+        .bind(expires_at as i64)
+        .execute(&mut *txn)
+        .await?;
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_request_tombstone(&self, digest: B256) -> Result<Option<SkipReason>, DbError> {
+        let reason: Option<(sqlx::types::Json<SkipReason>,)> = sqlx::query_as(
+            "SELECT reason FROM request_tombstones WHERE digest = $1 AND expires_at > $2",
+        )
+        .bind(digest.to_string())
+        .bind(crate::now_timestamp() as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(reason.map(|(reason,)| reason.0))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn clear_request_tombstone(&self, digest: B256) -> Result<bool, DbError> {
+        let res = sqlx::query("DELETE FROM request_tombstones WHERE digest = $1")
+            .bind(digest.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("0x{:x}", request.request_id)))]
+    async fn set_market_request(&self, request: &MarketRequest) -> Result<(), DbError> {
+        sqlx::query(
+            r#"INSERT INTO market_requests (id, data) VALUES ($1, $2)
+               ON CONFLICT(id) DO UPDATE SET data = excluded.data"#,
+        )
+        .bind(format!("0x{:x}", request.request_id))
+        .bind(sqlx::types::Json(request))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_market_request(&self, request_id: U256) -> Result<Option<MarketRequest>, DbError> {
+        let request: Option<DbMarketRequest> =
+            sqlx::query_as("SELECT * FROM market_requests WHERE id = $1")
+                .bind(format!("0x{request_id:x}"))
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(request.map(|x| x.data))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_locker_stats(&self, locker: Address) -> Result<LockerStats, DbError> {
+        let (fulfilled, missed): (i64, i64) = sqlx::query_as(
+            r#"SELECT
+                 COALESCE(SUM(fulfiller = locker AND slashed_block IS NULL), 0),
+                 COALESCE(SUM(slashed_block IS NOT NULL OR fulfiller <> locker), 0)
+               FROM (
+                 SELECT lower(data->>'locker') AS locker, lower(data->>'fulfiller') AS fulfiller,
+                        data->>'slashed_block' AS slashed_block
+                 FROM market_requests
+               )
+               WHERE locker = lower($1)"#,
+        )
+        .bind(locker.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(LockerStats { fulfilled: fulfilled as u64, missed: missed as u64 })
+    }
+
+    #[cfg(test)]
+    async fn add_batch(&self, batch_id: usize, batch: Batch) -> Result<(), DbError> {
+        let res = sqlx::query("INSERT INTO batches (id, data) VALUES ($1, $2)")
+            .bind(batch_id as i64)
+            .bind(sqlx::types::Json(batch))
+            .execute(&self.pool)
+            .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(DbError::BatchInsertFailure(batch_id));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    async fn set_batch_status(&self, batch_id: usize, status: BatchStatus) -> Result<(), DbError> {
+        let res = sqlx::query(
+            r#"
                 UPDATE batches
                 SET
                     data = json_set(data,
@@ -1054,13 +2216,85 @@ impl BrokerDb for SqliteDb {
 
         Ok(())
     }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn export_state(&self, path: &Path, manifest: &StateManifest) -> Result<(), DbError> {
+        sqlx::query("VACUUM INTO $1")
+            .bind(path.to_string_lossy().into_owned())
+            .execute(&self.pool)
+            .await?;
+
+        let snapshot = SqlitePool::connect_with(SqliteConnectOptions::new().filename(path)).await?;
+        sqlx::query("CREATE TABLE state_manifest (id INTEGER PRIMARY KEY, data JSONB)")
+            .execute(&snapshot)
+            .await?;
+        sqlx::query("INSERT INTO state_manifest (id, data) VALUES (1, $1)")
+            .bind(sqlx::types::Json(manifest))
+            .execute(&snapshot)
+            .await?;
+        snapshot.close().await;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn import_state(
+        &self,
+        path: &Path,
+        reset_proofs: bool,
+    ) -> Result<StateManifest, DbError> {
+        let manifest = read_state_manifest(path).await?;
+
+        let (orders, batches): (i64, i64) =
+            sqlx::query_as("SELECT (SELECT COUNT(*) FROM orders), (SELECT COUNT(*) FROM batches)")
+                .fetch_one(&self.pool)
+                .await?;
+        if orders > 0 || batches > 0 {
+            return Err(DbError::StateImport(anyhow::anyhow!(
+                "DB already has {orders} orders and {batches} batches"
+            )));
+        }
+
+        // Bring a copy of the snapshot up to the current schema, leaving the snapshot as is.
+        let tmp_dir = tempfile::tempdir().map_err(|err| DbError::StateImport(err.into()))?;
+        let snapshot_path = tmp_dir.path().join("state.db");
+        std::fs::copy(path, &snapshot_path).map_err(|err| DbError::StateImport(err.into()))?;
+        SqliteDb::new(&format!("sqlite:{}", snapshot_path.display())).await?.pool.close().await;
+
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("ATTACH DATABASE $1 AS snapshot")
+            .bind(snapshot_path.to_string_lossy().into_owned())
+            .execute(&mut *conn)
+            .await?;
+        let res = Self::copy_snapshot(&mut conn, reset_proofs).await;
+        sqlx::query("DETACH DATABASE snapshot").execute(&mut *conn).await?;
+        res?;
+
+        Ok(manifest)
+    }
+}
+
+/// Read the manifest of a snapshot written by [BrokerDb::export_state].
+pub(crate) async fn read_state_manifest(path: &Path) -> Result<StateManifest, DbError> {
+    let snapshot =
+        SqlitePool::connect_with(SqliteConnectOptions::new().filename(path).read_only(true))
+            .await?;
+    let manifest: Option<(sqlx::types::Json<StateManifest>,)> =
+        sqlx::query_as("SELECT data FROM state_manifest WHERE id = 1")
+            .fetch_optional(&snapshot)
+            .await?;
+    snapshot.close().await;
+
+    manifest.map(|(manifest,)| manifest.0).ok_or(DbError::MissingElm("state_manifest"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ProofRequest;
-    use alloy::primitives::{Address, Bytes, U256};
+    use crate::{
+        GasTxKind, OrderProvenance, OrderSource, PricingDecision, ProofRequest, SignatureScheme,
+    };
+    use alloy::primitives::{Address, Bytes, FixedBytes, U256};
     use boundless_market::contracts::{
         Offer, Predicate, PredicateType, RequestId, RequestInput, RequestInputType, Requirements,
     };
@@ -1197,9 +2431,394 @@ mod tests {
         let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
         let order = create_order_request();
 
-        db.insert_skipped_request(&order).await.unwrap();
+        db.insert_skipped_request(&order, SkipReason::PriceTooLow).await.unwrap();
         let db_order = db.get_order(&order.id()).await.unwrap().unwrap();
         assert_eq!(db_order.status, OrderStatus::Skipped);
+        assert_eq!(db_order.skip_reason, Some(SkipReason::PriceTooLow));
+    }
+
+    #[sqlx::test]
+    async fn skip_reason_counts(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let now = Utc::now();
+        let old = now - chrono::Duration::seconds(1000);
+
+        let reasons = [
+            (1, SkipReason::PriceTooLow, now),
+            (2, SkipReason::PriceTooLow, now),
+            (3, SkipReason::InsufficientDeadline, now),
+            (4, SkipReason::InsufficientDeadline, old),
+            (5, SkipReason::InsufficientDeadline, old),
+        ];
+        for (id, reason, updated_at) in reasons {
+            let mut order = create_order();
+            order.request.id = U256::from(id);
+            order.status = OrderStatus::Skipped;
+            order.skip_reason = Some(reason);
+            order.updated_at = updated_at;
+            db.add_order(&order).await.unwrap();
+        }
+        // Skipped orders without a reason and orders in other states are not counted
+        let mut order = create_order();
+        order.request.id = U256::from(6);
+        order.status = OrderStatus::Skipped;
+        db.add_order(&order).await.unwrap();
+        let mut order = create_order();
+        order.request.id = U256::from(7);
+        db.add_order(&order).await.unwrap();
+
+        let counts = db.get_skip_reason_counts(None).await.unwrap();
+        assert_eq!(
+            counts,
+            vec![(SkipReason::InsufficientDeadline, 3), (SkipReason::PriceTooLow, 2)]
+        );
+
+        let since = (now - chrono::Duration::seconds(500)).timestamp();
+        let counts = db.get_skip_reason_counts(Some(since)).await.unwrap();
+        assert_eq!(
+            counts,
+            vec![(SkipReason::PriceTooLow, 2), (SkipReason::InsufficientDeadline, 1)]
+        );
+
+        let orders = db.get_skipped_orders(SkipReason::InsufficientDeadline, 2).await.unwrap();
+        assert_eq!(orders.len(), 2);
+        // Most recent first
+        assert_eq!(orders[0].request.id, U256::from(3));
+        assert!(db.get_skipped_orders(SkipReason::LockFailed, 10).await.unwrap().is_empty());
+
+        let orders = db.get_orders_by_status(OrderStatus::Skipped, 10).await.unwrap();
+        assert_eq!(orders.len(), 6);
+        let orders = db.get_orders_by_status(OrderStatus::PendingProving, 10).await.unwrap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].request.id, U256::from(7));
+        assert!(db.get_orders_by_status(OrderStatus::Done, 10).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn query_orders(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let client = Address::from([0x11; 20]);
+        let now = Utc::now();
+        for idx in 0..6u32 {
+            let mut order = create_order();
+            // Clients with leading zero bytes are stored as hex without leading zeros
+            let address = if idx % 2 == 0 { client } else { Address::with_last_byte(2) };
+            order.request.id = RequestId::new(address, idx).into();
+            order.status = if idx < 4 { OrderStatus::Done } else { OrderStatus::Failed };
+            if idx == 5 {
+                order.fulfillment_type = FulfillmentType::FulfillAfterLockExpire;
+            }
+            order.updated_at = now - chrono::Duration::seconds(idx as i64 * 100);
+            db.add_order(&order).await.unwrap();
+        }
+
+        let ids = |orders: Vec<Order>| -> Vec<u32> {
+            orders.iter().map(|order| RequestId::from_lossy(order.request.id).index).collect()
+        };
+        let query =
+            OrderQuery { statuses: vec![OrderStatus::Done], limit: 2, ..Default::default() };
+        assert_eq!(ids(db.query_orders(&query).await.unwrap()), vec![0, 1]);
+        let page = OrderQuery { offset: 2, ..query.clone() };
+        assert_eq!(ids(db.query_orders(&page).await.unwrap()), vec![2, 3]);
+        assert_eq!(db.count_orders(&query).await.unwrap(), 4);
+
+        let query = OrderQuery { client: Some(client), limit: 10, ..Default::default() };
+        assert_eq!(ids(db.query_orders(&query).await.unwrap()), vec![0, 2, 4]);
+        let query = OrderQuery { client: Some(Address::with_last_byte(2)), ..query };
+        assert_eq!(db.count_orders(&query).await.unwrap(), 3);
+
+        let query = OrderQuery {
+            statuses: vec![OrderStatus::Done, OrderStatus::Failed],
+            fulfillment_type: Some(FulfillmentType::LockAndFulfill),
+            updated_since: Some((now - chrono::Duration::seconds(550)).timestamp()),
+            updated_before: Some((now - chrono::Duration::seconds(50)).timestamp()),
+            limit: 10,
+            ..Default::default()
+        };
+        assert_eq!(ids(db.query_orders(&query).await.unwrap()), vec![1, 2, 3, 4]);
+    }
+
+    #[sqlx::test]
+    async fn client_stats(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let client = Address::ZERO;
+        assert_eq!(db.get_client_stats(client).await.unwrap(), ClientStats::default());
+
+        db.record_client_event(client, ClientEvent::Preflight { journal_bytes: 100 })
+            .await
+            .unwrap();
+        db.record_client_event(client, ClientEvent::Preflight { journal_bytes: 50 }).await.unwrap();
+        db.record_client_event(client, ClientEvent::GuestPanic).await.unwrap();
+        db.record_client_event(Address::repeat_byte(1), ClientEvent::Locked).await.unwrap();
+
+        // Order outcomes are recorded with their status
+        let order = create_order();
+        db.add_order(&order).await.unwrap();
+        db.set_order_complete(&order.id()).await.unwrap();
+
+        assert_eq!(
+            db.get_client_stats(client).await.unwrap(),
+            ClientStats {
+                preflights: 2,
+                guest_panics: 1,
+                journal_bytes: 150,
+                fulfilled: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[sqlx::test]
+    async fn shadow_pricing_record(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let order = create_order();
+
+        assert!(db.get_shadow_pricing_record(&order.id()).await.unwrap().is_none());
+
+        let mut record = ShadowPricingRecord {
+            order_id: order.id(),
+            total_cycles: 1_000_000,
+            live: PricingDecision::Lock { target_timestamp: 0 },
+            shadow: PricingDecision::Skip,
+            created_at: Utc::now(),
+        };
+        db.set_shadow_pricing_record(&record).await.unwrap();
+        let db_record = db.get_shadow_pricing_record(&order.id()).await.unwrap().unwrap();
+        assert_eq!(db_record.live, PricingDecision::Lock { target_timestamp: 0 });
+        assert_eq!(db_record.shadow, PricingDecision::Skip);
+
+        // Re-pricing an order overwrites the previous record
+        record.shadow = PricingDecision::Lock { target_timestamp: 10 };
+        db.set_shadow_pricing_record(&record).await.unwrap();
+        let db_record = db.get_shadow_pricing_record(&order.id()).await.unwrap().unwrap();
+        assert_eq!(db_record.shadow, PricingDecision::Lock { target_timestamp: 10 });
+    }
+
+    #[sqlx::test]
+    async fn pricing_audit_records(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let order = create_order();
+
+        assert!(db.get_pricing_audit_records(order.request.id).await.unwrap().is_empty());
+
+        let mut record = PricingAuditRecord {
+            order_id: order.id(),
+            request_id: order.request.id,
+            fulfillment_type: order.fulfillment_type,
+            gas_price: Some(1_000_000_000),
+            gas_cost: Some(U256::from(1_000)),
+            available_gas: Some(U256::from(10_000)),
+            available_stake: Some(U256::from(5)),
+            exec_limit_cycles: None,
+            exec_limit_bound: None,
+            total_cycles: None,
+            risk: None,
+            mcycle_price: "0.0001".into(),
+            mcycle_price_stake_token: "0.001".into(),
+            decision: PricingDecision::Skip,
+            skip_reason: Some(SkipReason::InsufficientGas),
+            error: None,
+            created_at: Utc::now(),
+        };
+        db.add_pricing_audit_record(&record).await.unwrap();
+
+        // Re-pricing an order appends to the log
+        record.total_cycles = Some(1_000_000);
+        record.decision = PricingDecision::Lock { target_timestamp: 10 };
+        record.skip_reason = None;
+        db.add_pricing_audit_record(&record).await.unwrap();
+
+        let records = db.get_pricing_audit_records(order.request.id).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].decision, PricingDecision::Skip);
+        assert_eq!(records[0].skip_reason, Some(SkipReason::InsufficientGas));
+        assert_eq!(records[1].decision, PricingDecision::Lock { target_timestamp: 10 });
+        assert_eq!(records[1].total_cycles, Some(1_000_000));
+
+        assert!(db.get_pricing_audit_records(U256::from(999)).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn order_gas_records(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let order = create_order();
+        let record = |kind, tx_hash, gas_used: u64| OrderGasRecord {
+            order_id: order.id(),
+            kind,
+            tx_hash,
+            gas_used,
+            gas_cost: U256::from(gas_used) * U256::from(2),
+            created_at: Utc::now(),
+        };
+
+        assert!(db.get_order_gas_records(&order.id()).await.unwrap().is_empty());
+        db.add_order_gas_records(&[record(GasTxKind::Lock, FixedBytes::repeat_byte(1), 100_000)])
+            .await
+            .unwrap();
+        db.add_order_gas_records(&[
+            record(GasTxKind::Lock, FixedBytes::repeat_byte(2), 110_000),
+            record(GasTxKind::Fulfill, FixedBytes::repeat_byte(3), 200_000),
+        ])
+        .await
+        .unwrap();
+
+        let records = db.get_order_gas_records(&order.id()).await.unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].tx_hash, FixedBytes::repeat_byte(1));
+        assert_eq!(records[2].kind, GasTxKind::Fulfill);
+        assert_eq!(records[2].gas_cost, U256::from(400_000));
+        assert!(db.get_order_gas_records("other").await.unwrap().is_empty());
+
+        let recent = db.get_recent_gas_records(2).await.unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].tx_hash, FixedBytes::repeat_byte(3));
+        assert_eq!(recent[1].tx_hash, FixedBytes::repeat_byte(2));
+    }
+
+    #[sqlx::test]
+    async fn fulfillment_intents(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let (id_a, id_b) = (U256::from(1), U256::from(2));
+        let intent = |request_id, batch_id| FulfillmentIntent::new(request_id, "order", batch_id);
+        let now = Utc::now().timestamp() as u64;
+
+        assert!(db.claim_fulfillment_intents(&[intent(id_a, 1)], now - 60).await.unwrap());
+        // A recent intent blocks the claim of all requests
+        assert!(!db
+            .claim_fulfillment_intents(&[intent(id_b, 2), intent(id_a, 2)], now - 60)
+            .await
+            .unwrap());
+        let intents = db.get_fulfillment_intents(&[id_a, id_b]).await.unwrap();
+        assert_eq!(intents.len(), 1);
+        assert_eq!(intents[0].batch_id, 1);
+
+        db.set_fulfillment_intents_tx(&[id_a], B256::repeat_byte(1)).await.unwrap();
+        let intents = db.get_fulfillment_intents(&[id_a]).await.unwrap();
+        assert_eq!(intents[0].tx_hash, Some(B256::repeat_byte(1)));
+
+        // Stale intents are replaced
+        assert!(db
+            .claim_fulfillment_intents(&[intent(id_b, 3), intent(id_a, 3)], now + 60)
+            .await
+            .unwrap());
+        let intents = db.get_fulfillment_intents(&[id_a, id_b]).await.unwrap();
+        assert!(intents.iter().all(|intent| intent.batch_id == 3 && intent.tx_hash.is_none()));
+
+        db.release_fulfillment_intents(&[id_a, id_b]).await.unwrap();
+        assert!(db.get_fulfillment_intents(&[id_a, id_b]).await.unwrap().is_empty());
+        assert!(db.claim_fulfillment_intents(&[intent(id_a, 4)], now - 60).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn order_provenance_records(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let order = create_order_request();
+
+        // Orders without a recorded provenance have no record
+        assert!(order.provenance_record().is_none());
+
+        let stream_order = create_order_request().with_provenance(OrderProvenance::new(
+            OrderSource::OrderStream { url: "http://localhost:8585/".into(), stream_id: Some(7) },
+        ));
+        let chain_order = create_order_request().with_provenance(
+            OrderProvenance::new(OrderSource::ChainEvent {
+                block_number: Some(10),
+                tx_hash: Some(FixedBytes::repeat_byte(1)),
+            })
+            .with_signature_validated(),
+        );
+        for order in [&stream_order, &chain_order] {
+            db.add_order_provenance_record(&order.provenance_record().unwrap()).await.unwrap();
+        }
+
+        let records = db.get_order_provenance_records(order.request.id).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].provenance, stream_order.provenance.unwrap());
+        assert_eq!(records[1].provenance, chain_order.provenance.unwrap());
+        assert_eq!(records[1].signature.scheme, SignatureScheme::Ecdsa);
+        // The empty signature is not a valid ECDSA signature, and is only reported as valid when
+        // it was validated on receipt
+        assert!(!records[0].signature.validated);
+        assert!(records[1].signature.validated);
+
+        assert!(db.get_order_provenance_records(U256::from(999)).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn request_tombstones(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let (digest, other) = (B256::repeat_byte(1), B256::repeat_byte(2));
+
+        db.add_request_tombstone(digest, SkipReason::ImageDenied, u64::MAX).await.unwrap();
+        db.add_request_tombstone(other, SkipReason::PredicateFailed, 0).await.unwrap();
+        assert_eq!(db.get_request_tombstone(digest).await.unwrap(), Some(SkipReason::ImageDenied));
+        // Expired tombstones are ignored
+        assert_eq!(db.get_request_tombstone(other).await.unwrap(), None);
+
+        assert!(db.clear_request_tombstone(digest).await.unwrap());
+        assert!(!db.clear_request_tombstone(digest).await.unwrap());
+        assert_eq!(db.get_request_tombstone(digest).await.unwrap(), None);
+    }
+
+    #[sqlx::test]
+    async fn get_archivable_orders(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let now = Utc::now();
+        let old = now - chrono::Duration::seconds(1000);
+
+        let mut done_old = create_order();
+        done_old.request.id = U256::from(1);
+        done_old.status = OrderStatus::Done;
+        done_old.updated_at = old;
+        db.add_order(&done_old).await.unwrap();
+
+        let mut skipped_old = create_order();
+        skipped_old.request.id = U256::from(2);
+        skipped_old.status = OrderStatus::Skipped;
+        skipped_old.updated_at = old - chrono::Duration::seconds(10);
+        db.add_order(&skipped_old).await.unwrap();
+
+        let mut done_new = create_order();
+        done_new.request.id = U256::from(3);
+        done_new.status = OrderStatus::Done;
+        done_new.updated_at = now;
+        db.add_order(&done_new).await.unwrap();
+
+        let mut proving_old = create_order();
+        proving_old.request.id = U256::from(4);
+        proving_old.status = OrderStatus::Proving;
+        proving_old.updated_at = old;
+        db.add_order(&proving_old).await.unwrap();
+
+        let cutoff = (now - chrono::Duration::seconds(500)).timestamp();
+        let orders = db.get_archivable_orders(cutoff, 10).await.unwrap();
+        assert_eq!(orders.len(), 2);
+        // Oldest first
+        assert_eq!(orders[0].id(), skipped_old.id());
+        assert_eq!(orders[1].id(), done_old.id());
+
+        let orders = db.get_archivable_orders(cutoff, 1).await.unwrap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].id(), skipped_old.id());
+    }
+
+    #[sqlx::test]
+    async fn delete_orders(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+
+        let mut order_1 = create_order();
+        order_1.request.id = U256::from(1);
+        db.add_order(&order_1).await.unwrap();
+        let mut order_2 = create_order();
+        order_2.request.id = U256::from(2);
+        db.add_order(&order_2).await.unwrap();
+
+        assert_eq!(db.delete_orders(&[]).await.unwrap(), 0);
+        let deleted = db.delete_orders(&[&order_1.id(), "missing"]).await.unwrap();
+        assert_eq!(deleted, 1);
+
+        assert!(db.get_order(&order_1.id()).await.unwrap().is_none());
+        assert!(db.get_order(&order_2.id()).await.unwrap().is_some());
     }
 
     #[sqlx::test]
@@ -1218,6 +2837,19 @@ mod tests {
         assert_eq!(db_order.status, OrderStatus::Proving);
     }
 
+    #[sqlx::test]
+    async fn claim_proving_order(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+
+        let mut order = create_order();
+        order.status = OrderStatus::PendingProving;
+        db.add_order(&order).await.unwrap();
+
+        let db_order = db.claim_proving_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(db_order.status, OrderStatus::Proving);
+        assert!(db.claim_proving_order(&order.id()).await.unwrap().is_none());
+    }
+
     #[sqlx::test]
     async fn set_order_proof_id(pool: SqlitePool) {
         let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
@@ -1491,6 +3123,8 @@ mod tests {
         assert_eq!(db_batch.orders, vec![order1.id(), order2.id()]);
         assert_eq!(db_batch.deadline, Some(20));
         assert_eq!(db_batch.fees, U256::from(25));
+        let window_start = db_batch.window_start.unwrap();
+        assert!(window_start <= crate::now_timestamp());
         assert!(db_batch.aggregation_state.is_some());
         let agg_state = db_batch.aggregation_state.unwrap();
         assert_eq!(agg_state.groth16_proof_id.as_ref(), None);
@@ -1514,6 +3148,11 @@ mod tests {
 
         // Should now be fulfilled
         assert!(db.is_request_fulfilled(request_id).await.unwrap());
+        assert_eq!(db.get_request_fulfilled_block(request_id).await.unwrap(), Some(block_number));
+
+        // Replayed event is ignored
+        db.set_request_fulfilled(request_id, block_number).await.unwrap();
+        assert!(db.is_request_fulfilled(request_id).await.unwrap());
 
         // Different request should still not be fulfilled
         assert!(!db.is_request_fulfilled(U256::from(413)).await.unwrap());
@@ -1535,6 +3174,14 @@ mod tests {
         // Should now be locked
         assert!(db.is_request_locked(request_id).await.unwrap());
 
+        // Replayed event is ignored, keeping the original lock
+        db.set_request_locked(request_id, locker, block_number).await.unwrap();
+        db.set_request_locked(request_id, "other_locker", block_number + 1).await.unwrap();
+        assert_eq!(
+            db.get_request_locked(request_id).await.unwrap(),
+            Some((locker.to_string(), block_number))
+        );
+
         // Different request should still not be locked
         assert!(!db.is_request_locked(U256::from(413)).await.unwrap());
     }
@@ -1646,13 +3293,13 @@ mod tests {
 
         // Skipped request ignores duplicates
         let order_request = create_order_request();
-        db.insert_skipped_request(&order_request).await.unwrap();
+        db.insert_skipped_request(&order_request, SkipReason::PriceTooLow).await.unwrap();
 
         let stored_order = db.get_order(&order_request.id()).await.unwrap().unwrap();
         assert_eq!(stored_order.status, OrderStatus::Skipped);
 
         // Try to insert the same skipped request again - should be ignored
-        db.insert_skipped_request(&order_request).await.unwrap();
+        db.insert_skipped_request(&order_request, SkipReason::PriceTooLow).await.unwrap();
         assert!(logs_contain("already exists"));
 
         // Accepted request can overwrite skipped order
@@ -1665,8 +3312,10 @@ mod tests {
         assert_eq!(stored_order.status, OrderStatus::PendingProving);
         assert_eq!(stored_order.lock_price, Some(U256::from(100)));
 
-        // Accepted request errors on non-skipped duplicate
-        assert!(db.insert_accepted_request(&order_request, U256::from(200)).await.is_err());
+        // Accepted request on a non-skipped duplicate is a no-op returning the stored order
+        let replayed_order =
+            db.insert_accepted_request(&order_request, U256::from(200)).await.unwrap();
+        assert_eq!(replayed_order.lock_price, Some(U256::from(100)));
 
         // Verify the stored order still has the original lock price (wasn't updated)
         let stored_order = db.get_order(&order_request.id()).await.unwrap().unwrap();
@@ -1685,4 +3334,35 @@ mod tests {
         assert_eq!(new_order.status, OrderStatus::PendingProving);
         assert_eq!(new_order.lock_price, Some(U256::from(300)));
     }
+
+    #[tokio::test]
+    async fn export_and_import_state() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join("state.db");
+        let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
+        let order =
+            db.insert_accepted_request(&create_order_request(), U256::from(2)).await.unwrap();
+        db.set_order_proof_id(&order.id(), "proof").await.unwrap();
+        let manifest = StateManifest {
+            format_version: 1,
+            broker_version: "test".into(),
+            exported_at: Utc::now(),
+            chain_id: 1,
+            boundless_market_address: Address::ZERO,
+            prover_address: Address::ZERO,
+            committed_orders: vec![order.id()],
+        };
+        db.export_state(&path, &manifest).await.unwrap();
+        assert_eq!(read_state_manifest(&path).await.unwrap(), manifest);
+
+        // Importing into a DB with orders would drop them
+        assert!(matches!(db.import_state(&path, true).await, Err(DbError::StateImport(_))));
+
+        let new_db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
+        assert_eq!(new_db.import_state(&path, true).await.unwrap(), manifest);
+        let imported = new_db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(imported.status, OrderStatus::PendingProving);
+        assert_eq!(imported.lock_price, Some(U256::from(2)));
+        assert_eq!(imported.proof_id, None);
+    }
 }
@@ -0,0 +1,218 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Duty cycle limits on proving, for operators with power or thermal limits.
+
+use crate::{config::DutyCycleConf, FulfillmentType, OrderRequest};
+
+impl DutyCycleConf {
+    /// Maximum share of the window the prover may spend proving, in percent, if any limit is set.
+    pub(crate) fn max_percent(&self) -> Option<u64> {
+        let power_percent = match (self.max_power_watts, self.prover_power_watts) {
+            (Some(max_power), Some(prover_power)) if prover_power > 0 => {
+                Some(max_power.saturating_mul(100) / prover_power)
+            }
+            _ => None,
+        };
+        match (self.max_duty_cycle_percent, power_percent) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Whether the limit applies at the given timestamp.
+    pub(crate) fn is_active(&self, timestamp: u64) -> bool {
+        let hour = (timestamp % 86_400 / 3_600) as u8;
+        self.peak_hours.is_empty() || self.peak_hours.contains(&hour)
+    }
+}
+
+/// Duty cycle of the prover over a window, as fractions of the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct DutyCycle {
+    /// Time spent proving over the past window
+    pub(crate) actual: f64,
+    /// Time spent proving over the past window, plus the remaining work of committed orders and
+    /// of the orders admitted
+    pub(crate) projected: f64,
+}
+
+/// Defer orders that would push the projected duty cycle over the limit, returning the orders
+/// admitted and the resulting duty cycle.
+///
+/// `busy_secs` is the time spent proving over the past window and `queued_secs` the remaining
+/// proving time of committed orders. Orders that would miss their deadline if deferred for a full
+/// window are admitted regardless of the limit. Deferred orders are kept by the caller for later
+/// iterations, as the duty cycle falls once past proofs leave the window.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_duty_cycle_limits<T>(
+    orders: Vec<T>,
+    conf: &DutyCycleConf,
+    busy_secs: u64,
+    queued_secs: u64,
+    prove_khz: u64,
+    additional_proof_cycles: u64,
+    batch_buffer_time_secs: u64,
+    now: u64,
+) -> (Vec<T>, DutyCycle)
+where
+    T: AsRef<OrderRequest>,
+{
+    let window_secs = conf.window_secs.max(1);
+    let actual = busy_secs as f64 / window_secs as f64;
+    let mut projected_secs = busy_secs + queued_secs;
+    let Some(max_percent) = conf.max_percent().filter(|_| conf.is_active(now)) else {
+        let projected = projected_secs as f64 / window_secs as f64;
+        return (orders, DutyCycle { actual, projected });
+    };
+    let limit_secs = window_secs.saturating_mul(max_percent) / 100;
+
+    let orders = orders
+        .into_iter()
+        .filter(|order| {
+            let order = order.as_ref();
+            let cycles = order.total_cycles.unwrap_or_default() + additional_proof_cycles;
+            let proof_secs = cycles.div_ceil(1_000).div_ceil(prove_khz.max(1));
+            let expiration = match order.fulfillment_type {
+                FulfillmentType::LockAndFulfill => order.request.lock_expires_at(),
+                _ => order.request.expires_at(),
+            };
+            let urgent = now + window_secs + proof_secs + batch_buffer_time_secs > expiration;
+            if !urgent && projected_secs + proof_secs > limit_secs {
+                tracing::debug!(
+                    "Order {} deferred, proving it for {proof_secs}s would exceed the duty cycle limit of {max_percent}% with {projected_secs}s projected in the {window_secs}s window",
+                    order.id()
+                );
+                return false;
+            }
+            projected_secs += proof_secs;
+            true
+        })
+        .collect();
+
+    let projected = projected_secs as f64 / window_secs as f64;
+    (orders, DutyCycle { actual, projected })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use alloy::primitives::{Address, Bytes, U256};
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput, RequestInputType,
+        Requirements,
+    };
+    use risc0_zkvm::sha::Digest;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn conf(max_duty_cycle_percent: u64) -> DutyCycleConf {
+        DutyCycleConf {
+            max_duty_cycle_percent: Some(max_duty_cycle_percent),
+            max_power_watts: None,
+            prover_power_watts: None,
+            window_secs: 1_000,
+            peak_hours: Vec::new(),
+        }
+    }
+
+    fn order(idx: u32, total_cycles: u64, timeout: u32) -> Arc<OrderRequest> {
+        let mut order = OrderRequest::new(
+            ProofRequest::new(
+                RequestId::new(Address::ZERO, idx),
+                Requirements::new(
+                    Digest::ZERO,
+                    Predicate { predicateType: PredicateType::PrefixMatch, data: Bytes::new() },
+                ),
+                "http://risczero.com/image",
+                RequestInput { inputType: RequestInputType::Inline, data: Bytes::new() },
+                Offer {
+                    minPrice: U256::from(1),
+                    maxPrice: U256::from(2),
+                    biddingStart: NOW,
+                    timeout,
+                    lockTimeout: timeout,
+                    rampUpPeriod: 1,
+                    lockStake: U256::ZERO,
+                },
+            ),
+            Bytes::new(),
+            FulfillmentType::LockAndFulfill,
+            Address::ZERO,
+            1,
+        );
+        order.total_cycles = Some(total_cycles);
+        Arc::new(order)
+    }
+
+    #[test]
+    fn max_percent_is_lowest_limit() {
+        let mut conf = conf(80);
+        assert_eq!(conf.max_percent(), Some(80));
+
+        conf.max_power_watts = Some(3_000);
+        conf.prover_power_watts = Some(6_000);
+        assert_eq!(conf.max_percent(), Some(50));
+
+        conf.max_duty_cycle_percent = None;
+        assert_eq!(conf.max_percent(), Some(50));
+
+        conf.prover_power_watts = None;
+        assert_eq!(conf.max_percent(), None);
+    }
+
+    #[test]
+    fn applies_during_peak_hours() {
+        let mut conf = conf(50);
+        assert!(conf.is_active(NOW));
+
+        // NOW is 22:13 UTC
+        conf.peak_hours = vec![22];
+        assert!(conf.is_active(NOW));
+        conf.peak_hours = vec![8, 9];
+        assert!(!conf.is_active(NOW));
+
+        let orders = vec![order(1, 1_000_000_000, 10_000)];
+        let (orders, duty_cycle) =
+            apply_duty_cycle_limits(orders, &conf, 1_000, 0, 1_000, 0, 0, NOW);
+        assert_eq!(orders.len(), 1);
+        assert_eq!(duty_cycle.projected, 1.0);
+    }
+
+    #[test]
+    fn defers_non_urgent_orders_over_limit() {
+        let conf = conf(50);
+        // 100s proofs at 1000 kHz; the last order can not wait out the window
+        let orders = vec![
+            order(1, 100_000_000, 10_000),
+            order(2, 100_000_000, 10_000),
+            order(3, 100_000_000, 1_000),
+        ];
+        let (orders, duty_cycle) =
+            apply_duty_cycle_limits(orders, &conf, 200, 150, 1_000, 0, 0, NOW);
+
+        let ids: Vec<_> = orders.iter().map(|order| order.request.id).collect();
+        assert_eq!(
+            ids,
+            [
+                U256::from(RequestId::new(Address::ZERO, 1)),
+                U256::from(RequestId::new(Address::ZERO, 3))
+            ]
+        );
+        assert_eq!(duty_cycle.actual, 0.2);
+        assert_eq!(duty_cycle.projected, 0.55);
+    }
+}
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use boundless_market::order_stream_client::OrderStreamErr;
+
 pub trait CodedError: std::error::Error {
     fn code(&self) -> &str;
 }
@@ -43,3 +45,17 @@ macro_rules! impl_coded_debug {
 }
 
 pub use impl_coded_debug;
+
+impl CodedError for OrderStreamErr {
+    fn code(&self) -> &str {
+        match self {
+            OrderStreamErr::Auth(_) => "[B-OS-001]",
+            OrderStreamErr::RateLimited { .. } => "[B-OS-002]",
+            OrderStreamErr::Network(_) => "[B-OS-003]",
+            OrderStreamErr::Protocol(_) => "[B-OS-004]",
+            OrderStreamErr::Validation(_) => "[B-OS-005]",
+            OrderStreamErr::NotFound => "[B-OS-006]",
+            _ => "[B-OS-500]",
+        }
+    }
+}
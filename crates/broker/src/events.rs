@@ -0,0 +1,478 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Broker-wide bus of typed events, published by the broker tasks.
+//!
+//! Tasks publish what happens to orders without knowing who listens: the order picker and the
+//! proving service follow the on-chain state of orders, the metrics are counted from the events,
+//! the admin API streams them, and they are optionally forwarded to a webhook, e.g. to alert
+//! operators of locked orders or low balances.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::{
+    utils::{parse_ether, parse_units},
+    Address, U256,
+};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    Mutex,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::EventWebhookConf,
+    errors::CodedError,
+    impl_coded_debug,
+    metrics::MetricsObj,
+    now_timestamp,
+    pricing_webhook::{post_signed, PRICING_WEBHOOK_TIMEOUT},
+    task::{RetryRes, RetryTask, SupervisorErr},
+    OrderStateChange, PricingDecision,
+};
+
+/// Number of events buffered for each subscriber, beyond which slow subscribers miss events.
+pub(crate) const EVENT_BUS_CAPACITY: usize = 4096;
+
+/// Names of the order events the webhook can be configured with.
+const ORDER_EVENTS: &[&str] = &[
+    "order_received",
+    "order_priced",
+    "order_skipped",
+    "order_locked",
+    "proof_started",
+    "proof_completed",
+    "order_fulfilled",
+    "order_failed",
+];
+
+/// Event published on the [EventBus].
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum BrokerEvent {
+    /// An order was received by the order picker, from any source
+    OrderReceived { order_id: String, request_id: U256, client: Address },
+    /// An order was priced and scheduled for locking or proving
+    OrderPriced { order_id: String, request_id: U256, decision: PricingDecision },
+    /// An order was skipped, for a skip reason or the code of the error that failed its pricing
+    OrderSkipped { order_id: String, request_id: U256, reason: String },
+    /// An order was locked by the broker
+    OrderLocked { order_id: String, request_id: U256, lock_price: U256 },
+    /// Proving of an order started, or resumed after being suspended
+    ProofStarted { order_id: String, proof_id: String },
+    /// An order was proven and is waiting to be aggregated
+    ProofCompleted { order_id: String, proof_id: String },
+    /// An order was fulfilled by the broker
+    OrderFulfilled { order_id: String },
+    /// An order failed and will not be fulfilled by the broker
+    OrderFailed { order_id: String, error: String },
+    /// The on-chain state of a request changed, or a change was reorged out
+    OrderStateChanged { change: OrderStateChange },
+    /// Gas balance of the signer on a chain, in the native token, checked while pricing
+    GasBalance { chain_id: u64, balance: U256 },
+    /// Stake balance of the prover on a chain, in stake token units of the given decimals
+    StakeBalance { chain_id: u64, balance: U256, decimals: u8 },
+}
+
+impl BrokerEvent {
+    /// Name of the event, as serialized in the `event` field.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            BrokerEvent::OrderReceived { .. } => "order_received",
+            BrokerEvent::OrderPriced { .. } => "order_priced",
+            BrokerEvent::OrderSkipped { .. } => "order_skipped",
+            BrokerEvent::OrderLocked { .. } => "order_locked",
+            BrokerEvent::ProofStarted { .. } => "proof_started",
+            BrokerEvent::ProofCompleted { .. } => "proof_completed",
+            BrokerEvent::OrderFulfilled { .. } => "order_fulfilled",
+            BrokerEvent::OrderFailed { .. } => "order_failed",
+            BrokerEvent::OrderStateChanged { .. } => "order_state_changed",
+            BrokerEvent::GasBalance { .. } => "gas_balance",
+            BrokerEvent::StakeBalance { .. } => "stake_balance",
+        }
+    }
+}
+
+/// Bus the broker tasks publish [BrokerEvent]s to, cheap to clone.
+///
+/// Publishing never blocks. Subscribers that fall more than [EVENT_BUS_CAPACITY] events behind
+/// miss the oldest events.
+#[derive(Clone, Debug)]
+pub(crate) struct EventBus {
+    tx: broadcast::Sender<BrokerEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(EVENT_BUS_CAPACITY)
+    }
+}
+
+impl EventBus {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self { tx }
+    }
+
+    /// Publish an event, returning whether any task is subscribed.
+    pub(crate) fn publish(&self, event: BrokerEvent) -> bool {
+        self.tx.send(event).is_ok()
+    }
+
+    pub(crate) fn subscribe(&self) -> EventReceiver {
+        EventReceiver { rx: self.tx.subscribe() }
+    }
+}
+
+/// Subscription to the events of an [EventBus].
+pub(crate) struct EventReceiver {
+    rx: broadcast::Receiver<BrokerEvent>,
+}
+
+impl EventReceiver {
+    /// Next event. Cancellation safe.
+    pub(crate) async fn recv(&mut self) -> Result<BrokerEvent, RecvError> {
+        self.rx.recv().await
+    }
+
+    /// Next change of the on-chain state of a request, skipping other events. Cancellation safe.
+    pub(crate) async fn recv_order_state(&mut self) -> Result<OrderStateChange, RecvError> {
+        loop {
+            if let BrokerEvent::OrderStateChanged { change } = self.rx.recv().await? {
+                return Ok(change);
+            }
+        }
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum EventBusErr {
+    #[error("{code} Event bus closed", code = self.code())]
+    BusClosed,
+
+    #[error("{code} Failed to build HTTP client: {0}", code = self.code())]
+    ClientErr(#[from] reqwest::Error),
+}
+
+impl_coded_debug!(EventBusErr);
+
+impl CodedError for EventBusErr {
+    fn code(&self) -> &str {
+        match self {
+            EventBusErr::BusClosed => "[B-EVT-001]",
+            EventBusErr::ClientErr(_) => "[B-EVT-002]",
+        }
+    }
+}
+
+/// Counts the order events of the bus in the metrics.
+pub(crate) struct EventMetrics {
+    // Subscribed on creation, so that no event is missed while the task starts or restarts
+    rx: Arc<Mutex<EventReceiver>>,
+    metrics: MetricsObj,
+}
+
+impl EventMetrics {
+    pub(crate) fn new(events: &EventBus, metrics: MetricsObj) -> Self {
+        Self { rx: Arc::new(Mutex::new(events.subscribe())), metrics }
+    }
+
+    fn record(metrics: &MetricsObj, event: &BrokerEvent) {
+        match event {
+            BrokerEvent::OrderReceived { .. } => metrics.record_order_received(),
+            BrokerEvent::OrderPriced { .. } => metrics.record_order_priced(),
+            BrokerEvent::OrderSkipped { reason, .. } => metrics.record_order_skipped(reason),
+            BrokerEvent::OrderLocked { .. } => metrics.record_order_locked(),
+            BrokerEvent::OrderFulfilled { .. } => metrics.record_fulfillments(1),
+            BrokerEvent::ProofStarted { .. }
+            | BrokerEvent::ProofCompleted { .. }
+            | BrokerEvent::OrderFailed { .. }
+            | BrokerEvent::OrderStateChanged { .. }
+            | BrokerEvent::GasBalance { .. }
+            | BrokerEvent::StakeBalance { .. } => {}
+        }
+    }
+}
+
+impl RetryTask for EventMetrics {
+    type Error = EventBusErr;
+    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
+        let rx = self.rx.clone();
+        let metrics = self.metrics.clone();
+        Box::pin(async move {
+            let mut rx = rx.lock().await;
+            loop {
+                let event = tokio::select! {
+                    event = rx.recv() => event,
+                    _ = cancel_token.cancelled() => return Ok(()),
+                };
+                match event {
+                    Ok(event) => Self::record(&metrics, &event),
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!("Metrics missed {missed} broker events");
+                    }
+                    Err(RecvError::Closed) => {
+                        return Err(SupervisorErr::Fault(EventBusErr::BusClosed))
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Whether balances were below the threshold of the webhook, by balance event and chain, so that
+/// a low balance is forwarded once until it is topped up.
+type BalanceState = HashMap<(&'static str, u64), bool>;
+
+/// Forwards the order events of the bus, and low balances, to the operator webhook, see
+/// [EventWebhookConf].
+pub(crate) struct EventWebhook {
+    conf: EventWebhookConf,
+    // Subscribed on creation, so that no event is missed while the task starts or restarts
+    rx: Arc<Mutex<EventReceiver>>,
+}
+
+impl EventWebhook {
+    pub(crate) fn new(conf: EventWebhookConf, events: &EventBus) -> Result<Self> {
+        for event in &conf.events {
+            if !ORDER_EVENTS.contains(&event.as_str()) {
+                tracing::warn!("Event webhook configured with unknown event {event}");
+            }
+        }
+        for threshold in [&conf.gas_balance_below, &conf.stake_balance_below].into_iter().flatten()
+        {
+            parse_ether(threshold).with_context(|| {
+                format!("Invalid balance threshold {threshold} of the event webhook")
+            })?;
+        }
+        Ok(Self { conf, rx: Arc::new(Mutex::new(events.subscribe())) })
+    }
+
+    /// Whether the event is forwarded to the webhook, recording balances in `below`.
+    fn forwarded(conf: &EventWebhookConf, below: &mut BalanceState, event: &BrokerEvent) -> bool {
+        let (chain_id, balance, decimals, threshold) = match event {
+            BrokerEvent::GasBalance { chain_id, balance } => {
+                (*chain_id, *balance, 18, conf.gas_balance_below.as_deref())
+            }
+            BrokerEvent::StakeBalance { chain_id, balance, decimals } => {
+                (*chain_id, *balance, *decimals, conf.stake_balance_below.as_deref())
+            }
+            BrokerEvent::OrderStateChanged { .. } => return false,
+            BrokerEvent::OrderSkipped { reason, .. }
+                if !conf.skip_reasons.is_empty() && !conf.skip_reasons.contains(reason) =>
+            {
+                return false
+            }
+            event => {
+                return conf.events.is_empty()
+                    || conf.events.iter().any(|name| name == event.name())
+            }
+        };
+
+        let Some(threshold) = threshold else {
+            return false;
+        };
+        let threshold: U256 = match parse_units(threshold, decimals) {
+            Ok(threshold) => threshold.into(),
+            Err(err) => {
+                tracing::debug!("Balance threshold of the event webhook not applicable: {err}");
+                return false;
+            }
+        };
+        let is_below = balance < threshold;
+        let was_below = below.insert((event.name(), chain_id), is_below).unwrap_or(false);
+        is_below && !was_below
+    }
+
+    /// Next event forwarded to the webhook. Cancellation safe.
+    async fn recv_forwarded(
+        rx: &mut EventReceiver,
+        conf: &EventWebhookConf,
+        below: &mut BalanceState,
+    ) -> Result<BrokerEvent, RecvError> {
+        loop {
+            let event = rx.recv().await?;
+            if Self::forwarded(conf, below, &event) {
+                return Ok(event);
+            }
+        }
+    }
+
+    async fn run(
+        conf: EventWebhookConf,
+        rx: Arc<Mutex<EventReceiver>>,
+        cancel_token: CancellationToken,
+    ) -> Result<(), EventBusErr> {
+        let client = reqwest::Client::builder().timeout(PRICING_WEBHOOK_TIMEOUT).build()?;
+        let flush_interval = Duration::from_secs(conf.flush_interval_secs);
+        let batch_size = conf.batch_size.max(1);
+        let conf = Arc::new(conf);
+        let mut below = BalanceState::new();
+        let mut rx = rx.lock().await;
+        loop {
+            let event = tokio::select! {
+                event = Self::recv_forwarded(&mut rx, &conf, &mut below) => event,
+                _ = cancel_token.cancelled() => return Ok(()),
+            };
+            let event = match event {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("Event webhook missed {missed} broker events");
+                    continue;
+                }
+                Err(RecvError::Closed) => return Err(EventBusErr::BusClosed),
+            };
+
+            let mut batch = vec![event];
+            let deadline = tokio::time::Instant::from_std(Instant::now() + flush_interval);
+            while batch.len() < batch_size {
+                match tokio::time::timeout_at(
+                    deadline,
+                    Self::recv_forwarded(&mut rx, &conf, &mut below),
+                )
+                .await
+                {
+                    Ok(Ok(event)) => batch.push(event),
+                    Ok(Err(RecvError::Lagged(missed))) => {
+                        tracing::warn!("Event webhook missed {missed} broker events");
+                    }
+                    Ok(Err(RecvError::Closed)) | Err(_) => break,
+                }
+            }
+
+            // Fire and forget, so that a slow webhook does not hold up the next batch
+            let (client, conf) = (client.clone(), conf.clone());
+            tokio::spawn(async move {
+                let count = batch.len();
+                match post_signed(
+                    &client,
+                    &conf.url,
+                    conf.secret.as_deref(),
+                    &batch,
+                    now_timestamp(),
+                )
+                .await
+                {
+                    Ok(()) => tracing::trace!("Sent {count} events to the webhook"),
+                    Err(err) => {
+                        tracing::warn!("Failed to send {count} events to the webhook: {err}")
+                    }
+                }
+            });
+        }
+    }
+}
+
+impl RetryTask for EventWebhook {
+    type Error = EventBusErr;
+    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
+        let conf = self.conf.clone();
+        let rx = self.rx.clone();
+        Box::pin(async move {
+            tracing::info!("Starting event webhook for {}", conf.url);
+            Self::run(conf, rx, cancel_token).await.map_err(|err| match err {
+                EventBusErr::BusClosed => SupervisorErr::Fault(err),
+                EventBusErr::ClientErr(_) => SupervisorErr::Recover(err),
+            })?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn filters_order_state_changes() {
+        let bus = EventBus::new(16);
+        assert!(!bus.publish(BrokerEvent::OrderFulfilled { order_id: "a".into() }));
+
+        let mut rx = bus.subscribe();
+        let change = OrderStateChange::Fulfilled { request_id: U256::from(1), block_number: 2 };
+        assert!(bus.publish(BrokerEvent::OrderFulfilled { order_id: "a".into() }));
+        assert!(bus.publish(BrokerEvent::OrderStateChanged { change: change.clone() }));
+        assert_eq!(rx.recv_order_state().await.unwrap(), change);
+
+        let event = BrokerEvent::OrderSkipped {
+            order_id: "a".into(),
+            request_id: U256::from(1),
+            reason: "price_too_low".into(),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], event.name());
+        assert_eq!(json["reason"], "price_too_low");
+    }
+
+    #[test]
+    fn filters_forwarded_events_and_balances() {
+        let conf = EventWebhookConf {
+            url: "https://example.com/events".parse().unwrap(),
+            secret: None,
+            batch_size: 10,
+            flush_interval_secs: 1,
+            events: vec!["order_locked".into(), "order_skipped".into()],
+            skip_reasons: vec!["insufficient_stake".into()],
+            gas_balance_below: Some("0.1".into()),
+            stake_balance_below: None,
+        };
+        let mut below = BalanceState::new();
+        let mut forwarded = |event: &BrokerEvent| EventWebhook::forwarded(&conf, &mut below, event);
+        let skipped = |reason: &str| BrokerEvent::OrderSkipped {
+            order_id: "a".into(),
+            request_id: U256::from(1),
+            reason: reason.into(),
+        };
+
+        // Order events are filtered by name and skip reason
+        assert!(forwarded(&BrokerEvent::OrderLocked {
+            order_id: "a".into(),
+            request_id: U256::from(1),
+            lock_price: U256::from(1),
+        }));
+        assert!(!forwarded(&skipped("price_too_low")));
+        assert!(forwarded(&skipped("insufficient_stake")));
+        assert!(!forwarded(&BrokerEvent::OrderFulfilled { order_id: "a".into() }));
+
+        // Low balances are forwarded once, until topped up
+        let gas = |balance: &str| BrokerEvent::GasBalance {
+            chain_id: 1,
+            balance: parse_ether(balance).unwrap(),
+        };
+        assert!(!forwarded(&gas("1")));
+        assert!(forwarded(&gas("0.05")));
+        assert!(!forwarded(&gas("0.04")));
+        assert!(!forwarded(&gas("0.2")));
+        assert!(forwarded(&gas("0.01")));
+        assert!(!forwarded(&BrokerEvent::StakeBalance {
+            chain_id: 1,
+            balance: U256::ZERO,
+            decimals: 6
+        }));
+
+        // All order events are forwarded without filters
+        let conf = EventWebhookConf { events: vec![], skip_reasons: vec![], ..conf };
+        assert!(EventWebhook::forwarded(
+            &conf,
+            &mut BalanceState::new(),
+            &BrokerEvent::OrderFulfilled { order_id: "a".into() }
+        ));
+    }
+}
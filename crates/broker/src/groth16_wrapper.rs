@@ -0,0 +1,129 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Post-processing stage wrapping the STARK receipts of orders that require Groth16 proofs.
+//!
+//! Wrapping runs on its own pool of `groth16_workers`, so that a burst of Groth16 orders queues
+//! here instead of holding up the proving of other orders. The observed wrapping latency,
+//! including the wait for a worker, is used by pricing to check that orders can be wrapped
+//! before their deadline.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::sync::Semaphore;
+
+use crate::provers::{ProverError, ProverObj};
+
+/// Number of completed wraps kept for estimating the wrapping latency.
+const LATENCY_SAMPLES: usize = 20;
+
+/// Wraps STARK receipts into Groth16 on a bounded pool of workers.
+pub(crate) struct Groth16Wrapper {
+    prover: ProverObj,
+    workers: usize,
+    permits: Semaphore,
+    /// Proofs waiting for a worker or being wrapped
+    queued: AtomicUsize,
+    wrap_secs: Mutex<VecDeque<f64>>,
+}
+
+pub(crate) type Groth16WrapperObj = Arc<Groth16Wrapper>;
+
+/// Decrements the count of queued proofs when dropped.
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Groth16Wrapper {
+    pub(crate) fn new(prover: ProverObj, workers: usize) -> Self {
+        let workers = workers.max(1);
+        Self {
+            prover,
+            workers,
+            permits: Semaphore::new(workers),
+            queued: AtomicUsize::new(0),
+            wrap_secs: Mutex::new(VecDeque::with_capacity(LATENCY_SAMPLES)),
+        }
+    }
+
+    /// Wrap the receipt of the STARK proof into Groth16 once a worker is free, returning the ID
+    /// of the compressed proof.
+    pub(crate) async fn wrap(&self, proof_id: &str) -> Result<String, ProverError> {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        let _queued = QueuedGuard(&self.queued);
+        let _permit = self.permits.acquire().await.map_err(|err| {
+            ProverError::ProverInternalError(format!("Groth16 worker pool closed: {err}"))
+        })?;
+
+        let start = Instant::now();
+        let compressed_proof_id = self.prover.compress(proof_id).await?;
+        let elapsed = start.elapsed();
+        tracing::debug!("Wrapped proof {proof_id} into Groth16 in {:.1}s", elapsed.as_secs_f64());
+        self.record(elapsed);
+        Ok(compressed_proof_id)
+    }
+
+    fn record(&self, elapsed: Duration) {
+        let mut wrap_secs = self.wrap_secs.lock().unwrap();
+        if wrap_secs.len() == LATENCY_SAMPLES {
+            wrap_secs.pop_front();
+        }
+        wrap_secs.push_back(elapsed.as_secs_f64());
+    }
+
+    /// Expected seconds to wrap a proof submitted now, waiting for the proofs ahead of it, if
+    /// any wraps have completed.
+    pub(crate) fn expected_secs(&self) -> Option<u64> {
+        let average = {
+            let wrap_secs = self.wrap_secs.lock().unwrap();
+            if wrap_secs.is_empty() {
+                return None;
+            }
+            wrap_secs.iter().sum::<f64>() / wrap_secs.len() as f64
+        };
+        let rounds = self.queued.load(Ordering::Relaxed) / self.workers + 1;
+        Some((average * rounds as f64).ceil() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provers::DefaultProver;
+
+    #[test]
+    fn expected_secs_include_queue() {
+        let wrapper = Groth16Wrapper::new(Arc::new(DefaultProver::new()), 2);
+        assert_eq!(wrapper.expected_secs(), None);
+
+        wrapper.record(Duration::from_secs(10));
+        wrapper.record(Duration::from_secs(20));
+        assert_eq!(wrapper.expected_secs(), Some(15));
+
+        // A proof submitted now waits for a worker behind the queued proofs
+        wrapper.queued.store(3, Ordering::Relaxed);
+        assert_eq!(wrapper.expected_secs(), Some(30));
+    }
+}
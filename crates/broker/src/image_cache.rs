@@ -0,0 +1,253 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Content-addressed cache of guest images on local disk, see [ImageCacheConf].
+//!
+//! Images are stored in files named by their image ID, and verified against it when read, so
+//! that an image the prover backend no longer has is uploaded again without downloading it.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use risc0_zkvm::sha::Digest;
+
+use crate::config::ImageCacheConf;
+
+/// Extension of cached image files.
+const IMAGE_EXTENSION: &str = "elf";
+
+struct CachedImage {
+    bytes: u64,
+    /// Logical time the image was last stored or read, for LRU eviction
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheIndex {
+    images: HashMap<Digest, CachedImage>,
+    total_bytes: u64,
+    clock: u64,
+}
+
+impl CacheIndex {
+    fn touch(&mut self, image_id: &Digest) {
+        self.clock += 1;
+        if let Some(image) = self.images.get_mut(image_id) {
+            image.last_used = self.clock;
+        }
+    }
+
+    fn insert(&mut self, image_id: Digest, bytes: u64) {
+        self.clock += 1;
+        let image = CachedImage { bytes, last_used: self.clock };
+        if let Some(replaced) = self.images.insert(image_id, image) {
+            self.total_bytes -= replaced.bytes;
+        }
+        self.total_bytes += bytes;
+    }
+
+    fn remove(&mut self, image_id: &Digest) {
+        if let Some(image) = self.images.remove(image_id) {
+            self.total_bytes -= image.bytes;
+        }
+    }
+
+    /// Remove the least recently used images until the cache fits in `max_bytes`, returning them.
+    fn evict(&mut self, max_bytes: u64) -> Vec<Digest> {
+        let mut evicted = vec![];
+        while self.total_bytes > max_bytes {
+            let Some(image_id) =
+                self.images.iter().min_by_key(|(_, image)| image.last_used).map(|(id, _)| *id)
+            else {
+                break;
+            };
+            self.remove(&image_id);
+            evicted.push(image_id);
+        }
+        evicted
+    }
+}
+
+/// Guest images stored on disk by image ID, evicted least recently used first.
+pub(crate) struct ImageCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<CacheIndex>,
+}
+
+pub(crate) type ImageCacheObj = Arc<ImageCache>;
+
+fn parse_image_file(path: &Path) -> Option<Digest> {
+    if path.extension()? != IMAGE_EXTENSION {
+        return None;
+    }
+    let bytes = hex::decode(path.file_stem()?.to_str()?).ok()?;
+    Digest::try_from(bytes.as_slice()).ok()
+}
+
+impl ImageCache {
+    /// Open the cache in the configured directory, indexing the images already stored there.
+    ///
+    /// Images stored earlier are ordered by modification time, oldest evicted first.
+    pub(crate) async fn open(conf: &ImageCacheConf) -> Result<Self> {
+        tokio::fs::create_dir_all(&conf.dir)
+            .await
+            .with_context(|| format!("Failed to create image cache dir {}", conf.dir.display()))?;
+
+        let mut stored = vec![];
+        let mut entries = tokio::fs::read_dir(&conf.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            // Left over from a write that did not complete
+            if path.extension().is_some_and(|ext| ext == "tmp") {
+                tokio::fs::remove_file(&path).await?;
+                continue;
+            }
+            let Some(image_id) = parse_image_file(&path) else {
+                continue;
+            };
+            let metadata = entry.metadata().await?;
+            stored.push((metadata.modified().ok(), image_id, metadata.len()));
+        }
+        stored.sort_unstable_by_key(|(modified, ..)| *modified);
+
+        let mut index = CacheIndex::default();
+        for (_, image_id, bytes) in stored {
+            index.insert(image_id, bytes);
+        }
+        let cache = Self {
+            dir: conf.dir.clone(),
+            max_bytes: conf.max_size_mb.saturating_mul(1024 * 1024),
+            index: Mutex::new(index),
+        };
+        tracing::info!(
+            "Opened image cache in {} with {} images",
+            cache.dir.display(),
+            cache.index.lock().unwrap().images.len()
+        );
+        cache.evict().await;
+        Ok(cache)
+    }
+
+    fn path(&self, image_id: &Digest) -> PathBuf {
+        self.dir.join(format!("{image_id}.{IMAGE_EXTENSION}"))
+    }
+
+    /// Read the image from the cache, if stored and intact.
+    ///
+    /// Images that fail to read or no longer match their image ID are removed.
+    pub(crate) async fn get(&self, image_id: &Digest) -> Option<Vec<u8>> {
+        if !self.index.lock().unwrap().images.contains_key(image_id) {
+            return None;
+        }
+        let verified = match tokio::fs::read(self.path(image_id)).await {
+            Ok(image) => match risc0_zkvm::compute_image_id(&image) {
+                Ok(computed) if computed == *image_id => Ok(image),
+                Ok(computed) => Err(anyhow::anyhow!("image ID {computed} does not match")),
+                Err(err) => Err(err),
+            },
+            Err(err) => Err(err.into()),
+        };
+        match verified {
+            Ok(image) => {
+                self.index.lock().unwrap().touch(image_id);
+                Some(image)
+            }
+            Err(err) => {
+                tracing::warn!("Removing corrupt image {image_id} from the image cache: {err}");
+                self.index.lock().unwrap().remove(image_id);
+                self.remove_file(image_id).await;
+                None
+            }
+        }
+    }
+
+    /// Store an image that was verified to have the given image ID.
+    ///
+    /// Images larger than the cache are not stored.
+    pub(crate) async fn put(&self, image_id: &Digest, image: &[u8]) -> Result<()> {
+        let bytes = image.len() as u64;
+        if bytes > self.max_bytes {
+            tracing::debug!("Not caching image {image_id} of {bytes} bytes, larger than the cache");
+            return Ok(());
+        }
+        // Written to a temporary file first, so a partially written image is never indexed
+        let path = self.path(image_id);
+        let tmp_path = self.dir.join(format!("{image_id}-{}.tmp", uuid::Uuid::new_v4()));
+        tokio::fs::write(&tmp_path, image)
+            .await
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, &path)
+            .await
+            .with_context(|| format!("Failed to move image into {}", path.display()))?;
+
+        self.index.lock().unwrap().insert(*image_id, bytes);
+        self.evict().await;
+        Ok(())
+    }
+
+    async fn evict(&self) {
+        let evicted = self.index.lock().unwrap().evict(self.max_bytes);
+        for image_id in evicted {
+            tracing::debug!("Evicting image {image_id} from the image cache");
+            self.remove_file(&image_id).await;
+        }
+    }
+
+    async fn remove_file(&self, image_id: &Digest) {
+        if let Err(err) = tokio::fs::remove_file(self.path(image_id)).await {
+            tracing::warn!("Failed to remove image {image_id} from the image cache: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boundless_market_test_utils::{ECHO_ELF, ECHO_ID, LOOP_ELF, LOOP_ID};
+
+    #[tokio::test]
+    async fn stores_verifies_and_evicts_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = ImageCacheConf { dir: dir.path().to_path_buf(), max_size_mb: 64 };
+        let echo_id = Digest::from(ECHO_ID);
+        let loop_id = Digest::from(LOOP_ID);
+
+        let cache = ImageCache::open(&conf).await.unwrap();
+        assert_eq!(cache.get(&echo_id).await, None);
+        cache.put(&echo_id, ECHO_ELF).await.unwrap();
+        assert_eq!(cache.get(&echo_id).await.unwrap(), ECHO_ELF);
+
+        // Images stored earlier are indexed when the cache is opened again
+        let cache = ImageCache::open(&conf).await.unwrap();
+        assert_eq!(cache.get(&echo_id).await.unwrap(), ECHO_ELF);
+
+        // Corrupt images are removed
+        tokio::fs::write(cache.path(&echo_id), b"corrupt").await.unwrap();
+        assert_eq!(cache.get(&echo_id).await, None);
+        assert!(!cache.path(&echo_id).exists());
+
+        // The least recently used image is evicted once the cache is full
+        let max_bytes = (ECHO_ELF.len() + LOOP_ELF.len() - 1) as u64;
+        let cache = ImageCache { max_bytes, ..ImageCache::open(&conf).await.unwrap() };
+        cache.put(&echo_id, ECHO_ELF).await.unwrap();
+        cache.put(&loop_id, LOOP_ELF).await.unwrap();
+        assert_eq!(cache.get(&echo_id).await, None);
+        assert_eq!(cache.get(&loop_id).await.unwrap(), LOOP_ELF);
+    }
+}
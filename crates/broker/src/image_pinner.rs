@@ -0,0 +1,223 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Eager download of the images listed in the config, see [PinnedImageConf].
+//!
+//! Pinned images are fetched, verified and uploaded to the prover when the broker starts, and
+//! checked again periodically, so that the first orders for popular applications are not priced
+//! after a cold download.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use risc0_zkvm::sha::Digest;
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{ConfigErr, ConfigLock, PinnedImageConf},
+    errors::CodedError,
+    image_cache::ImageCacheObj,
+    impl_coded_debug,
+    provers::ProverObj,
+    storage::create_uri_handler,
+    task::{RetryRes, RetryTask, SupervisorErr},
+};
+
+#[derive(Error)]
+pub enum ImagePinnerErr {
+    #[error("{code} Config error {0}", code = self.code())]
+    ConfigReadErr(#[from] ConfigErr),
+}
+
+impl_coded_debug!(ImagePinnerErr);
+
+impl CodedError for ImagePinnerErr {
+    fn code(&self) -> &str {
+        match self {
+            ImagePinnerErr::ConfigReadErr(_) => "[B-PIN-001]",
+        }
+    }
+}
+
+fn parse_image_id(id: &str) -> anyhow::Result<Digest> {
+    let bytes = hex::decode(id.trim_start_matches("0x"))?;
+    Digest::try_from(bytes.as_slice()).map_err(|_| anyhow::anyhow!("expected 32 bytes"))
+}
+
+/// Keeps the pinned images uploaded to the prover.
+#[derive(Clone)]
+pub(crate) struct ImagePinner {
+    config: ConfigLock,
+    prover: ProverObj,
+    /// Image IDs of the pinned images configured without one, by URL
+    resolved: Arc<Mutex<HashMap<String, Digest>>>,
+    image_cache: Option<ImageCacheObj>,
+}
+
+impl ImagePinner {
+    pub(crate) fn new(config: ConfigLock, prover: ProverObj) -> Self {
+        Self { config, prover, resolved: Default::default(), image_cache: None }
+    }
+
+    /// Read and store pinned images in the given cache, shared with the order picker.
+    pub(crate) fn with_image_cache(self, image_cache: ImageCacheObj) -> Self {
+        Self { image_cache: Some(image_cache), ..self }
+    }
+
+    /// Upload the image to the prover if it does not have it, returning whether it was uploaded.
+    async fn pin(&self, image: &PinnedImageConf) -> anyhow::Result<bool> {
+        let expected = image
+            .image_id
+            .as_deref()
+            .map(parse_image_id)
+            .transpose()
+            .with_context(|| format!("Invalid image ID for pinned image {}", image.url))?;
+        let known = match expected {
+            Some(image_id) => Some(image_id),
+            None => self.resolved.lock().await.get(&image.url).copied(),
+        };
+        if let Some(image_id) = known {
+            if self.prover.has_image(&image_id.to_string()).await? {
+                return Ok(false);
+            }
+            let cached = match &self.image_cache {
+                Some(image_cache) => image_cache.get(&image_id).await,
+                None => None,
+            };
+            if let Some(image_data) = cached {
+                self.prover
+                    .upload_image(&image_id.to_string(), image_data)
+                    .await
+                    .context("Failed to upload image to prover")?;
+                return Ok(true);
+            }
+        }
+
+        let uri = create_uri_handler(&image.url, &self.config, false)
+            .await
+            .context("URL handling failed")?;
+        let image_data = uri
+            .fetch()
+            .await
+            .with_context(|| format!("Failed to fetch pinned image {}", image.url))?;
+        let image_id = risc0_zkvm::compute_image_id(&image_data)
+            .with_context(|| format!("Failed to compute image ID of {}", image.url))?;
+        if let Some(expected) = expected {
+            anyhow::ensure!(
+                image_id == expected,
+                "image ID of {} does not match the config; expect {expected}, got {image_id}",
+                image.url
+            );
+        }
+
+        if let Some(image_cache) = &self.image_cache {
+            if let Err(err) = image_cache.put(&image_id, &image_data).await {
+                tracing::warn!(
+                    "Failed to store pinned image {} in the image cache: {err:?}",
+                    image.url
+                );
+            }
+        }
+        self.prover
+            .upload_image(&image_id.to_string(), image_data)
+            .await
+            .context("Failed to upload image to prover")?;
+        self.resolved.lock().await.insert(image.url.clone(), image_id);
+        Ok(true)
+    }
+
+    /// Pin each configured image, logging the failures so that one unavailable image does not
+    /// hold up the others.
+    async fn pin_all(&self) -> Result<Duration, ImagePinnerErr> {
+        let (images, interval) = {
+            let config = self.config.lock_all()?;
+            (config.prover.pinned_images.clone(), config.prover.pinned_image_check_secs)
+        };
+        for image in &images {
+            match self.pin(image).await {
+                Ok(true) => tracing::info!("Pinned image {} in the prover", image.url),
+                Ok(false) => tracing::trace!("Pinned image {} already in the prover", image.url),
+                Err(err) => {
+                    tracing::warn!("[B-PIN-100] Failed to pin image {}: {err:?}", image.url)
+                }
+            }
+        }
+        Ok(Duration::from_secs(interval.max(1)))
+    }
+
+    async fn run(&self, cancel_token: CancellationToken) -> Result<(), ImagePinnerErr> {
+        loop {
+            let interval = self.pin_all().await?;
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = cancel_token.cancelled() => return Ok(()),
+            }
+        }
+    }
+}
+
+impl RetryTask for ImagePinner {
+    type Error = ImagePinnerErr;
+    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
+        let pinner = self.clone();
+        Box::pin(async move {
+            tracing::info!("Starting image pinner");
+            pinner.run(cancel_token).await.map_err(SupervisorErr::Recover)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provers::DefaultProver;
+    use boundless_market_test_utils::{ECHO_ELF, ECHO_ID};
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn pins_configured_images() {
+        let server = MockServer::start_async().await;
+        let image = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/echo");
+                then.status(200).body(ECHO_ELF);
+            })
+            .await;
+
+        let config = ConfigLock::default();
+        let echo_id = Digest::from(ECHO_ID);
+        config.load_write().unwrap().prover.pinned_images =
+            vec![PinnedImageConf { url: server.url("/echo"), image_id: Some(echo_id.to_string()) }];
+        let prover: ProverObj = Arc::new(DefaultProver::new());
+        let pinner = ImagePinner::new(config.clone(), prover.clone());
+
+        pinner.pin_all().await.unwrap();
+        assert!(prover.has_image(&echo_id.to_string()).await.unwrap());
+        image.assert_hits_async(1).await;
+
+        // Images already in the prover are not downloaded again
+        pinner.pin_all().await.unwrap();
+        image.assert_hits_async(1).await;
+
+        // Images configured without an ID are downloaded once to resolve it
+        config.load_write().unwrap().prover.pinned_images =
+            vec![PinnedImageConf { url: server.url("/echo"), image_id: None }];
+        pinner.pin_all().await.unwrap();
+        pinner.pin_all().await.unwrap();
+        image.assert_hits_async(2).await;
+    }
+}
@@ -0,0 +1,706 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of market events, and the normalized view of market requests indexed from them.
+//!
+//! The view is stored in the `market_requests` table of the broker DB, so that it can be read by
+//! the admin API and by analytics tools without decoding logs again.
+//!
+//! Lock, fulfillment and slashing events are also appended to the `market_events` log, along with
+//! a checkpoint of the last block whose events were broadcast to the other services. After
+//! downtime, the market monitor fetches the events emitted since the checkpoint into the log and
+//! replays them, so that [crate::OrderStateChange] messages are not lost while the broker is down.
+//!
+//! When the chain monitor reports a reorg, the events logged from the first replaced block on
+//! are reverted: they are removed from the log, the lock and fulfillment tables and the view, and
+//! their state changes are broadcast as reverted. The events of the new chain are then fetched
+//! and recorded again.
+
+use alloy::{
+    primitives::{Address, Bytes, B256, U256},
+    rpc::types::Log,
+    sol_types::SolEvent,
+};
+use boundless_market::contracts::{IBoundlessMarket, ProofRequest, RequestId};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    db::{DbError, DbObj},
+    events::{BrokerEvent, EventBus},
+    OrderStateChange,
+};
+
+/// Market event in the lifecycle of a request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum MarketEvent {
+    /// The request was submitted on-chain
+    Submitted { request: Box<ProofRequest>, client_signature: Bytes },
+    /// The request was locked by the given prover
+    Locked { prover: Address, request: Box<ProofRequest>, client_signature: Bytes },
+    /// The request was fulfilled by the given prover
+    Fulfilled { prover: Address },
+    /// The prover that locked the request was slashed for not fulfilling it before the lock
+    /// expired
+    Slashed { stake_burned: U256, stake_transferred: U256, stake_recipient: Address },
+}
+
+impl MarketEvent {
+    /// Whether the event is appended to the event log.
+    fn is_logged(&self) -> bool {
+        !matches!(self, MarketEvent::Submitted { .. })
+    }
+}
+
+impl From<&IBoundlessMarket::RequestSubmitted> for MarketEvent {
+    fn from(event: &IBoundlessMarket::RequestSubmitted) -> Self {
+        Self::Submitted {
+            request: Box::new(event.request.clone()),
+            client_signature: event.clientSignature.clone(),
+        }
+    }
+}
+
+impl From<&IBoundlessMarket::RequestLocked> for MarketEvent {
+    fn from(event: &IBoundlessMarket::RequestLocked) -> Self {
+        Self::Locked {
+            prover: event.prover,
+            request: Box::new(event.request.clone()),
+            client_signature: event.clientSignature.clone(),
+        }
+    }
+}
+
+impl From<&IBoundlessMarket::RequestFulfilled> for MarketEvent {
+    fn from(event: &IBoundlessMarket::RequestFulfilled) -> Self {
+        Self::Fulfilled { prover: event.prover }
+    }
+}
+
+impl From<&IBoundlessMarket::ProverSlashed> for MarketEvent {
+    fn from(event: &IBoundlessMarket::ProverSlashed) -> Self {
+        Self::Slashed {
+            stake_burned: event.stakeBurned,
+            stake_transferred: event.stakeTransferred,
+            stake_recipient: event.stakeRecipient,
+        }
+    }
+}
+
+/// Decode a market log into the ID of the request and the event.
+///
+/// Returns `None` for logs that are not request lifecycle events.
+pub(crate) fn decode_log(log: &Log) -> alloy::sol_types::Result<Option<(U256, MarketEvent)>> {
+    let Some(topic) = log.topic0() else {
+        return Ok(None);
+    };
+    let decoded = if *topic == IBoundlessMarket::RequestSubmitted::SIGNATURE_HASH {
+        let event = log.log_decode::<IBoundlessMarket::RequestSubmitted>()?.inner.data;
+        (U256::from(event.requestId), MarketEvent::from(&event))
+    } else if *topic == IBoundlessMarket::RequestLocked::SIGNATURE_HASH {
+        let event = log.log_decode::<IBoundlessMarket::RequestLocked>()?.inner.data;
+        (U256::from(event.requestId), MarketEvent::from(&event))
+    } else if *topic == IBoundlessMarket::RequestFulfilled::SIGNATURE_HASH {
+        let event = log.log_decode::<IBoundlessMarket::RequestFulfilled>()?.inner.data;
+        (U256::from(event.requestId), MarketEvent::from(&event))
+    } else if *topic == IBoundlessMarket::ProverSlashed::SIGNATURE_HASH {
+        let event = log.log_decode::<IBoundlessMarket::ProverSlashed>()?.inner.data;
+        (U256::from(event.requestId), MarketEvent::from(&event))
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(decoded))
+}
+
+/// Market event along with the position of its log on the chain, as stored in the event log.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct MarketEventRecord {
+    pub(crate) chain_id: u64,
+    pub(crate) request_id: U256,
+    pub(crate) block_number: u64,
+    pub(crate) log_index: u64,
+    pub(crate) tx_hash: Option<B256>,
+    pub(crate) event: MarketEvent,
+}
+
+impl MarketEventRecord {
+    /// Record of an event decoded from the given log.
+    ///
+    /// Returns `None` for logs of pending blocks, which have no position on the chain yet.
+    pub(crate) fn new(
+        chain_id: u64,
+        request_id: U256,
+        event: MarketEvent,
+        log: &Log,
+    ) -> Option<Self> {
+        Some(Self {
+            chain_id,
+            request_id,
+            block_number: log.block_number?,
+            log_index: log.log_index?,
+            tx_hash: log.transaction_hash,
+            event,
+        })
+    }
+
+    /// The state change of the request to broadcast to the other services, if any.
+    pub(crate) fn state_change(&self) -> Option<OrderStateChange> {
+        let (request_id, block_number) = (self.request_id, self.block_number);
+        match self.event {
+            MarketEvent::Locked { prover, .. } => {
+                Some(OrderStateChange::Locked { request_id, prover, block_number })
+            }
+            MarketEvent::Fulfilled { .. } => {
+                Some(OrderStateChange::Fulfilled { request_id, block_number })
+            }
+            MarketEvent::Submitted { .. } | MarketEvent::Slashed { .. } => None,
+        }
+    }
+
+    /// The state change to broadcast when the event is reorged out, if any.
+    pub(crate) fn reverted_state_change(&self) -> Option<OrderStateChange> {
+        let (request_id, block_number) = (self.request_id, self.block_number);
+        match self.event {
+            MarketEvent::Locked { prover, .. } => {
+                Some(OrderStateChange::LockReverted { request_id, prover, block_number })
+            }
+            MarketEvent::Fulfilled { .. } => {
+                Some(OrderStateChange::FulfillmentReverted { request_id, block_number })
+            }
+            MarketEvent::Submitted { .. } | MarketEvent::Slashed { .. } => None,
+        }
+    }
+}
+
+/// Furthest lifecycle stage of a market request that was indexed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MarketRequestStatus {
+    Submitted,
+    Locked,
+    Fulfilled,
+}
+
+/// Normalized view of a market request and its lifecycle.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct MarketRequest {
+    pub(crate) request_id: U256,
+    pub(crate) client: Address,
+    pub(crate) status: MarketRequestStatus,
+    /// Populated once the submission or lock of the request is indexed
+    pub(crate) min_price: Option<U256>,
+    pub(crate) max_price: Option<U256>,
+    pub(crate) lock_stake: Option<U256>,
+    pub(crate) lock_expires_at: Option<u64>,
+    pub(crate) expires_at: Option<u64>,
+    /// Block the request was submitted on-chain at, unset for off-chain requests
+    pub(crate) submitted_block: Option<u64>,
+    pub(crate) locker: Option<Address>,
+    pub(crate) locked_block: Option<u64>,
+    pub(crate) fulfiller: Option<Address>,
+    pub(crate) fulfilled_block: Option<u64>,
+    /// Block the locker of the request was slashed at
+    #[serde(default)]
+    pub(crate) slashed_block: Option<u64>,
+}
+
+impl MarketRequest {
+    fn new(request_id: U256) -> Self {
+        Self {
+            request_id,
+            client: RequestId::from_lossy(request_id).addr,
+            status: MarketRequestStatus::Submitted,
+            min_price: None,
+            max_price: None,
+            lock_stake: None,
+            lock_expires_at: None,
+            expires_at: None,
+            submitted_block: None,
+            locker: None,
+            locked_block: None,
+            fulfiller: None,
+            fulfilled_block: None,
+            slashed_block: None,
+        }
+    }
+
+    fn set_request(&mut self, request: &ProofRequest) {
+        self.min_price = Some(request.offer.minPrice);
+        self.max_price = Some(request.offer.maxPrice);
+        self.lock_stake = Some(request.offer.lockStake);
+        self.lock_expires_at = Some(request.lock_expires_at());
+        self.expires_at = Some(request.expires_at());
+    }
+
+    /// Apply an event, which may arrive out of order with the other events of the request.
+    fn apply(&mut self, block_number: u64, event: &MarketEvent) {
+        let status = match event {
+            MarketEvent::Submitted { request, .. } => {
+                self.set_request(request);
+                self.submitted_block = Some(block_number);
+                MarketRequestStatus::Submitted
+            }
+            MarketEvent::Locked { prover, request, .. } => {
+                self.set_request(request);
+                self.locker = Some(*prover);
+                self.locked_block = Some(block_number);
+                MarketRequestStatus::Locked
+            }
+            MarketEvent::Fulfilled { prover } => {
+                self.fulfiller = Some(*prover);
+                self.fulfilled_block = Some(block_number);
+                MarketRequestStatus::Fulfilled
+            }
+            MarketEvent::Slashed { .. } => {
+                self.slashed_block = Some(block_number);
+                return;
+            }
+        };
+        self.status = self.status.max(status);
+    }
+
+    /// Undo an event emitted in the given block, after it was reorged out.
+    fn revert(&mut self, block_number: u64, event: &MarketEvent) {
+        match event {
+            MarketEvent::Submitted { .. } => {
+                if self.submitted_block == Some(block_number) {
+                    self.submitted_block = None;
+                }
+            }
+            MarketEvent::Locked { .. } => {
+                if self.locked_block == Some(block_number) {
+                    self.locker = None;
+                    self.locked_block = None;
+                }
+            }
+            MarketEvent::Fulfilled { .. } => {
+                if self.fulfilled_block == Some(block_number) {
+                    self.fulfiller = None;
+                    self.fulfilled_block = None;
+                }
+            }
+            MarketEvent::Slashed { .. } => {
+                if self.slashed_block == Some(block_number) {
+                    self.slashed_block = None;
+                }
+            }
+        }
+        self.status = if self.fulfilled_block.is_some() {
+            MarketRequestStatus::Fulfilled
+        } else if self.locked_block.is_some() {
+            MarketRequestStatus::Locked
+        } else {
+            MarketRequestStatus::Submitted
+        };
+    }
+}
+
+/// Outcomes of the indexed requests locked by a prover.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LockerStats {
+    /// Requests the prover fulfilled, without being slashed
+    pub(crate) fulfilled: u64,
+    /// Requests the prover was slashed for, or that another prover fulfilled
+    pub(crate) missed: u64,
+}
+
+impl LockerStats {
+    /// Share of the settled locks of the prover that it missed, in percent, or `None` if fewer
+    /// than `min_locks` locks are settled.
+    pub(crate) fn miss_percent(&self, min_locks: u64) -> Option<u64> {
+        let settled = self.fulfilled + self.missed;
+        (settled > 0 && settled >= min_locks).then(|| self.missed * 100 / settled)
+    }
+}
+
+/// Persists market events into the DB, both into the view of market requests and the lock and
+/// fulfillment tables queried by the broker.
+pub(crate) struct MarketIndexer {
+    db: DbObj,
+    /// Serializes updates, as events of the same request are processed concurrently
+    update_lock: Mutex<()>,
+}
+
+impl MarketIndexer {
+    pub(crate) fn new(db: DbObj) -> Self {
+        Self { db, update_lock: Mutex::new(()) }
+    }
+
+    /// Index an event emitted in the given block, returning the updated view of the request.
+    pub(crate) async fn index(
+        &self,
+        request_id: U256,
+        block_number: u64,
+        event: &MarketEvent,
+    ) -> Result<MarketRequest, DbError> {
+        let _guard = self.update_lock.lock().await;
+        match event {
+            MarketEvent::Locked { prover, .. } => {
+                self.db.set_request_locked(request_id, &prover.to_string(), block_number).await?
+            }
+            MarketEvent::Fulfilled { .. } => {
+                self.db.set_request_fulfilled(request_id, block_number).await?
+            }
+            MarketEvent::Submitted { .. } | MarketEvent::Slashed { .. } => {}
+        }
+
+        let mut request = match self.db.get_market_request(request_id).await? {
+            Some(request) => request,
+            None => MarketRequest::new(request_id),
+        };
+        request.apply(block_number, event);
+        self.db.set_market_request(&request).await?;
+        Ok(request)
+    }
+
+    /// Append the event to the event log, unless it is a submission, and index it.
+    ///
+    /// Returns whether the event was not in the log yet. Submissions are always reported as new.
+    pub(crate) async fn record(&self, record: &MarketEventRecord) -> Result<bool, DbError> {
+        let new = !record.event.is_logged() || self.db.add_market_event(record).await?;
+        self.index(record.request_id, record.block_number, &record.event).await?;
+        Ok(new)
+    }
+
+    /// Broadcast the state change of a recorded event, and move the checkpoint up to the block
+    /// before the event, as other events of its block may not be recorded yet.
+    pub(crate) async fn broadcast(
+        &self,
+        record: &MarketEventRecord,
+        events: &EventBus,
+    ) -> Result<(), DbError> {
+        send_state_change(record, events);
+        self.db.set_indexer_checkpoint(record.chain_id, record.block_number.saturating_sub(1)).await
+    }
+
+    /// Revert the events logged on the chain from the given block on, after they were reorged
+    /// out, returning them in chain order.
+    ///
+    /// The locks and fulfillments of the events are removed, unless they were recorded at
+    /// another block, and the checkpoint is moved back to the block before.
+    pub(crate) async fn revert(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+    ) -> Result<Vec<MarketEventRecord>, DbError> {
+        let _guard = self.update_lock.lock().await;
+        let records = self.db.revert_market_events(chain_id, from_block).await?;
+        for record in &records {
+            let (request_id, block_number) = (record.request_id, record.block_number);
+            match record.event {
+                MarketEvent::Locked { .. } => {
+                    let lock = self.db.get_request_locked(request_id).await?;
+                    if lock.is_some_and(|(_, locked_block)| locked_block == block_number) {
+                        self.db.clear_request_locked(request_id).await?;
+                    }
+                }
+                MarketEvent::Fulfilled { .. } => {
+                    let fulfilled_block = self.db.get_request_fulfilled_block(request_id).await?;
+                    if fulfilled_block == Some(block_number) {
+                        self.db.clear_request_fulfilled(request_id).await?;
+                    }
+                }
+                MarketEvent::Submitted { .. } | MarketEvent::Slashed { .. } => {}
+            }
+            if let Some(mut request) = self.db.get_market_request(request_id).await? {
+                request.revert(block_number, &record.event);
+                self.db.set_market_request(&request).await?;
+            }
+        }
+        Ok(records)
+    }
+
+    /// The last block of the chain whose events were broadcast, if any.
+    pub(crate) async fn checkpoint(&self, chain_id: u64) -> Result<Option<u64>, DbError> {
+        self.db.get_indexer_checkpoint(chain_id).await
+    }
+
+    /// Broadcast the state changes of the events logged on the chain between the given blocks,
+    /// and move the checkpoint to the last of them. Returns the number of state changes sent.
+    pub(crate) async fn replay(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+        to_block: u64,
+        events: &EventBus,
+    ) -> Result<usize, DbError> {
+        let records = self.db.get_market_events(chain_id, from_block).await?;
+        let replayed = records
+            .iter()
+            .filter(|record| record.block_number <= to_block)
+            .filter(|record| send_state_change(record, events))
+            .count();
+        self.db.set_indexer_checkpoint(chain_id, to_block).await?;
+        Ok(replayed)
+    }
+}
+
+/// Send the state change of the event, if any. Returns whether one was sent.
+fn send_state_change(record: &MarketEventRecord, events: &EventBus) -> bool {
+    send(record.request_id, record.state_change(), events)
+}
+
+/// Send the state change of the event reorged out, if any. Returns whether one was sent.
+pub(crate) fn send_reverted_state_change(record: &MarketEventRecord, events: &EventBus) -> bool {
+    send(record.request_id, record.reverted_state_change(), events)
+}
+
+fn send(request_id: U256, state_change: Option<OrderStateChange>, events: &EventBus) -> bool {
+    let Some(change) = state_change else {
+        return false;
+    };
+    // Fails only when no service is subscribed, e.g. when the broker is starting up
+    if !events.publish(BrokerEvent::OrderStateChanged { change }) {
+        tracing::warn!("No service subscribed to the state change of request 0x{request_id:x}");
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::db::SqliteDb;
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, RequestInput, RequestInputType, Requirements,
+    };
+    use risc0_zkvm::sha::Digest;
+    use sqlx::SqlitePool;
+
+    fn proof_request(client: Address) -> ProofRequest {
+        ProofRequest::new(
+            RequestId::new(client, 1),
+            Requirements::new(
+                Digest::ZERO,
+                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+            ),
+            "http://risczero.com",
+            RequestInput { inputType: RequestInputType::Inline, data: "".into() },
+            Offer {
+                minPrice: U256::from(1),
+                maxPrice: U256::from(2),
+                biddingStart: 100,
+                timeout: 100,
+                lockTimeout: 50,
+                rampUpPeriod: 1,
+                lockStake: U256::from(3),
+            },
+        )
+    }
+
+    #[sqlx::test]
+    async fn indexes_request_lifecycle(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let indexer = MarketIndexer::new(db.clone());
+        let client = Address::repeat_byte(1);
+        let prover = Address::repeat_byte(2);
+        let request = proof_request(client);
+        let request_id = request.id;
+
+        // Fulfillment indexed before the lock
+        let fulfilled = MarketEvent::Fulfilled { prover };
+        let view = indexer.index(request_id, 12, &fulfilled).await.unwrap();
+        assert_eq!(view.status, MarketRequestStatus::Fulfilled);
+        assert_eq!(view.client, client);
+        assert_eq!(view.expires_at, None);
+
+        let locked = MarketEvent::Locked {
+            prover,
+            request: Box::new(request),
+            client_signature: Bytes::new(),
+        };
+        indexer.index(request_id, 10, &locked).await.unwrap();
+
+        let view = db.get_market_request(request_id).await.unwrap().unwrap();
+        assert_eq!(view.status, MarketRequestStatus::Fulfilled);
+        assert_eq!(view.locker, Some(prover));
+        assert_eq!(view.locked_block, Some(10));
+        assert_eq!(view.fulfilled_block, Some(12));
+        assert_eq!(view.lock_expires_at, Some(150));
+        assert_eq!(view.expires_at, Some(200));
+        assert_eq!(view.submitted_block, None);
+
+        // Also recorded in the lock and fulfillment tables
+        assert_eq!(
+            db.get_request_locked(request_id).await.unwrap(),
+            Some((prover.to_string(), 10))
+        );
+        assert!(db.is_request_fulfilled(request_id).await.unwrap());
+        assert!(db.get_market_request(U256::from(1)).await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn counts_locker_outcomes(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let indexer = MarketIndexer::new(db.clone());
+        let locker = Address::repeat_byte(2);
+        let other = Address::repeat_byte(3);
+        let slashed = MarketEvent::Slashed {
+            stake_burned: U256::from(1),
+            stake_transferred: U256::from(2),
+            stake_recipient: other,
+        };
+        let outcomes = [
+            (locker, Some(MarketEvent::Fulfilled { prover: locker })),
+            (locker, Some(MarketEvent::Fulfilled { prover: other })),
+            (locker, Some(slashed)),
+            // Still locked
+            (locker, None),
+            (other, Some(MarketEvent::Fulfilled { prover: other })),
+        ];
+        for (i, (prover, outcome)) in outcomes.into_iter().enumerate() {
+            let request = proof_request(Address::repeat_byte(10 + i as u8));
+            let request_id = request.id;
+            let locked = MarketEvent::Locked {
+                prover,
+                request: Box::new(request),
+                client_signature: Bytes::new(),
+            };
+            indexer.index(request_id, 10, &locked).await.unwrap();
+            if let Some(outcome) = outcome {
+                indexer.index(request_id, 20, &outcome).await.unwrap();
+            }
+        }
+
+        let stats = db.get_locker_stats(locker).await.unwrap();
+        assert_eq!(stats, LockerStats { fulfilled: 1, missed: 2 });
+        assert_eq!(stats.miss_percent(3), Some(66));
+        assert_eq!(stats.miss_percent(4), None);
+        assert_eq!(db.get_locker_stats(Address::ZERO).await.unwrap(), LockerStats::default());
+    }
+
+    #[sqlx::test]
+    async fn records_replayable_event_log(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let indexer = MarketIndexer::new(db.clone());
+        let prover = Address::repeat_byte(2);
+        let request = proof_request(Address::repeat_byte(1));
+        let request_id = request.id;
+        let record = |block_number, log_index, event| MarketEventRecord {
+            chain_id: 1,
+            request_id,
+            block_number,
+            log_index,
+            tx_hash: None,
+            event,
+        };
+
+        let locked = record(
+            10,
+            0,
+            MarketEvent::Locked {
+                prover,
+                request: Box::new(request.clone()),
+                client_signature: Bytes::new(),
+            },
+        );
+        let slashed = record(
+            20,
+            3,
+            MarketEvent::Slashed {
+                stake_burned: U256::from(1),
+                stake_transferred: U256::from(2),
+                stake_recipient: prover,
+            },
+        );
+        let submitted = record(
+            5,
+            1,
+            MarketEvent::Submitted { request: Box::new(request), client_signature: Bytes::new() },
+        );
+        for record in [&slashed, &locked, &submitted] {
+            assert!(indexer.record(record).await.unwrap());
+        }
+        // Events fetched again after downtime are not logged twice
+        assert!(!indexer.record(&locked).await.unwrap());
+
+        // Submissions are not logged, the rest are replayed in chain order
+        assert_eq!(
+            db.get_market_events(1, 0).await.unwrap(),
+            vec![locked.clone(), slashed.clone()]
+        );
+        assert_eq!(db.get_market_events(1, 11).await.unwrap(), vec![slashed.clone()]);
+        assert!(db.get_market_events(2, 0).await.unwrap().is_empty());
+        assert_eq!(
+            locked.state_change(),
+            Some(OrderStateChange::Locked { request_id, prover, block_number: 10 })
+        );
+        assert_eq!(slashed.state_change(), None);
+        assert_eq!(
+            db.get_market_request(request_id).await.unwrap().unwrap().slashed_block,
+            Some(20)
+        );
+
+        // The checkpoint only moves forward
+        assert_eq!(db.get_indexer_checkpoint(1).await.unwrap(), None);
+        db.set_indexer_checkpoint(1, 20).await.unwrap();
+        db.set_indexer_checkpoint(1, 15).await.unwrap();
+        assert_eq!(db.get_indexer_checkpoint(1).await.unwrap(), Some(20));
+        assert_eq!(db.get_indexer_checkpoint(2).await.unwrap(), None);
+    }
+
+    #[sqlx::test]
+    async fn reverts_reorged_events(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let indexer = MarketIndexer::new(db.clone());
+        let prover = Address::repeat_byte(2);
+        let request = proof_request(Address::repeat_byte(1));
+        let request_id = request.id;
+        let record = |block_number, event| MarketEventRecord {
+            chain_id: 1,
+            request_id,
+            block_number,
+            log_index: 0,
+            tx_hash: None,
+            event,
+        };
+
+        let locked = record(
+            10,
+            MarketEvent::Locked {
+                prover,
+                request: Box::new(request),
+                client_signature: Bytes::new(),
+            },
+        );
+        let fulfilled = record(12, MarketEvent::Fulfilled { prover });
+        indexer.record(&locked).await.unwrap();
+        indexer.record(&fulfilled).await.unwrap();
+        db.set_indexer_checkpoint(1, 12).await.unwrap();
+
+        // Reorg replacing the block of the fulfillment
+        assert_eq!(indexer.revert(1, 11).await.unwrap(), vec![fulfilled.clone()]);
+        assert!(!db.is_request_fulfilled(request_id).await.unwrap());
+        assert!(db.is_request_locked(request_id).await.unwrap());
+        let view = db.get_market_request(request_id).await.unwrap().unwrap();
+        assert_eq!(view.status, MarketRequestStatus::Locked);
+        assert_eq!(view.fulfiller, None);
+        assert_eq!(db.get_market_events(1, 0).await.unwrap(), vec![locked.clone()]);
+        assert_eq!(db.get_indexer_checkpoint(1).await.unwrap(), Some(10));
+        assert_eq!(
+            fulfilled.reverted_state_change(),
+            Some(OrderStateChange::FulfillmentReverted { request_id, block_number: 12 })
+        );
+
+        // Reorg replacing the block of the lock
+        assert_eq!(indexer.revert(1, 5).await.unwrap(), vec![locked]);
+        assert!(!db.is_request_locked(request_id).await.unwrap());
+        let view = db.get_market_request(request_id).await.unwrap().unwrap();
+        assert_eq!(view.status, MarketRequestStatus::Submitted);
+        assert_eq!(view.locker, None);
+        assert_eq!(db.get_indexer_checkpoint(1).await.unwrap(), Some(4));
+    }
+}
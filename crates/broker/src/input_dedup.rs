@@ -0,0 +1,246 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deduplication of inputs uploaded to the prover backend across orders.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use sha2::{Digest as Sha2Digest, Sha256};
+
+use crate::{
+    db::{DbError, DbObj},
+    now_timestamp,
+    provers::{ProverError, ProverObj},
+    OrderStatus,
+};
+
+/// Seconds after which a reference from an order that is not in the DB is released.
+///
+/// Orders are only stored once they are priced or skipped, so this bounds how long an order can
+/// be in pricing.
+const UNTRACKED_ORDER_GRACE_SECS: u64 = 60 * 60;
+
+struct InputEntry {
+    digest: [u8; 32],
+    /// Orders referencing the input, with the time the reference was taken
+    orders: HashMap<String, u64>,
+}
+
+#[derive(Default)]
+struct InputRefs {
+    by_digest: HashMap<[u8; 32], String>,
+    inputs: HashMap<String, InputEntry>,
+}
+
+/// Uploads each distinct input to the prover backend once, and tracks which orders reference it.
+///
+/// Inputs are deleted from the backend once all orders referencing them are closed. The Bonsai
+/// and Bento backends do not support deleting inputs, in which case only the references are
+/// released.
+pub(crate) struct InputDedup {
+    prover: ProverObj,
+    refs: Mutex<InputRefs>,
+}
+
+pub(crate) type InputDedupObj = Arc<InputDedup>;
+
+impl InputDedup {
+    pub(crate) fn new(prover: ProverObj) -> Self {
+        Self { prover, refs: Default::default() }
+    }
+
+    /// Upload the input for the given order, returning the ID of an identical input if one was
+    /// already uploaded.
+    pub(crate) async fn upload_input(
+        &self,
+        input: Vec<u8>,
+        order_id: &str,
+    ) -> Result<String, ProverError> {
+        let digest: [u8; 32] = Sha256::digest(&input).into();
+        if let Some(input_id) = self.retain_digest(&digest, order_id) {
+            tracing::debug!("Reusing uploaded input {input_id} for order {order_id}");
+            return Ok(input_id);
+        }
+
+        let input_id = self.prover.upload_input(input).await?;
+        let duplicate = {
+            let mut refs = self.refs.lock().unwrap();
+            match refs.by_digest.get(&digest).cloned() {
+                // An identical input was uploaded concurrently
+                Some(existing_id) => Some((existing_id, input_id.clone())),
+                None => {
+                    refs.by_digest.insert(digest, input_id.clone());
+                    refs.inputs
+                        .insert(input_id.clone(), InputEntry { digest, orders: HashMap::new() });
+                    None
+                }
+            }
+        };
+        let input_id = match duplicate {
+            Some((existing_id, duplicate_id)) => {
+                self.delete_input(&duplicate_id).await;
+                existing_id
+            }
+            None => input_id,
+        };
+        self.retain(&input_id, order_id);
+        Ok(input_id)
+    }
+
+    fn retain_digest(&self, digest: &[u8; 32], order_id: &str) -> Option<String> {
+        let mut refs = self.refs.lock().unwrap();
+        let input_id = refs.by_digest.get(digest)?.clone();
+        refs.inputs.get_mut(&input_id)?.orders.insert(order_id.to_string(), now_timestamp());
+        Some(input_id)
+    }
+
+    /// Add a reference from the order to an uploaded input.
+    ///
+    /// Returns false if the input is not known, e.g. because it was already reclaimed.
+    pub(crate) fn retain(&self, input_id: &str, order_id: &str) -> bool {
+        let mut refs = self.refs.lock().unwrap();
+        match refs.inputs.get_mut(input_id) {
+            Some(entry) => {
+                entry.orders.entry(order_id.to_string()).or_insert_with(now_timestamp);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Release all references from the given order, deleting inputs from the backend that are no
+    /// longer referenced.
+    pub(crate) async fn release(&self, order_id: &str) {
+        let unreferenced: Vec<String> = {
+            let mut refs = self.refs.lock().unwrap();
+            let mut unreferenced = Vec::new();
+            for (input_id, entry) in refs.inputs.iter_mut() {
+                if entry.orders.remove(order_id).is_some() && entry.orders.is_empty() {
+                    unreferenced.push(input_id.clone());
+                }
+            }
+            for input_id in &unreferenced {
+                if let Some(entry) = refs.inputs.remove(input_id) {
+                    refs.by_digest.remove(&entry.digest);
+                }
+            }
+            unreferenced
+        };
+        for input_id in unreferenced {
+            self.delete_input(&input_id).await;
+        }
+    }
+
+    async fn delete_input(&self, input_id: &str) {
+        tracing::debug!("Deleting unreferenced input {input_id} from the prover");
+        if let Err(err) = self.prover.delete_input(input_id).await {
+            tracing::warn!("Failed to delete input {input_id}: {err}");
+        }
+    }
+
+    /// Release the references of closed orders, and of orders that were never stored in the DB,
+    /// returning the number of orders released.
+    ///
+    /// Orders in `live_orders`, e.g. queued for pricing again or waiting in the order monitor, are
+    /// never released, whatever their status in the DB.
+    pub(crate) async fn reclaim(
+        &self,
+        db: &DbObj,
+        live_orders: &HashSet<String>,
+    ) -> Result<usize, DbError> {
+        let now = now_timestamp();
+        let referencing: HashMap<String, u64> = {
+            let refs = self.refs.lock().unwrap();
+            let mut referencing = HashMap::new();
+            for (order_id, referenced_at) in refs.inputs.values().flat_map(|entry| &entry.orders) {
+                let oldest = referencing.entry(order_id.clone()).or_insert(*referenced_at);
+                *oldest = (*oldest).min(*referenced_at);
+            }
+            referencing
+        };
+
+        let mut closed = HashSet::new();
+        for (order_id, referenced_at) in referencing {
+            if live_orders.contains(&order_id) {
+                continue;
+            }
+            let is_closed = match db.get_order(&order_id).await? {
+                // Inputs of orders whose pricing was cancelled are kept for the grace period, for
+                // the pricing to resume if the order is queued again
+                Some(order) if order.pricing_checkpoint.is_some() => {
+                    order.updated_at.timestamp() as u64 + UNTRACKED_ORDER_GRACE_SECS < now
+                }
+                Some(order) => {
+                    matches!(
+                        order.status,
+                        OrderStatus::Done | OrderStatus::Failed | OrderStatus::Skipped
+                    )
+                }
+                None => referenced_at + UNTRACKED_ORDER_GRACE_SECS < now,
+            };
+            if is_closed {
+                closed.insert(order_id);
+            }
+        }
+        for order_id in &closed {
+            self.release(order_id).await;
+        }
+        Ok(closed.len())
+    }
+
+    /// Number of distinct inputs currently uploaded.
+    #[cfg(test)]
+    fn num_inputs(&self) -> usize {
+        self.refs.lock().unwrap().inputs.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provers::DefaultProver;
+
+    #[tokio::test]
+    async fn dedups_and_reclaims_inputs() {
+        let prover: ProverObj = Arc::new(DefaultProver::new());
+        let dedup = InputDedup::new(prover.clone());
+
+        let input_a = dedup.upload_input(vec![1, 2, 3], "order-1").await.unwrap();
+        let input_b = dedup.upload_input(vec![1, 2, 3], "order-2").await.unwrap();
+        let input_c = dedup.upload_input(vec![4, 5, 6], "order-2").await.unwrap();
+        assert_eq!(input_a, input_b);
+        assert_ne!(input_a, input_c);
+        assert_eq!(dedup.num_inputs(), 2);
+
+        assert!(dedup.retain(&input_c, "order-3"));
+        assert!(!dedup.retain("unknown", "order-3"));
+
+        dedup.release("order-2").await;
+        assert_eq!(dedup.num_inputs(), 2);
+
+        dedup.release("order-1").await;
+        assert_eq!(dedup.num_inputs(), 1);
+        assert!(prover.delete_input(&input_a).await.is_err());
+
+        dedup.release("order-3").await;
+        assert_eq!(dedup.num_inputs(), 0);
+
+        // Uploading again after the input was reclaimed creates a new input
+        let input_d = dedup.upload_input(vec![1, 2, 3], "order-4").await.unwrap();
+        assert_ne!(input_a, input_d);
+    }
+}
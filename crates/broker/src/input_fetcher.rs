@@ -0,0 +1,227 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fetching and validation of URL inputs, shared by pricing and proving.
+
+use std::{io::Read, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use boundless_market::{
+    contracts::{ProofRequest, RequestInputType},
+    input::GuestEnv,
+};
+use flate2::read::GzDecoder;
+use moka::future::Cache;
+use sha2::{Digest as Sha2Digest, Sha256};
+use tokio::sync::Semaphore;
+use url::form_urlencoded;
+
+use crate::{config::ConfigLock, storage::create_uri_handler};
+
+/// Fragment parameter used by requests to give the hex SHA-256 digest of an input URL's content.
+const CONTENT_DIGEST_FRAGMENT_PARAM: &str = "sha256";
+
+/// Leading bytes of gzip compressed data.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Most bytes of decoded inputs kept in the cache.
+const INPUT_CACHE_MAX_BYTES: u64 = 512 * 1024 * 1024;
+const INPUT_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Downloads, validates and decodes URL inputs, caching them keyed by the digest of their URI.
+///
+/// Inputs are fetched once across pricing retries and proving. When prefetching is enabled,
+/// inputs of new orders are fetched in the background while the orders wait to be priced.
+pub(crate) struct InputFetcher {
+    config: ConfigLock,
+    cache: Cache<[u8; 32], Arc<GuestEnv>>,
+    prefetch_permits: Option<Arc<Semaphore>>,
+}
+
+pub(crate) type InputFetcherObj = Arc<InputFetcher>;
+
+impl InputFetcher {
+    /// Create a fetcher prefetching up to `prefetch_concurrency` inputs at a time, or none if 0.
+    pub(crate) fn new(config: ConfigLock, prefetch_concurrency: usize) -> Self {
+        Self {
+            config,
+            cache: Cache::builder()
+                .weigher(|_, env: &Arc<GuestEnv>| env.stdin.len().try_into().unwrap_or(u32::MAX))
+                .max_capacity(INPUT_CACHE_MAX_BYTES)
+                .time_to_live(INPUT_CACHE_TTL)
+                .build(),
+            prefetch_permits: (prefetch_concurrency > 0)
+                .then(|| Arc::new(Semaphore::new(prefetch_concurrency))),
+        }
+    }
+
+    /// Start fetching the input of the request in the background, if it is a URL input and
+    /// prefetching is enabled.
+    pub(crate) fn prefetch(self: &Arc<Self>, request: &ProofRequest) {
+        let Some(permits) = self.prefetch_permits.clone() else {
+            return;
+        };
+        if request.input.inputType != RequestInputType::Url {
+            return;
+        }
+        let fetcher = self.clone();
+        let request = request.clone();
+        tokio::spawn(async move {
+            let Ok(_permit) = permits.acquire_owned().await else {
+                return;
+            };
+            if let Err(err) = fetcher.fetch(&request).await {
+                tracing::debug!("Failed to prefetch input of request 0x{:x}: {err:?}", request.id);
+            }
+        });
+    }
+
+    /// Returns the decoded input of the request, fetching it if it is a URL input not cached.
+    pub(crate) async fn guest_env(&self, request: &ProofRequest) -> Result<Arc<GuestEnv>> {
+        match request.input.inputType {
+            RequestInputType::Inline => Ok(Arc::new(
+                GuestEnv::decode(&request.input.data).context("Failed to decode input")?,
+            )),
+            RequestInputType::Url => self.fetch(request).await,
+            _ => bail!("Invalid input type: {:?}", request.input.inputType),
+        }
+    }
+
+    /// Returns the decoded URL input of the request, fetching it if not cached.
+    ///
+    /// Concurrent fetches of the same URI coalesce into a single download.
+    pub(crate) async fn fetch(&self, request: &ProofRequest) -> Result<Arc<GuestEnv>> {
+        let uri = std::str::from_utf8(&request.input.data).context("input url is not utf8")?;
+        let key: [u8; 32] = Sha256::digest(uri.as_bytes()).into();
+        self.cache
+            .try_get_with(key, self.fetch_uncached(uri, request))
+            .await
+            .map_err(|err| anyhow!("{err:?}"))
+    }
+
+    async fn fetch_uncached(&self, uri: &str, request: &ProofRequest) -> Result<Arc<GuestEnv>> {
+        tracing::debug!("Input URI string: {uri}");
+        let (skip_max_size_limit, max_file_size) = {
+            let conf = self.config.lock_all().context("Failed to read config")?;
+            (conf.market.skips_exec_limits(request.client_address()), conf.market.max_file_size)
+        };
+        let max_size = if skip_max_size_limit { usize::MAX } else { max_file_size };
+
+        let handler = create_uri_handler(uri, &self.config, skip_max_size_limit)
+            .await
+            .context("URL handling failed")?;
+        let data =
+            handler.fetch().await.with_context(|| format!("Failed to fetch input URI: {uri}"))?;
+
+        if let Some(expected) = content_digest(uri)? {
+            let digest: [u8; 32] = Sha256::digest(&data).into();
+            ensure!(
+                digest == expected,
+                "Input from {uri} has digest {}, expected {}",
+                hex::encode(digest),
+                hex::encode(expected)
+            );
+        }
+
+        let data = decompress(data, max_size)
+            .with_context(|| format!("Failed to decompress input from URI: {uri}"))?;
+        let env = GuestEnv::decode(&data)
+            .with_context(|| format!("Failed to decode input from URI: {uri}"))?;
+        Ok(Arc::new(env))
+    }
+}
+
+/// Expected SHA-256 digest of the content of the URI, if given in its fragment.
+fn content_digest(uri: &str) -> Result<Option<[u8; 32]>> {
+    let uri = url::Url::parse(uri)?;
+    let Some(fragment) = uri.fragment() else {
+        return Ok(None);
+    };
+    let Some((_, digest)) = form_urlencoded::parse(fragment.as_bytes())
+        .find(|(key, _)| key == CONTENT_DIGEST_FRAGMENT_PARAM)
+    else {
+        return Ok(None);
+    };
+    let digest = hex::decode(digest.as_ref()).context("Invalid input content digest")?;
+    Ok(Some(digest.try_into().map_err(|_| anyhow!("Input content digest is not 32 bytes"))?))
+}
+
+/// Decompress gzip compressed data, up to `max_size` bytes; other data is returned as is.
+fn decompress(data: Vec<u8>, max_size: usize) -> Result<Vec<u8>> {
+    if !data.starts_with(&GZIP_MAGIC) {
+        return Ok(data);
+    }
+    let mut decompressed = Vec::new();
+    GzDecoder::new(data.as_slice())
+        .take(max_size.saturating_add(1) as u64)
+        .read_to_end(&mut decompressed)?;
+    ensure!(decompressed.len() <= max_size, "decompressed size exceeds {max_size} bytes");
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Address, U256};
+    use boundless_market::contracts::{Offer, Predicate, RequestId, RequestInput, Requirements};
+    use flate2::{write::GzEncoder, Compression};
+    use httpmock::prelude::*;
+    use risc0_zkvm::sha::Digest;
+    use std::io::Write;
+
+    fn request(url: String) -> ProofRequest {
+        ProofRequest::new(
+            RequestId::new(Address::ZERO, 1),
+            Requirements::new(Digest::ZERO, Predicate::prefix_match(vec![])),
+            "http://risczero.com/image",
+            RequestInput::url(url),
+            Offer {
+                minPrice: U256::from(1),
+                maxPrice: U256::from(2),
+                biddingStart: 0,
+                timeout: 200,
+                lockTimeout: 100,
+                rampUpPeriod: 1,
+                lockStake: U256::ZERO,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn fetches_validates_and_caches_inputs() {
+        let stdin = vec![0x41; 1024];
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&GuestEnv::from_stdin(stdin.clone()).encode().unwrap()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let digest = hex::encode(Sha256::digest(&compressed));
+
+        let server = MockServer::start();
+        let get_mock = server.mock(|when, then| {
+            when.method(GET).path("/input");
+            then.status(200).body(&compressed);
+        });
+        let fetcher = InputFetcher::new(ConfigLock::default(), 0);
+
+        // Decompressed and decoded, then served from the cache
+        let valid = request(format!("{}#sha256={digest}", server.url("/input")));
+        assert_eq!(fetcher.fetch(&valid).await.unwrap().stdin, stdin);
+        assert_eq!(fetcher.fetch(&valid).await.unwrap().stdin, stdin);
+        get_mock.assert_hits(1);
+
+        // Rejected if the content does not match the digest
+        let wrong_digest = hex::encode([0u8; 32]);
+        let tampered = request(format!("{}#sha256={wrong_digest}", server.url("/input")));
+        assert!(fetcher.fetch(&tampered).await.is_err());
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Active/standby failover of broker replicas, see `leader_lease_secs` of
+//! [OrderClaimsConf](crate::config::OrderClaimsConf).
+//!
+//! Standby replicas follow the market and price orders like the leader, but do not lock, prove or
+//! fulfill them. The leader renews a lease in the claims database. Once it stops, a standby replica
+//! takes the lease over and resumes proving the orders locked by the previous leader. A leader
+//! stepping down stops starting proofs and submitting batches, so that its locked orders are only
+//! fulfilled by the new leader.
+
+use std::{sync::Arc, time::Duration};
+
+use thiserror::Error;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    db::DbError,
+    errors::{impl_coded_debug, CodedError},
+    order_claims::OrderClaimsObj,
+    task::{RetryRes, RetryTask, SupervisorErr},
+};
+
+#[derive(Error)]
+pub enum LeadershipErr {
+    #[error("{code} Failed to renew the leadership lease: {0}", code = self.code())]
+    LeaseErr(#[from] DbError),
+}
+
+impl_coded_debug!(LeadershipErr);
+
+impl CodedError for LeadershipErr {
+    fn code(&self) -> &str {
+        match self {
+            LeadershipErr::LeaseErr(_) => "[B-LEAD-001]",
+        }
+    }
+}
+
+/// Whether this broker is the leader of its replicas, and as such locks orders.
+pub(crate) struct Leadership(watch::Sender<bool>);
+
+pub(crate) type LeadershipObj = Arc<Leadership>;
+
+impl Default for Leadership {
+    /// Brokers without a leadership lease are always the leader.
+    fn default() -> Self {
+        Self(watch::Sender::new(true))
+    }
+}
+
+impl Leadership {
+    /// Leadership of a replica starting on standby, until it acquires the lease.
+    pub(crate) fn standby() -> Self {
+        Self(watch::Sender::new(false))
+    }
+
+    pub(crate) fn is_leader(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Follow the changes of leadership, e.g. to act on a failover.
+    pub(crate) fn subscribe(&self) -> watch::Receiver<bool> {
+        self.0.subscribe()
+    }
+
+    fn set_leader(&self, is_leader: bool) {
+        let changed =
+            self.0.send_if_modified(|leader| std::mem::replace(leader, is_leader) != is_leader);
+        match (changed, is_leader) {
+            (true, true) => tracing::info!("Acquired the leadership lease, locking orders"),
+            (true, false) => {
+                tracing::warn!("On standby, not locking or fulfilling orders until leader")
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Acquires and renews the leadership lease of this replica.
+pub(crate) struct LeaderElection {
+    order_claims: OrderClaimsObj,
+    leadership: LeadershipObj,
+    lease_secs: u64,
+}
+
+impl LeaderElection {
+    pub(crate) fn new(
+        order_claims: OrderClaimsObj,
+        leadership: LeadershipObj,
+        lease_secs: u64,
+    ) -> Self {
+        Self { order_claims, leadership, lease_secs }
+    }
+
+    /// Claim the lease a third of its duration apart, so that the leader renews it well before it
+    /// expires.
+    ///
+    /// The leader steps down as soon as a renewal fails, as it can no longer tell whether another
+    /// replica will take the lease over, and releases the lease once cancelled.
+    async fn run(
+        order_claims: OrderClaimsObj,
+        leadership: LeadershipObj,
+        lease_secs: u64,
+        cancel_token: CancellationToken,
+    ) -> Result<(), LeadershipErr> {
+        let interval = Duration::from_secs((lease_secs / 3).max(1));
+        loop {
+            match order_claims.claim_leadership(lease_secs).await {
+                Ok(is_leader) => leadership.set_leader(is_leader),
+                Err(err) => {
+                    leadership.set_leader(false);
+                    return Err(err.into());
+                }
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = cancel_token.cancelled() => break,
+            }
+        }
+
+        if leadership.is_leader() {
+            leadership.set_leader(false);
+            if let Err(err) = order_claims.release_leadership().await {
+                tracing::warn!(
+                    "Failed to release the leadership lease, it expires in {lease_secs}s: {err}"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RetryTask for LeaderElection {
+    type Error = LeadershipErr;
+    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
+        let order_claims = self.order_claims.clone();
+        let leadership = self.leadership.clone();
+        let lease_secs = self.lease_secs;
+        Box::pin(async move {
+            tracing::info!("Starting leader election, with a lease of {lease_secs}s");
+            Self::run(order_claims, leadership, lease_secs, cancel_token)
+                .await
+                .map_err(SupervisorErr::Recover)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_claims::OrderClaims;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn standby_takes_over_released_lease() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let replica = |owner: &'static str| {
+            let pool = pool.clone();
+            async move {
+                let order_claims =
+                    Arc::new(OrderClaims::with_sqlite(pool, owner, 600).await.unwrap());
+                let leadership = Arc::new(Leadership::standby());
+                let cancel_token = CancellationToken::new();
+                let task = tokio::spawn(LeaderElection::run(
+                    order_claims,
+                    leadership.clone(),
+                    3,
+                    cancel_token.clone(),
+                ));
+                (leadership, cancel_token, task)
+            }
+        };
+
+        let (a, a_cancel, a_task) = replica("a").await;
+        a.subscribe().wait_for(|is_leader| *is_leader).await.unwrap();
+        let (b, _b_cancel, _b_task) = replica("b").await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!b.is_leader());
+
+        // The standby takes over once the leader shuts down
+        a_cancel.cancel();
+        a_task.await.unwrap().unwrap();
+        assert!(!a.is_leader());
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            b.subscribe().wait_for(|is_leader| *is_leader),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    }
+}
@@ -12,31 +12,46 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{path::PathBuf, sync::Arc, time::SystemTime};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
 
 use crate::storage::create_uri_handler;
 use alloy::{
-    network::Ethereum,
+    network::{Ethereum, EthereumWallet},
     primitives::{Address, Bytes, FixedBytes, U256},
     providers::{Provider, WalletProvider},
+    rpc::types::TransactionReceipt,
     signers::local::PrivateKeySigner,
 };
 use anyhow::{Context, Result};
 use boundless_market::{
-    contracts::{boundless_market::BoundlessMarketService, ProofRequest},
+    contracts::{
+        boundless_market::BoundlessMarketService, tx_submitter::PrivateRelay, ProofRequest,
+        RequestId,
+    },
+    deployments::DeploymentRegistry,
+    nonce_layer::NonceTracker,
     order_stream_client::OrderStreamClient,
     selector::is_groth16_selector,
     Deployment,
 };
-use chrono::{serde::ts_seconds, DateTime, Utc};
+use chrono::{
+    serde::{ts_milliseconds, ts_seconds},
+    DateTime, Utc,
+};
 use clap::Parser;
 pub use config::Config;
-use config::ConfigWatcher;
+use config::{ConfigLock, ConfigWatcher};
 use db::{DbObj, SqliteDb};
 use provers::ProverObj;
 use risc0_ethereum_contracts::set_verifier::SetVerifierService;
 use risc0_zkvm::sha::Digest;
 pub use rpc_retry_policy::CustomRetryPolicy;
+pub use rpc_tiers::TieredTransport;
 use serde::{Deserialize, Serialize};
 use task::{RetryPolicy, Supervisor};
 use tokio::sync::mpsc;
@@ -46,26 +61,63 @@ use url::Url;
 
 const NEW_ORDER_CHANNEL_CAPACITY: usize = 1000;
 const PRICING_CHANNEL_CAPACITY: usize = 1000;
-const ORDER_STATE_CHANNEL_CAPACITY: usize = 1000;
+const MANUAL_LOCK_CHANNEL_CAPACITY: usize = 16;
+const CANCEL_PRICING_CHANNEL_CAPACITY: usize = 16;
 
+pub(crate) mod admin;
 pub(crate) mod aggregator;
+pub(crate) mod archiver;
+pub(crate) mod benchmark;
 pub(crate) mod chain_monitor;
 pub mod config;
+pub(crate) mod config_lint;
+pub(crate) mod cycle_history;
+pub(crate) mod dataset;
 pub(crate) mod db;
+pub(crate) mod duty_cycle;
 pub(crate) mod errors;
+pub(crate) mod events;
 pub mod futures_retry;
+pub(crate) mod groth16_wrapper;
+pub(crate) mod image_cache;
+pub(crate) mod image_pinner;
+pub(crate) mod indexer;
+pub(crate) mod input_dedup;
+pub(crate) mod input_fetcher;
+pub(crate) mod leadership;
+pub(crate) mod lock_expired_pricing;
+pub(crate) mod lock_expired_strategy;
 pub(crate) mod market_monitor;
+pub(crate) mod market_stats;
+pub(crate) mod metrics;
 pub(crate) mod offchain_market_monitor;
+pub(crate) mod order_claims;
 pub(crate) mod order_monitor;
 pub(crate) mod order_picker;
+pub(crate) mod order_tags;
+pub(crate) mod preflight_batcher;
+pub(crate) mod preflight_concurrency;
+pub(crate) mod pricing_webhook;
 pub(crate) mod prioritization;
 pub(crate) mod provers;
 pub(crate) mod proving;
+pub(crate) mod proving_capacity;
 pub(crate) mod reaper;
+pub(crate) mod reputation;
+pub(crate) mod requestor_rate_limit;
+pub(crate) mod reservations;
+pub(crate) mod risk;
 pub(crate) mod rpc_retry_policy;
+pub(crate) mod rpc_tiers;
+pub(crate) mod scheduler;
+pub(crate) mod selectors;
+pub(crate) mod self_throttle;
+pub(crate) mod signature_verifier;
+pub(crate) mod skip_reevaluator;
 pub(crate) mod storage;
 pub(crate) mod submitter;
 pub(crate) mod task;
+pub(crate) mod tiny_orders;
 pub(crate) mod utils;
 
 #[derive(Parser, Debug, Clone)]
@@ -76,17 +128,41 @@ pub struct Args {
     pub db_url: String,
 
     /// RPC URL
+    ///
+    /// Transactions are sent through it, as well as all other calls unless `read_rpc_url` is set.
     #[clap(long, env, default_value = "http://localhost:8545")]
     pub rpc_url: Url,
 
+    /// RPC URL of a read replica serving heavy read traffic
+    ///
+    /// When set, calls other than sending transactions and tracking them until they are included,
+    /// such as balance queries, event backfills and simulations, are sent to it instead of
+    /// `rpc_url`.
+    #[clap(long, env)]
+    pub read_rpc_url: Option<Url>,
+
     /// wallet key
     #[clap(long, env)]
     pub private_key: PrivateKeySigner,
 
+    /// Delegated prover key
+    ///
+    /// When set, lock transactions are sent from the `private_key` wallet but authorized with a
+    /// signature from this key, so the lock stake, payment and fulfillment credit accrue to the
+    /// prover address. Gas for all transactions is paid by the `private_key` wallet.
+    #[clap(long, env)]
+    pub prover_private_key: Option<PrivateKeySigner>,
+
     /// Boundless deployment configuration (contract addresses, etc.)
     #[clap(flatten, next_help_heading = "Boundless Deployment")]
     pub deployment: Option<Deployment>,
 
+    /// Name of a known network to use the deployment configuration of, e.g. `base-sepolia`
+    ///
+    /// Must match the chain ID reported by the RPC provider.
+    #[clap(long, env = "BOUNDLESS_NETWORK", conflicts_with = "boundless_market_address")]
+    pub network: Option<String>,
+
     /// local prover API (Bento)
     ///
     /// Setting this value toggles using Bento for proving and disables Bonsai
@@ -136,6 +212,103 @@ pub struct Args {
     /// Log JSON
     #[clap(long, env, default_value_t = false)]
     pub log_json: bool,
+
+    /// Admin API listen address, e.g. `127.0.0.1:8090`
+    ///
+    /// The admin API is disabled unless set. Requires `admin_token`.
+    #[clap(long, env, requires = "admin_token")]
+    pub admin_listen_addr: Option<SocketAddr>,
+
+    /// Bearer token required on all admin API requests, must not be empty
+    #[clap(long, env, hide_env_values = true, value_parser = admin::parse_token)]
+    pub admin_token: Option<String>,
+
+    /// Prometheus metrics listen address, e.g. `0.0.0.0:9090`
+    ///
+    /// Metrics are served at `/metrics`. The endpoint is disabled unless set.
+    #[clap(long, env)]
+    pub metrics_listen_addr: Option<SocketAddr>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Price and preflight orders without ever locking or fulfilling them
+    ///
+    /// Orders that would have been locked or proven are recorded in the DB along with their
+    /// estimated profit. Equivalent to setting `market.dry_run` in the config file.
+    DryRun,
+    /// Config file utilities
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Export closed orders from the DB as a labeled Parquet dataset for training pricing models
+    ///
+    /// Each row joins an order's pricing decisions, outcome, cycles, fulfillment gas and lock race
+    /// result. The schema is documented in the broker's `dataset` module.
+    ExportDataset {
+        /// Path of the Parquet file to write
+        #[clap(long)]
+        output: PathBuf,
+
+        /// Only export orders closed at or after this UNIX timestamp
+        #[clap(long, default_value_t = 0)]
+        since: i64,
+    },
+    /// Export a snapshot of the DB, including the orders the prover is committed to
+    ///
+    /// The snapshot is a single SQLite file, to be imported with `import-state` when moving the
+    /// broker to new hardware without losing the obligations of locked orders.
+    ExportState {
+        /// Path of the snapshot file to write
+        #[clap(long)]
+        output: PathBuf,
+    },
+    /// Import a snapshot written by `export-state` into an empty DB
+    ImportState {
+        /// Path of the snapshot file to read
+        #[clap(long)]
+        input: PathBuf,
+
+        /// Keep the proof IDs of committed orders, when the new broker uses the same prover
+        ///
+        /// By default, committed orders are proven again from the start.
+        #[clap(long, default_value_t = false)]
+        keep_proof_ids: bool,
+    },
+    /// Calibrate `peak_prove_khz` and the gas estimates of the market config
+    ///
+    /// Proves the loop guest through the configured prover backend to measure the achieved
+    /// proving and preflight throughput, and derives the lock and fulfillment gas estimates from
+    /// the gas recorded for recent transactions.
+    Benchmark {
+        /// Cycles of each benchmark proof, in millions
+        #[clap(long, value_delimiter = ',', default_value = "4,16,64")]
+        mcycles: Vec<u64>,
+
+        /// Number of recent transactions the gas estimates are calibrated from
+        #[clap(long, default_value_t = 500)]
+        gas_samples: u32,
+
+        /// Write the calibrated values to the config file
+        #[clap(long, default_value_t = false)]
+        write: bool,
+    },
+}
+
+#[derive(clap::Subcommand, Debug, Clone, PartialEq)]
+pub enum ConfigCommand {
+    /// Check the market config against recent market activity and print warnings
+    Lint {
+        /// Number of blocks to look back for locked requests
+        #[clap(long, default_value_t = 10_000)]
+        lookback_blocks: u64,
+
+        /// Proving throughput measured with `boundless proving benchmark`, in kHz
+        #[clap(long)]
+        benchmark_khz: Option<u64>,
+    },
 }
 
 /// Status of a persistent order as it moves through the lifecycle in the database.
@@ -163,7 +336,121 @@ enum OrderStatus {
     Skipped,
 }
 
-#[derive(Clone, Copy, sqlx::Type, Debug, PartialEq, Serialize, Deserialize)]
+/// Reason an order was skipped, stored with the skipped order in the database.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SkipReason {
+    /// The order expired before it could be priced or locked
+    Expired,
+    /// The order expires within `min_deadline`, or too soon to be worth committing to
+    InsufficientDeadline,
+    /// The requestor is not in `allow_client_addresses`
+    ClientNotAllowed,
+    /// The requestor is in `deny_requestor_addresses`
+    ClientDenied,
+    /// The image ID is not in `allow_image_ids`
+    ImageNotAllowed,
+    /// The image ID is in `deny_image_ids`
+    ImageDenied,
+    /// The requestor's reputation score is below `client_reputation.min_score_percent`
+    LowReputation,
+    /// The order requires an unsupported selector
+    UnsupportedSelector,
+    /// The lock stake exceeds `max_stake`
+    StakeTooHigh,
+    /// The request was locked by another prover
+    AlreadyLocked,
+    /// The request was fulfilled by another prover
+    AlreadyFulfilled,
+    /// The lock expired before the order was locked
+    LockExpired,
+    /// The estimated gas cost exceeds the max price of the order
+    GasCostExceedsPrice,
+    /// Not enough gas balance to lock and fulfill the order
+    InsufficientGas,
+    /// Not enough stake balance to lock the order
+    InsufficientStake,
+    /// The price of the order is below the configured mcycle price
+    PriceTooLow,
+    /// Preflight exceeded the execution limit set by the price, config or deadline
+    ExecLimitExceeded,
+    /// The order can not be proven before its deadline
+    InsufficientProvingTime,
+    /// The order exceeds `max_mcycle_limit`
+    McycleLimitExceeded,
+    /// The journal exceeds `max_journal_bytes`
+    JournalTooLarge,
+    /// The journal does not match the predicate of the request
+    PredicateFailed,
+    /// The lock expired strategy decided not to fulfill the order
+    LockExpiredStrategy,
+    /// Pricing was cancelled, e.g. because the request was locked or fulfilled
+    Cancelled,
+    /// The order would have been acted on, but the broker is in dry run mode
+    DryRun,
+    /// Pricing the order failed with an error
+    PricingFailed,
+    /// Sending the lock transaction failed
+    LockFailed,
+    /// The risk score of locking the order exceeds `max_risk_score`
+    RiskTooHigh,
+    /// The request is claimed by another broker replica, see `order_claims`
+    ClaimedByReplica,
+}
+
+impl SkipReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::Expired => "expired",
+            SkipReason::InsufficientDeadline => "insufficient_deadline",
+            SkipReason::ClientNotAllowed => "client_not_allowed",
+            SkipReason::ClientDenied => "client_denied",
+            SkipReason::ImageNotAllowed => "image_not_allowed",
+            SkipReason::ImageDenied => "image_denied",
+            SkipReason::LowReputation => "low_reputation",
+            SkipReason::UnsupportedSelector => "unsupported_selector",
+            SkipReason::StakeTooHigh => "stake_too_high",
+            SkipReason::AlreadyLocked => "already_locked",
+            SkipReason::AlreadyFulfilled => "already_fulfilled",
+            SkipReason::LockExpired => "lock_expired",
+            SkipReason::GasCostExceedsPrice => "gas_cost_exceeds_price",
+            SkipReason::InsufficientGas => "insufficient_gas",
+            SkipReason::InsufficientStake => "insufficient_stake",
+            SkipReason::PriceTooLow => "price_too_low",
+            SkipReason::ExecLimitExceeded => "exec_limit_exceeded",
+            SkipReason::InsufficientProvingTime => "insufficient_proving_time",
+            SkipReason::McycleLimitExceeded => "mcycle_limit_exceeded",
+            SkipReason::JournalTooLarge => "journal_too_large",
+            SkipReason::PredicateFailed => "predicate_failed",
+            SkipReason::LockExpiredStrategy => "lock_expired_strategy",
+            SkipReason::Cancelled => "cancelled",
+            SkipReason::DryRun => "dry_run",
+            SkipReason::PricingFailed => "pricing_failed",
+            SkipReason::LockFailed => "lock_failed",
+            SkipReason::RiskTooHigh => "risk_too_high",
+            SkipReason::ClaimedByReplica => "claimed_by_replica",
+        }
+    }
+
+    /// Whether the request is skipped for this reason however many times it is priced, in which
+    /// case the request is tombstoned.
+    fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            SkipReason::UnsupportedSelector | SkipReason::ImageDenied | SkipReason::PredicateFailed
+        )
+    }
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(
+    Clone, Copy, sqlx::Type, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 enum FulfillmentType {
     LockAndFulfill,
     FulfillAfterLockExpire,
@@ -171,22 +458,44 @@ enum FulfillmentType {
     FulfillWithoutLocking,
 }
 
-/// Message sent from MarketMonitor to OrderPicker about order state changes
-#[derive(Debug, Clone)]
+/// Change of the on-chain state of a request, published by the MarketMonitor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum OrderStateChange {
-    /// Order has been locked by a prover
-    Locked { request_id: U256, prover: Address },
-    /// Order has been fulfilled
-    Fulfilled { request_id: U256 },
+    /// Order has been locked by a prover, in the given block
+    Locked { request_id: U256, prover: Address, block_number: u64 },
+    /// Order has been fulfilled, in the given block
+    Fulfilled { request_id: U256, block_number: u64 },
+    /// The lock of the order in the given block was reorged out, and is not on the chain anymore
+    LockReverted { request_id: U256, prover: Address, block_number: u64 },
+    /// The fulfillment of the order in the given block was reorged out, and is not on the chain
+    /// anymore
+    FulfillmentReverted { request_id: U256, block_number: u64 },
 }
 
-/// Helper function to format an order ID consistently
-fn format_order_id(
-    request_id: &U256,
-    signing_hash: &FixedBytes<32>,
-    fulfillment_type: &FulfillmentType,
-) -> String {
-    format!("0x{request_id:x}-{signing_hash}-{fulfillment_type:?}")
+/// Identifier of an order: the request id, the hash of the proof request, and the fulfillment
+/// type.
+///
+/// Cheap to copy, hash, and compare. Only allocates when formatted, e.g. to key the order in
+/// the DB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct OrderId {
+    request_id: U256,
+    signing_hash: FixedBytes<32>,
+    fulfillment_type: FulfillmentType,
+}
+
+impl std::fmt::Display for OrderId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{:x}-{}-{:?}", self.request_id, self.signing_hash, self.fulfillment_type)
+    }
+}
+
+/// Image and input uploaded to the prover for an order whose pricing was cancelled before its
+/// preflight completed, so that pricing resumes from the preflight if the order is queued again.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+struct PricingCheckpoint {
+    image_id: Option<String>,
+    input_id: Option<String>,
 }
 
 /// Order request from the network.
@@ -201,9 +510,15 @@ struct OrderRequest {
     chain_id: u64,
     image_id: Option<String>,
     input_id: Option<String>,
+    #[serde(default)]
+    assumption_ids: Option<Vec<String>>,
     total_cycles: Option<u64>,
     target_timestamp: Option<u64>,
     expire_timestamp: Option<u64>,
+    #[serde(default)]
+    provenance: Option<OrderProvenance>,
+    #[serde(default)]
+    pricing_checkpoint: Option<PricingCheckpoint>,
 }
 
 impl OrderRequest {
@@ -222,19 +537,87 @@ impl OrderRequest {
             chain_id,
             image_id: None,
             input_id: None,
+            assumption_ids: None,
             total_cycles: None,
             target_timestamp: None,
             expire_timestamp: None,
+            provenance: None,
+            pricing_checkpoint: None,
         }
     }
 
+    /// Record where and when the order was received.
+    fn with_provenance(self, provenance: OrderProvenance) -> Self {
+        Self { provenance: Some(provenance), ..self }
+    }
+
+    /// Mark the signature of the order as validated in its provenance, if it was recorded.
+    fn with_signature_validated(self) -> Self {
+        let provenance = self.provenance.map(OrderProvenance::with_signature_validated);
+        Self { provenance, ..self }
+    }
+
+    fn signature_validated(&self) -> bool {
+        self.provenance.as_ref().is_some_and(|provenance| provenance.signature_validated)
+    }
+
+    /// Whether the order is ECDSA signed and its signature was not validated yet.
+    fn needs_signature_verification(&self) -> bool {
+        !RequestId::from_lossy(self.request.id).smart_contract_signed && !self.signature_validated()
+    }
+
+    /// Whether the ECDSA signature of the order is valid.
+    fn has_valid_signature(&self) -> bool {
+        self.request
+            .verify_signature(&self.client_sig, self.boundless_market_address, self.chain_id)
+            .is_ok()
+    }
+
+    /// Provenance of the order with the details of its signature, if it was recorded.
+    ///
+    /// ECDSA signatures are validated here unless they were when the order was received;
+    /// ERC-1271 signatures are only reported as validated if the monitor that received the order
+    /// validated them.
+    fn provenance_record(&self) -> Option<OrderProvenanceRecord> {
+        let provenance = self.provenance.clone()?;
+        let order_id = self.order_id();
+        let smart_contract_signed = RequestId::from_lossy(self.request.id).smart_contract_signed;
+        let (scheme, validated) = if smart_contract_signed {
+            (SignatureScheme::Erc1271, provenance.signature_validated)
+        } else {
+            (SignatureScheme::Ecdsa, provenance.signature_validated || self.has_valid_signature())
+        };
+        Some(OrderProvenanceRecord {
+            order_id: order_id.to_string(),
+            request_id: self.request.id,
+            fulfillment_type: self.fulfillment_type,
+            provenance,
+            signature: SignatureDetails {
+                scheme,
+                signer: self.request.client_address(),
+                signing_hash: order_id.signing_hash,
+                signature: self.client_sig.clone(),
+                validated,
+            },
+        })
+    }
+
     // An Order is identified by the request_id, the fulfillment type, and the hash of the proof request.
     // This structure supports multiple different ProofRequests with the same request_id, and different
     // fulfillment types.
     pub fn id(&self) -> String {
-        let signing_hash =
-            self.request.signing_hash(self.boundless_market_address, self.chain_id).unwrap();
-        format_order_id(&self.request.id, &signing_hash, &self.fulfillment_type)
+        self.order_id().to_string()
+    }
+
+    fn order_id(&self) -> OrderId {
+        OrderId {
+            request_id: self.request.id,
+            signing_hash: self
+                .request
+                .signing_hash(self.boundless_market_address, self.chain_id)
+                .unwrap(),
+            fulfillment_type: self.fulfillment_type,
+        }
     }
 
     fn to_order(&self, status: OrderStatus) -> Order {
@@ -248,6 +631,7 @@ impl OrderRequest {
             updated_at: Utc::now(),
             image_id: self.image_id.clone(),
             input_id: self.input_id.clone(),
+            assumption_ids: self.assumption_ids.clone(),
             total_cycles: self.total_cycles,
             target_timestamp: self.target_timestamp,
             expire_timestamp: self.expire_timestamp,
@@ -256,11 +640,15 @@ impl OrderRequest {
             compressed_proof_id: None,
             lock_price: None,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: self.pricing_checkpoint.clone(),
         }
     }
 
-    fn to_skipped_order(&self) -> Order {
-        self.to_order(OrderStatus::Skipped)
+    fn to_skipped_order(&self, reason: SkipReason) -> Order {
+        let mut order = self.to_order(OrderStatus::Skipped);
+        order.skip_reason = Some(reason);
+        order
     }
 
     fn to_proving_order(&self, lock_price: U256) -> Order {
@@ -325,6 +713,11 @@ struct Order {
     ///
     ///  Populated after preflight
     input_id: Option<String>,
+    /// Receipt IDs of the assumptions of the input
+    ///
+    /// Populated after preflight
+    #[serde(default)]
+    assumption_ids: Option<Vec<String>>,
     /// Proof Id
     ///
     /// Populated after proof completion
@@ -343,6 +736,14 @@ struct Order {
     lock_price: Option<U256>,
     /// Failure message
     error_msg: Option<String>,
+    /// Reason the order was skipped
+    #[serde(default)]
+    skip_reason: Option<SkipReason>,
+    /// Image and input uploaded before pricing was cancelled
+    ///
+    /// Populated when pricing is cancelled before preflight completes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pricing_checkpoint: Option<PricingCheckpoint>,
 }
 
 impl Order {
@@ -350,9 +751,15 @@ impl Order {
     // This structure supports multiple different ProofRequests with the same request_id, and different
     // fulfillment types.
     pub fn id(&self) -> String {
-        let signing_hash =
-            self.request.signing_hash(self.boundless_market_address, self.chain_id).unwrap();
-        format_order_id(&self.request.id, &signing_hash, &self.fulfillment_type)
+        OrderId {
+            request_id: self.request.id,
+            signing_hash: self
+                .request
+                .signing_hash(self.boundless_market_address, self.chain_id)
+                .unwrap(),
+            fulfillment_type: self.fulfillment_type,
+        }
+        .to_string()
     }
 
     pub fn is_groth16(&self) -> bool {
@@ -371,6 +778,246 @@ impl std::fmt::Display for Order {
     }
 }
 
+/// Summary of a pricing decision, as recorded for shadow pricing comparisons.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+enum PricingDecision {
+    /// Lock the order once the price reaches the target timestamp (0 for ASAP)
+    Lock { target_timestamp: u64 },
+    /// Prove and fulfill the order after its lock expires
+    ProveAfterLockExpire,
+    /// Do not engage the order
+    Skip,
+}
+
+/// Live and shadow pricing decisions for a single order, priced from the same preflight.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ShadowPricingRecord {
+    order_id: String,
+    total_cycles: u64,
+    live: PricingDecision,
+    shadow: PricingDecision,
+    #[serde(with = "ts_seconds")]
+    created_at: DateTime<Utc>,
+}
+
+/// Pricing decision that was not acted on because the broker is in dry-run mode.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct DryRunRecord {
+    order_id: String,
+    total_cycles: u64,
+    decision: PricingDecision,
+    /// Expected payment for fulfilling the order. Denominated in the native token when locking,
+    /// or in stake tokens when proving after the lock expires.
+    reward: U256,
+    /// Estimated gas cost to lock (if applicable) and fulfill the order, in the native token.
+    gas_cost: U256,
+    /// Reward less the gas cost, if both are denominated in the native token.
+    estimated_profit: Option<U256>,
+    #[serde(with = "ts_seconds")]
+    created_at: DateTime<Utc>,
+}
+
+/// Inputs considered when pricing an order and the resulting decision, recorded so that
+/// operators can reconstruct why an order was locked or skipped.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct PricingAuditRecord {
+    order_id: String,
+    request_id: U256,
+    fulfillment_type: FulfillmentType,
+    /// Gas price the order was priced at, in wei
+    gas_price: Option<u128>,
+    /// Estimated gas cost to lock (if applicable) and fulfill the order, in the native token.
+    gas_cost: Option<U256>,
+    /// Native token balance left after reserving gas for committed orders
+    available_gas: Option<U256>,
+    /// Stake token balance left after the stake of committed orders
+    available_stake: Option<U256>,
+    /// Cycle limit the order was preflighted with
+    exec_limit_cycles: Option<u64>,
+    /// What bound the exec limit, e.g. "Price" or "Deadline"
+    exec_limit_bound: Option<String>,
+    /// Cycles used by the preflight execution
+    total_cycles: Option<u64>,
+    /// Risk score of locking the order and its components, if scored
+    #[serde(default)]
+    risk: Option<risk::RiskScore>,
+    /// Configured mcycle price, in the native token
+    mcycle_price: String,
+    /// Configured mcycle price, in the stake token
+    mcycle_price_stake_token: String,
+    decision: PricingDecision,
+    #[serde(default)]
+    skip_reason: Option<SkipReason>,
+    /// Error that failed pricing, if any
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(with = "ts_seconds")]
+    created_at: DateTime<Utc>,
+}
+
+/// Where an order was received from.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum OrderSource {
+    /// A `RequestSubmitted` event, seen live or when searching for open orders on startup
+    ChainEvent { block_number: Option<u64>, tx_hash: Option<FixedBytes<32>> },
+    /// An order stream server
+    OrderStream { url: String, stream_id: Option<i64> },
+    /// A `RequestLocked` event of another prover, with the request fetched from the market, or
+    /// from the order stream at `order_stream_url`
+    LockedByOther {
+        block_number: Option<u64>,
+        tx_hash: Option<FixedBytes<32>>,
+        order_stream_url: Option<String>,
+    },
+    /// Queued again after being skipped
+    Reevaluation,
+    /// A `RequestLocked` event of another prover in the given block, reorged out
+    ReorgedLock { block_number: u64, tx_hash: Option<FixedBytes<32>> },
+}
+
+/// Where and when an order was received.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct OrderProvenance {
+    source: OrderSource,
+    #[serde(with = "ts_milliseconds")]
+    received_at: DateTime<Utc>,
+    /// Whether the monitor that received the order validated its signature
+    #[serde(default)]
+    signature_validated: bool,
+}
+
+impl OrderProvenance {
+    fn new(source: OrderSource) -> Self {
+        Self { source, received_at: Utc::now(), signature_validated: false }
+    }
+
+    fn with_signature_validated(self) -> Self {
+        Self { signature_validated: true, ..self }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum SignatureScheme {
+    Ecdsa,
+    Erc1271,
+}
+
+/// Signature of the request of an order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct SignatureDetails {
+    scheme: SignatureScheme,
+    /// Requestor the request is signed by
+    signer: Address,
+    /// EIP-712 hash of the request that is signed
+    signing_hash: FixedBytes<32>,
+    signature: Bytes,
+    validated: bool,
+}
+
+/// Origin, time of receipt and signature of an order received by the broker, recorded to debug
+/// disputes about the origin and timing of orders.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct OrderProvenanceRecord {
+    order_id: String,
+    request_id: U256,
+    fulfillment_type: FulfillmentType,
+    #[serde(flatten)]
+    provenance: OrderProvenance,
+    signature: SignatureDetails,
+}
+
+/// Transaction an order spent gas on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum GasTxKind {
+    /// Lock transaction of the order, including reverted ones
+    Lock,
+    /// Fulfillment transaction of the batch of the order
+    Fulfill,
+}
+
+/// Gas spent by an order on a transaction, recorded to account for the gas cost of each order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct OrderGasRecord {
+    order_id: String,
+    kind: GasTxKind,
+    tx_hash: FixedBytes<32>,
+    /// Share of the gas used by the transaction, split evenly across the orders it is for
+    gas_used: u64,
+    /// Share of the gas used times the effective gas price, in the native token
+    gas_cost: U256,
+    #[serde(with = "ts_seconds")]
+    created_at: DateTime<Utc>,
+}
+
+impl OrderGasRecord {
+    /// Record of the share of one of `num_orders` orders sharing the transaction.
+    fn new(
+        order_id: &str,
+        kind: GasTxKind,
+        receipt: &TransactionReceipt,
+        num_orders: usize,
+    ) -> Self {
+        let num_orders = num_orders.max(1) as u64;
+        let gas_cost = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+        Self {
+            order_id: order_id.to_string(),
+            kind,
+            tx_hash: receipt.transaction_hash,
+            gas_used: receipt.gas_used / num_orders,
+            gas_cost: gas_cost / U256::from(num_orders),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Intent to fulfill a request, recorded before its fulfillment transaction is sent so that the
+/// request is only fulfilled once across restarts of the broker.
+///
+/// Intents are kept in the broker's own DB, so they do not guard against other broker replicas.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct FulfillmentIntent {
+    request_id: U256,
+    order_id: String,
+    batch_id: usize,
+    /// Hash of the fulfillment transaction, once confirmed
+    tx_hash: Option<FixedBytes<32>>,
+    #[serde(with = "ts_seconds")]
+    created_at: DateTime<Utc>,
+}
+
+impl FulfillmentIntent {
+    fn new(request_id: U256, order_id: &str, batch_id: usize) -> Self {
+        Self {
+            request_id,
+            order_id: order_id.to_string(),
+            batch_id,
+            tx_hash: None,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Version of the format of the snapshots written by `broker export-state`.
+const STATE_FORMAT_VERSION: u32 = 1;
+
+/// Metadata of a snapshot of the broker state, written by `broker export-state`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct StateManifest {
+    /// Version of the snapshot format, see [STATE_FORMAT_VERSION]
+    format_version: u32,
+    /// Version of the broker that wrote the snapshot
+    broker_version: String,
+    exported_at: DateTime<Utc>,
+    chain_id: u64,
+    boundless_market_address: Address,
+    prover_address: Address,
+    /// IDs of the orders the prover was committed to when the snapshot was taken
+    committed_orders: Vec<String>,
+}
+
 #[derive(sqlx::Type, Default, Serialize, Deserialize, Debug, Clone, PartialEq)]
 enum BatchStatus {
     #[default]
@@ -408,6 +1055,10 @@ struct Batch {
     pub aggregation_state: Option<AggregationState>,
     /// When the batch was initially created.
     pub start_time: DateTime<Utc>,
+    /// Unix timestamp of when the first orders were added to the batch, opening its fulfillment
+    /// window.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_start: Option<u64>,
     /// The deadline for the batch, which is the earliest deadline for any order in the batch.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deadline: Option<u64>,
@@ -420,6 +1071,7 @@ struct Batch {
 pub struct Broker<P> {
     args: Args,
     provider: Arc<P>,
+    nonce_tracker: Option<NonceTracker>,
     db: DbObj,
     config_watcher: ConfigWatcher,
 }
@@ -438,77 +1090,176 @@ where
         let chain_id = provider.get_chain_id().await.context("Failed to get chain ID")?;
 
         // Resolve deployment configuration if not provided, or validate if provided
-        if let Some(manual_deployment) = &args.deployment {
-            // Check if there's a default deployment for this chain ID
-            if let Some(expected_deployment) = Deployment::from_chain_id(chain_id) {
-                Self::validate_deployment_config(manual_deployment, &expected_deployment, chain_id);
+        let registry = DeploymentRegistry::default();
+        if let Some(network) = &args.network {
+            let entry = registry
+                .get_by_name(network)
+                .with_context(|| format!("Unknown network name: {network}"))?;
+            let network_chain_id = entry.deployment.chain_id.unwrap_or(chain_id);
+            if network_chain_id != chain_id {
+                anyhow::bail!("Network {network} has chain ID {network_chain_id}, but the RPC provider reports chain ID {chain_id}");
+            }
+            args.deployment = Some(entry.deployment.clone());
+            tracing::info!("Using deployment configuration for network {network}");
+        } else if let Some(manual_deployment) = &args.deployment {
+            // Check if there's a known deployment for this chain ID
+            if registry.get(chain_id).is_some() {
+                Self::validate_deployment_config(&registry, manual_deployment, chain_id);
             } else {
                 tracing::info!("Using manually configured deployment for chain ID {chain_id} (no default available)");
             }
         } else {
-            args.deployment = Some(Deployment::from_chain_id(chain_id)
+            args.deployment = Some(registry.get(chain_id).map(|entry| entry.deployment.clone())
                 .with_context(|| format!("No default deployment found for chain ID {chain_id}. Please specify deployment configuration manually."))?);
             tracing::info!("Using default deployment configuration for chain ID {chain_id}");
         }
 
-        Ok(Self { args, db, provider: Arc::new(provider), config_watcher })
+        Ok(Self { args, db, provider: Arc::new(provider), nonce_tracker: None, config_watcher })
+    }
+
+    /// Release and fill the nonces of lock and fulfill transactions given up on, with the tracker
+    /// of the nonces assigned by the provider.
+    pub fn with_nonce_tracker(self, nonce_tracker: NonceTracker) -> Self {
+        Self { nonce_tracker: Some(nonce_tracker), ..self }
     }
 
     pub fn deployment(&self) -> &Deployment {
         self.args.deployment.as_ref().unwrap()
     }
 
-    fn validate_deployment_config(manual: &Deployment, expected: &Deployment, chain_id: u64) {
-        let mut warnings = Vec::new();
+    /// Address that locks and fulfills orders, and holds the stake.
+    ///
+    /// This is the delegated prover address if configured, otherwise the wallet address.
+    pub fn prover_addr(&self) -> Address {
+        self.args.prover_private_key.as_ref().unwrap_or(&self.args.private_key).address()
+    }
 
-        if manual.boundless_market_address != expected.boundless_market_address {
-            warnings.push(format!(
-                "boundless_market_address mismatch: configured={}, expected={}",
-                manual.boundless_market_address, expected.boundless_market_address
-            ));
-        }
+    /// Check the market config against recent market activity, printing warnings with the most
+    /// severe first.
+    pub async fn lint_config(
+        &self,
+        lookback_blocks: u64,
+        benchmark_khz: Option<u64>,
+    ) -> Result<()> {
+        let chain_id = self.provider.get_chain_id().await.context("Failed to get chain ID")?;
+        let sample = config_lint::sample_market(
+            self.provider.as_ref(),
+            &self.db,
+            self.deployment().boundless_market_address,
+            chain_id,
+            lookback_blocks,
+        )
+        .await
+        .context("Failed to sample market activity")?;
 
-        if manual.set_verifier_address != expected.set_verifier_address {
-            warnings.push(format!(
-                "set_verifier_address mismatch: configured={}, expected={}",
-                manual.set_verifier_address, expected.set_verifier_address
-            ));
-        }
+        let stake_token_decimals = BoundlessMarketService::new(
+            self.deployment().boundless_market_address,
+            self.provider.clone(),
+            Address::ZERO,
+        )
+        .stake_token_decimals()
+        .await
+        .context("Failed to get stake token decimals")?;
 
-        if let (Some(manual_addr), Some(expected_addr)) =
-            (manual.verifier_router_address, expected.verifier_router_address)
-        {
-            if manual_addr != expected_addr {
-                warnings.push(format!(
-                    "verifier_router_address mismatch: configured={manual_addr}, expected={expected_addr}"
-                ));
-            }
+        let warnings = {
+            let config = self.config_watcher.config.lock_all().context("Failed to lock config")?;
+            config_lint::lint_market_config(
+                &config.market,
+                &sample,
+                benchmark_khz,
+                stake_token_decimals,
+            )
+        };
+        println!(
+            "Checked config against {} locked requests ({} with known cycle counts) in the past {lookback_blocks} blocks",
+            sample.lock_timeouts.len(),
+            sample.mcycle_prices.len()
+        );
+        for warning in warnings {
+            println!("{warning}");
         }
+        Ok(())
+    }
 
-        if let (Some(manual_addr), Some(expected_addr)) =
-            (manual.stake_token_address, expected.stake_token_address)
-        {
-            if manual_addr != expected_addr {
-                warnings.push(format!(
-                    "stake_token_address mismatch: configured={manual_addr}, expected={expected_addr}"
-                ));
-            }
-        }
+    /// Write the labeled dataset of orders closed since the given UNIX timestamp to a Parquet file.
+    pub async fn export_dataset(&self, output: &Path, since: i64) -> Result<()> {
+        let num_rows = dataset::export_dataset(&self.db, self.prover_addr(), since, output)
+            .await
+            .context("Failed to export dataset")?;
+        println!("Exported {num_rows} orders to {}", output.display());
+        Ok(())
+    }
 
-        if manual.order_stream_url != expected.order_stream_url {
-            warnings.push(format!(
-                "order_stream_url mismatch: configured={:?}, expected={:?}",
-                manual.order_stream_url, expected.order_stream_url
-            ));
-        }
+    /// Write a snapshot of the DB, including the orders the prover is committed to, to a new
+    /// file at `output`.
+    pub async fn export_state(&self, output: &Path) -> Result<()> {
+        anyhow::ensure!(!output.exists(), "{} already exists", output.display());
+        let chain_id = self.provider.get_chain_id().await.context("Failed to get chain ID")?;
+        let committed_orders =
+            self.db.get_committed_orders().await.context("Failed to get committed orders")?;
+        let manifest = StateManifest {
+            format_version: STATE_FORMAT_VERSION,
+            broker_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: Utc::now(),
+            chain_id,
+            boundless_market_address: self.deployment().boundless_market_address,
+            prover_address: self.prover_addr(),
+            committed_orders: committed_orders.iter().map(Order::id).collect(),
+        };
+        self.db.export_state(output, &manifest).await.context("Failed to export state")?;
+        println!(
+            "Exported state with {} committed orders to {}",
+            manifest.committed_orders.len(),
+            output.display()
+        );
+        Ok(())
+    }
 
-        if let (Some(chain_id), Some(expected_chain_id)) = (manual.chain_id, expected.chain_id) {
-            if chain_id != expected_chain_id {
-                warnings.push(format!(
-                    "chain_id mismatch: configured={chain_id}, expected={expected_chain_id}"
-                ));
-            }
-        }
+    /// Import a snapshot written by [Self::export_state] into the DB, which must not have any
+    /// orders yet.
+    ///
+    /// Unless `keep_proof_ids` is set, committed orders are proven again from the start, as
+    /// their proofs are held by the prover the snapshot was taken next to.
+    pub async fn import_state(&self, input: &Path, keep_proof_ids: bool) -> Result<()> {
+        let manifest =
+            db::read_state_manifest(input).await.context("Failed to read state snapshot")?;
+        anyhow::ensure!(
+            manifest.format_version == STATE_FORMAT_VERSION,
+            "Unsupported state snapshot format version {}",
+            manifest.format_version
+        );
+        let chain_id = self.provider.get_chain_id().await.context("Failed to get chain ID")?;
+        anyhow::ensure!(
+            manifest.chain_id == chain_id
+                && manifest.boundless_market_address == self.deployment().boundless_market_address,
+            "State snapshot is of market {} on chain {}, not market {} on chain {chain_id}",
+            manifest.boundless_market_address,
+            manifest.chain_id,
+            self.deployment().boundless_market_address
+        );
+        anyhow::ensure!(
+            manifest.prover_address == self.prover_addr(),
+            "State snapshot is of prover {}, not {}",
+            manifest.prover_address,
+            self.prover_addr()
+        );
+
+        self.db.import_state(input, !keep_proof_ids).await.context("Failed to import state")?;
+        println!(
+            "Imported state exported at {} by broker {}, with {} committed orders",
+            manifest.exported_at,
+            manifest.broker_version,
+            manifest.committed_orders.len()
+        );
+        Ok(())
+    }
+
+    fn validate_deployment_config(
+        registry: &DeploymentRegistry,
+        manual: &Deployment,
+        chain_id: u64,
+    ) {
+        let warnings = registry.check(chain_id, manual);
 
         if warnings.is_empty() {
             tracing::info!(
@@ -613,17 +1364,103 @@ where
         Ok(())
     }
 
+    /// Prover backend selected by the config and the broker arguments.
+    fn build_prover(&self, config: &ConfigLock) -> Result<ProverObj> {
+        let prover_backends = config.lock_all().context("Failed to read config")?.provers.clone();
+        let prover: provers::ProverObj = if is_dev_mode() {
+            tracing::warn!("WARNING: Running the Broker in dev mode does not generate valid receipts. \
+            Receipts generated from this process are invalid and should never be used in production.");
+            Arc::new(provers::DefaultProver::new())
+        } else if !prover_backends.is_empty() {
+            tracing::info!(
+                "Configured to run with a pool of {} prover backends",
+                prover_backends.len()
+            );
+            Arc::new(
+                provers::ProverPool::from_conf(config, &prover_backends)
+                    .context("Failed to construct prover pool")?,
+            )
+        } else if let (Some(bonsai_api_key), Some(bonsai_api_url)) =
+            (self.args.bonsai_api_key.as_ref(), self.args.bonsai_api_url.as_ref())
+        {
+            tracing::info!("Configured to run with Bonsai backend");
+            Arc::new(
+                provers::Bonsai::new(config.clone(), bonsai_api_url.as_ref(), bonsai_api_key)
+                    .context("Failed to construct Bonsai client")?,
+            )
+        } else if let Some(bento_api_url) = self.args.bento_api_url.as_ref() {
+            tracing::info!("Configured to run with Bento backend");
+
+            Arc::new(
+                provers::Bonsai::new(config.clone(), bento_api_url.as_ref(), "")
+                    .context("Failed to initialize Bento client")?,
+            )
+        } else {
+            Arc::new(provers::DefaultProver::new())
+        };
+        Ok(prover)
+    }
+
+    /// Benchmark the prover backend and calibrate the gas estimates, printing the calibrated
+    /// market config values and writing them to the config file if `write` is set.
+    pub async fn benchmark(&self, mcycles: &[u64], gas_samples: u32, write: bool) -> Result<()> {
+        let prover = self.build_prover(&self.config_watcher.config)?;
+        let runs = benchmark::run_loop_guest(&prover, mcycles).await?;
+        for run in &runs {
+            println!(
+                "{} cycles: proving {:.0} kHz ({:.1}s), preflight {:.0} kHz ({:.1}s)",
+                run.total_cycles,
+                run.prove_khz(),
+                run.prove_secs,
+                run.preflight_khz(),
+                run.preflight_secs
+            );
+        }
+        let gas_records = self
+            .db
+            .get_recent_gas_records(gas_samples)
+            .await
+            .context("Failed to get recent gas records")?;
+        let values = benchmark::Calibration::new(&runs, &gas_records).values();
+        if values.is_empty() {
+            println!("Nothing to calibrate");
+            return Ok(());
+        }
+        for (field, value) in &values {
+            println!("{field} = {value}");
+        }
+
+        if write {
+            let path = &self.args.config_file;
+            let config = tokio::fs::read_to_string(path)
+                .await
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let updated = benchmark::set_market_values(&config, &values)?;
+            tokio::fs::write(path, updated)
+                .await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("Wrote calibrated values to {}", path.display());
+        }
+        Ok(())
+    }
+
     pub async fn start_service(&self) -> Result<()> {
         let mut supervisor_tasks: JoinSet<Result<()>> = JoinSet::new();
 
         let config = self.config_watcher.config.clone();
 
-        let loopback_blocks = {
+        let (loopback_blocks, gas_oracle_conf, watch_lock_races, gas_spike_conf, finality) = {
             let config = match config.lock_all() {
                 Ok(res) => res,
                 Err(err) => anyhow::bail!("Failed to lock config in watcher: {err:?}"),
             };
-            config.market.lookback_blocks
+            (
+                config.market.lookback_blocks,
+                config.market.gas_oracle.clone(),
+                config.market.lock_race_watch.is_some(),
+                config.market.gas_spike.clone(),
+                config.market.confirmations.as_ref().map(|conf| conf.finality_depth),
+            )
         };
 
         // Create two cancellation tokens for graceful shutdown:
@@ -631,12 +1468,35 @@ where
         // 2. Critical tasks (proving, aggregation, submission) - cancelled only after committed orders complete
         let non_critical_cancel_token = CancellationToken::new();
         let critical_cancel_token = CancellationToken::new();
+        // Toggled through the admin API, shuts down as on a signal once drained
+        let drain_mode: admin::DrainModeObj = Default::default();
 
-        let chain_monitor = Arc::new(
-            chain_monitor::ChainMonitorService::new(self.provider.clone())
+        // Metrics collected by the broker services, served to Prometheus if configured
+        let metrics: metrics::MetricsObj = Default::default();
+
+        let gas_oracle =
+            chain_monitor::gas_oracle_from_conf(&gas_oracle_conf, self.provider.clone())
                 .await
-                .context("Failed to initialize chain monitor")?,
-        );
+                .context("Failed to initialize gas oracle")?;
+        let mut chain_monitor = chain_monitor::ChainMonitorService::new(self.provider.clone())
+            .await
+            .context("Failed to initialize chain monitor")?
+            .with_gas_oracle(gas_oracle)
+            .with_metrics(metrics.clone());
+        if let Some(conf) = &gas_spike_conf {
+            chain_monitor = chain_monitor.with_gas_spike(conf.clone());
+        }
+        if let Some(finality_depth) = finality {
+            chain_monitor = chain_monitor.with_finality(finality_depth);
+        }
+        // Lock transactions are sent by the wallet, also when locking for a delegated prover
+        if watch_lock_races {
+            chain_monitor = chain_monitor.with_mempool_watch(
+                self.deployment().boundless_market_address,
+                self.provider.default_signer_address(),
+            );
+        }
+        let chain_monitor = Arc::new(chain_monitor);
 
         let cloned_chain_monitor = chain_monitor.clone();
         let cloned_config = config.clone();
@@ -668,21 +1528,71 @@ where
         // Create a channel for new orders to be sent to the OrderPicker / from monitors
         let (new_order_tx, new_order_rx) = mpsc::channel(NEW_ORDER_CHANNEL_CAPACITY);
 
-        // Create a broadcast channel for order state change messages
-        let (order_state_tx, _) = tokio::sync::broadcast::channel(ORDER_STATE_CHANNEL_CAPACITY);
+        // Bus of the events of all services, including order state changes
+        let events = events::EventBus::default();
+        let event_metrics = Arc::new(events::EventMetrics::new(&events, metrics.clone()));
+        let cloned_config = config.clone();
+        let cancel_token = non_critical_cancel_token.clone();
+        supervisor_tasks.spawn(async move {
+            Supervisor::new(event_metrics, cloned_config, cancel_token)
+                .spawn()
+                .await
+                .context("Failed to start event metrics")?;
+            Ok(())
+        });
+
+        // Market statistics, collected by the monitors and used to schedule lock-expired orders
+        let market_stats: market_stats::MarketStatsObj = Default::default();
+
+        // Orders recently received by the order picker, invalidated by the services that queue
+        // orders again
+        let order_cache = order_picker::new_order_cache();
+
+        // Replicas with a leadership lease start on standby, and lock orders once they acquire it
+        let leader_lease_secs = config
+            .lock_all()
+            .context("Failed to read config")?
+            .market
+            .order_claims
+            .as_ref()
+            .and_then(|conf| conf.leader_lease_secs);
+        let leadership: leadership::LeadershipObj = match leader_lease_secs {
+            Some(_) => Arc::new(leadership::Leadership::standby()),
+            None => Default::default(),
+        };
+
+        if let Some(listen_addr) = self.args.metrics_listen_addr {
+            let metrics_server =
+                metrics::MetricsServer::new(listen_addr, metrics.clone()).with_db(self.db.clone());
+            let metrics_server = Arc::new(metrics_server);
+            let cloned_config = config.clone();
+            let cancel_token = non_critical_cancel_token.clone();
+            supervisor_tasks.spawn(async move {
+                Supervisor::new(metrics_server, cloned_config, cancel_token)
+                    .spawn()
+                    .await
+                    .context("Failed to start metrics endpoint")?;
+                Ok(())
+            });
+        }
 
         // spin up a supervisor for the market monitor
-        let market_monitor = Arc::new(market_monitor::MarketMonitor::new(
-            loopback_blocks,
-            self.deployment().boundless_market_address,
-            self.provider.clone(),
-            self.db.clone(),
-            chain_monitor.clone(),
-            self.args.private_key.address(),
-            client.clone(),
-            new_order_tx.clone(),
-            order_state_tx.clone(),
-        ));
+        let market_monitor = Arc::new(
+            market_monitor::MarketMonitor::new(
+                loopback_blocks,
+                self.deployment().boundless_market_address,
+                self.provider.clone(),
+                self.db.clone(),
+                chain_monitor.clone(),
+                self.prover_addr(),
+                client.clone(),
+                new_order_tx.clone(),
+                events.clone(),
+            )
+            .with_market_stats(market_stats.clone())
+            .with_order_cache(order_cache.clone())
+            .with_leadership(leadership.clone()),
+        );
 
         let block_times =
             market_monitor.get_block_time().await.context("Failed to sample block times")?;
@@ -701,12 +1611,33 @@ where
 
         // spin up a supervisor for the offchain market monitor
         if let Some(client_clone) = client {
-            let offchain_market_monitor =
-                Arc::new(offchain_market_monitor::OffchainMarketMonitor::new(
+            // Orders from the order stream have their signatures verified before being priced
+            let (stream_order_tx, stream_order_rx) = mpsc::channel(NEW_ORDER_CHANNEL_CAPACITY);
+            let signature_verifier = Arc::new(signature_verifier::SignatureVerifier::new(
+                config.clone(),
+                metrics.clone(),
+                stream_order_rx,
+                new_order_tx.clone(),
+            ));
+            let cloned_config = config.clone();
+            let cancel_token = non_critical_cancel_token.clone();
+            supervisor_tasks.spawn(async move {
+                Supervisor::new(signature_verifier, cloned_config, cancel_token)
+                    .spawn()
+                    .await
+                    .context("Failed to start signature verifier")?;
+                Ok(())
+            });
+
+            let offchain_market_monitor = Arc::new(
+                offchain_market_monitor::OffchainMarketMonitor::new(
                     client_clone,
                     self.args.private_key.clone(),
-                    new_order_tx.clone(),
-                ));
+                    stream_order_tx,
+                    config.clone(),
+                )
+                .with_metrics(metrics.clone()),
+            );
             let cloned_config = config.clone();
             let cancel_token = non_critical_cancel_token.clone();
             supervisor_tasks.spawn(async move {
@@ -719,29 +1650,35 @@ where
         }
 
         // Construct the prover object interface
-        let prover: provers::ProverObj = if is_dev_mode() {
-            tracing::warn!("WARNING: Running the Broker in dev mode does not generate valid receipts. \
-            Receipts generated from this process are invalid and should never be used in production.");
-            Arc::new(provers::DefaultProver::new())
-        } else if let (Some(bonsai_api_key), Some(bonsai_api_url)) =
-            (self.args.bonsai_api_key.as_ref(), self.args.bonsai_api_url.as_ref())
-        {
-            tracing::info!("Configured to run with Bonsai backend");
-            Arc::new(
-                provers::Bonsai::new(config.clone(), bonsai_api_url.as_ref(), bonsai_api_key)
-                    .context("Failed to construct Bonsai client")?,
-            )
-        } else if let Some(bento_api_url) = self.args.bento_api_url.as_ref() {
-            tracing::info!("Configured to run with Bento backend");
-
-            Arc::new(
-                provers::Bonsai::new(config.clone(), bento_api_url.as_ref(), "")
-                    .context("Failed to initialize Bento client")?,
-            )
-        } else {
-            Arc::new(provers::DefaultProver::new())
+        let prover = self.build_prover(&config)?;
+
+        // Images downloaded for orders, shared by the image pinner, order picker and proving
+        // service
+        let image_cache_conf =
+            config.lock_all().context("Failed to read config")?.prover.image_cache.clone();
+        let image_cache: Option<image_cache::ImageCacheObj> = match image_cache_conf {
+            Some(conf) => Some(Arc::new(
+                image_cache::ImageCache::open(&conf).await.context("Failed to open image cache")?,
+            )),
+            None => None,
         };
 
+        // Download the pinned images ahead of the first orders using them
+        let mut image_pinner = image_pinner::ImagePinner::new(config.clone(), prover.clone());
+        if let Some(image_cache) = &image_cache {
+            image_pinner = image_pinner.with_image_cache(image_cache.clone());
+        }
+        let image_pinner = Arc::new(image_pinner);
+        let cloned_config = config.clone();
+        let cancel_token = non_critical_cancel_token.clone();
+        supervisor_tasks.spawn(async move {
+            Supervisor::new(image_pinner, cloned_config, cancel_token)
+                .spawn()
+                .await
+                .context("Failed to start image pinner")?;
+            Ok(())
+        });
+
         let (pricing_tx, pricing_rx) = mpsc::channel(PRICING_CHANNEL_CAPACITY);
 
         let stake_token_decimals = BoundlessMarketService::new(
@@ -752,20 +1689,153 @@ where
         .stake_token_decimals()
         .await
         .context("Failed to get stake token decimals. Possible RPC error.")?;
+        let stake_token_decimals = utils::check_stake_token_decimals(
+            config.lock_all().context("Failed to read config")?.market.stake_token_decimals,
+            stake_token_decimals,
+            chain_id,
+        )?;
+
+        // Proving queue and throughput, recorded by the proving service and used when pricing
+        let capacity_tracker: proving_capacity::ProvingCapacityTrackerObj = Arc::new(
+            proving_capacity::ProvingCapacityTracker::new(self.db.clone(), prover.clone()),
+        );
+
+        // Inputs uploaded to the prover, shared by the order picker and proving service
+        let input_dedup: input_dedup::InputDedupObj =
+            Arc::new(input_dedup::InputDedup::new(prover.clone()));
+
+        // Inputs fetched from URLs, shared by the order picker and proving service
+        let input_prefetch_concurrency = {
+            let config = config.lock_all().context("Failed to read config")?;
+            config.market.input_prefetch_concurrency as usize
+        };
+        let input_fetcher: input_fetcher::InputFetcherObj =
+            Arc::new(input_fetcher::InputFetcher::new(config.clone(), input_prefetch_concurrency));
+
+        // Groth16 wrapping pool, used by the proving service and its latency by the order picker
+        let groth16_wrapper: groth16_wrapper::Groth16WrapperObj = {
+            let config = config.lock_all().context("Failed to read config")?;
+            Arc::new(groth16_wrapper::Groth16Wrapper::new(
+                prover.clone(),
+                config.prover.groth16_workers,
+            ))
+        };
+
+        // Cycles and deadlines of committed orders, estimated by the order picker and used to
+        // order proofs by urgency
+        let scheduler: scheduler::ProvingSchedulerObj = Default::default();
+
+        // Lock and fulfillment outcomes, used to tighten order intake while falling behind
+        let self_throttle: self_throttle::SelfThrottleObj = Default::default();
+
+        // Stake and gas reserved by the order picker for priced orders, until the order monitor
+        // commits or drops them
+        let reservations: reservations::ReservationsObj = Default::default();
+
+        // Claims on requests shared with other broker replicas, taken when pricing and locking
+        let order_claims_conf =
+            config.lock_all().context("Failed to read config")?.market.order_claims.clone();
+        let order_claims: Option<order_claims::OrderClaimsObj> = match order_claims_conf {
+            Some(conf) => Some(Arc::new(
+                order_claims::OrderClaims::connect(&conf)
+                    .await
+                    .context("Failed to connect to the order claims database")?,
+            )),
+            None => None,
+        };
+        if let Some((order_claims, lease_secs)) = order_claims.clone().zip(leader_lease_secs) {
+            let leader_election = Arc::new(leadership::LeaderElection::new(
+                order_claims,
+                leadership.clone(),
+                lease_secs,
+            ));
+            let cloned_config = config.clone();
+            // Critical task, so that the lease is held until committed orders complete
+            let cancel_token = critical_cancel_token.clone();
+            supervisor_tasks.spawn(async move {
+                Supervisor::new(leader_election, cloned_config, cancel_token)
+                    .spawn()
+                    .await
+                    .context("Failed to start leader election")?;
+                Ok(())
+            });
+        }
+
+        let admin_conf = self.args.admin_listen_addr.zip(self.args.admin_token.clone());
+        let (cancel_pricing_tx, cancel_pricing_rx) = mpsc::channel(CANCEL_PRICING_CHANNEL_CAPACITY);
 
         // Spin up the order picker to pre-flight and find orders to lock
-        let order_picker = Arc::new(order_picker::OrderPicker::new(
+        let mut order_picker = order_picker::OrderPicker::new(
             self.db.clone(),
             config.clone(),
             prover.clone(),
+            chain_id,
             self.deployment().boundless_market_address,
             self.provider.clone(),
             chain_monitor.clone(),
             new_order_rx,
             pricing_tx,
             stake_token_decimals,
-            order_state_tx.clone(),
-        ));
+            events.clone(),
+            capacity_tracker.clone(),
+            input_dedup.clone(),
+        )
+        .with_prover_addr(self.prover_addr())
+        .with_dry_run(self.args.command == Some(Command::DryRun))
+        .with_input_fetcher(input_fetcher.clone())
+        .with_scheduler(scheduler.clone())
+        .with_groth16_wrapper(groth16_wrapper.clone())
+        .with_self_throttle(self_throttle.clone())
+        .with_reservations(reservations.clone())
+        .with_order_cache(order_cache.clone())
+        .with_leadership(leadership.clone())
+        .with_metrics(metrics.clone());
+        if admin_conf.is_some() {
+            order_picker = order_picker.with_cancel_pricing_rx(cancel_pricing_rx);
+        }
+        if let Some(image_cache) = &image_cache {
+            order_picker = order_picker.with_image_cache(image_cache.clone());
+        }
+        if let Some(order_claims) = &order_claims {
+            order_picker = order_picker.with_order_claims(order_claims.clone());
+        }
+
+        // Pricing decisions are sent to the operator webhook in batches, off the pricing path
+        let pricing_webhook_conf =
+            config.lock_all().context("Failed to read config")?.market.pricing_webhook.clone();
+        if let Some(pricing_webhook_conf) = pricing_webhook_conf {
+            let (pricing_webhook, pricing_webhook_queue) =
+                pricing_webhook::PricingWebhook::new(pricing_webhook_conf);
+            order_picker = order_picker.with_pricing_webhook(pricing_webhook_queue);
+            let pricing_webhook = Arc::new(pricing_webhook);
+            let cloned_config = config.clone();
+            let cancel_token = non_critical_cancel_token.clone();
+            supervisor_tasks.spawn(async move {
+                Supervisor::new(pricing_webhook, cloned_config, cancel_token)
+                    .spawn()
+                    .await
+                    .context("Failed to start pricing webhook")?;
+                Ok(())
+            });
+        }
+
+        // Order events and low balances are forwarded to the operator webhook in batches
+        let event_webhook_conf =
+            config.lock_all().context("Failed to read config")?.market.event_webhook.clone();
+        if let Some(event_webhook_conf) = event_webhook_conf {
+            let event_webhook = Arc::new(events::EventWebhook::new(event_webhook_conf, &events)?);
+            let cloned_config = config.clone();
+            let cancel_token = non_critical_cancel_token.clone();
+            supervisor_tasks.spawn(async move {
+                Supervisor::new(event_webhook, cloned_config, cancel_token)
+                    .spawn()
+                    .await
+                    .context("Failed to start event webhook")?;
+                Ok(())
+            });
+        }
+
+        let order_picker = Arc::new(order_picker);
         let cloned_config = config.clone();
         let cancel_token = non_critical_cancel_token.clone();
         supervisor_tasks.spawn(async move {
@@ -776,16 +1846,45 @@ where
             Ok(())
         });
 
-        let proving_service = Arc::new(
-            proving::ProvingService::new(
-                self.db.clone(),
-                prover.clone(),
-                config.clone(),
-                order_state_tx.clone(),
-            )
-            .await
-            .context("Failed to initialize proving service")?,
-        );
+        // Re-queue orders skipped for insufficient balance or capacity once those improve
+        let skip_reevaluator = Arc::new(skip_reevaluator::SkipReevaluator::new(
+            self.db.clone(),
+            config.clone(),
+            self.provider.clone(),
+            self.deployment().boundless_market_address,
+            self.prover_addr(),
+            chain_id,
+            new_order_tx.clone(),
+            order_cache.clone(),
+        ));
+        let cloned_config = config.clone();
+        let cancel_token = non_critical_cancel_token.clone();
+        supervisor_tasks.spawn(async move {
+            Supervisor::new(skip_reevaluator, cloned_config, cancel_token)
+                .spawn()
+                .await
+                .context("Failed to start skip re-evaluator")?;
+            Ok(())
+        });
+
+        let mut proving_service = proving::ProvingService::new(
+            self.db.clone(),
+            prover.clone(),
+            config.clone(),
+            events.clone(),
+            capacity_tracker.clone(),
+            input_dedup.clone(),
+        )
+        .await
+        .context("Failed to initialize proving service")?
+        .with_input_fetcher(input_fetcher)
+        .with_scheduler(scheduler)
+        .with_groth16_wrapper(groth16_wrapper)
+        .with_leadership(leadership.clone());
+        if let Some(image_cache) = &image_cache {
+            proving_service = proving_service.with_image_cache(image_cache.clone());
+        }
+        let proving_service = Arc::new(proving_service);
 
         let cloned_config = config.clone();
         let cancel_token = critical_cancel_token.clone();
@@ -797,9 +1896,28 @@ where
             Ok(())
         });
 
-        let prover_addr = self.args.private_key.address();
+        let prover_addr = self.prover_addr();
+        // Shared by the lock and fulfill paths, so that their transactions share the queue and
+        // the nonces given up on
+        let mut tx_submitter = utils::tx_submitter(&config)?;
+        if let Some(nonce_tracker) = self.nonce_tracker.clone() {
+            tx_submitter = tx_submitter.with_nonce_tracker(nonce_tracker);
+        }
+        let private_relay_conf =
+            config.lock_all().context("Failed to read config")?.market.lock_private_relay.clone();
+        if let Some(private_relay_conf) = private_relay_conf {
+            tracing::info!(
+                "Sending lock transactions through private relay {}",
+                private_relay_conf.url
+            );
+            tx_submitter = tx_submitter.with_private_relay(PrivateRelay::new(
+                private_relay_conf.url,
+                EthereumWallet::from(self.args.private_key.clone()),
+                std::time::Duration::from_secs(private_relay_conf.fallback_secs),
+            ));
+        }
 
-        let order_monitor = Arc::new(order_monitor::OrderMonitor::new(
+        let mut order_monitor = order_monitor::OrderMonitor::new(
             self.db.clone(),
             self.provider.clone(),
             chain_monitor.clone(),
@@ -813,7 +1931,57 @@ where
                 retry_count: self.args.rpc_retry_max.into(),
                 retry_sleep_ms: self.args.rpc_retry_backoff,
             },
-        )?);
+            capacity_tracker,
+        )?
+        .with_tx_submitter(tx_submitter.clone())
+        .with_market_stats(market_stats)
+        .with_metrics(metrics.clone())
+        .with_events(events.clone())
+        .with_self_throttle(self_throttle.clone())
+        .with_drain_mode(drain_mode.clone())
+        .with_leadership(leadership.clone())
+        .with_reservations(reservations)
+        .with_dry_run(self.args.command == Some(Command::DryRun));
+        if let Some(prover_signer) = self.args.prover_private_key.clone() {
+            tracing::info!(
+                "Locking orders on behalf of delegated prover {prover_addr}, sending transactions from {}",
+                self.args.private_key.address()
+            );
+            order_monitor = order_monitor.with_delegated_prover(prover_signer, chain_id);
+        }
+        if let Some(order_claims) = order_claims {
+            order_monitor = order_monitor.with_order_claims(order_claims);
+        }
+        if let Some((listen_addr, token)) = admin_conf {
+            let (manual_lock_tx, manual_lock_rx) = mpsc::channel(MANUAL_LOCK_CHANNEL_CAPACITY);
+            order_monitor = order_monitor.with_manual_lock_rx(manual_lock_rx);
+
+            let admin_server = Arc::new(
+                admin::AdminServer::new(
+                    listen_addr,
+                    token,
+                    self.db.clone(),
+                    config.clone(),
+                    drain_mode.clone(),
+                )
+                .with_manual_lock_tx(manual_lock_tx)
+                .with_cancel_pricing_tx(cancel_pricing_tx)
+                .with_priced_orders(order_monitor.priced_orders())
+                .with_events(events.clone()),
+            );
+            let cloned_config = config.clone();
+            // Critical task, so that the drain status can be queried until the broker exits
+            let cancel_token = critical_cancel_token.clone();
+            supervisor_tasks.spawn(async move {
+                Supervisor::new(admin_server, cloned_config, cancel_token)
+                    .spawn()
+                    .await
+                    .context("Failed to start admin API")?;
+                Ok(())
+            });
+        }
+        let priced_orders = order_monitor.priced_orders();
+        let order_monitor = Arc::new(order_monitor);
         let cloned_config = config.clone();
         let cancel_token = non_critical_cancel_token.clone();
         supervisor_tasks.spawn(async move {
@@ -854,8 +2022,12 @@ where
         });
 
         // Start the ReaperTask to check for expired committed orders
-        let reaper =
-            Arc::new(reaper::ReaperTask::new(self.db.clone(), config.clone(), prover.clone()));
+        let reaper = Arc::new(
+            reaper::ReaperTask::new(self.db.clone(), config.clone(), prover.clone(), input_dedup)
+                .with_metrics(metrics.clone())
+                .with_live_orders(priced_orders, order_cache)
+                .with_self_throttle(self_throttle.clone()),
+        );
         let cloned_config = config.clone();
         // Using critical cancel token to ensure no stuck expired jobs on shutdown
         let cancel_token = critical_cancel_token.clone();
@@ -867,15 +2039,55 @@ where
             Ok(())
         });
 
-        let submitter = Arc::new(submitter::Submitter::new(
-            self.db.clone(),
-            config.clone(),
-            prover.clone(),
-            self.provider.clone(),
-            self.deployment().set_verifier_address,
-            self.deployment().boundless_market_address,
-            set_builder_img_id,
-        )?);
+        // Start the ArchiverTask to move closed orders to object storage, if configured
+        let archive_bucket = {
+            let config = config.lock_all().context("Failed to lock config")?;
+            config.archive.bucket.clone()
+        };
+        if let Some(bucket) = archive_bucket {
+            let store = archiver::S3ArchiveStore::new(bucket)
+                .await
+                .context("Failed to initialize archive store")?;
+            let mut archiver = archiver::ArchiverTask::new(
+                self.db.clone(),
+                config.clone(),
+                prover.clone(),
+                Arc::new(store),
+            );
+            if finality.is_some() {
+                archiver = archiver.with_finalized_block(chain_monitor.subscribe_finalized_block());
+            }
+            let archiver = Arc::new(archiver);
+            let cloned_config = config.clone();
+            let cancel_token = non_critical_cancel_token.clone();
+            supervisor_tasks.spawn(async move {
+                Supervisor::new(archiver, cloned_config, cancel_token)
+                    .spawn()
+                    .await
+                    .context("Failed to start archiver service")?;
+                Ok(())
+            });
+        }
+
+        let submitter = Arc::new(
+            submitter::Submitter::new(
+                self.db.clone(),
+                config.clone(),
+                prover.clone(),
+                self.provider.clone(),
+                self.deployment().set_verifier_address,
+                self.deployment().boundless_market_address,
+                set_builder_img_id,
+                self.prover_addr(),
+            )?
+            .with_tx_submitter(tx_submitter)
+            .with_events(events.clone())
+            .with_metrics(metrics.clone())
+            .with_self_throttle(self_throttle)
+            .with_stake_token_decimals(stake_token_decimals)
+            .with_chain_monitor(chain_monitor.clone())
+            .with_leadership(leadership),
+        );
         let cloned_config = config.clone();
         let cancel_token = critical_cancel_token.clone();
         supervisor_tasks.spawn(async move {
@@ -931,6 +2143,10 @@ where
                     tracing::info!("Received SIGINT, starting graceful shutdown...");
                     break;
                 }
+                _ = drain_mode.drained() => {
+                    tracing::info!("Drained, starting graceful shutdown...");
+                    break;
+                }
             }
         }
 
@@ -1073,8 +2289,11 @@ pub mod test_utils {
                 db_url: "sqlite::memory:".into(),
                 config_file: config_file.path().to_path_buf(),
                 deployment: Some(ctx.deployment.clone()),
+                network: None,
                 rpc_url,
+                read_rpc_url: None,
                 private_key: ctx.prover_signer.clone(),
+                prover_private_key: None,
                 bento_api_url: None,
                 bonsai_api_key: None,
                 bonsai_api_url: None,
@@ -1083,6 +2302,10 @@ pub mod test_utils {
                 rpc_retry_backoff: 200,
                 rpc_retry_cu: 1000,
                 log_json: false,
+                admin_listen_addr: None,
+                admin_token: None,
+                metrics_listen_addr: None,
+                command: None,
             };
             Self { args, provider: ctx.prover_provider.clone(), config_file }
         }
@@ -1098,5 +2321,128 @@ pub mod test_utils {
     }
 }
 
+#[cfg(feature = "bench")]
+pub mod bench {
+    //! Entry points to the per-order pricing hot path, for the benchmarks in `benches/pricing.rs`
+    //! and the `pricing-soak` binary.
+    //!
+    //! Orders are synthetic and nothing here touches the chain, the DB, or a prover, so timings
+    //! are comparable between machines running the same build.
+
+    use alloy::{
+        primitives::{Address, Bytes, U256},
+        sol_types::SolValue,
+    };
+    use anyhow::Result;
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput, Requirements,
+    };
+    use risc0_zkvm::sha::Digest;
+
+    use crate::{
+        config::{ConfigLock, MarketConf},
+        order_picker::check_order_static,
+        utils, FulfillmentType, OrderRequest,
+    };
+
+    pub use crate::selectors::SelectorRegistry;
+
+    const CHAIN_ID: u64 = 31337;
+
+    /// A synthetic order, as received from the market.
+    pub struct SyntheticOrder {
+        order: OrderRequest,
+        order_id: String,
+    }
+
+    impl SyntheticOrder {
+        /// Creates the `index`-th synthetic order, with bidding starting at `bidding_start`.
+        ///
+        /// Orders vary in client, image, input size and timeouts, and every 8th order is
+        /// an order to fulfill after its lock expired.
+        pub fn new(index: u32, bidding_start: u64) -> Self {
+            let client = Address::with_last_byte((index % 251) as u8);
+            let image_id = Digest::from([index % 17; 8]);
+            let input = vec![0x41; 4 + (index % 64) as usize * 32];
+            let lock_timeout = 300 + (index % 10) * 60;
+            let request = ProofRequest::new(
+                RequestId::new(client, index),
+                Requirements::new(
+                    image_id,
+                    Predicate { predicateType: PredicateType::PrefixMatch, data: Bytes::new() },
+                ),
+                format!("https://example.com/image/{}", index % 17),
+                RequestInput::builder().write_slice(&input).build_inline().unwrap(),
+                Offer {
+                    minPrice: U256::from(1_000_000_000u64),
+                    maxPrice: U256::from(1_000_000_000_000_000u64 + index as u64),
+                    biddingStart: bidding_start,
+                    rampUpPeriod: 60,
+                    lockTimeout: lock_timeout,
+                    timeout: lock_timeout * 2,
+                    lockStake: U256::from(10u64.pow(18)),
+                },
+            );
+            let fulfillment_type = if index % 8 == 0 {
+                FulfillmentType::FulfillAfterLockExpire
+            } else {
+                FulfillmentType::LockAndFulfill
+            };
+            let order = OrderRequest::new(
+                request,
+                Bytes::new(),
+                fulfillment_type,
+                Address::with_last_byte(0xbb),
+                CHAIN_ID,
+            );
+            Self { order_id: order.id(), order }
+        }
+
+        /// Runs the static checks of the order picker, returning whether the order is skipped.
+        pub fn is_skipped(&self, market: &MarketConf, now: u64) -> bool {
+            check_order_static(&self.order, &self.order_id, market, now).is_some()
+        }
+
+        /// Computes the order ID, which includes the EIP-712 digest of the request.
+        pub fn order_id(&self) -> String {
+            self.order.id()
+        }
+
+        /// Estimates the gas to lock, unless the lock expired, and fulfill the order.
+        pub async fn gas_estimate(
+            &self,
+            config: &ConfigLock,
+            selectors: &SelectorRegistry,
+        ) -> Result<u64> {
+            let fulfill =
+                utils::estimate_gas_to_fulfill(config, selectors, &self.order.request).await?;
+            if self.order.fulfillment_type == FulfillmentType::FulfillAfterLockExpire {
+                return Ok(fulfill);
+            }
+            Ok(utils::estimate_gas_to_lock(config, &self.order).await? + fulfill)
+        }
+
+        /// ABI encoding of the proof request, as in the calldata of a submitted request.
+        pub fn abi_encoded(&self) -> Vec<u8> {
+            self.order.request.abi_encode()
+        }
+
+        /// JSON encoding of the order, as stored in the DB.
+        pub fn json_encoded(&self) -> Vec<u8> {
+            serde_json::to_vec(&self.order).unwrap()
+        }
+    }
+
+    /// Decodes an ABI encoded proof request, returning its request ID.
+    pub fn decode_abi(data: &[u8]) -> Result<U256> {
+        Ok(ProofRequest::abi_decode(data)?.id)
+    }
+
+    /// Decodes a JSON encoded order, returning its request ID.
+    pub fn decode_json(data: &[u8]) -> Result<U256> {
+        Ok(serde_json::from_slice::<OrderRequest>(data)?.request.id)
+    }
+}
+
 #[cfg(test)]
 pub mod tests;
@@ -0,0 +1,177 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Profitability of fulfilling orders after another prover's lock has expired.
+//!
+//! The reward of such an order is a share of the slashed stake, paid in stake tokens, while the
+//! gas to fulfill it is paid in the native token. All amounts here are in the smallest unit of
+//! the stake token, so that tokens with few decimals do not lose precision to an intermediate
+//! mcycle price.
+
+use alloy::primitives::{uint, utils::parse_units, U256};
+use anyhow::{Context, Result};
+
+use crate::config::LockExpiredPricingConf;
+
+const ONE_MILLION: U256 = uint!(1_000_000_U256);
+
+/// One native token (e.g. ETH), in wei.
+const ONE_ETHER: U256 = uint!(1_000_000_000_000_000_000_U256);
+
+/// Expected payout and costs of fulfilling a lock-expired order, in stake token units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LockExpiredProfit {
+    /// Share of the slashed stake expected to be paid out, accounting for competing provers
+    pub(crate) expected_payout: U256,
+    /// Cost of proving the order at the configured mcycle price
+    pub(crate) proving_cost: U256,
+    /// Cost of the gas to fulfill the order, converted to stake tokens
+    pub(crate) gas_cost: U256,
+    /// Minimum profit required to fulfill the order
+    pub(crate) min_profit: U256,
+}
+
+impl LockExpiredProfit {
+    /// Whether the expected payout covers the costs and the minimum profit.
+    pub(crate) fn is_profitable(&self) -> bool {
+        self.expected_payout >= self.proving_cost + self.gas_cost + self.min_profit
+    }
+}
+
+/// Calculator of the profitability of lock-expired orders.
+///
+/// Without a [LockExpiredPricingConf], the full stake reward is expected to be paid out and the
+/// gas cost is not accounted for, as there is no price of the native token in stake tokens.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct LockExpiredPricing {
+    /// Price of one native token (e.g. ETH) in stake token units, zero if unknown
+    native_token_price: U256,
+    /// Expected share of lock-expired fulfillments won against other provers, in percent
+    win_percent: u64,
+    min_profit: U256,
+}
+
+impl LockExpiredPricing {
+    pub(crate) fn new(
+        conf: Option<&LockExpiredPricingConf>,
+        stake_token_decimals: u8,
+    ) -> Result<Self> {
+        let Some(conf) = conf else {
+            return Ok(Self { win_percent: 100, ..Default::default() });
+        };
+        Ok(Self {
+            native_token_price: parse_units(&conf.native_token_price, stake_token_decimals)
+                .context("Failed to parse lock_expired_pricing.native_token_price")?
+                .into(),
+            win_percent: conf.win_percent.min(100),
+            min_profit: parse_units(&conf.min_profit, stake_token_decimals)
+                .context("Failed to parse lock_expired_pricing.min_profit")?
+                .into(),
+        })
+    }
+
+    /// Share of the stake reward expected to be paid out.
+    pub(crate) fn expected_payout(&self, stake_reward: U256) -> U256 {
+        stake_reward.saturating_mul(U256::from(self.win_percent)) / U256::from(100)
+    }
+
+    /// Gas cost, in wei, converted to stake tokens, rounded up.
+    pub(crate) fn gas_cost(&self, gas_cost_wei: U256) -> U256 {
+        gas_cost_wei.saturating_mul(self.native_token_price).div_ceil(ONE_ETHER)
+    }
+
+    /// Expected payout and costs of proving `total_cycles` at `mcycle_price` stake tokens per
+    /// million cycles.
+    pub(crate) fn evaluate(
+        &self,
+        stake_reward: U256,
+        gas_cost_wei: U256,
+        total_cycles: u64,
+        mcycle_price: U256,
+    ) -> LockExpiredProfit {
+        LockExpiredProfit {
+            expected_payout: self.expected_payout(stake_reward),
+            proving_cost: U256::from(total_cycles)
+                .saturating_mul(mcycle_price)
+                .div_ceil(ONE_MILLION),
+            gas_cost: self.gas_cost(gas_cost_wei),
+            min_profit: self.min_profit,
+        }
+    }
+
+    /// Maximum number of cycles that can be proven at `mcycle_price` while the order stays
+    /// profitable.
+    pub(crate) fn exec_limit(
+        &self,
+        stake_reward: U256,
+        gas_cost_wei: U256,
+        mcycle_price: U256,
+    ) -> U256 {
+        if mcycle_price == U256::ZERO {
+            return U256::MAX;
+        }
+        self.expected_payout(stake_reward)
+            .saturating_sub(self.gas_cost(gas_cost_wei))
+            .saturating_sub(self.min_profit)
+            .saturating_mul(ONE_MILLION)
+            .div_ceil(mcycle_price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::utils::parse_ether;
+
+    #[test]
+    fn accounts_for_competition_and_gas() {
+        let conf = LockExpiredPricingConf {
+            native_token_price: "2000".into(),
+            win_percent: 50,
+            min_profit: "0.5".into(),
+        };
+        // A stake token with 6 decimals
+        let pricing = LockExpiredPricing::new(Some(&conf), 6).unwrap();
+        let stake_reward = parse_units("10", 6).unwrap().into();
+        let gas_cost_wei = parse_ether("0.001").unwrap();
+        let mcycle_price = parse_units("0.1", 6).unwrap().into();
+
+        let profit = pricing.evaluate(stake_reward, gas_cost_wei, 20_000_000, mcycle_price);
+        assert_eq!(profit.expected_payout, U256::from(5_000_000));
+        assert_eq!(profit.gas_cost, U256::from(2_000_000));
+        assert_eq!(profit.proving_cost, U256::from(2_000_000));
+        assert!(profit.is_profitable());
+
+        // 5 - 2 - 0.5 stake tokens at 0.1 per mcycle
+        let exec_limit = pricing.exec_limit(stake_reward, gas_cost_wei, mcycle_price);
+        assert_eq!(exec_limit, U256::from(25_000_000));
+        assert!(!pricing
+            .evaluate(stake_reward, gas_cost_wei, 25_000_001, mcycle_price)
+            .is_profitable());
+    }
+
+    #[test]
+    fn defaults_to_full_reward_without_gas() {
+        let pricing = LockExpiredPricing::new(None, 18).unwrap();
+        let profit =
+            pricing.evaluate(U256::from(10), parse_ether("1").unwrap(), 10, U256::from(1_000_000));
+        assert_eq!(profit.expected_payout, U256::from(10));
+        assert_eq!(profit.gas_cost, U256::ZERO);
+        assert!(profit.is_profitable());
+        assert_eq!(
+            pricing.exec_limit(U256::from(10), U256::ZERO, U256::from(1_000_000)),
+            U256::from(10)
+        );
+    }
+}
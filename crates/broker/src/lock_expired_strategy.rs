@@ -0,0 +1,199 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use crate::{config::LockExpiredStrategyConf, market_stats::MarketStats, OrderRequest};
+
+/// What to do with an order whose lock by another prover has expired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LockExpiredAction {
+    /// Start proving the order now.
+    Fulfill,
+    /// Keep the order and re-evaluate it on the next block.
+    Wait,
+    /// Drop the order.
+    Skip,
+}
+
+/// Market state available to a [LockExpiredStrategy] when deciding on an order.
+pub(crate) struct LockExpiredContext<'a> {
+    pub(crate) block_timestamp: u64,
+    pub(crate) gas_price: u128,
+    pub(crate) stats: &'a MarketStats,
+}
+
+/// Decides when, within the window after another prover's lock has expired, to fulfill an order.
+///
+/// Only consulted once the order's target timestamp (its lock expiry) has been reached.
+pub(crate) trait LockExpiredStrategy: Send + Sync {
+    fn decide(&self, order: &OrderRequest, ctx: &LockExpiredContext<'_>) -> LockExpiredAction;
+}
+
+pub(crate) type LockExpiredStrategyObj = Arc<dyn LockExpiredStrategy>;
+
+/// Always fulfill as soon as the lock has expired.
+pub(crate) struct Immediate;
+
+impl LockExpiredStrategy for Immediate {
+    fn decide(&self, _order: &OrderRequest, _ctx: &LockExpiredContext<'_>) -> LockExpiredAction {
+        LockExpiredAction::Fulfill
+    }
+}
+
+/// Wait for the gas price to dip below its recent average, for at most `max_wait_secs`.
+pub(crate) struct GasDip {
+    pub(crate) dip_percent: u64,
+    pub(crate) max_wait_secs: u64,
+}
+
+impl LockExpiredStrategy for GasDip {
+    fn decide(&self, order: &OrderRequest, ctx: &LockExpiredContext<'_>) -> LockExpiredAction {
+        let waited = ctx.block_timestamp.saturating_sub(order.target_timestamp.unwrap_or_default());
+        if waited >= self.max_wait_secs {
+            return LockExpiredAction::Fulfill;
+        }
+        let Some(average) = ctx.stats.average_gas_price() else {
+            return LockExpiredAction::Fulfill;
+        };
+        let threshold = average * 100u128.saturating_sub(self.dip_percent as u128) / 100;
+        if ctx.gas_price <= threshold {
+            LockExpiredAction::Fulfill
+        } else {
+            tracing::trace!(
+                "Request 0x{:x} waiting for gas price {} to drop to {} ({}s of {}s waited)",
+                order.request.id,
+                ctx.gas_price,
+                threshold,
+                waited,
+                self.max_wait_secs
+            );
+            LockExpiredAction::Wait
+        }
+    }
+}
+
+/// Only fulfill if no other prover has fulfilled a lock-expired order within `lookback_secs`.
+pub(crate) struct Uncontested {
+    pub(crate) lookback_secs: u64,
+}
+
+impl LockExpiredStrategy for Uncontested {
+    fn decide(&self, order: &OrderRequest, ctx: &LockExpiredContext<'_>) -> LockExpiredAction {
+        let since = ctx.block_timestamp.saturating_sub(self.lookback_secs);
+        let competitors = ctx.stats.competitor_fulfillments_since(since);
+        if competitors == 0 {
+            LockExpiredAction::Fulfill
+        } else {
+            tracing::debug!(
+                "Request 0x{:x}: {} lock-expired orders fulfilled by other provers in the last {}s",
+                order.request.id,
+                competitors,
+                self.lookback_secs
+            );
+            LockExpiredAction::Skip
+        }
+    }
+}
+
+impl From<LockExpiredStrategyConf> for LockExpiredStrategyObj {
+    fn from(conf: LockExpiredStrategyConf) -> Self {
+        match conf {
+            LockExpiredStrategyConf::Immediate => Arc::new(Immediate),
+            LockExpiredStrategyConf::GasDip { dip_percent, max_wait_secs } => {
+                Arc::new(GasDip { dip_percent, max_wait_secs })
+            }
+            LockExpiredStrategyConf::Uncontested { lookback_secs } => {
+                Arc::new(Uncontested { lookback_secs })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FulfillmentType;
+    use alloy::primitives::{Address, Bytes, U256};
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput, RequestInputType,
+        Requirements,
+    };
+    use risc0_zkvm::sha::Digest;
+
+    fn order(target_timestamp: u64) -> OrderRequest {
+        let mut order = OrderRequest::new(
+            ProofRequest::new(
+                RequestId::new(Address::ZERO, 1),
+                Requirements::new(
+                    Digest::ZERO,
+                    Predicate {
+                        predicateType: PredicateType::PrefixMatch,
+                        data: Default::default(),
+                    },
+                ),
+                "http://risczero.com/image",
+                RequestInput { inputType: RequestInputType::Inline, data: Default::default() },
+                Offer {
+                    minPrice: U256::from(1),
+                    maxPrice: U256::from(2),
+                    biddingStart: 0,
+                    timeout: 200,
+                    lockTimeout: 100,
+                    rampUpPeriod: 1,
+                    lockStake: U256::from(0),
+                },
+            ),
+            Bytes::new(),
+            FulfillmentType::FulfillAfterLockExpire,
+            Address::ZERO,
+            1,
+        );
+        order.target_timestamp = Some(target_timestamp);
+        order
+    }
+
+    #[test]
+    fn gas_dip_waits_for_dip_until_max_wait() {
+        let stats = MarketStats::default();
+        stats.record_gas_price(100);
+        let strategy = GasDip { dip_percent: 10, max_wait_secs: 60 };
+        let ctx = |block_timestamp, gas_price| LockExpiredContext {
+            block_timestamp,
+            gas_price,
+            stats: &stats,
+        };
+
+        let order = order(1000);
+        assert_eq!(strategy.decide(&order, &ctx(1010, 95)), LockExpiredAction::Wait);
+        assert_eq!(strategy.decide(&order, &ctx(1010, 90)), LockExpiredAction::Fulfill);
+        assert_eq!(strategy.decide(&order, &ctx(1060, 95)), LockExpiredAction::Fulfill);
+    }
+
+    #[test]
+    fn uncontested_skips_when_competitors_active() {
+        let stats = MarketStats::default();
+        let strategy = Uncontested { lookback_secs: 100 };
+        let ctx = LockExpiredContext { block_timestamp: 1000, gas_price: 1, stats: &stats };
+
+        let order = order(1000);
+        assert_eq!(strategy.decide(&order, &ctx), LockExpiredAction::Fulfill);
+
+        stats.record_competitor_fulfillment(850);
+        assert_eq!(strategy.decide(&order, &ctx), LockExpiredAction::Fulfill);
+
+        stats.record_competitor_fulfillment(950);
+        assert_eq!(strategy.decide(&order, &ctx), LockExpiredAction::Skip);
+    }
+}
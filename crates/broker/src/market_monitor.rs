@@ -18,7 +18,7 @@ use alloy::{
     network::Ethereum,
     primitives::{Address, U256},
     providers::Provider,
-    rpc::types::Filter,
+    rpc::types::{Filter, Log},
     sol,
     sol_types::SolEvent,
 };
@@ -28,23 +28,34 @@ use boundless_market::{
     contracts::{
         boundless_market::BoundlessMarketService, IBoundlessMarket, RequestId, RequestStatus,
     },
-    order_stream_client::OrderStreamClient,
+    order_stream_client::{OrderStreamClient, OrderStreamErr},
 };
 use futures_util::StreamExt;
 use tokio::sync::{broadcast, mpsc};
 use tokio_util::sync::CancellationToken;
 
 use crate::{
-    chain_monitor::ChainMonitorService,
-    db::{DbError, DbObj},
+    chain_monitor::{ChainMonitorService, ChainReorg},
+    db::DbObj,
     errors::{impl_coded_debug, CodedError},
+    events::EventBus,
+    indexer::{
+        decode_log, send_reverted_state_change, MarketEvent, MarketEventRecord, MarketIndexer,
+    },
+    leadership::LeadershipObj,
+    market_stats::MarketStatsObj,
+    now_timestamp,
+    order_picker::OrderCache,
     task::{RetryRes, RetryTask, SupervisorErr},
-    FulfillmentType, OrderRequest, OrderStateChange,
+    FulfillmentType, OrderProvenance, OrderRequest, OrderSource,
 };
 use thiserror::Error;
 
 const BLOCK_TIME_SAMPLE_SIZE: u64 = 10;
 
+/// Maximum number of blocks queried for logs at once when replaying market events.
+const REPLAY_BLOCK_RANGE: u64 = 10_000;
+
 #[derive(Error)]
 pub enum MarketMonitorErr {
     #[error("{code} Event polling failed: {0:?}", code = self.code())]
@@ -82,7 +93,11 @@ pub struct MarketMonitor<P> {
     prover_addr: Address,
     order_stream: Option<OrderStreamClient>,
     new_order_tx: mpsc::Sender<Box<OrderRequest>>,
-    order_state_tx: broadcast::Sender<OrderStateChange>,
+    events: EventBus,
+    market_stats: MarketStatsObj,
+    indexer: Arc<MarketIndexer>,
+    order_cache: Option<OrderCache>,
+    leadership: LeadershipObj,
 }
 
 sol! {
@@ -108,8 +123,9 @@ where
         prover_addr: Address,
         order_stream: Option<OrderStreamClient>,
         new_order_tx: mpsc::Sender<Box<OrderRequest>>,
-        order_state_tx: broadcast::Sender<OrderStateChange>,
+        events: EventBus,
     ) -> Self {
+        let indexer = Arc::new(MarketIndexer::new(db.clone()));
         Self {
             lookback_blocks,
             market_addr,
@@ -119,10 +135,31 @@ where
             prover_addr,
             order_stream,
             new_order_tx,
-            order_state_tx,
+            events,
+            market_stats: Default::default(),
+            indexer,
+            order_cache: None,
+            leadership: Default::default(),
         }
     }
 
+    /// Record market activity into the given statistics, shared with other services.
+    pub(crate) fn with_market_stats(self, market_stats: MarketStatsObj) -> Self {
+        Self { market_stats, ..self }
+    }
+
+    /// Cache of orders recently seen by the order picker, invalidated for orders queued again
+    /// after the lock of another prover was reorged out.
+    pub(crate) fn with_order_cache(self, order_cache: OrderCache) -> Self {
+        Self { order_cache: Some(order_cache), ..self }
+    }
+
+    /// Recover the locked orders on acquiring the leadership lease rather than on startup, so that
+    /// a standby replica resumes the obligations of the previous leader on failover.
+    pub(crate) fn with_leadership(self, leadership: LeadershipObj) -> Self {
+        Self { leadership, ..self }
+    }
+
     /// Queries chain history to sample for the median block time
     pub async fn get_block_time(&self) -> Result<u64> {
         let current_block = self.chain_monitor.current_block_number().await?;
@@ -152,6 +189,7 @@ where
         market_addr: Address,
         provider: Arc<P>,
         chain_monitor: Arc<ChainMonitorService<P>>,
+        indexer: &MarketIndexer,
         new_order_tx: &mpsc::Sender<Box<OrderRequest>>,
     ) -> Result<u64, MarketMonitorErr> {
         let current_block = chain_monitor.current_block_number().await?;
@@ -178,30 +216,34 @@ where
         // don't have a lot of clean log decoding samples, and the Event::query()
         // interface would randomly fail for me?
         let logs = provider.get_logs(&filter).await.context("Failed to get logs")?;
-        let decoded_logs = logs.iter().filter_map(|log| {
-            match log.log_decode::<IBoundlessMarket::RequestSubmitted>() {
-                Ok(res) => Some(res),
+
+        tracing::debug!("Found {} possible in the past {} blocks", logs.len(), lookback_blocks);
+        let mut order_count = 0;
+        for log in &logs {
+            let (request_id, event) = match decode_log(log) {
+                Ok(Some(decoded)) => decoded,
+                Ok(None) => continue,
                 Err(err) => {
                     tracing::error!("Failed to decode RequestSubmitted log: {err:?}");
-                    None
+                    continue;
+                }
+            };
+            let MarketEvent::Submitted { request, client_signature } = &event else {
+                continue;
+            };
+            if let Some(block_number) = log.block_number {
+                if let Err(err) = indexer.index(request_id, block_number, &event).await {
+                    tracing::warn!("Failed to index request 0x{request_id:x}: {err:?}");
                 }
             }
-        });
 
-        tracing::debug!("Found {} possible in the past {} blocks", logs.len(), lookback_blocks);
-        let mut order_count = 0;
-        for log in decoded_logs {
-            let event = &log.inner.data;
-            let request_id = U256::from(event.requestId);
-
-            let req_status =
-                match market.get_status(request_id, Some(event.request.expires_at())).await {
-                    Ok(val) => val,
-                    Err(err) => {
-                        tracing::warn!("Failed to get request status: {err:?}");
-                        continue;
-                    }
-                };
+            let req_status = match market.get_status(request_id, Some(request.expires_at())).await {
+                Ok(val) => val,
+                Err(err) => {
+                    tracing::warn!("Failed to get request status: {err:?}");
+                    continue;
+                }
+            };
 
             if !matches!(req_status, RequestStatus::Unknown) {
                 tracing::debug!(
@@ -220,12 +262,16 @@ where
             );
 
             let new_order = OrderRequest::new(
-                event.request.clone(),
-                event.clientSignature.clone(),
+                request.as_ref().clone(),
+                client_signature.clone(),
                 fulfillment_type,
                 market_addr,
                 chain_id,
-            );
+            )
+            .with_provenance(OrderProvenance::new(OrderSource::ChainEvent {
+                block_number: log.block_number,
+                tx_hash: log.transaction_hash,
+            }));
 
             new_order_tx
                 .send(Box::new(new_order))
@@ -239,9 +285,336 @@ where
         Ok(order_count)
     }
 
+    /// Reconcile requests locked by the prover in the last `lookback_blocks` against the DB.
+    ///
+    /// If the broker stopped after its lock transaction confirmed but before the order was
+    /// recorded, the proving obligation is restored so the lock stake is not silently slashed.
+    /// Locks that already expired can no longer be fulfilled in time, and are alerted on instead.
+    ///
+    /// Returns the number of orders restored.
+    async fn recover_own_locks(
+        lookback_blocks: u64,
+        market_addr: Address,
+        prover_addr: Address,
+        provider: Arc<P>,
+        db: &DbObj,
+        chain_monitor: Arc<ChainMonitorService<P>>,
+    ) -> Result<u64, MarketMonitorErr> {
+        let chain_head = chain_monitor.current_chain_head().await?;
+        let chain_id = provider.get_chain_id().await.context("Failed to get chain id")?;
+        let start_block = chain_head.block_number.saturating_sub(lookback_blocks);
+
+        let filter = Filter::new()
+            .event_signature(IBoundlessMarket::RequestLocked::SIGNATURE_HASH)
+            .from_block(start_block)
+            .address(market_addr);
+        let logs = provider.get_logs(&filter).await.context("Failed to get logs")?;
+
+        let market = BoundlessMarketService::new(market_addr, provider.clone(), Address::ZERO);
+        let mut recovered = 0;
+        for log in &logs {
+            let (request_id, request, client_signature) = match decode_log(log) {
+                Ok(Some((
+                    request_id,
+                    MarketEvent::Locked { prover, request, client_signature },
+                ))) if prover == prover_addr => (request_id, request, client_signature),
+                Ok(_) => continue,
+                Err(err) => {
+                    tracing::error!("Failed to decode RequestLocked log: {err:?}");
+                    continue;
+                }
+            };
+
+            let mut order = OrderRequest::new(
+                *request,
+                client_signature,
+                FulfillmentType::LockAndFulfill,
+                market_addr,
+                chain_id,
+            )
+            .with_provenance(OrderProvenance::new(OrderSource::ChainEvent {
+                block_number: log.block_number,
+                tx_hash: log.transaction_hash,
+            }));
+            let order_id = order.id();
+            if db.get_order(&order_id).await.context("Failed to get order")?.is_some() {
+                continue;
+            }
+
+            let status = market
+                .get_status(request_id, Some(order.request.expires_at()))
+                .await
+                .context("Failed to get request status")?;
+            if status != RequestStatus::Locked {
+                tracing::debug!(
+                    "Unrecorded lock of request 0x{request_id:x} no longer needs proving: {status:?}"
+                );
+                continue;
+            }
+
+            let lock_expires_at = order.request.lock_expires_at();
+            if lock_expires_at <= chain_head.block_timestamp {
+                tracing::error!(
+                    "FATAL STAKE AT RISK: request 0x{request_id:x} locked by this prover was never recorded and its lock expired at {lock_expires_at}, lock stake will be slashed"
+                );
+                continue;
+            }
+
+            let lock_timestamp = match log.block_timestamp {
+                Some(timestamp) => timestamp,
+                None => {
+                    let block_number = log.block_number.context("Lock log missing block number")?;
+                    provider
+                        .get_block_by_number(block_number.into())
+                        .await
+                        .with_context(|| format!("Failed to get block {block_number}"))?
+                        .with_context(|| format!("Missing block {block_number}"))?
+                        .header
+                        .timestamp
+                }
+            };
+            let lock_price =
+                order.request.offer.price_at(lock_timestamp).context("Failed to get lock price")?;
+
+            order.target_timestamp = Some(lock_timestamp);
+            order.expire_timestamp = Some(lock_expires_at);
+            db.insert_accepted_request(&order, lock_price)
+                .await
+                .context("Failed to restore locked order")?;
+            tracing::warn!(
+                "Restored order {order_id} locked by this prover but never recorded, proving before lock expiry at {lock_expires_at}"
+            );
+            recovered += 1;
+        }
+
+        Ok(recovered)
+    }
+
+    /// Fetch the lock, fulfillment and slashing events emitted since the checkpoint of the
+    /// indexer into the event log, and replay their state changes.
+    ///
+    /// Services that keep running while the monitor is restarted, e.g. after its event
+    /// subscriptions failed, do not miss the events emitted in the meantime, and the lock and
+    /// fulfillment tables are complete after downtime. Without a checkpoint, e.g. on the first
+    /// start, events are fetched from the last `lookback_blocks`.
+    ///
+    /// Returns the number of state changes replayed.
+    async fn replay_events(
+        lookback_blocks: u64,
+        market_addr: Address,
+        provider: Arc<P>,
+        chain_monitor: Arc<ChainMonitorService<P>>,
+        indexer: &MarketIndexer,
+        events: &EventBus,
+    ) -> Result<usize, MarketMonitorErr> {
+        let current_block = chain_monitor.current_block_number().await?;
+        let chain_id = provider.get_chain_id().await.context("Failed to get chain id")?;
+        let from_block =
+            match indexer.checkpoint(chain_id).await.context("Failed to get indexer checkpoint")? {
+                Some(checkpoint) => checkpoint + 1,
+                None => current_block.saturating_sub(lookback_blocks),
+            };
+        if from_block > current_block {
+            return Ok(0);
+        }
+
+        tracing::info!("Replaying market events: {from_block} - {current_block}");
+        let records =
+            Self::fetch_events(market_addr, &provider, chain_id, from_block, current_block).await?;
+        for record in &records {
+            indexer.record(record).await.context("Failed to record market event")?;
+        }
+
+        let replayed = indexer
+            .replay(chain_id, from_block, current_block, events)
+            .await
+            .context("Failed to replay market events")?;
+        tracing::info!("Replayed {replayed} market events");
+        Ok(replayed)
+    }
+
+    /// Fetch the lock, fulfillment and slashing events emitted between the given blocks.
+    async fn fetch_events(
+        market_addr: Address,
+        provider: &P,
+        chain_id: u64,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<MarketEventRecord>, MarketMonitorErr> {
+        let signatures = vec![
+            IBoundlessMarket::RequestLocked::SIGNATURE_HASH,
+            IBoundlessMarket::RequestFulfilled::SIGNATURE_HASH,
+            IBoundlessMarket::ProverSlashed::SIGNATURE_HASH,
+        ];
+        let mut records = Vec::new();
+        let mut start_block = from_block;
+        while start_block <= to_block {
+            let end_block = to_block.min(start_block + REPLAY_BLOCK_RANGE - 1);
+            let filter = Filter::new()
+                .event_signature(signatures.clone())
+                .from_block(start_block)
+                .to_block(end_block)
+                .address(market_addr);
+            let logs = provider.get_logs(&filter).await.context("Failed to get logs")?;
+            for log in &logs {
+                match decode_log(log) {
+                    Ok(Some((request_id, event))) => {
+                        records.extend(MarketEventRecord::new(chain_id, request_id, event, log))
+                    }
+                    Ok(None) => {}
+                    Err(err) => tracing::error!("Failed to decode market event log: {err:?}"),
+                }
+            }
+            start_block = end_block + 1;
+        }
+        Ok(records)
+    }
+
+    /// Revert the events of the blocks replaced by a reorg, and record the events of the new
+    /// chain instead.
+    ///
+    /// Locks and fulfillments that are not on the new chain are broadcast as reverted, so that
+    /// the broker does not keep believing a request is locked or fulfilled. Requests locked by
+    /// other provers are queued again for the order picker to reconsider.
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_reorg(
+        reorg: ChainReorg,
+        market_addr: Address,
+        prover_addr: Address,
+        provider: &P,
+        chain_monitor: &ChainMonitorService<P>,
+        indexer: &MarketIndexer,
+        new_order_tx: &mpsc::Sender<Box<OrderRequest>>,
+        order_cache: Option<&OrderCache>,
+        events: &EventBus,
+    ) -> Result<(), MarketMonitorErr> {
+        let chain_id = provider.get_chain_id().await.context("Failed to get chain id")?;
+        let current_block = chain_monitor.current_block_number().await?;
+        let fork_block = reorg.fork_block;
+        let reverted =
+            indexer.revert(chain_id, fork_block).await.context("Failed to revert market events")?;
+        let records =
+            Self::fetch_events(market_addr, provider, chain_id, fork_block, current_block).await?;
+        for record in &records {
+            indexer.record(record).await.context("Failed to record market event")?;
+        }
+
+        let reorged_out = reverted.iter().filter(|reverted| {
+            !records.iter().any(|record| {
+                record.request_id == reverted.request_id && record.event == reverted.event
+            })
+        });
+        for record in reorged_out {
+            if !send_reverted_state_change(record, events) {
+                continue;
+            }
+            tracing::warn!(
+                "Event of request 0x{:x} in block {} was reorged out",
+                record.request_id,
+                record.block_number
+            );
+            let MarketEvent::Locked { prover, request, client_signature } = &record.event else {
+                continue;
+            };
+            if *prover == prover_addr {
+                tracing::error!(
+                    "Lock of request 0x{:x} by the broker was reorged out, it can not be fulfilled unless the lock is included again",
+                    record.request_id
+                );
+                continue;
+            }
+
+            // The market validated the signature when the request was locked
+            let order = OrderRequest::new(
+                (**request).clone(),
+                client_signature.clone(),
+                FulfillmentType::LockAndFulfill,
+                market_addr,
+                chain_id,
+            )
+            .with_provenance(
+                OrderProvenance::new(OrderSource::ReorgedLock {
+                    block_number: record.block_number,
+                    tx_hash: record.tx_hash,
+                })
+                .with_signature_validated(),
+            );
+            // The order picker ignores orders it has recently seen
+            if let Some(order_cache) = order_cache {
+                order_cache.invalidate(&order.order_id()).await;
+            }
+            if new_order_tx.send(Box::new(order)).await.is_err() {
+                return Err(MarketMonitorErr::ReceiverDropped);
+            }
+        }
+
+        indexer
+            .replay(chain_id, fork_block, current_block, events)
+            .await
+            .context("Failed to replay market events")?;
+        Ok(())
+    }
+
+    /// Monitors the reorgs detected by the chain monitor and reverts the events of the replaced
+    /// blocks.
+    #[allow(clippy::too_many_arguments)]
+    async fn monitor_reorgs(
+        market_addr: Address,
+        prover_addr: Address,
+        provider: Arc<P>,
+        chain_monitor: Arc<ChainMonitorService<P>>,
+        indexer: Arc<MarketIndexer>,
+        new_order_tx: mpsc::Sender<Box<OrderRequest>>,
+        order_cache: Option<OrderCache>,
+        events: EventBus,
+        mut reorgs: broadcast::Receiver<ChainReorg>,
+        cancel_token: CancellationToken,
+    ) -> Result<(), MarketMonitorErr> {
+        loop {
+            let reorg = tokio::select! {
+                reorg = reorgs.recv() => reorg,
+                _ = cancel_token.cancelled() => return Ok(()),
+            };
+            let reorg = match reorg {
+                Ok(reorg) => reorg,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Missed {missed} chain reorgs, their events are not reverted");
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    return Err(MarketMonitorErr::ReceiverDropped);
+                }
+            };
+            tracing::info!("Reverting market events from block {}", reorg.fork_block);
+            let res = Self::handle_reorg(
+                reorg,
+                market_addr,
+                prover_addr,
+                &provider,
+                &chain_monitor,
+                &indexer,
+                &new_order_tx,
+                order_cache.as_ref(),
+                &events,
+            )
+            .await;
+            match res {
+                Ok(()) => {}
+                Err(MarketMonitorErr::ReceiverDropped) => {
+                    return Err(MarketMonitorErr::ReceiverDropped)
+                }
+                Err(err) => tracing::error!(
+                    "Failed to revert market events from block {}: {err:?}",
+                    reorg.fork_block
+                ),
+            }
+        }
+    }
+
     async fn monitor_orders(
         market_addr: Address,
         provider: Arc<P>,
+        indexer: Arc<MarketIndexer>,
         new_order_tx: mpsc::Sender<Box<OrderRequest>>,
         cancel_token: CancellationToken,
     ) -> Result<(), MarketMonitorErr> {
@@ -265,9 +638,21 @@ where
             tokio::select! {
                 log_res = stream.next() => {
                     match log_res {
-                        Some(Ok((event, _))) => {
+                        Some(Ok((event, log))) => {
+                            let request_id = U256::from(event.requestId);
+                            if let Err(err) = indexer
+                                .index(request_id, log.block_number.unwrap(), &MarketEvent::from(&event))
+                                .await
+                            {
+                                tracing::error!("Failed to index submission of request {request_id:x}: {err:?}");
+                            }
+                            let provenance = OrderProvenance::new(OrderSource::ChainEvent {
+                                block_number: log.block_number,
+                                tx_hash: log.transaction_hash,
+                            });
                             if let Err(err) = Self::process_event(
                                 event,
+                                provenance,
                                 provider.clone(),
                                 market_addr,
                                 chain_id,
@@ -303,10 +688,10 @@ where
         market_addr: Address,
         prover_addr: Address,
         provider: Arc<P>,
-        db: DbObj,
+        indexer: Arc<MarketIndexer>,
         new_order_tx: mpsc::Sender<Box<OrderRequest>>,
         order_stream: Option<OrderStreamClient>,
-        order_state_tx: broadcast::Sender<OrderStateChange>,
+        events: EventBus,
         cancel_token: CancellationToken,
     ) -> Result<(), MarketMonitorErr> {
         let market = BoundlessMarketService::new(market_addr, provider.clone(), Address::ZERO);
@@ -330,32 +715,17 @@ where
                                 event.requestId,
                                 event.prover,
                             );
-                            if let Err(e) = db
-                                .set_request_locked(
-                                    U256::from(event.requestId),
-                                    &event.prover.to_string(),
-                                    log.block_number.unwrap(),
-                                )
-                                .await
-                            {
-                                match e {
-                                    DbError::SqlUniqueViolation(_) => {
-                                        tracing::warn!("Duplicate request locked detected {:x}: {e:?}", event.requestId);
-                                    }
-                                    _ => {
-                                        tracing::error!("Failed to store request locked for request {:x} in db: {e:?}", event.requestId);
-                                    }
-                                }
-                            }
-
-                            // Send order state change message for any active preflight of this order
-                            let state_change = OrderStateChange::Locked {
-                                request_id: U256::from(event.requestId),
-                                prover: event.prover,
-                            };
-                            if let Err(e) = order_state_tx.send(state_change) {
-                                tracing::warn!("Failed to send order state change message for request {:x}: {e:?}", event.requestId);
-                            }
+                            // Record the lock and send the order state change message for any
+                            // active preflight of this order
+                            Self::record_event(
+                                &indexer,
+                                &events,
+                                chain_id,
+                                U256::from(event.requestId),
+                                MarketEvent::from(&event),
+                                &log,
+                            )
+                            .await;
 
                             // If the request was not locked by the prover, we create an order to evaluate the request
                             // for fulfilling after the lock expires.
@@ -363,6 +733,13 @@ where
                                 // Try to get from market first. If the request was submitted via the order stream, we will be unable to find it there.
                                 // In that case we check the order stream.
                                 let mut order: Option<OrderRequest> = None;
+                                let source = |order_stream_url: Option<String>| {
+                                    OrderProvenance::new(OrderSource::LockedByOther {
+                                        block_number: log.block_number,
+                                        tx_hash: log.transaction_hash,
+                                        order_stream_url,
+                                    })
+                                };
                                 if let Ok((proof_request, signature)) = market.get_submitted_request(event.requestId, None).await {
                                     order = Some(OrderRequest::new(
                                         proof_request,
@@ -370,18 +747,24 @@ where
                                         FulfillmentType::FulfillAfterLockExpire,
                                         market_addr,
                                         chain_id,
-                                    ));
+                                    ).with_provenance(source(None)));
                                 } else if let Some(order_stream) = &order_stream {
-                                    if let Ok(order_stream_order) = order_stream.fetch_order(event.requestId, None).await {
-                                        let proof_request = order_stream_order.request;
-                                        let signature = order_stream_order.signature;
-                                        order = Some(OrderRequest::new(
-                                            proof_request,
-                                            signature.as_bytes().into(),
-                                            FulfillmentType::FulfillAfterLockExpire,
-                                            market_addr,
-                                            chain_id,
-                                        ));
+                                    match order_stream.fetch_order(event.requestId, None).await {
+                                        Ok(order_stream_order) => {
+                                            let proof_request = order_stream_order.request;
+                                            let signature = order_stream_order.client_signature();
+                                            order = Some(OrderRequest::new(
+                                                proof_request,
+                                                signature,
+                                                FulfillmentType::FulfillAfterLockExpire,
+                                                market_addr,
+                                                chain_id,
+                                            ).with_provenance(source(Some(order_stream.base_url.to_string()))));
+                                        }
+                                        Err(OrderStreamErr::NotFound) => {}
+                                        Err(err) => {
+                                            tracing::warn!("Failed to fetch request 0x{:x} from order stream: {} {err}", event.requestId, err.code());
+                                        }
                                     }
                                 }
 
@@ -412,15 +795,48 @@ where
         }
     }
 
+    /// Records the fulfillment in the market stats if it was made by another prover after the
+    /// lock of a third prover expired.
+    async fn record_competitor_fulfillment(
+        db: &DbObj,
+        market_stats: &MarketStatsObj,
+        prover_addr: Address,
+        request_id: U256,
+        fulfiller: Address,
+    ) {
+        if fulfiller == prover_addr {
+            return;
+        }
+        match db.get_request_locked(request_id).await {
+            Ok(Some((locker, _))) => {
+                if locker.parse::<Address>().is_ok_and(|locker| locker != fulfiller) {
+                    tracing::debug!(
+                        "Request 0x{request_id:x} locked by {locker} was fulfilled by {fulfiller} after lock expiry"
+                    );
+                    market_stats.record_competitor_fulfillment(now_timestamp());
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!("Failed to get locker of fulfilled request 0x{request_id:x}: {e:?}")
+            }
+        }
+    }
+
     /// Monitors the RequestFulfilled events and updates the database accordingly.
+    #[allow(clippy::too_many_arguments)]
     async fn monitor_order_fulfillments(
         market_addr: Address,
+        prover_addr: Address,
         provider: Arc<P>,
         db: DbObj,
-        order_state_tx: broadcast::Sender<OrderStateChange>,
+        indexer: Arc<MarketIndexer>,
+        market_stats: MarketStatsObj,
+        events: EventBus,
         cancel_token: CancellationToken,
     ) -> Result<(), MarketMonitorErr> {
         let market = BoundlessMarketService::new(market_addr, provider.clone(), Address::ZERO);
+        let chain_id = provider.get_chain_id().await.context("Failed to get chain id")?;
         let event = market
             .instance()
             .RequestFulfilled_filter()
@@ -436,33 +852,23 @@ where
                     match log_res {
                         Some(Ok((event, log))) => {
                             tracing::debug!("Detected request fulfilled 0x{:x}", event.requestId);
-                            if let Err(e) = db
-                                .set_request_fulfilled(
-                                    U256::from(event.requestId),
-                                    log.block_number.unwrap(),
-                                )
-                                .await
-                            {
-                                match e {
-                                    DbError::SqlUniqueViolation(_) => {
-                                        tracing::warn!("Duplicate fulfillment event detected: {e:?}");
-                                    }
-                                    _ => {
-                                        tracing::error!(
-                                            "Failed to store fulfillment for request id {:x}: {e:?}",
-                                            event.requestId
-                                        );
-                                    }
-                                }
-                            }
-
-                            // Send order state change message
-                            let state_change = OrderStateChange::Fulfilled {
-                                request_id: U256::from(event.requestId),
-                            };
-                            if let Err(e) = order_state_tx.send(state_change) {
-                                tracing::warn!("Failed to send order state change message for fulfilled request {:x}: {e:?}", event.requestId);
-                            }
+                            Self::record_competitor_fulfillment(
+                                &db,
+                                &market_stats,
+                                prover_addr,
+                                U256::from(event.requestId),
+                                event.prover,
+                            )
+                            .await;
+                            Self::record_event(
+                                &indexer,
+                                &events,
+                                chain_id,
+                                U256::from(event.requestId),
+                                MarketEvent::from(&event),
+                                &log,
+                            )
+                            .await;
                         }
                         Some(Err(err)) => {
                             let event_err = MarketMonitorErr::EventPollingErr(anyhow::anyhow!(err));
@@ -482,8 +888,83 @@ where
         }
     }
 
+    /// Monitors the ProverSlashed events and records them in the event log.
+    async fn monitor_slashes(
+        market_addr: Address,
+        provider: Arc<P>,
+        indexer: Arc<MarketIndexer>,
+        events: EventBus,
+        cancel_token: CancellationToken,
+    ) -> Result<(), MarketMonitorErr> {
+        let market = BoundlessMarketService::new(market_addr, provider.clone(), Address::ZERO);
+        let chain_id = provider.get_chain_id().await.context("Failed to get chain id")?;
+        let event = market
+            .instance()
+            .ProverSlashed_filter()
+            .watch()
+            .await
+            .context("Failed to subscribe to ProverSlashed event")?;
+        tracing::info!("Subscribed to ProverSlashed event");
+
+        let mut stream = event.into_stream();
+        loop {
+            tokio::select! {
+                log_res = stream.next() => {
+                    match log_res {
+                        Some(Ok((event, log))) => {
+                            tracing::debug!("Detected prover slashed for request 0x{:x}", event.requestId);
+                            Self::record_event(
+                                &indexer,
+                                &events,
+                                chain_id,
+                                U256::from(event.requestId),
+                                MarketEvent::from(&event),
+                                &log,
+                            )
+                            .await;
+                        }
+                        Some(Err(err)) => {
+                            let event_err = MarketMonitorErr::EventPollingErr(anyhow::anyhow!(err));
+                            tracing::warn!("Failed to fetch ProverSlashed event log: {event_err:?}");
+                        }
+                        None => {
+                            return Err(MarketMonitorErr::EventPollingErr(anyhow::anyhow!(
+                                "Event polling prover slashes exited, polling failed (possible RPC error)",
+                            )));
+                        }
+                    }
+                }
+                _ = cancel_token.cancelled() => {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Record an event observed live in the event log and broadcast its state change.
+    async fn record_event(
+        indexer: &MarketIndexer,
+        events: &EventBus,
+        chain_id: u64,
+        request_id: U256,
+        event: MarketEvent,
+        log: &Log,
+    ) {
+        let Some(record) = MarketEventRecord::new(chain_id, request_id, event, log) else {
+            tracing::warn!("Ignoring event of request 0x{request_id:x} in a pending block");
+            return;
+        };
+        if let Err(err) = indexer.record(&record).await {
+            tracing::error!("Failed to record event of request 0x{request_id:x}: {err:?}");
+        }
+        if let Err(err) = indexer.broadcast(&record, events).await {
+            tracing::error!("Failed to broadcast event of request 0x{request_id:x}: {err:?}");
+        }
+    }
+
     async fn process_event(
         event: IBoundlessMarket::RequestSubmitted,
+        provenance: OrderProvenance,
         provider: Arc<P>,
         market_addr: Address,
         chain_id: u64,
@@ -529,7 +1010,8 @@ where
             FulfillmentType::LockAndFulfill,
             market_addr,
             chain_id,
-        );
+        )
+        .with_provenance(provenance.with_signature_validated());
 
         let order_id = new_order.id();
         if let Err(e) = new_order_tx.send(Box::new(new_order)).await {
@@ -539,6 +1021,54 @@ where
         }
         Ok(())
     }
+
+    /// Recover the locked orders each time this replica acquires the leadership lease, unless
+    /// already `recovered` for the current leadership.
+    #[allow(clippy::too_many_arguments)]
+    async fn resume_on_leadership(
+        lookback_blocks: u64,
+        market_addr: Address,
+        prover_addr: Address,
+        provider: Arc<P>,
+        db: DbObj,
+        chain_monitor: Arc<ChainMonitorService<P>>,
+        leadership: LeadershipObj,
+        mut recovered: bool,
+        cancel_token: CancellationToken,
+    ) -> Result<(), MarketMonitorErr> {
+        let mut leader = leadership.subscribe();
+        loop {
+            if !recovered {
+                tokio::select! {
+                    res = leader.wait_for(|is_leader| *is_leader) => {
+                        if res.is_err() {
+                            return Ok(());
+                        }
+                    }
+                    _ = cancel_token.cancelled() => return Ok(()),
+                }
+                tracing::info!("Acquired leadership, resuming the orders locked by the prover");
+                Self::recover_own_locks(
+                    lookback_blocks,
+                    market_addr,
+                    prover_addr,
+                    provider.clone(),
+                    &db,
+                    chain_monitor.clone(),
+                )
+                .await?;
+            }
+            tokio::select! {
+                res = leader.wait_for(|is_leader| !*is_leader) => {
+                    if res.is_err() {
+                        return Ok(());
+                    }
+                }
+                _ = cancel_token.cancelled() => return Ok(()),
+            }
+            recovered = false;
+        }
+    }
 }
 
 impl<P> RetryTask for MarketMonitor<P>
@@ -555,16 +1085,55 @@ where
         let new_order_tx = self.new_order_tx.clone();
         let db = self.db.clone();
         let order_stream = self.order_stream.clone();
-        let order_state_tx = self.order_state_tx.clone();
+        let events = self.events.clone();
+        let market_stats = self.market_stats.clone();
+        let indexer = self.indexer.clone();
+        let order_cache = self.order_cache.clone();
+        let leadership = self.leadership.clone();
 
         Box::pin(async move {
             tracing::info!("Starting up market monitor");
+            // Subscribe before replaying, so that no reorg is missed in the meantime
+            let reorgs = chain_monitor.subscribe_reorgs();
+
+            // Standby replicas recover locked orders once they acquire the leadership lease
+            let is_leader = leadership.is_leader();
+            if is_leader {
+                Self::recover_own_locks(
+                    lookback_blocks,
+                    market_addr,
+                    prover_addr,
+                    provider.clone(),
+                    &db,
+                    chain_monitor.clone(),
+                )
+                .await
+                .map_err(|err| {
+                    tracing::error!("Monitor failed to recover locked orders on startup.");
+                    SupervisorErr::Recover(err)
+                })?;
+            }
+
+            Self::replay_events(
+                lookback_blocks,
+                market_addr,
+                provider.clone(),
+                chain_monitor.clone(),
+                &indexer,
+                &events,
+            )
+            .await
+            .map_err(|err| {
+                tracing::error!("Monitor failed to replay market events on startup.");
+                SupervisorErr::Recover(err)
+            })?;
 
             Self::find_open_orders(
                 lookback_blocks,
                 market_addr,
                 provider.clone(),
-                chain_monitor,
+                chain_monitor.clone(),
+                &indexer,
                 &new_order_tx,
             )
             .await
@@ -574,27 +1143,61 @@ where
             })?;
 
             tokio::try_join!(
+                Self::resume_on_leadership(
+                    lookback_blocks,
+                    market_addr,
+                    prover_addr,
+                    provider.clone(),
+                    db.clone(),
+                    chain_monitor.clone(),
+                    leadership,
+                    is_leader,
+                    cancel_token.clone()
+                ),
                 Self::monitor_orders(
                     market_addr,
                     provider.clone(),
+                    indexer.clone(),
                     new_order_tx.clone(),
                     cancel_token.clone()
                 ),
                 Self::monitor_order_fulfillments(
                     market_addr,
+                    prover_addr,
                     provider.clone(),
-                    db.clone(),
-                    order_state_tx.clone(),
+                    db,
+                    indexer.clone(),
+                    market_stats,
+                    events.clone(),
+                    cancel_token.clone()
+                ),
+                Self::monitor_slashes(
+                    market_addr,
+                    provider.clone(),
+                    indexer.clone(),
+                    events.clone(),
+                    cancel_token.clone()
+                ),
+                Self::monitor_reorgs(
+                    market_addr,
+                    prover_addr,
+                    provider.clone(),
+                    chain_monitor,
+                    indexer.clone(),
+                    new_order_tx.clone(),
+                    order_cache,
+                    events.clone(),
+                    reorgs,
                     cancel_token.clone()
                 ),
                 Self::monitor_order_locks(
                     market_addr,
                     prover_addr,
                     provider.clone(),
-                    db,
+                    indexer,
                     new_order_tx,
                     order_stream,
-                    order_state_tx,
+                    events,
                     cancel_token
                 )
             )
@@ -692,15 +1295,27 @@ mod tests {
         let chain_monitor = Arc::new(ChainMonitorService::new(provider.clone()).await.unwrap());
         tokio::spawn(chain_monitor.spawn(Default::default()));
 
+        let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
+        let indexer = MarketIndexer::new(db.clone());
         let (order_tx, mut order_rx) = mpsc::channel(16);
-        let orders =
-            MarketMonitor::find_open_orders(2, market_address, provider, chain_monitor, &order_tx)
-                .await
-                .unwrap();
+        let orders = MarketMonitor::find_open_orders(
+            2,
+            market_address,
+            provider,
+            chain_monitor,
+            &indexer,
+            &order_tx,
+        )
+        .await
+        .unwrap();
         assert_eq!(orders, 1);
 
         order_rx.try_recv().unwrap();
         assert!(order_rx.try_recv().is_err());
+
+        let request = db.get_market_request(proving_request.id).await.unwrap().unwrap();
+        assert_eq!(request.status, crate::indexer::MarketRequestStatus::Submitted);
+        assert!(request.submitted_block.is_some());
     }
 
     #[tokio::test]
@@ -721,7 +1336,7 @@ mod tests {
         tokio::spawn(chain_monitor.spawn(Default::default()));
         let (order_tx, _order_rx) = mpsc::channel(16);
         let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
-        let (order_state_tx, _) = broadcast::channel(16);
+        let events = EventBus::new(16);
         let market_monitor = MarketMonitor::new(
             1,
             Address::ZERO,
@@ -731,7 +1346,7 @@ mod tests {
             Address::ZERO,
             None,
             order_tx,
-            order_state_tx,
+            events,
         );
 
         let block_time = market_monitor.get_block_time().await.unwrap();
@@ -806,6 +1421,51 @@ mod tests {
         assert_eq!(seal, fulfillment.seal);
     }
 
+    #[tokio::test]
+    async fn recovers_unrecorded_locks() {
+        let anvil = Anvil::new().spawn();
+        let ctx = create_test_ctx(&anvil).await.unwrap();
+        let market_address = ctx.deployment.boundless_market_address;
+
+        let request = new_request(1, &ctx).await;
+        let request_id =
+            ctx.customer_market.submit_request(&request, &ctx.customer_signer).await.unwrap();
+        let logs = ctx.customer_market.instance().RequestSubmitted_filter().query().await.unwrap();
+        let (event, _) = logs.first().unwrap();
+
+        // Lock the request without recording it, as if the broker stopped right after locking
+        let deposit = default_allowance();
+        ctx.prover_market.deposit_stake_with_permit(deposit, &ctx.prover_signer).await.unwrap();
+        ctx.prover_market
+            .lock_request(&event.request, event.clientSignature.clone(), None)
+            .await
+            .unwrap();
+
+        let provider = Arc::new(ctx.prover_provider.clone());
+        let chain_monitor = Arc::new(ChainMonitorService::new(provider.clone()).await.unwrap());
+        tokio::spawn(chain_monitor.spawn(Default::default()));
+        let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
+
+        let recover = || {
+            MarketMonitor::recover_own_locks(
+                10,
+                market_address,
+                ctx.prover_signer.address(),
+                provider.clone(),
+                &db,
+                chain_monitor.clone(),
+            )
+        };
+        assert_eq!(recover().await.unwrap(), 1);
+        let order = db.get_committed_orders().await.unwrap().pop().unwrap();
+        assert_eq!(order.request.id, request_id);
+        assert_eq!(order.status, crate::OrderStatus::PendingProving);
+        assert_eq!(order.expire_timestamp, Some(request.lock_expires_at()));
+
+        // Already recorded orders are left alone
+        assert_eq!(recover().await.unwrap(), 0);
+    }
+
     async fn new_request<P: Provider>(idx: u32, ctx: &TestCtx<P>) -> ProofRequest {
         ProofRequest::new(
             RequestId::new(ctx.customer_signer.address(), idx),
@@ -0,0 +1,100 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// Number of gas price samples kept for computing the recent average.
+const GAS_PRICE_SAMPLES: usize = 100;
+
+/// Number of competing lock-expired fulfillments kept in memory.
+const MAX_COMPETITOR_FULFILLMENTS: usize = 1000;
+
+/// Rolling statistics about market activity, shared between broker services.
+///
+/// Samples are kept in memory only and are collected from the moment the broker starts.
+#[derive(Default)]
+pub(crate) struct MarketStats {
+    gas_prices: Mutex<VecDeque<u128>>,
+    competitor_fulfillments: Mutex<VecDeque<u64>>,
+}
+
+pub(crate) type MarketStatsObj = Arc<MarketStats>;
+
+impl MarketStats {
+    /// Record the gas price observed at a new block.
+    pub(crate) fn record_gas_price(&self, gas_price: u128) {
+        let mut gas_prices = self.gas_prices.lock().unwrap();
+        if gas_prices.len() == GAS_PRICE_SAMPLES {
+            gas_prices.pop_front();
+        }
+        gas_prices.push_back(gas_price);
+    }
+
+    /// Average of the recently recorded gas prices, if any have been recorded.
+    pub(crate) fn average_gas_price(&self) -> Option<u128> {
+        let gas_prices = self.gas_prices.lock().unwrap();
+        if gas_prices.is_empty() {
+            return None;
+        }
+        Some(gas_prices.iter().sum::<u128>() / gas_prices.len() as u128)
+    }
+
+    /// Record that another prover fulfilled a request locked by someone else, i.e. in the
+    /// window after the lock expired.
+    pub(crate) fn record_competitor_fulfillment(&self, timestamp: u64) {
+        let mut fulfillments = self.competitor_fulfillments.lock().unwrap();
+        if fulfillments.len() == MAX_COMPETITOR_FULFILLMENTS {
+            fulfillments.pop_front();
+        }
+        fulfillments.push_back(timestamp);
+    }
+
+    /// Number of competing lock-expired fulfillments recorded at or after `since`.
+    pub(crate) fn competitor_fulfillments_since(&self, since: u64) -> usize {
+        self.competitor_fulfillments.lock().unwrap().iter().filter(|&&ts| ts >= since).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn average_gas_price_uses_recent_samples() {
+        let stats = MarketStats::default();
+        assert_eq!(stats.average_gas_price(), None);
+
+        for _ in 0..GAS_PRICE_SAMPLES {
+            stats.record_gas_price(100);
+        }
+        for _ in 0..GAS_PRICE_SAMPLES / 2 {
+            stats.record_gas_price(200);
+        }
+        assert_eq!(stats.average_gas_price(), Some(150));
+    }
+
+    #[test]
+    fn counts_competitor_fulfillments_in_window() {
+        let stats = MarketStats::default();
+        stats.record_competitor_fulfillment(100);
+        stats.record_competitor_fulfillment(200);
+
+        assert_eq!(stats.competitor_fulfillments_since(0), 2);
+        assert_eq!(stats.competitor_fulfillments_since(150), 1);
+        assert_eq!(stats.competitor_fulfillments_since(201), 0);
+    }
+}
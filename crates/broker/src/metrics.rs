@@ -0,0 +1,523 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Prometheus metrics for the broker, served in the text exposition format.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use axum::{extract::State, http::header::CONTENT_TYPE, routing::get, Router};
+use thiserror::Error;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    chain_monitor::GasSpike,
+    db::{DbError, DbObj, OrderQuery, COMMITTED_ORDER_STATUSES},
+    duty_cycle::DutyCycle,
+    errors::CodedError,
+    impl_coded_debug,
+    task::{task_restarts, RetryRes, RetryTask, SupervisorErr},
+    OrderStatus,
+};
+
+/// Upper bounds of the preflight latency histogram buckets, in seconds.
+const PREFLIGHT_SECONDS_BUCKETS: [f64; 10] =
+    [0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+#[derive(Error)]
+pub enum MetricsErr {
+    #[error("{code} Failed to bind metrics endpoint to {0}: {1}", code = self.code())]
+    BindFailed(SocketAddr, std::io::Error),
+
+    #[error("{code} Metrics endpoint server error: {0}", code = self.code())]
+    ServeFailed(std::io::Error),
+}
+
+impl_coded_debug!(MetricsErr);
+
+impl CodedError for MetricsErr {
+    fn code(&self) -> &str {
+        match self {
+            MetricsErr::BindFailed(..) => "[B-MET-001]",
+            MetricsErr::ServeFailed(_) => "[B-MET-002]",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; PREFLIGHT_SECONDS_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+/// Counters, gauges and histograms collected by the broker services.
+///
+/// Values are kept in memory only and are reset when the broker restarts.
+#[derive(Default)]
+pub(crate) struct Metrics {
+    orders_received: AtomicU64,
+    orders_priced: AtomicU64,
+    orders_locked: AtomicU64,
+    orders_skipped: Mutex<BTreeMap<String, u64>>,
+    preflight_seconds: Mutex<Histogram>,
+    gas_balance_eth: Mutex<BTreeMap<u64, f64>>,
+    stake_balance: Mutex<BTreeMap<u64, f64>>,
+    fulfillments: AtomicU64,
+    fulfilled_batches: AtomicU64,
+    batch_gas_used: AtomicU64,
+    batch_gas_saved: AtomicI64,
+    slashes: AtomicU64,
+    duty_cycle: Mutex<Option<DutyCycle>>,
+    gas_spikes: AtomicU64,
+    gas_spike_percent: Mutex<BTreeMap<u64, f64>>,
+    invalid_stream_orders: AtomicU64,
+    tombstoned_orders: AtomicU64,
+    signatures_verified: AtomicU64,
+    signatures_invalid: AtomicU64,
+    signature_verify_micros: AtomicU64,
+    chain_reorgs: AtomicU64,
+}
+
+pub(crate) type MetricsObj = Arc<Metrics>;
+
+impl Metrics {
+    /// Record an order received from the market or order stream monitors.
+    pub(crate) fn record_order_received(&self) {
+        self.orders_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an order that was priced and scheduled for locking or proving.
+    pub(crate) fn record_order_priced(&self) {
+        self.orders_priced.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an order locked by the broker.
+    pub(crate) fn record_order_locked(&self) {
+        self.orders_locked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an order skipped by the order picker, labelled with a skip reason or error code.
+    pub(crate) fn record_order_skipped(&self, reason: &str) {
+        let reason = reason.trim_start_matches('[').trim_end_matches(']');
+        *self.orders_skipped.lock().unwrap().entry(reason.to_string()).or_default() += 1;
+    }
+
+    /// Record the duration of a preflight execution.
+    pub(crate) fn record_preflight(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        let mut histogram = self.preflight_seconds.lock().unwrap();
+        for (bucket, bound) in histogram.buckets.iter_mut().zip(PREFLIGHT_SECONDS_BUCKETS) {
+            if secs <= bound {
+                *bucket += 1;
+            }
+        }
+        histogram.count += 1;
+        histogram.sum += secs;
+    }
+
+    /// Record the gas token balance of the signer on the given chain, in ether.
+    pub(crate) fn record_gas_balance(&self, chain_id: u64, balance_eth: f64) {
+        self.gas_balance_eth.lock().unwrap().insert(chain_id, balance_eth);
+    }
+
+    /// Record the stake balance of the prover on the given chain, in stake tokens.
+    pub(crate) fn record_stake_balance(&self, chain_id: u64, balance: f64) {
+        self.stake_balance.lock().unwrap().insert(chain_id, balance);
+    }
+
+    /// Record orders fulfilled in a submitted batch.
+    pub(crate) fn record_fulfillments(&self, num_orders: usize) {
+        self.fulfillments.fetch_add(num_orders as u64, Ordering::Relaxed);
+    }
+
+    /// Record a fulfilled batch, the gas its fulfillment used and the gas saved compared to
+    /// fulfilling each order individually, negative if the batch used more.
+    pub(crate) fn record_batch_fulfilled(&self, gas_used: u64, gas_saved: i64) {
+        self.fulfilled_batches.fetch_add(1, Ordering::Relaxed);
+        self.batch_gas_used.fetch_add(gas_used, Ordering::Relaxed);
+        self.batch_gas_saved.fetch_add(gas_saved, Ordering::Relaxed);
+    }
+
+    /// Record a locked order that expired before the broker fulfilled it, forfeiting its stake.
+    pub(crate) fn record_slash(&self) {
+        self.slashes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record the actual and projected duty cycle of the prover.
+    pub(crate) fn record_duty_cycle(&self, duty_cycle: DutyCycle) {
+        *self.duty_cycle.lock().unwrap() = Some(duty_cycle);
+    }
+
+    /// Record the start or end of a gas spike on the given chain.
+    pub(crate) fn record_gas_spike(&self, chain_id: u64, spike: Option<GasSpike>) {
+        if spike.is_some() {
+            self.gas_spikes.fetch_add(1, Ordering::Relaxed);
+        }
+        let pct = spike.map_or(0.0, |spike| spike.pct as f64);
+        self.gas_spike_percent.lock().unwrap().insert(chain_id, pct);
+    }
+
+    /// Record a reorg detected by the chain monitor.
+    pub(crate) fn record_chain_reorg(&self) {
+        self.chain_reorgs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an order received from the order stream that failed validation.
+    pub(crate) fn record_invalid_stream_order(&self) {
+        self.invalid_stream_orders.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an order skipped without pricing because its request was tombstoned.
+    pub(crate) fn record_tombstoned_order(&self) {
+        self.tombstoned_orders.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a batch of order signatures verified in the given time, of which `invalid` were
+    /// invalid.
+    pub(crate) fn record_signatures_verified(&self, count: u64, invalid: u64, elapsed: Duration) {
+        self.signatures_verified.fetch_add(count, Ordering::Relaxed);
+        self.signatures_invalid.fetch_add(invalid, Ordering::Relaxed);
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        self.signature_verify_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    /// Encode all metrics in the Prometheus text exposition format.
+    pub(crate) fn encode(&self) -> String {
+        let mut out = String::new();
+        let counter = |out: &mut String, name: &str, help: &str, value: &AtomicU64| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            let _ = writeln!(out, "{name} {}", value.load(Ordering::Relaxed));
+        };
+        let gauge_by_chain =
+            |out: &mut String, name: &str, help: &str, values: &Mutex<BTreeMap<u64, f64>>| {
+                let values = values.lock().unwrap();
+                let _ = writeln!(out, "# HELP {name} {help}");
+                let _ = writeln!(out, "# TYPE {name} gauge");
+                for (chain_id, value) in values.iter() {
+                    let _ = writeln!(out, "{name}{{chain_id=\"{chain_id}\"}} {value}");
+                }
+            };
+
+        counter(
+            &mut out,
+            "broker_orders_received_total",
+            "Orders received for pricing.",
+            &self.orders_received,
+        );
+        counter(
+            &mut out,
+            "broker_orders_priced_total",
+            "Orders priced and scheduled for locking or proving.",
+            &self.orders_priced,
+        );
+        counter(&mut out, "broker_orders_locked_total", "Orders locked.", &self.orders_locked);
+
+        let _ = writeln!(out, "# HELP broker_orders_skipped_total Orders skipped, by reason.");
+        let _ = writeln!(out, "# TYPE broker_orders_skipped_total counter");
+        for (reason, count) in self.orders_skipped.lock().unwrap().iter() {
+            let _ = writeln!(out, "broker_orders_skipped_total{{reason=\"{reason}\"}} {count}");
+        }
+
+        {
+            let histogram = self.preflight_seconds.lock().unwrap();
+            let name = "broker_preflight_duration_seconds";
+            let _ = writeln!(out, "# HELP {name} Duration of preflight executions.");
+            let _ = writeln!(out, "# TYPE {name} histogram");
+            for (count, bound) in histogram.buckets.iter().zip(PREFLIGHT_SECONDS_BUCKETS) {
+                let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {count}");
+            }
+            let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {}", histogram.count);
+            let _ = writeln!(out, "{name}_sum {}", histogram.sum);
+            let _ = writeln!(out, "{name}_count {}", histogram.count);
+        }
+
+        gauge_by_chain(
+            &mut out,
+            "broker_gas_balance_eth",
+            "Gas token balance of the signer, in ether.",
+            &self.gas_balance_eth,
+        );
+        gauge_by_chain(
+            &mut out,
+            "broker_stake_balance",
+            "Stake balance of the prover, in stake tokens.",
+            &self.stake_balance,
+        );
+        counter(
+            &mut out,
+            "broker_fulfillments_total",
+            "Orders fulfilled on chain.",
+            &self.fulfillments,
+        );
+        counter(
+            &mut out,
+            "broker_fulfilled_batches_total",
+            "Batches of orders fulfilled on chain.",
+            &self.fulfilled_batches,
+        );
+        counter(
+            &mut out,
+            "broker_batch_gas_used_total",
+            "Gas used by the fulfillment transactions of batches.",
+            &self.batch_gas_used,
+        );
+        {
+            let name = "broker_batch_gas_saved";
+            let _ = writeln!(
+                out,
+                "# HELP {name} Gas saved by fulfilling orders in batches rather than individually."
+            );
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            let _ = writeln!(out, "{name} {}", self.batch_gas_saved.load(Ordering::Relaxed));
+        }
+        counter(
+            &mut out,
+            "broker_slashes_total",
+            "Locked orders that expired before being fulfilled.",
+            &self.slashes,
+        );
+        counter(&mut out, "broker_gas_spikes_total", "Gas spikes detected.", &self.gas_spikes);
+        gauge_by_chain(
+            &mut out,
+            "broker_gas_spike_percent",
+            "Increase of the gas price during the ongoing gas spike, in percent; 0 when none.",
+            &self.gas_spike_percent,
+        );
+        counter(
+            &mut out,
+            "broker_chain_reorgs_total",
+            "Chain reorgs detected.",
+            &self.chain_reorgs,
+        );
+        counter(
+            &mut out,
+            "broker_order_stream_invalid_orders_total",
+            "Orders received from the order stream that failed validation and were dropped.",
+            &self.invalid_stream_orders,
+        );
+        counter(
+            &mut out,
+            "broker_tombstoned_orders_total",
+            "Orders skipped without pricing because their request was skipped for a permanent reason.",
+            &self.tombstoned_orders,
+        );
+        counter(
+            &mut out,
+            "broker_signatures_verified_total",
+            "Signatures of orders from the order stream verified.",
+            &self.signatures_verified,
+        );
+        counter(
+            &mut out,
+            "broker_signatures_invalid_total",
+            "Orders from the order stream dropped for an invalid signature.",
+            &self.signatures_invalid,
+        );
+        {
+            let name = "broker_signature_verify_seconds_total";
+            let secs = self.signature_verify_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+            let _ = writeln!(out, "# HELP {name} Time spent verifying batches of signatures.");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            let _ = writeln!(out, "{name} {secs}");
+        }
+
+        if let Some(duty_cycle) = *self.duty_cycle.lock().unwrap() {
+            for (name, help, value) in [
+                (
+                    "broker_duty_cycle_actual",
+                    "Share of the duty cycle window spent proving.",
+                    duty_cycle.actual,
+                ),
+                (
+                    "broker_duty_cycle_projected",
+                    "Share of the duty cycle window spent proving, including committed orders.",
+                    duty_cycle.projected,
+                ),
+            ] {
+                let _ = writeln!(out, "# HELP {name} {help}");
+                let _ = writeln!(out, "# TYPE {name} gauge");
+                let _ = writeln!(out, "{name} {value}");
+            }
+        }
+
+        let _ =
+            writeln!(out, "# HELP broker_task_restarts_total Restarts of failed tasks, by task.");
+        let _ = writeln!(out, "# TYPE broker_task_restarts_total counter");
+        for (task, count) in task_restarts() {
+            let _ = writeln!(out, "broker_task_restarts_total{{task=\"{task}\"}} {count}");
+        }
+        out
+    }
+}
+
+/// Number of orders in the DB by status, counted on each scrape.
+async fn count_orders(db: &DbObj) -> Result<Vec<(OrderStatus, u64)>, DbError> {
+    let statuses = COMMITTED_ORDER_STATUSES.into_iter().chain([
+        OrderStatus::Done,
+        OrderStatus::Failed,
+        OrderStatus::Skipped,
+    ]);
+    let mut counts = Vec::new();
+    for status in statuses {
+        let query = OrderQuery { statuses: vec![status], ..Default::default() };
+        counts.push((status, db.count_orders(&query).await?));
+    }
+    Ok(counts)
+}
+
+fn encode_order_counts(out: &mut String, counts: &[(OrderStatus, u64)]) {
+    let _ = writeln!(out, "# HELP broker_db_orders Orders in the database, by status.");
+    let _ = writeln!(out, "# TYPE broker_db_orders gauge");
+    for (status, count) in counts {
+        let _ = writeln!(out, "broker_db_orders{{status=\"{status:?}\"}} {count}");
+    }
+}
+
+/// HTTP server exposing the broker metrics at `/metrics`.
+#[derive(Clone)]
+pub struct MetricsServer {
+    listen_addr: SocketAddr,
+    metrics: MetricsObj,
+    db: Option<DbObj>,
+}
+
+impl MetricsServer {
+    pub(crate) fn new(listen_addr: SocketAddr, metrics: MetricsObj) -> Self {
+        Self { listen_addr, metrics, db: None }
+    }
+
+    /// Export the number of orders in the DB by status.
+    pub(crate) fn with_db(self, db: DbObj) -> Self {
+        Self { db: Some(db), ..self }
+    }
+
+    fn router(&self) -> Router {
+        Router::new().route("/metrics", get(serve_metrics)).with_state(self.clone())
+    }
+}
+
+async fn serve_metrics(State(server): State<MetricsServer>) -> impl axum::response::IntoResponse {
+    let mut out = server.metrics.encode();
+    if let Some(db) = &server.db {
+        match count_orders(db).await {
+            Ok(counts) => encode_order_counts(&mut out, &counts),
+            Err(err) => tracing::warn!("Failed to count orders for metrics: {err}"),
+        }
+    }
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], out)
+}
+
+impl RetryTask for MetricsServer {
+    type Error = MetricsErr;
+
+    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
+        let listen_addr = self.listen_addr;
+        let router = self.router();
+        Box::pin(async move {
+            let listener = TcpListener::bind(listen_addr)
+                .await
+                .map_err(|err| SupervisorErr::Recover(MetricsErr::BindFailed(listen_addr, err)))?;
+            tracing::info!("Metrics endpoint listening on {listen_addr}");
+            axum::serve(listener, router)
+                .with_graceful_shutdown(cancel_token.cancelled_owned())
+                .await
+                .map_err(|err| SupervisorErr::Recover(MetricsErr::ServeFailed(err)))?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_counters_and_labels() {
+        let metrics = Metrics::default();
+        metrics.record_order_received();
+        metrics.record_order_received();
+        metrics.record_order_skipped("exec_limit_exceeded");
+        metrics.record_order_skipped("[B-OP-006]");
+        metrics.record_gas_balance(1, 0.5);
+
+        let encoded = metrics.encode();
+        assert!(encoded.contains("broker_orders_received_total 2\n"));
+        assert!(encoded.contains("broker_orders_skipped_total{reason=\"exec_limit_exceeded\"} 1\n"));
+        assert!(encoded.contains("broker_orders_skipped_total{reason=\"B-OP-006\"} 1\n"));
+        assert!(encoded.contains("broker_gas_balance_eth{chain_id=\"1\"} 0.5\n"));
+        assert!(!encoded.contains("broker_stake_balance{"));
+        assert!(!encoded.contains("broker_duty_cycle_actual"));
+
+        metrics.record_duty_cycle(DutyCycle { actual: 0.25, projected: 0.5 });
+        let encoded = metrics.encode();
+        assert!(encoded.contains("broker_duty_cycle_actual 0.25\n"));
+        assert!(encoded.contains("broker_duty_cycle_projected 0.5\n"));
+
+        metrics.record_gas_spike(1, Some(GasSpike { pct: 80, window: Duration::from_secs(60) }));
+        metrics.record_gas_spike(1, None);
+        let encoded = metrics.encode();
+        assert!(encoded.contains("broker_gas_spikes_total 1\n"));
+        assert!(encoded.contains("broker_gas_spike_percent{chain_id=\"1\"} 0\n"));
+
+        metrics.record_batch_fulfilled(1_000_000, 1_250_000);
+        metrics.record_batch_fulfilled(800_000, -50_000);
+        let encoded = metrics.encode();
+        assert!(encoded.contains("broker_fulfilled_batches_total 2\n"));
+        assert!(encoded.contains("broker_batch_gas_used_total 1800000\n"));
+        assert!(encoded.contains("broker_batch_gas_saved 1200000\n"));
+    }
+
+    #[test]
+    fn preflight_histogram_is_cumulative() {
+        let metrics = Metrics::default();
+        metrics.record_preflight(Duration::from_millis(50));
+        metrics.record_preflight(Duration::from_secs(3));
+        metrics.record_preflight(Duration::from_secs(600));
+
+        let encoded = metrics.encode();
+        assert!(encoded.contains("broker_preflight_duration_seconds_bucket{le=\"0.1\"} 1\n"));
+        assert!(encoded.contains("broker_preflight_duration_seconds_bucket{le=\"5\"} 2\n"));
+        assert!(encoded.contains("broker_preflight_duration_seconds_bucket{le=\"300\"} 2\n"));
+        assert!(encoded.contains("broker_preflight_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(encoded.contains("broker_preflight_duration_seconds_count 3\n"));
+    }
+
+    #[tokio::test]
+    async fn serves_metrics() {
+        let metrics: MetricsObj = Default::default();
+        metrics.record_order_locked();
+        let db: DbObj = Arc::new(crate::db::SqliteDb::new("sqlite::memory:").await.unwrap());
+        let server = MetricsServer::new(([127, 0, 0, 1], 0).into(), metrics).with_db(db);
+        let listener = TcpListener::bind(server.listen_addr).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = server.router();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let body = reqwest::get(format!("http://{addr}/metrics")).await.unwrap().text().await;
+        let body = body.unwrap();
+        assert!(body.contains("broker_orders_locked_total 1\n"));
+        assert!(body.contains("broker_db_orders{status=\"Proving\"} 0\n"));
+    }
+}
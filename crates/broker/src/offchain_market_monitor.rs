@@ -12,16 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use alloy::signers::{local::PrivateKeySigner, Signer};
+use std::time::Duration;
+
+use alloy::signers::local::PrivateKeySigner;
 use anyhow::Result;
-use boundless_market::order_stream_client::{order_stream, OrderStreamClient};
+use boundless_market::order_stream_client::{
+    ConsumerGroup, OrderData, OrderError, OrderStreamClient, OrderStreamHook, OrderValidation,
+    StreamWatchdog,
+};
 use futures_util::StreamExt;
 
 use crate::{
+    config::{ConfigErr, ConfigLock},
     errors::CodedError,
     impl_coded_debug,
+    metrics::MetricsObj,
     task::{RetryRes, RetryTask, SupervisorErr},
-    FulfillmentType, OrderRequest,
+    FulfillmentType, OrderProvenance, OrderRequest, OrderSource,
 };
 use thiserror::Error;
 use tokio_util::sync::CancellationToken;
@@ -34,6 +41,12 @@ pub enum OffchainMarketMonitorErr {
     #[error("{code} Receiver dropped", code = self.code())]
     ReceiverDropped,
 
+    #[error("{code} Config error {0}", code = self.code())]
+    ConfigReadErr(#[from] ConfigErr),
+
+    #[error("{code} Order stream degraded, no message received for {0:?}", code = self.code())]
+    StreamDegraded(Duration),
+
     #[error("{code} Unexpected error: {0:?}", code = self.code())]
     UnexpectedErr(#[from] anyhow::Error),
 }
@@ -45,15 +58,34 @@ impl CodedError for OffchainMarketMonitorErr {
         match self {
             OffchainMarketMonitorErr::WebSocketErr(_) => "[B-OMM-001]",
             OffchainMarketMonitorErr::ReceiverDropped => "[B-OMM-002]",
+            OffchainMarketMonitorErr::ConfigReadErr(_) => "[B-OMM-003]",
+            OffchainMarketMonitorErr::StreamDegraded(_) => "[B-OMM-004]",
             OffchainMarketMonitorErr::UnexpectedErr(_) => "[B-OMM-500]",
         }
     }
 }
 
+/// Records orders received from the order stream that failed validation in the metrics.
+struct InvalidOrderMetrics(MetricsObj);
+
+impl std::fmt::Debug for InvalidOrderMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("InvalidOrderMetrics")
+    }
+}
+
+impl OrderStreamHook for InvalidOrderMetrics {
+    fn on_invalid_order(&self, _order: &OrderData, _err: &OrderError) {
+        self.0.record_invalid_stream_order();
+    }
+}
+
 pub struct OffchainMarketMonitor {
     client: OrderStreamClient,
     signer: PrivateKeySigner,
     new_order_tx: tokio::sync::mpsc::Sender<Box<OrderRequest>>,
+    config: ConfigLock,
+    metrics: MetricsObj,
 }
 
 impl OffchainMarketMonitor {
@@ -61,22 +93,48 @@ impl OffchainMarketMonitor {
         client: OrderStreamClient,
         signer: PrivateKeySigner,
         new_order_tx: tokio::sync::mpsc::Sender<Box<OrderRequest>>,
+        config: ConfigLock,
     ) -> Self {
-        Self { client, signer, new_order_tx }
+        Self { client, signer, new_order_tx, config, metrics: Default::default() }
+    }
+
+    /// Record orders dropped by validation in the given metrics.
+    pub(crate) fn with_metrics(self, metrics: MetricsObj) -> Self {
+        Self { metrics, ..self }
     }
 
     async fn monitor_orders(
         client: OrderStreamClient,
-        signer: &impl Signer,
+        signer: PrivateKeySigner,
         new_order_tx: tokio::sync::mpsc::Sender<Box<OrderRequest>>,
+        poll_interval: Option<Duration>,
+        watchdog: Option<StreamWatchdog>,
         cancel_token: CancellationToken,
     ) -> Result<(), OffchainMarketMonitorErr> {
-        tracing::debug!("Connecting to off-chain market: {}", client.base_url);
-        let socket =
-            client.connect_async(signer).await.map_err(OffchainMarketMonitorErr::WebSocketErr)?;
+        let mut stream = match poll_interval {
+            Some(poll_interval) => {
+                tracing::info!(
+                    "Polling off-chain market {} for orders every {poll_interval:?}",
+                    client.base_url
+                );
+                client.polling_order_stream(poll_interval, None)
+            }
+            None => {
+                tracing::debug!("Connecting to off-chain market: {}", client.base_url);
+                // Reconnects on its own, resuming from the last received order.
+                let stream = client.resilient_order_stream(signer);
+                tracing::info!("Subscribed to offchain Order stream");
+                stream
+            }
+        };
 
-        let mut stream = order_stream(socket);
-        tracing::info!("Subscribed to offchain Order stream");
+        let degraded = async {
+            match &watchdog {
+                Some(watchdog) => watchdog.degraded().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(degraded);
 
         loop {
             tokio::select! {
@@ -91,11 +149,15 @@ impl OffchainMarketMonitor {
 
                             let new_order = OrderRequest::new(
                                 order_data.order.request,
-                                order_data.order.signature.as_bytes().into(),
+                                order_data.order.client_signature(),
                                 FulfillmentType::LockAndFulfill,
                                 client.boundless_market_address,
                                 client.chain_id,
-                            );
+                            )
+                            .with_provenance(OrderProvenance::new(OrderSource::OrderStream {
+                                url: client.base_url.to_string(),
+                                stream_id: Some(order_data.id),
+                            }));
 
                             if let Err(e) = new_order_tx.send(Box::new(new_order)).await {
                                 tracing::error!("Failed to send new order to broker: {}", e);
@@ -114,6 +176,18 @@ impl OffchainMarketMonitor {
                         }
                     }
                 }
+                _ = &mut degraded => {
+                    let silence = watchdog.as_ref().map(|watchdog| watchdog.silence()).unwrap_or_default();
+                    match client.health().await {
+                        Ok(health) => tracing::warn!(
+                            "Order stream degraded after {silence:?} without messages, server health: {health:?}"
+                        ),
+                        Err(err) => tracing::warn!(
+                            "Order stream degraded after {silence:?} without messages, health check failed: {err:?}"
+                        ),
+                    }
+                    return Err(OffchainMarketMonitorErr::StreamDegraded(silence));
+                }
                 _ = cancel_token.cancelled() => {
                     tracing::info!("Offchain market monitor received cancellation, shutting down gracefully");
                     return Ok(());
@@ -129,12 +203,50 @@ impl RetryTask for OffchainMarketMonitor {
         let client = self.client.clone();
         let signer = self.signer.clone();
         let new_order_tx = self.new_order_tx.clone();
+        let config = self.config.clone();
+        let metrics = self.metrics.clone();
 
         Box::pin(async move {
             tracing::info!("Starting up offchain market monitor");
-            Self::monitor_orders(client, &signer, new_order_tx, cancel_token)
-                .await
-                .map_err(SupervisorErr::Recover)?;
+            let (poll_interval, consumer_group, validation, watchdog_secs) = {
+                let config = config
+                    .lock_all()
+                    .map_err(|err| SupervisorErr::Recover(OffchainMarketMonitorErr::from(err)))?;
+                (
+                    config.market.order_stream_poll_interval_secs.map(Duration::from_secs),
+                    config.market.order_stream_consumer_group.clone(),
+                    config.market.order_stream_validation,
+                    config.market.order_stream_watchdog_secs,
+                )
+            };
+            let client = match consumer_group {
+                Some(name) => client.with_consumer_group(ConsumerGroup::new(name)),
+                None => client,
+            };
+            let client = if validation {
+                client
+                    .with_validation(OrderValidation::Drop)
+                    .with_hook(InvalidOrderMetrics(metrics))
+            } else {
+                client
+            };
+            // The watchdog relies on the pongs of the websocket, so it does not apply to polling
+            let watchdog = (poll_interval.is_none() && watchdog_secs > 0)
+                .then(|| StreamWatchdog::new(Duration::from_secs(watchdog_secs)));
+            let client = match &watchdog {
+                Some(watchdog) => client.with_hook(watchdog.clone()),
+                None => client,
+            };
+            Self::monitor_orders(
+                client,
+                signer,
+                new_order_tx,
+                poll_interval,
+                watchdog,
+                cancel_token,
+            )
+            .await
+            .map_err(SupervisorErr::Recover)?;
             Ok(())
         })
     }
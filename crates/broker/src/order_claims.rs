@@ -0,0 +1,207 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Claims on requests shared by broker replicas, see [OrderClaimsConf].
+//!
+//! The order cache only deduplicates orders within a broker. Replicas sharing a wallet claim each
+//! request in a shared database before pricing it, so that only one of them preflights and locks
+//! it. Claims are leased, so that the requests of a replica that stopped are picked up by the
+//! others once its leases expire.
+//!
+//! The leadership lease of active/standby replicas, see [crate::leadership], is held in the same
+//! table.
+
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use alloy::primitives::U256;
+use sqlx::{
+    postgres::PgPoolOptions,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+    PgPool, SqlitePool,
+};
+
+use crate::{config::OrderClaimsConf, db::DbError, now_timestamp};
+
+const CREATE_TABLE_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS order_claims (
+        claim_key TEXT PRIMARY KEY,
+        owner TEXT NOT NULL,
+        expires_at BIGINT NOT NULL
+    )"#;
+
+/// Claims the key, unless another replica holds an unexpired claim on it.
+const CLAIM_SQL: &str = r#"
+    INSERT INTO order_claims (claim_key, owner, expires_at) VALUES ($1, $2, $3)
+        ON CONFLICT (claim_key) DO UPDATE
+        SET owner = excluded.owner, expires_at = excluded.expires_at
+        WHERE order_claims.owner = excluded.owner OR order_claims.expires_at <= $4"#;
+
+const RELEASE_SQL: &str = "DELETE FROM order_claims WHERE claim_key = $1 AND owner = $2";
+
+/// Key of the leadership lease, distinct from the hex request IDs.
+const LEADER_KEY: &str = "leader";
+
+enum ClaimsPool {
+    Sqlite(SqlitePool),
+    Postgres(PgPool),
+}
+
+/// Claims of this replica on the requests it prices.
+pub(crate) struct OrderClaims {
+    pool: ClaimsPool,
+    owner: String,
+    lease_secs: u64,
+}
+
+pub(crate) type OrderClaimsObj = Arc<OrderClaims>;
+
+impl OrderClaims {
+    /// Connect to the claims database, creating the claims table if missing.
+    pub(crate) async fn connect(conf: &OrderClaimsConf) -> Result<Self, DbError> {
+        let pool = if conf.db_url.starts_with("postgres") {
+            let pool = PgPoolOptions::new()
+                .max_connections(2)
+                .acquire_timeout(Duration::from_secs(5))
+                .connect(&conf.db_url)
+                .await?;
+            ClaimsPool::Postgres(pool)
+        } else {
+            // The database file is shared with the other replicas
+            let opts = SqliteConnectOptions::from_str(&conf.db_url)?
+                .journal_mode(SqliteJournalMode::Wal)
+                .create_if_missing(true)
+                .busy_timeout(Duration::from_secs(5));
+            ClaimsPool::Sqlite(
+                SqlitePoolOptions::new().max_connections(1).connect_with(opts).await?,
+            )
+        };
+        let owner = conf.replica_id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        Self::new(pool, owner, conf.lease_secs).await
+    }
+
+    async fn new(pool: ClaimsPool, owner: String, lease_secs: u64) -> Result<Self, DbError> {
+        match &pool {
+            ClaimsPool::Sqlite(pool) => {
+                sqlx::query(CREATE_TABLE_SQL).execute(pool).await?;
+            }
+            ClaimsPool::Postgres(pool) => {
+                sqlx::query(CREATE_TABLE_SQL).execute(pool).await?;
+            }
+        }
+        tracing::info!("Claiming orders as replica {owner}, with leases of {lease_secs}s");
+        Ok(Self { pool, owner, lease_secs })
+    }
+
+    /// Claim the request for this replica, or extend its claim, returning false if another
+    /// replica holds it.
+    pub(crate) async fn claim(&self, request_id: U256) -> Result<bool, DbError> {
+        self.claim_key(&format!("0x{request_id:x}"), self.lease_secs).await
+    }
+
+    /// Acquire the leadership lease for this replica, or renew it, returning false if another
+    /// replica holds it.
+    pub(crate) async fn claim_leadership(&self, lease_secs: u64) -> Result<bool, DbError> {
+        self.claim_key(LEADER_KEY, lease_secs).await
+    }
+
+    /// Release the leadership lease, if held by this replica, so that another replica can take
+    /// it over without waiting for it to expire.
+    pub(crate) async fn release_leadership(&self) -> Result<(), DbError> {
+        match &self.pool {
+            ClaimsPool::Sqlite(pool) => {
+                sqlx::query(RELEASE_SQL).bind(LEADER_KEY).bind(&self.owner).execute(pool).await?;
+            }
+            ClaimsPool::Postgres(pool) => {
+                sqlx::query(RELEASE_SQL).bind(LEADER_KEY).bind(&self.owner).execute(pool).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn claim_key(&self, key: &str, lease_secs: u64) -> Result<bool, DbError> {
+        let now = now_timestamp() as i64;
+        let expires_at = now + lease_secs as i64;
+        let claimed = match &self.pool {
+            ClaimsPool::Sqlite(pool) => sqlx::query(CLAIM_SQL)
+                .bind(key)
+                .bind(&self.owner)
+                .bind(expires_at)
+                .bind(now)
+                .execute(pool)
+                .await?
+                .rows_affected(),
+            ClaimsPool::Postgres(pool) => sqlx::query(CLAIM_SQL)
+                .bind(key)
+                .bind(&self.owner)
+                .bind(expires_at)
+                .bind(now)
+                .execute(pool)
+                .await?
+                .rows_affected(),
+        };
+        Ok(claimed > 0)
+    }
+
+    /// Claims of a replica in the given SQLite database, e.g. shared in memory by the replicas of
+    /// a test.
+    #[cfg(test)]
+    pub(crate) async fn with_sqlite(
+        pool: SqlitePool,
+        owner: &str,
+        lease_secs: u64,
+    ) -> Result<Self, DbError> {
+        Self::new(ClaimsPool::Sqlite(pool), owner.into(), lease_secs).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn claims_requests_across_replicas() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let replica = |owner: &str, lease_secs| {
+            OrderClaims::new(ClaimsPool::Sqlite(pool.clone()), owner.into(), lease_secs)
+        };
+        let a = replica("a", 600).await.unwrap();
+        let b = replica("b", 600).await.unwrap();
+
+        assert!(a.claim(U256::from(1)).await.unwrap());
+        // Claims are extended by their owner only
+        assert!(a.claim(U256::from(1)).await.unwrap());
+        assert!(!b.claim(U256::from(1)).await.unwrap());
+        assert!(b.claim(U256::from(2)).await.unwrap());
+
+        // Expired claims are taken over
+        let c = replica("c", 0).await.unwrap();
+        assert!(c.claim(U256::from(3)).await.unwrap());
+        assert!(a.claim(U256::from(3)).await.unwrap());
+        assert!(!c.claim(U256::from(3)).await.unwrap());
+
+        // The leadership lease is held apart from the claims on requests
+        assert!(b.claim_leadership(600).await.unwrap());
+        assert!(!a.claim_leadership(600).await.unwrap());
+        a.release_leadership().await.unwrap();
+        assert!(!a.claim_leadership(600).await.unwrap());
+        b.release_leadership().await.unwrap();
+        assert!(a.claim_leadership(600).await.unwrap());
+    }
+}
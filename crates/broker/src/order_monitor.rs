@@ -15,13 +15,31 @@
 use crate::chain_monitor::ChainHead;
 use crate::OrderRequest;
 use crate::{
+    admin::DrainModeObj,
     chain_monitor::ChainMonitorService,
-    config::{ConfigLock, OrderCommitmentPriority},
+    config::{
+        ConfigLock, DutyCycleConf, LockRaceAction, LockRaceWatchConf, OrderCommitmentPriority,
+        OrderTagConf, RequestorTierConf,
+    },
     db::DbObj,
+    duty_cycle,
     errors::CodedError,
-    impl_coded_debug, now_timestamp,
+    events::{BrokerEvent, EventBus},
+    impl_coded_debug,
+    leadership::LeadershipObj,
+    lock_expired_strategy::{LockExpiredAction, LockExpiredContext, LockExpiredStrategyObj},
+    market_stats::MarketStatsObj,
+    metrics::MetricsObj,
+    now_timestamp,
+    order_claims::OrderClaimsObj,
+    order_tags,
+    proving_capacity::ProvingCapacityTrackerObj,
+    reputation::ClientEvent,
+    reservations::ReservationsObj,
+    selectors::SelectorRegistry,
+    self_throttle::{PipelineOutcome, SelfThrottleObj},
     task::{RetryRes, RetryTask, SupervisorErr},
-    utils, FulfillmentType, Order,
+    utils, FulfillmentType, GasTxKind, Order, OrderGasRecord, SkipReason,
 };
 use alloy::{
     network::Ethereum,
@@ -30,24 +48,29 @@ use alloy::{
         Address, U256,
     },
     providers::{Provider, WalletProvider},
+    rpc::types::TransactionReceipt,
+    signers::local::PrivateKeySigner,
 };
 use anyhow::{Context, Result};
 use boundless_market::contracts::{
     boundless_market::{BoundlessMarketService, MarketError},
+    tx_submitter::TxSubmitter,
     IBoundlessMarket::IBoundlessMarketErrors,
     RequestStatus, TxnErr,
 };
-use boundless_market::selector::SupportedSelectors;
 use moka::{future::Cache, Expiry};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use thiserror::Error;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, oneshot, Mutex, MutexGuard};
 use tokio_util::sync::CancellationToken;
 
 /// Hard limit on the number of orders to concurrently kick off proving work for.
 const MAX_PROVING_BATCH_SIZE: u32 = 10;
 
+/// Minimum number of settled locks of a prover to tell whether it is likely to miss a lock.
+const MIN_LOCKER_SETTLED_LOCKS: u64 = 5;
+
 #[derive(Error)]
 pub enum OrderMonitorErr {
     #[error("{code} Failed to lock order: {0}", code = self.code())]
@@ -59,12 +82,39 @@ pub enum OrderMonitorErr {
     #[error("{code} Insufficient balance for lock", code = self.code())]
     InsufficientBalance,
 
+    #[error("{code} Requestor has insufficient balance at lock time: {0}", code = self.code())]
+    RequestorInsufficientBalance(String),
+
     #[error("{code} Order already locked", code = self.code())]
     AlreadyLocked,
 
     #[error("{code} RPC error: {0:?}", code = self.code())]
     RpcErr(anyhow::Error),
 
+    #[error("{code} Order {0} is not pending lock", code = self.code())]
+    UnknownOrder(String),
+
+    #[error("{code} Order lock has expired", code = self.code())]
+    LockExpired,
+
+    #[error("{code} Competing lock from {0} pending in the mempool", code = self.code())]
+    CompetingLock(Address),
+
+    #[error("{code} Gas cost {0} would exceed the gas budget {1} of the order", code = self.code())]
+    GasBudgetExceeded(U256, U256),
+
+    #[error("{code} Request claimed by another broker replica", code = self.code())]
+    ClaimedByReplica,
+
+    #[error("{code} Dry run, not locking orders", code = self.code())]
+    DryRun,
+
+    #[error("{code} Draining, not locking orders", code = self.code())]
+    Draining,
+
+    #[error("{code} On standby, not locking orders", code = self.code())]
+    Standby,
+
     #[error("{code} Unexpected error: {0:?}", code = self.code())]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -79,6 +129,15 @@ impl CodedError for OrderMonitorErr {
             OrderMonitorErr::AlreadyLocked => "[B-OM-009]",
             OrderMonitorErr::InsufficientBalance => "[B-OM-010]",
             OrderMonitorErr::RpcErr(_) => "[B-OM-011]",
+            OrderMonitorErr::UnknownOrder(_) => "[B-OM-012]",
+            OrderMonitorErr::LockExpired => "[B-OM-013]",
+            OrderMonitorErr::RequestorInsufficientBalance(_) => "[B-OM-014]",
+            OrderMonitorErr::CompetingLock(_) => "[B-OM-015]",
+            OrderMonitorErr::GasBudgetExceeded(_, _) => "[B-OM-016]",
+            OrderMonitorErr::ClaimedByReplica => "[B-OM-017]",
+            OrderMonitorErr::DryRun => "[B-OM-018]",
+            OrderMonitorErr::Draining => "[B-OM-019]",
+            OrderMonitorErr::Standby => "[B-OM-020]",
             OrderMonitorErr::UnexpectedError(_) => "[B-OM-500]",
         }
     }
@@ -134,6 +193,11 @@ struct OrderMonitorConfig {
     batch_buffer_time_secs: u64,
     order_commitment_priority: OrderCommitmentPriority,
     priority_addresses: Option<Vec<Address>>,
+    requestor_tiers: Vec<RequestorTierConf>,
+    speculative_prove_window_secs: u64,
+    speculative_prove_min_miss_percent: u64,
+    order_tags: Vec<OrderTagConf>,
+    duty_cycle: Option<DutyCycleConf>,
 }
 
 #[derive(Clone)]
@@ -142,6 +206,41 @@ pub struct RpcRetryConfig {
     pub retry_sleep_ms: u64,
 }
 
+/// Prover that orders are locked on behalf of, with a lock signature, when the transaction sender
+/// is a different address.
+#[derive(Clone)]
+struct DelegatedProver {
+    signer: PrivateKeySigner,
+    chain_id: u64,
+}
+
+/// Request to lock a priced order immediately, ignoring its target timestamp.
+pub(crate) struct ManualLockRequest {
+    pub order_id: String,
+    /// Receives the lock price on success.
+    pub reply: oneshot::Sender<Result<U256, OrderMonitorErr>>,
+}
+
+/// Priced orders waiting in the order monitor to be locked and/or proven.
+#[derive(Clone)]
+pub(crate) struct PricedOrders {
+    lock_and_prove: Arc<Cache<String, Arc<OrderRequest>>>,
+    prove: Arc<Cache<String, Arc<OrderRequest>>>,
+}
+
+impl PricedOrders {
+    pub(crate) fn list(&self) -> Vec<Arc<OrderRequest>> {
+        self.lock_and_prove.iter().chain(self.prove.iter()).map(|(_, order)| order).collect()
+    }
+
+    pub(crate) async fn get(&self, order_id: &str) -> Option<Arc<OrderRequest>> {
+        match self.lock_and_prove.get(order_id).await {
+            Some(order) => Some(order),
+            None => self.prove.get(order_id).await,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct OrderMonitor<P> {
     db: DbObj,
@@ -151,11 +250,23 @@ pub struct OrderMonitor<P> {
     market: BoundlessMarketService<Arc<P>>,
     provider: Arc<P>,
     prover_addr: Address,
+    delegated_prover: Option<DelegatedProver>,
     priced_order_rx: Arc<Mutex<mpsc::Receiver<Box<OrderRequest>>>>,
+    manual_lock_rx: Option<Arc<Mutex<mpsc::Receiver<ManualLockRequest>>>>,
     lock_and_prove_cache: Arc<Cache<String, Arc<OrderRequest>>>,
     prove_cache: Arc<Cache<String, Arc<OrderRequest>>>,
-    supported_selectors: SupportedSelectors,
+    selectors: SelectorRegistry,
     rpc_retry_config: RpcRetryConfig,
+    market_stats: MarketStatsObj,
+    metrics: MetricsObj,
+    events: EventBus,
+    capacity_tracker: ProvingCapacityTrackerObj,
+    self_throttle: SelfThrottleObj,
+    drain_mode: DrainModeObj,
+    leadership: LeadershipObj,
+    reservations: ReservationsObj,
+    order_claims: Option<OrderClaimsObj>,
+    dry_run: bool,
 }
 
 impl<P> OrderMonitor<P>
@@ -174,20 +285,14 @@ where
         priced_orders_rx: mpsc::Receiver<Box<OrderRequest>>,
         stake_token_decimals: u8,
         rpc_retry_config: RpcRetryConfig,
+        capacity_tracker: ProvingCapacityTrackerObj,
     ) -> Result<Self> {
-        let txn_timeout_opt = {
-            let config = config.lock_all().context("Failed to read config")?;
-            config.batcher.txn_timeout
-        };
-
         let mut market = BoundlessMarketService::new(
             market_addr,
             provider.clone(),
             provider.default_signer_address(),
-        );
-        if let Some(txn_timeout) = txn_timeout_opt {
-            market = market.with_timeout(Duration::from_secs(txn_timeout));
-        }
+        )
+        .with_tx_submitter(utils::tx_submitter(&config)?);
         {
             let config = config.lock_all()?;
 
@@ -212,15 +317,297 @@ where
             market,
             provider,
             prover_addr,
+            delegated_prover: None,
             priced_order_rx: Arc::new(Mutex::new(priced_orders_rx)),
+            manual_lock_rx: None,
             lock_and_prove_cache: Arc::new(Cache::builder().expire_after(OrderExpiry).build()),
             prove_cache: Arc::new(Cache::builder().expire_after(OrderExpiry).build()),
-            supported_selectors: SupportedSelectors::default(),
+            selectors: SelectorRegistry::default(),
             rpc_retry_config,
+            market_stats: Default::default(),
+            metrics: Default::default(),
+            events: Default::default(),
+            capacity_tracker,
+            self_throttle: Default::default(),
+            drain_mode: Default::default(),
+            leadership: Default::default(),
+            reservations: Default::default(),
+            order_claims: None,
+            dry_run: false,
         };
         Ok(monitor)
     }
 
+    /// Lock orders on behalf of the given prover, instead of the transaction sender.
+    ///
+    /// Lock transactions are sent by the provider's signer, with a lock signature from the
+    /// delegated prover. The stake is taken from, and the order is attributed to, the prover.
+    pub fn with_delegated_prover(self, signer: PrivateKeySigner, chain_id: u64) -> Self {
+        Self {
+            prover_addr: signer.address(),
+            delegated_prover: Some(DelegatedProver { signer, chain_id }),
+            ..self
+        }
+    }
+
+    /// Accept manual lock requests, e.g. from the admin API.
+    pub(crate) fn with_manual_lock_rx(self, rx: mpsc::Receiver<ManualLockRequest>) -> Self {
+        Self { manual_lock_rx: Some(Arc::new(Mutex::new(rx))), ..self }
+    }
+
+    /// Send lock transactions with the given submitter, e.g. shared with the submitter service.
+    pub(crate) fn with_tx_submitter(self, tx_submitter: TxSubmitter) -> Self {
+        Self { market: self.market.with_tx_submitter(tx_submitter), ..self }
+    }
+
+    /// Share market statistics with other services, e.g. the market monitor.
+    pub(crate) fn with_market_stats(self, market_stats: MarketStatsObj) -> Self {
+        Self { market_stats, ..self }
+    }
+
+    /// Record the duty cycle in the given metrics.
+    pub(crate) fn with_metrics(self, metrics: MetricsObj) -> Self {
+        Self { metrics, ..self }
+    }
+
+    /// Publish locked orders to the given event bus.
+    pub(crate) fn with_events(self, events: EventBus) -> Self {
+        Self { events, ..self }
+    }
+
+    /// Record lock outcomes in the given self-throttle, and reduce concurrent proofs by its level.
+    pub(crate) fn with_self_throttle(self, self_throttle: SelfThrottleObj) -> Self {
+        Self { self_throttle, ..self }
+    }
+
+    /// Commit to no new orders while the broker is draining.
+    pub(crate) fn with_drain_mode(self, drain_mode: DrainModeObj) -> Self {
+        Self { drain_mode, ..self }
+    }
+
+    /// Commit to no new orders while on standby, until this replica acquires the leadership lease.
+    pub(crate) fn with_leadership(self, leadership: LeadershipObj) -> Self {
+        Self { leadership, ..self }
+    }
+
+    /// Confirm or abort the reservations of priced orders, made by the order picker, as orders are
+    /// committed or dropped.
+    pub(crate) fn with_reservations(self, reservations: ReservationsObj) -> Self {
+        Self { reservations, ..self }
+    }
+
+    /// Extend the claims on requests before locking them, skipping those taken over by other
+    /// broker replicas.
+    pub(crate) fn with_order_claims(self, order_claims: OrderClaimsObj) -> Self {
+        Self { order_claims: Some(order_claims), ..self }
+    }
+
+    /// Never lock or prove orders, regardless of the `market.dry_run` config.
+    pub(crate) fn with_dry_run(self, dry_run: bool) -> Self {
+        Self { dry_run: self.dry_run || dry_run, ..self }
+    }
+
+    fn is_dry_run(&self) -> Result<bool, OrderMonitorErr> {
+        if self.dry_run {
+            return Ok(true);
+        }
+        Ok(self.config.lock_all().context("Failed to read config")?.market.dry_run)
+    }
+
+    /// Priced orders waiting to be locked and/or proven, e.g. to list them in the admin API.
+    pub(crate) fn priced_orders(&self) -> PricedOrders {
+        PricedOrders {
+            lock_and_prove: self.lock_and_prove_cache.clone(),
+            prove: self.prove_cache.clone(),
+        }
+    }
+
+    /// Priority gas to add to the network fee estimate for the lock transaction.
+    ///
+    /// This is the configured `lockin_priority_gas`, plus the amount by which the priority fee
+    /// projected by the gas oracle over the lock window exceeds the network estimate.
+    async fn lock_priority_gas(
+        &self,
+        order: &OrderRequest,
+        conf_priority_gas: Option<u64>,
+    ) -> Result<Option<u64>, OrderMonitorErr> {
+        let lock_window = order.request.lock_expires_at().saturating_sub(now_timestamp());
+        let projected = self
+            .chain_monitor
+            .projected_gas_fees(lock_window)
+            .await
+            .context("Failed to project gas fees")?;
+        if projected.max_priority_fee_per_gas == 0 {
+            return Ok(conf_priority_gas);
+        }
+        let estimate = self
+            .provider
+            .estimate_eip1559_fees()
+            .await
+            .map_err(|err| OrderMonitorErr::RpcErr(err.into()))?;
+        let extra_priority_fee =
+            projected.max_priority_fee_per_gas.saturating_sub(estimate.max_priority_fee_per_gas);
+        if extra_priority_fee == 0 {
+            return Ok(conf_priority_gas);
+        }
+        tracing::debug!(
+            "Adding {extra_priority_fee} wei to the priority fee for locking request 0x{:x}",
+            order.request.id
+        );
+        Ok(Some(
+            conf_priority_gas
+                .unwrap_or_default()
+                .saturating_add(extra_priority_fee.try_into().unwrap_or(u64::MAX)),
+        ))
+    }
+
+    /// Handle a lock transaction of another prover for the order pending in the mempool, as
+    /// configured in `lock_race_watch`.
+    ///
+    /// Returns the priority gas to lock with, bumped above the competing transaction if needed,
+    /// or an error if the lock should be abandoned.
+    async fn outbid_competing_lock(
+        &self,
+        order: &OrderRequest,
+        priority_gas: Option<u64>,
+        conf: Option<LockRaceWatchConf>,
+    ) -> Result<Option<u64>, OrderMonitorErr> {
+        let Some(conf) = conf else {
+            return Ok(priority_gas);
+        };
+        let Some(competing) = self.chain_monitor.competing_lock(order.request.id) else {
+            return Ok(priority_gas);
+        };
+        if conf.action == LockRaceAction::Cancel {
+            tracing::info!(
+                "Lock of request 0x{:x} by {} pending in the mempool, not locking",
+                order.request.id,
+                competing.sender
+            );
+            return Err(OrderMonitorErr::CompetingLock(competing.sender));
+        }
+
+        let estimate = self
+            .provider
+            .estimate_eip1559_fees()
+            .await
+            .map_err(|err| OrderMonitorErr::RpcErr(err.into()))?;
+        let target_fee =
+            competing.priority_fee_per_gas.saturating_mul(100 + conf.bump_percent as u128) / 100;
+        let required_gas: u64 = target_fee
+            .saturating_sub(estimate.max_priority_fee_per_gas)
+            .try_into()
+            .unwrap_or(u64::MAX);
+        let bumped = priority_gas.unwrap_or_default().max(required_gas);
+        if conf.max_priority_gas.is_some_and(|max| bumped > max) {
+            tracing::info!(
+                "Lock of request 0x{:x} by {} pending in the mempool, outbidding it requires {bumped} wei of priority gas, above the max of {}; not locking",
+                order.request.id,
+                competing.sender,
+                conf.max_priority_gas.unwrap_or_default()
+            );
+            return Err(OrderMonitorErr::CompetingLock(competing.sender));
+        }
+        tracing::info!(
+            "Lock of request 0x{:x} by {} pending in the mempool, bumping priority gas to {bumped} wei",
+            order.request.id,
+            competing.sender
+        );
+        Ok(Some(bumped))
+    }
+
+    /// Send the lock transaction, returning its receipt.
+    async fn send_lock_tx(
+        &self,
+        order: &OrderRequest,
+        priority_gas: Option<u64>,
+    ) -> Result<TransactionReceipt, MarketError> {
+        let Some(delegated) = &self.delegated_prover else {
+            return self
+                .market
+                .lock_request_receipt(&order.request, order.client_sig.clone(), priority_gas)
+                .await;
+        };
+
+        let prover_sig = order
+            .request
+            .sign_lock_request(
+                &delegated.signer,
+                *self.market.instance().address(),
+                delegated.chain_id,
+            )
+            .await
+            .context("Failed to sign lock request")?;
+        self.market
+            .lock_request_with_signature_receipt(
+                &order.request,
+                order.client_sig.clone(),
+                prover_sig.as_bytes(),
+                priority_gas.map(u128::from),
+            )
+            .await
+    }
+
+    /// Fail if the cost to lock and fulfill the order at the current gas price, added to the gas
+    /// it already spent, would exceed its gas budget.
+    async fn check_gas_budget(&self, order: &OrderRequest) -> Result<(), OrderMonitorErr> {
+        let price = order
+            .request
+            .offer
+            .price_at(now_timestamp())
+            .context("Failed to calculate order price")?;
+        let Some(budget) =
+            self.config.lock_all().context("Failed to lock config")?.market.gas_budget(price)
+        else {
+            return Ok(());
+        };
+
+        let gas_price = self
+            .provider
+            .get_gas_price()
+            .await
+            .map_err(|err| OrderMonitorErr::RpcErr(err.into()))?;
+        let estimate = self.calculate_order_gas_cost_wei(order, gas_price).await?;
+        let spent = self
+            .db
+            .get_order_gas_records(&order.id())
+            .await
+            .context("Failed to get order gas records")?
+            .iter()
+            .fold(U256::ZERO, |spent, record| spent + record.gas_cost);
+        let cost = spent + estimate;
+        if cost > budget {
+            return Err(OrderMonitorErr::GasBudgetExceeded(cost, budget));
+        }
+        Ok(())
+    }
+
+    /// Record the gas spent by the lock transaction of the order, including reverted ones.
+    async fn record_lock_gas(
+        &self,
+        order: &OrderRequest,
+        lock_res: &Result<TransactionReceipt, MarketError>,
+    ) {
+        let receipt = match lock_res {
+            Ok(receipt) => receipt.clone(),
+            Err(MarketError::LockRevert(tx_hash)) => {
+                match self.provider.get_transaction_receipt(*tx_hash).await {
+                    Ok(Some(receipt)) => receipt,
+                    Ok(None) => return,
+                    Err(err) => {
+                        tracing::warn!("Failed to get receipt of reverted lock {tx_hash}: {err}");
+                        return;
+                    }
+                }
+            }
+            Err(_) => return,
+        };
+        let record = OrderGasRecord::new(&order.id(), GasTxKind::Lock, &receipt, 1);
+        if let Err(err) = self.db.add_order_gas_records(&[record]).await {
+            tracing::warn!("Failed to record lock gas of order {}: {err}", order.id());
+        }
+    }
+
     async fn lock_order(&self, order: &OrderRequest) -> Result<U256, OrderMonitorErr> {
         let request_id = order.request.id;
 
@@ -246,21 +633,47 @@ where
             return Err(OrderMonitorErr::AlreadyLocked);
         }
 
-        let conf_priority_gas = {
+        // The claim taken when pricing may have expired and been taken over since
+        if let Some(order_claims) = &self.order_claims {
+            match order_claims.claim(request_id).await {
+                Ok(true) => {}
+                Ok(false) => return Err(OrderMonitorErr::ClaimedByReplica),
+                Err(err) => {
+                    tracing::warn!("Failed to claim request 0x{request_id:x}: {err}");
+                }
+            }
+        }
+
+        let (conf_priority_gas, lock_race_watch, lock_confirmations) = {
             let conf = self.config.lock_all().context("Failed to lock config")?;
-            conf.market.lockin_priority_gas
+            (
+                conf.market.lockin_priority_gas,
+                conf.market.lock_race_watch.clone(),
+                conf.market.confirmations.as_ref().map_or(1, |conf| conf.lock),
+            )
         };
+        let priority_gas = self.lock_priority_gas(order, conf_priority_gas).await?;
+        let priority_gas =
+            match self.outbid_competing_lock(order, priority_gas, lock_race_watch).await {
+                Err(err @ OrderMonitorErr::CompetingLock(_)) => {
+                    // Lost the race, without paying for a reverted lock transaction
+                    self.self_throttle.record(PipelineOutcome::LockLost, now_timestamp());
+                    return Err(err);
+                }
+                res => res?,
+            };
+
+        self.check_gas_budget(order).await?;
 
         tracing::info!(
             "Locking request: 0x{:x} for stake: {}",
             request_id,
             order.request.offer.lockStake
         );
-        let lock_block = self
-            .market
-            .lock_request(&order.request, order.client_sig.clone(), conf_priority_gas)
-            .await
-            .map_err(|e| -> OrderMonitorErr {
+        let lock_res = self.send_lock_tx(order, priority_gas).await;
+        self.record_lock_gas(order, &lock_res).await;
+        let lock_res =
+            lock_res.map(|receipt| receipt.block_number).map_err(|e| -> OrderMonitorErr {
                 match e {
                     MarketError::TxnError(txn_err) => match txn_err {
                         TxnErr::BoundlessMarketErr(IBoundlessMarketErrors::RequestIsLocked(_)) => {
@@ -292,9 +705,7 @@ where
                             if e.to_string().to_lowercase().contains(&prover_addr_str) {
                                 OrderMonitorErr::InsufficientBalance
                             } else {
-                                OrderMonitorErr::LockTxFailed(format!(
-                                    "Requestor has insufficient balance at lock time: {e}"
-                                ))
+                                OrderMonitorErr::RequestorInsufficientBalance(e.to_string())
                             }
                         } else if e.to_string().contains("RequestIsLocked") {
                             OrderMonitorErr::AlreadyLocked
@@ -310,7 +721,49 @@ where
                         }
                     }
                 }
-            })?;
+            });
+        let client_event = match &lock_res {
+            Ok(_) => Some(ClientEvent::Locked),
+            Err(OrderMonitorErr::RequestorInsufficientBalance(_)) => {
+                Some(ClientEvent::PaymentFailed)
+            }
+            Err(_) => None,
+        };
+        let pipeline_outcome = match &lock_res {
+            Ok(_) => Some(PipelineOutcome::LockWon),
+            Err(OrderMonitorErr::AlreadyLocked | OrderMonitorErr::LockTxFailed(_)) => {
+                Some(PipelineOutcome::LockLost)
+            }
+            Err(_) => None,
+        };
+        if let Some(pipeline_outcome) = pipeline_outcome {
+            self.self_throttle.record(pipeline_outcome, now_timestamp());
+        }
+        if let Some(client_event) = client_event {
+            if let Err(err) =
+                self.db.record_client_event(order.request.client_address(), client_event).await
+            {
+                tracing::warn!(
+                    "Failed to record {client_event:?} of order {} in client stats: {err}",
+                    order.id()
+                );
+            }
+        }
+        let lock_block = lock_res?.context("Lock receipt missing block number")?;
+
+        if lock_confirmations > 1 {
+            tracing::debug!(
+                "Waiting for {lock_confirmations} confirmations of the lock of request 0x{request_id:x} in block {lock_block}"
+            );
+            // The lock was already sent, failing to confirm it must not drop the order.
+            if let Err(err) =
+                self.chain_monitor.wait_for_confirmations(lock_block, lock_confirmations).await
+            {
+                tracing::warn!(
+                    "Failed to wait for confirmations of the lock of request 0x{request_id:x}: {err:?}"
+                );
+            }
+        }
 
         // Fetch the block to retrieve the lock timestamp. This has been observed to return
         // inconsistent state between the receipt being available but the block not yet.
@@ -337,6 +790,11 @@ where
             .offer
             .price_at(lock_timestamp)
             .context("Failed to calculate lock price")?;
+        self.events.publish(BrokerEvent::OrderLocked {
+            order_id: order.id(),
+            request_id,
+            lock_price,
+        });
 
         Ok(lock_price)
     }
@@ -390,11 +848,13 @@ where
         }
     }
 
-    /// Helper method to skip an order in the database and invalidate the appropriate cache
-    async fn skip_order(&self, order: &OrderRequest, reason: &str) {
-        if let Err(e) = self.db.insert_skipped_request(order).await {
+    /// Helper method to skip an order in the database, abort its reservation and invalidate the
+    /// appropriate cache
+    async fn skip_order(&self, order: &OrderRequest, reason: SkipReason) {
+        if let Err(e) = self.db.insert_skipped_request(order, reason).await {
             tracing::error!("Failed to skip order ({}): {} - {e:?}", reason, order.id());
         }
+        self.reservations.abort(&order.id());
 
         match order.fulfillment_type {
             FulfillmentType::LockAndFulfill => {
@@ -429,6 +889,19 @@ where
             }
         }
 
+        // When set, the deadline margin policy replaces min_deadline, scaled by the order cycles
+        let (deadline_margin, prove_khz) = {
+            let config = self.config.lock_all().context("Failed to read config")?;
+            (
+                config.market.deadline_margin.clone(),
+                self.capacity_tracker.prove_khz(config.market.peak_prove_khz),
+            )
+        };
+        let min_deadline_for = |order: &OrderRequest| match &deadline_margin {
+            Some(conf) => conf.margin_secs(order.total_cycles, prove_khz),
+            None => min_deadline,
+        };
+
         fn is_target_time_reached(order: &OrderRequest, current_block_timestamp: u64) -> bool {
             // Note: this could use current timestamp, but avoiding cases where clock has drifted.
             match order.target_timestamp {
@@ -454,6 +927,11 @@ where
             }
         }
 
+        let lock_expired_strategy: LockExpiredStrategyObj = {
+            let config = self.config.lock_all().context("Failed to read config")?;
+            config.market.lock_expired_strategy.into()
+        };
+
         for (_, order) in self.prove_cache.iter() {
             let is_fulfilled = self
                 .db
@@ -465,12 +943,31 @@ where
                     "Request 0x{:x} was locked by another prover and was fulfilled. Skipping.",
                     order.request.id
                 );
-                self.skip_order(&order, "was fulfilled by other").await;
-            } else if !is_within_deadline(&order, current_block_timestamp, min_deadline) {
-                self.skip_order(&order, "expired").await;
+                self.skip_order(&order, SkipReason::AlreadyFulfilled).await;
+            } else if !is_within_deadline(&order, current_block_timestamp, min_deadline_for(&order))
+            {
+                self.skip_order(&order, SkipReason::Expired).await;
             } else if is_target_time_reached(&order, current_block_timestamp) {
-                tracing::info!("Request 0x{:x} was locked by another prover but expired unfulfilled, setting status to pending proving", order.request.id);
-                candidate_orders.push(order);
+                let gas_price = self
+                    .chain_monitor
+                    .current_gas_price()
+                    .await
+                    .context("Failed to get gas price")?;
+                let ctx = LockExpiredContext {
+                    block_timestamp: current_block_timestamp,
+                    gas_price,
+                    stats: &self.market_stats,
+                };
+                match lock_expired_strategy.decide(&order, &ctx) {
+                    LockExpiredAction::Fulfill => {
+                        tracing::info!("Request 0x{:x} was locked by another prover but expired unfulfilled, setting status to pending proving", order.request.id);
+                        candidate_orders.push(order);
+                    }
+                    LockExpiredAction::Wait => {}
+                    LockExpiredAction::Skip => {
+                        self.skip_order(&order, SkipReason::LockExpiredStrategy).await;
+                    }
+                }
             }
         }
 
@@ -478,11 +975,11 @@ where
             let is_lock_expired = order.request.lock_expires_at() < current_block_timestamp;
             if is_lock_expired {
                 tracing::debug!("Request {:x} was scheduled to be locked by us, but its lock has now expired. Skipping.", order.request.id);
-                self.skip_order(&order, "lock expired before we locked").await;
+                self.skip_order(&order, SkipReason::LockExpired).await;
             } else if let Some((locker, _)) =
                 self.db.get_request_locked(U256::from(order.request.id)).await?
             {
-                let our_address = self.provider.default_signer_address().to_string().to_lowercase();
+                let our_address = self.prover_addr.to_string().to_lowercase();
                 let locker_address = locker.to_lowercase();
                 // Compare normalized addresses (lowercase without 0x prefix)
                 let our_address_normalized = our_address.trim_start_matches("0x");
@@ -490,14 +987,15 @@ where
 
                 if locker_address_normalized != our_address_normalized {
                     tracing::debug!("Request 0x{:x} was scheduled to be locked by us ({}), but is already locked by another prover ({}). Skipping.", order.request.id, our_address, locker_address);
-                    self.skip_order(&order, "locked by another prover").await;
+                    self.skip_order(&order, SkipReason::AlreadyLocked).await;
                 } else {
                     // Edge case where we locked the order, but due to some reason was not moved to proving state. Should not happen.
                     tracing::debug!("Request 0x{:x} was scheduled to be locked by us, but is already locked by us. Proceeding to prove.", order.request.id);
                     candidate_orders.push(order);
                 }
-            } else if !is_within_deadline(&order, current_block_timestamp, min_deadline) {
-                self.skip_order(&order, "insufficient deadline").await;
+            } else if !is_within_deadline(&order, current_block_timestamp, min_deadline_for(&order))
+            {
+                self.skip_order(&order, SkipReason::InsufficientDeadline).await;
             } else if is_target_time_reached(&order, current_block_timestamp) {
                 candidate_orders.push(order);
             }
@@ -520,53 +1018,174 @@ where
         Ok(candidate_orders)
     }
 
+    /// Returns orders locked by other provers that can be proven speculatively while idle.
+    ///
+    /// Only orders whose lock expires within `window_secs` are considered, and only when there
+    /// are no committed orders. Of those, only the orders of lockers that missed at least
+    /// `min_miss_percent` of their indexed locks are likely to become available, and are proven.
+    /// The proving service holds the resulting proof until the lock has expired, and drops it if
+    /// the original locker fulfills the request in the meantime.
+    async fn get_speculative_orders(
+        &self,
+        current_block_timestamp: u64,
+        window_secs: u64,
+        min_miss_percent: u64,
+    ) -> Result<Vec<Arc<OrderRequest>>> {
+        if window_secs == 0 {
+            return Ok(Vec::new());
+        }
+
+        let committed_orders = self.db.get_committed_orders().await?;
+        if !committed_orders.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let candidate_orders: Vec<Arc<OrderRequest>> = self
+            .prove_cache
+            .iter()
+            .map(|(_, order)| order)
+            .filter(|order| {
+                order.fulfillment_type == FulfillmentType::FulfillAfterLockExpire
+                    && order.request.lock_expires_at() >= current_block_timestamp
+                    && order.request.lock_expires_at() - current_block_timestamp <= window_secs
+            })
+            .collect();
+
+        let mut likely_orders = Vec::with_capacity(candidate_orders.len());
+        for order in candidate_orders {
+            let request_id = U256::from(order.request.id);
+            let Some(locker) = self
+                .db
+                .get_request_locked(request_id)
+                .await?
+                .and_then(|(locker, _)| locker.parse::<Address>().ok())
+            else {
+                continue;
+            };
+            let stats = self.db.get_locker_stats(locker).await?;
+            match stats.miss_percent(MIN_LOCKER_SETTLED_LOCKS) {
+                Some(miss_percent) if miss_percent >= min_miss_percent => likely_orders.push(order),
+                miss_percent => tracing::trace!(
+                    "Not proving request 0x{request_id:x} speculatively, its locker {locker} missed {miss_percent:?}% of its locks"
+                ),
+            }
+        }
+        let candidate_orders = likely_orders;
+
+        if !candidate_orders.is_empty() {
+            tracing::info!(
+                "Prover is idle, speculatively proving {} orders before their lock expires: {}",
+                candidate_orders.len(),
+                candidate_orders.iter().map(|order| order.id()).collect::<Vec<_>>().join(", ")
+            );
+        }
+
+        Ok(candidate_orders)
+    }
+
+    /// Lock a LockAndFulfill order and record the outcome in the DB, removing it from the cache.
+    ///
+    /// The reservation of the order is confirmed once locked, as the locked stake and the gas of
+    /// committed orders are accounted for by the order picker from then on, and aborted otherwise.
+    async fn lock_and_commit_order(&self, order: &OrderRequest) -> Result<U256, OrderMonitorErr> {
+        let order_id = order.id();
+        let request_id = order.request.id;
+        let res = self.lock_order(order).await;
+        match res {
+            Ok(lock_price) => {
+                tracing::info!("Locked request: 0x{:x}", request_id);
+                if let Err(err) = self.db.insert_accepted_request(order, lock_price).await {
+                    tracing::error!(
+                        "FATAL STAKE AT RISK: {} failed to move from locking -> proving status {}",
+                        order_id,
+                        err
+                    );
+                }
+                self.reservations.confirm(&order_id);
+            }
+            Err(ref err) => {
+                match err {
+                    OrderMonitorErr::UnexpectedError(inner) => {
+                        tracing::error!(
+                            "Failed to lock order: {order_id} - {} - {inner:?}",
+                            err.code()
+                        );
+                    }
+                    _ => {
+                        tracing::warn!(
+                            "Soft failed to lock request: {order_id} - {} - {err:?}",
+                            err.code()
+                        );
+                    }
+                }
+                if let Err(err) =
+                    self.db.insert_skipped_request(order, SkipReason::LockFailed).await
+                {
+                    tracing::error!(
+                        "Failed to set DB failure state for order: {order_id} - {err:?}"
+                    );
+                }
+                self.reservations.abort(&order_id);
+            }
+        }
+        self.lock_and_prove_cache.invalidate(&order_id).await;
+        res
+    }
+
+    /// Lock a priced order now, without waiting for its target timestamp.
+    ///
+    /// The order must still be pending lock, and the same on-chain and DB checks as automated
+    /// locking apply before the lock transaction is sent. Capacity limits are not applied.
+    async fn manual_lock(&self, order_id: &str) -> Result<U256, OrderMonitorErr> {
+        let order = self
+            .lock_and_prove_cache
+            .get(order_id)
+            .await
+            .ok_or_else(|| OrderMonitorErr::UnknownOrder(order_id.to_string()))?;
+
+        if self.drain_mode.is_draining() {
+            return Err(OrderMonitorErr::Draining);
+        }
+        if !self.leadership.is_leader() {
+            return Err(OrderMonitorErr::Standby);
+        }
+        if self.is_dry_run()? {
+            return Err(OrderMonitorErr::DryRun);
+        }
+
+        let ChainHead { block_timestamp, .. } = self.chain_monitor.current_chain_head().await?;
+        if order.request.lock_expires_at() <= block_timestamp {
+            self.skip_order(&order, SkipReason::LockExpired).await;
+            return Err(OrderMonitorErr::LockExpired);
+        }
+
+        tracing::info!(
+            "Manually locking order {order_id}, ignoring target timestamp {:?}",
+            order.target_timestamp
+        );
+        self.lock_and_commit_order(&order).await
+    }
+
     async fn lock_and_prove_orders(&self, orders: &[Arc<OrderRequest>]) -> Result<()> {
         let lock_jobs = orders.iter().map(|order| {
             async move {
                 let order_id = order.id();
                 if order.fulfillment_type == FulfillmentType::LockAndFulfill {
-                    let request_id = order.request.id;
-                    match self.lock_order(order).await {
-                        Ok(lock_price) => {
-                            tracing::info!("Locked request: 0x{:x}", request_id);
-                            if let Err(err) = self.db.insert_accepted_request(order, lock_price).await {
-                                tracing::error!(
-                                    "FATAL STAKE AT RISK: {} failed to move from locking -> proving status {}",
-                                    order_id,
-                                    err
-                                );
-                            }
+                    // Failures are logged and recorded in the DB.
+                    let _ = self.lock_and_commit_order(order).await;
+                } else {
+                    match self.db.insert_accepted_request(order, U256::ZERO).await {
+                        Ok(_) => {
+                            self.reservations.confirm(&order_id);
                         }
-                        Err(ref err) => {
-                            match err {
-                                OrderMonitorErr::UnexpectedError(inner) => {
-                                    tracing::error!(
-                                        "Failed to lock order: {order_id} - {} - {inner:?}",
-                                        err.code()
-                                    );
-                                }
-                                _ => {
-                                    tracing::warn!(
-                                        "Soft failed to lock request: {order_id} - {} - {err:?}",
-                                        err.code()
-                                    );
-                                }
-                            }
-                            if let Err(err) = self.db.insert_skipped_request(order).await {
-                                tracing::error!(
-                                    "Failed to set DB failure state for order: {order_id} - {err:?}"
-                                );
-                            }
+                        Err(err) => {
+                            tracing::error!(
+                                "Failed to set order status to pending proving: {} - {err:?}",
+                                order_id
+                            );
+                            self.reservations.abort(&order_id);
                         }
                     }
-                    self.lock_and_prove_cache.invalidate(&order_id).await;
-                } else {
-                    if let Err(err) = self.db.insert_accepted_request(order, U256::ZERO).await {
-                        tracing::error!(
-                            "Failed to set order status to pending proving: {} - {err:?}",
-                            order_id
-                        );
-                    }
                     self.prove_cache.invalidate(&order_id).await;
                 }
             }
@@ -587,22 +1206,14 @@ where
         let order_gas_units = if order.fulfillment_type == FulfillmentType::LockAndFulfill {
             U256::from(utils::estimate_gas_to_lock(&self.config, order).await?).saturating_add(
                 U256::from(
-                    utils::estimate_gas_to_fulfill(
-                        &self.config,
-                        &self.supported_selectors,
-                        &order.request,
-                    )
-                    .await?,
+                    utils::estimate_gas_to_fulfill(&self.config, &self.selectors, &order.request)
+                        .await?,
                 ),
             )
         } else {
             U256::from(
-                utils::estimate_gas_to_fulfill(
-                    &self.config,
-                    &self.supported_selectors,
-                    &order.request,
-                )
-                .await?,
+                utils::estimate_gas_to_fulfill(&self.config, &self.selectors, &order.request)
+                    .await?,
             )
         };
 
@@ -611,6 +1222,47 @@ where
         Ok(order_cost_wei)
     }
 
+    async fn apply_duty_cycle_limits(
+        &self,
+        orders: Vec<Arc<OrderRequest>>,
+        duty_cycle_conf: &DutyCycleConf,
+        config: &OrderMonitorConfig,
+    ) -> Result<Vec<Arc<OrderRequest>>> {
+        let Some(prove_khz) = self.capacity_tracker.prove_khz(config.peak_prove_khz) else {
+            tracing::warn!("Duty cycle limit requires peak_prove_khz to be set until proofs have completed, not applying it");
+            return Ok(orders);
+        };
+        let now = now_timestamp();
+        let busy_secs = self
+            .capacity_tracker
+            .busy_secs(now.saturating_sub(duty_cycle_conf.window_secs))
+            .await?;
+        let queued_secs =
+            self.capacity_tracker.queue_secs(prove_khz, config.additional_proof_cycles).await?;
+
+        let num_orders = orders.len();
+        let (orders, duty_cycle) = duty_cycle::apply_duty_cycle_limits(
+            orders,
+            duty_cycle_conf,
+            busy_secs,
+            queued_secs,
+            prove_khz,
+            config.additional_proof_cycles,
+            config.batch_buffer_time_secs,
+            now,
+        );
+        self.metrics.record_duty_cycle(duty_cycle);
+        if orders.len() < num_orders {
+            tracing::info!(
+                "Deferred {} orders to stay within the duty cycle limit, actual duty cycle {:.0}%, projected {:.0}%",
+                num_orders - orders.len(),
+                duty_cycle.actual * 100.0,
+                duty_cycle.projected * 100.0,
+            );
+        }
+        Ok(orders)
+    }
+
     async fn apply_capacity_limits(
         &self,
         orders: Vec<Arc<OrderRequest>>,
@@ -646,11 +1298,7 @@ where
         let committed_orders = self.db.get_committed_orders().await?;
         let committed_gas_units =
             futures::future::try_join_all(committed_orders.iter().map(|order| {
-                utils::estimate_gas_to_fulfill(
-                    &self.config,
-                    &self.supported_selectors,
-                    &order.request,
-                )
+                utils::estimate_gas_to_fulfill(&self.config, &self.selectors, &order.request)
             }))
             .await?
             .iter()
@@ -678,6 +1326,18 @@ where
             return Ok(Vec::new());
         }
 
+        // Defer orders of tags that are already at their concurrent proof limit
+        let orders =
+            order_tags::apply_tag_capacity_limits(orders, &committed_orders, &config.order_tags);
+
+        // Defer non-urgent orders that would exceed the duty cycle limit
+        let orders = match &config.duty_cycle {
+            Some(duty_cycle_conf) => {
+                self.apply_duty_cycle_limits(orders, duty_cycle_conf, config).await?
+            }
+            None => orders,
+        };
+
         // Calculate remaining balance after accounting for committed orders
         let mut remaining_balance_wei = available_balance_wei - committed_cost_wei;
 
@@ -731,7 +1391,7 @@ where
                         format_ether(order_cost_wei),
                         format_ether(remaining_balance_wei)
                     );
-                    self.skip_order(&order, "insufficient balance").await;
+                    self.skip_order(&order, SkipReason::InsufficientGas).await;
                     continue;
                 }
 
@@ -766,7 +1426,7 @@ where
                         );
                         // If the order cannot be completed regardless of other orders, skip it
                         // permanently. Otherwise, will retry including the order.
-                        self.skip_order(&order, "cannot be completed before expiration").await;
+                        self.skip_order(&order, SkipReason::InsufficientProvingTime).await;
                     } else {
                         tracing::debug!("Given current commited orders and capacity, order 0x{:x} cannot be completed before its expiration. Not skipping as capacity may free up before it expires.", order.request.id);
                     }
@@ -795,7 +1455,7 @@ where
                         format_ether(order_cost_wei),
                         format_ether(remaining_balance_wei)
                     );
-                    self.skip_order(&order, "insufficient balance").await;
+                    self.skip_order(&order, SkipReason::InsufficientGas).await;
                     continue;
                 }
 
@@ -838,6 +1498,10 @@ where
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
         let mut new_orders = self.priced_order_rx.lock().await;
+        let mut manual_locks = match &self.manual_lock_rx {
+            Some(rx) => Some(rx.lock().await),
+            None => None,
+        };
         let mut prev_orders_by_status = String::new();
 
         loop {
@@ -849,6 +1513,13 @@ where
                     self.handle_new_order_result(result).await?;
                 }
 
+                Some(request) = recv_manual_lock(&mut manual_locks) => {
+                    let res = self.manual_lock(&request.order_id).await;
+                    if request.reply.send(res).is_err() {
+                        tracing::warn!("Manual lock of {} finished after the requester went away", request.order_id);
+                    }
+                }
+
                 // On each interval, process all pending orders and do the block-based logic
                 _ = interval.tick() => {
                     let ChainHead { block_number, block_timestamp } =
@@ -862,22 +1533,43 @@ where
                             "Order monitor processing block {block_number} at timestamp {block_timestamp}"
                         );
 
+                        let gas_price =
+                            self.chain_monitor.current_gas_price().await.context("Failed to get gas price")?;
+                        self.market_stats.record_gas_price(gas_price);
+
                         let monitor_config = {
                             let config = self.config.lock_all().context("Failed to read config")?;
                             OrderMonitorConfig {
                                 min_deadline: config.market.min_deadline,
                                 peak_prove_khz: config.market.peak_prove_khz,
-                                max_concurrent_proofs: config.market.max_concurrent_proofs,
+                                max_concurrent_proofs: match &config.market.self_throttle {
+                                    Some(conf) => self.self_throttle.scale_max_concurrent_proofs(
+                                        conf,
+                                        config.market.max_concurrent_proofs,
+                                        block_timestamp,
+                                    ),
+                                    None => config.market.max_concurrent_proofs,
+                                },
                                 additional_proof_cycles: config.market.additional_proof_cycles,
                                 batch_buffer_time_secs: config.batcher.block_deadline_buffer_secs,
                                 order_commitment_priority: config.market.order_commitment_priority,
                                 priority_addresses: config.market.priority_requestor_addresses.clone(),
+                                requestor_tiers: config.market.requestor_tiers.clone(),
+                                speculative_prove_window_secs: config.market.speculative_prove_window_secs,
+                                speculative_prove_min_miss_percent: config.market.speculative_prove_min_miss_percent,
+                                order_tags: config.market.order_tags.clone(),
+                                duty_cycle: config.market.duty_cycle.clone(),
                             }
                         };
 
                         // Get orders that are valid and ready for locking/proving, skipping orders that are now invalid for proving, due to expiring, being locked by another prover, etc.
                         let mut valid_orders = self.get_valid_orders(block_timestamp, monitor_config.min_deadline).await?;
 
+                        // If nothing is ready, use idle capacity to get a head start on orders whose lock is about to expire.
+                        if valid_orders.is_empty() {
+                            valid_orders = self.get_speculative_orders(block_timestamp, monitor_config.speculative_prove_window_secs, monitor_config.speculative_prove_min_miss_percent).await?;
+                        }
+
                         if valid_orders.is_empty() {
                             tracing::trace!(
                                 "No orders to lock and/or prove as of block timestamp {}",
@@ -886,8 +1578,33 @@ where
                             continue;
                         }
 
+                        if self.drain_mode.is_draining() {
+                            tracing::debug!(
+                                "Draining, not committing to {} valid orders",
+                                valid_orders.len()
+                            );
+                            continue;
+                        }
+
+                        if !self.leadership.is_leader() {
+                            tracing::debug!(
+                                "On standby, not committing to {} valid orders",
+                                valid_orders.len()
+                            );
+                            continue;
+                        }
+
+                        // Drop the orders cached before the config switched to dry run.
+                        if self.is_dry_run()? {
+                            for order in valid_orders {
+                                tracing::info!("Dry run, not locking or proving order {}", order.id());
+                                self.skip_order(&order, SkipReason::DryRun).await;
+                            }
+                            continue;
+                        }
+
                         // Prioritize the orders that intend to fulfill based on configured commitment priority.
-                        valid_orders = self.prioritize_orders(valid_orders, monitor_config.order_commitment_priority, monitor_config.priority_addresses.as_deref());
+                        valid_orders = self.prioritize_orders(valid_orders, monitor_config.order_commitment_priority, monitor_config.priority_addresses.as_deref(), &monitor_config.requestor_tiers, &monitor_config.order_tags);
 
                         // Filter down the orders given our max concurrent proofs, peak khz limits, and gas limitations.
                         let final_orders = self
@@ -924,6 +1641,12 @@ where
         &self,
         order: Box<OrderRequest>,
     ) -> Result<(), OrderMonitorErr> {
+        if self.is_dry_run()? {
+            tracing::info!("Dry run, not locking or proving order {}", order.id());
+            self.skip_order(&order, SkipReason::DryRun).await;
+            return Ok(());
+        }
+
         match order.fulfillment_type {
             FulfillmentType::LockAndFulfill => {
                 // Note: this could be done without waiting for the batch to minimize latency, but
@@ -940,6 +1663,15 @@ where
     }
 }
 
+async fn recv_manual_lock(
+    rx: &mut Option<MutexGuard<'_, mpsc::Receiver<ManualLockRequest>>>,
+) -> Option<ManualLockRequest> {
+    match rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 impl<P> RetryTask for OrderMonitor<P>
 where
     P: Provider<Ethereum> + WalletProvider + 'static + Clone,
@@ -958,8 +1690,16 @@ where
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
-    use crate::OrderStatus;
-    use crate::{db::SqliteDb, now_timestamp, FulfillmentType};
+    use crate::{config::LockExpiredStrategyConf, reservations::Reserved, OrderStatus};
+    use crate::{
+        db::SqliteDb,
+        indexer::{MarketEvent, MarketIndexer},
+        leadership::Leadership,
+        now_timestamp,
+        provers::DefaultProver,
+        proving_capacity::ProvingCapacityTracker,
+        FulfillmentType,
+    };
     use alloy::node_bindings::AnvilInstance;
     use alloy::{
         network::EthereumWallet,
@@ -1057,12 +1797,15 @@ pub(crate) mod tests {
                 request,
                 image_id: None,
                 input_id: None,
+                assumption_ids: None,
                 expire_timestamp: None,
                 client_sig,
                 fulfillment_type,
                 boundless_market_address: self.market_address,
                 chain_id: self.anvil.chain_id(),
                 total_cycles: None,
+                provenance: None,
+                pricing_checkpoint: None,
             })
         }
     }
@@ -1135,6 +1878,7 @@ pub(crate) mod tests {
             priced_order_rx,
             stake_token_decimals,
             RpcRetryConfig { retry_count: 2, retry_sleep_ms: 500 },
+            Arc::new(ProvingCapacityTracker::new(db.clone(), Arc::new(DefaultProver::new()))),
         )
         .unwrap();
 
@@ -1203,6 +1947,111 @@ pub(crate) mod tests {
         .await;
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn lock_order_delegated_prover() {
+        let mut ctx = setup_om_test_context().await;
+        let delegated_signer: PrivateKeySigner = ctx.anvil.keys()[1].clone().into();
+        let delegated_addr = delegated_signer.address();
+
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        ctx.market_service.submit_request(&order.request, &ctx.signer).await.unwrap();
+
+        let monitor = ctx.monitor.with_delegated_prover(delegated_signer, ctx.anvil.chain_id());
+        assert_eq!(monitor.prover_addr, delegated_addr);
+        monitor.lock_order(&order).await.unwrap();
+
+        // The lock is sent by the wallet, but attributed to the delegated prover.
+        let logs = ctx
+            .market_service
+            .instance()
+            .RequestLocked_filter()
+            .from_block(0)
+            .query()
+            .await
+            .unwrap();
+        let (event, _) =
+            logs.iter().find(|(event, _)| event.requestId == order.request.id).unwrap();
+        assert_eq!(event.prover, delegated_addr);
+        assert_ne!(event.prover, ctx.signer.address());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn manual_lock_ignores_target_timestamp() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let mut order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order.target_timestamp = Some(current_timestamp + 100);
+        let order_id = order.id();
+        ctx.market_service.submit_request(&order.request, &ctx.signer).await.unwrap();
+        ctx.monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+
+        // Not picked up automatically until the target timestamp.
+        assert!(ctx.monitor.get_valid_orders(current_timestamp, 50).await.unwrap().is_empty());
+
+        ctx.monitor.manual_lock(&order_id).await.unwrap();
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::PendingProving);
+        assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_none());
+
+        // The order is no longer pending lock.
+        let err = ctx.monitor.manual_lock(&order_id).await.unwrap_err();
+        assert!(matches!(err, OrderMonitorErr::UnknownOrder(_)));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn dry_run_does_not_lock() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+        let lock_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let prove_order = ctx
+            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 100, 200)
+            .await;
+        let monitor = ctx.monitor.with_dry_run(true);
+
+        // Manual locks are refused
+        let order_id = lock_order.id();
+        monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(lock_order)).await;
+        let err = monitor.manual_lock(&order_id).await.unwrap_err();
+        assert!(matches!(err, OrderMonitorErr::DryRun));
+        assert!(ctx.db.get_order(&order_id).await.unwrap().is_none());
+
+        // Orders sent to the monitor, e.g. re-queued for evaluation, are skipped
+        let order_id = prove_order.id();
+        monitor.handle_new_order_result(prove_order).await.unwrap();
+        assert!(monitor.prove_cache.get(&order_id).await.is_none());
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+        assert_eq!(order.skip_reason, Some(SkipReason::DryRun));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn standby_does_not_lock() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+        let order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let order_id = order.id();
+        ctx.market_service.submit_request(&order.request, &ctx.signer).await.unwrap();
+        let monitor = ctx.monitor.with_leadership(Arc::new(Leadership::standby()));
+
+        monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+        let err = monitor.manual_lock(&order_id).await.unwrap_err();
+        assert!(matches!(err, OrderMonitorErr::Standby));
+        assert!(ctx.db.get_order(&order_id).await.unwrap().is_none());
+        assert!(monitor.lock_and_prove_cache.get(&order_id).await.is_some());
+    }
+
     // Capacity tests
     #[test]
     fn test_capacity_unlimited() {
@@ -1232,6 +2081,8 @@ pub(crate) mod tests {
             .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp - 100, 50, 50)
             .await;
         let expired_order_id = expired_order.id();
+        let reserved = Reserved { stake: U256::from(10), gas: U256::from(1) };
+        ctx.monitor.reservations.reserve(expired_order_id.clone(), 1, reserved, u64::MAX);
         ctx.monitor
             .lock_and_prove_cache
             .insert(expired_order_id.clone(), Arc::from(expired_order))
@@ -1243,6 +2094,8 @@ pub(crate) mod tests {
 
         let order = ctx.db.get_order(&expired_order_id).await.unwrap().unwrap();
         assert_eq!(order.status, OrderStatus::Skipped);
+        // The reservation of the skipped order is released
+        assert_eq!(ctx.monitor.reservations.reserved(1), Reserved::default());
     }
 
     #[tokio::test]
@@ -1322,6 +2175,119 @@ pub(crate) mod tests {
         assert_eq!(updated_order.status, OrderStatus::PendingProving);
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn test_speculative_orders_when_idle() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Index the settled locks of two lockers, one of them missing 3 of 5.
+        let unreliable = Address::repeat_byte(1);
+        let reliable = Address::repeat_byte(2);
+        let indexer = MarketIndexer::new(ctx.db.clone());
+        let indexed_order = ctx
+            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 30, 600)
+            .await;
+        for i in 0..10u64 {
+            let (locker, fulfiller) = match i {
+                0..=2 => (unreliable, reliable),
+                3..=4 => (unreliable, unreliable),
+                _ => (reliable, reliable),
+            };
+            let request_id = U256::from(i + 1);
+            let locked = MarketEvent::Locked {
+                prover: locker,
+                request: Box::new(indexed_order.request.clone()),
+                client_signature: Default::default(),
+            };
+            indexer.index(request_id, 1, &locked).await.unwrap();
+            indexer
+                .index(request_id, 2, &MarketEvent::Fulfilled { prover: fulfiller })
+                .await
+                .unwrap();
+        }
+        async fn lock(db: &DbObj, order: &OrderRequest, locker: Address) {
+            db.set_request_locked(U256::from(order.request.id), &locker.to_string(), 1)
+                .await
+                .unwrap();
+        }
+
+        // Lock expires within the speculative window.
+        let mut soon_order = ctx
+            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 30, 600)
+            .await;
+        soon_order.target_timestamp = Some(soon_order.request.lock_expires_at());
+        let soon_order_id = soon_order.id();
+        lock(&ctx.db, &soon_order, unreliable).await;
+        ctx.monitor.prove_cache.insert(soon_order.id(), Arc::from(soon_order)).await;
+
+        // Lock expires within the speculative window, but the locker fulfills its locks.
+        let mut reliable_order = ctx
+            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 30, 600)
+            .await;
+        reliable_order.target_timestamp = Some(reliable_order.request.lock_expires_at());
+        lock(&ctx.db, &reliable_order, reliable).await;
+        ctx.monitor.prove_cache.insert(reliable_order.id(), Arc::from(reliable_order)).await;
+
+        // Lock expires well after the speculative window.
+        let mut later_order = ctx
+            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 500, 600)
+            .await;
+        later_order.target_timestamp = Some(later_order.request.lock_expires_at());
+        lock(&ctx.db, &later_order, unreliable).await;
+        ctx.monitor.prove_cache.insert(later_order.id(), Arc::from(later_order)).await;
+
+        // Disabled by default.
+        let orders = ctx.monitor.get_speculative_orders(current_timestamp, 0, 50).await.unwrap();
+        assert!(orders.is_empty());
+
+        let orders = ctx.monitor.get_speculative_orders(current_timestamp, 60, 50).await.unwrap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].id(), soon_order_id);
+
+        // The locker is not likely enough to miss the lock.
+        let orders = ctx.monitor.get_speculative_orders(current_timestamp, 60, 70).await.unwrap();
+        assert!(orders.is_empty());
+
+        // No speculation once the prover has committed work.
+        let committed_order = ctx
+            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 1, 600)
+            .await;
+        ctx.monitor.lock_and_prove_orders(&[Arc::from(committed_order)]).await.unwrap();
+
+        let orders = ctx.monitor.get_speculative_orders(current_timestamp, 60, 50).await.unwrap();
+        assert!(orders.is_empty());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_lock_expired_strategy_uncontested() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+        ctx.config.load_write().unwrap().market.lock_expired_strategy =
+            LockExpiredStrategyConf::Uncontested { lookback_secs: 600 };
+
+        let mut order = ctx
+            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 1, 600)
+            .await;
+        order.target_timestamp = Some(current_timestamp);
+        let order_id = order.id();
+        let order = Arc::new(order);
+
+        // No competitors seen, so the order is fulfilled once the lock has expired.
+        ctx.monitor.prove_cache.insert(order_id.clone(), order.clone()).await;
+        let result = ctx.monitor.get_valid_orders(current_timestamp, 0).await.unwrap();
+        assert_eq!(result.len(), 1);
+
+        // Another prover recently won a lock-expired order, so the order is skipped.
+        ctx.monitor.market_stats.record_competitor_fulfillment(current_timestamp - 10);
+        let result = ctx.monitor.get_valid_orders(current_timestamp, 0).await.unwrap();
+        assert!(result.is_empty());
+
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_apply_capacity_limits_unlimited() {
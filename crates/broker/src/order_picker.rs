@@ -14,19 +14,46 @@
 
 use risc0_zkvm::sha::Digest;
 use sha2::{Digest as Sha2Digest, Sha256};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crate::{
     chain_monitor::ChainMonitorService,
-    config::ConfigLock,
+    config::{
+        AdaptivePreflightConf, ClientReputationConf, ConfigLock, MarketConf, ShadowPricingConf,
+        TinyOrderConf,
+    },
+    cycle_history,
     db::DbObj,
     errors::CodedError,
-    provers::{ProverError, ProverObj},
-    storage::{upload_image_uri, upload_input_uri},
+    events::{BrokerEvent, EventBus},
+    groth16_wrapper::Groth16WrapperObj,
+    image_cache::ImageCacheObj,
+    input_dedup::InputDedupObj,
+    input_fetcher::{InputFetcher, InputFetcherObj},
+    leadership::LeadershipObj,
+    lock_expired_pricing::LockExpiredPricing,
+    metrics::MetricsObj,
+    order_claims::OrderClaimsObj,
+    order_tags::order_tags,
+    preflight_batcher::PreflightBatcher,
+    preflight_concurrency::{PreflightConcurrencyObj, SystemLoad},
+    pricing_webhook::{PricingDecisionSummary, PricingWebhookQueue},
+    provers::{PreflightJob, ProverError, ProverObj},
+    proving_capacity::ProvingCapacityTrackerObj,
+    reputation::{ClientEvent, ClientReputation},
+    requestor_rate_limit::RequestorRateLimiter,
+    reservations::{ReservationsObj, Reserved},
+    risk::{RiskInputs, RiskScore},
+    scheduler::ProvingSchedulerObj,
+    selectors::SelectorRegistry,
+    self_throttle::SelfThrottleObj,
+    storage::{upload_assumptions_uri, upload_image_uri, upload_input_uri},
     task::{RetryRes, RetryTask, SupervisorErr},
-    utils, FulfillmentType, OrderRequest, OrderStateChange,
+    tiny_orders::{self, TinyOrdersObj},
+    utils, DryRunRecord, FulfillmentType, OrderId, OrderRequest, OrderStateChange,
+    PricingAuditRecord, PricingCheckpoint, PricingDecision, ShadowPricingRecord, SkipReason,
 };
 use crate::{
     now_timestamp,
@@ -42,27 +69,45 @@ use alloy::{
     uint,
 };
 use anyhow::{Context, Result};
-use boundless_market::{
-    contracts::{boundless_market::BoundlessMarketService, RequestError, RequestInputType},
-    selector::SupportedSelectors,
+use boundless_market::contracts::{
+    boundless_market::BoundlessMarketService, RequestError, RequestInputType,
 };
+use chrono::Utc;
 use moka::future::Cache;
 use thiserror::Error;
-use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::sync::{mpsc, oneshot, Mutex, MutexGuard};
 use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
 use OrderPricingOutcome::{Lock, ProveAfterLockExpire, Skip};
 
 const MIN_CAPACITY_CHECK_INTERVAL: Duration = Duration::from_secs(5);
 
+/// Interval to check whether a gas spike has cleared while pausing new locks.
+const GAS_SPIKE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
 const ONE_MILLION: U256 = uint!(1_000_000_U256);
 
 /// Maximum number of orders to cache for deduplication
 const ORDER_DEDUP_CACHE_SIZE: u64 = 5000;
 
 /// In-memory LRU cache for order deduplication by ID (prevents duplicate order processing)
-type OrderCache = Arc<Cache<String, ()>>;
+pub(crate) type OrderCache = Arc<Cache<OrderId, ()>>;
+
+/// Empty cache for order deduplication, shared by the order picker and the services that queue
+/// orders again.
+pub(crate) fn new_order_cache() -> OrderCache {
+    Arc::new(
+        Cache::builder()
+            .max_capacity(ORDER_DEDUP_CACHE_SIZE)
+            .time_to_live(Duration::from_secs(60 * 60)) // 1 hour
+            .build(),
+    )
+}
+
+/// Uploads of orders in pricing, recorded if the pricing is cancelled
+type CheckpointCache = Arc<Cache<OrderId, PricingCheckpoint>>;
 
 /// Configuration for preflight result caching
 const PREFLIGHT_CACHE_SIZE: u64 = 5000;
@@ -89,6 +134,9 @@ pub enum OrderPickerErr {
     #[error("{code} RPC error: {0:?}", code = self.code())]
     RpcErr(Arc<anyhow::Error>),
 
+    #[error("{code} order for unsupported chain: {0}", code = self.code())]
+    UnsupportedChain(u64),
+
     #[error("{code} Unexpected error: {0:?}", code = self.code())]
     UnexpectedErr(Arc<anyhow::Error>),
 }
@@ -101,6 +149,7 @@ impl CodedError for OrderPickerErr {
             OrderPickerErr::GuestPanic(_) => "[B-OP-003]",
             OrderPickerErr::RequestError(_) => "[B-OP-004]",
             OrderPickerErr::RpcErr(_) => "[B-OP-005]",
+            OrderPickerErr::UnsupportedChain(_) => "[B-OP-006]",
             OrderPickerErr::UnexpectedErr(_) => "[B-OP-500]",
         }
     }
@@ -118,22 +167,72 @@ impl From<RequestError> for OrderPickerErr {
     }
 }
 
+/// Chain specific services and parameters used when pricing orders for that chain.
+#[derive(Clone)]
+struct PickerChain<P> {
+    provider: Arc<P>,
+    chain_monitor: Arc<ChainMonitorService<P>>,
+    market: BoundlessMarketService<Arc<P>>,
+    stake_token_decimals: u8,
+}
+
+impl<P> PickerChain<P>
+where
+    P: Provider<Ethereum> + WalletProvider,
+{
+    fn new(
+        market_addr: Address,
+        provider: Arc<P>,
+        chain_monitor: Arc<ChainMonitorService<P>>,
+        stake_token_decimals: u8,
+    ) -> Self {
+        let market = BoundlessMarketService::new(
+            market_addr,
+            provider.clone(),
+            provider.default_signer_address(),
+        );
+        Self { provider, chain_monitor, market, stake_token_decimals }
+    }
+}
+
+/// Request to cancel pricing of an order, whether queued or in progress.
+pub(crate) struct CancelPricingRequest {
+    pub order_id: String,
+    /// Receives whether the order was queued or being priced.
+    pub reply: oneshot::Sender<bool>,
+}
+
 #[derive(Clone)]
 pub struct OrderPicker<P> {
     db: DbObj,
     config: ConfigLock,
     prover: ProverObj,
-    provider: Arc<P>,
-    chain_monitor: Arc<ChainMonitorService<P>>,
-    market: BoundlessMarketService<Arc<P>>,
-    supported_selectors: SupportedSelectors,
+    chains: HashMap<u64, PickerChain<P>>,
+    prover_addr: Address,
+    selectors: SelectorRegistry,
     // TODO ideal not to wrap in mutex, but otherwise would require supervisor refactor, try to find alternative
     new_order_rx: Arc<Mutex<mpsc::Receiver<Box<OrderRequest>>>>,
     priced_orders_tx: mpsc::Sender<Box<OrderRequest>>,
-    stake_token_decimals: u8,
     order_cache: OrderCache,
+    order_claims: Option<OrderClaimsObj>,
+    leadership: LeadershipObj,
     preflight_cache: PreflightCache,
-    order_state_tx: broadcast::Sender<OrderStateChange>,
+    preflight_batcher: PreflightBatcher,
+    preflight_concurrency: PreflightConcurrencyObj,
+    pricing_checkpoints: CheckpointCache,
+    capacity_tracker: ProvingCapacityTrackerObj,
+    input_dedup: InputDedupObj,
+    input_fetcher: InputFetcherObj,
+    image_cache: Option<ImageCacheObj>,
+    self_throttle: SelfThrottleObj,
+    tiny_orders: TinyOrdersObj,
+    scheduler: ProvingSchedulerObj,
+    reservations: ReservationsObj,
+    metrics: MetricsObj,
+    events: EventBus,
+    cancel_pricing_rx: Option<Arc<Mutex<mpsc::Receiver<CancelPricingRequest>>>>,
+    pricing_webhook: Option<PricingWebhookQueue>,
+    dry_run: bool,
 }
 
 #[derive(Debug)]
@@ -152,8 +251,63 @@ enum OrderPricingOutcome {
         lock_expire_timestamp_secs: u64,
         expiry_secs: u64,
     },
+    // Preflight exceeded the execution limit, which was set by the given bound
+    SessionLimitExceeded {
+        bound: ExecLimitBound,
+    },
     // Do not accept engage order
-    Skip,
+    Skip {
+        reason: SkipReason,
+    },
+}
+
+/// The constraint that determined the preflight execution limit for an order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExecLimitBound {
+    /// Most cycles that can be proven profitably at the order's price.
+    Price,
+    /// The configured `max_mcycle_limit`.
+    Config,
+    /// Most cycles that can be proven before the deadline, after the current proving queue.
+    Deadline,
+    /// Reduced in line with the requestor's reputation score.
+    Reputation,
+    /// Learned from the cycles used by past executions of the image.
+    History,
+}
+
+impl From<&OrderPricingOutcome> for PricingDecision {
+    fn from(outcome: &OrderPricingOutcome) -> Self {
+        match outcome {
+            Lock { target_timestamp_secs, .. } => {
+                PricingDecision::Lock { target_timestamp: *target_timestamp_secs }
+            }
+            ProveAfterLockExpire { .. } => PricingDecision::ProveAfterLockExpire,
+            SessionLimitExceeded { .. } | Skip { .. } => PricingDecision::Skip,
+        }
+    }
+}
+
+/// Minimum mcycle prices an order is evaluated against after preflight.
+#[derive(Debug, Clone, Copy)]
+struct MinMcyclePrices {
+    /// Denominated in the native token, for lockable orders
+    native: U256,
+    /// Denominated in the stake token, for lock expired orders
+    stake_token: U256,
+}
+
+/// Inputs considered while pricing an order, as far as pricing got before deciding.
+#[derive(Debug, Default)]
+struct PricingAudit {
+    gas_price: Option<u128>,
+    gas_cost: Option<U256>,
+    available_gas: Option<U256>,
+    available_stake: Option<U256>,
+    exec_limit_cycles: Option<u64>,
+    exec_limit_bound: Option<ExecLimitBound>,
+    total_cycles: Option<u64>,
+    risk: Option<RiskScore>,
 }
 
 impl<P> OrderPicker<P>
@@ -165,47 +319,149 @@ where
         db: DbObj,
         config: ConfigLock,
         prover: ProverObj,
+        chain_id: u64,
         market_addr: Address,
         provider: Arc<P>,
         chain_monitor: Arc<ChainMonitorService<P>>,
         new_order_rx: mpsc::Receiver<Box<OrderRequest>>,
         order_result_tx: mpsc::Sender<Box<OrderRequest>>,
         stake_token_decimals: u8,
-        order_state_tx: broadcast::Sender<OrderStateChange>,
+        events: EventBus,
+        capacity_tracker: ProvingCapacityTrackerObj,
+        input_dedup: InputDedupObj,
     ) -> Self {
-        let market = BoundlessMarketService::new(
-            market_addr,
-            provider.clone(),
-            provider.default_signer_address(),
-        );
+        let prover_addr = provider.default_signer_address();
+        let preflight_batcher = PreflightBatcher::new(prover.clone(), config.clone());
+        let input_fetcher = Arc::new(InputFetcher::new(config.clone(), 0));
+        let chains = HashMap::from([(
+            chain_id,
+            PickerChain::new(market_addr, provider, chain_monitor, stake_token_decimals),
+        )]);
 
         Self {
             db,
             config,
             prover,
-            provider,
-            chain_monitor,
-            market,
-            supported_selectors: SupportedSelectors::default(),
+            chains,
+            prover_addr,
+            selectors: SelectorRegistry::default(),
             new_order_rx: Arc::new(Mutex::new(new_order_rx)),
             priced_orders_tx: order_result_tx,
-            stake_token_decimals,
-            order_cache: Arc::new(
-                Cache::builder()
-                    .max_capacity(ORDER_DEDUP_CACHE_SIZE)
-                    .time_to_live(Duration::from_secs(60 * 60)) // 1 hour
-                    .build(),
-            ),
+            order_cache: new_order_cache(),
+            order_claims: None,
+            leadership: Default::default(),
             preflight_cache: Arc::new(
                 Cache::builder()
                     .max_capacity(PREFLIGHT_CACHE_SIZE)
                     .time_to_live(Duration::from_secs(PREFLIGHT_CACHE_TTL_SECS))
                     .build(),
             ),
-            order_state_tx,
+            preflight_batcher,
+            preflight_concurrency: Default::default(),
+            pricing_checkpoints: Arc::new(
+                Cache::builder()
+                    .max_capacity(ORDER_DEDUP_CACHE_SIZE)
+                    .time_to_live(Duration::from_secs(60 * 60))
+                    .build(),
+            ),
+            capacity_tracker,
+            input_dedup,
+            input_fetcher,
+            image_cache: None,
+            self_throttle: Default::default(),
+            tiny_orders: Default::default(),
+            scheduler: Default::default(),
+            reservations: Default::default(),
+            metrics: Default::default(),
+            events,
+            cancel_pricing_rx: None,
+            pricing_webhook: None,
+            dry_run: false,
         }
     }
 
+    /// Set the address that locks orders and holds the stake, if different from the signer.
+    pub fn with_prover_addr(self, prover_addr: Address) -> Self {
+        Self { prover_addr, ..self }
+    }
+
+    /// Never lock or prove orders, regardless of the `market.dry_run` config.
+    pub fn with_dry_run(self, dry_run: bool) -> Self {
+        Self { dry_run: self.dry_run || dry_run, ..self }
+    }
+
+    /// Fetch inputs through the given fetcher, shared with the proving service.
+    pub(crate) fn with_input_fetcher(self, input_fetcher: InputFetcherObj) -> Self {
+        Self { input_fetcher, ..self }
+    }
+
+    /// Read and store images in the given cache, shared with the proving service.
+    pub(crate) fn with_image_cache(self, image_cache: ImageCacheObj) -> Self {
+        Self { image_cache: Some(image_cache), ..self }
+    }
+
+    /// Raise minimum prices by the level of the given self-throttle.
+    pub(crate) fn with_self_throttle(self, self_throttle: SelfThrottleObj) -> Self {
+        Self { self_throttle, ..self }
+    }
+
+    /// Report the cycles and deadlines of priced orders to the given proving scheduler.
+    pub(crate) fn with_scheduler(self, scheduler: ProvingSchedulerObj) -> Self {
+        Self { scheduler, ..self }
+    }
+
+    /// Account for the wrapping latency of the given wrapper when pricing Groth16 orders.
+    pub(crate) fn with_groth16_wrapper(self, groth16_wrapper: Groth16WrapperObj) -> Self {
+        Self { selectors: self.selectors.with_groth16_wrapper(groth16_wrapper), ..self }
+    }
+
+    /// Reserve the stake and gas of priced orders in the given reservations, confirmed or aborted
+    /// by the order monitor.
+    pub(crate) fn with_reservations(self, reservations: ReservationsObj) -> Self {
+        Self { reservations, ..self }
+    }
+
+    /// Accept requests to cancel pricing of orders, e.g. from the admin API.
+    pub(crate) fn with_cancel_pricing_rx(self, rx: mpsc::Receiver<CancelPricingRequest>) -> Self {
+        Self { cancel_pricing_rx: Some(Arc::new(Mutex::new(rx))), ..self }
+    }
+
+    /// Notify the operator webhook of pricing decisions.
+    pub(crate) fn with_pricing_webhook(self, pricing_webhook: PricingWebhookQueue) -> Self {
+        Self { pricing_webhook: Some(pricing_webhook), ..self }
+    }
+
+    /// Deduplicate orders with the given cache, shared with the services that queue orders again.
+    pub(crate) fn with_order_cache(self, order_cache: OrderCache) -> Self {
+        Self { order_cache, ..self }
+    }
+
+    /// Claim requests before pricing them, skipping those claimed by other broker replicas.
+    pub(crate) fn with_order_claims(self, order_claims: OrderClaimsObj) -> Self {
+        Self { order_claims: Some(order_claims), ..self }
+    }
+
+    /// Leave requests unclaimed while on standby, so that the leader is not kept from them.
+    pub(crate) fn with_leadership(self, leadership: LeadershipObj) -> Self {
+        Self { leadership, ..self }
+    }
+
+    /// Record preflights, tombstoned orders and the gas balance in the given metrics.
+    pub(crate) fn with_metrics(self, metrics: MetricsObj) -> Self {
+        Self { metrics, ..self }
+    }
+
+    fn chain(&self, chain_id: u64) -> Result<&PickerChain<P>, OrderPickerErr> {
+        self.chains.get(&chain_id).ok_or(OrderPickerErr::UnsupportedChain(chain_id))
+    }
+
+    fn is_dry_run(&self) -> Result<bool, OrderPickerErr> {
+        if self.dry_run {
+            return Ok(true);
+        }
+        Ok(self.config.lock_all().context("Failed to read config")?.market.dry_run)
+    }
+
     async fn price_order_and_update_state(
         &self,
         mut order: Box<OrderRequest>,
@@ -213,24 +469,68 @@ where
     ) -> bool {
         let order_id = order.id();
         let f = || async {
+            if let Some(reason) = self.request_tombstone(&order).await {
+                tracing::info!(
+                    "Skipping order {order_id}, its request was previously skipped: {reason}"
+                );
+                self.metrics.record_tombstoned_order();
+                self.publish_skipped(&order, reason.as_str());
+                self.db
+                    .insert_skipped_request(&order, reason)
+                    .await
+                    .context("Failed to add tombstoned order to database")?;
+                return Ok(false);
+            }
+
+            if !self.claim_request(&order).await {
+                self.publish_skipped(&order, SkipReason::ClaimedByReplica.as_str());
+                self.db
+                    .insert_skipped_request(&order, SkipReason::ClaimedByReplica)
+                    .await
+                    .context("Failed to add claimed order to database")?;
+                return Ok(false);
+            }
+
+            let mut audit = PricingAudit::default();
             let pricing_result = tokio::select! {
-                result = self.price_order(&mut order) => result,
+                result = self.price_order(&mut order, &mut audit, &cancel_token) => result,
                 _ = cancel_token.cancelled() => {
                     tracing::info!("Order pricing cancelled during pricing for order {order_id}");
 
+                    self.publish_skipped(&order, SkipReason::Cancelled.as_str());
+
+                    // Record what was uploaded, to resume from the preflight if queued again
+                    order.pricing_checkpoint =
+                        self.pricing_checkpoints.remove(&order.order_id()).await;
+
                     // Add the cancelled order to the database as skipped
-                    if let Err(e) = self.db.insert_skipped_request(&order).await {
+                    if let Err(e) = self.db.insert_skipped_request(&order, SkipReason::Cancelled).await {
                         tracing::error!("Failed to add cancelled order to database: {e}");
                     }
                     return Ok(false);
                 }
             };
+            self.pricing_checkpoints.invalidate(&order.order_id()).await;
+            self.record_pricing_audit(&order, &audit, &pricing_result).await;
+
+            if matches!(pricing_result, Ok(Lock { .. } | ProveAfterLockExpire { .. }))
+                && self.is_dry_run()?
+            {
+                tracing::info!("Dry run, not acting on pricing decision for order {order_id}");
+                self.publish_skipped(&order, SkipReason::DryRun.as_str());
+                self.db
+                    .insert_skipped_request(&order, SkipReason::DryRun)
+                    .await
+                    .context("Failed to add dry run order to database")?;
+                return Ok(false);
+            }
 
             match pricing_result {
                 Ok(Lock { total_cycles, target_timestamp_secs, expiry_secs }) => {
                     order.total_cycles = Some(total_cycles);
                     order.target_timestamp = Some(target_timestamp_secs);
                     order.expire_timestamp = Some(expiry_secs);
+                    self.scheduler.record_estimate(order_id.clone(), total_cycles, expiry_secs);
 
                     tracing::info!(
                         "Order {order_id} scheduled for lock attempt in {}s (timestamp: {}), when price threshold met",
@@ -238,11 +538,9 @@ where
                         target_timestamp_secs,
                     );
 
-                    self.priced_orders_tx
-                        .send(order)
-                        .await
-                        .context("Failed to send to order_result_tx")?;
-
+                    let decision =
+                        PricingDecision::Lock { target_timestamp: target_timestamp_secs };
+                    self.hand_off(order, &audit, decision, expiry_secs).await?;
                     Ok::<_, OrderPickerErr>(true)
                 }
                 Ok(ProveAfterLockExpire {
@@ -254,28 +552,65 @@ where
                     order.total_cycles = Some(total_cycles);
                     order.target_timestamp = Some(lock_expire_timestamp_secs);
                     order.expire_timestamp = Some(expiry_secs);
+                    self.scheduler.record_estimate(order_id.clone(), total_cycles, expiry_secs);
 
-                    self.priced_orders_tx
-                        .send(order)
-                        .await
-                        .context("Failed to send to order_result_tx")?;
-
+                    let decision = PricingDecision::ProveAfterLockExpire;
+                    self.hand_off(order, &audit, decision, expiry_secs).await?;
                     Ok(true)
                 }
-                Ok(Skip) => {
-                    tracing::info!("Skipping order {order_id}");
+                Ok(SessionLimitExceeded { bound: ExecLimitBound::Deadline })
+                    if order.fulfillment_type == FulfillmentType::LockAndFulfill
+                        && order.request.offer.timeout > order.request.offer.lockTimeout =>
+                {
+                    // The order may still be provable in the window after the lock expires, so it
+                    // is not recorded as skipped. If another prover locks it, the request is
+                    // evaluated again for fulfillment after the lock expires.
+                    tracing::info!(
+                        "Not locking order {order_id}, it can not be proven before the lock deadline; leaving it to be re-evaluated after lock expiry"
+                    );
+                    Ok(false)
+                }
+                Ok(outcome @ (SessionLimitExceeded { .. } | Skip { .. })) => {
+                    let reason = match outcome {
+                        Skip { reason } => reason,
+                        _ => SkipReason::ExecLimitExceeded,
+                    };
+                    tracing::info!("Skipping order {order_id}: {reason}");
+                    self.publish_skipped(&order, reason.as_str());
+                    if reason.is_permanent() {
+                        self.add_request_tombstone(&order, reason).await?;
+                    }
 
-                    // Add the skipped order to the database
+                    // Add the skipped order to the database, batched with other tiny orders
+                    if let Some(conf) = self.tiny_order_conf(&order)? {
+                        let batch =
+                            self.tiny_orders.push_skipped(*order, reason, conf.skip_batch_size);
+                        if let Some(batch) = batch {
+                            self.db
+                                .insert_skipped_requests(&batch)
+                                .await
+                                .context("Failed to add skipped tiny orders to database")?;
+                        }
+                        return Ok(false);
+                    }
                     self.db
-                        .insert_skipped_request(&order)
+                        .insert_skipped_request(&order, reason)
                         .await
                         .context("Failed to add skipped order to database")?;
                     Ok(false)
                 }
                 Err(err) => {
                     tracing::warn!("Failed to price order {order_id}: {err}");
+                    if matches!(err, OrderPickerErr::GuestPanic(_)) {
+                        self.record_client_event(
+                            order.request.client_address(),
+                            ClientEvent::GuestPanic,
+                        )
+                        .await;
+                    }
+                    self.publish_skipped(&order, err.code());
                     self.db
-                        .insert_skipped_request(&order)
+                        .insert_skipped_request(&order, SkipReason::PricingFailed)
                         .await
                         .context("Failed to skip failed priced order")?;
                     Ok(false)
@@ -293,12 +628,215 @@ where
         }
     }
 
+    /// Reputation of the order's requestor, with the config it was scored with, if requestors
+    /// are scored and it does not skip the exec limits, as a priority requestor or through its
+    /// tier.
+    async fn client_reputation(
+        &self,
+        order: &OrderRequest,
+    ) -> Result<Option<(ClientReputation, ClientReputationConf)>, OrderPickerErr> {
+        let client_addr = order.request.client_address();
+        let conf = {
+            let config = self.config.lock_all().context("Failed to read config")?;
+            match &config.market.client_reputation {
+                Some(conf) if !config.market.skips_exec_limits(client_addr) => conf.clone(),
+                _ => return Ok(None),
+            }
+        };
+        let stats =
+            self.db.get_client_stats(client_addr).await.context("Failed to get client stats")?;
+        Ok(Some((ClientReputation::new(&stats, &conf), conf)))
+    }
+
+    /// Fast path config, if the order is tiny.
+    fn tiny_order_conf(
+        &self,
+        order: &OrderRequest,
+    ) -> Result<Option<TinyOrderConf>, OrderPickerErr> {
+        let config = self.config.lock_all().context("Failed to read config")?;
+        match &config.market.tiny_orders {
+            Some(conf) if tiny_orders::is_tiny_order(conf, &order.request)? => {
+                Ok(Some(conf.clone()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Gas price to price a tiny order with, from a recent enough snapshot if there is one.
+    async fn tiny_order_gas_price(
+        &self,
+        chain_id: u64,
+        window_secs: u64,
+        conf: &TinyOrderConf,
+    ) -> Result<u128, OrderPickerErr> {
+        let max_age = Duration::from_secs(conf.gas_snapshot_secs);
+        if let Some(gas_price) = self.tiny_orders.gas_snapshot(chain_id, max_age) {
+            return Ok(gas_price);
+        }
+        let gas_price = self
+            .chain(chain_id)?
+            .chain_monitor
+            .projected_gas_fees(window_secs)
+            .await
+            .context("Failed to get gas price")?
+            .max_fee_per_gas;
+        self.tiny_orders.set_gas_snapshot(chain_id, gas_price);
+        Ok(gas_price)
+    }
+
+    /// Pause pricing an order to lock while the gas price of its chain is spiking.
+    ///
+    /// Returns once the spike clears, or once the lock expiration passes so the caller skips the
+    /// order as expired.
+    async fn wait_for_gas_spike(
+        &self,
+        order: &OrderRequest,
+        lock_expiration: u64,
+    ) -> Result<(), OrderPickerErr> {
+        let chain_monitor = &self.chain(order.chain_id)?.chain_monitor;
+        let mut paused = false;
+        while now_timestamp() < lock_expiration {
+            let Some(spike) =
+                chain_monitor.current_gas_spike().await.context("Failed to get gas spike")?
+            else {
+                break;
+            };
+            if !paused {
+                tracing::info!(
+                    "Pausing lock of order {} during gas spike: gas price up {}% over the last {}s",
+                    order.id(),
+                    spike.pct,
+                    spike.window.as_secs()
+                );
+                paused = true;
+            }
+            tokio::time::sleep(GAS_SPIKE_POLL_INTERVAL).await;
+        }
+        Ok(())
+    }
+
+    fn publish_skipped(&self, order: &OrderRequest, reason: &str) {
+        self.events.publish(BrokerEvent::OrderSkipped {
+            order_id: order.id(),
+            request_id: U256::from(order.request.id),
+            reason: reason.to_string(),
+        });
+    }
+
+    /// Record where and when the order was received, logging any failure.
+    async fn record_provenance(&self, order: &OrderRequest) {
+        let Some(record) = order.provenance_record() else {
+            return;
+        };
+        if let Err(err) = self.db.add_order_provenance_record(&record).await {
+            tracing::warn!("Failed to record provenance of order {}: {err}", record.order_id);
+        }
+    }
+
+    /// Record an event in the statistics of a requestor, logging any failure.
+    async fn record_client_event(&self, client_addr: Address, event: ClientEvent) {
+        if let Err(err) = self.db.record_client_event(client_addr, event).await {
+            tracing::warn!("Failed to record {event:?} in stats of client {client_addr}: {err}");
+        }
+    }
+
+    /// Returns the uploads of an earlier pricing of the order that was cancelled before its
+    /// preflight completed, if any.
+    async fn resume_checkpoint(&self, order: &OrderRequest) -> PricingCheckpoint {
+        if let Some(checkpoint) = &order.pricing_checkpoint {
+            return checkpoint.clone();
+        }
+        match self.db.get_order(&order.id()).await {
+            Ok(Some(prev)) if prev.skip_reason == Some(SkipReason::Cancelled) => {
+                prev.pricing_checkpoint.unwrap_or_default()
+            }
+            Ok(_) => PricingCheckpoint::default(),
+            Err(err) => {
+                tracing::warn!("Failed to read pricing checkpoint of order {}: {err}", order.id());
+                PricingCheckpoint::default()
+            }
+        }
+    }
+
+    /// Returns the reason the request of the order was permanently skipped, if it is tombstoned.
+    async fn request_tombstone(&self, order: &OrderRequest) -> Option<SkipReason> {
+        let digest = order.order_id().signing_hash;
+        match self.db.get_request_tombstone(digest).await {
+            Ok(reason) => reason,
+            Err(err) => {
+                tracing::warn!("Failed to read tombstone of request {digest}: {err}");
+                None
+            }
+        }
+    }
+
+    /// Claim the request of the order from the other broker replicas, if claims are configured,
+    /// returning false if another replica holds it.
+    ///
+    /// Orders are priced if the claims database can't be reached, as without claims. Standby
+    /// replicas price orders without claiming them, as they do not lock them.
+    async fn claim_request(&self, order: &OrderRequest) -> bool {
+        let Some(order_claims) = &self.order_claims else {
+            return true;
+        };
+        if !self.leadership.is_leader() {
+            return true;
+        }
+        match order_claims.claim(order.request.id).await {
+            Ok(true) => true,
+            Ok(false) => {
+                tracing::info!(
+                    "Skipping order {}, its request is claimed by another replica",
+                    order.id()
+                );
+                false
+            }
+            Err(err) => {
+                tracing::warn!("Failed to claim request of order {}: {err}", order.id());
+                true
+            }
+        }
+    }
+
+    /// Tombstone the request of an order skipped for a permanent reason, unless disabled.
+    async fn add_request_tombstone(
+        &self,
+        order: &OrderRequest,
+        reason: SkipReason,
+    ) -> Result<(), OrderPickerErr> {
+        let ttl_secs = self
+            .config
+            .lock_all()
+            .context("Failed to read config")?
+            .market
+            .request_tombstone_ttl_secs;
+        if ttl_secs == 0 {
+            return Ok(());
+        }
+        let digest = order.order_id().signing_hash;
+        let expires_at = now_timestamp().saturating_add(ttl_secs);
+        if let Err(err) = self.db.add_request_tombstone(digest, reason, expires_at).await {
+            tracing::warn!("Failed to tombstone request {digest}: {err}");
+        }
+        Ok(())
+    }
+
     async fn price_order(
         &self,
         order: &mut OrderRequest,
+        audit: &mut PricingAudit,
+        cancel_token: &CancellationToken,
     ) -> Result<OrderPricingOutcome, OrderPickerErr> {
         let order_id = order.id();
-        tracing::debug!("Pricing order {order_id}");
+        let tags = {
+            let config = self.config.lock_all().context("Failed to read config")?;
+            order_tags(&config.market.order_tags, &order.request).join(", ")
+        };
+        if tags.is_empty() {
+            tracing::debug!("Pricing order {order_id}");
+        } else {
+            tracing::debug!("Pricing order {order_id} with tags: {tags}");
+        }
 
         // Lock expiration is the timestamp before which the order must be filled in order to avoid slashing
         let lock_expiration =
@@ -307,6 +845,10 @@ where
         let order_expiration =
             order.request.offer.biddingStart + order.request.offer.timeout as u64;
 
+        if order.fulfillment_type != FulfillmentType::FulfillAfterLockExpire {
+            self.wait_for_gas_spike(order, lock_expiration).await?;
+        }
+
         let now = now_timestamp();
 
         // If order_expiration > lock_expiration the period in-between is when order can be filled
@@ -319,64 +861,53 @@ where
             (lock_expiration, U256::from(order.request.offer.lockStake))
         };
 
-        if expiration <= now {
-            tracing::info!("Removing order {order_id} because it has expired");
-            return Ok(Skip);
-        };
-
-        let (min_deadline, allowed_addresses_opt, denied_addresses_opt) = {
+        let skip_reason = {
             let config = self.config.lock_all().context("Failed to read config")?;
-            (
-                config.market.min_deadline,
-                config.market.allow_client_addresses.clone(),
-                config.market.deny_requestor_addresses.clone(),
-            )
+            check_order_static(order, &order_id, &config.market, now)
         };
-
-        // Does the order expire within the min deadline
-        let seconds_left = expiration.saturating_sub(now);
-        if seconds_left <= min_deadline {
-            tracing::info!("Removing order {order_id} because it expires within min_deadline: {seconds_left}, min_deadline: {min_deadline}");
-            return Ok(Skip);
+        if let Some(reason) = skip_reason {
+            return Ok(Skip { reason });
         }
 
-        // Initial sanity checks:
-        if let Some(allow_addresses) = allowed_addresses_opt {
-            let client_addr = order.request.client_address();
-            if !allow_addresses.contains(&client_addr) {
-                tracing::info!("Removing order {order_id} from {client_addr} because it is not in allowed addrs");
-                return Ok(Skip);
-            }
-        }
-
-        if let Some(deny_addresses) = denied_addresses_opt {
-            let client_addr = order.request.client_address();
-            if deny_addresses.contains(&client_addr) {
+        let reputation = self.client_reputation(order).await?;
+        if let Some((reputation, conf)) = &reputation {
+            if reputation.is_below(conf) {
                 tracing::info!(
-                    "Removing order {order_id} from {client_addr} because it is in denied addrs"
+                    "Removing order {order_id} because the reputation of {} is {reputation}, below min_score_percent {}",
+                    order.request.client_address(),
+                    conf.min_score_percent
                 );
-                return Ok(Skip);
+                return Ok(Skip { reason: SkipReason::LowReputation });
             }
         }
 
-        if !self.supported_selectors.is_supported(order.request.requirements.selector) {
+        let selector_profile = {
+            let config = self.config.lock_all().context("Failed to read config")?;
+            self.selectors.supported_profile(&config.market, order.request.requirements.selector)
+        };
+        let Some(selector_profile) = selector_profile else {
             tracing::info!(
                 "Removing order {order_id} because it has an unsupported selector requirement"
             );
 
-            return Ok(Skip);
+            return Ok(Skip { reason: SkipReason::UnsupportedSelector });
         };
 
         // Check if the stake is sane and if we can afford it
         // For lock expired orders, we don't check the max stake because we can't lock those orders.
-        let max_stake = {
+        let max_stake: U256 = {
             let config = self.config.lock_all().context("Failed to read config")?;
-            parse_ether(&config.market.max_stake).context("Failed to parse max_stake")?
+            parse_units(
+                config.market.max_stake_for(order.request.client_address()),
+                self.chain(order.chain_id)?.stake_token_decimals,
+            )
+            .context("Failed to parse max_stake")?
+            .into()
         };
 
         if !lock_expired && lockin_stake > max_stake {
             tracing::info!("Removing high stake order {order_id}, lock stake: {lockin_stake}, max stake: {max_stake}");
-            return Ok(Skip);
+            return Ok(Skip { reason: SkipReason::StakeTooHigh });
         }
 
         // Short circuit if the order has been locked.
@@ -388,7 +919,7 @@ where
                 .context("Failed to check if request is locked before pricing")?
         {
             tracing::debug!("Order {order_id} is already locked, skipping");
-            return Ok(Skip);
+            return Ok(Skip { reason: SkipReason::AlreadyLocked });
         }
 
         if order.fulfillment_type == FulfillmentType::FulfillAfterLockExpire
@@ -399,39 +930,49 @@ where
                 .context("Failed to check if request is fulfilled before pricing")?
         {
             tracing::debug!("Order {order_id} is already fulfilled, skipping");
-            return Ok(Skip);
+            return Ok(Skip { reason: SkipReason::AlreadyFulfilled });
         }
 
         // Check that we have both enough staking tokens to stake, and enough gas tokens to lock and fulfil
-        // NOTE: We use the current gas price and a rough heuristic on gas costs. Its possible that
-        // gas prices may go up (or down) by the time its time to fulfill. This does not aim to be
-        // a tight estimate, although improving this estimate will allow for a more profit.
-        let gas_price =
-            self.chain_monitor.current_gas_price().await.context("Failed to get gas price")?;
+        // NOTE: We use the gas price projected over the order's remaining time and a rough
+        // heuristic on gas costs. Its possible that gas prices may go up (or down) by the time its
+        // time to fulfill. This does not aim to be a tight estimate, although improving this
+        // estimate will allow for a more profit.
+        //
+        // Tiny orders take a fast path, priced from a recent gas price snapshot without checking
+        // balances. The order monitor still checks the gas balance before locking.
+        let chain = self.chain(order.chain_id)?;
+        let fulfillment_window = expiration.saturating_sub(now);
+        let tiny_order = self.tiny_order_conf(order)?;
+        let gas_price = match &tiny_order {
+            Some(conf) => {
+                self.tiny_order_gas_price(order.chain_id, fulfillment_window, conf).await?
+            }
+            None => {
+                chain
+                    .chain_monitor
+                    .projected_gas_fees(fulfillment_window)
+                    .await
+                    .context("Failed to get gas price")?
+                    .max_fee_per_gas
+            }
+        };
         let order_gas = if lock_expired {
             // No need to include lock gas if its a lock expired order
             U256::from(
-                utils::estimate_gas_to_fulfill(
-                    &self.config,
-                    &self.supported_selectors,
-                    &order.request,
-                )
-                .await?,
+                utils::estimate_gas_to_fulfill(&self.config, &self.selectors, &order.request)
+                    .await?,
             )
         } else {
             U256::from(
                 utils::estimate_gas_to_lock(&self.config, order).await?
-                    + utils::estimate_gas_to_fulfill(
-                        &self.config,
-                        &self.supported_selectors,
-                        &order.request,
-                    )
-                    .await?,
+                    + utils::estimate_gas_to_fulfill(&self.config, &self.selectors, &order.request)
+                        .await?,
             )
         };
         let order_gas_cost = U256::from(gas_price) * order_gas;
-        let available_gas = self.available_gas_balance().await?;
-        let available_stake = self.available_stake_balance().await?;
+        audit.gas_price = Some(gas_price);
+        audit.gas_cost = Some(order_gas_cost);
         tracing::debug!(
             "Estimated {order_gas} gas to {} order {order_id}; {} ether @ {} gwei",
             if lock_expired { "fulfill" } else { "lock and fulfill" },
@@ -440,58 +981,89 @@ where
         );
 
         if order_gas_cost > order.request.offer.maxPrice && !lock_expired {
-            // Cannot check the gas cost for lock expired orders where the reward is a fraction of the stake
-            // TODO: This can be added once we have a price feed for the stake token in gas tokens
+            // The gas cost of lock expired orders, where the reward is a fraction of the stake, is
+            // accounted for by the lock expired pricing model
             tracing::info!(
                 "Estimated gas cost to lock and fulfill order {order_id}: {} exceeds max price; max price {}",
                 format_ether(order_gas_cost),
                 format_ether(order.request.offer.maxPrice)
             );
-            return Ok(Skip);
+            return Ok(Skip { reason: SkipReason::GasCostExceedsPrice });
         }
 
-        if order_gas_cost > available_gas {
-            tracing::warn!("Estimated there will be insufficient gas for order {order_id} after locking and fulfilling pending orders; available_gas {} ether", format_ether(available_gas));
-            return Ok(Skip);
-        }
+        if tiny_order.is_some() {
+            tracing::trace!("Not checking available balances for tiny order {order_id}");
+        } else {
+            let available_gas =
+                self.available_gas_balance(order.chain_id, fulfillment_window).await?;
+            audit.available_gas = Some(available_gas);
+            if order_gas_cost > available_gas {
+                tracing::warn!("Estimated there will be insufficient gas for order {order_id} after locking and fulfilling pending orders; available_gas {} ether", format_ether(available_gas));
+                return Ok(Skip { reason: SkipReason::InsufficientGas });
+            }
 
-        if !lock_expired && lockin_stake > available_stake {
-            tracing::warn!(
-                "Insufficient available stake to lock order {order_id}. Requires {lockin_stake}, has {available_stake}"
-            );
-            return Ok(Skip);
+            let available_stake = self.available_stake_balance(order.chain_id).await?;
+            audit.available_stake = Some(available_stake);
+            if !lock_expired && lockin_stake > available_stake {
+                tracing::warn!(
+                    "Insufficient available stake to lock order {order_id}. Requires {lockin_stake}, has {available_stake}"
+                );
+                return Ok(Skip { reason: SkipReason::InsufficientStake });
+            }
         }
 
-        let (max_mcycle_limit, peak_prove_khz) = {
+        let (
+            max_mcycle_limit,
+            peak_prove_khz,
+            additional_proof_cycles,
+            deadline_margin,
+            exec_limit_learning,
+            assumption_cycles,
+        ) = {
             let config = self.config.lock_all().context("Failed to read config")?;
-            (config.market.max_mcycle_limit, config.market.peak_prove_khz)
+            (
+                config.market.max_mcycle_limit,
+                config.market.peak_prove_khz,
+                config.market.additional_proof_cycles,
+                config.market.deadline_margin.clone(),
+                config.market.exec_limit_learning.clone(),
+                config.market.assumption_cycles,
+            )
         };
 
         // Create a executor limit based on the max price of the order
         let mut exec_limit_cycles: u64 = if lock_expired {
-            let min_mcycle_price_stake_token = {
+            let (min_mcycle_price_stake_token, pricing) = {
                 let config = self.config.lock_all().context("Failed to read config")?;
-                parse_units(&config.market.mcycle_price_stake_token, self.stake_token_decimals)
-                    .context("Failed to parse mcycle_price")?
-                    .into()
+                let min_mcycle_price_stake_token: U256 = parse_units(
+                    config.market.mcycle_price_stake_token_for(order.request.client_address()),
+                    chain.stake_token_decimals,
+                )
+                .context("Failed to parse mcycle_price")?
+                .into();
+                let pricing = LockExpiredPricing::new(
+                    config.market.lock_expired_pricing.as_ref(),
+                    chain.stake_token_decimals,
+                )?;
+                (min_mcycle_price_stake_token, pricing)
             };
 
             if min_mcycle_price_stake_token == U256::ZERO {
                 tracing::warn!("min_mcycle_price_stake_token is 0, setting unlimited exec limit");
                 u64::MAX
             } else {
-                // Note this does not account for gas cost unlike a normal order
-                // TODO: Update to account for gas once the stake token to gas token exchange rate is known
-                let price = order.request.offer.stake_reward_if_locked_and_not_fulfilled();
-                // (stake price * 1_000_000) / stake mcycle price = max cycles
-                (price.saturating_mul(ONE_MILLION).div_ceil(min_mcycle_price_stake_token))
-                    .try_into()
-                    .context("Failed to convert U256 exec limit to u64")?
+                // ((expected stake reward - gas cost - min profit) * 1_000_000) / stake mcycle price = max cycles
+                let stake_reward = order.request.offer.stake_reward_if_locked_and_not_fulfilled();
+                pricing
+                    .exec_limit(stake_reward, order_gas_cost, min_mcycle_price_stake_token)
+                    .min(U256::from(u64::MAX))
+                    .to()
             }
         } else {
             let min_mcycle_price = {
                 let config = self.config.lock_all().context("Failed to read config")?;
-                parse_ether(&config.market.mcycle_price).context("Failed to parse mcycle_price")?
+                parse_ether(config.market.mcycle_price_for(order.request.client_address()))
+                    .context("Failed to parse mcycle_price")?
             };
             // ((max_price - gas_cost) * 1_000_000) / mcycle_price = max cycles
             (U256::from(order.request.offer.maxPrice)
@@ -508,57 +1080,92 @@ where
             // TODO when/if total cycle limit is allowed in future, update this to be total cycle min
             tracing::info!("Removing order {order_id} because its exec limit is too low");
 
-            return Ok(Skip);
+            return Ok(Skip { reason: SkipReason::PriceTooLow });
         } else {
             tracing::trace!("exec limit cycles for order {order_id}: {}", exec_limit_cycles);
         }
 
-        let priority_requestor_addresses = {
+        let client_addr = order.request.client_address();
+        let skip_mcycle_limit = {
             let config = self.config.lock_all().context("Failed to read config")?;
-            config.market.priority_requestor_addresses.clone()
+            config.market.skips_exec_limits(client_addr)
         };
 
-        let mut skip_mcycle_limit = false;
-        let client_addr = order.request.client_address();
-        if let Some(allow_addresses) = priority_requestor_addresses {
-            if allow_addresses.contains(&client_addr) {
-                skip_mcycle_limit = true;
-            }
-        }
-
-        // If the order is from a priority requestor address, skip the mcycle limit
+        // If the order is from a priority requestor address or tier, skip the mcycle limit
         // If a max_mcycle_limit is configured, override the exec limit if the order is over that limit
+        let mut exec_limit_bound = ExecLimitBound::Price;
         if skip_mcycle_limit {
             exec_limit_cycles = u64::MAX;
-            tracing::debug!("Order {order_id} exec limit skipped due to client {} being part of priority_requestor_addresses.", client_addr);
+            tracing::debug!("Order {order_id} exec limit skipped due to client {} being a priority requestor or in a tier that skips exec limits.", client_addr);
         } else if let Some(config_mcycle_limit) = max_mcycle_limit {
             let config_cycle_limit = config_mcycle_limit.saturating_mul(1_000_000);
             if exec_limit_cycles >= config_cycle_limit {
                 tracing::debug!("Order {order_id} exec limit computed from max price {} exceeds config max_mcycle_limit {}, setting exec limit to max_mcycle_limit", exec_limit_cycles / 1_000_000, config_mcycle_limit);
                 exec_limit_cycles = config_cycle_limit;
+                exec_limit_bound = ExecLimitBound::Config;
             }
         }
 
-        // Cap the exec limit based on the peak prove khz and the time until expiration.
-        if let Some(peak_prove_khz) = peak_prove_khz {
-            let time_until_expiration = expiration.saturating_sub(now);
+        if let Some((reputation, conf)) = reputation.filter(|(_, conf)| conf.scale_exec_limits) {
+            let scaled_limit = reputation.scale_exec_limit(exec_limit_cycles);
+            if scaled_limit < exec_limit_cycles {
+                tracing::debug!(
+                    "Order {order_id} exec limit reduced to {scaled_limit} cycles, {client_addr} has a reputation of {reputation}"
+                );
+                exec_limit_cycles = scaled_limit;
+                exec_limit_bound = ExecLimitBound::Reputation;
+            }
+        }
+
+        if let Some(conf) = exec_limit_learning.as_ref().filter(|_| !skip_mcycle_limit) {
+            let learned_limit = cycle_history::exec_limit(&self.db, &order.request, conf)
+                .await
+                .context("Failed to get cycle history")?;
+            if let Some(learned_limit) = learned_limit.filter(|&limit| limit < exec_limit_cycles) {
+                tracing::debug!(
+                    "Order {order_id} exec limit reduced to {learned_limit} cycles, the {} percentile of past executions of its image",
+                    conf.percentile
+                );
+                exec_limit_cycles = learned_limit;
+                exec_limit_bound = ExecLimitBound::History;
+            }
+        }
+
+        // Cap the exec limit based on the proving throughput and the time left until expiration
+        // once the prover has worked through the orders already queued for proving.
+        if let Some(prove_khz) = self.capacity_tracker.prove_khz(peak_prove_khz) {
+            let queue_secs = self
+                .capacity_tracker
+                .queue_secs(prove_khz, additional_proof_cycles)
+                .await
+                .context("Failed to estimate proving queue")?;
+            let time_until_expiration = expiration.saturating_sub(now).saturating_sub(queue_secs);
             let deadline_cycle_limit =
-                calculate_max_cycles_for_time(peak_prove_khz, time_until_expiration);
+                calculate_max_cycles_for_time(prove_khz, time_until_expiration);
 
             if exec_limit_cycles > deadline_cycle_limit {
+                let khz_source = if self.capacity_tracker.observed_khz().is_some() {
+                    "observed"
+                } else {
+                    "peak_prove_khz config"
+                };
                 tracing::debug!(
-                    "Order {order_id} preflight cycle limit adjusted to {} cycles (capped by {:.1}s fulfillment deadline at {} peak_prove_khz config)",
+                    "Order {order_id} preflight cycle limit adjusted to {} cycles (capped by {:.1}s fulfillment deadline after {}s proving queue at {} {khz_source})",
                     deadline_cycle_limit,
                     time_until_expiration,
-                    peak_prove_khz
+                    queue_secs,
+                    prove_khz
                 );
                 exec_limit_cycles = deadline_cycle_limit;
+                exec_limit_bound = ExecLimitBound::Deadline;
             }
         }
 
+        audit.exec_limit_cycles = Some(exec_limit_cycles);
+        audit.exec_limit_bound = Some(exec_limit_bound);
         if exec_limit_cycles == 0 {
             tracing::debug!("Order {order_id} has no time left to prove within deadline, skipping");
-            return Ok(Skip);
+            return Ok(Skip { reason: SkipReason::InsufficientProvingTime });
         }
 
         tracing::debug!(
@@ -592,15 +1199,29 @@ where
             }
         };
 
+        let checkpoint = self.resume_checkpoint(order).await;
+        let checkpoint_key = order.order_id();
+
         // Loop while the cached result is skipped and has a lower exec limit than the current order.
         let preflight_result = loop {
             let prover = self.prover.clone();
+            let input_dedup = self.input_dedup.clone();
+            let input_fetcher = self.input_fetcher.clone();
+            let image_cache = self.image_cache.clone();
+            let preflight_batcher = self.preflight_batcher.clone();
             let config = self.config.clone();
             let request = order.request.clone();
             let order_id_clone = order_id.clone();
             let cache_key_clone = cache_key.clone();
 
             let cache_cloned = self.preflight_cache.clone();
+            let metrics = self.metrics.clone();
+            let preflight_concurrency = self.preflight_concurrency.clone();
+            let db = self.db.clone();
+            let exec_limit_learning = exec_limit_learning.clone();
+            let checkpoint = checkpoint.clone();
+            let checkpoints = self.pricing_checkpoints.clone();
+            let cancel_token = cancel_token.clone();
             let result = tokio::task::spawn(async move {
 
                 // Multiple concurrent calls of this coalesce into a single execution. This is done
@@ -612,26 +1233,53 @@ where
                             "Starting preflight of {order_id_clone} with exec limit {exec_limit_cycles} mcycles",
                         );
 
-                        // Upload image and input only if not cached
-                        let image_id = upload_image_uri(&prover, &request, &config)
-                            .await
-                            .map_err(|e| OrderPickerErr::FetchImageErr(Arc::new(e)))?;
-
-                        let input_id = upload_input_uri(&prover, &request, &config)
+                        // Upload image and input only if not cached, or uploaded by a cancelled
+                        // pricing of the order
+                        let image_id = match checkpoint.image_id {
+                            Some(image_id) if prover.has_image(&image_id).await.unwrap_or(false) => {
+                                tracing::debug!("Resuming pricing of {order_id_clone} with uploaded image {image_id}");
+                                image_id
+                            }
+                            _ => upload_image_uri(&prover, &request, &config, image_cache.as_ref())
+                                .await
+                                .map_err(|e| OrderPickerErr::FetchImageErr(Arc::new(e)))?,
+                        };
+                        let mut progress = PricingCheckpoint { image_id: Some(image_id.clone()), input_id: None };
+                        checkpoints.insert(checkpoint_key, progress.clone()).await;
+
+                        let input_id = match checkpoint.input_id {
+                            Some(input_id) if input_dedup.retain(&input_id, &order_id_clone) => {
+                                tracing::debug!("Resuming pricing of {order_id_clone} with uploaded input {input_id}");
+                                input_id
+                            }
+                            _ => upload_input_uri(&input_dedup, &input_fetcher, &request, &order_id_clone)
+                                .await
+                                .map_err(|e| OrderPickerErr::FetchInputErr(Arc::new(e)))?,
+                        };
+                        progress.input_id = Some(input_id.clone());
+                        checkpoints.insert(checkpoint_key, progress).await;
+
+                        let assumption_ids = upload_assumptions_uri(&prover, &input_fetcher, &request, &config)
                             .await
                             .map_err(|e| OrderPickerErr::FetchInputErr(Arc::new(e)))?;
 
                         // TODO add a future timeout here to put a upper bound on how long to preflight for
-                        match prover
+                        let preflight_start = Instant::now();
+                        let preflight_res = preflight_batcher
                             .preflight(
-                                &image_id,
-                                &input_id,
-                                vec![],
-                                Some(exec_limit_cycles),
-                                &order_id_clone,
+                                PreflightJob {
+                                    image_id: image_id.clone(),
+                                    input_id: input_id.clone(),
+                                    assumptions: assumption_ids.clone(),
+                                    executor_limit: Some(exec_limit_cycles),
+                                    order_id: order_id_clone.clone(),
+                                },
+                                &cancel_token,
                             )
-                            .await
-                        {
+                            .await;
+                        metrics.record_preflight(preflight_start.elapsed());
+                        preflight_concurrency.record(preflight_start.elapsed());
+                        match preflight_res {
                             Ok(res) => {
                                 tracing::debug!(
                                     "Preflight execution of {order_id_clone} with session id {} and {} mcycles completed in {} seconds",
@@ -639,11 +1287,17 @@ where
                                     res.stats.total_cycles / 1_000_000,
                                     res.elapsed_time
                                 );
+                                if let Some(conf) = &exec_limit_learning {
+                                    if let Err(err) = cycle_history::record(&db, &request, res.stats.total_cycles, conf).await {
+                                        tracing::warn!("Failed to record cycles of {order_id_clone}: {err:?}");
+                                    }
+                                }
                                 Ok(PreflightCacheValue::Success {
                                     exec_session_id: res.id,
                                     cycle_count: res.stats.total_cycles,
                                     image_id,
                                     input_id,
+                                    assumption_ids,
                                 })
                             }
                             Err(err) => match err {
@@ -698,6 +1352,7 @@ where
                 cycle_count,
                 image_id,
                 input_id,
+                assumption_ids,
             }) => {
                 tracing::debug!(
                     "Using preflight result for {order_id}: session id {} with {} mcycles",
@@ -705,14 +1360,25 @@ where
                     cycle_count / 1_000_000
                 );
 
-                // Update order with the uploaded IDs
+                // Update order with the uploaded IDs. The input may have been uploaded for another
+                // order with the same preflight cache key, and is uploaded again when proving if it
+                // has since been reclaimed.
                 order.image_id = Some(image_id.clone());
-                order.input_id = Some(input_id.clone());
+                if self.input_dedup.retain(&input_id, &order_id) {
+                    order.input_id = Some(input_id.clone());
+                }
+
+                // Resolving the assumptions when proving is not part of the preflight execution
+                let cycle_count = cycle_count + assumption_ids.len() as u64 * assumption_cycles;
+                order.assumption_ids = Some(assumption_ids);
 
                 (exec_session_id, cycle_count)
             }
             Ok(PreflightCacheValue::Skip { .. }) => {
-                return Ok(Skip);
+                tracing::debug!(
+                    "Order {order_id} exceeded exec limit of {exec_limit_cycles} cycles, bound by {exec_limit_bound:?}"
+                );
+                return Ok(SessionLimitExceeded { bound: exec_limit_bound });
             }
             Err(err) => {
                 return Err(err);
@@ -724,13 +1390,32 @@ where
             stats: ExecutorResp { total_cycles: cycle_count, ..Default::default() },
             elapsed_time: 0.0,
         };
+        audit.total_cycles = Some(cycle_count);
 
         // If a max_mcycle_limit is configured check if the order is over that limit
         if let Some(mcycle_limit) = max_mcycle_limit {
             let mcycles = proof_res.stats.total_cycles / 1_000_000;
             if !skip_mcycle_limit && mcycles >= mcycle_limit {
                 tracing::info!("Order {order_id} max_mcycle_limit check failed req: {mcycles} | config: {mcycle_limit}");
-                return Ok(Skip);
+                return Ok(Skip { reason: SkipReason::McycleLimitExceeded });
+            }
+        }
+
+        // Now that the cycles are known, check the margin required to prove an order of this size
+        // and post-process its proof
+        let margin = deadline_margin.map(|deadline_margin| {
+            deadline_margin.margin_secs(
+                Some(proof_res.stats.total_cycles),
+                self.capacity_tracker.prove_khz(peak_prove_khz),
+            )
+        });
+        let post_processing_secs = selector_profile.post_processing_secs;
+        if margin.is_some() || post_processing_secs > 0 {
+            let margin = margin.unwrap_or(0) + post_processing_secs;
+            let seconds_left = expiration.saturating_sub(now_timestamp());
+            if seconds_left <= margin {
+                tracing::info!("Removing order {order_id} because it expires within its deadline margin: {seconds_left}, margin: {margin}");
+                return Ok(Skip { reason: SkipReason::InsufficientDeadline });
             }
         }
 
@@ -741,6 +1426,12 @@ where
             .context("Failed to fetch preflight journal")?
             .context("Failed to find preflight journal")?;
 
+        self.record_client_event(
+            order.request.client_address(),
+            ClientEvent::Preflight { journal_bytes: journal.len() as u64 },
+        )
+        .await;
+
         // ensure the journal is a size we are willing to submit on-chain
         let max_journal_bytes =
             self.config.lock_all().context("Failed to read config")?.market.max_journal_bytes;
@@ -750,13 +1441,53 @@ where
                 journal.len(),
                 max_journal_bytes
             );
-            return Ok(Skip);
+            return Ok(Skip { reason: SkipReason::JournalTooLarge });
         }
 
         // Validate the predicates:
         if !order.request.requirements.predicate.eval(journal.clone()) {
             tracing::info!("Order {order_id} predicate check failed, skipping");
-            return Ok(Skip);
+            return Ok(Skip { reason: SkipReason::PredicateFailed });
+        }
+
+        let max_risk_score =
+            self.config.lock_all().context("Failed to read config")?.market.max_risk_score;
+        if let Some(max_risk_score) = max_risk_score.filter(|_| !lock_expired) {
+            let prove_khz = self.capacity_tracker.prove_khz(peak_prove_khz);
+            let queue_secs = match prove_khz {
+                Some(prove_khz) => Some(
+                    self.capacity_tracker
+                        .queue_secs(prove_khz, additional_proof_cycles)
+                        .await
+                        .context("Failed to estimate proving queue")?,
+                ),
+                None => None,
+            };
+            let client_stats = self
+                .db
+                .get_client_stats(order.request.client_address())
+                .await
+                .context("Failed to get client stats")?;
+            let risk = RiskScore::new(&RiskInputs {
+                seconds_left: expiration.saturating_sub(now_timestamp()),
+                prove_secs: prove_khz.map(|prove_khz| {
+                    (proof_res.stats.total_cycles + additional_proof_cycles)
+                        .div_ceil(prove_khz.saturating_mul(1_000).max(1))
+                        + post_processing_secs
+                }),
+                queue_secs,
+                lock_stake: lockin_stake,
+                max_stake,
+                client_stats: &client_stats,
+            });
+            audit.risk = Some(risk);
+            if risk.exceeds(max_risk_score) {
+                tracing::info!(
+                    "Removing order {order_id} because its risk score {risk} exceeds max_risk_score {max_risk_score}"
+                );
+                return Ok(Skip { reason: SkipReason::RiskTooHigh });
+            }
+            tracing::debug!("Order {order_id} risk score: {risk}");
         }
 
         self.evaluate_order(order, &proof_res, order_gas_cost, lock_expired).await
@@ -768,11 +1499,250 @@ where
         proof_res: &ProofResult,
         order_gas_cost: U256,
         lock_expired: bool,
+    ) -> Result<OrderPricingOutcome, OrderPickerErr> {
+        let (prices, shadow_conf) = {
+            let config = self.config.lock_all().context("Failed to read config")?;
+            let client_addr = order.request.client_address();
+            let mut prices = MinMcyclePrices {
+                native: parse_ether(config.market.mcycle_price_for(client_addr))
+                    .context("Failed to parse mcycle_price")?,
+                stake_token: parse_units(
+                    config.market.mcycle_price_stake_token_for(client_addr),
+                    self.chain(order.chain_id)?.stake_token_decimals,
+                )
+                .context("Failed to parse mcycle_price_stake_token")?
+                .into(),
+            };
+            if let Some(conf) = &config.market.self_throttle {
+                let now = now_timestamp();
+                prices = MinMcyclePrices {
+                    native: self.self_throttle.scale_min_price(conf, prices.native, now),
+                    stake_token: self.self_throttle.scale_min_price(conf, prices.stake_token, now),
+                };
+            }
+            (prices, config.market.shadow_pricing.clone())
+        };
+
+        let outcome = self
+            .evaluate_order_at_prices(order, proof_res, order_gas_cost, lock_expired, prices)
+            .await?;
+
+        if let Some(shadow_conf) = shadow_conf {
+            if let Err(err) = self
+                .record_shadow_pricing(
+                    order,
+                    proof_res,
+                    order_gas_cost,
+                    lock_expired,
+                    prices,
+                    &shadow_conf,
+                    &outcome,
+                )
+                .await
+            {
+                tracing::warn!("Failed to shadow price order {}: {err}", order.id());
+            }
+        }
+
+        if self.is_dry_run()? {
+            if let Err(err) = self.record_dry_run(order, proof_res, order_gas_cost, &outcome).await
+            {
+                tracing::warn!("Failed to record dry run decision for order {}: {err}", order.id());
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    async fn evaluate_order_at_prices(
+        &self,
+        order: &OrderRequest,
+        proof_res: &ProofResult,
+        order_gas_cost: U256,
+        lock_expired: bool,
+        prices: MinMcyclePrices,
     ) -> Result<OrderPricingOutcome, OrderPickerErr> {
         if lock_expired {
-            return self.evaluate_lock_expired_order(order, proof_res).await;
+            self.evaluate_lock_expired_order(order, proof_res, order_gas_cost, prices.stake_token)
+                .await
         } else {
-            self.evaluate_lockable_order(order, proof_res, order_gas_cost).await
+            self.evaluate_lockable_order(order, proof_res, order_gas_cost, prices.native).await
+        }
+    }
+
+    /// Price the order again using the shadow pricing profile and record both decisions.
+    ///
+    /// The shadow outcome is never acted on.
+    #[allow(clippy::too_many_arguments)]
+    async fn record_shadow_pricing(
+        &self,
+        order: &OrderRequest,
+        proof_res: &ProofResult,
+        order_gas_cost: U256,
+        lock_expired: bool,
+        live_prices: MinMcyclePrices,
+        shadow_conf: &ShadowPricingConf,
+        live_outcome: &OrderPricingOutcome,
+    ) -> Result<(), OrderPickerErr> {
+        let order_id = order.id();
+        let shadow_prices = MinMcyclePrices {
+            native: match shadow_conf.mcycle_price.as_ref() {
+                Some(price) => parse_ether(price).context("Failed to parse shadow mcycle_price")?,
+                None => live_prices.native,
+            },
+            stake_token: match shadow_conf.mcycle_price_stake_token.as_ref() {
+                Some(price) => parse_units(price, self.chain(order.chain_id)?.stake_token_decimals)
+                    .context("Failed to parse shadow mcycle_price_stake_token")?
+                    .into(),
+                None => live_prices.stake_token,
+            },
+        };
+
+        let exceeds_shadow_limit = shadow_conf
+            .max_mcycle_limit
+            .is_some_and(|limit| proof_res.stats.total_cycles / 1_000_000 >= limit);
+        let shadow_outcome = if exceeds_shadow_limit {
+            Skip
+        } else {
+            self.evaluate_order_at_prices(
+                order,
+                proof_res,
+                order_gas_cost,
+                lock_expired,
+                shadow_prices,
+            )
+            .instrument(tracing::debug_span!("shadow_pricing", order_id = %order_id))
+            .await?
+        };
+
+        let record = ShadowPricingRecord {
+            order_id,
+            total_cycles: proof_res.stats.total_cycles,
+            live: live_outcome.into(),
+            shadow: (&shadow_outcome).into(),
+            created_at: Utc::now(),
+        };
+        if record.live != record.shadow {
+            tracing::info!(
+                "Shadow pricing diverged for order {}: live {:?}, shadow {:?}",
+                record.order_id,
+                record.live,
+                record.shadow
+            );
+        }
+
+        self.db
+            .set_shadow_pricing_record(&record)
+            .await
+            .context("Failed to record shadow pricing result")?;
+        Ok(())
+    }
+
+    /// Record a decision to lock or prove an order, with its expected reward, without acting on it.
+    async fn record_dry_run(
+        &self,
+        order: &OrderRequest,
+        proof_res: &ProofResult,
+        order_gas_cost: U256,
+        outcome: &OrderPricingOutcome,
+    ) -> Result<(), OrderPickerErr> {
+        let offer = &order.request.offer;
+        let (reward, estimated_profit) = match outcome {
+            Lock { target_timestamp_secs, .. } => {
+                let price = offer
+                    .price_at(*target_timestamp_secs)
+                    .context("Failed to get price at target timestamp")?;
+                (price, Some(price.saturating_sub(order_gas_cost)))
+            }
+            ProveAfterLockExpire { .. } => (offer.stake_reward_if_locked_and_not_fulfilled(), None),
+            SessionLimitExceeded { .. } | Skip { .. } => return Ok(()),
+        };
+
+        let record = DryRunRecord {
+            order_id: order.id(),
+            total_cycles: proof_res.stats.total_cycles,
+            decision: outcome.into(),
+            reward,
+            gas_cost: order_gas_cost,
+            estimated_profit,
+            created_at: Utc::now(),
+        };
+        tracing::info!(
+            "Dry run: would {:?} order {} for a reward of {} (estimated profit {:?} ETH)",
+            record.decision,
+            record.order_id,
+            record.reward,
+            record.estimated_profit.map(format_ether),
+        );
+
+        self.db.set_dry_run_record(&record).await.context("Failed to record dry run decision")?;
+        Ok(())
+    }
+
+    /// Record the inputs and outcome of pricing the order in the audit log, and notify the pricing
+    /// webhook of the decision, if enabled.
+    async fn record_pricing_audit(
+        &self,
+        order: &OrderRequest,
+        audit: &PricingAudit,
+        result: &Result<OrderPricingOutcome, OrderPickerErr>,
+    ) {
+        let (pricing_audit, mcycle_price, mcycle_price_stake_token) = {
+            let Ok(config) = self.config.lock_all() else {
+                tracing::warn!("Failed to read config to record pricing audit of {}", order.id());
+                return;
+            };
+            let client_addr = order.request.client_address();
+            (
+                config.market.pricing_audit,
+                config.market.mcycle_price_for(client_addr).to_string(),
+                config.market.mcycle_price_stake_token_for(client_addr).to_string(),
+            )
+        };
+        if !pricing_audit && self.pricing_webhook.is_none() {
+            return;
+        }
+
+        let (decision, skip_reason, error) = match result {
+            Ok(outcome) => {
+                let skip_reason = match outcome {
+                    Skip { reason } => Some(*reason),
+                    SessionLimitExceeded { .. } => Some(SkipReason::ExecLimitExceeded),
+                    Lock { .. } | ProveAfterLockExpire { .. } => None,
+                };
+                (outcome.into(), skip_reason, None)
+            }
+            Err(err) => {
+                (PricingDecision::Skip, Some(SkipReason::PricingFailed), Some(err.to_string()))
+            }
+        };
+        let record = PricingAuditRecord {
+            order_id: order.id(),
+            request_id: order.request.id,
+            fulfillment_type: order.fulfillment_type,
+            gas_price: audit.gas_price,
+            gas_cost: audit.gas_cost,
+            available_gas: audit.available_gas,
+            available_stake: audit.available_stake,
+            exec_limit_cycles: audit.exec_limit_cycles,
+            exec_limit_bound: audit.exec_limit_bound.map(|bound| format!("{bound:?}")),
+            total_cycles: audit.total_cycles,
+            risk: audit.risk,
+            mcycle_price,
+            mcycle_price_stake_token,
+            decision,
+            skip_reason,
+            error,
+            created_at: Utc::now(),
+        };
+
+        if let Some(pricing_webhook) = &self.pricing_webhook {
+            pricing_webhook.notify(PricingDecisionSummary::new(order, &record));
+        }
+        if pricing_audit {
+            if let Err(err) = self.db.add_pricing_audit_record(&record).await {
+                tracing::warn!("Failed to record pricing audit of {}: {err}", record.order_id);
+            }
         }
     }
 
@@ -782,12 +1752,8 @@ where
         order: &OrderRequest,
         proof_res: &ProofResult,
         order_gas_cost: U256,
+        config_min_mcycle_price: U256,
     ) -> Result<OrderPricingOutcome, OrderPickerErr> {
-        let config_min_mcycle_price = {
-            let config = self.config.lock_all().context("Failed to read config")?;
-            parse_ether(&config.market.mcycle_price).context("Failed to parse mcycle_price")?
-        };
-
         let order_id = order.id();
         let one_mill = U256::from(1_000_000);
 
@@ -806,14 +1772,14 @@ where
             format_ether(U256::from(order.request.offer.maxPrice)),
             format_ether(mcycle_price_min),
             format_ether(mcycle_price_max),
-            format_units(U256::from(order.request.offer.lockStake), self.stake_token_decimals).unwrap_or_default(),
+            format_units(U256::from(order.request.offer.lockStake), self.chain(order.chain_id)?.stake_token_decimals).unwrap_or_default(),
             format_ether(order_gas_cost),
         );
 
         // Skip the order if it will never be worth it
         if mcycle_price_max < config_min_mcycle_price {
             tracing::debug!("Removing under priced order {order_id}");
-            return Ok(Skip);
+            return Ok(Skip { reason: SkipReason::PriceTooLow });
         }
 
         let target_timestamp_secs = if mcycle_price_min >= config_min_mcycle_price {
@@ -844,43 +1810,53 @@ where
         Ok(Lock { total_cycles: proof_res.stats.total_cycles, target_timestamp_secs, expiry_secs })
     }
 
-    /// Evaluate if a lock expired order is worth picking based on how much of the slashed stake token we can recover
-    /// and the configured min mcycle price in stake tokens
+    /// Evaluate if a lock expired order is worth picking based on how much of the slashed stake
+    /// token we expect to recover, versus the cost of proving at the configured min mcycle price
+    /// in stake tokens and the gas to fulfill it
     async fn evaluate_lock_expired_order(
         &self,
         order: &OrderRequest,
         proof_res: &ProofResult,
+        order_gas_cost: U256,
+        config_min_mcycle_price_stake_tokens: U256,
     ) -> Result<OrderPricingOutcome, OrderPickerErr> {
-        let config_min_mcycle_price_stake_tokens: U256 = {
+        let decimals = self.chain(order.chain_id)?.stake_token_decimals;
+        let pricing = {
             let config = self.config.lock_all().context("Failed to read config")?;
-            parse_units(&config.market.mcycle_price_stake_token, self.stake_token_decimals)
-                .context("Failed to parse mcycle_price")?
-                .into()
+            LockExpiredPricing::new(config.market.lock_expired_pricing.as_ref(), decimals)?
         };
-
-        let total_cycles = U256::from(proof_res.stats.total_cycles);
+        let format_stake = |amount: U256| format_units(amount, decimals).unwrap_or_default();
 
         // Reward for the order is a fraction of the stake once the lock has expired
-        let price = order.request.offer.stake_reward_if_locked_and_not_fulfilled();
-        let mcycle_price_in_stake_tokens = price.saturating_mul(ONE_MILLION) / total_cycles;
+        let stake_reward = order.request.offer.stake_reward_if_locked_and_not_fulfilled();
+        let profit = pricing.evaluate(
+            stake_reward,
+            order_gas_cost,
+            proof_res.stats.total_cycles,
+            config_min_mcycle_price_stake_tokens,
+        );
 
         tracing::info!(
-            "Order price: {} (stake tokens) - cycles: {} - mcycle price: {} (stake tokens), config_min_mcycle_price_stake_tokens: {} (stake tokens)",
-            format_ether(price),
+            "Order stake reward: {} - expected payout: {} - cycles: {} - proving cost: {} - gas cost: {} (stake tokens), config_min_mcycle_price_stake_tokens: {} (stake tokens)",
+            format_stake(stake_reward),
+            format_stake(profit.expected_payout),
             proof_res.stats.total_cycles,
-            format_ether(mcycle_price_in_stake_tokens),
-            format_ether(config_min_mcycle_price_stake_tokens),
+            format_stake(profit.proving_cost),
+            format_stake(profit.gas_cost),
+            format_stake(config_min_mcycle_price_stake_tokens),
         );
 
         // Skip the order if it will never be worth it
-        if mcycle_price_in_stake_tokens < config_min_mcycle_price_stake_tokens {
+        if !profit.is_profitable() {
             tracing::info!(
-                "Removing under priced order (slashed stake reward too low) {} (stake price {} < config min stake price {})",
+                "Removing under priced order (slashed stake reward too low) {} (expected payout {} < proving cost {} + gas cost {} + min profit {})",
                 order.id(),
-                format_ether(mcycle_price_in_stake_tokens),
-                format_ether(config_min_mcycle_price_stake_tokens)
+                format_stake(profit.expected_payout),
+                format_stake(profit.proving_cost),
+                format_stake(profit.gas_cost),
+                format_stake(profit.min_profit)
             );
-            return Ok(Skip);
+            return Ok(Skip { reason: SkipReason::PriceTooLow });
         }
 
         Ok(ProveAfterLockExpire {
@@ -891,45 +1867,90 @@ where
         })
     }
 
-    /// Estimate of gas for fulfilling any orders either pending lock or locked
-    async fn estimate_gas_to_fulfill_pending(&self) -> Result<u64> {
+    /// Estimate of gas for fulfilling any orders on the chain either pending lock or locked
+    async fn estimate_gas_to_fulfill_pending(&self, chain_id: u64) -> Result<u64> {
         let mut gas = 0;
-        for order in self.db.get_committed_orders().await? {
-            let gas_estimate = utils::estimate_gas_to_fulfill(
-                &self.config,
-                &self.supported_selectors,
-                &order.request,
-            )
-            .await?;
+        let committed_orders = self.db.get_committed_orders().await?;
+        for order in committed_orders.iter().filter(|order| order.chain_id == chain_id) {
+            let gas_estimate =
+                utils::estimate_gas_to_fulfill(&self.config, &self.selectors, &order.request)
+                    .await?;
             gas += gas_estimate;
         }
-        tracing::debug!("Total gas estimate to fulfill pending orders: {}", gas);
+        tracing::debug!("Total gas estimate to fulfill pending orders on chain {chain_id}: {gas}");
         Ok(gas)
     }
 
-    /// Estimate the total gas tokens reserved to lock and fulfill all pending orders
-    async fn gas_balance_reserved(&self) -> Result<U256> {
-        let gas_price =
-            self.chain_monitor.current_gas_price().await.context("Failed to get gas price")?;
-        let fulfill_pending_gas = self.estimate_gas_to_fulfill_pending().await?;
+    /// Estimate the total gas tokens reserved to lock and fulfill all pending orders on the chain,
+    /// at the gas price projected over the next `window_secs`
+    async fn gas_balance_reserved(&self, chain_id: u64, window_secs: u64) -> Result<U256> {
+        let gas_price = self
+            .chain(chain_id)?
+            .chain_monitor
+            .projected_gas_fees(window_secs)
+            .await
+            .context("Failed to get gas price")?
+            .max_fee_per_gas;
+        let fulfill_pending_gas = self.estimate_gas_to_fulfill_pending(chain_id).await?;
         Ok(U256::from(gas_price) * U256::from(fulfill_pending_gas))
     }
 
+    /// Reserve the stake and gas of a priced order and hand it to the order monitor.
+    ///
+    /// The reservation is held until the monitor confirms or aborts it, or until `expires_at`.
+    /// It is aborted here if the order can not be handed off.
+    async fn hand_off(
+        &self,
+        order: Box<OrderRequest>,
+        audit: &PricingAudit,
+        decision: PricingDecision,
+        expires_at: u64,
+    ) -> Result<(), OrderPickerErr> {
+        let order_id = order.id();
+        let stake = match order.fulfillment_type {
+            FulfillmentType::LockAndFulfill => order.request.offer.lockStake,
+            FulfillmentType::FulfillAfterLockExpire | FulfillmentType::FulfillWithoutLocking => {
+                U256::ZERO
+            }
+        };
+        let reserved = Reserved { stake, gas: audit.gas_cost.unwrap_or_default() };
+        self.reservations.reserve(order_id.clone(), order.chain_id, reserved, expires_at);
+
+        let request_id = U256::from(order.request.id);
+        let sent = self.priced_orders_tx.send(order).await;
+        if sent.is_err() {
+            self.reservations.abort(&order_id);
+        }
+        sent.context("Failed to send to order_result_tx")?;
+        self.events.publish(BrokerEvent::OrderPriced { order_id, request_id, decision });
+        Ok(())
+    }
+
     /// Return available gas balance.
     ///
-    /// This is defined as the balance of the signer account.
-    async fn available_gas_balance(&self) -> Result<U256, OrderPickerErr> {
-        let balance = self
-            .provider
-            .get_balance(self.provider.default_signer_address())
+    /// This is defined as the balance of the signer account on the chain, minus the gas reserved
+    /// for pending orders over the next `window_secs` and for priced orders not yet committed.
+    async fn available_gas_balance(
+        &self,
+        chain_id: u64,
+        window_secs: u64,
+    ) -> Result<U256, OrderPickerErr> {
+        let provider = &self.chain(chain_id)?.provider;
+        let balance = provider
+            .get_balance(provider.default_signer_address())
             .await
             .map_err(|err| OrderPickerErr::RpcErr(Arc::new(err.into())))?;
 
-        let gas_balance_reserved = self.gas_balance_reserved().await?;
+        self.metrics
+            .record_gas_balance(chain_id, format_ether(balance).parse().unwrap_or_default());
+        self.events.publish(BrokerEvent::GasBalance { chain_id, balance });
+
+        let gas_balance_reserved = self.gas_balance_reserved(chain_id, window_secs).await?
+            + self.reservations.reserved(chain_id).gas;
 
         let available = balance.saturating_sub(gas_balance_reserved);
         tracing::debug!(
-            "available gas balance: (account_balance) {} - (expected_future_gas) {} = {}",
+            "available gas balance on chain {chain_id}: (account_balance) {} - (expected_future_gas) {} = {}",
             format_ether(balance),
             format_ether(gas_balance_reserved),
             format_ether(available)
@@ -940,10 +1961,22 @@ where
 
     /// Return available stake balance.
     ///
-    /// This is defined as the balance in staking tokens of the signer account minus any pending locked stake.
-    async fn available_stake_balance(&self) -> Result<U256> {
-        let balance = self.market.balance_of_stake(self.provider.default_signer_address()).await?;
-        Ok(balance)
+    /// This is defined as the balance in staking tokens of the prover account on the chain minus
+    /// the stake reserved for priced orders not yet locked.
+    async fn available_stake_balance(&self, chain_id: u64) -> Result<U256> {
+        let chain = self.chain(chain_id)?;
+        let balance = chain.market.balance_of_stake(self.prover_addr).await?;
+        self.metrics.record_stake_balance(
+            chain_id,
+            format_units(balance, chain.stake_token_decimals)
+                .map_or(0.0, |balance| balance.parse().unwrap_or_default()),
+        );
+        self.events.publish(BrokerEvent::StakeBalance {
+            chain_id,
+            balance,
+            decimals: chain.stake_token_decimals,
+        });
+        Ok(balance.saturating_sub(self.reservations.reserved(chain_id).stake))
     }
 }
 
@@ -964,8 +1997,16 @@ struct PreflightCacheKey {
 /// Value type for the preflight cache
 #[derive(Clone, Debug)]
 enum PreflightCacheValue {
-    Success { exec_session_id: String, cycle_count: u64, image_id: String, input_id: String },
-    Skip { cached_limit: u64 },
+    Success {
+        exec_session_id: String,
+        cycle_count: u64,
+        image_id: String,
+        input_id: String,
+        assumption_ids: Vec<String>,
+    },
+    Skip {
+        cached_limit: u64,
+    },
 }
 
 /// Handles a lock event for a request
@@ -973,14 +2014,14 @@ enum PreflightCacheValue {
 #[allow(clippy::vec_box)]
 fn handle_lock_event(
     request_id: U256,
-    active_tasks: &mut BTreeMap<U256, BTreeMap<String, CancellationToken>>,
+    active_tasks: &mut BTreeMap<U256, BTreeMap<OrderId, CancellationToken>>,
     pending_orders: &mut Vec<Box<OrderRequest>>,
 ) {
     // Cancel only LockAndFulfill active tasks
     if let Some(order_tasks) = active_tasks.get_mut(&request_id) {
         let initial_count = order_tasks.len();
         order_tasks.retain(|order_id, task_token| {
-            if order_id.contains("LockAndFulfill") {
+            if order_id.fulfillment_type == FulfillmentType::LockAndFulfill {
                 task_token.cancel();
                 false
             } else {
@@ -1026,7 +2067,7 @@ fn handle_lock_event(
 #[allow(clippy::vec_box)]
 fn handle_fulfill_event(
     request_id: U256,
-    active_tasks: &mut BTreeMap<U256, BTreeMap<String, CancellationToken>>,
+    active_tasks: &mut BTreeMap<U256, BTreeMap<OrderId, CancellationToken>>,
     pending_orders: &mut Vec<Box<OrderRequest>>,
 ) {
     // Cancel all active tasks
@@ -1075,19 +2116,48 @@ where
                 })?;
                 Ok((
                     cfg.market.max_concurrent_preflights as usize,
+                    cfg.market.adaptive_preflights.clone(),
                     cfg.market.order_pricing_priority,
                     cfg.market.priority_requestor_addresses.clone(),
+                    cfg.market.requestor_tiers.clone(),
+                    cfg.market.order_tags.clone(),
+                    cfg.market.requestor_rate_limit.clone(),
                 ))
             };
 
-            let (mut current_capacity, mut priority_mode, mut priority_addresses) =
-                read_config().map_err(SupervisorErr::Fault)?;
-            let mut tasks: JoinSet<(String, U256)> = JoinSet::new();
+            // The number of orders priced at once, from the config or adapted to the load
+            let pricing_capacity =
+                |configured: usize, adaptive: &Option<AdaptivePreflightConf>| match adaptive {
+                    Some(conf) => picker.preflight_concurrency.limit(
+                        conf,
+                        configured,
+                        SystemLoad::sample(),
+                        Instant::now(),
+                    ),
+                    None => configured,
+                };
+
+            let (
+                configured_capacity,
+                adaptive_preflights,
+                mut priority_mode,
+                mut priority_addresses,
+                mut requestor_tiers,
+                mut order_tags,
+                mut requestor_rate_limit,
+            ) = read_config().map_err(SupervisorErr::Fault)?;
+            let mut current_capacity = pricing_capacity(configured_capacity, &adaptive_preflights);
+            let mut requestor_limiter = RequestorRateLimiter::default();
+            let mut tasks: JoinSet<(OrderId, U256)> = JoinSet::new();
             let mut rx = picker.new_order_rx.lock().await;
-            let mut order_state_rx = picker.order_state_tx.subscribe();
+            let mut cancel_requests = match &picker.cancel_pricing_rx {
+                Some(rx) => Some(rx.lock().await),
+                None => None,
+            };
+            let mut order_state_rx = picker.events.subscribe();
             let mut capacity_check_interval = tokio::time::interval(MIN_CAPACITY_CHECK_INTERVAL);
             let mut pending_orders: Vec<Box<OrderRequest>> = Vec::new();
-            let mut active_tasks: BTreeMap<U256, BTreeMap<String, CancellationToken>> =
+            let mut active_tasks: BTreeMap<U256, BTreeMap<OrderId, CancellationToken>> =
                 BTreeMap::new();
             let mut last_active_tasks_log: String = String::new();
 
@@ -1095,7 +2165,14 @@ where
                 tokio::select! {
                     // This channel is cancellation safe, so it's fine to use in the select!
                     Some(order) = rx.recv() => {
-                        let order_id = order.id();
+                        picker.events.publish(BrokerEvent::OrderReceived {
+                            order_id: order.id(),
+                            request_id: U256::from(order.request.id),
+                            client: order.request.client_address(),
+                        });
+                        picker.input_fetcher.prefetch(&order.request);
+                        picker.record_provenance(&order).await;
+                        let order_id = order.order_id();
                         pending_orders.push(order);
                         tracing::debug!(
                             "Queued order {} to be priced. Currently {} queued pricing tasks: {}",
@@ -1108,20 +2185,51 @@ where
                                 .join(", ")
                         );
                     }
-                    Ok(state_change) = order_state_rx.recv() => {
+                    Some(request) = recv_cancel_pricing(&mut cancel_requests) => {
+                        let pending = pending_orders
+                            .iter()
+                            .position(|order| order.id() == request.order_id)
+                            .map(|idx| pending_orders.remove(idx));
+                        let cancelled = match pending {
+                            Some(order) => {
+                                picker.publish_skipped(&order, SkipReason::Cancelled.as_str());
+                                if let Err(err) = picker.db.insert_skipped_request(&order, SkipReason::Cancelled).await {
+                                    tracing::error!("Failed to add cancelled order to database: {err}");
+                                }
+                                true
+                            }
+                            None => cancel_active_pricing(&request.order_id, &active_tasks),
+                        };
+                        if cancelled {
+                            tracing::info!("Cancelled pricing of order {}", request.order_id);
+                        }
+                        // The requester may have gone away, nothing to do then
+                        let _ = request.reply.send(cancelled);
+                    }
+                    Ok(state_change) = order_state_rx.recv_order_state() => {
                         match state_change {
-                            OrderStateChange::Locked { request_id, prover } => {
-                                tracing::debug!("Received order state change for request 0x{:x}: Locked by prover {:x}",
-                                    request_id, prover);
+                            OrderStateChange::Locked { request_id, prover, block_number } => {
+                                tracing::debug!("Received order state change for request 0x{:x}: Locked by prover {:x} in block {}",
+                                    request_id, prover, block_number);
 
                                 handle_lock_event(request_id, &mut active_tasks, &mut pending_orders);
                             }
-                            OrderStateChange::Fulfilled { request_id } => {
-                                tracing::debug!("Received order state change for request 0x{:x}: Fulfilled",
-                                    request_id);
+                            OrderStateChange::Fulfilled { request_id, block_number } => {
+                                tracing::debug!("Received order state change for request 0x{:x}: Fulfilled in block {}",
+                                    request_id, block_number);
 
                                 handle_fulfill_event(request_id, &mut active_tasks, &mut pending_orders);
                             }
+                            // The lock and fulfillment tables are rolled back by the market
+                            // monitor, which queues the request again if it can be locked
+                            OrderStateChange::LockReverted { request_id, block_number, .. } => {
+                                tracing::debug!("Received order state change for request 0x{:x}: Lock in block {} reverted",
+                                    request_id, block_number);
+                            }
+                            OrderStateChange::FulfillmentReverted { request_id, block_number } => {
+                                tracing::debug!("Received order state change for request 0x{:x}: Fulfillment in block {} reverted",
+                                    request_id, block_number);
+                            }
                         }
                     }
                     Some(result) = tasks.join_next(), if !tasks.is_empty() => {
@@ -1140,8 +2248,11 @@ where
                         }
                     }
                     _ = capacity_check_interval.tick() => {
+                        flush_tiny_orders(&picker.tiny_orders, &picker.db).await;
+
                         // Check capacity on an interval for capacity changes in config
-                        let (new_capacity, new_priority_mode, new_priority_addresses) = read_config().map_err(SupervisorErr::Fault)?;
+                        let (new_configured_capacity, new_adaptive_preflights, new_priority_mode, new_priority_addresses, new_requestor_tiers, new_order_tags, new_requestor_rate_limit) = read_config().map_err(SupervisorErr::Fault)?;
+                        let new_capacity = pricing_capacity(new_configured_capacity, &new_adaptive_preflights);
                         if new_capacity != current_capacity{
                             tracing::debug!("Pricing capacity changed from {} to {}", current_capacity, new_capacity);
                             current_capacity = new_capacity;
@@ -1154,6 +2265,21 @@ where
                             tracing::debug!("Priority requestor addresses changed");
                             priority_addresses = new_priority_addresses;
                         }
+                        if new_requestor_tiers != requestor_tiers {
+                            tracing::debug!("Requestor tiers changed");
+                            requestor_tiers = new_requestor_tiers;
+                        }
+                        if new_order_tags != order_tags {
+                            tracing::debug!("Order tags changed");
+                            order_tags = new_order_tags;
+                        }
+                        if new_requestor_rate_limit != requestor_rate_limit {
+                            tracing::debug!("Requestor rate limit changed");
+                            requestor_rate_limit = new_requestor_rate_limit;
+                        }
+                        if let Some(conf) = &requestor_rate_limit {
+                            requestor_limiter.prune(conf, Instant::now());
+                        }
 
                         // Log active pricing tasks if they've changed
                         let current_tasks_log = format_active_tasks(&active_tasks);
@@ -1169,6 +2295,7 @@ where
 
                         // Wait for all pricing tasks to be cancelled gracefully
                         while tasks.join_next().await.is_some() {}
+                        flush_tiny_orders(&picker.tiny_orders, &picker.db).await;
                         break;
                     }
                 }
@@ -1176,15 +2303,28 @@ where
                 // Process pending orders if we have capacity
                 if !pending_orders.is_empty() && tasks.len() < current_capacity {
                     let available_capacity = current_capacity - tasks.len();
+                    let (now, now_secs) = (Instant::now(), now_timestamp());
                     let selected_orders = picker.select_pricing_orders(
                         &mut pending_orders,
                         priority_mode,
                         priority_addresses.as_deref(),
+                        &requestor_tiers,
+                        &order_tags,
                         available_capacity,
+                        |order| match &requestor_rate_limit {
+                            Some(conf) => requestor_limiter.admit(
+                                conf,
+                                priority_addresses.as_deref(),
+                                order,
+                                now,
+                                now_secs,
+                            ),
+                            None => true,
+                        },
                     );
 
                     for order in selected_orders {
-                        let order_id = order.id();
+                        let order_id = order.order_id();
                         let request_id = U256::from(order.request.id);
 
                         // Check if we're already processing this specific order
@@ -1206,7 +2346,7 @@ where
                         }
 
                         // Mark order as being processed immediately to prevent duplicates
-                        picker.order_cache.insert(order_id.clone(), ()).await;
+                        picker.order_cache.insert(order_id, ()).await;
 
                         let picker_clone = picker.clone();
                         let task_cancel_token = cancel_token.child_token();
@@ -1215,7 +2355,7 @@ where
                         active_tasks
                             .entry(request_id)
                             .or_default()
-                            .insert(order_id.clone(), task_cancel_token.clone());
+                            .insert(order_id, task_cancel_token.clone());
 
                         tasks.spawn(async move {
                             picker_clone
@@ -1231,11 +2371,50 @@ where
     }
 }
 
+/// Writes skipped tiny orders queued for a batched write to the DB, logging any failure.
+async fn flush_tiny_orders(tiny_orders: &TinyOrdersObj, db: &DbObj) {
+    match tiny_orders.flush(db).await {
+        Ok(0) => {}
+        Ok(count) => tracing::debug!("Added {count} skipped tiny orders to database"),
+        Err(err) => tracing::error!("Failed to add skipped tiny orders to database: {err}"),
+    }
+}
+
+/// Cancels the active pricing task of the order with the given ID, returning whether there was
+/// one.
+fn cancel_active_pricing(
+    order_id: &str,
+    active_tasks: &BTreeMap<U256, BTreeMap<OrderId, CancellationToken>>,
+) -> bool {
+    let task_token = active_tasks
+        .values()
+        .flat_map(|order_tasks| order_tasks.iter())
+        .find(|(id, _)| id.to_string() == order_id)
+        .map(|(_, task_token)| task_token);
+    match task_token {
+        Some(task_token) => {
+            task_token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+async fn recv_cancel_pricing(
+    rx: &mut Option<MutexGuard<'_, mpsc::Receiver<CancelPricingRequest>>>,
+) -> Option<CancelPricingRequest> {
+    match rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 /// Format active pricing tasks for logging, limiting to first 3 and showing total count
 fn format_active_tasks(
-    active_tasks: &BTreeMap<U256, BTreeMap<String, CancellationToken>>,
+    active_tasks: &BTreeMap<U256, BTreeMap<OrderId, CancellationToken>>,
 ) -> String {
-    let mut order_iter = active_tasks.values().flat_map(|orders| orders.keys().cloned());
+    let mut order_iter =
+        active_tasks.values().flat_map(|orders| orders.keys().map(ToString::to_string));
 
     let first_three: Vec<String> = order_iter.by_ref().take(3).collect();
     let remaining_count = order_iter.count();
@@ -1250,6 +2429,75 @@ fn format_active_tasks(
 
 /// Returns the maximum cycles that can be proven within a given time period
 /// based on the proving rate provided, in khz.
+/// Checks of an order that need neither the chain nor the DB: its expiration, and the client and
+/// image filters of the market config.
+///
+/// These run for every order priced, before any RPC call. See `benches/pricing.rs`.
+pub(crate) fn check_order_static(
+    order: &OrderRequest,
+    order_id: &str,
+    market: &MarketConf,
+    now: u64,
+) -> Option<SkipReason> {
+    let expiration = if order.fulfillment_type == FulfillmentType::FulfillAfterLockExpire {
+        order.request.offer.biddingStart + order.request.offer.timeout as u64
+    } else {
+        order.request.offer.biddingStart + order.request.offer.lockTimeout as u64
+    };
+
+    if expiration <= now {
+        tracing::info!("Removing order {order_id} because it has expired");
+        return Some(SkipReason::Expired);
+    };
+
+    // Does the order expire within the min deadline, or the base of the deadline margin
+    let min_deadline = market.deadline_margin_secs(None, None);
+    let seconds_left = expiration.saturating_sub(now);
+    if seconds_left <= min_deadline {
+        tracing::info!("Removing order {order_id} because it expires within min_deadline: {seconds_left}, min_deadline: {min_deadline}");
+        return Some(SkipReason::InsufficientDeadline);
+    }
+
+    // Initial sanity checks:
+    let client_addr = order.request.client_address();
+    if let Some(allow_addresses) = &market.allow_client_addresses {
+        if !allow_addresses.contains(&client_addr) {
+            tracing::info!(
+                "Removing order {order_id} from {client_addr} because it is not in allowed addrs"
+            );
+            return Some(SkipReason::ClientNotAllowed);
+        }
+    }
+
+    if let Some(deny_addresses) = &market.deny_requestor_addresses {
+        if deny_addresses.contains(&client_addr) {
+            tracing::info!(
+                "Removing order {order_id} from {client_addr} because it is in denied addrs"
+            );
+            return Some(SkipReason::ClientDenied);
+        }
+    }
+
+    let image_id = order.request.requirements.imageId;
+    if let Some(allow_images) = &market.allow_image_ids {
+        if !allow_images.contains(&image_id) {
+            tracing::info!("Removing order {order_id} for image {image_id} because it is not in allowed images");
+            return Some(SkipReason::ImageNotAllowed);
+        }
+    }
+
+    if let Some(deny_images) = &market.deny_image_ids {
+        if deny_images.contains(&image_id) {
+            tracing::info!(
+                "Removing order {order_id} for image {image_id} because it is in denied images"
+            );
+            return Some(SkipReason::ImageDenied);
+        }
+    }
+
+    None
+}
+
 fn calculate_max_cycles_for_time(prove_khz: u64, time_seconds: u64) -> u64 {
     (prove_khz.saturating_mul(1_000)).saturating_mul(time_seconds)
 }
@@ -1261,8 +2509,11 @@ pub(crate) mod tests {
     use super::*;
     use crate::{
         chain_monitor::ChainMonitorService,
+        config::RequestorTierConf,
         db::SqliteDb,
+        input_dedup::InputDedup,
         provers::{DefaultProver, Prover},
+        proving_capacity::ProvingCapacityTracker,
         FulfillmentType, OrderStatus,
     };
     use alloy::{
@@ -1369,7 +2620,10 @@ pub(crate) mod tests {
                 target_timestamp: None,
                 image_id: None,
                 input_id: None,
+                assumption_ids: None,
                 expire_timestamp: None,
+                provenance: None,
+                pricing_checkpoint: None,
                 client_sig: Bytes::new(),
                 fulfillment_type: params.fulfillment_type,
                 boundless_market_address: *boundless_market_address,
@@ -1420,7 +2674,10 @@ pub(crate) mod tests {
                 target_timestamp: None,
                 image_id: None,
                 input_id: None,
+                assumption_ids: None,
                 expire_timestamp: None,
+                provenance: None,
+                pricing_checkpoint: None,
                 client_sig: Bytes::new(),
                 fulfillment_type: params.fulfillment_type,
                 boundless_market_address: *boundless_market_address,
@@ -1515,19 +2772,25 @@ pub(crate) mod tests {
             const TEST_CHANNEL_CAPACITY: usize = 50;
             let (_new_order_tx, new_order_rx) = mpsc::channel(TEST_CHANNEL_CAPACITY);
             let (priced_orders_tx, priced_orders_rx) = mpsc::channel(TEST_CHANNEL_CAPACITY);
-            let (order_state_tx, _) = tokio::sync::broadcast::channel(TEST_CHANNEL_CAPACITY);
+            let events = EventBus::new(TEST_CHANNEL_CAPACITY);
 
+            let capacity_tracker =
+                Arc::new(ProvingCapacityTracker::new(db.clone(), prover.clone()));
+            let input_dedup = Arc::new(InputDedup::new(prover.clone()));
             let picker = OrderPicker::new(
                 db.clone(),
                 config,
                 prover,
+                anvil.chain_id(),
                 market_address,
                 provider.clone(),
                 chain_monitor,
                 new_order_rx,
                 priced_orders_tx,
                 self.stake_token_decimals.unwrap_or(6),
-                order_state_tx,
+                events,
+                capacity_tracker,
+                input_dedup,
             );
 
             PickerTestCtx {
@@ -1564,6 +2827,95 @@ pub(crate) mod tests {
         assert_eq!(priced_order.target_timestamp, Some(0));
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn resume_pricing_from_checkpoint() {
+        let mut ctx = PickerTestCtxBuilder::default().build().await;
+
+        let order = ctx.generate_next_order(Default::default()).await;
+        let request = order.request.clone();
+        let client_sig = order.client_sig.clone();
+        let locked = ctx.picker.price_order_and_update_state(order, CancellationToken::new()).await;
+        assert!(locked);
+        let priced_order = ctx.priced_orders_rx.try_recv().unwrap();
+        assert!(!logs_contain("Resuming pricing of"));
+
+        // Queue the order again with the uploads of the first pricing, as if it was cancelled
+        let mut order = Box::new(OrderRequest::new(
+            request,
+            client_sig,
+            priced_order.fulfillment_type,
+            priced_order.boundless_market_address,
+            priced_order.chain_id,
+        ));
+        order.pricing_checkpoint = Some(PricingCheckpoint {
+            image_id: priced_order.image_id.clone(),
+            input_id: priced_order.input_id.clone(),
+        });
+        ctx.picker.preflight_cache.invalidate_all();
+        let locked = ctx.picker.price_order_and_update_state(order, CancellationToken::new()).await;
+        assert!(locked);
+        assert!(logs_contain("with uploaded image"));
+        assert!(logs_contain("with uploaded input"));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn shadow_pricing_recorded() {
+        let config = ConfigLock::default();
+        {
+            let mut config = config.load_write().unwrap();
+            config.market.mcycle_price = "0.0000001".into();
+            config.market.shadow_pricing =
+                Some(ShadowPricingConf { mcycle_price: Some("1".into()), ..Default::default() });
+        }
+        let mut ctx = PickerTestCtxBuilder::default().with_config(config).build().await;
+
+        let order = ctx.generate_next_order(Default::default()).await;
+        let order_id = order.id();
+
+        let locked = ctx.picker.price_order_and_update_state(order, CancellationToken::new()).await;
+        assert!(locked);
+
+        // Shadow decision does not affect the live decision
+        let priced_order = ctx.priced_orders_rx.try_recv().unwrap();
+        assert_eq!(priced_order.target_timestamp, Some(0));
+
+        let record = ctx.db.get_shadow_pricing_record(&order_id).await.unwrap().unwrap();
+        assert_eq!(record.live, PricingDecision::Lock { target_timestamp: 0 });
+        assert_eq!(record.shadow, PricingDecision::Skip);
+        assert!(logs_contain("Shadow pricing diverged"));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn dry_run_records_without_locking() {
+        let config = ConfigLock::default();
+        {
+            let mut config = config.load_write().unwrap();
+            config.market.mcycle_price = "0.0000001".into();
+            config.market.dry_run = true;
+        }
+        let mut ctx = PickerTestCtxBuilder::default().with_config(config).build().await;
+
+        let order = ctx.generate_next_order(Default::default()).await;
+        let order_id = order.id();
+
+        let locked = ctx.picker.price_order_and_update_state(order, CancellationToken::new()).await;
+        assert!(!locked);
+        assert!(ctx.priced_orders_rx.try_recv().is_err());
+
+        let db_order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(db_order.status, OrderStatus::Skipped);
+        assert_eq!(db_order.skip_reason, Some(SkipReason::DryRun));
+
+        let record = ctx.db.get_dry_run_record(&order_id).await.unwrap().unwrap();
+        assert_eq!(record.decision, PricingDecision::Lock { target_timestamp: 0 });
+        assert!(record.reward > U256::ZERO);
+        assert!(record.estimated_profit.is_some());
+        assert!(logs_contain("Dry run"));
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn skip_bad_predicate() {
@@ -1587,6 +2939,7 @@ pub(crate) mod tests {
 
         let db_order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
         assert_eq!(db_order.status, OrderStatus::Skipped);
+        assert_eq!(db_order.skip_reason, Some(SkipReason::PredicateFailed));
 
         assert!(logs_contain("predicate check failed, skipping"));
     }
@@ -1605,6 +2958,13 @@ pub(crate) mod tests {
         // set an unsupported selector
         order.request.requirements.selector = FixedBytes::from(Selector::Groth16V1_1 as u32);
         let order_id = order.id();
+        let rebroadcast = Box::new(OrderRequest::new(
+            order.request.clone(),
+            order.client_sig.clone(),
+            FulfillmentType::FulfillAfterLockExpire,
+            order.boundless_market_address,
+            order.chain_id,
+        ));
 
         let _request_id =
             ctx.boundless_market.submit_request(&order.request, &ctx.signer(0)).await.unwrap();
@@ -1616,6 +2976,15 @@ pub(crate) mod tests {
         assert_eq!(db_order.status, OrderStatus::Skipped);
 
         assert!(logs_contain("has an unsupported selector requirement"));
+
+        // A re-broadcast of the request is skipped by its tombstone without pricing it
+        let rebroadcast_id = rebroadcast.id();
+        let locked =
+            ctx.picker.price_order_and_update_state(rebroadcast, CancellationToken::new()).await;
+        assert!(!locked);
+        let db_order = ctx.db.get_order(&rebroadcast_id).await.unwrap().unwrap();
+        assert_eq!(db_order.skip_reason, Some(SkipReason::UnsupportedSelector));
+        assert!(logs_contain("its request was previously skipped"));
     }
 
     #[tokio::test]
@@ -1876,6 +3245,33 @@ pub(crate) mod tests {
         assert!(logs_contain("because it is in denied addrs"));
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn skip_denied_image() {
+        let config = ConfigLock::default();
+        let ctx = PickerTestCtxBuilder::default().with_config(config.clone()).build().await;
+
+        let order = ctx.generate_next_order(Default::default()).await;
+        {
+            let mut cfg = config.load_write().unwrap();
+            cfg.market.mcycle_price = "0.0000001".into();
+            cfg.market.deny_image_ids =
+                Some([order.request.requirements.imageId].into_iter().collect());
+        }
+
+        let _request_id =
+            ctx.boundless_market.submit_request(&order.request, &ctx.signer(0)).await.unwrap();
+
+        let order_id = order.id();
+        let locked = ctx.picker.price_order_and_update_state(order, CancellationToken::new()).await;
+        assert!(!locked);
+
+        let db_order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(db_order.status, OrderStatus::Skipped);
+
+        assert!(logs_contain("because it is in denied images"));
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn resume_order_pricing() {
@@ -1946,6 +3342,25 @@ pub(crate) mod tests {
         let priced = ctx.priced_orders_rx.try_recv().unwrap();
         assert_eq!(priced.id(), order1_id);
 
+        // The stake of the first order is reserved until the order monitor locks or drops it
+        let order = ctx
+            .generate_next_order(OrderParams {
+                order_index: 2,
+                lock_stake: U256::from(100),
+                ..Default::default()
+            })
+            .await;
+        assert!(!ctx.picker.price_order_and_update_state(order, CancellationToken::new()).await);
+        ctx.picker.reservations.abort(&order1_id);
+        let order = ctx
+            .generate_next_order(OrderParams {
+                order_index: 3,
+                lock_stake: U256::from(100),
+                ..Default::default()
+            })
+            .await;
+        assert!(ctx.picker.price_order_and_update_state(order, CancellationToken::new()).await);
+
         let order = ctx
             .generate_next_order(OrderParams {
                 lock_stake: lockin_stake + U256::from(1),
@@ -1977,6 +3392,87 @@ pub(crate) mod tests {
         assert!(logs_contain("Removing high stake order"));
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn skip_high_risk_order() {
+        let config = ConfigLock::default();
+        {
+            let mut config = config.load_write().unwrap();
+            config.market.mcycle_price = "0.0000001".into();
+            config.market.max_stake = "10".into();
+            config.market.max_risk_score = Some(20);
+        }
+        let lock_stake: U256 = parse_units("5", 18).unwrap().into();
+        let mut ctx = PickerTestCtxBuilder::default()
+            .with_initial_hp(parse_units("10", 18).unwrap().into())
+            .with_config(config)
+            .build()
+            .await;
+
+        // Half of max_stake is at risk, but the requestor has no failed orders
+        let order = ctx.generate_next_order(OrderParams { lock_stake, ..Default::default() }).await;
+        let client_addr = order.request.client_address();
+        assert!(ctx.picker.price_order_and_update_state(order, CancellationToken::new()).await);
+        ctx.priced_orders_rx.try_recv().unwrap();
+
+        ctx.db.record_client_event(client_addr, ClientEvent::Failed).await.unwrap();
+        let order = ctx
+            .generate_next_order(OrderParams { order_index: 2, lock_stake, ..Default::default() })
+            .await;
+        let order_id = order.id();
+        assert!(!ctx.picker.price_order_and_update_state(order, CancellationToken::new()).await);
+        assert!(logs_contain("exceeds max_risk_score 20"));
+        assert_eq!(
+            ctx.db.get_order(&order_id).await.unwrap().unwrap().status,
+            OrderStatus::Skipped
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn max_stake_uses_stake_token_decimals() {
+        for decimals in [6, 8, 18] {
+            let config = ConfigLock::default();
+            config.load_write().unwrap().market.max_stake = "2.5".into();
+            let ctx = PickerTestCtxBuilder::default()
+                .with_stake_token_decimals(decimals)
+                .with_config(config)
+                .build()
+                .await;
+
+            let mut order = ctx
+                .generate_next_order(OrderParams {
+                    lock_stake: parse_units("2.6", decimals).unwrap().into(),
+                    ..Default::default()
+                })
+                .await;
+            let outcome = ctx
+                .picker
+                .price_order(&mut order, &mut Default::default(), &CancellationToken::new())
+                .await;
+            assert!(
+                matches!(outcome, Ok(Skip { reason: SkipReason::StakeTooHigh })),
+                "{decimals} decimals: {outcome:?}"
+            );
+
+            // At max stake, the order is only skipped for lack of stake balance
+            let mut order = ctx
+                .generate_next_order(OrderParams {
+                    lock_stake: parse_units("2.5", decimals).unwrap().into(),
+                    ..Default::default()
+                })
+                .await;
+            let outcome = ctx
+                .picker
+                .price_order(&mut order, &mut Default::default(), &CancellationToken::new())
+                .await;
+            assert!(
+                matches!(outcome, Ok(Skip { reason: SkipReason::InsufficientStake })),
+                "{decimals} decimals: {outcome:?}"
+            );
+        }
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn use_gas_to_fulfill_estimate_from_config() {
@@ -1997,7 +3493,10 @@ pub(crate) mod tests {
         let order = ctx.priced_orders_rx.try_recv().unwrap();
         ctx.db.insert_accepted_request(&order, order.request.offer.minPrice).await.unwrap();
 
-        assert_eq!(ctx.picker.estimate_gas_to_fulfill_pending().await.unwrap(), fulfill_gas);
+        assert_eq!(
+            ctx.picker.estimate_gas_to_fulfill_pending(ctx.anvil.chain_id()).await.unwrap(),
+            fulfill_gas
+        );
 
         // add another order
         let order =
@@ -2008,7 +3507,10 @@ pub(crate) mod tests {
         ctx.db.insert_accepted_request(&order, order.request.offer.minPrice).await.unwrap();
 
         // gas estimate stacks (until estimates factor in bundling)
-        assert_eq!(ctx.picker.estimate_gas_to_fulfill_pending().await.unwrap(), 2 * fulfill_gas);
+        assert_eq!(
+            ctx.picker.estimate_gas_to_fulfill_pending(ctx.anvil.chain_id()).await.unwrap(),
+            2 * fulfill_gas
+        );
     }
 
     #[tokio::test]
@@ -2143,7 +3645,7 @@ pub(crate) mod tests {
 
         // Check logs for the expected message about skipping mcycle limit
         assert!(logs_contain(&format!(
-            "Order {order_id} exec limit skipped due to client {} being part of priority_requestor_addresses.",
+            "Order {order_id} exec limit skipped due to client {} being a priority requestor or in a tier that skips exec limits.",
             ctx.provider.default_signer_address()
         )));
 
@@ -2164,6 +3666,36 @@ pub(crate) mod tests {
         assert!(logs_contain("setting exec limit to max_mcycle_limit"));
     }
 
+    #[tokio::test]
+    async fn client_reputation_exempts_tiers_skipping_exec_limits() {
+        let ctx = PickerTestCtxBuilder::default().build().await;
+        let tier = RequestorTierConf {
+            name: "enterprise".into(),
+            requestors: vec![ctx.provider.default_signer_address()],
+            mcycle_price: None,
+            mcycle_price_stake_token: None,
+            max_stake: None,
+            skip_exec_limits: false,
+            priority: 0,
+        };
+        {
+            let mut config = ctx.picker.config.load_write().unwrap();
+            config.market.client_reputation = Some(ClientReputationConf {
+                min_orders: 10,
+                min_score_percent: 50,
+                scale_exec_limits: true,
+                max_average_journal_bytes: None,
+            });
+            config.market.requestor_tiers = vec![tier.clone()];
+        }
+        let order = ctx.generate_next_order(Default::default()).await;
+        assert!(ctx.picker.client_reputation(&order).await.unwrap().is_some());
+
+        ctx.picker.config.load_write().unwrap().market.requestor_tiers =
+            vec![RequestorTierConf { skip_exec_limits: true, ..tier }];
+        assert!(ctx.picker.client_reputation(&order).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_deadline_exec_limit_and_peak_prove_khz() {
@@ -2199,6 +3731,42 @@ pub(crate) mod tests {
         assert!(logs_contain("peak_prove_khz config"));
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn test_deadline_exec_limit_includes_proving_queue() {
+        let config = ConfigLock::default();
+        {
+            config.load_write().unwrap().market.mcycle_price = "0.0000001".into();
+            config.load_write().unwrap().market.peak_prove_khz = Some(1);
+            config.load_write().unwrap().market.min_deadline = 10;
+        }
+        let ctx = PickerTestCtxBuilder::default().with_config(config).build().await;
+
+        // At 1 khz, the additional proof cycles of the committed order alone take longer than
+        // the lock timeout to prove.
+        let committed =
+            ctx.generate_next_order(OrderParams { order_index: 2, ..Default::default() }).await;
+        ctx.db.insert_accepted_request(&committed, U256::ZERO).await.unwrap();
+
+        let order = ctx
+            .generate_next_order(OrderParams {
+                min_price: parse_ether("10").unwrap(),
+                max_price: parse_ether("10").unwrap(),
+                bidding_start: now_timestamp(),
+                lock_timeout: 150,
+                timeout: 300,
+                ..Default::default()
+            })
+            .await;
+
+        let order_id = order.id();
+        let locked = ctx.picker.price_order_and_update_state(order, CancellationToken::new()).await;
+        assert!(!locked);
+        assert!(logs_contain(&format!(
+            "Order {order_id} has no time left to prove within deadline"
+        )));
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_capacity_change() {
@@ -2275,8 +3843,11 @@ pub(crate) mod tests {
         let stake_reward = order.request.offer.stake_reward_if_locked_and_not_fulfilled();
         assert_eq!(stake_reward, U256::from(1));
 
-        let locked = ctx.picker.price_order(&mut order).await;
-        assert!(matches!(locked, Ok(OrderPricingOutcome::Skip)));
+        let locked = ctx
+            .picker
+            .price_order(&mut order, &mut Default::default(), &CancellationToken::new())
+            .await;
+        assert!(matches!(locked, Ok(OrderPricingOutcome::Skip { .. })));
 
         assert!(logs_contain(&format!(
             "Removing order {order_id} because its exec limit is too low"
@@ -2298,8 +3869,14 @@ pub(crate) mod tests {
         let stake_reward2 = order2.request.offer.stake_reward_if_locked_and_not_fulfilled();
         assert_eq!(stake_reward2, U256::from(10));
 
-        let locked = ctx.picker.price_order(&mut order2).await;
-        assert!(matches!(locked, Ok(OrderPricingOutcome::Skip)));
+        let locked = ctx
+            .picker
+            .price_order(&mut order2, &mut Default::default(), &CancellationToken::new())
+            .await;
+        assert!(matches!(
+            locked,
+            Ok(OrderPricingOutcome::SessionLimitExceeded { bound: ExecLimitBound::Price })
+        ));
 
         // Stake token denom offsets the mcycle multiplier, so for 1stake/mcycle, this will be 10
         assert!(logs_contain(&format!("exec limit cycles for order {order2_id}: 10")));
@@ -2324,8 +3901,14 @@ pub(crate) mod tests {
 
         assert!(ctx.db.is_request_locked(U256::from(order.request.id)).await?);
 
-        let pricing_outcome = ctx.picker.price_order(&mut order).await?;
-        assert!(matches!(pricing_outcome, OrderPricingOutcome::Skip));
+        let pricing_outcome = ctx
+            .picker
+            .price_order(&mut order, &mut Default::default(), &CancellationToken::new())
+            .await?;
+        assert!(matches!(
+            pricing_outcome,
+            OrderPricingOutcome::Skip { reason: SkipReason::AlreadyLocked }
+        ));
 
         assert!(logs_contain(&format!("Order {order_id} is already locked, skipping")));
 
@@ -2349,9 +3932,12 @@ pub(crate) mod tests {
             chain_id: order1.chain_id,
             image_id: order1.image_id.clone(),
             input_id: order1.input_id.clone(),
+            assumption_ids: order1.assumption_ids.clone(),
             total_cycles: order1.total_cycles,
             target_timestamp: order1.target_timestamp,
             expire_timestamp: order1.expire_timestamp,
+            provenance: None,
+            pricing_checkpoint: None,
         });
 
         assert_eq!(order1.id(), order2.id(), "Both orders should have the same ID");
@@ -2395,8 +3981,14 @@ pub(crate) mod tests {
 
         assert!(ctx.db.is_request_fulfilled(U256::from(order.request.id)).await?);
 
-        let pricing_outcome = ctx.picker.price_order(&mut order).await?;
-        assert!(matches!(pricing_outcome, OrderPricingOutcome::Skip));
+        let pricing_outcome = ctx
+            .picker
+            .price_order(&mut order, &mut Default::default(), &CancellationToken::new())
+            .await?;
+        assert!(matches!(
+            pricing_outcome,
+            OrderPricingOutcome::Skip { reason: SkipReason::AlreadyFulfilled }
+        ));
 
         assert!(logs_contain(&format!("Order {order_id} is already fulfilled, skipping")));
 
@@ -2454,7 +4046,8 @@ pub(crate) mod tests {
     #[tokio::test]
     async fn test_handle_lock_event() {
         let ctx = PickerTestCtxBuilder::default().build().await;
-        let mut active_tasks: BTreeMap<U256, BTreeMap<String, CancellationToken>> = BTreeMap::new();
+        let mut active_tasks: BTreeMap<U256, BTreeMap<OrderId, CancellationToken>> =
+            BTreeMap::new();
         let mut pending_orders: Vec<Box<OrderRequest>> = Vec::new();
 
         let lock_and_fulfill_order = ctx
@@ -2480,8 +4073,9 @@ pub(crate) mod tests {
 
         // Add active tasks using actual order IDs
         let mut order_tasks = BTreeMap::new();
-        order_tasks.insert(lock_and_fulfill_order.id(), lock_and_fulfill_token.clone());
-        order_tasks.insert(fulfill_after_expire_order.id(), fulfill_after_expire_token.clone());
+        order_tasks.insert(lock_and_fulfill_order.order_id(), lock_and_fulfill_token.clone());
+        order_tasks
+            .insert(fulfill_after_expire_order.order_id(), fulfill_after_expire_token.clone());
         active_tasks.insert(request_id, order_tasks);
 
         pending_orders.push(lock_and_fulfill_order);
@@ -2499,7 +4093,7 @@ pub(crate) mod tests {
         let remaining_tasks = active_tasks.get(&request_id).unwrap();
         assert_eq!(remaining_tasks.len(), 1);
         let remaining_order_id = remaining_tasks.keys().next().unwrap();
-        assert!(remaining_order_id.contains("FulfillAfterLockExpire"));
+        assert_eq!(remaining_order_id.fulfillment_type, FulfillmentType::FulfillAfterLockExpire);
 
         assert_eq!(pending_orders.len(), 1);
         assert_eq!(pending_orders[0].fulfillment_type, FulfillmentType::FulfillAfterLockExpire);
@@ -2509,7 +4103,8 @@ pub(crate) mod tests {
     async fn test_handle_fulfill_event() {
         // Create test context and orders
         let ctx = PickerTestCtxBuilder::default().build().await;
-        let mut active_tasks: BTreeMap<U256, BTreeMap<String, CancellationToken>> = BTreeMap::new();
+        let mut active_tasks: BTreeMap<U256, BTreeMap<OrderId, CancellationToken>> =
+            BTreeMap::new();
         let mut pending_orders: Vec<Box<OrderRequest>> = Vec::new();
 
         let lock_and_fulfill_order = ctx
@@ -2534,8 +4129,8 @@ pub(crate) mod tests {
         let token2 = CancellationToken::new();
 
         let mut order_tasks = BTreeMap::new();
-        order_tasks.insert(lock_and_fulfill_order.id(), token1.clone());
-        order_tasks.insert(fulfill_after_expire_order.id(), token2.clone());
+        order_tasks.insert(lock_and_fulfill_order.order_id(), token1.clone());
+        order_tasks.insert(fulfill_after_expire_order.order_id(), token2.clone());
         active_tasks.insert(request_id, order_tasks);
 
         pending_orders.push(lock_and_fulfill_order);
@@ -2679,14 +4274,19 @@ pub(crate) mod tests {
         );
 
         // Process order1 and order2 concurrently to test cache atomicity
-        let (pricing1, pricing2) =
-            tokio::join!(ctx.picker.price_order(&mut order1), ctx.picker.price_order(&mut order2));
+        let (pricing1, pricing2) = tokio::join!(
+            ctx.picker.price_order(&mut order1, &mut Default::default(), &CancellationToken::new()),
+            ctx.picker.price_order(&mut order2, &mut Default::default(), &CancellationToken::new())
+        );
 
         assert!(pricing1.is_ok(), "Order1 pricing should succeed");
         assert!(pricing2.is_ok(), "Order2 pricing should succeed");
 
         // Process order3 (should use cache)
-        let pricing3 = ctx.picker.price_order(&mut order3).await;
+        let pricing3 = ctx
+            .picker
+            .price_order(&mut order3, &mut Default::default(), &CancellationToken::new())
+            .await;
         assert!(pricing3.is_ok(), "Order3 pricing should succeed");
 
         // Check preflight calls - should only be called once since all orders are identical
@@ -2756,12 +4356,25 @@ pub(crate) mod tests {
             .await;
 
         // Process short timeout order first - this should hit session limit and cache the Skip result
-        let result1 = ctx.picker.price_order(&mut low_timeout_order).await;
-        assert!(matches!(result1, Ok(OrderPricingOutcome::Skip)));
+        let result1 = ctx
+            .picker
+            .price_order(&mut low_timeout_order, &mut Default::default(), &CancellationToken::new())
+            .await;
+        assert!(matches!(
+            result1,
+            Ok(OrderPricingOutcome::SessionLimitExceeded { bound: ExecLimitBound::Deadline })
+        ));
 
         // Process long timeout order second - this should NOT reuse the low-limit cached result
         // It should succeed with its own higher exec limit via a new preflight call
-        let result2 = ctx.picker.price_order(&mut high_timeout_order).await;
+        let result2 = ctx
+            .picker
+            .price_order(
+                &mut high_timeout_order,
+                &mut Default::default(),
+                &CancellationToken::new(),
+            )
+            .await;
         assert!(matches!(result2, Ok(OrderPricingOutcome::Lock { .. })));
 
         // We expect 2 preflight calls since the orders have different deadline-based exec limits
@@ -2781,6 +4394,48 @@ pub(crate) mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn test_deadline_bound_session_limit_not_skipped() -> Result<()> {
+        let mock_prover = Arc::new(MockPreflightTracker::new());
+        let image_id = Digest::from(LOOP_ID).to_string();
+        mock_prover.upload_image(&image_id, LOOP_ELF.to_vec()).await.unwrap();
+
+        let config = ConfigLock::default();
+        {
+            config.load_write().unwrap().market.mcycle_price = "0.0000001".into();
+            config.load_write().unwrap().market.peak_prove_khz = Some(1000);
+            config.load_write().unwrap().market.min_deadline = 0;
+        }
+        let ctx = PickerTestCtxBuilder::default()
+            .with_prover(mock_prover.clone())
+            .with_config(config)
+            .build()
+            .await;
+
+        // Profitable, but too large to prove before the lock expires at peak_prove_khz.
+        let order = ctx
+            .generate_loop_order(
+                OrderParams {
+                    min_price: parse_ether("100.0").unwrap(),
+                    max_price: parse_ether("100.0").unwrap(),
+                    timeout: 3600,
+                    lock_timeout: 2,
+                    ..Default::default()
+                },
+                5_000_000,
+            )
+            .await;
+        let order_id = order.id();
+
+        assert!(!ctx.picker.price_order_and_update_state(order, CancellationToken::new()).await);
+        assert!(logs_contain(&format!("bound by {:?}", ExecLimitBound::Deadline)));
+        assert!(logs_contain("re-evaluated after lock expiry"));
+        assert!(ctx.db.get_order(&order_id).await?.is_none());
+
+        Ok(())
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_concurrent_preflights_with_cancellation() -> Result<()> {
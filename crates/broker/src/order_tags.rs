@@ -0,0 +1,239 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use alloy::primitives::utils::parse_ether;
+use boundless_market::contracts::ProofRequest;
+
+use crate::{config::OrderTagConf, Order, OrderRequest};
+
+impl OrderTagConf {
+    /// Whether the request matches all predicates of this tag.
+    pub(crate) fn matches(&self, request: &ProofRequest) -> bool {
+        if let Some(requestors) = &self.requestors {
+            if !requestors.contains(&request.client_address()) {
+                return false;
+            }
+        }
+        if let Some(image_ids) = &self.image_ids {
+            if !image_ids.contains(&request.requirements.imageId) {
+                return false;
+            }
+        }
+        let price = request.offer.maxPrice;
+        if let Some(min_price) = &self.min_price {
+            match parse_ether(min_price) {
+                Ok(min_price) if price >= min_price => {}
+                Ok(_) => return false,
+                Err(err) => {
+                    tracing::warn!(
+                        "Invalid min_price {min_price} for order tag {}: {err}",
+                        self.name
+                    );
+                    return false;
+                }
+            }
+        }
+        if let Some(max_price) = &self.max_price {
+            match parse_ether(max_price) {
+                Ok(max_price) if price <= max_price => {}
+                Ok(_) => return false,
+                Err(err) => {
+                    tracing::warn!(
+                        "Invalid max_price {max_price} for order tag {}: {err}",
+                        self.name
+                    );
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Names of the tags matching the request.
+pub(crate) fn order_tags<'a>(tags: &'a [OrderTagConf], request: &ProofRequest) -> Vec<&'a str> {
+    tags.iter().filter(|tag| tag.matches(request)).map(|tag| tag.name.as_str()).collect()
+}
+
+/// Highest priority of the tags matching the request, or 0 if it matches none.
+pub(crate) fn tag_priority(tags: &[OrderTagConf], request: &ProofRequest) -> u32 {
+    tags.iter().filter(|tag| tag.matches(request)).map(|tag| tag.priority).max().unwrap_or(0)
+}
+
+/// Drop orders that would exceed the `max_concurrent_proofs` of one of their tags, given the
+/// orders already committed to.
+///
+/// Orders are considered in the given order. Dropped orders are kept by the caller for later
+/// iterations, as capacity for the tag may free up.
+pub(crate) fn apply_tag_capacity_limits<T>(
+    orders: Vec<T>,
+    committed_orders: &[Order],
+    tags: &[OrderTagConf],
+) -> Vec<T>
+where
+    T: AsRef<OrderRequest>,
+{
+    let limited_tags: Vec<_> =
+        tags.iter().filter(|tag| tag.max_concurrent_proofs.is_some()).collect();
+    if limited_tags.is_empty() {
+        return orders;
+    }
+
+    let mut in_progress: HashMap<&str, u32> = HashMap::new();
+    for order in committed_orders {
+        for tag in limited_tags.iter().filter(|tag| tag.matches(&order.request)) {
+            *in_progress.entry(tag.name.as_str()).or_default() += 1;
+        }
+    }
+
+    orders
+        .into_iter()
+        .filter(|order| {
+            let request = &order.as_ref().request;
+            let matching: Vec<_> = limited_tags.iter().filter(|tag| tag.matches(request)).collect();
+            if let Some(full) = matching.iter().find(|tag| {
+                in_progress.get(tag.name.as_str()).copied().unwrap_or(0)
+                    >= tag.max_concurrent_proofs.unwrap()
+            }) {
+                tracing::debug!(
+                    "Order {} deferred, tag {} is at its limit of {} concurrent proofs",
+                    order.as_ref().id(),
+                    full.name,
+                    full.max_concurrent_proofs.unwrap()
+                );
+                return false;
+            }
+            for tag in matching {
+                *in_progress.entry(tag.name.as_str()).or_default() += 1;
+            }
+            true
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{now_timestamp, FulfillmentType};
+    use alloy::primitives::{Address, Bytes, B256, U256};
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, RequestId, RequestInput, RequestInputType, Requirements,
+    };
+    use risc0_zkvm::sha::Digest;
+
+    fn request(client: Address, image_id: Digest, max_price: &str) -> ProofRequest {
+        ProofRequest::new(
+            RequestId::new(client, 1),
+            Requirements::new(
+                image_id,
+                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+            ),
+            "http://risczero.com/image",
+            RequestInput { inputType: RequestInputType::Inline, data: Default::default() },
+            Offer {
+                minPrice: U256::ZERO,
+                maxPrice: parse_ether(max_price).unwrap(),
+                biddingStart: now_timestamp(),
+                timeout: 200,
+                lockTimeout: 100,
+                rampUpPeriod: 1,
+                lockStake: U256::ZERO,
+            },
+        )
+    }
+
+    fn tag(name: &str) -> OrderTagConf {
+        OrderTagConf {
+            name: name.into(),
+            requestors: None,
+            image_ids: None,
+            min_price: None,
+            max_price: None,
+            priority: 0,
+            max_concurrent_proofs: None,
+        }
+    }
+
+    fn order_request(request: ProofRequest) -> Box<OrderRequest> {
+        Box::new(OrderRequest::new(
+            request,
+            Bytes::new(),
+            FulfillmentType::LockAndFulfill,
+            Address::ZERO,
+            1,
+        ))
+    }
+
+    #[test]
+    fn matches_all_predicates() {
+        let client = Address::repeat_byte(1);
+        let image_id = Digest::from([1u32; 8]);
+        let tags = [
+            OrderTagConf { requestors: Some(vec![client]), ..tag("client") },
+            OrderTagConf {
+                image_ids: Some(vec![B256::from(<[u8; 32]>::from(image_id))]),
+                ..tag("image")
+            },
+            OrderTagConf {
+                min_price: Some("0.01".into()),
+                max_price: Some("0.1".into()),
+                ..tag("mid-price")
+            },
+        ];
+
+        assert_eq!(
+            order_tags(&tags, &request(client, image_id, "0.05")),
+            ["client", "image", "mid-price"]
+        );
+        assert_eq!(order_tags(&tags, &request(client, Digest::ZERO, "1")), ["client"]);
+        assert!(order_tags(&tags, &request(Address::ZERO, Digest::ZERO, "0.001")).is_empty());
+    }
+
+    #[test]
+    fn priority_is_highest_matching_tag() {
+        let client = Address::repeat_byte(1);
+        let tags = [
+            OrderTagConf { requestors: Some(vec![client]), priority: 5, ..tag("client") },
+            OrderTagConf { min_price: Some("0.01".into()), priority: 2, ..tag("high-price") },
+        ];
+
+        assert_eq!(tag_priority(&tags, &request(client, Digest::ZERO, "1")), 5);
+        assert_eq!(tag_priority(&tags, &request(Address::ZERO, Digest::ZERO, "1")), 2);
+        assert_eq!(tag_priority(&tags, &request(Address::ZERO, Digest::ZERO, "0.001")), 0);
+    }
+
+    #[test]
+    fn tag_capacity_limits_include_committed_orders() {
+        let client = Address::repeat_byte(1);
+        let tags = [OrderTagConf {
+            requestors: Some(vec![client]),
+            max_concurrent_proofs: Some(2),
+            ..tag("client")
+        }];
+        let committed =
+            order_request(request(client, Digest::ZERO, "1")).to_proving_order(U256::from(1));
+
+        let orders = vec![
+            order_request(request(client, Digest::ZERO, "1")),
+            order_request(request(Address::ZERO, Digest::ZERO, "1")),
+            order_request(request(client, Digest::ZERO, "1")),
+        ];
+        let orders = apply_tag_capacity_limits(orders, &[committed], &tags);
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].request.client_address(), client);
+        assert_eq!(orders[1].request.client_address(), Address::ZERO);
+    }
+}
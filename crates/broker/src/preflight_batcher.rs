@@ -0,0 +1,339 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::ConfigLock,
+    provers::{PreflightJob, ProofResult, ProverError, ProverObj},
+};
+
+type PendingPreflight =
+    (PreflightJob, CancellationToken, oneshot::Sender<Result<ProofResult, ProverError>>);
+
+/// Groups preflight executions requested within a short window into a single
+/// [crate::provers::Prover::preflight_batch] call.
+///
+/// Orders selected for pricing in the same picker iteration reach preflight at around the same
+/// time, so collecting them for `market.preflight_batch_window_ms` lets the prover amortize
+/// session setup across them. Each caller receives its own result as soon as it completes.
+///
+/// Jobs of orders whose pricing is cancelled while they wait for the window are dropped from the
+/// batch rather than executed.
+#[derive(Clone)]
+pub(crate) struct PreflightBatcher {
+    prover: ProverObj,
+    config: ConfigLock,
+    pending: Arc<Mutex<Vec<PendingPreflight>>>,
+}
+
+impl PreflightBatcher {
+    pub(crate) fn new(prover: ProverObj, config: ConfigLock) -> Self {
+        Self { prover, config, pending: Arc::new(Mutex::new(Vec::new())) }
+    }
+
+    /// Preflight the job, unless `cancel_token` of the pricing of its order is cancelled before
+    /// the job is dispatched.
+    pub(crate) async fn preflight(
+        &self,
+        job: PreflightJob,
+        cancel_token: &CancellationToken,
+    ) -> Result<ProofResult, ProverError> {
+        let (window_ms, max_batch_size) = {
+            let config = self.config.lock_all()?;
+            (config.market.preflight_batch_window_ms, config.market.max_concurrent_preflights)
+        };
+        if cancel_token.is_cancelled() {
+            return Err(cancelled_err(&job));
+        }
+        if window_ms == 0 {
+            return self
+                .prover
+                .preflight(
+                    &job.image_id,
+                    &job.input_id,
+                    job.assumptions,
+                    job.executor_limit,
+                    &job.order_id,
+                )
+                .await;
+        }
+
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let (first, full) = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.push((job, cancel_token.clone(), reply_tx));
+            (pending.len() == 1, pending.len() >= max_batch_size as usize)
+        };
+
+        // The batch is flushed from a separate task, so that it is still submitted if the
+        // pricing task that started it is cancelled.
+        if full {
+            let batch = std::mem::take(&mut *self.pending.lock().unwrap());
+            tokio::spawn(submit_batch(self.prover.clone(), batch));
+        } else if first {
+            let batcher = self.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(window_ms)).await;
+                let batch = std::mem::take(&mut *batcher.pending.lock().unwrap());
+                if !batch.is_empty() {
+                    submit_batch(batcher.prover, batch).await;
+                }
+            });
+        }
+
+        reply_rx.await.unwrap_or_else(|_| {
+            Err(ProverError::ProverInternalError("Preflight batch ended without a result".into()))
+        })
+    }
+}
+
+fn cancelled_err(job: &PreflightJob) -> ProverError {
+    ProverError::ProverInternalError(format!(
+        "Pricing of order {} cancelled before preflight",
+        job.order_id
+    ))
+}
+
+async fn submit_batch(prover: ProverObj, batch: Vec<PendingPreflight>) {
+    let (jobs, replies): (Vec<_>, Vec<_>) = batch
+        .into_iter()
+        .filter_map(|(job, cancel_token, reply)| {
+            if cancel_token.is_cancelled() {
+                tracing::debug!("Dropping preflight of cancelled order {}", job.order_id);
+                let _ = reply.send(Err(cancelled_err(&job)));
+                return None;
+            }
+            Some((job, reply))
+        })
+        .unzip();
+    if jobs.is_empty() {
+        return;
+    }
+    let mut replies: Vec<_> = replies.into_iter().map(Some).collect();
+    tracing::debug!(
+        "Submitting batch of {} preflights: {}",
+        jobs.len(),
+        jobs.iter().map(|job| job.order_id.as_str()).collect::<Vec<_>>().join(", ")
+    );
+
+    let mut results = prover.preflight_batch(jobs);
+    while let Some((idx, res)) = results.next().await {
+        if let Some(reply) = replies.get_mut(idx).and_then(Option::take) {
+            // The receiver is gone if pricing of the order was cancelled.
+            let _ = reply.send(res);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use boundless_market_test_utils::{ECHO_ELF, ECHO_ID};
+    use futures::stream::BoxStream;
+    use risc0_zkvm::{sha::Digest, Receipt};
+
+    use super::*;
+    use crate::provers::{DefaultProver, Prover};
+
+    /// Prover recording the size of each preflight batch, delegating to the default prover.
+    struct BatchRecorder {
+        batch_sizes: Mutex<Vec<usize>>,
+        default_prover: DefaultProver,
+    }
+
+    #[async_trait]
+    impl Prover for BatchRecorder {
+        async fn has_image(&self, image_id: &str) -> Result<bool, ProverError> {
+            self.default_prover.has_image(image_id).await
+        }
+        async fn upload_input(&self, input: Vec<u8>) -> Result<String, ProverError> {
+            self.default_prover.upload_input(input).await
+        }
+        async fn upload_image(&self, image_id: &str, image: Vec<u8>) -> Result<(), ProverError> {
+            self.default_prover.upload_image(image_id, image).await
+        }
+        async fn preflight(
+            &self,
+            image_id: &str,
+            input_id: &str,
+            assumptions: Vec<String>,
+            executor_limit: Option<u64>,
+            order_id: &str,
+        ) -> Result<ProofResult, ProverError> {
+            self.default_prover
+                .preflight(image_id, input_id, assumptions, executor_limit, order_id)
+                .await
+        }
+        fn preflight_batch(
+            &self,
+            jobs: Vec<PreflightJob>,
+        ) -> BoxStream<'_, (usize, Result<ProofResult, ProverError>)> {
+            self.batch_sizes.lock().unwrap().push(jobs.len());
+            // Complete the batch out of order
+            futures::stream::iter(jobs.into_iter().enumerate().rev())
+                .then(|(idx, job)| async move {
+                    let res = self
+                        .preflight(
+                            &job.image_id,
+                            &job.input_id,
+                            job.assumptions,
+                            job.executor_limit,
+                            &job.order_id,
+                        )
+                        .await;
+                    (idx, res)
+                })
+                .boxed()
+        }
+        async fn prove_stark(
+            &self,
+            image_id: &str,
+            input_id: &str,
+            assumptions: Vec<String>,
+        ) -> Result<String, ProverError> {
+            self.default_prover.prove_stark(image_id, input_id, assumptions).await
+        }
+        async fn wait_for_stark(&self, proof_id: &str) -> Result<ProofResult, ProverError> {
+            self.default_prover.wait_for_stark(proof_id).await
+        }
+        async fn cancel_stark(&self, proof_id: &str) -> Result<(), ProverError> {
+            self.default_prover.cancel_stark(proof_id).await
+        }
+        async fn get_receipt(&self, proof_id: &str) -> Result<Option<Receipt>, ProverError> {
+            self.default_prover.get_receipt(proof_id).await
+        }
+        async fn get_preflight_journal(
+            &self,
+            proof_id: &str,
+        ) -> Result<Option<Vec<u8>>, ProverError> {
+            self.default_prover.get_preflight_journal(proof_id).await
+        }
+        async fn get_journal(&self, proof_id: &str) -> Result<Option<Vec<u8>>, ProverError> {
+            self.default_prover.get_journal(proof_id).await
+        }
+        async fn compress(&self, proof_id: &str) -> Result<String, ProverError> {
+            self.default_prover.compress(proof_id).await
+        }
+        async fn get_compressed_receipt(
+            &self,
+            proof_id: &str,
+        ) -> Result<Option<Vec<u8>>, ProverError> {
+            self.default_prover.get_compressed_receipt(proof_id).await
+        }
+    }
+
+    /// Job executing the echo guest, whose journal is the input.
+    async fn job(prover: &BatchRecorder, input: &str) -> PreflightJob {
+        let image_id = Digest::from(ECHO_ID).to_string();
+        prover.upload_image(&image_id, ECHO_ELF.to_vec()).await.unwrap();
+        PreflightJob {
+            image_id,
+            input_id: prover.upload_input(input.as_bytes().to_vec()).await.unwrap(),
+            assumptions: vec![],
+            executor_limit: None,
+            order_id: format!("order-{input}"),
+        }
+    }
+
+    fn setup(window_ms: u64, max_batch: u32) -> (Arc<BatchRecorder>, PreflightBatcher) {
+        let config = ConfigLock::default();
+        {
+            let mut config = config.load_write().unwrap();
+            config.market.preflight_batch_window_ms = window_ms;
+            config.market.max_concurrent_preflights = max_batch;
+        }
+        let prover = Arc::new(BatchRecorder {
+            batch_sizes: Default::default(),
+            default_prover: DefaultProver::new(),
+        });
+        let batcher = PreflightBatcher::new(prover.clone(), config);
+        (prover, batcher)
+    }
+
+    #[tokio::test]
+    async fn groups_concurrent_preflights() {
+        let (prover, batcher) = setup(50, 10);
+        let mut jobs = vec![];
+        for input in ["a", "b", "c"] {
+            jobs.push(job(&prover, input).await);
+        }
+
+        let cancel_token = CancellationToken::new();
+        let results = futures::future::join_all(
+            jobs.into_iter().map(|job| batcher.preflight(job, &cancel_token)),
+        )
+        .await;
+
+        // Each caller gets its own result, even though the batch completes out of order.
+        let mut journals = vec![];
+        for res in results {
+            let journal = prover.get_preflight_journal(&res.unwrap().id).await.unwrap().unwrap();
+            journals.push(String::from_utf8(journal).unwrap());
+        }
+        assert_eq!(journals, ["a", "b", "c"]);
+        assert_eq!(*prover.batch_sizes.lock().unwrap(), [3]);
+    }
+
+    #[tokio::test]
+    async fn flushes_full_batch_immediately() {
+        let (prover, batcher) = setup(60_000, 2);
+        let jobs = [job(&prover, "a").await, job(&prover, "b").await];
+
+        let cancel_token = CancellationToken::new();
+        let results = tokio::time::timeout(
+            Duration::from_secs(30),
+            futures::future::join_all(jobs.map(|job| batcher.preflight(job, &cancel_token))),
+        )
+        .await
+        .unwrap();
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(*prover.batch_sizes.lock().unwrap(), [2]);
+    }
+
+    #[tokio::test]
+    async fn drops_cancelled_orders_from_batch() {
+        let (prover, batcher) = setup(50, 10);
+        let (live, cancelled) = (job(&prover, "a").await, job(&prover, "b").await);
+
+        let live_token = CancellationToken::new();
+        let cancelled_token = CancellationToken::new();
+        let (live_res, cancelled_res, _) = tokio::join!(
+            batcher.preflight(live, &live_token),
+            batcher.preflight(cancelled, &cancelled_token),
+            async { cancelled_token.cancel() },
+        );
+
+        assert!(live_res.is_ok());
+        assert!(cancelled_res.is_err());
+        assert_eq!(*prover.batch_sizes.lock().unwrap(), [1]);
+    }
+
+    #[tokio::test]
+    async fn disabled_without_window() {
+        let (prover, batcher) = setup(0, 10);
+        let job = job(&prover, "a").await;
+
+        batcher.preflight(job, &CancellationToken::new()).await.unwrap();
+        assert!(prover.batch_sizes.lock().unwrap().is_empty());
+    }
+}
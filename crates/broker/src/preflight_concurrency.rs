@@ -0,0 +1,210 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Controller adapting the number of concurrent preflights to the load of the host.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::config::AdaptivePreflightConf;
+
+/// Maximum number of preflight durations kept between two adjustments.
+const MAX_DURATIONS: usize = 1_000;
+
+/// Utilization of the host, as a share from 0 to 1, where it could be measured.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct SystemLoad {
+    pub(crate) cpu: Option<f64>,
+    pub(crate) memory: Option<f64>,
+}
+
+impl SystemLoad {
+    /// Samples the one minute load average per core and the share of memory not available.
+    pub(crate) fn sample() -> Self {
+        let cores = std::thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1);
+        let cpu = std::fs::read_to_string("/proc/loadavg")
+            .ok()
+            .and_then(|loadavg| parse_loadavg(&loadavg))
+            .map(|load| load / cores as f64);
+        let memory = std::fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|meminfo| parse_meminfo(&meminfo));
+        Self { cpu, memory }
+    }
+}
+
+fn parse_loadavg(loadavg: &str) -> Option<f64> {
+    loadavg.split_whitespace().next()?.parse().ok()
+}
+
+fn parse_meminfo(meminfo: &str) -> Option<f64> {
+    let field = |name: &str| -> Option<u64> {
+        let line = meminfo.lines().find(|line| line.starts_with(name))?;
+        line[name.len()..].split_whitespace().next()?.parse().ok()
+    };
+    let total = field("MemTotal:")?;
+    let available = field("MemAvailable:")?;
+    (total > 0).then(|| total.saturating_sub(available) as f64 / total as f64)
+}
+
+#[derive(Default)]
+struct ControllerState {
+    limit: Option<usize>,
+    durations: Vec<Duration>,
+    adjusted_at: Option<Instant>,
+}
+
+/// Tracks preflight durations and the resulting number of concurrent preflights.
+///
+/// The limit is re-evaluated whenever it is read, see [AdaptivePreflightConf].
+#[derive(Default)]
+pub(crate) struct PreflightConcurrency {
+    state: Mutex<ControllerState>,
+}
+
+pub(crate) type PreflightConcurrencyObj = Arc<PreflightConcurrency>;
+
+impl PreflightConcurrency {
+    pub(crate) fn record(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        if state.durations.len() < MAX_DURATIONS {
+            state.durations.push(duration);
+        }
+    }
+
+    /// Current number of concurrent preflights, adjusting it to the load if due.
+    ///
+    /// Starts from `initial`, the configured `max_concurrent_preflights`.
+    pub(crate) fn limit(
+        &self,
+        conf: &AdaptivePreflightConf,
+        initial: usize,
+        load: SystemLoad,
+        now: Instant,
+    ) -> usize {
+        let (min, max) = (conf.min.max(1) as usize, conf.max.max(conf.min).max(1) as usize);
+        let mut state = self.state.lock().unwrap();
+        let limit = state.limit.unwrap_or(initial).clamp(min, max);
+        let due = state.adjusted_at.is_none_or(|adjusted_at| {
+            now.saturating_duration_since(adjusted_at)
+                >= Duration::from_secs(conf.adjust_interval_secs)
+        });
+        if !due {
+            state.limit = Some(limit);
+            return limit;
+        }
+
+        let median_duration = {
+            let durations = &mut state.durations;
+            durations.sort();
+            durations.get(durations.len() / 2).copied()
+        };
+        let over = |value: Option<f64>, max_percent: u64| {
+            value.is_some_and(|value| value * 100.0 > max_percent as f64)
+        };
+        let cpu_over = over(load.cpu, conf.max_cpu_percent);
+        let memory_over = over(load.memory, conf.max_memory_percent);
+        let slow = match (median_duration, conf.max_preflight_secs) {
+            (Some(median), Some(max_secs)) => median > Duration::from_secs(max_secs),
+            _ => false,
+        };
+
+        let new_limit = if cpu_over || memory_over || slow {
+            (limit - limit / 4).min(limit - 1).max(min)
+        } else {
+            (limit + 1).min(max)
+        };
+        if new_limit < limit {
+            tracing::warn!(
+                "Lowering concurrent preflights from {limit} to {new_limit}: CPU {}, memory {}, median preflight {median_duration:?}",
+                format_utilization(load.cpu),
+                format_utilization(load.memory),
+            );
+        } else if new_limit > limit {
+            tracing::debug!(
+                "Raising concurrent preflights from {limit} to {new_limit}: CPU {}, memory {}, median preflight {median_duration:?}",
+                format_utilization(load.cpu),
+                format_utilization(load.memory),
+            );
+        }
+        state.durations.clear();
+        state.adjusted_at = Some(now);
+        state.limit = Some(new_limit);
+        new_limit
+    }
+}
+
+fn format_utilization(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.0}%", value * 100.0),
+        None => "unknown".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_proc_files() {
+        assert_eq!(parse_loadavg("3.50 2.10 1.00 2/345 6789\n"), Some(3.5));
+        let meminfo = "MemTotal:       16000000 kB\nMemFree:         1000000 kB\nMemAvailable:    4000000 kB\n";
+        assert_eq!(parse_meminfo(meminfo), Some(0.75));
+        assert_eq!(parse_meminfo("MemTotal: 100 kB\n"), None);
+    }
+
+    #[test]
+    fn adapts_to_load() {
+        let conf = AdaptivePreflightConf {
+            min: 2,
+            max: 10,
+            max_cpu_percent: 80,
+            max_memory_percent: 90,
+            max_preflight_secs: Some(30),
+            adjust_interval_secs: 10,
+        };
+        let idle = SystemLoad { cpu: Some(0.2), memory: Some(0.5) };
+        let busy = SystemLoad { cpu: Some(0.95), memory: Some(0.5) };
+        let controller = PreflightConcurrency::default();
+        let start = Instant::now();
+
+        // Starts from the configured concurrency, and raises it while idle
+        assert_eq!(controller.limit(&conf, 8, idle, start), 9);
+        // At most one adjustment per interval
+        assert_eq!(controller.limit(&conf, 8, busy, start + Duration::from_secs(5)), 9);
+        assert_eq!(controller.limit(&conf, 8, idle, start + Duration::from_secs(10)), 10);
+        assert_eq!(controller.limit(&conf, 8, idle, start + Duration::from_secs(20)), 10);
+
+        // Lowered by a quarter under load, down to the minimum
+        assert_eq!(controller.limit(&conf, 8, busy, start + Duration::from_secs(30)), 8);
+        assert_eq!(controller.limit(&conf, 8, busy, start + Duration::from_secs(40)), 6);
+        assert_eq!(controller.limit(&conf, 8, busy, start + Duration::from_secs(50)), 5);
+        assert_eq!(controller.limit(&conf, 8, busy, start + Duration::from_secs(60)), 4);
+        assert_eq!(controller.limit(&conf, 8, busy, start + Duration::from_secs(70)), 3);
+        assert_eq!(controller.limit(&conf, 8, busy, start + Duration::from_secs(80)), 2);
+        assert_eq!(controller.limit(&conf, 8, busy, start + Duration::from_secs(90)), 2);
+
+        // Slow preflights lower it too, unknown utilization does not
+        let unknown = SystemLoad::default();
+        assert_eq!(controller.limit(&conf, 8, unknown, start + Duration::from_secs(100)), 3);
+        controller.record(Duration::from_secs(40));
+        controller.record(Duration::from_secs(45));
+        controller.record(Duration::from_secs(5));
+        assert_eq!(controller.limit(&conf, 8, unknown, start + Duration::from_secs(110)), 2);
+        assert_eq!(controller.limit(&conf, 8, unknown, start + Duration::from_secs(120)), 3);
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Operator-defined webhook notified of pricing decisions, see [PricingWebhookConf].
+//!
+//! The order picker queues a summary of each pricing decision without waiting on the webhook.
+//! The webhook service sends the queued summaries in batches, so that external systems can
+//! follow the decisions of the broker without reading its DB.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::{Address, U256};
+use chrono::{serde::ts_seconds, DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+use tokio::sync::{mpsc, Mutex};
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+use crate::{
+    config::PricingWebhookConf,
+    errors::CodedError,
+    impl_coded_debug, now_timestamp,
+    task::{RetryRes, RetryTask, SupervisorErr},
+    FulfillmentType, OrderRequest, PricingAuditRecord, PricingDecision, SkipReason,
+};
+
+/// Maximum number of pricing decisions queued for the webhook, beyond which they are dropped.
+const PRICING_WEBHOOK_QUEUE_CAPACITY: usize = 1024;
+
+/// Timeout of a request to the webhook.
+pub(crate) const PRICING_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(crate) const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub(crate) const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+#[derive(Error)]
+pub enum PricingWebhookErr {
+    #[error("{code} Failed to build HTTP client: {0}", code = self.code())]
+    ClientErr(#[from] reqwest::Error),
+}
+
+impl_coded_debug!(PricingWebhookErr);
+
+impl CodedError for PricingWebhookErr {
+    fn code(&self) -> &str {
+        match self {
+            PricingWebhookErr::ClientErr(_) => "[B-HOOK-001]",
+        }
+    }
+}
+
+/// Summary of a pricing decision sent to the webhook.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct PricingDecisionSummary {
+    order_id: String,
+    request_id: U256,
+    client: Address,
+    chain_id: u64,
+    fulfillment_type: FulfillmentType,
+    decision: PricingDecision,
+    skip_reason: Option<SkipReason>,
+    /// Error that failed pricing, if any
+    error: Option<String>,
+    /// Cycles used by the preflight execution
+    total_cycles: Option<u64>,
+    /// Estimated gas cost to lock (if applicable) and fulfill the order, in the native token
+    gas_cost: Option<U256>,
+    min_price: U256,
+    max_price: U256,
+    lock_stake: U256,
+    #[serde(with = "ts_seconds")]
+    decided_at: DateTime<Utc>,
+}
+
+impl PricingDecisionSummary {
+    pub(crate) fn new(order: &OrderRequest, record: &PricingAuditRecord) -> Self {
+        Self {
+            order_id: record.order_id.clone(),
+            request_id: record.request_id,
+            client: order.request.client_address(),
+            chain_id: order.chain_id,
+            fulfillment_type: record.fulfillment_type,
+            decision: record.decision.clone(),
+            skip_reason: record.skip_reason,
+            error: record.error.clone(),
+            total_cycles: record.total_cycles,
+            gas_cost: record.gas_cost,
+            min_price: order.request.offer.minPrice,
+            max_price: order.request.offer.maxPrice,
+            lock_stake: order.request.offer.lockStake,
+            decided_at: record.created_at,
+        }
+    }
+}
+
+/// Queue of pricing decisions for the webhook, cheap to clone.
+#[derive(Clone)]
+pub(crate) struct PricingWebhookQueue {
+    tx: mpsc::Sender<PricingDecisionSummary>,
+}
+
+impl PricingWebhookQueue {
+    /// Queue a pricing decision, dropping it if the queue is full.
+    pub(crate) fn notify(&self, summary: PricingDecisionSummary) {
+        if let Err(err) = self.tx.try_send(summary) {
+            tracing::debug!("Dropping pricing decision for the webhook: {err}");
+        }
+    }
+}
+
+/// Sends the pricing decisions queued by the order picker to the webhook.
+pub(crate) struct PricingWebhook {
+    conf: PricingWebhookConf,
+    rx: Arc<Mutex<mpsc::Receiver<PricingDecisionSummary>>>,
+}
+
+impl PricingWebhook {
+    /// Webhook service and the queue the order picker notifies it through.
+    pub(crate) fn new(conf: PricingWebhookConf) -> (Self, PricingWebhookQueue) {
+        let (tx, rx) = mpsc::channel(PRICING_WEBHOOK_QUEUE_CAPACITY);
+        (Self { conf, rx: Arc::new(Mutex::new(rx)) }, PricingWebhookQueue { tx })
+    }
+
+    async fn run(
+        conf: PricingWebhookConf,
+        rx: Arc<Mutex<mpsc::Receiver<PricingDecisionSummary>>>,
+        cancel_token: CancellationToken,
+    ) -> Result<(), PricingWebhookErr> {
+        let client = reqwest::Client::builder().timeout(PRICING_WEBHOOK_TIMEOUT).build()?;
+        let flush_interval = Duration::from_secs(conf.flush_interval_secs);
+        let batch_size = conf.batch_size.max(1);
+        let conf = Arc::new(conf);
+        let mut rx = rx.lock().await;
+        loop {
+            let summary = tokio::select! {
+                summary = rx.recv() => summary,
+                _ = cancel_token.cancelled() => return Ok(()),
+            };
+            let Some(summary) = summary else {
+                return Ok(());
+            };
+
+            let mut batch = vec![summary];
+            let deadline = tokio::time::Instant::from_std(Instant::now() + flush_interval);
+            while batch.len() < batch_size {
+                match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(Some(summary)) => batch.push(summary),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+
+            // Fire and forget, so that a slow webhook does not hold up the next batch
+            let (client, conf) = (client.clone(), conf.clone());
+            tokio::spawn(async move {
+                let count = batch.len();
+                match post_signed(
+                    &client,
+                    &conf.url,
+                    conf.secret.as_deref(),
+                    &batch,
+                    now_timestamp(),
+                )
+                .await
+                {
+                    Ok(()) => tracing::trace!("Sent {count} pricing decisions to the webhook"),
+                    Err(err) => {
+                        tracing::warn!(
+                            "Failed to send {count} pricing decisions to the webhook: {err}"
+                        )
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// `sha256=` followed by the hex HMAC-SHA256 of `{timestamp}.{body}` keyed with the secret.
+fn sign_payload(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// POST the payload as JSON, signed with the secret if any.
+pub(crate) async fn post_signed<T: Serialize + ?Sized>(
+    client: &reqwest::Client,
+    url: &Url,
+    secret: Option<&str>,
+    payload: &T,
+    timestamp: u64,
+) -> anyhow::Result<()> {
+    let body = serde_json::to_vec(payload)?;
+    let mut request = client
+        .post(url.clone())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(TIMESTAMP_HEADER, timestamp);
+    if let Some(secret) = secret {
+        request = request.header(SIGNATURE_HEADER, sign_payload(secret, timestamp, &body));
+    }
+    request.body(body).send().await?.error_for_status()?;
+    Ok(())
+}
+
+impl RetryTask for PricingWebhook {
+    type Error = PricingWebhookErr;
+    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
+        let conf = self.conf.clone();
+        let rx = self.rx.clone();
+        Box::pin(async move {
+            tracing::info!("Starting pricing webhook for {}", conf.url);
+            Self::run(conf, rx, cancel_token).await.map_err(SupervisorErr::Recover)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[test]
+    fn signs_payloads() {
+        assert_eq!(
+            sign_payload("secret", 1700000000, b"[]"),
+            "sha256=74f76d8933679a54d6be8c7560a5233b124658241ca5a3b0f09af80d3ea60d78"
+        );
+    }
+
+    fn summary(index: u64) -> PricingDecisionSummary {
+        PricingDecisionSummary {
+            order_id: format!("0x{index:x}"),
+            request_id: U256::from(index),
+            client: Address::repeat_byte(1),
+            chain_id: 1,
+            fulfillment_type: FulfillmentType::LockAndFulfill,
+            decision: PricingDecision::Skip,
+            skip_reason: Some(SkipReason::PriceTooLow),
+            error: None,
+            total_cycles: Some(1_000_000),
+            gas_cost: None,
+            min_price: U256::from(1),
+            max_price: U256::from(2),
+            lock_stake: U256::from(3),
+            decided_at: DateTime::from_timestamp(1700000000, 0).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_signed_batches() {
+        let server = MockServer::start_async().await;
+        let conf = PricingWebhookConf {
+            url: server.url("/pricing").parse().unwrap(),
+            secret: Some("secret".into()),
+            batch_size: 2,
+            flush_interval_secs: 60,
+        };
+        let batch = [summary(1), summary(2)];
+        let body = String::from_utf8(serde_json::to_vec(&batch).unwrap()).unwrap();
+        let signed = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/pricing")
+                    .header(TIMESTAMP_HEADER, "1700000000")
+                    .header(SIGNATURE_HEADER, sign_payload("secret", 1700000000, body.as_bytes()))
+                    .body(body.clone());
+                then.status(200);
+            })
+            .await;
+        post_signed(&reqwest::Client::new(), &conf.url, Some("secret"), &batch, 1700000000)
+            .await
+            .unwrap();
+        signed.assert_async().await;
+        signed.delete_async().await;
+
+        // Queued decisions are sent together once the batch is full
+        let batched = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/pricing").header_exists(SIGNATURE_HEADER).body(body);
+                then.status(200);
+            })
+            .await;
+        let (webhook, queue) = PricingWebhook::new(conf.clone());
+        let cancel_token = CancellationToken::new();
+        let task =
+            tokio::spawn(PricingWebhook::run(conf, webhook.rx.clone(), cancel_token.clone()));
+        for summary in batch {
+            queue.notify(summary);
+        }
+        for _ in 0..100 {
+            if batched.hits_async().await > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        cancel_token.cancel();
+        task.await.unwrap().unwrap();
+        batched.assert_async().await;
+    }
+}
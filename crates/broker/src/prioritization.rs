@@ -13,14 +13,15 @@
 // limitations under the License.
 
 use crate::{
-    config::{OrderCommitmentPriority, OrderPricingPriority},
+    config::{OrderCommitmentPriority, OrderPricingPriority, OrderTagConf, RequestorTierConf},
     order_monitor::OrderMonitor,
     order_picker::OrderPicker,
+    order_tags::tag_priority,
     FulfillmentType, OrderRequest,
 };
 
 use rand::seq::SliceRandom;
-use std::sync::Arc;
+use std::{cmp::Reverse, sync::Arc};
 
 /// Unified priority mode for both pricing and commitment
 #[derive(Debug, Clone, Copy)]
@@ -50,26 +51,25 @@ impl From<OrderCommitmentPriority> for UnifiedPriorityMode {
 }
 
 fn sort_orders_by_priority_and_mode<T>(
-    orders: &mut Vec<T>,
+    orders: &mut [T],
     priority_addresses: Option<&[alloy::primitives::Address]>,
+    tiers: &[RequestorTierConf],
+    tags: &[OrderTagConf],
     mode: UnifiedPriorityMode,
 ) where
     T: AsRef<OrderRequest>,
 {
-    let Some(addresses) = priority_addresses else {
-        sort_by_mode(orders, mode);
-        return;
-    };
-
-    let (mut priority_orders, mut regular_orders): (Vec<T>, Vec<T>) = orders
-        .drain(..)
-        .partition(|order| addresses.contains(&order.as_ref().request.client_address()));
-
-    sort_by_mode(&mut priority_orders, mode);
-    sort_by_mode(&mut regular_orders, mode);
-
-    orders.extend(priority_orders);
-    orders.extend(regular_orders);
+    sort_by_mode(orders, mode);
+
+    // Stable sort, keeping the order given by the mode within orders of equal priority.
+    orders.sort_by_cached_key(|order| {
+        let request = &order.as_ref().request;
+        let client = request.client_address();
+        let is_priority_address =
+            priority_addresses.is_some_and(|addresses| addresses.contains(&client));
+        let tier_priority = RequestorTierConf::find(tiers, client).map_or(0, |tier| tier.priority);
+        Reverse((is_priority_address, tier_priority, tag_priority(tags, request)))
+    });
 }
 
 fn sort_by_mode<T>(orders: &mut [T], mode: UnifiedPriorityMode)
@@ -100,16 +100,34 @@ impl<P> OrderPicker<P> {
         orders: &mut Vec<Box<OrderRequest>>,
         priority_mode: OrderPricingPriority,
         priority_addresses: Option<&[alloy::primitives::Address]>,
+        tiers: &[RequestorTierConf],
+        tags: &[OrderTagConf],
         capacity: usize,
+        mut admit: impl FnMut(&OrderRequest) -> bool,
     ) -> Vec<Box<OrderRequest>> {
         if orders.is_empty() || capacity == 0 {
             return Vec::new();
         }
 
-        sort_orders_by_priority_and_mode(orders, priority_addresses, priority_mode.into());
+        sort_orders_by_priority_and_mode(
+            orders,
+            priority_addresses,
+            tiers,
+            tags,
+            priority_mode.into(),
+        );
 
-        let take_count = std::cmp::min(capacity, orders.len());
-        orders.drain(..take_count).collect()
+        // Orders that are not admitted are deferred, keeping their place in the queue.
+        let mut selected = Vec::new();
+        let mut idx = 0;
+        while selected.len() < capacity && idx < orders.len() {
+            if admit(&orders[idx]) {
+                selected.push(orders.remove(idx));
+            } else {
+                idx += 1;
+            }
+        }
+        selected
     }
 }
 
@@ -121,9 +139,17 @@ impl<P> OrderMonitor<P> {
         mut orders: Vec<Arc<OrderRequest>>,
         priority_mode: OrderCommitmentPriority,
         priority_addresses: Option<&[alloy::primitives::Address]>,
+        tiers: &[RequestorTierConf],
+        tags: &[OrderTagConf],
     ) -> Vec<Arc<OrderRequest>> {
-        // Sort orders with priority addresses first, then by mode
-        sort_orders_by_priority_and_mode(&mut orders, priority_addresses, priority_mode.into());
+        // Sort orders with priority addresses first, then by tier and tag priority, then by mode
+        sort_orders_by_priority_and_mode(
+            &mut orders,
+            priority_addresses,
+            tiers,
+            tags,
+            priority_mode.into(),
+        );
 
         tracing::debug!(
             "Orders ready for proving, prioritized. Before applying capacity limits: {}",
@@ -167,7 +193,10 @@ mod tests {
                 &mut orders,
                 OrderPricingPriority::ObservationTime,
                 None,
+                &[],
+                &[],
                 1,
+                |_| true,
             );
             if let Some(order) = selected_orders.into_iter().next() {
                 let order_index =
@@ -211,7 +240,10 @@ mod tests {
                 &mut orders,
                 OrderPricingPriority::ShortestExpiry,
                 None,
+                &[],
+                &[],
                 1,
+                |_| true,
             );
             if let Some(order) = selected_orders.into_iter().next() {
                 let order_index =
@@ -281,7 +313,10 @@ mod tests {
                 &mut orders,
                 OrderPricingPriority::ShortestExpiry,
                 None,
+                &[],
+                &[],
                 1,
+                |_| true,
             );
             if let Some(order) = selected_orders.into_iter().next() {
                 let order_index =
@@ -323,7 +358,10 @@ mod tests {
                     &mut orders,
                     OrderPricingPriority::Random,
                     None,
+                    &[],
+                    &[],
                     1,
+                    |_| true,
                 );
                 if let Some(order) = selected_orders.into_iter().next() {
                     let order_index =
@@ -379,8 +417,13 @@ mod tests {
 
         let orders =
             vec![Arc::from(order1), Arc::from(order2), Arc::from(order3), Arc::from(order4)];
-        let orders =
-            ctx.monitor.prioritize_orders(orders, OrderCommitmentPriority::ShortestExpiry, None);
+        let orders = ctx.monitor.prioritize_orders(
+            orders,
+            OrderCommitmentPriority::ShortestExpiry,
+            None,
+            &[],
+            &[],
+        );
 
         assert!(orders[0].id() == order_1_id);
         assert!(orders[1].id() == order_3_id);
@@ -428,8 +471,13 @@ mod tests {
 
         for _ in 0..10 {
             let test_orders = orders.clone();
-            let test_orders =
-                ctx.monitor.prioritize_orders(test_orders, OrderCommitmentPriority::Random, None);
+            let test_orders = ctx.monitor.prioritize_orders(
+                test_orders,
+                OrderCommitmentPriority::Random,
+                None,
+                &[],
+                &[],
+            );
 
             // Extract the ordering of all orders
             let order_ids: Vec<_> = test_orders.iter().map(|order| order.request.id).collect();
@@ -441,7 +489,7 @@ mod tests {
 
         // Test that random mode produces different orderings
         let prioritized =
-            ctx.monitor.prioritize_orders(orders, OrderCommitmentPriority::Random, None);
+            ctx.monitor.prioritize_orders(orders, OrderCommitmentPriority::Random, None, &[], &[]);
 
         // We should have 3 LockAndFulfill and 3 FulfillAfterLockExpire orders in total
         let lock_and_fulfill_count = prioritized
@@ -489,8 +537,13 @@ mod tests {
             orders.push(Arc::from(order));
         }
 
-        let prioritized =
-            ctx.monitor.prioritize_orders(orders, OrderCommitmentPriority::ShortestExpiry, None);
+        let prioritized = ctx.monitor.prioritize_orders(
+            orders,
+            OrderCommitmentPriority::ShortestExpiry,
+            None,
+            &[],
+            &[],
+        );
 
         // Orders should be sorted by their relevant expiry times, regardless of type
         // Expected order: LockAndFulfill(100), LockAndFulfill(150), FulfillAfterLockExpire(150), LockAndFulfill(200), FulfillAfterLockExpire(250), FulfillAfterLockExpire(300)
@@ -550,11 +603,18 @@ mod tests {
             _prioritized_random,
             OrderCommitmentPriority::Random,
             None,
+            &[],
+            &[],
         );
 
         // Test shortest expiry mode
-        let prioritized_shortest =
-            ctx.monitor.prioritize_orders(orders, OrderCommitmentPriority::ShortestExpiry, None);
+        let prioritized_shortest = ctx.monitor.prioritize_orders(
+            orders,
+            OrderCommitmentPriority::ShortestExpiry,
+            None,
+            &[],
+            &[],
+        );
 
         // In shortest expiry mode, orders should be sorted by expiry time
         for i in 0..3 {
@@ -609,7 +669,10 @@ mod tests {
             &mut test_orders,
             OrderPricingPriority::ShortestExpiry,
             None,
+            &[],
+            &[],
             1,
+            |_| true,
         );
         let selected_order = selected_orders.into_iter().next().unwrap();
         assert_eq!(selected_order.request.client_address(), regular_addr); // Regular order selected due to shorter expiry
@@ -642,7 +705,10 @@ mod tests {
             &mut test_orders,
             OrderPricingPriority::ShortestExpiry,
             Some(&priority_addresses),
+            &[],
+            &[],
             1,
+            |_| true,
         );
         let selected_order = selected_orders.into_iter().next().unwrap();
         assert_eq!(selected_order.request.client_address(), priority_addr); // Priority order selected first despite longer expiry
@@ -682,6 +748,8 @@ mod tests {
             test_orders,
             OrderCommitmentPriority::ShortestExpiry,
             None,
+            &[],
+            &[],
         );
         assert_eq!(prioritized_orders[0].request.lock_expires_at(), current_timestamp + 100); // Regular order first
 
@@ -691,6 +759,8 @@ mod tests {
             test_orders,
             OrderCommitmentPriority::ShortestExpiry,
             Some(&priority_addresses),
+            &[],
+            &[],
         );
 
         // Priority order should be first despite longer expiry, regular order second
@@ -698,4 +768,71 @@ mod tests {
         assert_eq!(prioritized_orders[0].request.client_address(), priority_addr);
         assert_eq!(prioritized_orders[1].request.lock_expires_at(), current_timestamp + 100);
     }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_order_tag_priority_commitment() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let regular_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        ctx.signer = crate::PrivateKeySigner::random();
+        let low_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 300, 400)
+            .await;
+        let low_addr = ctx.signer.address();
+        ctx.signer = crate::PrivateKeySigner::random();
+        let high_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 500, 600)
+            .await;
+        let high_addr = ctx.signer.address();
+        let orders: Vec<_> =
+            [regular_order, low_order, high_order].into_iter().map(Arc::from).collect();
+
+        let tag = |name: &str, requestor, priority| OrderTagConf {
+            name: name.into(),
+            requestors: Some(vec![requestor]),
+            image_ids: None,
+            min_price: None,
+            max_price: None,
+            priority,
+            max_concurrent_proofs: None,
+        };
+        let tags = [tag("low", low_addr, 1), tag("high", high_addr, 2)];
+
+        let prioritized_orders = ctx.monitor.prioritize_orders(
+            orders,
+            OrderCommitmentPriority::ShortestExpiry,
+            None,
+            &[],
+            &tags,
+        );
+        let clients: Vec<_> =
+            prioritized_orders.iter().map(|order| order.request.client_address()).collect();
+        assert_eq!(clients[0], high_addr);
+        assert_eq!(clients[1], low_addr);
+        assert_eq!(prioritized_orders[2].request.lock_expires_at(), current_timestamp + 100);
+
+        // The priority of the tier of the requestor comes before that of the tags of the order
+        let tier = RequestorTierConf {
+            name: "enterprise".into(),
+            requestors: vec![low_addr],
+            mcycle_price: None,
+            mcycle_price_stake_token: None,
+            max_stake: None,
+            skip_exec_limits: false,
+            priority: 1,
+        };
+        let prioritized_orders = ctx.monitor.prioritize_orders(
+            prioritized_orders,
+            OrderCommitmentPriority::ShortestExpiry,
+            None,
+            &[tier],
+            &tags,
+        );
+        assert_eq!(prioritized_orders[0].request.client_address(), low_addr);
+        assert_eq!(prioritized_orders[1].request.client_address(), high_addr);
+    }
 }
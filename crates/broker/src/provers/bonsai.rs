@@ -19,10 +19,11 @@ use bonsai_sdk::{
     non_blocking::{Client as BonsaiClient, SessionId, SnarkId},
     SdkErr,
 };
+use futures::stream::{BoxStream, StreamExt};
 use risc0_zkvm::Receipt;
 use sqlx::{self, Postgres, Transaction};
 
-use super::{ExecutorResp, ProofResult, Prover, ProverError};
+use super::{ExecutorResp, PreflightJob, ProofResult, Prover, ProverError};
 use crate::{config::ProverConf, futures_retry::retry_only};
 use crate::{
     config::{ConfigErr, ConfigLock},
@@ -159,6 +160,47 @@ impl Bonsai {
         )
         .await
     }
+
+    fn status_poller(&self) -> StatusPoller {
+        StatusPoller {
+            poll_sleep_ms: self.status_poll_ms,
+            retry_counts: self.status_poll_retry_count,
+        }
+    }
+
+    async fn create_preflight_session(
+        &self,
+        image_id: &str,
+        input_id: &str,
+        assumptions: Vec<String>,
+        executor_limit: Option<u64>,
+        order_id: &str,
+    ) -> Result<SessionId, ProverError> {
+        // Convert cycles to Mi-cycles (1024*1024) for Bonsai API
+        let bonsai_limit = executor_limit.map(|cycles| cycles.div_ceil(1024 * 1024));
+        let preflight_id: SessionId = self
+            .retry(
+                || async {
+                    Ok(self
+                        .client
+                        .create_session_with_limit(
+                            image_id.into(),
+                            input_id.into(),
+                            assumptions.clone(),
+                            true,
+                            bonsai_limit,
+                        )
+                        .await?)
+                },
+                "create session for preflight",
+            )
+            .await?;
+
+        tracing::debug!(
+            "Created session for preflight: {preflight_id:?} for order id {order_id:?} with image id {image_id} and input id {input_id}"
+        );
+        Ok(preflight_id)
+    }
 }
 
 struct StatusPoller {
@@ -285,6 +327,9 @@ impl Prover for Bonsai {
             .await
     }
 
+    // Inputs are not deleted: neither the Bonsai nor the Bento API have an endpoint to delete
+    // them, so the default of [Prover::delete_input] is kept.
+
     async fn upload_image(&self, image_id: &str, image: Vec<u8>) -> Result<(), ProverError> {
         self.retry(
             || async { Ok(self.client.upload_img(image_id, image.clone()).await.map(|_| ())?) },
@@ -293,6 +338,14 @@ impl Prover for Bonsai {
         .await
     }
 
+    async fn upload_receipt(&self, receipt: Vec<u8>) -> Result<String, ProverError> {
+        self.retry(
+            || async { Ok(self.client.upload_receipt(receipt.clone()).await?) },
+            "upload receipt",
+        )
+        .await
+    }
+
     async fn preflight(
         &self,
         image_id: &str,
@@ -303,34 +356,16 @@ impl Prover for Bonsai {
     ) -> Result<ProofResult, ProverError> {
         self.retry_only(
             || async {
-                // Convert cycles to Mi-cycles (1024*1024) for Bonsai API
-                let bonsai_limit = executor_limit.map(|cycles| cycles.div_ceil(1024 * 1024));
-                let preflight_id: SessionId = self
-                    .retry(
-                        || async {
-                            Ok(self
-                                .client
-                                .create_session_with_limit(
-                                    image_id.into(),
-                                    input_id.into(),
-                                    assumptions.clone(),
-                                    true,
-                                    bonsai_limit,
-                                )
-                                .await?)
-                        },
-                        "create session for preflight",
+                let preflight_id = self
+                    .create_preflight_session(
+                        image_id,
+                        input_id,
+                        assumptions.clone(),
+                        executor_limit,
+                        order_id,
                     )
                     .await?;
-
-                tracing::debug!(
-                    "Created session for preflight: {preflight_id:?} for order id {order_id:?} with image id {image_id} and input id {input_id}"
-                );
-                let poller = StatusPoller {
-                    poll_sleep_ms: self.status_poll_ms,
-                    retry_counts: self.status_poll_retry_count,
-                };
-                poller.poll_with_retries_session_id(&preflight_id, &self.client).await
+                self.status_poller().poll_with_retries_session_id(&preflight_id, &self.client).await
             },
             "preflight",
             |err| matches!(err, ProverError::ProverInternalError(_)),
@@ -338,6 +373,57 @@ impl Prover for Bonsai {
         .await
     }
 
+    fn preflight_batch(
+        &self,
+        jobs: Vec<PreflightJob>,
+    ) -> BoxStream<'_, (usize, Result<ProofResult, ProverError>)> {
+        futures::stream::once(async move {
+            // Start every execution before polling any of them, so they all run concurrently on
+            // the backend and share a single round of status polling.
+            let sessions = futures::future::join_all(jobs.iter().map(|job| {
+                self.create_preflight_session(
+                    &job.image_id,
+                    &job.input_id,
+                    job.assumptions.clone(),
+                    job.executor_limit,
+                    &job.order_id,
+                )
+            }))
+            .await;
+            let num_jobs = jobs.len();
+
+            futures::stream::iter(jobs.into_iter().zip(sessions).enumerate())
+                .map(move |(idx, (job, session))| async move {
+                    let res = match session {
+                        Ok(preflight_id) => {
+                            self.status_poller()
+                                .poll_with_retries_session_id(&preflight_id, &self.client)
+                                .await
+                        }
+                        Err(err) => Err(err),
+                    };
+                    let res = match res {
+                        // Fall back to a standalone preflight, with its own retries.
+                        Err(ProverError::ProverInternalError(_)) => {
+                            self.preflight(
+                                &job.image_id,
+                                &job.input_id,
+                                job.assumptions,
+                                job.executor_limit,
+                                &job.order_id,
+                            )
+                            .await
+                        }
+                        res => res,
+                    };
+                    (idx, res)
+                })
+                .buffer_unordered(num_jobs.max(1))
+        })
+        .flatten()
+        .boxed()
+    }
+
     async fn prove_stark(
         &self,
         image_id: &str,
@@ -459,6 +545,28 @@ impl Prover for Bonsai {
         }
     }
 
+    async fn active_proofs(&self) -> Result<Option<Vec<String>>, ProverError> {
+        // The Bonsai API cannot list sessions, Bento jobs are listed from its task DB
+        match self.prover_type {
+            ProverType::Bonsai => Ok(None),
+            ProverType::Bento => {
+                let pool = create_pg_pool().await.map_err(|e| {
+                    ProverError::ProverInternalError(format!("Failed to connect to postgres: {e}"))
+                })?;
+                let job_ids: Vec<String> =
+                    sqlx::query_scalar("SELECT id::text FROM jobs WHERE state = 'running'")
+                        .fetch_all(&pool)
+                        .await
+                        .map_err(|e| {
+                            ProverError::ProverInternalError(format!(
+                                "Failed to list running jobs: {e}"
+                            ))
+                        })?;
+                Ok(Some(job_ids))
+            }
+        }
+    }
+
     async fn get_receipt(&self, proof_id: &str) -> Result<Option<Receipt>, ProverError> {
         let session_id = SessionId { uuid: proof_id.into() };
         let receipt = self
@@ -146,6 +146,13 @@ impl Prover for DefaultProver {
         Ok(input_id)
     }
 
+    async fn delete_input(&self, input_id: &str) -> Result<(), ProverError> {
+        match self.state.inputs.write().await.remove(input_id) {
+            Some(_) => Ok(()),
+            None => Err(ProverError::NotFound(format!("input {input_id}"))),
+        }
+    }
+
     async fn upload_image(&self, image_id: &str, image: Vec<u8>) -> Result<(), ProverError> {
         let mut images = self.state.images.write().await;
         images.insert(image_id.to_string(), image);
@@ -153,6 +160,18 @@ impl Prover for DefaultProver {
         Ok(())
     }
 
+    async fn upload_receipt(&self, receipt: Vec<u8>) -> Result<String, ProverError> {
+        let receipt: Receipt = bincode::deserialize(&receipt)?;
+        let receipt_id = format!("receipt_{}", Uuid::new_v4());
+
+        // Stored as a completed proof, so that it resolves like the assumptions of aggregation
+        let proof =
+            ProofData { status: Status::Succeeded, receipt: Some(receipt), ..Default::default() };
+        self.state.proofs.write().await.insert(receipt_id.clone(), proof);
+
+        Ok(receipt_id)
+    }
+
     async fn preflight(
         &self,
         image_id: &str,
@@ -320,6 +339,19 @@ impl Prover for DefaultProver {
         Ok(())
     }
 
+    async fn active_proofs(&self) -> Result<Option<Vec<String>>, ProverError> {
+        let proofs = self.state.proofs.read().await;
+        Ok(Some(
+            proofs
+                .iter()
+                .filter(|(proof_id, proof)| {
+                    proof_id.starts_with("stark_") && matches!(proof.status, Status::Running)
+                })
+                .map(|(proof_id, _)| proof_id.clone())
+                .collect(),
+        ))
+    }
+
     async fn get_receipt(&self, proof_id: &str) -> Result<Option<Receipt>, ProverError> {
         let proofs = self.state.proofs.read().await;
         let proof_data = proofs
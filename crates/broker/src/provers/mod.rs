@@ -17,15 +17,21 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use bonsai_sdk::SdkErr;
 use boundless_market::input::GuestEnv;
+use futures::{
+    stream::{BoxStream, FuturesUnordered},
+    StreamExt,
+};
 use risc0_zkvm::Receipt;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 mod bonsai;
 mod default;
+mod pool;
 
 pub use bonsai::Bonsai;
 pub use default::DefaultProver;
+pub use pool::ProverPool;
 
 /// Executor output
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -100,6 +106,16 @@ pub struct ProofResult {
     pub elapsed_time: f64,
 }
 
+/// A single preflight execution, as submitted to [Prover::preflight_batch].
+#[derive(Clone, Debug)]
+pub struct PreflightJob {
+    pub image_id: String,
+    pub input_id: String,
+    pub assumptions: Vec<String>,
+    pub executor_limit: Option<u64>,
+    pub order_id: String,
+}
+
 /// Encode inputs for Prover::upload_slice()
 pub fn encode_input(input: &impl serde::Serialize) -> Result<Vec<u8>, anyhow::Error> {
     Ok(GuestEnv::builder().write(input)?.stdin)
@@ -109,7 +125,23 @@ pub fn encode_input(input: &impl serde::Serialize) -> Result<Vec<u8>, anyhow::Er
 pub trait Prover {
     async fn has_image(&self, image_id: &str) -> Result<bool, ProverError>;
     async fn upload_input(&self, input: Vec<u8>) -> Result<String, ProverError>;
+    /// Delete an uploaded input from the backend.
+    ///
+    /// Backends that do not support deleting inputs, or that expire them on their own, keep the
+    /// default, which does nothing.
+    async fn delete_input(&self, _input_id: &str) -> Result<(), ProverError> {
+        Ok(())
+    }
     async fn upload_image(&self, image_id: &str, image: Vec<u8>) -> Result<(), ProverError>;
+    /// Upload a bincode encoded [Receipt], returning an ID to pass as an assumption to
+    /// [Prover::preflight] and [Prover::prove_stark].
+    ///
+    /// Backends that cannot resolve assumptions keep the default, which fails.
+    async fn upload_receipt(&self, _receipt: Vec<u8>) -> Result<String, ProverError> {
+        Err(ProverError::ProverInternalError(
+            "Cannot upload receipt: assumptions are not supported".into(),
+        ))
+    }
     async fn preflight(
         &self,
         image_id: &str,
@@ -118,6 +150,35 @@ pub trait Prover {
         executor_limit: Option<u64>,
         order_id: &str,
     ) -> Result<ProofResult, ProverError>;
+    /// Run several preflight executions at once.
+    ///
+    /// Yields the index of each job along with its result, in order of completion. Backends
+    /// that can amortize session setup across executions should override the default, which
+    /// runs each job concurrently through [Prover::preflight].
+    fn preflight_batch(
+        &self,
+        jobs: Vec<PreflightJob>,
+    ) -> BoxStream<'_, (usize, Result<ProofResult, ProverError>)>
+    where
+        Self: Sync,
+    {
+        jobs.into_iter()
+            .enumerate()
+            .map(|(idx, job)| async move {
+                let res = self
+                    .preflight(
+                        &job.image_id,
+                        &job.input_id,
+                        job.assumptions,
+                        job.executor_limit,
+                        &job.order_id,
+                    )
+                    .await;
+                (idx, res)
+            })
+            .collect::<FuturesUnordered<_>>()
+            .boxed()
+    }
     async fn prove_stark(
         &self,
         image_id: &str,
@@ -135,6 +196,32 @@ pub trait Prover {
     }
     async fn wait_for_stark(&self, proof_id: &str) -> Result<ProofResult, ProverError>;
     async fn cancel_stark(&self, proof_id: &str) -> Result<(), ProverError>;
+    /// IDs of the STARK proofs queued or running on the backend, including proofs started by
+    /// other clients of the backend.
+    ///
+    /// Backends that cannot list their proofs keep the default, which returns `None`.
+    async fn active_proofs(&self) -> Result<Option<Vec<String>>, ProverError> {
+        Ok(None)
+    }
+    /// Whether running proofs can be suspended and later resumed from a checkpoint.
+    ///
+    /// Backends that support checkpointing override this along with [Prover::suspend_stark] and
+    /// [Prover::resume_stark].
+    fn supports_checkpointing(&self) -> bool {
+        false
+    }
+    /// Suspend a running proof, checkpointing its progress.
+    async fn suspend_stark(&self, proof_id: &str) -> Result<(), ProverError> {
+        Err(ProverError::ProverInternalError(format!(
+            "Cannot suspend proof {proof_id}: checkpointing is not supported"
+        )))
+    }
+    /// Resume a suspended proof from its last checkpoint.
+    async fn resume_stark(&self, proof_id: &str) -> Result<(), ProverError> {
+        Err(ProverError::ProverInternalError(format!(
+            "Cannot resume proof {proof_id}: checkpointing is not supported"
+        )))
+    }
     async fn get_receipt(&self, proof_id: &str) -> Result<Option<Receipt>, ProverError>;
     async fn get_preflight_journal(&self, proof_id: &str) -> Result<Option<Vec<u8>>, ProverError>;
     async fn get_journal(&self, proof_id: &str) -> Result<Option<Vec<u8>>, ProverError>;
@@ -0,0 +1,673 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pool of prover backends that routes jobs between them and fails over on errors.
+//!
+//! IDs handed out by the pool are prefixed with the name of the backend they belong to, so
+//! proofs can be routed back to their backend after a restart. Inputs and receipts are uploaded
+//! to every backend, and their pool ID lists the ID on each backend the upload succeeded on.
+
+use std::{
+    collections::HashSet,
+    future::Future,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::future::join_all;
+use moka::future::Cache;
+use risc0_zkvm::Receipt;
+
+use super::{Bonsai, DefaultProver, ProofResult, Prover, ProverError, ProverObj};
+use crate::config::{ConfigLock, ProverBackendConf, ProverBackendKind};
+
+/// Separates the backend name from the ID on the backend.
+const NAME_SEPARATOR: char = ':';
+/// Separates the IDs of an input on each backend.
+const INPUT_SEPARATOR: char = ',';
+/// Max inputs whose preflight cycle count is kept to route their proofs.
+const INPUT_CYCLES_CACHE_SIZE: u64 = 10_000;
+
+struct Backend {
+    conf: ProverBackendConf,
+    prover: ProverObj,
+    /// Preflights and proof submissions in progress
+    active: AtomicU32,
+    /// Proofs started on the backend that have not completed yet
+    running: Mutex<HashSet<String>>,
+    failed_until: Mutex<Option<Instant>>,
+}
+
+impl Backend {
+    fn in_flight(&self) -> u64 {
+        u64::from(self.active.load(Ordering::Relaxed)) + self.running.lock().unwrap().len() as u64
+    }
+
+    fn accepts(&self, cycles: Option<u64>) -> bool {
+        cycles.is_none_or(|cycles| {
+            self.conf.min_cycles.is_none_or(|min| cycles >= min)
+                && self.conf.max_cycles.is_none_or(|max| cycles <= max)
+        })
+    }
+
+    /// Sort key of the backend for a job of the given cycles; lower is preferred.
+    ///
+    /// Backends that recently failed are tried last, and backends outside their cycle limits
+    /// are only tried when the others failed.
+    fn rank(&self, cycles: Option<u64>, now: Instant) -> (bool, bool, bool, u64) {
+        let backing_off = self.failed_until.lock().unwrap().is_some_and(|until| until > now);
+        let in_flight = self.in_flight();
+        let at_capacity = self.conf.max_concurrent.is_some_and(|max| in_flight >= u64::from(max));
+        let load = match self.conf.weight {
+            0 => u64::MAX,
+            weight => in_flight * 1_000 / u64::from(weight),
+        };
+        (backing_off, !self.accepts(cycles), at_capacity, load)
+    }
+
+    fn failed(&self) {
+        let backoff = Duration::from_secs(self.conf.failover_backoff_secs);
+        *self.failed_until.lock().unwrap() = Some(Instant::now() + backoff);
+    }
+
+    /// Run a call on the backend, failing it if it exceeds the configured timeout.
+    async fn with_timeout<T>(
+        &self,
+        call: impl Future<Output = Result<T, ProverError>>,
+    ) -> Result<T, ProverError> {
+        match self.conf.timeout_secs {
+            Some(secs) => {
+                tokio::time::timeout(Duration::from_secs(secs), call).await.unwrap_or_else(|_| {
+                    Err(ProverError::ProverInternalError(format!("timed out after {secs}s")))
+                })
+            }
+            None => call.await,
+        }
+    }
+
+    fn pool_id(&self, id: &str) -> String {
+        format!("{}{NAME_SEPARATOR}{id}", self.conf.name)
+    }
+}
+
+/// Decrements the count of active jobs of a backend when dropped.
+struct ActiveGuard<'a>(&'a AtomicU32);
+
+impl Drop for ActiveGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// [Prover] delegating to multiple backends, configured with `[[provers]]`.
+pub struct ProverPool {
+    backends: Vec<Backend>,
+    /// Preflight cycle counts by pool input ID, used to route proofs of the input
+    input_cycles: Cache<String, u64>,
+}
+
+impl ProverPool {
+    /// Creates an empty pool; add backends with [ProverPool::with_backend].
+    pub fn new() -> Self {
+        Self {
+            backends: Vec::new(),
+            input_cycles: Cache::builder().max_capacity(INPUT_CYCLES_CACHE_SIZE).build(),
+        }
+    }
+
+    /// Creates a pool of the configured backends.
+    pub fn from_conf(config: &ConfigLock, backends: &[ProverBackendConf]) -> Result<Self> {
+        let mut pool = Self::new();
+        for conf in backends {
+            let prover: ProverObj = match conf.kind {
+                ProverBackendKind::Local => Arc::new(DefaultProver::new()),
+                ProverBackendKind::Bento | ProverBackendKind::Bonsai => {
+                    let url = conf
+                        .url
+                        .as_ref()
+                        .with_context(|| format!("Prover backend {} requires a url", conf.name))?;
+                    let api_key = match conf.kind {
+                        ProverBackendKind::Bonsai => {
+                            conf.api_key.as_deref().with_context(|| {
+                                format!("Prover backend {} requires an api_key", conf.name)
+                            })?
+                        }
+                        _ => "",
+                    };
+                    Arc::new(Bonsai::new(config.clone(), url.as_ref(), api_key).with_context(
+                        || format!("Failed to initialize prover backend {}", conf.name),
+                    )?)
+                }
+            };
+            pool = pool.with_backend(conf.clone(), prover)?;
+        }
+        anyhow::ensure!(!pool.backends.is_empty(), "No prover backends configured");
+        Ok(pool)
+    }
+
+    /// Adds a backend to the pool.
+    pub fn with_backend(mut self, conf: ProverBackendConf, prover: ProverObj) -> Result<Self> {
+        anyhow::ensure!(
+            !conf.name.is_empty() && !conf.name.contains([NAME_SEPARATOR, INPUT_SEPARATOR]),
+            "Invalid prover backend name {:?}",
+            conf.name
+        );
+        anyhow::ensure!(
+            self.backends.iter().all(|backend| backend.conf.name != conf.name),
+            "Duplicate prover backend name {}",
+            conf.name
+        );
+        self.backends.push(Backend {
+            conf,
+            prover,
+            active: AtomicU32::new(0),
+            running: Mutex::new(HashSet::new()),
+            failed_until: Mutex::new(None),
+        });
+        Ok(self)
+    }
+
+    /// The backend of a pool ID, along with the ID on the backend.
+    fn backend<'a>(&self, pool_id: &'a str) -> Result<(&Backend, &'a str), ProverError> {
+        pool_id
+            .split_once(NAME_SEPARATOR)
+            .and_then(|(name, id)| {
+                self.backends.iter().find(|backend| backend.conf.name == name).map(|b| (b, id))
+            })
+            .ok_or_else(|| ProverError::NotFound(format!("prover backend of {pool_id}")))
+    }
+
+    /// IDs of a pool input on each backend it was uploaded to, by backend index.
+    fn input_ids(&self, input_id: &str) -> Vec<Option<String>> {
+        let mut ids = vec![None; self.backends.len()];
+        for (name, id) in
+            input_id.split(INPUT_SEPARATOR).filter_map(|entry| entry.split_once(NAME_SEPARATOR))
+        {
+            if let Some(idx) = self.backends.iter().position(|backend| backend.conf.name == name) {
+                ids[idx] = Some(id.to_string());
+            }
+        }
+        ids
+    }
+
+    /// IDs on the backend at `idx` of the given assumptions, which are pool IDs of uploaded
+    /// receipts or of proofs.
+    fn backend_assumptions(&self, assumptions: &[String], idx: usize) -> Vec<String> {
+        assumptions
+            .iter()
+            .map(|id| self.input_ids(id).swap_remove(idx).unwrap_or_else(|| id.clone()))
+            .collect()
+    }
+
+    /// Upload to every backend, returning the pool ID listing the ID on each backend the upload
+    /// succeeded on.
+    async fn upload_to_all<'a, F, Fut>(
+        &'a self,
+        what: &str,
+        upload: F,
+    ) -> Result<String, ProverError>
+    where
+        F: Fn(&'a ProverObj) -> Fut,
+        Fut: Future<Output = Result<String, ProverError>>,
+    {
+        let uploads =
+            self.backends.iter().map(|backend| backend.with_timeout(upload(&backend.prover)));
+
+        let mut ids = Vec::new();
+        let mut last_err = None;
+        for (backend, res) in self.backends.iter().zip(join_all(uploads).await) {
+            match res {
+                Ok(id) => ids.push(backend.pool_id(&id)),
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to upload {what} to prover backend {}: {err}",
+                        backend.conf.name
+                    );
+                    backend.failed();
+                    last_err = Some(err);
+                }
+            }
+        }
+        if ids.is_empty() {
+            return Err(last_err.unwrap_or_else(|| {
+                ProverError::ProverInternalError("No prover backends configured".into())
+            }));
+        }
+        Ok(ids.join(&INPUT_SEPARATOR.to_string()))
+    }
+
+    /// Indices of the backends to try in order, among those that have the input.
+    fn candidates(&self, input_ids: &[Option<String>], cycles: Option<u64>) -> Vec<usize> {
+        let mut candidates: Vec<usize> =
+            (0..self.backends.len()).filter(|&idx| input_ids[idx].is_some()).collect();
+        let now = Instant::now();
+        candidates.sort_by_cached_key(|&idx| self.backends[idx].rank(cycles, now));
+        candidates
+    }
+
+    /// Run a job on the candidate backends in turn, until one succeeds.
+    ///
+    /// Errors of the job itself, such as a guest panic, are returned as is, as they would fail on
+    /// any backend.
+    async fn with_failover<T, F, Fut>(
+        &self,
+        op: &str,
+        candidates: Vec<usize>,
+        timeout: bool,
+        f: F,
+    ) -> Result<(usize, T), ProverError>
+    where
+        F: Fn(usize, ProverObj) -> Fut,
+        Fut: Future<Output = Result<T, ProverError>>,
+    {
+        let mut last_err = None;
+        for idx in candidates {
+            let backend = &self.backends[idx];
+            backend.active.fetch_add(1, Ordering::Relaxed);
+            let _guard = ActiveGuard(&backend.active);
+
+            let job = f(idx, backend.prover.clone());
+            let res = match timeout {
+                true => backend.with_timeout(job).await,
+                false => job.await,
+            };
+            match res {
+                Ok(res) => return Ok((idx, res)),
+                Err(err @ ProverError::ProvingFailed(_)) => return Err(err),
+                Err(err) => {
+                    tracing::warn!(
+                        "Prover backend {} failed to {op}, failing over: {err}",
+                        backend.conf.name
+                    );
+                    backend.failed();
+                    last_err = Some(err);
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            ProverError::ProverInternalError(format!("No prover backend available to {op}"))
+        }))
+    }
+
+    fn proof_finished(&self, proof_id: &str) {
+        if let Ok((backend, id)) = self.backend(proof_id) {
+            backend.running.lock().unwrap().remove(id);
+        }
+    }
+}
+
+impl Default for ProverPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Prover for ProverPool {
+    async fn has_image(&self, image_id: &str) -> Result<bool, ProverError> {
+        for backend in &self.backends {
+            if !backend.prover.has_image(image_id).await.unwrap_or(false) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn upload_input(&self, input: Vec<u8>) -> Result<String, ProverError> {
+        self.upload_to_all("input", |prover| prover.upload_input(input.clone())).await
+    }
+
+    async fn delete_input(&self, input_id: &str) -> Result<(), ProverError> {
+        self.input_cycles.invalidate(input_id).await;
+        for (backend, id) in self.backends.iter().zip(self.input_ids(input_id)) {
+            let Some(id) = id else { continue };
+            if let Err(err) = backend.prover.delete_input(&id).await {
+                tracing::warn!(
+                    "Failed to delete input from prover backend {}: {err}",
+                    backend.conf.name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn upload_image(&self, image_id: &str, image: Vec<u8>) -> Result<(), ProverError> {
+        let uploads = self
+            .backends
+            .iter()
+            .map(|backend| backend.prover.upload_image(image_id, image.clone()));
+        let results = join_all(uploads).await;
+        let mut uploaded = false;
+        let mut last_err = None;
+        for (backend, res) in self.backends.iter().zip(results) {
+            match res {
+                Ok(()) => uploaded = true,
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to upload image to prover backend {}: {err}",
+                        backend.conf.name
+                    );
+                    backend.failed();
+                    last_err = Some(err);
+                }
+            }
+        }
+        match (uploaded, last_err) {
+            (false, Some(err)) => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    async fn upload_receipt(&self, receipt: Vec<u8>) -> Result<String, ProverError> {
+        self.upload_to_all("receipt", |prover| prover.upload_receipt(receipt.clone())).await
+    }
+
+    async fn preflight(
+        &self,
+        image_id: &str,
+        input_id: &str,
+        assumptions: Vec<String>,
+        executor_limit: Option<u64>,
+        order_id: &str,
+    ) -> Result<ProofResult, ProverError> {
+        let input_ids = self.input_ids(input_id);
+        let candidates = self.candidates(&input_ids, None);
+        let (idx, mut res) = self
+            .with_failover("preflight", candidates, true, |idx, prover| {
+                let input_id = input_ids[idx].clone().unwrap();
+                let assumptions = self.backend_assumptions(&assumptions, idx);
+                async move {
+                    prover
+                        .preflight(image_id, &input_id, assumptions, executor_limit, order_id)
+                        .await
+                }
+            })
+            .await?;
+        self.input_cycles.insert(input_id.to_string(), res.stats.total_cycles).await;
+        res.id = self.backends[idx].pool_id(&res.id);
+        Ok(res)
+    }
+
+    async fn prove_stark(
+        &self,
+        image_id: &str,
+        input_id: &str,
+        assumptions: Vec<String>,
+    ) -> Result<String, ProverError> {
+        let input_ids = self.input_ids(input_id);
+        let cycles = self.input_cycles.get(input_id).await;
+        let candidates = self.candidates(&input_ids, cycles);
+        let (idx, proof_id) = self
+            .with_failover("prove", candidates, true, |idx, prover| {
+                let input_id = input_ids[idx].clone().unwrap();
+                let assumptions = self.backend_assumptions(&assumptions, idx);
+                async move { prover.prove_stark(image_id, &input_id, assumptions).await }
+            })
+            .await?;
+        let backend = &self.backends[idx];
+        backend.running.lock().unwrap().insert(proof_id.clone());
+        tracing::debug!("Proving input {input_id} on prover backend {}", backend.conf.name);
+        Ok(backend.pool_id(&proof_id))
+    }
+
+    async fn prove_and_monitor_stark(
+        &self,
+        image_id: &str,
+        input_id: &str,
+        assumptions: Vec<String>,
+    ) -> Result<ProofResult, ProverError> {
+        let input_ids = self.input_ids(input_id);
+        let cycles = self.input_cycles.get(input_id).await;
+        let candidates = self.candidates(&input_ids, cycles);
+        let (idx, mut res) = self
+            .with_failover("prove", candidates, false, |idx, prover| {
+                let input_id = input_ids[idx].clone().unwrap();
+                let assumptions = self.backend_assumptions(&assumptions, idx);
+                async move { prover.prove_and_monitor_stark(image_id, &input_id, assumptions).await }
+            })
+            .await?;
+        res.id = self.backends[idx].pool_id(&res.id);
+        Ok(res)
+    }
+
+    async fn wait_for_stark(&self, proof_id: &str) -> Result<ProofResult, ProverError> {
+        let (backend, id) = self.backend(proof_id)?;
+        let res = backend.prover.wait_for_stark(id).await;
+        self.proof_finished(proof_id);
+        let mut res = res?;
+        res.id = backend.pool_id(&res.id);
+        Ok(res)
+    }
+
+    async fn cancel_stark(&self, proof_id: &str) -> Result<(), ProverError> {
+        let (backend, id) = self.backend(proof_id)?;
+        backend.prover.cancel_stark(id).await?;
+        self.proof_finished(proof_id);
+        Ok(())
+    }
+
+    /// Proofs listed by each backend, or the proofs the pool started on backends that cannot
+    /// list them.
+    async fn active_proofs(&self) -> Result<Option<Vec<String>>, ProverError> {
+        let mut active_proofs = Vec::new();
+        for backend in &self.backends {
+            let proof_ids = match backend.prover.active_proofs().await? {
+                Some(proof_ids) => proof_ids,
+                None => backend.running.lock().unwrap().iter().cloned().collect(),
+            };
+            active_proofs.extend(proof_ids.iter().map(|id| backend.pool_id(id)));
+        }
+        Ok(Some(active_proofs))
+    }
+
+    fn supports_checkpointing(&self) -> bool {
+        self.backends.iter().all(|backend| backend.prover.supports_checkpointing())
+    }
+
+    async fn suspend_stark(&self, proof_id: &str) -> Result<(), ProverError> {
+        let (backend, id) = self.backend(proof_id)?;
+        backend.prover.suspend_stark(id).await?;
+        self.proof_finished(proof_id);
+        Ok(())
+    }
+
+    async fn resume_stark(&self, proof_id: &str) -> Result<(), ProverError> {
+        let (backend, id) = self.backend(proof_id)?;
+        backend.prover.resume_stark(id).await?;
+        backend.running.lock().unwrap().insert(id.to_string());
+        Ok(())
+    }
+
+    async fn get_receipt(&self, proof_id: &str) -> Result<Option<Receipt>, ProverError> {
+        let (backend, id) = self.backend(proof_id)?;
+        backend.prover.get_receipt(id).await
+    }
+
+    async fn get_preflight_journal(&self, proof_id: &str) -> Result<Option<Vec<u8>>, ProverError> {
+        let (backend, id) = self.backend(proof_id)?;
+        backend.prover.get_preflight_journal(id).await
+    }
+
+    async fn get_journal(&self, proof_id: &str) -> Result<Option<Vec<u8>>, ProverError> {
+        let (backend, id) = self.backend(proof_id)?;
+        backend.prover.get_journal(id).await
+    }
+
+    async fn compress(&self, proof_id: &str) -> Result<String, ProverError> {
+        let (backend, id) = self.backend(proof_id)?;
+        let compressed_id = backend.prover.compress(id).await?;
+        Ok(backend.pool_id(&compressed_id))
+    }
+
+    async fn get_compressed_receipt(&self, proof_id: &str) -> Result<Option<Vec<u8>>, ProverError> {
+        let (backend, id) = self.backend(proof_id)?;
+        backend.prover.get_compressed_receipt(id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provers::ExecutorResp;
+    use std::sync::atomic::AtomicBool;
+
+    /// Backend that succeeds with fixed cycle counts, unless set to fail.
+    #[derive(Default)]
+    struct MockProver {
+        failing: AtomicBool,
+        proofs: AtomicU32,
+    }
+
+    impl MockProver {
+        fn check(&self) -> Result<(), ProverError> {
+            match self.failing.load(Ordering::Relaxed) {
+                true => Err(ProverError::ProverInternalError("backend down".into())),
+                false => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Prover for MockProver {
+        async fn has_image(&self, _image_id: &str) -> Result<bool, ProverError> {
+            Ok(true)
+        }
+        async fn upload_input(&self, _input: Vec<u8>) -> Result<String, ProverError> {
+            self.check()?;
+            Ok("input".into())
+        }
+        async fn upload_image(&self, _image_id: &str, _image: Vec<u8>) -> Result<(), ProverError> {
+            self.check()
+        }
+        async fn preflight(
+            &self,
+            _image_id: &str,
+            input_id: &str,
+            _assumptions: Vec<String>,
+            _executor_limit: Option<u64>,
+            _order_id: &str,
+        ) -> Result<ProofResult, ProverError> {
+            self.check()?;
+            Ok(ProofResult {
+                id: format!("preflight-{input_id}"),
+                stats: ExecutorResp { total_cycles: 1_000_000, ..Default::default() },
+                elapsed_time: 0.0,
+            })
+        }
+        async fn prove_stark(
+            &self,
+            _image_id: &str,
+            _input_id: &str,
+            _assumptions: Vec<String>,
+        ) -> Result<String, ProverError> {
+            self.check()?;
+            Ok(format!("proof-{}", self.proofs.fetch_add(1, Ordering::Relaxed)))
+        }
+        async fn wait_for_stark(&self, proof_id: &str) -> Result<ProofResult, ProverError> {
+            Ok(ProofResult { id: proof_id.into(), ..Default::default() })
+        }
+        async fn cancel_stark(&self, _proof_id: &str) -> Result<(), ProverError> {
+            Ok(())
+        }
+        async fn get_receipt(&self, _proof_id: &str) -> Result<Option<Receipt>, ProverError> {
+            Ok(None)
+        }
+        async fn get_preflight_journal(
+            &self,
+            proof_id: &str,
+        ) -> Result<Option<Vec<u8>>, ProverError> {
+            Ok(Some(proof_id.as_bytes().to_vec()))
+        }
+        async fn get_journal(&self, _proof_id: &str) -> Result<Option<Vec<u8>>, ProverError> {
+            Ok(None)
+        }
+        async fn compress(&self, proof_id: &str) -> Result<String, ProverError> {
+            Ok(proof_id.into())
+        }
+        async fn get_compressed_receipt(
+            &self,
+            _proof_id: &str,
+        ) -> Result<Option<Vec<u8>>, ProverError> {
+            Ok(None)
+        }
+    }
+
+    fn conf(name: &str) -> ProverBackendConf {
+        ProverBackendConf {
+            name: name.into(),
+            kind: ProverBackendKind::Local,
+            url: None,
+            api_key: None,
+            weight: 1,
+            min_cycles: None,
+            max_cycles: None,
+            max_concurrent: None,
+            timeout_secs: None,
+            failover_backoff_secs: 60,
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_by_cycles_and_fails_over() {
+        let small = Arc::new(MockProver::default());
+        let large = Arc::new(MockProver::default());
+        let pool = ProverPool::new()
+            .with_backend(
+                ProverBackendConf { max_cycles: Some(10_000_000), ..conf("small") },
+                small.clone(),
+            )
+            .unwrap()
+            .with_backend(
+                ProverBackendConf { min_cycles: Some(10_000_000), ..conf("large") },
+                large.clone(),
+            )
+            .unwrap();
+
+        let input_id = pool.upload_input(vec![1]).await.unwrap();
+        assert_eq!(input_id, "small:input,large:input");
+
+        let preflight = pool.preflight("image", &input_id, vec![], None, "order").await.unwrap();
+        let journal = pool.get_preflight_journal(&preflight.id).await.unwrap().unwrap();
+        assert!(preflight.id.ends_with(":preflight-input"));
+        assert_eq!(journal, b"preflight-input");
+
+        // 1M preflight cycles are only accepted by the small backend
+        let proof_id = pool.prove_stark("image", &input_id, vec![]).await.unwrap();
+        assert_eq!(proof_id, "small:proof-0");
+        assert_eq!(pool.wait_for_stark(&proof_id).await.unwrap().id, proof_id);
+
+        // Fails over to the large backend when the small one is down, and avoids the small one
+        // while it backs off
+        small.failing.store(true, Ordering::Relaxed);
+        assert_eq!(pool.prove_stark("image", &input_id, vec![]).await.unwrap(), "large:proof-0");
+        small.failing.store(false, Ordering::Relaxed);
+        assert_eq!(pool.prove_stark("image", &input_id, vec![]).await.unwrap(), "large:proof-1");
+
+        // Inputs that failed to upload to a backend are not routed there
+        large.failing.store(true, Ordering::Relaxed);
+        let input_id = pool.upload_input(vec![2]).await.unwrap();
+        assert_eq!(input_id, "small:input");
+        assert!(pool.prove_stark("image", &input_id, vec![]).await.unwrap().starts_with("small:"));
+
+        assert!(matches!(
+            pool.wait_for_stark("unknown:proof-0").await,
+            Err(ProverError::NotFound(_))
+        ));
+    }
+}
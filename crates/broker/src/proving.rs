@@ -13,21 +13,34 @@
 // limitations under the License.
 
 use std::future::pending;
+use std::sync::Arc;
 use std::time::Duration;
 
 use crate::{
     config::ConfigLock,
     db::DbObj,
     errors::CodedError,
+    events::{BrokerEvent, EventBus},
     futures_retry::retry,
+    groth16_wrapper::{Groth16Wrapper, Groth16WrapperObj},
+    image_cache::ImageCacheObj,
     impl_coded_debug,
+    input_dedup::InputDedupObj,
+    input_fetcher::{InputFetcher, InputFetcherObj},
+    leadership::LeadershipObj,
     provers::ProverObj,
+    proving_capacity::ProvingCapacityTrackerObj,
+    scheduler::{ProvingScheduler, ProvingSchedulerObj},
     task::{RetryRes, RetryTask, SupervisorErr},
     utils::cancel_proof_and_fail_order,
     Order, OrderStateChange, OrderStatus,
 };
+
+/// Max number of orders waiting to be proven that are considered by the scheduler at once
+const MAX_SCHEDULED_ORDERS: u32 = 1000;
 use anyhow::{Context, Result};
 use thiserror::Error;
+use tokio::sync::broadcast::error::RecvError;
 use tokio_util::sync::CancellationToken;
 
 #[derive(Error)]
@@ -63,7 +76,14 @@ pub struct ProvingService {
     db: DbObj,
     prover: ProverObj,
     config: ConfigLock,
-    order_state_tx: tokio::sync::broadcast::Sender<OrderStateChange>,
+    events: EventBus,
+    capacity_tracker: ProvingCapacityTrackerObj,
+    input_dedup: InputDedupObj,
+    input_fetcher: InputFetcherObj,
+    scheduler: ProvingSchedulerObj,
+    image_cache: Option<ImageCacheObj>,
+    groth16_wrapper: Groth16WrapperObj,
+    leadership: LeadershipObj,
 }
 
 impl ProvingService {
@@ -71,9 +91,52 @@ impl ProvingService {
         db: DbObj,
         prover: ProverObj,
         config: ConfigLock,
-        order_state_tx: tokio::sync::broadcast::Sender<OrderStateChange>,
+        events: EventBus,
+        capacity_tracker: ProvingCapacityTrackerObj,
+        input_dedup: InputDedupObj,
     ) -> Result<Self> {
-        Ok(Self { db, prover, config, order_state_tx })
+        let input_fetcher = Arc::new(InputFetcher::new(config.clone(), 0));
+        let groth16_workers =
+            config.lock_all().context("Failed to read config")?.prover.groth16_workers;
+        let groth16_wrapper = Arc::new(Groth16Wrapper::new(prover.clone(), groth16_workers));
+        Ok(Self {
+            db,
+            prover,
+            config,
+            events,
+            capacity_tracker,
+            input_dedup,
+            input_fetcher,
+            scheduler: Arc::new(ProvingScheduler::default()),
+            image_cache: None,
+            groth16_wrapper,
+            leadership: Default::default(),
+        })
+    }
+
+    /// Fetch inputs through the given fetcher, shared with the order picker.
+    pub(crate) fn with_input_fetcher(self, input_fetcher: InputFetcherObj) -> Self {
+        Self { input_fetcher, ..self }
+    }
+
+    /// Read and store images in the given cache, shared with the order picker.
+    pub(crate) fn with_image_cache(self, image_cache: ImageCacheObj) -> Self {
+        Self { image_cache: Some(image_cache), ..self }
+    }
+
+    /// Schedule proofs with the given scheduler, shared with the order picker.
+    pub(crate) fn with_scheduler(self, scheduler: ProvingSchedulerObj) -> Self {
+        Self { scheduler, ..self }
+    }
+
+    /// Wrap Groth16 proofs with the given wrapper, shared with the order picker.
+    pub(crate) fn with_groth16_wrapper(self, groth16_wrapper: Groth16WrapperObj) -> Self {
+        Self { groth16_wrapper, ..self }
+    }
+
+    /// Start no proofs while on standby, until this replica acquires the leadership lease.
+    pub(crate) fn with_leadership(self, leadership: LeadershipObj) -> Self {
+        Self { leadership, ..self }
     }
 
     async fn cancel_stark_session(&self, proof_id: &str, order_id: &str, reason: &str) {
@@ -100,10 +163,14 @@ impl ProvingService {
             .wait_for_stark(stark_proof_id)
             .await
             .context("Monitoring proof (stark) failed")?;
+        self.capacity_tracker.record_proof(&proof_res);
 
         if is_groth16 && snark_proof_id.is_none() {
-            let compressed_proof_id =
-                self.prover.compress(stark_proof_id).await.context("Failed to compress proof")?;
+            let compressed_proof_id = self
+                .groth16_wrapper
+                .wrap(stark_proof_id)
+                .await
+                .context("Failed to compress proof")?;
             self.db
                 .set_order_compressed_proof_id(order_id, &compressed_proof_id)
                 .await
@@ -145,25 +212,43 @@ impl ProvingService {
                 // Mostly hit by skipping pre-flight
                 let image_id = match order.image_id.as_ref() {
                     Some(val) => val.clone(),
-                    None => {
-                        crate::storage::upload_image_uri(&self.prover, &order.request, &self.config)
-                            .await
-                            .context("Failed to upload image")?
-                    }
+                    None => crate::storage::upload_image_uri(
+                        &self.prover,
+                        &order.request,
+                        &self.config,
+                        self.image_cache.as_ref(),
+                    )
+                    .await
+                    .context("Failed to upload image")?,
                 };
 
                 let input_id = match order.input_id.as_ref() {
                     Some(val) => val.clone(),
-                    None => {
-                        crate::storage::upload_input_uri(&self.prover, &order.request, &self.config)
-                            .await
-                            .context("Failed to upload input")?
-                    }
+                    None => crate::storage::upload_input_uri(
+                        &self.input_dedup,
+                        &self.input_fetcher,
+                        &order.request,
+                        &order_id,
+                    )
+                    .await
+                    .context("Failed to upload input")?,
+                };
+
+                let assumption_ids = match order.assumption_ids.as_ref() {
+                    Some(val) => val.clone(),
+                    None => crate::storage::upload_assumptions_uri(
+                        &self.prover,
+                        &self.input_fetcher,
+                        &order.request,
+                        &self.config,
+                    )
+                    .await
+                    .context("Failed to upload assumptions")?,
                 };
 
                 let proof_id = self
                     .prover
-                    .prove_stark(&image_id, &input_id, /* TODO assumptions */ vec![])
+                    .prove_stark(&image_id, &input_id, assumption_ids)
                     .await
                     .context("Failed to prove customer proof STARK order")?;
 
@@ -198,7 +283,7 @@ impl ProvingService {
             order.fulfillment_type,
             crate::FulfillmentType::FulfillAfterLockExpire
         ) {
-            let rx = self.order_state_tx.subscribe();
+            let rx = self.events.subscribe();
 
             // Check if the order has already been fulfilled before starting proof
             match self.db.is_request_fulfilled(request_id).await {
@@ -224,12 +309,33 @@ impl ProvingService {
             None
         };
 
-        let monitor_task = self.monitor_proof_internal(
-            &order_id,
-            proof_id,
-            order.is_groth16(),
-            order.compressed_proof_id,
-        );
+        let lock_expires_at = order.request.lock_expires_at();
+        let is_lock_expire_order =
+            order.fulfillment_type == crate::FulfillmentType::FulfillAfterLockExpire;
+        let monitor_task = async {
+            let status = self
+                .monitor_proof_internal(
+                    &order_id,
+                    proof_id,
+                    order.is_groth16(),
+                    order.compressed_proof_id,
+                )
+                .await?;
+
+            // Proving may have started speculatively before the lock expired. Hold the proof
+            // back from aggregation until the lock has expired, as fulfilling earlier would not
+            // be paid. The original locker fulfilling in the meantime cancels the order below.
+            let now = crate::now_timestamp();
+            if is_lock_expire_order && lock_expires_at >= now {
+                tracing::info!(
+                    "Order {order_id} proven before its lock expired, holding for {} seconds until lock expiry",
+                    lock_expires_at + 1 - now
+                );
+                tokio::time::sleep(Duration::from_secs(lock_expires_at + 1 - now)).await;
+            }
+
+            Ok::<_, anyhow::Error>(status)
+        };
         tokio::pin!(monitor_task);
 
         // Note: this timeout may not exactly match the order expiry exactly due to
@@ -260,12 +366,12 @@ impl ProvingService {
                 // External fulfillment notification (only active for FulfillAfterLockExpire orders)
                 Some(recv_res) = async {
                     match &mut order_state_rx {
-                        Some(rx) => Some(rx.recv().await),
-                        None => pending::<Option<Result<OrderStateChange, tokio::sync::broadcast::error::RecvError>>>().await,
+                        Some(rx) => Some(rx.recv_order_state().await),
+                        None => pending::<Option<Result<OrderStateChange, RecvError>>>().await,
                     }
                 } => {
                     match recv_res {
-                        Ok(OrderStateChange::Fulfilled { request_id: fulfilled_request_id }) if fulfilled_request_id == request_id => {
+                        Ok(OrderStateChange::Fulfilled { request_id: fulfilled_request_id, .. }) if fulfilled_request_id == request_id => {
                             tracing::debug!(
                                 "Order {} (request {}) was fulfilled by another prover, cancelling proof {}",
                                 order_id,
@@ -312,24 +418,45 @@ impl ProvingService {
                 tracing::error!(
                     "Failed to create stark session for order {order_id}: {proving_err:?}"
                 );
-                handle_order_failure(&self.db, &order_id, "Proving session create failed").await;
+                self.handle_order_failure(&order_id, "Proving session create failed").await;
+                self.scheduler.finished(&order_id);
                 return;
             }
         };
 
+        let suspended = self.scheduler.started(&order, &proof_id, crate::now_timestamp());
+        self.events.publish(BrokerEvent::ProofStarted {
+            order_id: order_id.clone(),
+            proof_id: proof_id.clone(),
+        });
         order.proof_id = Some(proof_id);
 
-        let result = retry(
-            proof_retry_count,
-            proof_retry_sleep_ms,
-            || async { self.monitor_proof_with_timeout(order.clone()).await },
-            "monitor_proof_with_timeout",
-        )
-        .await;
+        let result = tokio::select! {
+            biased;
+            _ = suspended.cancelled() => None,
+            res = retry(
+                proof_retry_count,
+                proof_retry_sleep_ms,
+                || async { self.monitor_proof_with_timeout(order.clone()).await },
+                "monitor_proof_with_timeout",
+            ) => Some(res),
+        };
+        // The proof was suspended for a more urgent order, and requeued to be resumed later
+        let Some(result) = result.filter(|_| !suspended.is_cancelled()) else {
+            tracing::info!("Proof of order {order_id} suspended");
+            return;
+        };
+        self.scheduler.finished(&order_id);
 
         match result {
             Ok(order_status) => {
                 tracing::info!("Successfully completed proof monitoring for order {order_id}");
+                if let Some(proof_id) = order.proof_id.clone() {
+                    self.events.publish(BrokerEvent::ProofCompleted {
+                        order_id: order_id.clone(),
+                        proof_id,
+                    });
+                }
 
                 if let Err(e) = self.db.set_aggregation_status(&order_id, order_status).await {
                     tracing::error!("Failed to set aggregation status for order {order_id}: {e:?}");
@@ -337,7 +464,7 @@ impl ProvingService {
             }
             Err(ProvingErr::ExternallyFulfilled) => {
                 tracing::info!("Order {order_id} was fulfilled by another prover, cancelled proof");
-                handle_order_failure(&self.db, &order_id, "Externally fulfilled").await;
+                self.handle_order_failure(&order_id, "Externally fulfilled").await;
             }
             Err(err) => {
                 tracing::error!(
@@ -346,9 +473,98 @@ impl ProvingService {
                     proof_retry_count
                 );
 
-                handle_order_failure(&self.db, &order_id, "Proving failed").await;
+                self.handle_order_failure(&order_id, "Proving failed").await;
+            }
+        }
+    }
+
+    async fn handle_order_failure(&self, order_id: &str, failure_reason: &'static str) {
+        if let Err(inner_err) = self.db.set_order_failure(order_id, failure_reason).await {
+            tracing::error!("Failed to set order {order_id} failure: {inner_err:?}");
+        }
+        self.events.publish(BrokerEvent::OrderFailed {
+            order_id: order_id.to_string(),
+            error: failure_reason.to_string(),
+        });
+    }
+
+    /// Suspend the running proof of the order and requeue it, returning whether it was suspended.
+    async fn suspend_proof(&self, order_id: &str, peak_prove_khz: Option<u64>) -> bool {
+        let Some(proof_id) = self.scheduler.running_proof_id(order_id) else {
+            return false;
+        };
+        if let Err(err) = self.prover.suspend_stark(&proof_id).await {
+            tracing::warn!("Failed to suspend proof {proof_id} for order {order_id}: {err}");
+            return false;
+        }
+        self.scheduler.suspended(order_id, peak_prove_khz, crate::now_timestamp());
+        if let Err(err) =
+            self.db.set_aggregation_status(order_id, OrderStatus::PendingProving).await
+        {
+            tracing::error!("Failed to requeue suspended order {order_id}: {err:?}");
+        }
+        true
+    }
+
+    /// Start proving the most urgent orders waiting to be proven, suspending running proofs
+    /// to make room for orders that would otherwise miss their deadline.
+    async fn schedule_proofs(&self) -> Result<()> {
+        let (max_active_proofs, peak_prove_khz) = {
+            let config = self.config.lock_all().context("Failed to read config")?;
+            (config.prover.max_active_proofs, config.market.peak_prove_khz)
+        };
+
+        let waiting = self
+            .db
+            .get_orders_by_status(OrderStatus::PendingProving, MAX_SCHEDULED_ORDERS)
+            .await
+            .context("Failed to get orders pending proving")?;
+        if waiting.is_empty() {
+            return Ok(());
+        }
+
+        let mut plan = self.scheduler.plan(
+            &waiting,
+            max_active_proofs,
+            peak_prove_khz,
+            self.prover.supports_checkpointing(),
+            crate::now_timestamp(),
+        );
+        // Orders started in place of a suspended proof are last in the plan, so drop one for
+        // each proof that failed to suspend
+        for order_id in &plan.suspend {
+            if !self.suspend_proof(order_id, peak_prove_khz).await {
+                plan.start.pop();
             }
         }
+
+        for order_id in plan.start {
+            let Some(order) = self
+                .db
+                .claim_proving_order(&order_id)
+                .await
+                .context("Failed to claim proving order")?
+            else {
+                continue;
+            };
+            let prov_serv = self.clone();
+            tokio::spawn(async move {
+                // Orders requeued after being suspended already have a proof to resume
+                if let Some(proof_id) = order.proof_id.as_deref() {
+                    if let Err(err) = prov_serv.prover.resume_stark(proof_id).await {
+                        tracing::error!(
+                            "Failed to resume proof {proof_id} for order {order_id}: {err:?}"
+                        );
+                        prov_serv.handle_order_failure(&order_id, "Proof resume failed").await;
+                        prov_serv.scheduler.finished(&order_id);
+                        return;
+                    }
+                }
+                prov_serv.prove_and_update_db(order).await
+            });
+        }
+
+        Ok(())
     }
 
     pub async fn find_and_monitor_proofs(&self) -> Result<(), ProvingErr> {
@@ -373,8 +589,7 @@ impl ProvingService {
 
             if order.proof_id.is_none() {
                 tracing::error!("Order in status Proving missing proof_id: {order_id}");
-                handle_order_failure(&prove_serv.db, &order_id, "Proving status missing proof_id")
-                    .await;
+                prove_serv.handle_order_failure(&order_id, "Proving status missing proof_id").await;
                 continue;
             }
 
@@ -395,38 +610,39 @@ impl RetryTask for ProvingService {
         Box::pin(async move {
             tracing::info!("Starting proving service");
 
-            // First search the DB for any existing dangling proofs and kick off their concurrent
-            // monitors
-            proving_service_copy.find_and_monitor_proofs().await.map_err(SupervisorErr::Fault)?;
-
             // Start monitoring for new proofs
             let mut proving_interval = tokio::time::interval(Duration::from_millis(500));
             proving_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut resumed = false;
             loop {
                 if cancel_token.is_cancelled() {
                     tracing::debug!("Proving service received cancellation");
                     break;
                 }
 
-                // TODO: parallel_proofs management
-                // we need to query the Bento/Bonsai backend and constrain the number of running
-                // parallel proofs currently bonsai does not have this feature but
-                // we could add it to both to support it. Alternatively we could
-                // track it in our local DB but that could de-sync from the proving-backend so
-                // its not ideal
-                let order_res = proving_service_copy
-                    .db
-                    .get_proving_order()
+                // Standby replicas leave the locked orders to the leader, which proves them
+                if !proving_service_copy.leadership.is_leader() {
+                    tracing::trace!("On standby, not proving orders until leader");
+                    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+                    continue;
+                }
+
+                // First search the DB for any existing dangling proofs and kick off their
+                // concurrent monitors
+                if !resumed {
+                    proving_service_copy
+                        .find_and_monitor_proofs()
+                        .await
+                        .map_err(SupervisorErr::Fault)?;
+                    resumed = true;
+                }
+
+                proving_service_copy
+                    .schedule_proofs()
                     .await
-                    .context("Failed to get proving order")
                     .map_err(ProvingErr::UnexpectedError)
                     .map_err(SupervisorErr::Recover)?;
 
-                if let Some(order) = order_res {
-                    let prov_serv = proving_service_copy.clone();
-                    tokio::spawn(async move { prov_serv.prove_and_update_db(order).await });
-                }
-
                 // TODO: configuration
                 tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
             }
@@ -436,19 +652,15 @@ impl RetryTask for ProvingService {
     }
 }
 
-async fn handle_order_failure(db: &DbObj, order_id: &str, failure_reason: &'static str) {
-    if let Err(inner_err) = db.set_order_failure(order_id, failure_reason).await {
-        tracing::error!("Failed to set order {order_id} failure: {inner_err:?}");
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
         db::SqliteDb,
+        input_dedup::InputDedup,
         now_timestamp,
         provers::{encode_input, DefaultProver},
+        proving_capacity::ProvingCapacityTracker,
         FulfillmentType, OrderStatus,
     };
     use alloy::primitives::{Address, Bytes, U256};
@@ -461,6 +673,26 @@ mod tests {
     use std::sync::Arc;
     use tracing_test::traced_test;
 
+    async fn proving_service(
+        db: &DbObj,
+        prover: &ProverObj,
+        config: &ConfigLock,
+        events: EventBus,
+    ) -> ProvingService {
+        let capacity_tracker = Arc::new(ProvingCapacityTracker::new(db.clone(), prover.clone()));
+        let input_dedup = Arc::new(InputDedup::new(prover.clone()));
+        ProvingService::new(
+            db.clone(),
+            prover.clone(),
+            config.clone(),
+            events,
+            capacity_tracker,
+            input_dedup,
+        )
+        .await
+        .unwrap()
+    }
+
     fn create_test_order(
         request_id: U256,
         image_id: String,
@@ -499,6 +731,7 @@ mod tests {
             },
             image_id: Some(image_id),
             input_id: Some(input_id),
+            assumption_ids: None,
             proof_id,
             compressed_proof_id: None,
             expire_timestamp: Some(now_timestamp() + 3600), // 1 hour from now
@@ -506,6 +739,8 @@ mod tests {
             lock_price: None,
             fulfillment_type,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: Address::ZERO,
             chain_id: 1,
             total_cycles: None,
@@ -513,14 +748,11 @@ mod tests {
         }
     }
 
-    async fn send_order_state_event(
-        order_state_tx: tokio::sync::broadcast::Sender<OrderStateChange>,
-        state_change: OrderStateChange,
-    ) {
+    async fn send_order_state_event(events: EventBus, change: OrderStateChange) {
         for _ in 0..50 {
             // Try for up to 5 seconds
             tokio::time::sleep(Duration::from_millis(100)).await;
-            if order_state_tx.send(state_change.clone()).is_ok() {
+            if events.publish(BrokerEvent::OrderStateChanged { change: change.clone() }) {
                 return;
             }
         }
@@ -541,11 +773,8 @@ mod tests {
             .await
             .unwrap();
 
-        let (order_state_tx, _) = tokio::sync::broadcast::channel(100);
-        let proving_service =
-            ProvingService::new(db.clone(), prover.clone(), config.clone(), order_state_tx)
-                .await
-                .unwrap();
+        let events = EventBus::new(100);
+        let proving_service = proving_service(&db, &prover, &config, events).await;
 
         let order = create_test_order(
             U256::ZERO,
@@ -564,11 +793,9 @@ mod tests {
         assert_eq!(order.status, OrderStatus::PendingAgg);
 
         // Test that LockAndFulfill orders ignore fulfillment events
-        let (order_state_tx, _) = tokio::sync::broadcast::channel(100);
+        let events = EventBus::new(100);
         let proving_service_with_fulfillment =
-            ProvingService::new(db.clone(), prover.clone(), config.clone(), order_state_tx.clone())
-                .await
-                .unwrap();
+            proving_service(&db, &prover, &config, events.clone()).await;
 
         let lock_and_fulfill_order = create_test_order(
             U256::from(999),
@@ -584,8 +811,11 @@ mod tests {
         // Spawn fulfillment event that should be ignored
         tokio::spawn(async move {
             send_order_state_event(
-                order_state_tx,
-                OrderStateChange::Fulfilled { request_id: lock_and_fulfill_order.request.id },
+                events,
+                OrderStateChange::Fulfilled {
+                    request_id: lock_and_fulfill_order.request.id,
+                    block_number: 1,
+                },
             )
             .await
         });
@@ -614,9 +844,8 @@ mod tests {
         // pre-prove the stark so it already exists before the service comes up
         let proof_id = prover.prove_stark(&image_id, &input_id, vec![]).await.unwrap();
 
-        let (order_state_tx, _) = tokio::sync::broadcast::channel(100);
-        let proving_service =
-            ProvingService::new(db.clone(), prover, config.clone(), order_state_tx).await.unwrap();
+        let events = EventBus::new(100);
+        let proving_service = proving_service(&db, &prover, &config, events).await;
 
         let order_id = U256::ZERO;
         let min_price = 2;
@@ -652,6 +881,7 @@ mod tests {
             },
             image_id: Some(image_id),
             input_id: Some(input_id),
+            assumption_ids: None,
             proof_id: Some(proof_id.clone()),
             compressed_proof_id: None,
             expire_timestamp: Some(now_timestamp() + 3600), // 1 hour from now
@@ -659,6 +889,8 @@ mod tests {
             lock_price: None,
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: Address::ZERO,
             chain_id: 1,
             total_cycles: None,
@@ -698,11 +930,8 @@ mod tests {
             .await
             .unwrap();
 
-        let (order_state_tx, _) = tokio::sync::broadcast::channel(100);
-        let proving_service =
-            ProvingService::new(db.clone(), prover.clone(), config.clone(), order_state_tx.clone())
-                .await
-                .unwrap();
+        let events = EventBus::new(100);
+        let proving_service = proving_service(&db, &prover, &config, events.clone()).await;
 
         let request_id = U256::from(123);
         let proof_id = prover.prove_stark(&image_id, &input_id, vec![]).await.unwrap();
@@ -726,8 +955,11 @@ mod tests {
         });
 
         // Send fulfillment event for the same request - should cancel proof
-        send_order_state_event(order_state_tx.clone(), OrderStateChange::Fulfilled { request_id })
-            .await;
+        send_order_state_event(
+            events.clone(),
+            OrderStateChange::Fulfilled { request_id, block_number: 1 },
+        )
+        .await;
 
         let result = monitor_task.await.unwrap();
         assert!(result.is_err());
@@ -738,7 +970,7 @@ mod tests {
         let different_fulfillment_id = U256::from(999);
         let proof_id_2 = prover.prove_stark(&image_id, &input_id, vec![]).await.unwrap();
 
-        let order_2 = create_test_order(
+        let mut order_2 = create_test_order(
             request_id_2,
             image_id,
             input_id,
@@ -746,6 +978,9 @@ mod tests {
             FulfillmentType::FulfillAfterLockExpire,
             OrderStatus::Proving,
         );
+        // Lock already expired, so the proof is not held back after completion.
+        order_2.request.offer.biddingStart = now_timestamp() - 10;
+        order_2.request.offer.lockTimeout = 1;
 
         db.add_order(&order_2).await.unwrap();
 
@@ -757,8 +992,8 @@ mod tests {
 
         // Send fulfillment event for different request ID - should be ignored
         send_order_state_event(
-            order_state_tx,
-            OrderStateChange::Fulfilled { request_id: different_fulfillment_id },
+            events,
+            OrderStateChange::Fulfilled { request_id: different_fulfillment_id, block_number: 1 },
         )
         .await;
 
@@ -768,4 +1003,40 @@ mod tests {
 
         assert!(logs_contain("was fulfilled by another prover"));
     }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn speculative_proof_held_until_lock_expiry() {
+        let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
+        let config = ConfigLock::default();
+        let prover: ProverObj = Arc::new(DefaultProver::new());
+
+        let image_id = Digest::from(ECHO_ID).to_string();
+        prover.upload_image(&image_id, ECHO_ELF.to_vec()).await.unwrap();
+        let input_id = prover
+            .upload_input(encode_input(&vec![0x41, 0x41, 0x41, 0x41]).unwrap())
+            .await
+            .unwrap();
+
+        let events = EventBus::new(100);
+        let proving_service = proving_service(&db, &prover, &config, events).await;
+
+        let proof_id = prover.prove_stark(&image_id, &input_id, vec![]).await.unwrap();
+        let mut order = create_test_order(
+            U256::from(321),
+            image_id,
+            input_id,
+            Some(proof_id),
+            FulfillmentType::FulfillAfterLockExpire,
+            OrderStatus::Proving,
+        );
+        order.request.offer.lockTimeout = 2;
+        let lock_expires_at = order.request.lock_expires_at();
+        db.add_order(&order).await.unwrap();
+
+        let status = proving_service.monitor_proof_with_timeout(order).await.unwrap();
+        assert_eq!(status, OrderStatus::PendingAgg);
+        assert!(now_timestamp() > lock_expires_at);
+        assert!(logs_contain("proven before its lock expired"));
+    }
 }
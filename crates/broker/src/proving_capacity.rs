@@ -0,0 +1,478 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    db::{DbError, DbObj},
+    now_timestamp,
+    provers::{ProofResult, ProverObj},
+    OrderStatus,
+};
+
+/// Number of completed proofs kept for estimating the proving throughput.
+const THROUGHPUT_SAMPLES: usize = 20;
+
+/// Age after which the busy intervals of completed proofs are dropped, in seconds.
+const BUSY_HISTORY_SECS: u64 = 24 * 60 * 60;
+
+/// Interval at which the proofs active on the prover backend are listed again.
+const ACTIVE_PROOFS_REFRESH: Duration = Duration::from_secs(5);
+
+/// Completed proof, as reported by the prover backend.
+#[derive(Clone, Copy)]
+struct ProofSample {
+    start: u64,
+    end: u64,
+    cycles: u64,
+}
+
+/// Tracks the proving queue and the throughput reported by the prover backend, to estimate when
+/// the prover will be free to start on a newly priced order.
+///
+/// The proofs in flight are listed from the backend where supported, so that proofs that
+/// completed or were started by other clients of the backend are accounted for. Shared by the
+/// order picker, the order monitor and the proving service.
+pub(crate) struct ProvingCapacityTracker {
+    db: DbObj,
+    prover: ProverObj,
+    throughput_samples: Mutex<VecDeque<ProofSample>>,
+    /// Start and end timestamps of completed proofs
+    busy_intervals: Mutex<VecDeque<(u64, u64)>>,
+    /// Proofs last listed by the backend, if it can list them
+    active_proofs: tokio::sync::Mutex<Option<(Instant, Option<HashSet<String>>)>>,
+}
+
+pub(crate) type ProvingCapacityTrackerObj = Arc<ProvingCapacityTracker>;
+
+impl ProvingCapacityTracker {
+    pub(crate) fn new(db: DbObj, prover: ProverObj) -> Self {
+        Self {
+            db,
+            prover,
+            throughput_samples: Mutex::new(VecDeque::with_capacity(THROUGHPUT_SAMPLES)),
+            busy_intervals: Mutex::new(VecDeque::new()),
+            active_proofs: Default::default(),
+        }
+    }
+
+    /// Record the cycles and elapsed time of a proof completed by the prover backend.
+    pub(crate) fn record_proof(&self, result: &ProofResult) {
+        // Backends that do not report an elapsed time return NaN or 0
+        if result.stats.total_cycles == 0
+            || result.elapsed_time.is_nan()
+            || result.elapsed_time <= 0.0
+        {
+            return;
+        }
+        let now = now_timestamp();
+        let start = now.saturating_sub(result.elapsed_time.ceil() as u64);
+        self.record_busy(start, now);
+
+        let mut samples = self.throughput_samples.lock().unwrap();
+        if samples.len() == THROUGHPUT_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(ProofSample { start, end: now, cycles: result.stats.total_cycles });
+    }
+
+    /// Record that the prover was busy proving between the given timestamps.
+    fn record_busy(&self, start: u64, end: u64) {
+        let mut busy_intervals = self.busy_intervals.lock().unwrap();
+        while busy_intervals.front().is_some_and(|(_, end)| *end + BUSY_HISTORY_SECS < start) {
+            busy_intervals.pop_front();
+        }
+        busy_intervals.push_back((start, end));
+    }
+
+    /// Proofs queued or running on the backend, listed at most every [ACTIVE_PROOFS_REFRESH],
+    /// or `None` if the backend cannot list them.
+    async fn active_proofs(&self) -> Option<HashSet<String>> {
+        let mut active_proofs = self.active_proofs.lock().await;
+        if let Some((listed_at, proofs)) = active_proofs.as_ref() {
+            if listed_at.elapsed() < ACTIVE_PROOFS_REFRESH {
+                return proofs.clone();
+            }
+        }
+        let proofs = match self.prover.active_proofs().await {
+            Ok(proofs) => proofs.map(HashSet::from_iter),
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to list the proofs of the prover backend, estimating the queue from the DB: {err}"
+                );
+                None
+            }
+        };
+        *active_proofs = Some((Instant::now(), proofs.clone()));
+        proofs
+    }
+
+    /// Seconds since `since` that the prover has spent proving, counting completed proofs and
+    /// orders that are being proven.
+    ///
+    /// Overlapping proofs are only counted once.
+    pub(crate) async fn busy_secs(&self, since: u64) -> Result<u64, DbError> {
+        let now = now_timestamp();
+        let mut intervals: Vec<(u64, u64)> = self
+            .db
+            .get_committed_orders()
+            .await?
+            .iter()
+            .filter(|order| order.status == OrderStatus::Proving)
+            .filter_map(|order| order.proving_started_at)
+            .map(|started_at| (started_at, now))
+            .collect();
+        intervals.extend(self.busy_intervals.lock().unwrap().iter().copied());
+        Ok(union_secs(intervals, since, now))
+    }
+
+    /// Throughput of recently completed proofs, in kHz: the cycles they proved over the time
+    /// the prover spent proving them, so that concurrent proofs add up.
+    pub(crate) fn observed_khz(&self) -> Option<u64> {
+        let samples = self.throughput_samples.lock().unwrap();
+        if samples.is_empty() {
+            return None;
+        }
+        let cycles: u64 = samples.iter().map(|sample| sample.cycles).sum();
+        let secs = union_secs(samples.iter().map(|sample| (sample.start, sample.end)), 0, u64::MAX);
+        Some((cycles / secs.max(1) / 1_000).max(1))
+    }
+
+    /// Average cycles of recently completed proofs.
+    fn average_cycles(&self) -> Option<u64> {
+        let samples = self.throughput_samples.lock().unwrap();
+        let cycles: u64 = samples.iter().map(|sample| sample.cycles).sum();
+        cycles.checked_div(samples.len() as u64)
+    }
+
+    /// Throughput to schedule with: the observed throughput if any proofs have completed,
+    /// otherwise the configured `peak_prove_khz`.
+    pub(crate) fn prove_khz(&self, peak_prove_khz: Option<u64>) -> Option<u64> {
+        self.observed_khz().or(peak_prove_khz)
+    }
+
+    /// Estimated seconds until the prover has worked through all orders that are queued or
+    /// being proven, at the given throughput.
+    ///
+    /// Progress on orders that are already proving is estimated from the time they started.
+    /// When the backend lists its proofs, orders whose proof is no longer active are not
+    /// counted, and active proofs of other clients are counted at the average cycles of the
+    /// completed proofs.
+    pub(crate) async fn queue_secs(
+        &self,
+        prove_khz: u64,
+        additional_proof_cycles: u64,
+    ) -> Result<u64, DbError> {
+        let now = now_timestamp();
+        let cycles_per_sec = prove_khz.saturating_mul(1_000);
+        let mut active_proofs = self.active_proofs().await;
+        let mut remaining_cycles: u64 = self
+            .db
+            .get_committed_orders()
+            .await?
+            .iter()
+            .filter(|order| match (order.status, &mut active_proofs) {
+                (OrderStatus::PendingProving, _) => true,
+                (OrderStatus::Proving, None) => true,
+                (OrderStatus::Proving, Some(active_proofs)) => {
+                    order.proof_id.as_ref().is_some_and(|proof_id| active_proofs.remove(proof_id))
+                }
+                _ => false,
+            })
+            .map(|order| {
+                let cycles = order.total_cycles.unwrap_or_default() + additional_proof_cycles;
+                let proven = match (order.status, order.proving_started_at) {
+                    (OrderStatus::Proving, Some(started_at)) => {
+                        now.saturating_sub(started_at).saturating_mul(cycles_per_sec)
+                    }
+                    _ => 0,
+                };
+                cycles.saturating_sub(proven)
+            })
+            .sum();
+
+        // Proofs left over are not from the orders of this broker
+        let foreign_proofs = active_proofs.map_or(0, |active_proofs| active_proofs.len() as u64);
+        if foreign_proofs > 0 {
+            let cycles = self.average_cycles().unwrap_or_default() + additional_proof_cycles;
+            remaining_cycles =
+                remaining_cycles.saturating_add(foreign_proofs.saturating_mul(cycles));
+        }
+
+        Ok(remaining_cycles.div_ceil(cycles_per_sec.max(1)))
+    }
+}
+
+/// Seconds between `since` and `until` covered by the intervals, counting overlaps once.
+fn union_secs(intervals: impl IntoIterator<Item = (u64, u64)>, since: u64, until: u64) -> u64 {
+    let mut intervals: Vec<(u64, u64)> = intervals.into_iter().collect();
+    intervals.sort_unstable();
+
+    let mut secs = 0;
+    let mut covered_until = since;
+    for (start, end) in intervals {
+        let start = start.max(covered_until);
+        let end = end.min(until);
+        if end > start {
+            secs += end - start;
+            covered_until = end;
+        }
+    }
+    secs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::SqliteDb,
+        provers::{DefaultProver, ExecutorResp, Prover, ProverError},
+        FulfillmentType, Order,
+    };
+    use alloy::primitives::{Address, Bytes, U256};
+    use async_trait::async_trait;
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput, RequestInputType,
+        Requirements,
+    };
+    use chrono::Utc;
+    use risc0_zkvm::{sha::Digest, Receipt};
+    use sqlx::SqlitePool;
+
+    // Mock prover that lists a fixed set of active proofs
+    struct ListedProofs {
+        active_proofs: Option<Vec<String>>,
+        default_prover: DefaultProver,
+    }
+
+    impl ListedProofs {
+        fn new(active_proofs: Option<&[&str]>) -> ProverObj {
+            Arc::new(Self {
+                active_proofs: active_proofs
+                    .map(|proofs| proofs.iter().map(|proof| proof.to_string()).collect()),
+                default_prover: DefaultProver::new(),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Prover for ListedProofs {
+        async fn upload_image(&self, image_id: &str, image: Vec<u8>) -> Result<(), ProverError> {
+            self.default_prover.upload_image(image_id, image).await
+        }
+
+        async fn upload_input(&self, input: Vec<u8>) -> Result<String, ProverError> {
+            self.default_prover.upload_input(input).await
+        }
+
+        async fn preflight(
+            &self,
+            image_id: &str,
+            input_id: &str,
+            assumptions: Vec<String>,
+            executor_limit: Option<u64>,
+            order_id: &str,
+        ) -> Result<ProofResult, ProverError> {
+            self.default_prover
+                .preflight(image_id, input_id, assumptions, executor_limit, order_id)
+                .await
+        }
+
+        async fn has_image(&self, image_id: &str) -> Result<bool, ProverError> {
+            self.default_prover.has_image(image_id).await
+        }
+
+        async fn prove_stark(
+            &self,
+            image_id: &str,
+            input_id: &str,
+            assumptions: Vec<String>,
+        ) -> Result<String, ProverError> {
+            self.default_prover.prove_stark(image_id, input_id, assumptions).await
+        }
+
+        async fn wait_for_stark(&self, proof_id: &str) -> Result<ProofResult, ProverError> {
+            self.default_prover.wait_for_stark(proof_id).await
+        }
+
+        async fn cancel_stark(&self, proof_id: &str) -> Result<(), ProverError> {
+            self.default_prover.cancel_stark(proof_id).await
+        }
+
+        async fn active_proofs(&self) -> Result<Option<Vec<String>>, ProverError> {
+            Ok(self.active_proofs.clone())
+        }
+
+        async fn get_receipt(&self, proof_id: &str) -> Result<Option<Receipt>, ProverError> {
+            self.default_prover.get_receipt(proof_id).await
+        }
+
+        async fn get_preflight_journal(
+            &self,
+            proof_id: &str,
+        ) -> Result<Option<Vec<u8>>, ProverError> {
+            self.default_prover.get_preflight_journal(proof_id).await
+        }
+
+        async fn get_journal(&self, proof_id: &str) -> Result<Option<Vec<u8>>, ProverError> {
+            self.default_prover.get_journal(proof_id).await
+        }
+
+        async fn compress(&self, proof_id: &str) -> Result<String, ProverError> {
+            self.default_prover.compress(proof_id).await
+        }
+
+        async fn get_compressed_receipt(
+            &self,
+            proof_id: &str,
+        ) -> Result<Option<Vec<u8>>, ProverError> {
+            self.default_prover.get_compressed_receipt(proof_id).await
+        }
+    }
+
+    fn proof_result(total_cycles: u64, elapsed_time: f64) -> ProofResult {
+        ProofResult {
+            id: "proof".into(),
+            stats: ExecutorResp { total_cycles, ..Default::default() },
+            elapsed_time,
+        }
+    }
+
+    fn order(idx: u32, status: OrderStatus, total_cycles: u64) -> Order {
+        Order {
+            boundless_market_address: Address::ZERO,
+            chain_id: 1,
+            fulfillment_type: FulfillmentType::LockAndFulfill,
+            request: ProofRequest::new(
+                RequestId::new(Address::ZERO, idx),
+                Requirements::new(
+                    Digest::ZERO,
+                    Predicate {
+                        predicateType: PredicateType::PrefixMatch,
+                        data: Default::default(),
+                    },
+                ),
+                "http://risczero.com/image",
+                RequestInput { inputType: RequestInputType::Inline, data: Default::default() },
+                Offer {
+                    minPrice: U256::from(1),
+                    maxPrice: U256::from(2),
+                    biddingStart: 0,
+                    timeout: 1000,
+                    lockTimeout: 1000,
+                    rampUpPeriod: 1,
+                    lockStake: U256::ZERO,
+                },
+            ),
+            status,
+            client_sig: Bytes::new(),
+            updated_at: Utc::now(),
+            image_id: None,
+            input_id: None,
+            assumption_ids: None,
+            total_cycles: Some(total_cycles),
+            target_timestamp: None,
+            expire_timestamp: None,
+            proving_started_at: None,
+            proof_id: None,
+            compressed_proof_id: None,
+            lock_price: None,
+            error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn prove_khz_prefers_observed_throughput(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let tracker = ProvingCapacityTracker::new(db, ListedProofs::new(None));
+        assert_eq!(tracker.prove_khz(Some(100)), Some(100));
+
+        tracker.record_proof(&proof_result(1_000_000, f64::NAN));
+        tracker.record_proof(&proof_result(1_000_000, 0.0));
+        assert_eq!(tracker.prove_khz(Some(100)), Some(100));
+
+        tracker.record_proof(&proof_result(2_000_000, 1.0));
+        tracker.record_proof(&proof_result(4_000_000, 1.0));
+        // Both proofs ran concurrently over the same second
+        assert_eq!(tracker.prove_khz(Some(100)), Some(6_000));
+    }
+
+    #[sqlx::test]
+    async fn queue_secs_includes_committed_orders(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let tracker = ProvingCapacityTracker::new(db.clone(), ListedProofs::new(None));
+        assert_eq!(tracker.queue_secs(1, 0).await.unwrap(), 0);
+
+        db.add_order(&order(1, OrderStatus::PendingProving, 10_000)).await.unwrap();
+        let mut proving = order(2, OrderStatus::Proving, 10_000);
+        proving.proving_started_at = Some(now_timestamp() - 5);
+        db.add_order(&proving).await.unwrap();
+        db.add_order(&order(3, OrderStatus::PendingAgg, 10_000)).await.unwrap();
+
+        // 10s for the pending order and ~5s left on the order being proven
+        let queue_secs = tracker.queue_secs(1, 0).await.unwrap();
+        assert!((15..=16).contains(&queue_secs), "queue_secs: {queue_secs}");
+        assert_eq!(tracker.queue_secs(1, 1_000).await.unwrap(), queue_secs + 2);
+    }
+
+    #[sqlx::test]
+    async fn queue_secs_follows_backend_proofs(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let tracker =
+            ProvingCapacityTracker::new(db.clone(), ListedProofs::new(Some(&["running", "other"])));
+        tracker.record_proof(&proof_result(4_000, 1.0));
+
+        let mut running = order(1, OrderStatus::Proving, 10_000);
+        running.proof_id = Some("running".into());
+        db.add_order(&running).await.unwrap();
+        let mut completed = order(2, OrderStatus::Proving, 10_000);
+        completed.proof_id = Some("completed".into());
+        db.add_order(&completed).await.unwrap();
+        db.add_order(&order(3, OrderStatus::PendingProving, 10_000)).await.unwrap();
+
+        // The completed proof is not counted, the proof of another client of the backend is
+        // counted at the average cycles of completed proofs
+        assert_eq!(tracker.queue_secs(1, 0).await.unwrap(), 24);
+
+        // DefaultProver only lists the proofs it is running
+        let tracker = ProvingCapacityTracker::new(db, Arc::new(DefaultProver::new()));
+        assert_eq!(tracker.queue_secs(1, 0).await.unwrap(), 10);
+    }
+
+    #[sqlx::test]
+    async fn busy_secs_merges_overlapping_proofs(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let tracker = ProvingCapacityTracker::new(db.clone(), ListedProofs::new(None));
+        let now = now_timestamp();
+        assert_eq!(tracker.busy_secs(now - 100).await.unwrap(), 0);
+
+        tracker.record_busy(now - 200, now - 90);
+        tracker.record_busy(now - 95, now - 80);
+        tracker.record_busy(now - 50, now - 40);
+        // Only the last 10s of the first proof are in the window, and the second overlaps it by 5s
+        assert_eq!(tracker.busy_secs(now - 100).await.unwrap(), 30);
+
+        let mut proving = order(1, OrderStatus::Proving, 10_000);
+        proving.proving_started_at = Some(now - 45);
+        db.add_order(&proving).await.unwrap();
+        let busy_secs = tracker.busy_secs(now - 100).await.unwrap();
+        assert!((70..=71).contains(&busy_secs), "busy_secs: {busy_secs}");
+    }
+}
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::time::Duration;
+use std::{collections::HashSet, time::Duration};
 
 use async_trait::async_trait;
 use thiserror::Error;
@@ -23,9 +23,16 @@ use crate::{
     config::{ConfigErr, ConfigLock},
     db::{DbError, DbObj},
     errors::CodedError,
+    input_dedup::InputDedupObj,
+    metrics::MetricsObj,
+    now_timestamp,
+    order_monitor::PricedOrders,
+    order_picker::OrderCache,
     provers::ProverObj,
+    self_throttle::{PipelineOutcome, SelfThrottleObj},
     task::{RetryRes, RetryTask, SupervisorErr},
     utils::cancel_proof_and_fail_order,
+    FulfillmentType,
 };
 
 #[derive(Error, Debug)]
@@ -55,11 +62,48 @@ pub struct ReaperTask {
     db: DbObj,
     config: ConfigLock,
     prover: ProverObj,
+    metrics: MetricsObj,
+    input_dedup: InputDedupObj,
+    live_orders: Option<(PricedOrders, OrderCache)>,
+    self_throttle: SelfThrottleObj,
 }
 
 impl ReaperTask {
-    pub fn new(db: DbObj, config: ConfigLock, prover: ProverObj) -> Self {
-        Self { db, config, prover }
+    pub fn new(
+        db: DbObj,
+        config: ConfigLock,
+        prover: ProverObj,
+        input_dedup: InputDedupObj,
+    ) -> Self {
+        Self {
+            db,
+            config,
+            prover,
+            metrics: Default::default(),
+            input_dedup,
+            live_orders: None,
+            self_throttle: Default::default(),
+        }
+    }
+
+    /// Record forfeited stake from expired locked orders in the given metrics.
+    pub(crate) fn with_metrics(self, metrics: MetricsObj) -> Self {
+        Self { metrics, ..self }
+    }
+
+    /// Keep the inputs of orders queued for pricing or waiting in the order monitor, whatever
+    /// their status in the DB, e.g. orders that were skipped and queued again.
+    pub(crate) fn with_live_orders(
+        self,
+        priced_orders: PricedOrders,
+        order_cache: OrderCache,
+    ) -> Self {
+        Self { live_orders: Some((priced_orders, order_cache)), ..self }
+    }
+
+    /// Record missed deadlines in the given self-throttle.
+    pub(crate) fn with_self_throttle(self, self_throttle: SelfThrottleObj) -> Self {
+        Self { self_throttle, ..self }
     }
 
     async fn check_expired_orders(&self) -> Result<(), ReaperError> {
@@ -87,6 +131,10 @@ impl ReaperTask {
                 match self.db.set_order_failure(&order_id, "Order expired").await {
                     Ok(()) => {
                         warn!("Order {} has expired, marked as failed", order_id);
+                        self.self_throttle.record(PipelineOutcome::DeadlineMissed, now_timestamp());
+                        if order.fulfillment_type == FulfillmentType::LockAndFulfill {
+                            self.metrics.record_slash();
+                        }
                     }
                     Err(err) => {
                         error!("Failed to update status for expired order {}: {}", order_id, err);
@@ -99,6 +147,19 @@ impl ReaperTask {
         Ok(())
     }
 
+    /// IDs of the orders queued for pricing or waiting in the order monitor.
+    fn live_orders(&self) -> HashSet<String> {
+        let Some((priced_orders, order_cache)) = &self.live_orders else {
+            return HashSet::new();
+        };
+        priced_orders
+            .list()
+            .iter()
+            .map(|order| order.id())
+            .chain(order_cache.iter().map(|(order_id, _)| order_id.to_string()))
+            .collect()
+    }
+
     async fn run_reaper_loop(&self, cancel_token: CancellationToken) -> Result<(), ReaperError> {
         let interval = {
             let config = self.config.lock_all()?;
@@ -118,6 +179,12 @@ impl ReaperTask {
             if let Err(err) = self.check_expired_orders().await {
                 warn!("Error checking expired orders: {}", err);
             }
+
+            match self.input_dedup.reclaim(&self.db, &self.live_orders()).await {
+                Ok(0) => {}
+                Ok(released) => debug!("Released inputs of {released} closed orders"),
+                Err(err) => warn!("Error reclaiming inputs of closed orders: {}", err),
+            }
         }
     }
 }
@@ -139,7 +206,8 @@ impl RetryTask for ReaperTask {
 mod tests {
     use super::*;
     use crate::{
-        db::SqliteDb, now_timestamp, provers::DefaultProver, FulfillmentType, Order, OrderStatus,
+        db::SqliteDb, input_dedup::InputDedup, now_timestamp, provers::DefaultProver,
+        FulfillmentType, Order, OrderStatus,
     };
     use alloy::primitives::{Address, Bytes, U256};
     use boundless_market::contracts::{
@@ -183,6 +251,7 @@ mod tests {
             ),
             image_id: None,
             input_id: None,
+            assumption_ids: None,
             proof_id: None,
             compressed_proof_id: None,
             expire_timestamp,
@@ -190,6 +259,8 @@ mod tests {
             lock_price: Some(U256::from(1)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: Address::ZERO,
             chain_id: 1,
             total_cycles: None,
@@ -203,7 +274,8 @@ mod tests {
         let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
         let config = ConfigLock::default();
         let prover: ProverObj = Arc::new(DefaultProver::new());
-        let reaper = ReaperTask::new(db.clone(), config, prover);
+        let reaper =
+            ReaperTask::new(db.clone(), config, prover.clone(), Arc::new(InputDedup::new(prover)));
 
         let current_time = now_timestamp();
         let future_time = current_time + 100;
@@ -239,7 +311,8 @@ mod tests {
         let config = ConfigLock::default();
         config.load_write().unwrap().prover.reaper_grace_period_secs = 30;
         let prover: ProverObj = Arc::new(DefaultProver::new());
-        let reaper = ReaperTask::new(db.clone(), config, prover);
+        let reaper =
+            ReaperTask::new(db.clone(), config, prover.clone(), Arc::new(InputDedup::new(prover)));
 
         let current_time = now_timestamp();
         let past_time = current_time - 100;
@@ -290,7 +363,8 @@ mod tests {
         let config = ConfigLock::default();
         config.load_write().unwrap().prover.reaper_grace_period_secs = 30;
         let prover: ProverObj = Arc::new(DefaultProver::new());
-        let reaper = ReaperTask::new(db.clone(), config, prover);
+        let reaper =
+            ReaperTask::new(db.clone(), config, prover.clone(), Arc::new(InputDedup::new(prover)));
 
         let current_time = now_timestamp();
         let past_time = current_time - 100;
@@ -320,4 +394,24 @@ mod tests {
             assert_eq!(stored_order.error_msg, Some("Order expired".to_string()));
         }
     }
+
+    #[tokio::test]
+    async fn test_reclaim_keeps_inputs_of_live_orders() {
+        let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
+        let prover: ProverObj = Arc::new(DefaultProver::new());
+        let input_dedup = Arc::new(InputDedup::new(prover.clone()));
+
+        // A skipped order that was queued for pricing again
+        let order = create_order_with_status_and_expiration(1, OrderStatus::Skipped, None);
+        db.add_order(&order).await.unwrap();
+        let input_id = input_dedup.upload_input(vec![1, 2, 3], &order.id()).await.unwrap();
+
+        let live_orders = HashSet::from([order.id()]);
+        assert_eq!(input_dedup.reclaim(&db, &live_orders).await.unwrap(), 0);
+        assert!(input_dedup.retain(&input_id, &order.id()));
+
+        // Once no longer live, the skipped order is closed
+        assert_eq!(input_dedup.reclaim(&db, &HashSet::new()).await.unwrap(), 1);
+        assert!(prover.delete_input(&input_id).await.is_err());
+    }
 }
@@ -0,0 +1,191 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Historical statistics of requestors, and the reputation score derived from them.
+
+use std::fmt;
+
+use crate::config::ClientReputationConf;
+
+/// Event in the lifecycle of a requestor's order, recorded in the requestor's statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ClientEvent {
+    /// Preflight of the order completed with a journal of the given size
+    Preflight { journal_bytes: u64 },
+    /// The guest panicked during preflight
+    GuestPanic,
+    /// The order was locked
+    Locked,
+    /// Locking the order failed because the requestor had insufficient balance to pay for it
+    PaymentFailed,
+    /// The order was fulfilled
+    Fulfilled,
+    /// The order failed after the broker committed to it
+    Failed,
+}
+
+/// Counts of events recorded for a requestor.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ClientStats {
+    pub(crate) preflights: u64,
+    pub(crate) guest_panics: u64,
+    /// Total journal size of completed preflights
+    pub(crate) journal_bytes: u64,
+    pub(crate) locks: u64,
+    pub(crate) payment_failures: u64,
+    pub(crate) fulfilled: u64,
+    pub(crate) failed: u64,
+}
+
+impl From<ClientEvent> for ClientStats {
+    fn from(event: ClientEvent) -> Self {
+        match event {
+            ClientEvent::Preflight { journal_bytes } => {
+                Self { preflights: 1, journal_bytes, ..Default::default() }
+            }
+            ClientEvent::GuestPanic => Self { guest_panics: 1, ..Default::default() },
+            ClientEvent::Locked => Self { locks: 1, ..Default::default() },
+            ClientEvent::PaymentFailed => Self { payment_failures: 1, ..Default::default() },
+            ClientEvent::Fulfilled => Self { fulfilled: 1, ..Default::default() },
+            ClientEvent::Failed => Self { failed: 1, ..Default::default() },
+        }
+    }
+}
+
+/// Ratio of `num` to `num + other`, or 1 if both are 0.
+fn ratio(num: u64, other: u64) -> f64 {
+    match num + other {
+        0 => 1.0,
+        total => num as f64 / total as f64,
+    }
+}
+
+impl ClientStats {
+    /// Number of orders from the requestor that were preflighted.
+    pub(crate) fn orders(&self) -> u64 {
+        self.preflights + self.guest_panics
+    }
+
+    /// Share of committed orders that were fulfilled.
+    pub(crate) fn fulfillment_rate(&self) -> f64 {
+        ratio(self.fulfilled, self.failed)
+    }
+
+    /// Share of preflights in which the guest panicked.
+    pub(crate) fn guest_panic_rate(&self) -> f64 {
+        1.0 - ratio(self.preflights, self.guest_panics)
+    }
+
+    /// Share of locks that did not fail for lack of funds of the requestor.
+    pub(crate) fn payment_reliability(&self) -> f64 {
+        ratio(self.locks, self.payment_failures)
+    }
+
+    /// Average journal size of completed preflights.
+    pub(crate) fn average_journal_bytes(&self) -> u64 {
+        self.journal_bytes.checked_div(self.preflights).unwrap_or_default()
+    }
+}
+
+/// Reputation of a requestor, between 0 and 1.
+///
+/// The score is the product of the fulfillment rate, the share of preflights without a guest
+/// panic, the payment reliability and, if `max_average_journal_bytes` is set, the ratio of that
+/// limit to the average journal size when above it. Requestors with fewer than `min_orders`
+/// orders have a score of 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ClientReputation {
+    pub(crate) score: f64,
+}
+
+impl ClientReputation {
+    pub(crate) fn new(stats: &ClientStats, conf: &ClientReputationConf) -> Self {
+        if stats.orders() < conf.min_orders {
+            return Self { score: 1.0 };
+        }
+        let journal_factor = match conf.max_average_journal_bytes {
+            Some(max_bytes) if stats.average_journal_bytes() > max_bytes => {
+                max_bytes as f64 / stats.average_journal_bytes() as f64
+            }
+            _ => 1.0,
+        };
+        let score = stats.fulfillment_rate()
+            * (1.0 - stats.guest_panic_rate())
+            * stats.payment_reliability()
+            * journal_factor;
+        Self { score }
+    }
+
+    /// Whether orders from the requestor should be skipped.
+    pub(crate) fn is_below(&self, conf: &ClientReputationConf) -> bool {
+        self.score * 100.0 < conf.min_score_percent as f64
+    }
+
+    /// Scale an execution limit by the score.
+    pub(crate) fn scale_exec_limit(&self, exec_limit_cycles: u64) -> u64 {
+        if self.score >= 1.0 {
+            return exec_limit_cycles;
+        }
+        (exec_limit_cycles as f64 * self.score) as u64
+    }
+}
+
+impl fmt::Display for ClientReputation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.0}%", self.score * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conf() -> ClientReputationConf {
+        ClientReputationConf {
+            min_orders: 10,
+            min_score_percent: 50,
+            scale_exec_limits: true,
+            max_average_journal_bytes: Some(1_000),
+        }
+    }
+
+    #[test]
+    fn scores_client_stats() {
+        let conf = conf();
+        let mut stats = ClientStats { preflights: 5, guest_panics: 4, ..Default::default() };
+        // Not enough orders to score
+        assert_eq!(ClientReputation::new(&stats, &conf).score, 1.0);
+
+        stats.preflights = 6;
+        let reputation = ClientReputation::new(&stats, &conf);
+        assert_eq!(reputation.score, 0.6);
+        assert!(!reputation.is_below(&conf));
+        assert_eq!(reputation.scale_exec_limit(1_000), 600);
+
+        stats.fulfilled = 1;
+        stats.failed = 1;
+        let reputation = ClientReputation::new(&stats, &conf);
+        assert_eq!(reputation.score, 0.3);
+        assert!(reputation.is_below(&conf));
+
+        let stats = ClientStats {
+            preflights: 10,
+            journal_bytes: 40_000,
+            locks: 3,
+            payment_failures: 1,
+            ..Default::default()
+        };
+        assert_eq!(ClientReputation::new(&stats, &conf).score, 0.75 * 0.25);
+    }
+}
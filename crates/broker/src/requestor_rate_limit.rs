@@ -0,0 +1,125 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-requestor token buckets bounding the rate at which orders of each requestor are priced.
+
+use std::{collections::HashMap, time::Instant};
+
+use alloy::primitives::Address;
+
+use crate::{config::RequestorRateLimitConf, FulfillmentType, OrderRequest};
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token buckets of requestors, keyed by client address.
+///
+/// Orders of a requestor whose bucket is empty are deferred, staying queued for pricing until
+/// the bucket refills or the order nears its deadline.
+#[derive(Default)]
+pub(crate) struct RequestorRateLimiter {
+    buckets: HashMap<Address, Bucket>,
+}
+
+impl RequestorRateLimiter {
+    /// Whether the order may be priced now, taking a token from its requestor's bucket if so.
+    ///
+    /// Orders from `priority_addresses`, and orders within `expiry_margin_secs` of their
+    /// deadline, are always admitted without taking a token.
+    pub(crate) fn admit(
+        &mut self,
+        conf: &RequestorRateLimitConf,
+        priority_addresses: Option<&[Address]>,
+        order: &OrderRequest,
+        now: Instant,
+        now_secs: u64,
+    ) -> bool {
+        let client = order.request.client_address();
+        if priority_addresses.is_some_and(|addresses| addresses.contains(&client)) {
+            return true;
+        }
+        let deadline = match order.fulfillment_type {
+            FulfillmentType::LockAndFulfill => order.request.lock_expires_at(),
+            _ => order.request.expires_at(),
+        };
+        if deadline.saturating_sub(now_secs) <= conf.expiry_margin_secs {
+            return true;
+        }
+        self.try_acquire(conf, client, now)
+    }
+
+    /// Take a token from the bucket of the client, returning whether one was available.
+    fn try_acquire(
+        &mut self,
+        conf: &RequestorRateLimitConf,
+        client: Address,
+        now: Instant,
+    ) -> bool {
+        let burst = conf.burst.max(1) as f64;
+        let bucket =
+            self.buckets.entry(client).or_insert(Bucket { tokens: burst, refilled_at: now });
+        Self::refill(conf, bucket, now);
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Drop the buckets that have refilled, so that idle requestors are not kept around.
+    pub(crate) fn prune(&mut self, conf: &RequestorRateLimitConf, now: Instant) {
+        let burst = conf.burst.max(1) as f64;
+        self.buckets.retain(|_, bucket| {
+            Self::refill(conf, bucket, now);
+            bucket.tokens < burst
+        });
+    }
+
+    fn refill(conf: &RequestorRateLimitConf, bucket: &mut Bucket, now: Instant) {
+        let elapsed = now.saturating_duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * conf.orders_per_minute as f64 / 60.0)
+            .min(conf.burst.max(1) as f64);
+        bucket.refilled_at = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn refills_buckets_per_requestor() {
+        let conf =
+            RequestorRateLimitConf { orders_per_minute: 60, burst: 2, expiry_margin_secs: 0 };
+        let mut limiter = RequestorRateLimiter::default();
+        let (flooder, other) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire(&conf, flooder, now));
+        assert!(limiter.try_acquire(&conf, flooder, now));
+        assert!(!limiter.try_acquire(&conf, flooder, now));
+        assert!(limiter.try_acquire(&conf, other, now));
+
+        // One order per second
+        let later = now + Duration::from_secs(1);
+        assert!(limiter.try_acquire(&conf, flooder, later));
+        assert!(!limiter.try_acquire(&conf, flooder, later));
+
+        limiter.prune(&conf, now + Duration::from_secs(10));
+        assert!(limiter.buckets.is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reservations of stake and gas for orders handed from the order picker to the order monitor.
+//!
+//! The picker reserves the stake and gas of an order before handing it to the monitor, and
+//! deducts outstanding reservations from the balances available to the orders it prices next.
+//! The monitor confirms the reservation once the order is committed in the DB, from where it is
+//! accounted for as a committed order, or aborts it when the order is dropped. A reservation
+//! expires at the deadline of its order, so that one left behind by an order lost between the
+//! two, e.g. to a restart of the monitor, is released.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use alloy::primitives::U256;
+
+/// Stake and gas reserved for orders.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Reserved {
+    pub(crate) stake: U256,
+    /// Gas cost to lock and/or fulfill, in wei
+    pub(crate) gas: U256,
+}
+
+struct Reservation {
+    chain_id: u64,
+    reserved: Reserved,
+    expires_at: u64,
+}
+
+/// Outstanding reservations of priced orders not yet committed, keyed by order ID.
+#[derive(Default)]
+pub(crate) struct Reservations {
+    reservations: Mutex<HashMap<String, Reservation>>,
+}
+
+pub(crate) type ReservationsObj = Arc<Reservations>;
+
+impl Reservations {
+    /// Reserve stake and gas for an order on the given chain until `expires_at`, replacing any
+    /// previous reservation of the order.
+    pub(crate) fn reserve(
+        &self,
+        order_id: String,
+        chain_id: u64,
+        reserved: Reserved,
+        expires_at: u64,
+    ) {
+        tracing::trace!(
+            "Reserved stake {} and gas {} for order {order_id} until {expires_at}",
+            reserved.stake,
+            reserved.gas
+        );
+        self.reservations
+            .lock()
+            .unwrap()
+            .insert(order_id, Reservation { chain_id, reserved, expires_at });
+    }
+
+    /// Release the reservation of an order that was committed. Returns whether it was reserved.
+    pub(crate) fn confirm(&self, order_id: &str) -> bool {
+        let confirmed = self.reservations.lock().unwrap().remove(order_id).is_some();
+        if confirmed {
+            tracing::trace!("Confirmed reservation of order {order_id}");
+        }
+        confirmed
+    }
+
+    /// Release the reservation of an order that was dropped. Returns whether it was reserved.
+    pub(crate) fn abort(&self, order_id: &str) -> bool {
+        let aborted = self.reservations.lock().unwrap().remove(order_id).is_some();
+        if aborted {
+            tracing::debug!("Aborted reservation of order {order_id}");
+        }
+        aborted
+    }
+
+    /// Total stake and gas reserved on the chain, releasing expired reservations.
+    pub(crate) fn reserved(&self, chain_id: u64) -> Reserved {
+        let now = crate::now_timestamp();
+        let mut reservations = self.reservations.lock().unwrap();
+        reservations.retain(|order_id, reservation| {
+            let expired = reservation.expires_at < now;
+            if expired {
+                tracing::debug!("Reservation of order {order_id} expired, releasing it");
+            }
+            !expired
+        });
+        reservations.values().filter(|reservation| reservation.chain_id == chain_id).fold(
+            Reserved::default(),
+            |total, reservation| Reserved {
+                stake: total.stake.saturating_add(reservation.reserved.stake),
+                gas: total.gas.saturating_add(reservation.reserved.gas),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn releases_confirmed_aborted_and_expired_reservations() {
+        let reservations = Reservations::default();
+        let reserved = Reserved { stake: U256::from(10), gas: U256::from(3) };
+        reservations.reserve("a".into(), 1, reserved, u64::MAX);
+        reservations.reserve("b".into(), 1, reserved, u64::MAX);
+        reservations.reserve("c".into(), 2, reserved, u64::MAX);
+        reservations.reserve("d".into(), 1, reserved, 0);
+
+        assert_eq!(
+            reservations.reserved(1),
+            Reserved { stake: U256::from(20), gas: U256::from(6) }
+        );
+        assert_eq!(reservations.reserved(2), reserved);
+
+        assert!(reservations.confirm("a"));
+        assert!(reservations.abort("b"));
+        assert!(!reservations.abort("b"));
+        assert!(!reservations.confirm("d"));
+        assert_eq!(reservations.reserved(1), Reserved::default());
+    }
+}
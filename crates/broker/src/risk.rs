@@ -0,0 +1,164 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Composite risk score of locking an order, gated on by `max_risk_score`.
+
+use std::fmt;
+
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::reputation::ClientStats;
+
+/// Precision of the stake share, in parts per [STAKE_SHARE_PRECISION].
+const STAKE_SHARE_PRECISION: u64 = 1_000_000;
+
+/// What is known about an order when its risk is scored, after preflight.
+pub(crate) struct RiskInputs<'a> {
+    /// Seconds left until the lock expires
+    pub(crate) seconds_left: u64,
+    /// Seconds to prove the order once the prover gets to it, if the throughput is known
+    pub(crate) prove_secs: Option<u64>,
+    /// Seconds until the prover has worked through the orders already queued, if the
+    /// throughput is known
+    pub(crate) queue_secs: Option<u64>,
+    pub(crate) lock_stake: U256,
+    pub(crate) max_stake: U256,
+    pub(crate) client_stats: &'a ClientStats,
+}
+
+/// Risk of locking an order, with the components it was scored from.
+///
+/// The components are between 0 and 1. The chance of failing to fulfill the order before the
+/// lock expires combines the share of the time left needed to work through the proving queue
+/// and prove the order, and the share of the requestor's committed orders that failed. The
+/// slash exposure weighs the stake at risk, as a share of `max_stake`, by that chance. The
+/// score, between 0 and 100, combines the slash exposure and the capacity risk as independent
+/// risks: `100 * (1 - (1 - slash_exposure) * (1 - capacity))`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RiskScore {
+    /// Share of the time left until the lock expires needed to prove the order
+    pub(crate) deadline: f64,
+    /// Share of the time left until the lock expires taken by the proving queue
+    pub(crate) capacity: f64,
+    /// Share of the requestor's committed orders that failed
+    pub(crate) requestor: f64,
+    /// Lock stake as a share of `max_stake`
+    pub(crate) stake: f64,
+    /// Estimated chance of failing to fulfill the order before the lock expires
+    pub(crate) failure_chance: f64,
+    /// Stake share weighted by the chance of failing to fulfill the order
+    pub(crate) slash_exposure: f64,
+    pub(crate) score: f64,
+}
+
+/// Ratio of `num` to `denom`, capped at 1.
+fn share(num: u64, denom: u64) -> f64 {
+    match denom {
+        0 => 1.0,
+        denom => (num as f64 / denom as f64).min(1.0),
+    }
+}
+
+impl RiskScore {
+    pub(crate) fn new(inputs: &RiskInputs) -> Self {
+        let deadline = share(inputs.prove_secs.unwrap_or_default(), inputs.seconds_left);
+        let capacity = share(inputs.queue_secs.unwrap_or_default(), inputs.seconds_left);
+        let requestor = 1.0 - inputs.client_stats.fulfillment_rate();
+        let stake = if inputs.max_stake.is_zero() {
+            if inputs.lock_stake.is_zero() {
+                0.0
+            } else {
+                1.0
+            }
+        } else {
+            let parts = (inputs.lock_stake.saturating_mul(U256::from(STAKE_SHARE_PRECISION))
+                / inputs.max_stake)
+                .min(U256::from(STAKE_SHARE_PRECISION));
+            parts.to::<u64>() as f64 / STAKE_SHARE_PRECISION as f64
+        };
+
+        let time_pressure = (deadline + capacity).min(1.0);
+        let failure_chance = 1.0 - (1.0 - time_pressure) * (1.0 - requestor);
+        let slash_exposure = failure_chance * stake;
+        let score = 100.0 * (1.0 - (1.0 - slash_exposure) * (1.0 - capacity));
+        Self { deadline, capacity, requestor, stake, failure_chance, slash_exposure, score }
+    }
+
+    /// Whether the order is too risky to lock.
+    pub(crate) fn exceeds(&self, max_score: u64) -> bool {
+        self.score > max_score as f64
+    }
+}
+
+impl fmt::Display for RiskScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.1} (deadline {:.2}, capacity {:.2}, requestor {:.2}, stake {:.2})",
+            self.score, self.deadline, self.capacity, self.requestor, self.stake
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_risk_components() {
+        let stats = ClientStats::default();
+        let inputs = RiskInputs {
+            seconds_left: 1_000,
+            prove_secs: Some(200),
+            queue_secs: Some(300),
+            lock_stake: U256::from(50),
+            max_stake: U256::from(100),
+            client_stats: &stats,
+        };
+        let risk = RiskScore::new(&inputs);
+        assert_eq!(risk.deadline, 0.2);
+        assert_eq!(risk.capacity, 0.3);
+        assert_eq!(risk.requestor, 0.0);
+        assert_eq!(risk.stake, 0.5);
+        assert_eq!(risk.failure_chance, 0.5);
+        assert_eq!(risk.slash_exposure, 0.25);
+        assert!((risk.score - 47.5).abs() < 1e-9);
+        assert!(risk.exceeds(40));
+        assert!(!risk.exceeds(50));
+
+        // Failures of the requestor's orders add to the chance of failing
+        let stats = ClientStats { fulfilled: 1, failed: 1, ..Default::default() };
+        let risk = RiskScore::new(&RiskInputs { client_stats: &stats, ..inputs });
+        assert_eq!(risk.requestor, 0.5);
+        assert_eq!(risk.failure_chance, 0.75);
+
+        // Without a known throughput only the requestor history puts the stake at risk
+        let risk = RiskScore::new(&RiskInputs {
+            prove_secs: None,
+            queue_secs: None,
+            client_stats: &stats,
+            ..inputs
+        });
+        assert_eq!(risk.capacity, 0.0);
+        assert_eq!(risk.slash_exposure, 0.25);
+        assert_eq!(risk.score, 25.0);
+
+        // Orders that can not be proven in time are as risky as their stake
+        let risk =
+            RiskScore::new(&RiskInputs { prove_secs: Some(2_000), queue_secs: Some(0), ..inputs });
+        assert_eq!(risk.failure_chance, 1.0);
+        assert_eq!(risk.score, 50.0);
+    }
+}
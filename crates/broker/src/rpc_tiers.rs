@@ -0,0 +1,105 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::task::{Context, Poll};
+
+use alloy::{
+    rpc::json_rpc::{RequestPacket, ResponsePacket},
+    transports::{
+        http::{Client, Http},
+        TransportError, TransportFut,
+    },
+};
+use tower::Service;
+use url::Url;
+
+/// Methods sent to the transaction endpoint.
+///
+/// Besides sending transactions, these are the calls that track them until they are included,
+/// which must see the transactions just sent and are on the latency critical path of locking and
+/// fulfilling orders.
+const TX_METHODS: &[&str] = &[
+    "eth_sendRawTransaction",
+    "eth_sendTransaction",
+    "eth_getTransactionCount",
+    "eth_getTransactionReceipt",
+    "eth_getTransactionByHash",
+];
+
+fn is_tx_method(method: &str) -> bool {
+    TX_METHODS.contains(&method)
+}
+
+/// Transport routing RPC calls by type between a transaction endpoint and a read endpoint.
+///
+/// Transactions and the calls tracking them go to the transaction endpoint, all other calls,
+/// such as balance queries, event backfills and simulations, go to the read endpoint, e.g. a read
+/// replica. Batches containing any transaction call go to the transaction endpoint.
+///
+/// As the routing happens at the transport, every user of the provider built on it, including the
+/// order picker, chain monitor and transaction submitter, is routed the same way.
+#[derive(Clone, Debug)]
+pub struct TieredTransport {
+    tx: Http<Client>,
+    read: Http<Client>,
+}
+
+impl TieredTransport {
+    pub fn new(tx_rpc_url: Url, read_rpc_url: Url) -> Self {
+        Self { tx: Http::new(tx_rpc_url), read: Http::new(read_rpc_url) }
+    }
+
+    fn is_tx_request(req: &RequestPacket) -> bool {
+        match req {
+            RequestPacket::Single(req) => is_tx_method(req.method()),
+            RequestPacket::Batch(reqs) => reqs.iter().any(|req| is_tx_method(req.method())),
+        }
+    }
+}
+
+impl Service<RequestPacket> for TieredTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // HTTP transports are always ready
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: RequestPacket) -> Self::Future {
+        if Self::is_tx_request(&req) {
+            tracing::trace!("Routing RPC request to the transaction endpoint");
+            self.tx.call(req)
+        } else {
+            self.read.call(req)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_by_method() {
+        assert!(is_tx_method("eth_sendRawTransaction"));
+        assert!(is_tx_method("eth_getTransactionReceipt"));
+        assert!(is_tx_method("eth_getTransactionCount"));
+        assert!(!is_tx_method("eth_getBalance"));
+        assert!(!is_tx_method("eth_getLogs"));
+        assert!(!is_tx_method("eth_call"));
+        assert!(!is_tx_method("eth_estimateGas"));
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deadline-aware scheduling of committed proving work.
+//!
+//! Committed orders waiting to be proven are started most urgent first. The urgency of an order is
+//! its slack: the time left until its deadline, less the time needed to prove its remaining cycles
+//! at `peak_prove_khz`. Without a configured throughput, orders are started earliest deadline
+//! first.
+//!
+//! When `max_active_proofs` are running and the most urgent waiting order would miss its deadline
+//! by waiting for a running proof to complete, the running proof with the most slack is suspended
+//! and requeued to start it, provided the prover backend supports checkpointing and the suspended
+//! proof can still complete in time.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::Order;
+
+/// Cycle count and deadline of an order, as estimated when it was priced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Estimate {
+    total_cycles: u64,
+    deadline: u64,
+}
+
+struct RunningProof {
+    proof_id: String,
+    estimate: Estimate,
+    /// Cycles proven before the proof was last suspended
+    proven_cycles: u64,
+    started_at: u64,
+    cancel: CancellationToken,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    /// Estimates of priced orders, reported by the order picker
+    estimates: HashMap<String, Estimate>,
+    running: HashMap<String, RunningProof>,
+    /// Cycles proven by suspended proofs, keyed by order ID
+    suspended: HashMap<String, u64>,
+}
+
+/// Orders to start proving, most urgent first, and running proofs to suspend to make room.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct SchedulePlan {
+    pub(crate) start: Vec<String>,
+    pub(crate) suspend: Vec<String>,
+}
+
+/// Tracks the cycles and deadlines of committed orders, and the proofs running on the prover.
+#[derive(Default)]
+pub(crate) struct ProvingScheduler {
+    state: Mutex<SchedulerState>,
+}
+
+pub(crate) type ProvingSchedulerObj = Arc<ProvingScheduler>;
+
+/// Seconds needed to prove the given cycles, if the throughput is known.
+fn proving_secs(cycles: u64, peak_prove_khz: Option<u64>) -> u64 {
+    match peak_prove_khz {
+        Some(khz) if khz > 0 => cycles.div_ceil(khz * 1_000),
+        _ => 0,
+    }
+}
+
+impl RunningProof {
+    fn remaining_cycles(&self, peak_prove_khz: Option<u64>, now: u64) -> u64 {
+        let proven = match peak_prove_khz {
+            Some(khz) => now.saturating_sub(self.started_at).saturating_mul(khz * 1_000),
+            None => 0,
+        };
+        self.estimate.total_cycles.saturating_sub(self.proven_cycles.saturating_add(proven))
+    }
+
+    /// Seconds that can be spent before the proof must resume to meet its deadline.
+    fn slack(&self, peak_prove_khz: Option<u64>, now: u64) -> i64 {
+        let remaining = proving_secs(self.remaining_cycles(peak_prove_khz, now), peak_prove_khz);
+        self.estimate.deadline as i64 - now as i64 - remaining as i64
+    }
+}
+
+impl SchedulerState {
+    fn estimate(&self, order: &Order) -> Estimate {
+        let estimate = self.estimates.get(&order.id()).copied();
+        Estimate {
+            total_cycles: estimate
+                .map(|e| e.total_cycles)
+                .or(order.total_cycles)
+                .unwrap_or_default(),
+            deadline: estimate
+                .map(|e| e.deadline)
+                .or(order.expire_timestamp)
+                .unwrap_or_else(|| order.request.expires_at()),
+        }
+    }
+
+    /// Slack of a waiting order, and the seconds needed to prove its remaining cycles.
+    fn waiting_slack(&self, order: &Order, peak_prove_khz: Option<u64>, now: u64) -> (i64, u64) {
+        let estimate = self.estimate(order);
+        let proven = self.suspended.get(&order.id()).copied().unwrap_or_default();
+        let remaining = proving_secs(estimate.total_cycles.saturating_sub(proven), peak_prove_khz);
+        (estimate.deadline as i64 - now as i64 - remaining as i64, remaining)
+    }
+}
+
+impl ProvingScheduler {
+    /// Record the cycle count and deadline of a priced order.
+    pub(crate) fn record_estimate(&self, order_id: String, total_cycles: u64, deadline: u64) {
+        let mut state = self.state.lock().unwrap();
+        let now = crate::now_timestamp();
+        state.estimates.retain(|_, estimate| estimate.deadline >= now);
+        state.estimates.insert(order_id, Estimate { total_cycles, deadline });
+    }
+
+    /// Plan which of the orders waiting to be proven to start, and which running proofs to
+    /// suspend for them.
+    pub(crate) fn plan(
+        &self,
+        waiting: &[Order],
+        max_active_proofs: Option<u32>,
+        peak_prove_khz: Option<u64>,
+        can_suspend: bool,
+        now: u64,
+    ) -> SchedulePlan {
+        let state = self.state.lock().unwrap();
+        let mut waiting: Vec<_> = waiting
+            .iter()
+            .map(|order| (order.id(), state.waiting_slack(order, peak_prove_khz, now)))
+            .collect();
+        waiting.sort_by_key(|(_, (slack, _))| *slack);
+
+        let free = match max_active_proofs {
+            Some(max) => (max as usize).saturating_sub(state.running.len()),
+            None => waiting.len(),
+        };
+        let mut plan = SchedulePlan::default();
+        let mut waiting = waiting.into_iter();
+        plan.start.extend(waiting.by_ref().take(free).map(|(order_id, _)| order_id));
+        if !can_suspend || peak_prove_khz.is_none() {
+            return plan;
+        }
+
+        // Running proofs by decreasing slack, as candidates for suspension
+        let mut running: Vec<_> = state
+            .running
+            .iter()
+            .map(|(order_id, proof)| (order_id, proof.slack(peak_prove_khz, now)))
+            .collect();
+        running.sort_by_key(|(_, slack)| std::cmp::Reverse(*slack));
+        let next_completion = state
+            .running
+            .values()
+            .map(|proof| proving_secs(proof.remaining_cycles(peak_prove_khz, now), peak_prove_khz))
+            .min()
+            .unwrap_or_default() as i64;
+
+        let mut running = running.into_iter().peekable();
+        for (order_id, (slack, remaining)) in waiting {
+            // Only preempt for orders that miss their deadline by waiting, but not if started now
+            if slack >= next_completion || slack < 0 {
+                continue;
+            }
+            let Some((_, victim_slack)) = running.peek() else {
+                break;
+            };
+            // The suspended proof is delayed by the time taken to prove the started order
+            if *victim_slack <= slack || *victim_slack < remaining as i64 {
+                break;
+            }
+            let (victim_id, _) = running.next().unwrap();
+            tracing::info!(
+                "Suspending proof of order {victim_id} to prove order {order_id}, which would otherwise miss its deadline"
+            );
+            plan.suspend.push(victim_id.clone());
+            plan.start.push(order_id);
+        }
+        plan
+    }
+
+    /// Record that the order is being proven, returning a token cancelled if it is suspended.
+    pub(crate) fn started(&self, order: &Order, proof_id: &str, now: u64) -> CancellationToken {
+        let mut state = self.state.lock().unwrap();
+        let order_id = order.id();
+        let proof = RunningProof {
+            proof_id: proof_id.to_string(),
+            estimate: state.estimate(order),
+            proven_cycles: state.suspended.remove(&order_id).unwrap_or_default(),
+            started_at: now,
+            cancel: CancellationToken::new(),
+        };
+        let cancel = proof.cancel.clone();
+        state.running.insert(order_id, proof);
+        cancel
+    }
+
+    /// Proof ID of the running proof of the order.
+    pub(crate) fn running_proof_id(&self, order_id: &str) -> Option<String> {
+        self.state.lock().unwrap().running.get(order_id).map(|proof| proof.proof_id.clone())
+    }
+
+    /// Record that the proof of the order was suspended, cancelling its token.
+    pub(crate) fn suspended(&self, order_id: &str, peak_prove_khz: Option<u64>, now: u64) {
+        let mut state = self.state.lock().unwrap();
+        let Some(proof) = state.running.remove(order_id) else {
+            return;
+        };
+        proof.cancel.cancel();
+        let remaining = proof.remaining_cycles(peak_prove_khz, now);
+        let proven = proof.estimate.total_cycles.saturating_sub(remaining);
+        state.suspended.insert(order_id.to_string(), proven);
+    }
+
+    /// Record that the order is no longer being proven, as its proof completed or failed.
+    pub(crate) fn finished(&self, order_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.running.remove(order_id);
+        state.suspended.remove(order_id);
+        state.estimates.remove(order_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FulfillmentType, OrderRequest};
+    use alloy::primitives::{Address, Bytes, U256};
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput, RequestInputType,
+        Requirements,
+    };
+    use risc0_zkvm::sha::Digest;
+
+    fn order(idx: u32, total_cycles: u64, deadline: u64) -> Order {
+        let request = ProofRequest::new(
+            RequestId::new(Address::repeat_byte(1), idx),
+            Requirements::new(
+                Digest::ZERO,
+                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+            ),
+            "http://risczero.com",
+            RequestInput { inputType: RequestInputType::Inline, data: "".into() },
+            Offer {
+                minPrice: U256::ZERO,
+                maxPrice: U256::from(1),
+                biddingStart: 0,
+                timeout: 10_000,
+                lockTimeout: 10_000,
+                rampUpPeriod: 1,
+                lockStake: U256::ZERO,
+            },
+        );
+        let mut order = OrderRequest::new(
+            request,
+            Bytes::new(),
+            FulfillmentType::LockAndFulfill,
+            Address::ZERO,
+            1,
+        )
+        .to_proving_order(U256::ZERO);
+        order.total_cycles = Some(total_cycles);
+        order.expire_timestamp = Some(deadline);
+        order
+    }
+
+    #[test]
+    fn starts_urgent_orders_and_suspends_for_them() {
+        let scheduler = ProvingScheduler::default();
+        // At 1 kHz, 1000 cycles take a second
+        let khz = Some(1);
+        let relaxed = order(1, 100_000, 1_000);
+        let tight = order(2, 100_000, 300);
+        let plan = scheduler.plan(&[relaxed.clone(), tight.clone()], Some(1), khz, true, 0);
+        assert_eq!(plan, SchedulePlan { start: vec![tight.id()], suspend: vec![] });
+
+        // The picker's estimate takes precedence over the cycles stored with the order
+        scheduler.record_estimate(relaxed.id(), 10_000, 1_000);
+        scheduler.started(&relaxed, "proof-1", 0);
+        assert_eq!(scheduler.running_proof_id(&relaxed.id()).as_deref(), Some("proof-1"));
+
+        // Without checkpointing, the urgent order waits for the running proof
+        let plan = scheduler.plan(&[tight.clone()], Some(1), khz, false, 0);
+        assert_eq!(plan, SchedulePlan::default());
+
+        // Completing the running proof first leaves enough time, so nothing is suspended
+        let plan = scheduler.plan(&[tight.clone()], Some(1), khz, true, 0);
+        assert_eq!(plan, SchedulePlan::default());
+
+        // A longer running proof is suspended for the urgent order
+        let long = order(3, 500_000, 2_000);
+        scheduler.finished(&relaxed.id());
+        let cancel = scheduler.started(&long, "proof-3", 0);
+        let plan = scheduler.plan(&[tight.clone()], Some(1), khz, true, 100);
+        assert_eq!(plan, SchedulePlan { start: vec![tight.id()], suspend: vec![long.id()] });
+
+        scheduler.suspended(&long.id(), khz, 100);
+        assert!(cancel.is_cancelled());
+        assert_eq!(scheduler.running_proof_id(&long.id()), None);
+
+        // Resumes with the cycles proven so far
+        scheduler.started(&long, "proof-3", 200);
+        let state = scheduler.state.lock().unwrap();
+        assert_eq!(state.running[&long.id()].proven_cycles, 100_000);
+        assert_eq!(state.running[&long.id()].remaining_cycles(khz, 300), 300_000);
+    }
+}
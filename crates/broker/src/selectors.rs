@@ -0,0 +1,147 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Registry of the selectors orders can require, with the gas and latency of fulfilling each.
+//!
+//! Pricing differentiates orders by the proof they require: Groth16 proofs are costly to verify
+//! on-chain and are wrapped after proving, while inclusion proofs of the batch are cheap.
+
+use alloy::primitives::FixedBytes;
+use boundless_market::selector::{ProofType, SupportedSelectors};
+use clap::ValueEnum;
+
+use crate::{config::MarketConf, groth16_wrapper::Groth16WrapperObj};
+
+/// Default time to wrap a proof in Groth16 after proving, until the wrapper has observed any.
+const GROTH16_POST_PROCESSING_SECS: u64 = 60;
+
+/// Cost of fulfilling orders requiring a selector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SelectorProfile {
+    pub(crate) proof_type: ProofType,
+    /// Gas to verify the proof, on top of the `fulfill_gas_estimate`
+    pub(crate) fulfill_gas: u64,
+    /// Seconds to post-process the proof after proving
+    pub(crate) post_processing_secs: u64,
+    /// Whether the proving hardware can produce the proof
+    pub(crate) supported: bool,
+}
+
+/// Selectors known to the broker, profiled from the market config.
+///
+/// Profiles are computed on lookup so that config changes apply without a restart.
+#[derive(Clone, Default)]
+pub struct SelectorRegistry {
+    selectors: SupportedSelectors,
+    groth16_wrapper: Option<Groth16WrapperObj>,
+}
+
+impl SelectorRegistry {
+    /// Estimate the time to wrap Groth16 proofs from the latency observed by the wrapper.
+    pub(crate) fn with_groth16_wrapper(self, groth16_wrapper: Groth16WrapperObj) -> Self {
+        Self { groth16_wrapper: Some(groth16_wrapper), ..self }
+    }
+
+    /// Profile of the selector, or `None` if it is not known to the broker.
+    pub(crate) fn profile(
+        &self,
+        market: &MarketConf,
+        selector: FixedBytes<4>,
+    ) -> Option<SelectorProfile> {
+        let proof_type = self.selectors.proof_type(selector)?;
+        let mut profile = match proof_type {
+            ProofType::Groth16 => SelectorProfile {
+                proof_type,
+                fulfill_gas: market.groth16_verify_gas_estimate,
+                post_processing_secs: self
+                    .groth16_wrapper
+                    .as_ref()
+                    .and_then(|wrapper| wrapper.expected_secs())
+                    .unwrap_or(GROTH16_POST_PROCESSING_SECS),
+                supported: true,
+            },
+            _ => SelectorProfile {
+                proof_type,
+                fulfill_gas: 0,
+                post_processing_secs: 0,
+                supported: true,
+            },
+        };
+
+        // Profiles of the proof type apply first, so that those of the selector override them
+        let type_name = proof_type.to_possible_value().map(|value| value.get_name().to_string());
+        let selector_hex = selector.to_string();
+        let type_confs = market.selector_profiles.iter().filter(|conf| {
+            type_name.as_deref().is_some_and(|name| conf.selector.eq_ignore_ascii_case(name))
+        });
+        let selector_confs = market
+            .selector_profiles
+            .iter()
+            .filter(|conf| conf.selector.eq_ignore_ascii_case(&selector_hex));
+        for conf in type_confs.chain(selector_confs) {
+            profile.fulfill_gas = conf.fulfill_gas.unwrap_or(profile.fulfill_gas);
+            profile.post_processing_secs =
+                conf.post_processing_secs.unwrap_or(profile.post_processing_secs);
+            profile.supported = conf.supported.unwrap_or(profile.supported);
+        }
+        Some(profile)
+    }
+
+    /// Profile of the selector, if it is known and the proving hardware supports it.
+    pub(crate) fn supported_profile(
+        &self,
+        market: &MarketConf,
+        selector: FixedBytes<4>,
+    ) -> Option<SelectorProfile> {
+        self.profile(market, selector).filter(|profile| profile.supported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boundless_market::contracts::UNSPECIFIED_SELECTOR;
+    use risc0_ethereum_contracts::selector::Selector;
+
+    #[test]
+    fn profiles_selectors() {
+        let groth16 = FixedBytes::from(Selector::Groth16V2_2 as u32);
+        let registry = SelectorRegistry::default();
+        let mut market = MarketConf { groth16_verify_gas_estimate: 250_000, ..Default::default() };
+
+        let any = registry.profile(&market, UNSPECIFIED_SELECTOR).unwrap();
+        assert_eq!((any.fulfill_gas, any.post_processing_secs, any.supported), (0, 0, true));
+        let profile = registry.profile(&market, groth16).unwrap();
+        assert_eq!(profile.proof_type, ProofType::Groth16);
+        assert_eq!((profile.fulfill_gas, profile.post_processing_secs), (250_000, 60));
+        assert!(registry.profile(&market, FixedBytes::from([0xff; 4])).is_none());
+
+        // Profiles of the selector override those of its proof type
+        let conf = |selector: String, fulfill_gas, supported| crate::config::SelectorProfileConf {
+            selector,
+            fulfill_gas,
+            post_processing_secs: None,
+            supported,
+        };
+        market.selector_profiles = vec![
+            conf(groth16.to_string(), Some(300_000), None),
+            conf("Groth16".into(), Some(1), Some(false)),
+        ];
+        let profile = registry.profile(&market, groth16).unwrap();
+        assert_eq!((profile.fulfill_gas, profile.post_processing_secs), (300_000, 60));
+        assert!(!profile.supported);
+        assert!(registry.supported_profile(&market, groth16).is_none());
+        assert!(registry.supported_profile(&market, UNSPECIFIED_SELECTOR).is_some());
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Feedback loop tightening order intake while the broker falls behind.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use alloy::primitives::U256;
+
+use crate::config::SelfThrottleConf;
+
+/// Maximum number of outcomes kept in memory, regardless of the window.
+const MAX_OUTCOMES: usize = 10_000;
+
+/// Outcome of a commitment of the broker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PipelineOutcome {
+    /// The broker locked an order
+    LockWon,
+    /// Another prover locked an order first
+    LockLost,
+    /// A committed order was fulfilled
+    Fulfilled,
+    /// A committed order expired before it was fulfilled
+    DeadlineMissed,
+}
+
+#[derive(Default)]
+struct ThrottleState {
+    outcomes: VecDeque<(u64, PipelineOutcome)>,
+    level: u32,
+    adjusted_at: u64,
+}
+
+/// Tracks recent lock and fulfillment outcomes, and the resulting throttle level.
+///
+/// The level is re-evaluated whenever it is read, see [SelfThrottleConf].
+#[derive(Default)]
+pub(crate) struct SelfThrottle {
+    state: Mutex<ThrottleState>,
+}
+
+pub(crate) type SelfThrottleObj = Arc<SelfThrottle>;
+
+/// Share of `num` in `num + other`, if there are at least `min_samples` of them.
+fn rate(num: usize, other: usize, min_samples: u64) -> f64 {
+    let total = num + other;
+    if total == 0 || (total as u64) < min_samples {
+        return 0.0;
+    }
+    num as f64 / total as f64
+}
+
+impl SelfThrottle {
+    pub(crate) fn record(&self, outcome: PipelineOutcome, now: u64) {
+        let mut state = self.state.lock().unwrap();
+        if state.outcomes.len() == MAX_OUTCOMES {
+            state.outcomes.pop_front();
+        }
+        state.outcomes.push_back((now, outcome));
+    }
+
+    /// Current throttle level, adjusting it from the outcomes in the window if due.
+    pub(crate) fn level(&self, conf: &SelfThrottleConf, now: u64) -> u32 {
+        let mut state = self.state.lock().unwrap();
+        let since = now.saturating_sub(conf.window_secs);
+        while state.outcomes.front().is_some_and(|(ts, _)| *ts < since) {
+            state.outcomes.pop_front();
+        }
+        let level = state.level.min(conf.max_level);
+        if now < state.adjusted_at + conf.adjust_interval_secs {
+            return level;
+        }
+
+        let count = |outcome| state.outcomes.iter().filter(|(_, o)| *o == outcome).count();
+        let lock_loss_rate = rate(
+            count(PipelineOutcome::LockLost),
+            count(PipelineOutcome::LockWon),
+            conf.min_samples,
+        );
+        let deadline_miss_rate = rate(
+            count(PipelineOutcome::DeadlineMissed),
+            count(PipelineOutcome::Fulfilled),
+            conf.min_samples,
+        );
+        let max_lock_loss = conf.max_lock_loss_percent as f64 / 100.0;
+        let max_deadline_miss = conf.max_deadline_miss_percent as f64 / 100.0;
+
+        let new_level = if lock_loss_rate > max_lock_loss || deadline_miss_rate > max_deadline_miss
+        {
+            (level + 1).min(conf.max_level)
+        } else if lock_loss_rate <= max_lock_loss / 2.0
+            && deadline_miss_rate <= max_deadline_miss / 2.0
+        {
+            level.saturating_sub(1)
+        } else {
+            level
+        };
+        if new_level > level {
+            tracing::warn!(
+                "Falling behind, {:.0}% of lock attempts lost and {:.0}% of deadlines missed in the last {}s; raising self-throttle level to {new_level}",
+                lock_loss_rate * 100.0,
+                deadline_miss_rate * 100.0,
+                conf.window_secs
+            );
+        } else if new_level < level {
+            tracing::info!(
+                "Recovered, {:.0}% of lock attempts lost and {:.0}% of deadlines missed in the last {}s; lowering self-throttle level to {new_level}",
+                lock_loss_rate * 100.0,
+                deadline_miss_rate * 100.0,
+                conf.window_secs
+            );
+        }
+        if new_level != level {
+            state.adjusted_at = now;
+        }
+        state.level = new_level;
+        new_level
+    }
+
+    /// Minimum price raised for the current level.
+    pub(crate) fn scale_min_price(&self, conf: &SelfThrottleConf, price: U256, now: u64) -> U256 {
+        let percent = 100 + self.level(conf, now) as u64 * conf.price_step_percent;
+        price * U256::from(percent) / U256::from(100)
+    }
+
+    /// Maximum number of concurrent proofs lowered for the current level, to no less than one.
+    pub(crate) fn scale_max_concurrent_proofs(
+        &self,
+        conf: &SelfThrottleConf,
+        max_concurrent_proofs: Option<u32>,
+        now: u64,
+    ) -> Option<u32> {
+        let reduction = self.level(conf, now) as u64 * conf.concurrency_step_percent;
+        max_concurrent_proofs.map(|max| {
+            let scaled = max as u64 * 100u64.saturating_sub(reduction) / 100;
+            (scaled as u32).max(1)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tightens_and_loosens_intake() {
+        let conf = SelfThrottleConf {
+            window_secs: 100,
+            adjust_interval_secs: 10,
+            min_samples: 4,
+            max_lock_loss_percent: 50,
+            max_deadline_miss_percent: 10,
+            max_level: 2,
+            price_step_percent: 50,
+            concurrency_step_percent: 40,
+        };
+        let throttle = SelfThrottle::default();
+        for _ in 0..3 {
+            throttle.record(PipelineOutcome::LockLost, 1_000);
+        }
+        // Not enough samples
+        assert_eq!(throttle.level(&conf, 1_000), 0);
+
+        throttle.record(PipelineOutcome::LockWon, 1_000);
+        assert_eq!(throttle.level(&conf, 1_000), 1);
+        // At most one adjustment per interval
+        assert_eq!(throttle.level(&conf, 1_005), 1);
+        assert_eq!(throttle.scale_min_price(&conf, U256::from(100), 1_005), U256::from(150));
+        assert_eq!(throttle.scale_max_concurrent_proofs(&conf, Some(10), 1_005), Some(6));
+        assert_eq!(throttle.scale_max_concurrent_proofs(&conf, None, 1_005), None);
+
+        assert_eq!(throttle.level(&conf, 1_010), 2);
+        assert_eq!(throttle.level(&conf, 1_020), 2);
+        assert_eq!(throttle.scale_max_concurrent_proofs(&conf, Some(2), 1_020), Some(1));
+
+        // Outcomes leave the window
+        assert_eq!(throttle.level(&conf, 1_101), 1);
+        assert_eq!(throttle.level(&conf, 1_111), 0);
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification of the signatures of orders received from the order stream, off the intake path
+//! of the order picker.
+//!
+//! Recovering the signer of an EIP-712 signature is CPU bound. The orders queued when the
+//! verifier picks up work are verified together, split across a pool of blocking workers, and
+//! those with a valid signature are forwarded to the order picker with their signature marked
+//! as validated, so that it is not verified again. Orders with an invalid signature can not be
+//! locked, and are dropped. Smart contract signed orders are forwarded as is.
+
+use std::{num::NonZeroUsize, sync::Arc, time::Instant};
+
+use thiserror::Error;
+use tokio::{
+    sync::{mpsc, Mutex},
+    task::JoinSet,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{ConfigErr, ConfigLock},
+    errors::CodedError,
+    impl_coded_debug,
+    metrics::MetricsObj,
+    task::{RetryRes, RetryTask, SupervisorErr},
+    OrderRequest,
+};
+
+#[derive(Error)]
+pub enum SignatureVerifierErr {
+    #[error("{code} Config error {0}", code = self.code())]
+    ConfigReadErr(#[from] ConfigErr),
+
+    #[error("{code} Receiver dropped", code = self.code())]
+    ReceiverDropped,
+
+    #[error("{code} Unexpected error: {0:?}", code = self.code())]
+    UnexpectedErr(#[from] anyhow::Error),
+}
+
+impl_coded_debug!(SignatureVerifierErr);
+
+impl CodedError for SignatureVerifierErr {
+    fn code(&self) -> &str {
+        match self {
+            SignatureVerifierErr::ConfigReadErr(_) => "[B-SIG-001]",
+            SignatureVerifierErr::ReceiverDropped => "[B-SIG-002]",
+            SignatureVerifierErr::UnexpectedErr(_) => "[B-SIG-500]",
+        }
+    }
+}
+
+pub(crate) struct SignatureVerifier {
+    config: ConfigLock,
+    metrics: MetricsObj,
+    order_rx: Arc<Mutex<mpsc::Receiver<Box<OrderRequest>>>>,
+    verified_tx: mpsc::Sender<Box<OrderRequest>>,
+}
+
+impl SignatureVerifier {
+    pub(crate) fn new(
+        config: ConfigLock,
+        metrics: MetricsObj,
+        order_rx: mpsc::Receiver<Box<OrderRequest>>,
+        verified_tx: mpsc::Sender<Box<OrderRequest>>,
+    ) -> Self {
+        Self { config, metrics, order_rx: Arc::new(Mutex::new(order_rx)), verified_tx }
+    }
+
+    /// Number of workers and batch size to verify signatures with.
+    fn read_config(&self) -> Result<(usize, usize), SignatureVerifierErr> {
+        let config = self.config.lock_all()?;
+        let workers = config.market.signature_verify_workers.unwrap_or_else(|| {
+            std::thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1)
+        });
+        Ok((workers.max(1), config.market.signature_verify_batch_size.max(1)))
+    }
+
+    async fn run(&self, cancel_token: CancellationToken) -> Result<(), SignatureVerifierErr> {
+        let mut order_rx = self.order_rx.lock().await;
+        loop {
+            let order = tokio::select! {
+                order = order_rx.recv() => order,
+                _ = cancel_token.cancelled() => return Ok(()),
+            };
+            // The order stream monitors hold the sender for the lifetime of the broker
+            let Some(order) = order else {
+                return Ok(());
+            };
+
+            let (workers, batch_size) = self.read_config()?;
+            let mut batch = vec![order];
+            while batch.len() < batch_size {
+                match order_rx.try_recv() {
+                    Ok(order) => batch.push(order),
+                    Err(_) => break,
+                }
+            }
+
+            for order in verify_batch(batch, workers, &self.metrics).await? {
+                self.verified_tx
+                    .send(order)
+                    .await
+                    .map_err(|_| SignatureVerifierErr::ReceiverDropped)?;
+            }
+        }
+    }
+}
+
+/// Verify the signatures of a batch of orders across up to `workers` blocking tasks, returning
+/// the orders to forward.
+async fn verify_batch(
+    orders: Vec<Box<OrderRequest>>,
+    workers: usize,
+    metrics: &MetricsObj,
+) -> anyhow::Result<Vec<Box<OrderRequest>>> {
+    let (to_verify, mut forwarded): (Vec<_>, Vec<_>) =
+        orders.into_iter().partition(|order| order.needs_signature_verification());
+    if to_verify.is_empty() {
+        return Ok(forwarded);
+    }
+
+    let started = Instant::now();
+    let count = to_verify.len();
+    let chunk_size = count.div_ceil(workers);
+    let mut tasks = JoinSet::new();
+    let mut to_verify = to_verify.into_iter().peekable();
+    while to_verify.peek().is_some() {
+        let chunk: Vec<_> = to_verify.by_ref().take(chunk_size).collect();
+        tasks.spawn_blocking(move || {
+            chunk
+                .into_iter()
+                .map(|order| {
+                    let valid = order.has_valid_signature();
+                    (order, valid)
+                })
+                .collect::<Vec<_>>()
+        });
+    }
+
+    let mut invalid = 0;
+    while let Some(results) = tasks.join_next().await {
+        for (order, valid) in results? {
+            if valid {
+                forwarded.push(Box::new(order.with_signature_validated()));
+            } else {
+                tracing::warn!("Dropping order {} with an invalid signature", order.id());
+                invalid += 1;
+            }
+        }
+    }
+    let elapsed = started.elapsed();
+    metrics.record_signatures_verified(count as u64, invalid, elapsed);
+    tracing::trace!("Verified {count} signatures in {elapsed:?} with {workers} workers");
+
+    Ok(forwarded)
+}
+
+impl RetryTask for SignatureVerifier {
+    type Error = SignatureVerifierErr;
+    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
+        let verifier = Self {
+            config: self.config.clone(),
+            metrics: self.metrics.clone(),
+            order_rx: self.order_rx.clone(),
+            verified_tx: self.verified_tx.clone(),
+        };
+        Box::pin(async move {
+            tracing::info!("Starting signature verifier");
+            verifier.run(cancel_token).await.map_err(SupervisorErr::Recover)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FulfillmentType, OrderProvenance, OrderSource};
+    use alloy::{
+        primitives::{Address, U256},
+        signers::local::PrivateKeySigner,
+    };
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput, RequestInputType,
+        Requirements,
+    };
+    use risc0_zkvm::sha::Digest;
+
+    async fn order(signer: &PrivateKeySigner, id: RequestId, valid: bool) -> Box<OrderRequest> {
+        let (market, chain_id) = (Address::repeat_byte(9), 1);
+        let request = ProofRequest::new(
+            id,
+            Requirements::new(
+                Digest::ZERO,
+                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+            ),
+            "http://risczero.com",
+            RequestInput { inputType: RequestInputType::Inline, data: "".into() },
+            Offer {
+                minPrice: U256::from(1),
+                maxPrice: U256::from(2),
+                biddingStart: 100,
+                timeout: 100,
+                lockTimeout: 50,
+                rampUpPeriod: 1,
+                lockStake: U256::from(3),
+            },
+        );
+        // A signature for another chain does not recover the client address
+        let signing_chain_id = if valid { chain_id } else { chain_id + 1 };
+        let signature = request.sign_request(signer, market, signing_chain_id).await.unwrap();
+        let order = OrderRequest::new(
+            request,
+            signature.as_bytes().into(),
+            FulfillmentType::LockAndFulfill,
+            market,
+            chain_id,
+        )
+        .with_provenance(OrderProvenance::new(OrderSource::OrderStream {
+            url: "http://localhost:8585/".into(),
+            stream_id: None,
+        }));
+        Box::new(order)
+    }
+
+    #[tokio::test]
+    async fn forwards_orders_with_valid_signatures() {
+        let signer = PrivateKeySigner::random();
+        let id = |index| RequestId::new(signer.address(), index);
+        let (order_tx, order_rx) = mpsc::channel(8);
+        let (verified_tx, mut verified_rx) = mpsc::channel(8);
+        let metrics = MetricsObj::default();
+        let verifier =
+            SignatureVerifier::new(ConfigLock::default(), metrics.clone(), order_rx, verified_tx);
+
+        order_tx.send(order(&signer, id(1), false).await).await.unwrap();
+        order_tx.send(order(&signer, id(2), true).await).await.unwrap();
+        // Smart contract signatures are validated on-chain and forwarded as is
+        let smart_contract_signed = id(3).set_smart_contract_signed_flag();
+        order_tx.send(order(&signer, smart_contract_signed, false).await).await.unwrap();
+        drop(order_tx);
+        verifier.run(CancellationToken::new()).await.unwrap();
+        drop(verifier);
+
+        let mut forwarded = Vec::new();
+        while let Some(order) = verified_rx.recv().await {
+            forwarded.push(order);
+        }
+        forwarded.sort_by_key(|order| RequestId::from_lossy(order.request.id).index);
+        assert_eq!(forwarded.len(), 2);
+        assert_eq!(RequestId::from_lossy(forwarded[0].request.id).index, 2);
+        assert!(forwarded[0].signature_validated());
+        assert!(!forwarded[1].signature_validated());
+
+        let encoded = metrics.encode();
+        assert!(encoded.contains("broker_signatures_verified_total 2\n"));
+        assert!(encoded.contains("broker_signatures_invalid_total 1\n"));
+    }
+}
@@ -0,0 +1,299 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Re-queues orders that were skipped for transient reasons once the condition that caused the
+//! skip has improved.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, U256},
+    providers::{Provider, WalletProvider},
+};
+use boundless_market::contracts::boundless_market::{BoundlessMarketService, MarketError};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{ConfigErr, ConfigLock},
+    db::{DbError, DbObj},
+    errors::CodedError,
+    impl_coded_debug, now_timestamp,
+    order_picker::OrderCache,
+    task::{RetryRes, RetryTask, SupervisorErr},
+    FulfillmentType, Order, OrderProvenance, OrderRequest, OrderSource, SkipReason,
+};
+
+/// Maximum number of skipped orders per reason re-queued at once.
+const MAX_REQUEUED_PER_REASON: u32 = 100;
+
+/// Maximum number of times a skipped order is re-queued.
+const MAX_REQUEUES_PER_ORDER: u32 = 3;
+
+#[derive(Error)]
+pub enum SkipReevaluatorErr {
+    #[error("{code} DB error: {0}", code = self.code())]
+    DbError(#[from] DbError),
+
+    #[error("{code} Config error {0}", code = self.code())]
+    ConfigReadErr(#[from] ConfigErr),
+
+    #[error("{code} RPC error: {0:?}", code = self.code())]
+    RpcErr(anyhow::Error),
+
+    #[error("{code} Market error: {0}", code = self.code())]
+    MarketErr(#[from] MarketError),
+
+    #[error("{code} New order channel closed", code = self.code())]
+    ChannelClosed,
+}
+
+impl_coded_debug!(SkipReevaluatorErr);
+
+impl CodedError for SkipReevaluatorErr {
+    fn code(&self) -> &str {
+        match self {
+            SkipReevaluatorErr::DbError(_) => "[B-REEVAL-001]",
+            SkipReevaluatorErr::ConfigReadErr(_) => "[B-REEVAL-002]",
+            SkipReevaluatorErr::RpcErr(_) => "[B-REEVAL-003]",
+            SkipReevaluatorErr::MarketErr(_) => "[B-REEVAL-004]",
+            SkipReevaluatorErr::ChannelClosed => "[B-REEVAL-005]",
+        }
+    }
+}
+
+/// Conditions that orders skipped for transient reasons depend on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Conditions {
+    gas_balance: U256,
+    stake_balance: U256,
+    committed_orders: usize,
+}
+
+impl Conditions {
+    /// Skip reasons whose condition improved since `prev`.
+    fn improved_since(&self, prev: &Conditions) -> Vec<SkipReason> {
+        let mut reasons = Vec::new();
+        if self.gas_balance > prev.gas_balance {
+            reasons.push(SkipReason::InsufficientGas);
+        }
+        if self.stake_balance > prev.stake_balance {
+            reasons.push(SkipReason::InsufficientStake);
+        }
+        if self.committed_orders < prev.committed_orders {
+            reasons.push(SkipReason::InsufficientProvingTime);
+        }
+        reasons
+    }
+}
+
+/// Watches the prover's balances and proving queue, and re-injects orders skipped for
+/// insufficient gas, stake or proving capacity into the order picker when they improve.
+///
+/// Orders are only re-queued while there is more than `min_deadline` (or the base of the
+/// `deadline_margin`) left before their lock expires, or before the request expires for orders
+/// fulfilled after lock expiry.
+#[derive(Clone)]
+pub struct SkipReevaluator<P> {
+    db: DbObj,
+    config: ConfigLock,
+    provider: Arc<P>,
+    market_addr: Address,
+    prover_addr: Address,
+    chain_id: u64,
+    new_order_tx: mpsc::Sender<Box<OrderRequest>>,
+    order_cache: OrderCache,
+}
+
+impl<P> SkipReevaluator<P>
+where
+    P: Provider<Ethereum> + WalletProvider + 'static + Clone,
+{
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        db: DbObj,
+        config: ConfigLock,
+        provider: Arc<P>,
+        market_addr: Address,
+        prover_addr: Address,
+        chain_id: u64,
+        new_order_tx: mpsc::Sender<Box<OrderRequest>>,
+        order_cache: OrderCache,
+    ) -> Self {
+        Self { db, config, provider, market_addr, prover_addr, chain_id, new_order_tx, order_cache }
+    }
+
+    async fn conditions(&self) -> Result<Conditions, SkipReevaluatorErr> {
+        let gas_balance = self
+            .provider
+            .get_balance(self.provider.default_signer_address())
+            .await
+            .map_err(|err| SkipReevaluatorErr::RpcErr(err.into()))?;
+        let market =
+            BoundlessMarketService::new(self.market_addr, self.provider.clone(), Address::ZERO);
+        let stake_balance = market.balance_of_stake(self.prover_addr).await?;
+        let committed_orders = self.db.get_committed_orders().await?.len();
+        Ok(Conditions { gas_balance, stake_balance, committed_orders })
+    }
+
+    /// Returns the deadline by which the skipped order must be locked or fulfilled, if it is for
+    /// this market.
+    fn deadline(&self, order: &Order) -> Option<u64> {
+        if order.chain_id != self.chain_id || order.boundless_market_address != self.market_addr {
+            return None;
+        }
+        Some(match order.fulfillment_type {
+            FulfillmentType::LockAndFulfill => order.request.lock_expires_at(),
+            FulfillmentType::FulfillAfterLockExpire | FulfillmentType::FulfillWithoutLocking => {
+                order.request.expires_at()
+            }
+        })
+    }
+
+    /// Re-queue orders skipped for the given reasons, returning the number of orders re-queued.
+    async fn requeue_skipped(
+        &self,
+        reasons: &[SkipReason],
+        requeues: &mut HashMap<String, (u32, u64)>,
+    ) -> Result<usize, SkipReevaluatorErr> {
+        let min_deadline = self.config.lock_all()?.market.deadline_margin_secs(None, None);
+        requeues.retain(|_, (_, deadline)| *deadline > now_timestamp());
+
+        let mut count = 0;
+        for reason in reasons {
+            let orders = self.db.get_skipped_orders(*reason, MAX_REQUEUED_PER_REASON).await?;
+            for order in orders {
+                let Some(deadline) = self.deadline(&order) else {
+                    continue;
+                };
+                if deadline <= now_timestamp() + min_deadline {
+                    continue;
+                }
+                let (attempts, _) = requeues.entry(order.id()).or_insert((0, deadline));
+                if *attempts >= MAX_REQUEUES_PER_ORDER {
+                    continue;
+                }
+                *attempts += 1;
+
+                let order_request = OrderRequest::new(
+                    order.request,
+                    order.client_sig,
+                    order.fulfillment_type,
+                    order.boundless_market_address,
+                    order.chain_id,
+                )
+                .with_provenance(OrderProvenance::new(OrderSource::Reevaluation));
+                tracing::info!(
+                    "Re-queueing order {} skipped for {reason}, attempt {attempts}",
+                    order_request.id()
+                );
+                // The order picker ignores orders it has recently seen
+                self.order_cache.invalidate(&order_request.order_id()).await;
+                self.new_order_tx
+                    .send(Box::new(order_request))
+                    .await
+                    .map_err(|_| SkipReevaluatorErr::ChannelClosed)?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    async fn run_loop(&self, cancel_token: CancellationToken) -> Result<(), SkipReevaluatorErr> {
+        let interval = self.config.lock_all()?.market.skipped_order_reevaluation_interval_secs;
+        if interval == 0 {
+            tracing::debug!("Re-evaluation of skipped orders disabled");
+            return Ok(());
+        }
+
+        let mut prev = self.conditions().await?;
+        let mut requeues: HashMap<String, (u32, u64)> = HashMap::new();
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(interval)) => {},
+                _ = cancel_token.cancelled() => {
+                    tracing::info!("Skip re-evaluator received cancellation, shutting down gracefully");
+                    return Ok(());
+                }
+            }
+
+            let current = match self.conditions().await {
+                Ok(current) => current,
+                Err(err) => {
+                    tracing::warn!("Failed to check conditions for skipped orders: {err}");
+                    continue;
+                }
+            };
+            let reasons = current.improved_since(&prev);
+            prev = current;
+            if reasons.is_empty() {
+                continue;
+            }
+
+            tracing::debug!("Conditions improved for orders skipped for {reasons:?}");
+            match self.requeue_skipped(&reasons, &mut requeues).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Re-queued {count} skipped orders"),
+                Err(SkipReevaluatorErr::ChannelClosed) => {
+                    return Err(SkipReevaluatorErr::ChannelClosed)
+                }
+                Err(err) => tracing::warn!("Failed to re-queue skipped orders: {err}"),
+            }
+        }
+    }
+}
+
+impl<P> RetryTask for SkipReevaluator<P>
+where
+    P: Provider<Ethereum> + WalletProvider + 'static + Clone,
+{
+    type Error = SkipReevaluatorErr;
+
+    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
+        let this = self.clone();
+        Box::pin(async move {
+            this.run_loop(cancel_token).await.map_err(SupervisorErr::Recover)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn improved_conditions() {
+        let prev = Conditions {
+            gas_balance: U256::from(10),
+            stake_balance: U256::from(10),
+            committed_orders: 2,
+        };
+        assert!(prev.improved_since(&prev).is_empty());
+
+        let current = Conditions { gas_balance: U256::from(5), ..prev };
+        assert!(current.improved_since(&prev).is_empty());
+
+        let current = Conditions { gas_balance: U256::from(20), committed_orders: 1, ..prev };
+        assert_eq!(
+            current.improved_since(&prev),
+            [SkipReason::InsufficientGas, SkipReason::InsufficientProvingTime]
+        );
+
+        let current = Conditions { stake_balance: U256::from(11), committed_orders: 3, ..prev };
+        assert_eq!(current.improved_since(&prev), [SkipReason::InsufficientStake]);
+    }
+}
@@ -12,11 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{config::ConfigLock, errors::CodedError, is_dev_mode};
+use crate::{
+    config::{ArtifactMirrorConf, ConfigLock},
+    errors::CodedError,
+    is_dev_mode,
+};
 use alloy::primitives::bytes::Buf;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use aws_config::retry::RetryConfig;
+use aws_config::{retry::RetryConfig, SdkConfig};
 use aws_sdk_s3::{
     config::{ProvideCredentials, SharedCredentialsProvider},
     error::ProvideErrorMetadata,
@@ -26,17 +30,29 @@ use futures::StreamExt;
 use http_cache_reqwest::{CACacheManager, Cache, CacheMode, HttpCache, HttpCacheOptions};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
-use risc0_zkvm::Digest;
+use risc0_zkvm::{Digest, Receipt, VerifierContext};
 use std::{
+    collections::HashMap,
     env,
     error::Error as StdError,
     fmt::{Display, Formatter},
     path::PathBuf,
-    sync::Arc,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
 };
+use url::form_urlencoded;
 
 const ENV_VAR_ROLE_ARN: &str = "AWS_ROLE_ARN";
 
+/// Fragment parameter used by requests to list mirrors of an image or input URL.
+const MIRROR_FRAGMENT_PARAM: &str = "mirror";
+
+/// Time after its last failure after which a host is no longer deprioritized.
+const MIRROR_FAILURE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Fetch failures per artifact host, shared by all fetches in the process.
+static MIRROR_HEALTH: LazyLock<MirrorHealth> = LazyLock::new(MirrorHealth::default);
+
 #[derive(thiserror::Error, Debug)]
 #[non_exhaustive]
 pub enum StorageErr {
@@ -71,13 +87,88 @@ impl CodedError for StorageErr {
     }
 }
 
+/// Create a handler for the given URI, falling back to its mirrors if any are configured or given
+/// in the URI fragment.
 pub(crate) async fn create_uri_handler(
     uri_str: &str,
     config: &ConfigLock,
     skip_max_size_check: bool,
 ) -> Result<Arc<dyn Handler>, StorageErr> {
     let uri = url::Url::parse(uri_str)?;
+    let artifact_mirrors = config.lock_all().expect("lock failed").market.artifact_mirrors.clone();
+
+    let mut urls = artifact_urls(uri, &artifact_mirrors);
+    if urls.len() == 1 {
+        return create_url_handler(urls.remove(0), config, skip_max_size_check).await;
+    }
+
+    let mut handlers = Vec::with_capacity(urls.len());
+    let mut first_err = None;
+    for url in urls {
+        let host = mirror_host(&url);
+        match create_url_handler(url, config, skip_max_size_check).await {
+            Ok(handler) => handlers.push((host, handler)),
+            Err(err) => {
+                tracing::debug!("Skipping artifact location on {host}: {err:?}");
+                first_err.get_or_insert(err);
+            }
+        }
+    }
+    match first_err {
+        Some(err) if handlers.is_empty() => Err(err),
+        _ => Ok(Arc::new(MirrorHandler { handlers })),
+    }
+}
 
+/// The given URL followed by its mirrors, from the URL fragment and from the configured
+/// artifact mirrors.
+fn artifact_urls(mut uri: url::Url, artifact_mirrors: &[ArtifactMirrorConf]) -> Vec<url::Url> {
+    let request_mirrors: Vec<String> = uri
+        .fragment()
+        .map(|fragment| {
+            form_urlencoded::parse(fragment.as_bytes())
+                .filter(|(key, _)| key == MIRROR_FRAGMENT_PARAM)
+                .map(|(_, value)| value.into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    if !request_mirrors.is_empty() {
+        uri.set_fragment(None);
+    }
+
+    let configured_mirrors = artifact_mirrors.iter().flat_map(|conf| {
+        let path = uri.as_str().strip_prefix(conf.prefix.as_str());
+        conf.mirrors.iter().filter_map(move |mirror| Some(format!("{mirror}{}", path?)))
+    });
+    let mirrors: Vec<String> = request_mirrors.into_iter().chain(configured_mirrors).collect();
+
+    let mut urls = vec![uri];
+    for mirror in mirrors {
+        match url::Url::parse(&mirror) {
+            Ok(url) if !urls.contains(&url) => urls.push(url),
+            Ok(_) => {}
+            Err(err) => tracing::debug!("Ignoring invalid mirror URL {mirror}: {err}"),
+        }
+    }
+    urls
+}
+
+/// Key used to track the health of the host serving the given URL.
+fn mirror_host(url: &url::Url) -> String {
+    match url.host_str() {
+        Some(host) => match url.port() {
+            Some(port) => format!("{}://{host}:{port}", url.scheme()),
+            None => format!("{}://{host}", url.scheme()),
+        },
+        None => url.scheme().to_string(),
+    }
+}
+
+async fn create_url_handler(
+    uri: url::Url,
+    config: &ConfigLock,
+    skip_max_size_check: bool,
+) -> Result<Arc<dyn Handler>, StorageErr> {
     match uri.scheme() {
         "file" => {
             if !is_dev_mode() {
@@ -122,6 +213,82 @@ pub(crate) trait Handler: Display + Send + Sync {
     async fn fetch(&self) -> Result<Vec<u8>, StorageErr>;
 }
 
+struct HostHealth {
+    consecutive_failures: u32,
+    last_failure: Instant,
+}
+
+/// Recent fetch failures per host, used to try healthy mirrors first.
+#[derive(Default)]
+struct MirrorHealth {
+    hosts: Mutex<HashMap<String, HostHealth>>,
+}
+
+impl MirrorHealth {
+    fn record_success(&self, host: &str) {
+        self.hosts.lock().unwrap().remove(host);
+    }
+
+    fn record_failure(&self, host: &str) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let health = hosts
+            .entry(host.to_string())
+            .or_insert(HostHealth { consecutive_failures: 0, last_failure: Instant::now() });
+        health.consecutive_failures += 1;
+        health.last_failure = Instant::now();
+    }
+
+    /// Consecutive failures of the host, or 0 if it has not failed within [MIRROR_FAILURE_TTL].
+    fn failures(&self, host: &str) -> u32 {
+        match self.hosts.lock().unwrap().get(host) {
+            Some(health) if health.last_failure.elapsed() < MIRROR_FAILURE_TTL => {
+                health.consecutive_failures
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// Fetches an artifact from the first of its locations that succeeds.
+///
+/// Locations are tried in order of the recent failures of their host, then in the order given,
+/// with the original URL first.
+struct MirrorHandler {
+    handlers: Vec<(String, Arc<dyn Handler>)>,
+}
+
+impl Display for MirrorHandler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.handlers[0].1.fmt(f)
+    }
+}
+
+#[async_trait]
+impl Handler for MirrorHandler {
+    async fn fetch(&self) -> Result<Vec<u8>, StorageErr> {
+        let mut handlers: Vec<_> = self.handlers.iter().collect();
+        handlers.sort_by_key(|(host, _)| MIRROR_HEALTH.failures(host));
+
+        let mut last_err = None;
+        for (host, handler) in handlers {
+            match handler.fetch().await {
+                Ok(data) => {
+                    MIRROR_HEALTH.record_success(host);
+                    return Ok(data);
+                }
+                // The artifact is the same on every mirror
+                Err(err @ StorageErr::SizeLimitExceeded(_)) => return Err(err),
+                Err(err) => {
+                    tracing::warn!("Failed to fetch {handler}, trying next mirror: {err:?}");
+                    MIRROR_HEALTH.record_failure(host);
+                    last_err = Some(err);
+                }
+            }
+        }
+        Err(last_err.expect("mirror handler has at least one location"))
+    }
+}
+
 struct FileHandler {
     path: PathBuf,
     max_size: usize,
@@ -229,6 +396,44 @@ impl Handler for HttpHandler {
     }
 }
 
+/// Loads the AWS SDK config from the default credential chain.
+///
+/// If `AWS_ROLE_ARN` is set, the resolved credentials are used to assume that role. Returns
+/// [StorageErr::UnsupportedScheme] if no credentials can be resolved from the environment.
+pub(crate) async fn load_aws_config(max_retries: Option<u8>) -> Result<SdkConfig, StorageErr> {
+    let retry_config = if let Some(max_retries) = max_retries {
+        RetryConfig::standard().with_max_attempts(max_retries as u32 + 1)
+    } else {
+        RetryConfig::disabled()
+    };
+
+    let mut config = aws_config::from_env().retry_config(retry_config).load().await;
+
+    if let Some(provider) = config.credentials_provider() {
+        if let Err(e) = provider.provide_credentials().await {
+            tracing::debug!(error=%e, "Could not load initial AWS credentials required for S3 support. S3 support disabled.");
+            return Err(StorageErr::UnsupportedScheme("s3".to_string()));
+        }
+    } else {
+        // This should not happen with aws_config::from_env()
+        return Err(StorageErr::UnsupportedScheme("s3".to_string()));
+    }
+
+    if let Ok(role_arn) = env::var(ENV_VAR_ROLE_ARN) {
+        // Create the AssumeRoleProvider using the base_config for its STS client needs
+        let role_provider = aws_config::sts::AssumeRoleProvider::builder(role_arn)
+            .configure(&config) // Use the base config to configure the provider
+            .build()
+            .await;
+        config = config
+            .into_builder()
+            .credentials_provider(SharedCredentialsProvider::new(role_provider))
+            .build();
+    }
+
+    Ok(config)
+}
+
 /// Handles fetching data specified by `s3://` URIs using the AWS SDK.
 ///
 /// This handler authenticates using the default AWS credential chain (environment variables,
@@ -257,35 +462,7 @@ impl S3Handler {
         max_size: usize,
         max_retries: Option<u8>,
     ) -> Result<Self, StorageErr> {
-        let retry_config = if let Some(max_retries) = max_retries {
-            RetryConfig::standard().with_max_attempts(max_retries as u32 + 1)
-        } else {
-            RetryConfig::disabled()
-        };
-
-        let mut config = aws_config::from_env().retry_config(retry_config).load().await;
-
-        if let Some(provider) = config.credentials_provider() {
-            if let Err(e) = provider.provide_credentials().await {
-                tracing::debug!(error=%e, "Could not load initial AWS credentials required for S3 support. S3 support disabled.");
-                return Err(StorageErr::UnsupportedScheme("s3".to_string()));
-            }
-        } else {
-            // This should not happen with aws_config::from_env()
-            return Err(StorageErr::UnsupportedScheme("s3".to_string()));
-        }
-
-        if let Ok(role_arn) = env::var(ENV_VAR_ROLE_ARN) {
-            // Create the AssumeRoleProvider using the base_config for its STS client needs
-            let role_provider = aws_config::sts::AssumeRoleProvider::builder(role_arn)
-                .configure(&config) // Use the base config to configure the provider
-                .build()
-                .await;
-            config = config
-                .into_builder()
-                .credentials_provider(SharedCredentialsProvider::new(role_provider))
-                .build();
-        }
+        let config = load_aws_config(max_retries).await?;
 
         let bucket = url.host_str().ok_or(StorageErr::InvalidURL("missing bucket"))?;
         let key = url.path().trim_start_matches('/');
@@ -342,10 +519,15 @@ impl Handler for S3Handler {
     }
 }
 
+/// Upload the image of the request to the prover, if it does not have it.
+///
+/// The image is read from the image cache if stored there, and otherwise downloaded, verified and
+/// stored in the cache.
 pub async fn upload_image_uri(
     prover: &crate::provers::ProverObj,
     request: &crate::ProofRequest,
     config: &crate::config::ConfigLock,
+    image_cache: Option<&crate::image_cache::ImageCacheObj>,
 ) -> Result<String> {
     let required_image_id = Digest::from(request.requirements.imageId.0);
     let image_id_str = required_image_id.to_string();
@@ -357,6 +539,22 @@ pub async fn upload_image_uri(
         return Ok(image_id_str);
     }
 
+    let cached = match image_cache {
+        Some(image_cache) => image_cache.get(&required_image_id).await,
+        None => None,
+    };
+    if let Some(image_data) = cached {
+        tracing::debug!(
+            "Uploading cached program for request {:x} with image ID {image_id_str} to prover",
+            request.id
+        );
+        prover
+            .upload_image(&image_id_str, image_data)
+            .await
+            .context("Failed to upload image to prover")?;
+        return Ok(image_id_str);
+    }
+
     tracing::debug!(
         "Fetching program for request {:x} with image ID {image_id_str} from URI {}",
         request.id,
@@ -379,6 +577,11 @@ pub async fn upload_image_uri(
         required_image_id,
         image_id
     );
+    if let Some(image_cache) = image_cache {
+        if let Err(err) = image_cache.put(&image_id, &image_data).await {
+            tracing::warn!("Failed to store image {image_id_str} in the image cache: {err:?}");
+        }
+    }
 
     tracing::debug!(
         "Uploading program for request {:x} with image ID {image_id_str} to prover",
@@ -392,54 +595,49 @@ pub async fn upload_image_uri(
     Ok(image_id_str)
 }
 
-pub async fn upload_input_uri(
-    prover: &crate::provers::ProverObj,
+/// Upload the request input for the given order, reusing an identical input if one was already
+/// uploaded.
+pub(crate) async fn upload_input_uri(
+    input_dedup: &crate::input_dedup::InputDedupObj,
+    input_fetcher: &crate::input_fetcher::InputFetcherObj,
     request: &crate::ProofRequest,
-    config: &crate::config::ConfigLock,
+    order_id: &str,
 ) -> Result<String> {
-    Ok(match request.input.inputType {
-        boundless_market::contracts::RequestInputType::Inline => prover
-            .upload_input(
-                boundless_market::input::GuestEnv::decode(&request.input.data)
-                    .with_context(|| "Failed to decode input")?
-                    .stdin,
-            )
-            .await
-            .context("Failed to upload input data")?,
-
-        boundless_market::contracts::RequestInputType::Url => {
-            let input_uri_str =
-                std::str::from_utf8(&request.input.data).context("input url is not utf8")?;
-            tracing::debug!("Input URI string: {input_uri_str}");
-            let priority_requestor_addresses = {
-                let conf = config.lock_all().context("Failed to read config")?;
-                conf.market.priority_requestor_addresses.clone()
-            };
-
-            let client_addr = request.client_address();
-            let skip_max_size_limit = if let Some(allow_addresses) = priority_requestor_addresses {
-                allow_addresses.contains(&client_addr)
-            } else {
-                false
-            };
-            let input_uri = create_uri_handler(input_uri_str, config, skip_max_size_limit)
-                .await
-                .context("URL handling failed")?;
-
-            let input_data = boundless_market::input::GuestEnv::decode(
-                &input_uri
-                    .fetch()
-                    .await
-                    .with_context(|| format!("Failed to fetch input URI: {input_uri_str}"))?,
-            )
-            .with_context(|| format!("Failed to decode input from URI: {input_uri_str}"))?
-            .stdin;
+    let env = input_fetcher.guest_env(request).await?;
+    input_dedup.upload_input(env.stdin.clone(), order_id).await.context("Failed to upload input")
+}
 
-            prover.upload_input(input_data).await.context("Failed to upload input")?
-        }
-        //???
-        _ => anyhow::bail!("Invalid input type: {:?}", request.input.inputType),
-    })
+/// Upload the receipts of the assumptions of the request input to the prover, returning their
+/// IDs.
+///
+/// Receipts are verified before upload, as the prover only checks them when resolving the
+/// assumptions at the end of proving.
+pub(crate) async fn upload_assumptions_uri(
+    prover: &crate::provers::ProverObj,
+    input_fetcher: &crate::input_fetcher::InputFetcherObj,
+    request: &crate::ProofRequest,
+    config: &ConfigLock,
+) -> Result<Vec<String>> {
+    let env = input_fetcher.guest_env(request).await?;
+    let mut receipt_ids = Vec::with_capacity(env.assumptions.len());
+    for url in &env.assumptions {
+        tracing::debug!("Fetching assumption receipt for request {:x} from URI {url}", request.id);
+        let uri = create_uri_handler(url, config, false).await.context("URL handling failed")?;
+        let data = uri
+            .fetch()
+            .await
+            .with_context(|| format!("Failed to fetch assumption receipt URI: {url}"))?;
+        let receipt: Receipt = bincode::deserialize(&data)
+            .with_context(|| format!("Failed to decode assumption receipt from URI: {url}"))?;
+        receipt
+            .verify_integrity_with_context(&VerifierContext::default())
+            .with_context(|| format!("Invalid assumption receipt from URI: {url}"))?;
+
+        let receipt_id =
+            prover.upload_receipt(data).await.context("Failed to upload assumption receipt")?;
+        receipt_ids.push(receipt_id);
+    }
+    Ok(receipt_ids)
 }
 
 #[cfg(test)]
@@ -517,6 +715,75 @@ mod tests {
         assert!(matches!(result, Err(StorageErr::SizeLimitExceeded(_))));
     }
 
+    #[test]
+    fn artifact_urls_from_fragment_and_config() {
+        let mirrors = vec![ArtifactMirrorConf {
+            prefix: "https://origin.com/ipfs/".into(),
+            mirrors: vec!["https://mirror-a.com/ipfs/".into(), "not a url/".into()],
+        }];
+
+        let url = url::Url::parse("https://origin.com/ipfs/cid").unwrap();
+        let urls: Vec<String> =
+            artifact_urls(url, &mirrors).iter().map(ToString::to_string).collect();
+        assert_eq!(urls, ["https://origin.com/ipfs/cid", "https://mirror-a.com/ipfs/cid"]);
+
+        let url = url::Url::parse(
+            "https://origin.com/ipfs/cid#mirror=https%3A%2F%2Fmirror-b.com%2Fcid&mirror=https%3A%2F%2Fmirror-a.com%2Fipfs%2Fcid",
+        )
+        .unwrap();
+        let urls: Vec<String> =
+            artifact_urls(url, &mirrors).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://origin.com/ipfs/cid",
+                "https://mirror-b.com/cid",
+                "https://mirror-a.com/ipfs/cid"
+            ]
+        );
+
+        let url = url::Url::parse("https://other.com/cid#section").unwrap();
+        let urls: Vec<String> =
+            artifact_urls(url, &mirrors).iter().map(ToString::to_string).collect();
+        assert_eq!(urls, ["https://other.com/cid#section"]);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn http_fetch_falls_back_to_mirror() {
+        let resp_data = vec![0x41, 0x41, 0x41, 0x41];
+        let origin = MockServer::start();
+        let origin_mock = origin.mock(|when, then| {
+            when.method(GET).path("/image");
+            then.status(404);
+        });
+        let mirror = MockServer::start();
+        let mirror_mock = mirror.mock(|when, then| {
+            when.method(GET).path("/image");
+            then.status(200).body(&resp_data);
+        });
+
+        let config = ConfigLock::default();
+        {
+            let mut config = config.load_write().unwrap();
+            config.market.max_fetch_retries = None;
+            config.market.artifact_mirrors = vec![ArtifactMirrorConf {
+                prefix: origin.base_url(),
+                mirrors: vec![mirror.base_url()],
+            }];
+        }
+
+        let handler = create_uri_handler(&origin.url("/image"), &config, false).await.unwrap();
+        assert_eq!(handler.fetch().await.unwrap(), resp_data);
+        origin_mock.assert_hits(1);
+        mirror_mock.assert_hits(1);
+
+        // The failing origin is tried after the healthy mirror
+        assert_eq!(handler.fetch().await.unwrap(), resp_data);
+        origin_mock.assert_hits(1);
+        mirror_mock.assert_hits(2);
+    }
+
     // NOTE: These are dummy values, they don't need to be real AWS keys but their presence allows
     // the default provider chain to "succeed" initially.
     const DUMMY_AWS_CREDENTIALS: [(&str, Option<&str>); 6] = [
@@ -585,4 +852,69 @@ mod tests {
         let result = handler.fetch().await;
         assert!(matches!(result, Err(StorageErr::SizeLimitExceeded(_))));
     }
+
+    #[tokio::test]
+    async fn uploads_verified_assumption_receipts() {
+        use crate::provers::{DefaultProver, ProverObj};
+        use alloy::primitives::{Address, U256};
+        use boundless_market::{
+            contracts::{Offer, Predicate, RequestId, Requirements},
+            input::GuestEnv,
+        };
+        use guest_util::{ECHO_ELF, ECHO_ID};
+
+        let prover: ProverObj = Arc::new(DefaultProver::new());
+        let image_id = Digest::from(ECHO_ID).to_string();
+        prover.upload_image(&image_id, ECHO_ELF.to_vec()).await.unwrap();
+        let input_id = prover.upload_input(b"assumption".to_vec()).await.unwrap();
+        let proof = prover.prove_and_monitor_stark(&image_id, &input_id, vec![]).await.unwrap();
+        let receipt = prover.get_receipt(&proof.id).await.unwrap().unwrap();
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/receipt");
+            then.status(200).body(bincode::serialize(&receipt).unwrap());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/invalid");
+            then.status(200).body([0x41; 4]);
+        });
+
+        let config = ConfigLock::default();
+        let input_fetcher = Arc::new(crate::input_fetcher::InputFetcher::new(config.clone(), 0));
+        let request = |path: &str| {
+            let input = GuestEnv::builder()
+                .write_slice(b"input")
+                .with_assumption(server.url(path))
+                .build_inline()
+                .unwrap();
+            crate::ProofRequest::new(
+                RequestId::new(Address::ZERO, 1),
+                Requirements::new(Digest::from(ECHO_ID), Predicate::prefix_match(vec![])),
+                "http://risczero.com/image",
+                input,
+                Offer {
+                    minPrice: U256::from(1),
+                    maxPrice: U256::from(2),
+                    biddingStart: 0,
+                    timeout: 200,
+                    lockTimeout: 100,
+                    rampUpPeriod: 1,
+                    lockStake: U256::ZERO,
+                },
+            )
+        };
+
+        // The receipt is uploaded and usable as an assumption
+        let assumptions =
+            upload_assumptions_uri(&prover, &input_fetcher, &request("/receipt"), &config)
+                .await
+                .unwrap();
+        assert_eq!(assumptions.len(), 1);
+        let input_id = prover.upload_input(b"input".to_vec()).await.unwrap();
+        prover.preflight(&image_id, &input_id, assumptions, None, "order").await.unwrap();
+
+        let invalid = request("/invalid");
+        upload_assumptions_uri(&prover, &input_fetcher, &invalid, &config).await.unwrap_err();
+    }
 }
@@ -16,7 +16,10 @@ use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use alloy::{
     network::Ethereum,
-    primitives::{utils::format_ether, Address, B256, U256},
+    primitives::{
+        utils::{format_ether, format_units},
+        Address, B256, U256,
+    },
     providers::{Provider, WalletProvider},
     sol_types::{SolStruct, SolValue},
 };
@@ -24,7 +27,9 @@ use anyhow::{anyhow, Context, Result};
 use boundless_market::{
     contracts::{
         boundless_market::{BoundlessMarketService, FulfillmentTx, MarketError, UnlockedRequest},
-        encode_seal, AssessorJournal, AssessorReceipt, Fulfillment,
+        encode_seal,
+        tx_submitter::TxSubmitter,
+        AssessorJournal, AssessorReceipt, Fulfillment,
     },
     selector::is_groth16_selector,
 };
@@ -36,12 +41,18 @@ use risc0_zkvm::{
 };
 
 use crate::{
+    chain_monitor::ChainMonitorService,
     config::ConfigLock,
     db::DbObj,
-    impl_coded_debug, now_timestamp,
+    events::{BrokerEvent, EventBus},
+    impl_coded_debug,
+    leadership::LeadershipObj,
+    metrics::MetricsObj,
+    now_timestamp,
     provers::ProverObj,
+    self_throttle::{PipelineOutcome, SelfThrottleObj},
     task::{RetryRes, RetryTask, SupervisorErr},
-    Batch, FulfillmentType, Order,
+    utils, Batch, FulfillmentIntent, FulfillmentType, GasTxKind, Order, OrderGasRecord,
 };
 use thiserror::Error;
 
@@ -49,6 +60,9 @@ use crate::errors::CodedError;
 
 use tokio_util::sync::CancellationToken;
 
+/// Interval to check whether a gas spike has cleared while deferring a batch.
+const GAS_SPIKE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Error)]
 pub enum SubmitterErr {
     #[error("{code} Batch submission failed: {0:?}", code = self.code())]
@@ -69,6 +83,12 @@ pub enum SubmitterErr {
     #[error("{code} Market error: {0}", code = self.code())]
     MarketError(#[from] MarketError),
 
+    #[error("{code} Gas cost {0} of the batch would exceed its gas budget {1}", code = self.code())]
+    GasBudgetExceeded(U256, U256),
+
+    #[error("{code} Requests already being fulfilled: {0:?}", code = self.code())]
+    FulfillmentInFlight(Vec<String>),
+
     #[error("{code} Unexpected error: {0:?}", code = self.code())]
     UnexpectedErr(#[from] anyhow::Error),
 }
@@ -85,6 +105,8 @@ impl CodedError for SubmitterErr {
             SubmitterErr::BatchSubmissionFailed(_) => "[B-SUB-004]",
             SubmitterErr::BatchSubmissionFailedTimeouts(_) => "[B-SUB-003]",
             SubmitterErr::TxnConfirmationError(_) => "[B-SUB-006]",
+            SubmitterErr::GasBudgetExceeded(_, _) => "[B-SUB-007]",
+            SubmitterErr::FulfillmentInFlight(_) => "[B-SUB-008]",
         }
     }
 }
@@ -99,12 +121,20 @@ pub struct Submitter<P> {
     set_builder_img_id: Digest,
     prover_address: Address,
     config: ConfigLock,
+    events: EventBus,
+    metrics: MetricsObj,
+    self_throttle: SelfThrottleObj,
+    stake_token_decimals: u8,
+    chain_monitor: Option<Arc<ChainMonitorService<P>>>,
+    leadership: LeadershipObj,
 }
 
 impl<P> Submitter<P>
 where
     P: Provider<Ethereum> + WalletProvider + 'static + Clone,
 {
+    /// Fulfillments are sent by the provider's signer, and paid to `prover_address`, the prover
+    /// the orders were locked for, e.g. a delegated prover.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         db: DbObj,
@@ -114,21 +144,19 @@ where
         set_verifier_addr: Address,
         market_addr: Address,
         set_builder_img_id: Digest,
+        prover_address: Address,
     ) -> Result<Self> {
         let txn_timeout_opt = {
             let config = config.lock_all().context("Failed to read config")?;
             config.batcher.txn_timeout
         };
 
-        let mut market = BoundlessMarketService::new(
+        let market = BoundlessMarketService::new(
             market_addr,
             provider.clone(),
             provider.default_signer_address(),
-        );
-        if let Some(txn_timeout) = txn_timeout_opt {
-            tracing::debug!("Setting market timeout to {}", txn_timeout);
-            market = market.with_timeout(Duration::from_secs(txn_timeout));
-        }
+        )
+        .with_tx_submitter(utils::tx_submitter(&config)?);
 
         let mut set_verifier = SetVerifierService::new(
             set_verifier_addr,
@@ -140,8 +168,6 @@ where
             set_verifier = set_verifier.with_timeout(Duration::from_secs(txn_timeout));
         }
 
-        let prover_address = provider.default_signer_address();
-
         Ok(Self {
             db,
             prover,
@@ -151,9 +177,112 @@ where
             set_builder_img_id,
             prover_address,
             config,
+            events: Default::default(),
+            metrics: Default::default(),
+            self_throttle: Default::default(),
+            stake_token_decimals: 18,
+            chain_monitor: None,
+            leadership: Default::default(),
         })
     }
 
+    /// Send fulfillment transactions with the given submitter, e.g. shared with the order monitor.
+    pub(crate) fn with_tx_submitter(self, tx_submitter: TxSubmitter) -> Self {
+        Self { market: self.market.with_tx_submitter(tx_submitter), ..self }
+    }
+
+    /// Publish fulfilled orders to the given event bus.
+    pub(crate) fn with_events(self, events: EventBus) -> Self {
+        Self { events, ..self }
+    }
+
+    /// Record the gas used and saved by fulfilled batches in the given metrics.
+    pub(crate) fn with_metrics(self, metrics: MetricsObj) -> Self {
+        Self { metrics, ..self }
+    }
+
+    /// Record fulfilled orders in the given self-throttle.
+    pub(crate) fn with_self_throttle(self, self_throttle: SelfThrottleObj) -> Self {
+        Self { self_throttle, ..self }
+    }
+
+    /// Format stake rewards with the decimals of the stake token.
+    pub(crate) fn with_stake_token_decimals(self, stake_token_decimals: u8) -> Self {
+        Self { stake_token_decimals, ..self }
+    }
+
+    /// Defer batches that are not close to their deadline during gas spikes reported by the
+    /// given chain monitor.
+    pub(crate) fn with_chain_monitor(self, chain_monitor: Arc<ChainMonitorService<P>>) -> Self {
+        Self { chain_monitor: Some(chain_monitor), ..self }
+    }
+
+    /// Submit no batches while on standby, until this replica acquires the leadership lease.
+    pub(crate) fn with_leadership(self, leadership: LeadershipObj) -> Self {
+        Self { leadership, ..self }
+    }
+
+    /// Hold the batch while a gas spike is ongoing, until the spike clears or the batch
+    /// deadline is within the submit deadline margin.
+    async fn wait_for_gas_spike(&self, batch_id: usize, batch: &Batch) -> Result<()> {
+        let Some(chain_monitor) = &self.chain_monitor else {
+            return Ok(());
+        };
+        let Some(margin_secs) = ({
+            let config = self.config.lock_all().context("Failed to read config")?;
+            config.market.gas_spike.as_ref().map(|conf| conf.submit_deadline_margin_secs)
+        }) else {
+            return Ok(());
+        };
+        let Some(deadline) = batch.deadline else {
+            return Ok(());
+        };
+        let mut deferred = false;
+        while now_timestamp() + margin_secs < deadline {
+            let Some(spike) = chain_monitor.current_gas_spike().await? else {
+                break;
+            };
+            if !deferred {
+                tracing::info!(
+                    "Deferring batch {batch_id} with deadline {deadline} during gas spike: gas price up {}% over the last {}s",
+                    spike.pct,
+                    spike.window.as_secs()
+                );
+                deferred = true;
+            }
+            tokio::time::sleep(GAS_SPIKE_POLL_INTERVAL).await;
+        }
+        Ok(())
+    }
+
+    /// Wait for the configured confirmations of the fulfillment transaction of the batch,
+    /// included in the given block.
+    ///
+    /// Failures are logged, as the orders are fulfilled either way.
+    async fn wait_for_fulfillment_confirmations(&self, batch_id: usize, block_number: u64) {
+        let Some(chain_monitor) = &self.chain_monitor else {
+            return;
+        };
+        let confirmations = match self.config.lock_all() {
+            Ok(config) => config.market.confirmations.as_ref().map_or(1, |conf| conf.fulfillment),
+            Err(err) => {
+                tracing::warn!("Failed to read config for fulfillment confirmations: {err}");
+                return;
+            }
+        };
+        if confirmations <= 1 {
+            return;
+        }
+        tracing::debug!(
+            "Waiting for {confirmations} confirmations of batch {batch_id} fulfilled in block {block_number}"
+        );
+        if let Err(err) = chain_monitor.wait_for_confirmations(block_number, confirmations).await {
+            tracing::warn!(
+                "Failed to wait for confirmations of the fulfillment of batch {batch_id}: {err:?}"
+            );
+        }
+    }
+
     async fn fetch_encode_g16(&self, g16_proof_id: &str) -> Result<Vec<u8>> {
         let groth16_receipt = self
             .prover
@@ -251,6 +380,7 @@ where
         struct OrderPrice {
             price: U256,
             stake_reward: U256,
+            fulfillment_type: FulfillmentType,
         }
         let mut order_prices: HashMap<&str, OrderPrice> = HashMap::new();
         let mut fulfillment_to_order_id: HashMap<U256, &str> = HashMap::new();
@@ -278,7 +408,10 @@ where
                     stake_reward = order_request.offer.stake_reward_if_locked_and_not_fulfilled();
                 }
 
-                order_prices.insert(order_id, OrderPrice { price: lock_price, stake_reward });
+                order_prices.insert(
+                    order_id,
+                    OrderPrice { price: lock_price, stake_reward, fulfillment_type },
+                );
 
                 let order_journal = self
                     .prover
@@ -379,11 +512,121 @@ where
             callbacks: assessor_journal.callbacks,
         };
 
-        let (single_txn_fulfill, withdraw) = {
+        let (
+            single_txn_fulfill,
+            withdraw,
+            fulfill_gas_estimate,
+            gas_budget,
+            fulfillment_intent_stale_secs,
+        ) = {
             let config = self.config.lock_all().context("Failed to read config")?;
-            (config.batcher.single_txn_fulfill, config.batcher.withdraw)
+            let gas_budget = config.market.gas_budget_percent.map(|_| {
+                fulfillment_to_order_id.values().fold(U256::ZERO, |budget, order_id| {
+                    let price = order_prices.get(order_id).map_or(U256::ZERO, |p| p.price);
+                    budget + config.market.gas_budget(price).unwrap_or_default()
+                })
+            });
+            (
+                config.batcher.single_txn_fulfill,
+                config.batcher.withdraw,
+                config.market.fulfill_gas_estimate,
+                gas_budget,
+                config.batcher.fulfillment_intent_stale_secs,
+            )
         };
 
+        if let Some(budget) = gas_budget {
+            let order_ids: Vec<&str> =
+                fulfillments.iter().map(|f| *fulfillment_to_order_id.get(&f.id).unwrap()).collect();
+            let gas_price = self
+                .market
+                .instance()
+                .provider()
+                .get_gas_price()
+                .await
+                .context("Failed to get gas price")?;
+            let mut cost = U256::from(gas_price)
+                * U256::from(fulfill_gas_estimate)
+                * U256::from(order_ids.len());
+            for order_id in order_ids.iter() {
+                let records = self
+                    .db
+                    .get_order_gas_records(order_id)
+                    .await
+                    .context("Failed to get order gas records")?;
+                cost = records.iter().fold(cost, |cost, record| cost + record.gas_cost);
+            }
+            if cost > budget {
+                // Orders locked by the broker are fulfilled regardless, as abandoning them would
+                // forfeit the lock stake.
+                let locked = order_ids.iter().any(|order_id| {
+                    order_prices.get(order_id).is_some_and(|order_price| {
+                        order_price.fulfillment_type == FulfillmentType::LockAndFulfill
+                    })
+                });
+                if locked {
+                    tracing::warn!(
+                        "Batch {batch_id} gas cost {cost} exceeds its gas budget {budget}, fulfilling locked orders anyway"
+                    );
+                } else {
+                    tracing::warn!(
+                        "Batch {batch_id} gas cost {cost} exceeds its gas budget {budget}, abandoning orders: {order_ids:?}"
+                    );
+                    for order_id in order_ids.iter() {
+                        if let Err(db_err) =
+                            self.db.set_order_failure(order_id, "Gas budget exceeded").await
+                        {
+                            tracing::error!(
+                                "Failed to set order failure during proof submission: {order_id} {db_err:?}"
+                            );
+                        }
+                    }
+                    return Err(SubmitterErr::GasBudgetExceeded(cost, budget));
+                }
+            }
+        }
+
+        // Requests fulfilled on chain since their intents were recorded, e.g. before a restart,
+        // are completed without sending their fulfillments again.
+        let fulfilled = self
+            .reconcile_fulfillment_intents(&fulfillments)
+            .await
+            .context("Failed to reconcile fulfillment intents")?;
+        if !fulfilled.is_empty() {
+            fulfillments.retain(|fulfillment| !fulfilled.contains(&fulfillment.id));
+            requests_to_price.retain(|unlocked| !fulfilled.contains(&unlocked.request.id));
+            for request_id in fulfilled.iter() {
+                let order_id = fulfillment_to_order_id.get(request_id).unwrap();
+                tracing::info!("Order {order_id} already fulfilled on chain, skipping submission");
+                if let Err(db_err) = self.db.set_order_complete(order_id).await {
+                    tracing::error!(
+                        "Failed to set order complete during proof submission: {order_id} {db_err:?}"
+                    );
+                }
+            }
+            if fulfillments.is_empty() {
+                return Ok(());
+            }
+        }
+
+        let stale_before = now_timestamp().saturating_sub(fulfillment_intent_stale_secs);
+        let intents: Vec<FulfillmentIntent> = fulfillments
+            .iter()
+            .map(|f| {
+                FulfillmentIntent::new(f.id, fulfillment_to_order_id.get(&f.id).unwrap(), batch_id)
+            })
+            .collect();
+        if !self
+            .db
+            .claim_fulfillment_intents(&intents, stale_before)
+            .await
+            .context("Failed to record fulfillment intents")?
+        {
+            return Err(SubmitterErr::FulfillmentInFlight(
+                fulfillments.iter().map(|f| format!("0x{:x}", f.id)).collect(),
+            ));
+        }
+
         let mut fulfillment_tx = FulfillmentTx::new(fulfillments.clone(), assessor_receipt)
             .with_withdraw(withdraw)
             .with_unlocked_requests(requests_to_price);
@@ -408,6 +651,7 @@ where
                         .map(|f| *fulfillment_to_order_id.get(&f.id).unwrap())
                         .collect();
                     tracing::warn!("Failed to submit app merkle root for orders: {order_ids:?}");
+                    self.release_fulfillment_intents(batch_id, &fulfillments).await;
 
                     // Map the error from the R0 Contracts crate crate to an error type from BoundlessMarket
                     if err.to_string().contains("failed to confirm tx") {
@@ -433,11 +677,77 @@ where
             }
         };
 
-        if let Err(err) = self.market.fulfill(fulfillment_tx).await {
-            let order_ids: Vec<&str> =
-                fulfillments.iter().map(|f| *fulfillment_to_order_id.get(&f.id).unwrap()).collect();
-            tracing::warn!("Failed to fulfill batch for orders: {order_ids:?}");
-            self.handle_fulfillment_error(err, batch_id, &fulfillments, &order_ids).await?;
+        match self.market.fulfill_with_receipt(fulfillment_tx).await {
+            Ok(receipt) => {
+                let num_orders = fulfillments.len();
+                let now = now_timestamp();
+                for _ in 0..num_orders {
+                    self.self_throttle.record(PipelineOutcome::Fulfilled, now);
+                }
+                let savings = batch_gas_savings(num_orders, fulfill_gas_estimate, receipt.gas_used);
+                self.metrics.record_batch_fulfilled(receipt.gas_used, savings);
+                tracing::info!(
+                    "Batch {batch_id} fulfilled {num_orders} orders using {} gas ({} per order), saving {savings} gas vs. individual fulfillment",
+                    receipt.gas_used,
+                    receipt.gas_used / num_orders as u64,
+                );
+                let order_ids: Vec<&str> = fulfillments
+                    .iter()
+                    .map(|f| *fulfillment_to_order_id.get(&f.id).unwrap())
+                    .collect();
+                for order_id in &order_ids {
+                    self.events
+                        .publish(BrokerEvent::OrderFulfilled { order_id: order_id.to_string() });
+                }
+                if let Err(db_err) = self
+                    .db
+                    .set_fulfillment_gas(
+                        &order_ids,
+                        fulfill_gas_estimate,
+                        receipt.gas_used / num_orders as u64,
+                    )
+                    .await
+                {
+                    tracing::error!(
+                        "Failed to record fulfillment gas for batch {batch_id}: {db_err:?}"
+                    );
+                }
+                let gas_records: Vec<OrderGasRecord> = order_ids
+                    .iter()
+                    .map(|order_id| {
+                        OrderGasRecord::new(order_id, GasTxKind::Fulfill, &receipt, num_orders)
+                    })
+                    .collect();
+                if let Err(db_err) = self.db.add_order_gas_records(&gas_records).await {
+                    tracing::error!(
+                        "Failed to record fulfillment gas spending for batch {batch_id}: {db_err:?}"
+                    );
+                }
+                let request_ids: Vec<U256> = fulfillments.iter().map(|f| f.id).collect();
+                if let Err(db_err) =
+                    self.db.set_fulfillment_intents_tx(&request_ids, receipt.transaction_hash).await
+                {
+                    tracing::error!(
+                        "Failed to record fulfillment transaction of batch {batch_id}: {db_err:?}"
+                    );
+                }
+                if let Some(block_number) = receipt.block_number {
+                    self.wait_for_fulfillment_confirmations(batch_id, block_number).await;
+                }
+            }
+            Err(err) => {
+                let order_ids: Vec<&str> = fulfillments
+                    .iter()
+                    .map(|f| *fulfillment_to_order_id.get(&f.id).unwrap())
+                    .collect();
+                tracing::warn!("Failed to fulfill batch for orders: {order_ids:?}");
+                // The transaction may still be confirmed, in which case the intents block sending
+                // it again until they are reconciled with the chain
+                if !matches!(err, MarketError::TxnConfirmationError(_)) {
+                    self.release_fulfillment_intents(batch_id, &fulfillments).await;
+                }
+                self.handle_fulfillment_error(err, batch_id, &fulfillments, &order_ids).await?;
+            }
         }
 
         for fulfillment in fulfillments.iter() {
@@ -449,20 +759,60 @@ where
                 );
                 continue;
             }
-            let order_price = order_prices
-                .get(order_id)
-                .unwrap_or(&OrderPrice { price: U256::ZERO, stake_reward: U256::ZERO });
+            let order_price = order_prices.get(order_id).unwrap_or(&OrderPrice {
+                price: U256::ZERO,
+                stake_reward: U256::ZERO,
+                fulfillment_type: FulfillmentType::LockAndFulfill,
+            });
             tracing::info!(
                 "✨ Completed order: {:x} fee: {} stake_reward: {} ✨",
                 fulfillment.id,
                 format_ether(order_price.price),
-                format_ether(order_price.stake_reward)
+                format_units(order_price.stake_reward, self.stake_token_decimals)
+                    .unwrap_or_default()
             );
         }
 
         Ok(())
     }
 
+    /// Returns the requests with recorded fulfillment intents that are fulfilled on chain,
+    /// removing their intents.
+    async fn reconcile_fulfillment_intents(
+        &self,
+        fulfillments: &[Fulfillment],
+    ) -> Result<Vec<U256>> {
+        let request_ids: Vec<U256> = fulfillments.iter().map(|f| f.id).collect();
+        let intents = self.db.get_fulfillment_intents(&request_ids).await?;
+        let mut fulfilled = vec![];
+        for intent in intents {
+            if self.market.is_fulfilled(intent.request_id).await? {
+                fulfilled.push(intent.request_id);
+            } else {
+                tracing::debug!(
+                    "Request 0x{:x} of batch {} has a pending fulfillment intent, tx: {:?}",
+                    intent.request_id,
+                    intent.batch_id,
+                    intent.tx_hash
+                );
+            }
+        }
+        if !fulfilled.is_empty() {
+            self.db.release_fulfillment_intents(&fulfilled).await?;
+        }
+        Ok(fulfilled)
+    }
+
+    /// Remove the intents to fulfill the requests after failing to send their fulfillment.
+    async fn release_fulfillment_intents(&self, batch_id: usize, fulfillments: &[Fulfillment]) {
+        let request_ids: Vec<U256> = fulfillments.iter().map(|f| f.id).collect();
+        if let Err(db_err) = self.db.release_fulfillment_intents(&request_ids).await {
+            tracing::error!(
+                "Failed to release fulfillment intents of batch {batch_id}: {db_err:?}"
+            );
+        }
+    }
+
     async fn handle_expired_requests_error(
         &self,
         batch_id: usize,
@@ -517,6 +867,8 @@ where
             return Ok(());
         };
 
+        self.wait_for_gas_spike(batch_id, &batch).await?;
+
         let max_batch_submission_attempts = self
             .config
             .lock_all()
@@ -544,7 +896,18 @@ where
                         attempt + 1,
                         max_batch_submission_attempts,
                     );
+                    // Another submission of the requests may still be confirmed, the batch is
+                    // submitted again once its fulfillment intents are reconciled or stale
+                    if let SubmitterErr::FulfillmentInFlight(_) = err {
+                        tracing::info!("Deferring batch {batch_id}: {err}");
+                        return Ok(());
+                    }
+                    // The orders were abandoned, retrying would not change the outcome
+                    let abandoned = matches!(err, SubmitterErr::GasBudgetExceeded(_, _));
                     errors.push(err);
+                    if abandoned {
+                        break;
+                    }
                 }
             }
         }
@@ -578,6 +941,13 @@ where
                     break;
                 }
 
+                // Standby replicas leave the fulfillment of the locked orders to the leader
+                if !obj_clone.leadership.is_leader() {
+                    tracing::trace!("On standby, not submitting batches until leader");
+                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    continue;
+                }
+
                 // Process batch without interruption
                 let result = obj_clone.process_next_batch().await;
                 if let Err(err) = result {
@@ -602,6 +972,16 @@ where
     }
 }
 
+/// Gas saved by fulfilling `num_orders` in a single transaction, compared to fulfilling each
+/// order individually at the configured `fulfill_gas_estimate`.
+///
+/// Negative if the batch used more gas than the estimate. When the merkle root is submitted in a
+/// separate transaction, its gas is not included in `gas_used`.
+fn batch_gas_savings(num_orders: usize, fulfill_gas_estimate: u64, gas_used: u64) -> i64 {
+    let individual_gas = (num_orders as u64).saturating_mul(fulfill_gas_estimate);
+    (individual_gas as i64).saturating_sub(gas_used as i64)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -621,8 +1001,9 @@ mod tests {
     use boundless_assessor::{AssessorInput, Fulfillment};
     use boundless_market::{
         contracts::{
-            hit_points::default_allowance, Offer, Predicate, PredicateType, ProofRequest,
-            RequestId, RequestInput, RequestInputType, Requirements,
+            hit_points::{default_allowance, HitPointsService},
+            Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput,
+            RequestInputType, Requirements,
         },
         input::GuestEnv,
     };
@@ -636,13 +1017,18 @@ mod tests {
     use risc0_zkvm::sha::Digest;
     use tracing_test::traced_test;
 
+    /// Build a submitter and a complete batch of an order locked for the wallet, or for a
+    /// delegated prover.
     async fn build_submitter_and_batch(
         config: ConfigLock,
+        delegated: bool,
     ) -> (AnvilInstance, Submitter<impl Provider + WalletProvider + Clone + 'static>, DbObj, usize)
     {
         let anvil = Anvil::new().spawn();
         let signer: PrivateKeySigner = anvil.keys()[0].clone().into();
         let customer_signer: PrivateKeySigner = anvil.keys()[1].clone().into();
+        let delegated_prover: Option<PrivateKeySigner> =
+            delegated.then(|| anvil.keys()[2].clone().into());
         let prover_addr = signer.address();
         let customer_addr = customer_signer.address();
         tracing::info!("prover: {prover_addr} customer: {customer_addr}");
@@ -688,6 +1074,30 @@ mod tests {
         let market = BoundlessMarketService::new(market_address, provider.clone(), prover_addr);
         market.deposit_stake_with_permit(default_allowance(), &signer).await.unwrap();
 
+        // The prover the order is locked for, and the assessor receipt is paid to
+        let locked_prover_addr = match &delegated_prover {
+            Some(delegated_signer) => {
+                let delegated_addr = delegated_signer.address();
+                HitPointsService::new(hit_points, provider.clone(), prover_addr)
+                    .mint(delegated_addr, default_allowance())
+                    .await
+                    .unwrap();
+                let delegated_provider = Arc::new(
+                    ProviderBuilder::new()
+                        .wallet(EthereumWallet::from(delegated_signer.clone()))
+                        .connect(&anvil.endpoint())
+                        .await
+                        .unwrap(),
+                );
+                BoundlessMarketService::new(market_address, delegated_provider, delegated_addr)
+                    .deposit_stake_with_permit(default_allowance(), delegated_signer)
+                    .await
+                    .unwrap();
+                delegated_addr
+            }
+            None => prover_addr,
+        };
+
         let market_customer =
             BoundlessMarketService::new(market_address, customer_provider.clone(), customer_addr);
         market_customer.deposit(U256::from(10000000000u64)).await.unwrap();
@@ -748,7 +1158,7 @@ mod tests {
                 signature: client_sig.into(),
                 journal: echo_receipt.journal.bytes.clone(),
             }],
-            prover_address: prover_addr,
+            prover_address: locked_prover_addr,
         };
         let assessor_stdin = GuestEnv::builder().write_frame(&assessor_input.encode()).stdin;
 
@@ -807,6 +1217,7 @@ mod tests {
             request: order_request,
             image_id: Some(echo_id_str.clone()),
             input_id: Some(input_id.clone()),
+            assumption_ids: None,
             proof_id: Some(echo_proof.id.clone()),
             compressed_proof_id: None,
             expire_timestamp: Some(now_timestamp() + 100),
@@ -814,6 +1225,8 @@ mod tests {
             lock_price: Some(U256::ZERO),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            skip_reason: None,
+            pricing_checkpoint: None,
             boundless_market_address: market_address,
             chain_id,
             total_cycles: None,
@@ -829,6 +1242,7 @@ mod tests {
             orders: vec![order_id],
             fees: U256::ZERO,
             start_time: Utc::now(),
+            window_start: None,
             deadline: Some(order.request.offer.biddingStart + order.request.offer.timeout as u64),
             error_msg: None,
             aggregation_state: Some(AggregationState {
@@ -843,7 +1257,27 @@ mod tests {
         };
         db.add_batch(batch_id, batch).await.unwrap();
 
-        market.lock_request(&order.request, client_sig.to_vec(), None).await.unwrap();
+        match &delegated_prover {
+            Some(delegated_signer) => {
+                let prover_sig = order
+                    .request
+                    .sign_lock_request(delegated_signer, market_address, chain_id)
+                    .await
+                    .unwrap();
+                market
+                    .lock_request_with_signature(
+                        &order.request,
+                        client_sig.to_vec(),
+                        prover_sig.as_bytes(),
+                        None,
+                    )
+                    .await
+                    .unwrap();
+            }
+            None => {
+                market.lock_request(&order.request, client_sig.to_vec(), None).await.unwrap();
+            }
+        }
 
         let submitter = Submitter::new(
             db.clone(),
@@ -853,6 +1287,7 @@ mod tests {
             set_verifier,
             market_address,
             set_builder_id,
+            locked_prover_addr,
         )
         .unwrap();
 
@@ -872,7 +1307,7 @@ mod tests {
     #[traced_test]
     async fn submit_batch() {
         let config = ConfigLock::default();
-        let (_anvil, submitter, db, batch_id) = build_submitter_and_batch(config).await;
+        let (_anvil, submitter, db, batch_id) = build_submitter_and_batch(config, false).await;
         process_next_batch(submitter, db, batch_id).await;
     }
 
@@ -881,15 +1316,37 @@ mod tests {
     async fn submit_batch_merged_txn() {
         let config = ConfigLock::default();
         config.load_write().as_mut().unwrap().batcher.single_txn_fulfill = true;
-        let (_anvil, submitter, db, batch_id) = build_submitter_and_batch(config).await;
+        let (_anvil, submitter, db, batch_id) = build_submitter_and_batch(config, false).await;
         process_next_batch(submitter, db, batch_id).await;
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn submit_batch_delegated_prover() {
+        let config = ConfigLock::default();
+        let (anvil, submitter, db, batch_id) = build_submitter_and_batch(config, true).await;
+        let delegated_addr = PrivateKeySigner::from(anvil.keys()[2].clone()).address();
+        assert_eq!(submitter.prover_address, delegated_addr);
+        let order_id = db.get_batch(batch_id).await.unwrap().orders[0].clone();
+        let request = db.get_order(&order_id).await.unwrap().unwrap().request;
+
+        submitter.process_next_batch().await.unwrap();
+        assert_eq!(db.get_batch(batch_id).await.unwrap().status, BatchStatus::Submitted);
+
+        // The delegated prover is paid, and its stake is returned
+        assert!(submitter.market.is_fulfilled(request.id).await.unwrap());
+        assert!(submitter.market.balance_of(delegated_addr).await.unwrap() > U256::ZERO);
+        assert_eq!(
+            submitter.market.balance_of_stake(delegated_addr).await.unwrap(),
+            default_allowance()
+        );
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn submit_batch_retry_max_attempts() {
         let config = ConfigLock::default();
-        let (anvil, submitter, _db, _batch_id) = build_submitter_and_batch(config).await;
+        let (anvil, submitter, _db, _batch_id) = build_submitter_and_batch(config, false).await;
 
         drop(anvil); // drop anvil to simluate an RPC fault
 
@@ -898,4 +1355,31 @@ mod tests {
         assert!(logs_contain("reached max submission attempts"));
         assert!(matches!(res, Err(SubmitterErr::BatchSubmissionFailed(_))));
     }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn submit_batch_fulfillment_in_flight() {
+        let config = ConfigLock::default();
+        let (_anvil, submitter, db, batch_id) = build_submitter_and_batch(config, false).await;
+        let order_id = db.get_batch(batch_id).await.unwrap().orders[0].clone();
+        let request_id = db.get_order(&order_id).await.unwrap().unwrap().request.id;
+
+        // A recent intent, e.g. recorded before a restart, defers the batch
+        let intent = FulfillmentIntent::new(request_id, &order_id, batch_id);
+        assert!(db.claim_fulfillment_intents(&[intent], 0).await.unwrap());
+        submitter.process_next_batch().await.unwrap();
+        assert!(logs_contain("Deferring batch"));
+        assert_eq!(db.get_batch(batch_id).await.unwrap().status, BatchStatus::Complete);
+
+        db.release_fulfillment_intents(&[request_id]).await.unwrap();
+        process_next_batch(submitter, db.clone(), batch_id).await;
+        let intents = db.get_fulfillment_intents(&[request_id]).await.unwrap();
+        assert!(intents[0].tx_hash.is_some());
+    }
+
+    #[test]
+    fn batch_gas_savings_vs_individual() {
+        assert_eq!(batch_gas_savings(3, 750_000, 1_000_000), 1_250_000);
+        assert_eq!(batch_gas_savings(1, 750_000, 800_000), -50_000);
+    }
 }
@@ -12,14 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context, Result as AnyhowRes};
 use thiserror::Error;
 use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
 
-use crate::{config::ConfigLock, errors::CodedError};
+use crate::{
+    config::{ConfigLock, RestartPolicyConf},
+    errors::CodedError,
+};
 
 #[derive(Error, Debug)]
 pub enum SupervisorErr<E: CodedError> {
@@ -33,6 +42,38 @@ pub enum SupervisorErr<E: CodedError> {
 
 const FAULT_CODE: &str = "[B-SUP-FAULT]";
 
+/// Restarts of each supervised task by name, shared by all supervisors in the process.
+static TASK_RESTARTS: LazyLock<Mutex<BTreeMap<String, u64>>> = LazyLock::new(Default::default);
+
+/// Restarts of each supervised task since the broker started, by task name.
+pub(crate) fn task_restarts() -> BTreeMap<String, u64> {
+    TASK_RESTARTS.lock().unwrap().clone()
+}
+
+fn record_restart(name: &str) {
+    *TASK_RESTARTS.lock().unwrap().entry(name.to_string()).or_default() += 1;
+}
+
+/// Name of a task in logs, metrics and the `[supervisor.tasks]` config, from its type, e.g.
+/// `order_picker` for `OrderPicker<P>`.
+fn task_name<T>() -> String {
+    let name = std::any::type_name::<T>();
+    let name = name.split('<').next().unwrap_or(name);
+    let name = name.rsplit("::").next().unwrap_or(name);
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (idx, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if idx > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
 impl<E: CodedError> CodedError for SupervisorErr<E> {
     fn code(&self) -> &str {
         match self {
@@ -63,6 +104,10 @@ pub(crate) struct RetryPolicy {
     pub max_delay: Duration,
     /// Duration after which to reset the retry counter if a task runs successfully
     pub reset_after: Option<Duration>,
+    /// Maximum restarts within `restart_window`, after which the supervisor fails
+    pub max_restarts: Option<u32>,
+    /// Window over which restarts are counted for `max_restarts`
+    pub restart_window: Duration,
     pub(crate) critical: bool,
 }
 
@@ -74,6 +119,8 @@ impl Default for RetryPolicy {
             max_delay: std::time::Duration::from_secs(60),
             // Reset the backoff after 5 minutes of running without a failure.
             reset_after: Some(std::time::Duration::from_secs(60 * 5)),
+            max_restarts: None,
+            restart_window: std::time::Duration::from_secs(60 * 10),
             critical: false,
         }
     }
@@ -85,14 +132,32 @@ impl RetryPolicy {
         backoff_multiplier: 1.5,
         max_delay: std::time::Duration::from_secs(2),
         reset_after: Some(std::time::Duration::from_secs(60)),
+        max_restarts: None,
+        restart_window: std::time::Duration::from_secs(60 * 10),
         critical: true,
     };
+
+    /// Apply the overrides of the `[supervisor]` config.
+    fn with_conf(self, conf: &RestartPolicyConf) -> Self {
+        Self {
+            delay: conf.initial_delay_ms.map_or(self.delay, Duration::from_millis),
+            backoff_multiplier: conf.backoff_multiplier.unwrap_or(self.backoff_multiplier),
+            max_delay: conf.max_delay_ms.map_or(self.max_delay, Duration::from_millis),
+            max_restarts: conf.max_restarts.or(self.max_restarts),
+            restart_window: conf
+                .restart_window_secs
+                .map_or(self.restart_window, Duration::from_secs),
+            ..self
+        }
+    }
 }
 
 /// Supervisor for managing and monitoring tasks with retry capabilities
 pub(crate) struct Supervisor<T: RetryTask> {
     /// The task to be supervised
     task: Arc<T>,
+    /// Name of the task, see [task_name]
+    name: String,
     /// Configuration for retry behavior
     retry_policy: RetryPolicy,
     config: ConfigLock,
@@ -107,7 +172,13 @@ where
 {
     /// Create a new supervisor with a single task
     pub fn new(task: Arc<T>, config: ConfigLock, cancel_token: CancellationToken) -> Self {
-        Self { task, retry_policy: RetryPolicy::default(), config, cancel_token }
+        Self {
+            task,
+            name: task_name::<T>(),
+            retry_policy: RetryPolicy::default(),
+            config,
+            cancel_token,
+        }
     }
 
     /// Configure the retry policy
@@ -116,39 +187,55 @@ where
         self
     }
 
+    /// Retry policy of the task, with the overrides of the `[supervisor]` config for all tasks and
+    /// then for this task.
+    fn effective_retry_policy(&self) -> AnyhowRes<RetryPolicy> {
+        let config = self.config.lock_all().context("Failed to read config")?;
+        let mut policy = self.retry_policy.clone().with_conf(&config.supervisor.restart);
+        if let Some(conf) = config.supervisor.tasks.get(&self.name) {
+            policy = policy.with_conf(conf);
+        }
+        Ok(policy)
+    }
+
     /// Run the supervisor, monitoring tasks and handling retries
     pub async fn spawn(self) -> AnyhowRes<()> {
         let mut tasks = JoinSet::new();
         let mut retry_count = 0;
-        let mut current_delay = self.retry_policy.delay;
+        let mut current_delay = self.effective_retry_policy()?.delay;
         let mut last_spawn_time = std::time::Instant::now();
+        // Times of the restarts within the restart window
+        let mut restarts: VecDeque<Instant> = VecDeque::new();
 
         // Spawn initial task
-        tracing::debug!("Spawning task");
+        tracing::debug!(task = %self.name, "Spawning task");
         tasks.spawn(self.task.spawn(self.cancel_token.clone()));
 
         while let Some(res) = tasks.join_next().await {
+            // Read on each exit, so that changes to the config apply without a restart
+            let retry_policy = self.effective_retry_policy()?;
             // Check if we should reset the retry counter based on how long the task ran
-            if let Some(reset_duration) = self.retry_policy.reset_after {
+            if let Some(reset_duration) = retry_policy.reset_after {
                 let task_duration = last_spawn_time.elapsed();
                 if task_duration >= reset_duration && retry_count > 0 {
                     tracing::info!(
+                        task = %self.name,
                         "Task ran successfully for {:?}, resetting retry counter from {}",
                         task_duration,
                         retry_count
                     );
                     retry_count = 0;
-                    current_delay = self.retry_policy.delay; // Reset delay to initial value
+                    current_delay = retry_policy.delay; // Reset delay to initial value
                 }
             }
             match res {
                 Ok(task_res) => match task_res {
                     Ok(()) => {
-                        tracing::debug!("Task exited cleanly");
+                        tracing::debug!(task = %self.name, "Task exited cleanly");
                     }
                     Err(ref supervisor_err) => match supervisor_err {
                         SupervisorErr::Recover(ref _err) => {
-                            if self.retry_policy.critical {
+                            if retry_policy.critical {
                                 let max_retries = {
                                     let config =
                                         self.config.lock_all().context("Failed to read config")?;
@@ -161,6 +248,7 @@ where
                                         // We manually log the fault code rather than rendering the SupervisorErr::Recover
                                         // code so that we indicate we are now in a hard fault state after exhausting retries.
                                         tracing::error!(
+                                            task = %self.name,
                                             "{} Exceeded maximum retries ({max}) for task",
                                             FAULT_CODE
                                         );
@@ -169,12 +257,39 @@ where
                                 }
                             }
 
+                            // Escalate to a hard fault if the task keeps failing within the window
+                            let now = Instant::now();
+                            while restarts.front().is_some_and(|restart| {
+                                now.duration_since(*restart) > retry_policy.restart_window
+                            }) {
+                                restarts.pop_front();
+                            }
+                            if let Some(max) = retry_policy.max_restarts {
+                                if restarts.len() >= max as usize {
+                                    tracing::error!(
+                                        task = %self.name,
+                                        restarts = restarts.len(),
+                                        "{} Exceeded maximum restarts ({max}) within {:?}: {}",
+                                        FAULT_CODE,
+                                        retry_policy.restart_window,
+                                        supervisor_err
+                                    );
+                                    anyhow::bail!(
+                                        "Exceeded maximum restarts for task {}",
+                                        self.name
+                                    );
+                                }
+                            }
+                            restarts.push_back(now);
+                            record_restart(&self.name);
+
                             tracing::warn!(
-                                "{}, spawning replacement (retry {})",
+                                task = %self.name,
+                                retry = retry_count + 1,
+                                delay = ?current_delay,
+                                "{}, spawning replacement",
                                 supervisor_err,
-                                retry_count + 1,
                             );
-                            tracing::debug!("Waiting {:?} before retry", current_delay);
 
                             // Instead of sleeping here, wrap the task spawn with a delay
                             let task_clone = self.task.clone();
@@ -190,20 +305,23 @@ where
 
                             // Update the delay for next retry, ensuring it doesn't exceed max_delay
                             current_delay = current_delay
-                                .mul_f64(self.retry_policy.backoff_multiplier)
-                                .min(self.retry_policy.max_delay);
+                                .mul_f64(retry_policy.backoff_multiplier)
+                                .min(retry_policy.max_delay);
                         }
                         SupervisorErr::Fault(_err) => {
-                            tracing::error!("{}", supervisor_err);
+                            tracing::error!(task = %self.name, "{}", supervisor_err);
                             anyhow::bail!("Hard failure in supervisor task");
                         }
                     },
                 },
                 Err(err) => {
                     if err.is_cancelled() {
-                        tracing::warn!("Task was canceled, treating it like a clean exit");
+                        tracing::warn!(
+                            task = %self.name,
+                            "Task was canceled, treating it like a clean exit"
+                        );
                     } else {
-                        tracing::error!("ABORT: supervisor join failed");
+                        tracing::error!(task = %self.name, "ABORT: supervisor join failed");
                         anyhow::bail!(err);
                     }
                 }
@@ -364,6 +482,8 @@ mod tests {
                 backoff_multiplier: 2.0,
                 max_delay: std::time::Duration::from_millis(500),
                 reset_after: None,
+                max_restarts: None,
+                restart_window: std::time::Duration::from_secs(60),
                 critical: true,
             })
             .spawn();
@@ -382,6 +502,40 @@ mod tests {
         assert!(res.unwrap_err().to_string().contains("Exceeded maximum retries for task"));
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn supervisor_max_restarts_from_config() {
+        let task = Arc::new(TestTask::new());
+        assert_eq!(task_name::<TestTask>(), "test_task");
+        let config = ConfigLock::default();
+        {
+            let mut config = config.load_write().unwrap();
+            config.supervisor.restart.initial_delay_ms = Some(10);
+            config.supervisor.tasks.insert(
+                "test_task".into(),
+                RestartPolicyConf { max_restarts: Some(2), ..Default::default() },
+            );
+        }
+
+        let supervisor_task =
+            Supervisor::new(task.clone(), config, CancellationToken::new()).spawn();
+
+        // Restarts are counted within the window, regardless of successful runs in between
+        task.tx(2).await.unwrap();
+        task.tx(0).await.unwrap();
+        task.tx(2).await.unwrap();
+        task.tx(2).await.unwrap();
+        task.close();
+
+        let res = supervisor_task.await;
+        assert!(res
+            .unwrap_err()
+            .to_string()
+            .contains("Exceeded maximum restarts for task test_task"));
+        // Shared with the other tests restarting the task
+        assert!(task_restarts()["test_task"] >= 2);
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn supervisor_cancellation() {
@@ -126,8 +126,11 @@ fn broker_args(
         db_url: "sqlite::memory:".into(),
         config_file,
         deployment: Some(deployment),
+        network: None,
         rpc_url,
+        read_rpc_url: None,
         private_key,
+        prover_private_key: None,
         bento_api_url: None,
         bonsai_api_key,
         bonsai_api_url,
@@ -136,6 +139,10 @@ fn broker_args(
         rpc_retry_backoff: 200,
         rpc_retry_cu: 1000,
         log_json: false,
+        admin_listen_addr: None,
+        admin_token: None,
+        metrics_listen_addr: None,
+        command: None,
     }
 }
 
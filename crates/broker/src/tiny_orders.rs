@@ -0,0 +1,164 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! State of the fast path for pricing tiny orders.
+
+use std::{
+    collections::HashMap,
+    mem,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::utils::parse_ether;
+use anyhow::{Context, Result};
+use boundless_market::contracts::ProofRequest;
+
+use crate::{
+    config::TinyOrderConf,
+    db::{DbError, DbObj},
+    OrderRequest, SkipReason,
+};
+
+/// Whether the request is tiny under the given config.
+pub(crate) fn is_tiny_order(conf: &TinyOrderConf, request: &ProofRequest) -> Result<bool> {
+    let max_price =
+        parse_ether(&conf.max_price).context("Failed to parse tiny_orders.max_price")?;
+    Ok(request.offer.maxPrice < max_price)
+}
+
+/// Gas price snapshots and skipped orders pending a batched DB write, shared by the pricing tasks.
+#[derive(Default)]
+pub(crate) struct TinyOrders {
+    /// Gas price per chain, with the time it was fetched
+    gas_snapshots: Mutex<HashMap<u64, (Instant, u128)>>,
+    skipped: Mutex<Vec<(OrderRequest, SkipReason)>>,
+}
+
+pub(crate) type TinyOrdersObj = Arc<TinyOrders>;
+
+impl TinyOrders {
+    /// Gas price of the chain, if a snapshot was taken within `max_age`.
+    pub(crate) fn gas_snapshot(&self, chain_id: u64, max_age: Duration) -> Option<u128> {
+        let snapshots = self.gas_snapshots.lock().unwrap();
+        let (taken_at, gas_price) = snapshots.get(&chain_id)?;
+        (taken_at.elapsed() <= max_age).then_some(*gas_price)
+    }
+
+    pub(crate) fn set_gas_snapshot(&self, chain_id: u64, gas_price: u128) {
+        self.gas_snapshots.lock().unwrap().insert(chain_id, (Instant::now(), gas_price));
+    }
+
+    /// Queue a skipped order to be written to the DB, returning the queued orders if there are
+    /// at least `batch_size` of them.
+    pub(crate) fn push_skipped(
+        &self,
+        order: OrderRequest,
+        reason: SkipReason,
+        batch_size: usize,
+    ) -> Option<Vec<(OrderRequest, SkipReason)>> {
+        let mut skipped = self.skipped.lock().unwrap();
+        skipped.push((order, reason));
+        (skipped.len() >= batch_size).then(|| mem::take(&mut *skipped))
+    }
+
+    /// Write all queued skipped orders to the DB, returning how many were written.
+    pub(crate) async fn flush(&self, db: &DbObj) -> Result<usize, DbError> {
+        let skipped = mem::take(&mut *self.skipped.lock().unwrap());
+        if skipped.is_empty() {
+            return Ok(0);
+        }
+        db.insert_skipped_requests(&skipped).await?;
+        Ok(skipped.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::SqliteDb, FulfillmentType, OrderStatus};
+    use alloy::primitives::{Address, Bytes, U256};
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, RequestId, RequestInput, RequestInputType, Requirements,
+    };
+    use risc0_zkvm::sha::Digest;
+    use sqlx::SqlitePool;
+
+    fn order_request(idx: u32, max_price: U256) -> OrderRequest {
+        let request = ProofRequest::new(
+            RequestId::new(Address::repeat_byte(1), idx),
+            Requirements::new(
+                Digest::ZERO,
+                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+            ),
+            "http://risczero.com",
+            RequestInput { inputType: RequestInputType::Inline, data: "".into() },
+            Offer {
+                minPrice: U256::ZERO,
+                maxPrice: max_price,
+                biddingStart: 100,
+                timeout: 100,
+                lockTimeout: 100,
+                rampUpPeriod: 1,
+                lockStake: U256::ZERO,
+            },
+        );
+        OrderRequest::new(request, Bytes::new(), FulfillmentType::LockAndFulfill, Address::ZERO, 1)
+    }
+
+    #[sqlx::test]
+    async fn snapshots_gas_and_batches_skipped_orders(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let conf = TinyOrderConf {
+            max_price: "0.00001".into(),
+            gas_snapshot_secs: 30,
+            skip_batch_size: 2,
+        };
+        let price = parse_ether("0.00001").unwrap();
+        assert!(is_tiny_order(&conf, &order_request(1, price - U256::from(1)).request).unwrap());
+        assert!(!is_tiny_order(&conf, &order_request(1, price).request).unwrap());
+
+        let tiny_orders = TinyOrders::default();
+        assert_eq!(tiny_orders.gas_snapshot(1, Duration::from_secs(30)), None);
+        tiny_orders.set_gas_snapshot(1, 100);
+        assert_eq!(tiny_orders.gas_snapshot(1, Duration::from_secs(30)), Some(100));
+        assert_eq!(tiny_orders.gas_snapshot(2, Duration::from_secs(30)), None);
+        assert_eq!(tiny_orders.gas_snapshot(1, Duration::ZERO), None);
+
+        let ids: Vec<_> = (1..=3).map(|idx| order_request(idx, U256::from(1)).id()).collect();
+        assert!(tiny_orders
+            .push_skipped(order_request(1, U256::from(1)), SkipReason::PriceTooLow, 2)
+            .is_none());
+        let batch = tiny_orders
+            .push_skipped(order_request(2, U256::from(1)), SkipReason::PriceTooLow, 2)
+            .unwrap();
+        assert_eq!(batch.len(), 2);
+        db.insert_skipped_requests(&batch).await.unwrap();
+
+        tiny_orders.push_skipped(
+            order_request(3, U256::from(1)),
+            SkipReason::GasCostExceedsPrice,
+            2,
+        );
+        assert_eq!(tiny_orders.flush(&db).await.unwrap(), 1);
+        assert_eq!(tiny_orders.flush(&db).await.unwrap(), 0);
+
+        for id in &ids {
+            let stored = db.get_order(id).await.unwrap().unwrap();
+            assert_eq!(stored.status, OrderStatus::Skipped);
+        }
+        let stored = db.get_order(&ids[2]).await.unwrap().unwrap();
+        assert_eq!(stored.skip_reason, Some(SkipReason::GasCostExceedsPrice));
+    }
+}
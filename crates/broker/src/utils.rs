@@ -12,14 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::time::Duration;
+
 use alloy::primitives::aliases::U96;
 use anyhow::{Context, Result};
-use boundless_market::{
-    contracts::ProofRequest,
-    selector::{ProofType, SupportedSelectors},
+use boundless_market::contracts::{
+    tx_submitter::{TxQueue, TxSubmitter},
+    ProofRequest,
 };
 
-use crate::{config::ConfigLock, Order, OrderRequest, OrderStatus};
+use crate::{config::ConfigLock, selectors::SelectorRegistry, Order, OrderRequest, OrderStatus};
 
 /// Gas allocated to verifying a smart contract signature. Copied from BoundlessMarket.sol.
 pub const ERC1271_MAX_GAS_FOR_CHECK: u64 = 100000;
@@ -53,6 +55,37 @@ pub async fn cancel_proof_and_fail_order(
     }
 }
 
+/// Build the submitter for lock and fulfill transactions from the batcher config
+///
+/// The queue of transactions in flight is held by the submitter, so it should be shared by all
+/// services sending transactions from the same account.
+pub fn tx_submitter(config: &ConfigLock) -> Result<TxSubmitter> {
+    let config = config.lock_all().context("Failed to read config")?;
+    let mut tx_submitter = TxSubmitter::default()
+        .with_simulation(config.batcher.txn_simulate)
+        .with_max_resubmissions(config.batcher.txn_max_resubmissions)
+        .with_max_fee_per_gas(config.batcher.txn_max_fee_per_gas.map(u128::from));
+    if let Some(max_in_flight) = config.batcher.txn_max_in_flight {
+        tx_submitter =
+            tx_submitter.with_queue(TxQueue::new(max_in_flight, config.batcher.txn_max_queued));
+    }
+    if let Some(txn_timeout) = config.batcher.txn_timeout {
+        tx_submitter = tx_submitter.with_timeout(Duration::from_secs(txn_timeout));
+    }
+    Ok(tx_submitter)
+}
+
+/// Check the decimals reported by the stake token against the configured expectation, if any
+pub fn check_stake_token_decimals(expected: Option<u8>, decimals: u8, chain_id: u64) -> Result<u8> {
+    if let Some(expected) = expected {
+        anyhow::ensure!(
+            expected == decimals,
+            "Stake token on chain {chain_id} has {decimals} decimals, but stake_token_decimals is configured as {expected}"
+        );
+    }
+    Ok(decimals)
+}
+
 /// Estimate of gas for locking a single order
 /// Currently just uses the config estimate but this may change in the future
 pub async fn estimate_gas_to_lock(config: &ConfigLock, order: &OrderRequest) -> Result<u64> {
@@ -70,13 +103,16 @@ pub async fn estimate_gas_to_lock(config: &ConfigLock, order: &OrderRequest) ->
 /// Currently just uses the config estimate but this may change in the future
 pub async fn estimate_gas_to_fulfill(
     config: &ConfigLock,
-    supported_selectors: &SupportedSelectors,
+    selectors: &SelectorRegistry,
     request: &ProofRequest,
 ) -> Result<u64> {
     // TODO: Add gas costs for orders with large journals.
-    let (base, groth16) = {
+    let (base, selector_gas) = {
         let config = config.lock_all().context("Failed to read config")?;
-        (config.market.fulfill_gas_estimate, config.market.groth16_verify_gas_estimate)
+        let profile = selectors
+            .profile(&config.market, request.requirements.selector)
+            .context("unsupported selector")?;
+        (config.market.fulfill_gas_estimate, profile.fulfill_gas)
     };
 
     let mut estimate = base;
@@ -91,17 +127,8 @@ pub async fn estimate_gas_to_fulfill(
             .unwrap_or(U96::ZERO),
     )?;
 
-    estimate += match supported_selectors
-        .proof_type(request.requirements.selector)
-        .context("unsupported selector")?
-    {
-        ProofType::Any | ProofType::Inclusion => 0,
-        ProofType::Groth16 => groth16,
-        proof_type => {
-            tracing::warn!("Unknown proof type in gas cost estimation: {proof_type:?}");
-            0
-        }
-    };
+    // Add gas to verify the proof required by the selector.
+    estimate += selector_gas;
 
     Ok(estimate)
 }
@@ -16,10 +16,12 @@ use alloy::primitives::Address;
 use anyhow::Context;
 use axum::extract::{Json, Path, Query, State};
 use boundless_market::order_stream_client::{
-    ErrMsg, Nonce, OrderData, SubmitOrderRes, AUTH_GET_NONCE, HEALTH_CHECK, ORDER_LIST_PATH,
-    ORDER_SUBMISSION_PATH,
+    ErrMsg, HealthInfo, Nonce, OrderData, SubmitOrderRes, SubmitOrderResult, WithdrawOrder,
+    AUTH_GET_NONCE, HEALTH_CHECK, MAX_ORDER_BATCH_SIZE, ORDER_BATCH_SUBMISSION_PATH,
+    ORDER_LIST_PATH, ORDER_SUBMISSION_PATH, ORDER_WITHDRAW_PATH,
 };
 use serde::Deserialize;
+use sqlx::types::chrono::Utc;
 use std::sync::Arc;
 use utoipa::IntoParams;
 
@@ -43,7 +45,9 @@ pub(crate) async fn submit_order(
     Json(order): Json<Order>,
 ) -> Result<Json<SubmitOrderRes>, AppError> {
     // Validate the order
-    order.validate(state.config.market_address, state.chain_id)?;
+    order
+        .validate_with_provider(state.config.market_address, state.chain_id, &state.rpc_provider)
+        .await?;
     let order_req_id = order.request.id;
     let order_id = state.db.add_order(order).await.context("failed to add order to db")?;
 
@@ -51,15 +55,103 @@ pub(crate) async fn submit_order(
     Ok(Json(SubmitOrderRes { status: "success".into(), request_id: order_req_id }))
 }
 
-const MAX_ORDERS: u64 = 1000;
+#[utoipa::path(
+    post,
+    path = ORDER_BATCH_SUBMISSION_PATH,
+    request_body = Vec<Order>,
+    responses(
+        (status = 200, description = "Result of each order", body = Vec<SubmitOrderResult>),
+        (status = 400, description = "Too many orders in the batch", body = ErrMsg),
+        (status = 500, description = "Internal error", body = ErrMsg)
+    )
+)]
+/// Submit multiple orders to the market order-stream at once
+///
+/// Each order is validated and stored independently, invalid orders do not prevent the others
+/// from being submitted.
+pub(crate) async fn submit_orders(
+    State(state): State<Arc<AppState>>,
+    Json(orders): Json<Vec<Order>>,
+) -> Result<Json<Vec<SubmitOrderResult>>, AppError> {
+    if orders.len() > MAX_ORDER_BATCH_SIZE {
+        return Err(AppError::BatchTooLarge(orders.len()));
+    }
+
+    let mut results = Vec::with_capacity(orders.len());
+    for order in orders {
+        let order_req_id = order.request.id;
+        let validation = order
+            .validate_with_provider(
+                state.config.market_address,
+                state.chain_id,
+                &state.rpc_provider,
+            )
+            .await;
+        let error = match validation {
+            Err(err) => Some(format!("invalid order: {err}")),
+            Ok(()) => match state.db.add_order(order).await {
+                Ok(order_id) => {
+                    tracing::debug!("Order 0x{order_req_id:x} - [{order_id}] submitted in batch");
+                    None
+                }
+                Err(err) => {
+                    tracing::error!("Failed to add order 0x{order_req_id:x} to db: {err:?}");
+                    Some("internal error".into())
+                }
+            },
+        };
+        results.push(SubmitOrderResult { request_id: order_req_id, error });
+    }
+    Ok(Json(results))
+}
+
+#[utoipa::path(
+    post,
+    path = ORDER_WITHDRAW_PATH,
+    request_body = WithdrawOrder,
+    responses(
+        (status = 200, description = "Order withdrawn"),
+        (status = 400, description = "Invalid signature", body = ErrMsg),
+        (status = 403, description = "Signer is not the client of the order", body = ErrMsg),
+        (status = 404, description = "Order not found", body = ErrMsg),
+        (status = 500, description = "Internal error", body = ErrMsg)
+    )
+)]
+/// Withdraw an order from the market order-stream
+///
+/// The withdrawal must be signed by the client of the request. Withdrawn orders are no longer
+/// listed, but remain valid on-chain.
+pub(crate) async fn withdraw_order(
+    State(state): State<Arc<AppState>>,
+    Json(withdraw): Json<WithdrawOrder>,
+) -> Result<(), AppError> {
+    let signer = withdraw.signer()?;
+    let order = state
+        .db
+        .find_orders_by_request_id(withdraw.request_id.to_string())
+        .await
+        .context("Failed to query DB")?
+        .into_iter()
+        .find(|order| order.order.request_digest == withdraw.request_digest)
+        .ok_or(AppError::OrderNotFound(withdraw.request_digest))?;
+    if order.order.request.client_address() != signer {
+        return Err(AppError::NotOrderClient(signer));
+    }
+    state.db.delete_order(order.id).await.context("Failed to delete order")?;
+
+    tracing::debug!("Order 0x{:x} - [{}] withdrawn", withdraw.request_id, order.id);
+    Ok(())
+}
+
+pub(crate) const MAX_ORDERS: u64 = 1000;
 
 /// Paging query parameters
 #[derive(Deserialize, IntoParams)]
 pub struct Pagination {
     /// order id offset to start at
-    offset: u64,
+    pub(crate) offset: u64,
     /// Limit of orders returned, max 1000
-    limit: u64,
+    pub(crate) limit: u64,
 }
 
 #[utoipa::path(
@@ -146,12 +238,20 @@ pub(crate) async fn get_nonce(
     get,
     path = HEALTH_CHECK,
     responses(
-        (status = 200, description = "Healthy"),
+        (status = 200, description = "Healthy", body = HealthInfo),
         (status = 500, description = "Unhealthy", body = ErrMsg)
     )
 )]
-/// Submit a new order to the market order-stream
-pub(crate) async fn health(State(state): State<Arc<AppState>>) -> Result<(), AppError> {
-    state.db.health_check().await.context("Failed health check")?;
-    Ok(())
+/// Health of the server, with its version, connected provers and time of the last order
+pub(crate) async fn health(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HealthInfo>, AppError> {
+    let last_order_at = state.db.health_check().await.context("Failed health check")?;
+    let connected_provers = state.connections.read().await.len();
+    Ok(Json(HealthInfo {
+        version: Some(env!("CARGO_PKG_VERSION").into()),
+        connected_provers: Some(connected_provers),
+        last_order_at,
+        server_time: Some(Utc::now()),
+    }))
 }
@@ -0,0 +1,441 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Order stream server for local development, see [DevArgs].
+//!
+//! Serves the API of the order stream from a single process, keeping the orders in memory, so
+//! that requestors and brokers can run end-to-end flows locally, e.g. against Anvil, without a
+//! database or an RPC node. Orders are validated as in production, but websocket connections are
+//! not authenticated, no minimum stake balance is required, and consumer groups are ignored:
+//! every connection receives all the orders matching its filter. Orders are lost on restart.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use alloy::primitives::Address;
+use anyhow::{Context, Result};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Json, Path, Query, State, WebSocketUpgrade,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use boundless_market::order_stream_client::{
+    HealthInfo, Nonce, Order, OrderEncoding, OrderFilter, SubmitOrderRes, SubmitOrderResult,
+    WithdrawOrder, AUTH_GET_NONCE, HEALTH_CHECK, MAX_ORDER_BATCH_SIZE, ORDER_BATCH_SUBMISSION_PATH,
+    ORDER_ENCODING_HEADER, ORDER_FILTER_HEADER, ORDER_LIST_PATH, ORDER_SUBMISSION_PATH,
+    ORDER_WITHDRAW_PATH, ORDER_WS_PATH,
+};
+use clap::Parser;
+use futures_util::{SinkExt, StreamExt};
+use rand::Rng;
+use sqlx::types::chrono::Utc;
+use tokio::sync::{broadcast, RwLock};
+use tokio_util::sync::CancellationToken;
+use tower_http::{limit::RequestBodyLimitLayer, trace::TraceLayer};
+
+use crate::{
+    api::{Pagination, MAX_ORDERS},
+    order_db::DbOrder,
+    shutdown_signal,
+    ws::{order_message, parse_auth_msg, parse_order_encoding, parse_order_filter},
+    AppError, MAX_ORDER_SIZE,
+};
+
+/// Number of new orders buffered for each websocket connection.
+const DEV_BROADCAST_CAPACITY: usize = 1024;
+
+/// Time between sending websocket pings.
+const DEV_PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Command line arguments of the development order stream server
+#[derive(Parser, Debug)]
+#[clap(author, version, about = "In-memory order stream server for local development")]
+#[non_exhaustive]
+pub struct DevArgs {
+    /// Bind address for REST api
+    #[clap(long, env, default_value = "127.0.0.1:8585")]
+    bind_addr: String,
+
+    /// Address of the BoundlessMarket contract
+    #[clap(long, env)]
+    boundless_market_address: Address,
+
+    /// Chain ID of the network the market is deployed on, defaults to Anvil
+    #[clap(long, env, default_value_t = 31337)]
+    chain_id: u64,
+}
+
+/// State of the development order stream server
+pub struct DevState {
+    market_address: Address,
+    chain_id: u64,
+    /// Orders by order stream id
+    orders: RwLock<BTreeMap<i64, DbOrder>>,
+    next_id: AtomicI64,
+    /// New orders, pushed to the websocket connections
+    new_orders: broadcast::Sender<Arc<DbOrder>>,
+    /// Cancelation token set when a graceful shutdown is triggered
+    shutdown: CancellationToken,
+}
+
+impl DevState {
+    /// Create an empty DevState for the given market
+    pub fn new(market_address: Address, chain_id: u64) -> Arc<Self> {
+        Arc::new(Self {
+            market_address,
+            chain_id,
+            orders: RwLock::new(BTreeMap::new()),
+            next_id: AtomicI64::new(1),
+            new_orders: broadcast::channel(DEV_BROADCAST_CAPACITY).0,
+            shutdown: CancellationToken::new(),
+        })
+    }
+
+    /// Validate and store an order, notifying the websocket connections
+    async fn add_order(&self, order: Order) -> Result<i64, AppError> {
+        order.validate(self.market_address, self.chain_id)?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let db_order = DbOrder { id, order, created_at: Some(Utc::now()) };
+        tracing::debug!("Order 0x{:x} - [{id}] submitted", db_order.order.request.id);
+        // Sending only fails without connections
+        let _ = self.new_orders.send(Arc::new(db_order.clone()));
+        self.orders.write().await.insert(id, db_order);
+        Ok(id)
+    }
+}
+
+async fn submit_order(
+    State(state): State<Arc<DevState>>,
+    Json(order): Json<Order>,
+) -> Result<Json<SubmitOrderRes>, AppError> {
+    let request_id = order.request.id;
+    state.add_order(order).await?;
+    Ok(Json(SubmitOrderRes { status: "success".into(), request_id }))
+}
+
+async fn submit_orders(
+    State(state): State<Arc<DevState>>,
+    Json(orders): Json<Vec<Order>>,
+) -> Result<Json<Vec<SubmitOrderResult>>, AppError> {
+    if orders.len() > MAX_ORDER_BATCH_SIZE {
+        return Err(AppError::BatchTooLarge(orders.len()));
+    }
+
+    let mut results = Vec::with_capacity(orders.len());
+    for order in orders {
+        let request_id = order.request.id;
+        let error = state.add_order(order).await.err().map(|err| err.to_string());
+        results.push(SubmitOrderResult { request_id, error });
+    }
+    Ok(Json(results))
+}
+
+async fn withdraw_order(
+    State(state): State<Arc<DevState>>,
+    Json(withdraw): Json<WithdrawOrder>,
+) -> Result<(), AppError> {
+    let signer = withdraw.signer()?;
+    let mut orders = state.orders.write().await;
+    let (id, order) = orders
+        .iter()
+        .find(|(_, order)| {
+            order.order.request.id == withdraw.request_id
+                && order.order.request_digest == withdraw.request_digest
+        })
+        .ok_or(AppError::OrderNotFound(withdraw.request_digest))?;
+    if order.order.request.client_address() != signer {
+        return Err(AppError::NotOrderClient(signer));
+    }
+    let id = *id;
+    orders.remove(&id);
+
+    tracing::debug!("Order 0x{:x} - [{id}] withdrawn", withdraw.request_id);
+    Ok(())
+}
+
+async fn list_orders(
+    State(state): State<Arc<DevState>>,
+    paging: Query<Pagination>,
+) -> Result<Json<Vec<DbOrder>>, AppError> {
+    let limit = usize::try_from(paging.limit.min(MAX_ORDERS))
+        .map_err(|_| AppError::QueryParamErr("limit"))?;
+    let offset = i64::try_from(paging.offset).map_err(|_| AppError::QueryParamErr("index"))?;
+
+    let orders = state.orders.read().await;
+    Ok(Json(orders.range(offset..).take(limit).map(|(_, order)| order.clone()).collect()))
+}
+
+async fn find_orders_by_request_id(
+    State(state): State<Arc<DevState>>,
+    Path(request_id): Path<String>,
+) -> Result<Json<Vec<DbOrder>>, AppError> {
+    let orders = state.orders.read().await;
+    Ok(Json(
+        orders
+            .values()
+            .filter(|order| order.order.request.id.to_string() == request_id)
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// Returns a fresh nonce, which is not checked when connecting
+async fn get_nonce(Path(_addr): Path<Address>) -> Json<Nonce> {
+    let rand_bytes: [u8; 16] = rand::random();
+    Json(Nonce { nonce: hex::encode(rand_bytes.as_slice()) })
+}
+
+async fn health(State(state): State<Arc<DevState>>) -> Json<HealthInfo> {
+    let last_order_at =
+        state.orders.read().await.last_key_value().and_then(|(_, order)| order.created_at);
+    Json(HealthInfo {
+        version: Some(env!("CARGO_PKG_VERSION").into()),
+        connected_provers: Some(state.new_orders.receiver_count()),
+        last_order_at,
+        server_time: Some(Utc::now()),
+    })
+}
+
+/// Websocket connection point, accepting any well formed auth message
+async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    State(state): State<Arc<DevState>>,
+) -> Response {
+    let Some(auth_msg) = headers.get("X-Auth-Data").and_then(|value| {
+        parse_auth_msg(value).inspect_err(|err| tracing::warn!("Invalid auth-msg: {err:?}")).ok()
+    }) else {
+        return (StatusCode::BAD_REQUEST, "Missing or invalid auth header").into_response();
+    };
+    let filter = match headers.get(ORDER_FILTER_HEADER).map(parse_order_filter).transpose() {
+        Ok(filter) => filter.unwrap_or_default(),
+        Err(err) => {
+            tracing::warn!("Invalid order filter format: {err:?}");
+            return (StatusCode::BAD_REQUEST, "Invalid order filter format").into_response();
+        }
+    };
+    let encoding = match headers.get(ORDER_ENCODING_HEADER).map(parse_order_encoding).transpose() {
+        Ok(encoding) => encoding.unwrap_or_default(),
+        Err(err) => {
+            tracing::warn!("Invalid order encoding: {err:?}");
+            return (StatusCode::BAD_REQUEST, "Invalid order encoding").into_response();
+        }
+    };
+
+    let address = auth_msg.address();
+    tracing::info!("New webSocket connection from {address}");
+    ws.on_upgrade(move |socket| websocket_connection(socket, address, filter, encoding, state))
+}
+
+async fn websocket_connection(
+    socket: WebSocket,
+    address: Address,
+    filter: OrderFilter,
+    encoding: OrderEncoding,
+    state: Arc<DevState>,
+) {
+    let (mut sender_ws, mut recver_ws) = socket.split();
+    let mut new_orders = state.new_orders.subscribe();
+    let mut ping_interval = tokio::time::interval(DEV_PING_INTERVAL);
+
+    loop {
+        tokio::select! {
+            order = new_orders.recv() => {
+                let order = match order {
+                    Ok(order) => order,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Client {address} missed {missed} orders");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if !filter.matches(&order.order.request) {
+                    continue;
+                }
+                let Some(msg) = order_message(&order, encoding, &mut HashMap::new()) else {
+                    continue;
+                };
+                if let Err(err) = sender_ws.send(msg).await {
+                    tracing::warn!("Failed to send order to client {address}: {err}");
+                    break;
+                }
+            }
+            _ = ping_interval.tick() => {
+                let random_bytes: [u8; 16] = rand::rng().random();
+                if let Err(err) = sender_ws.send(Message::Ping(random_bytes.to_vec().into())).await {
+                    tracing::warn!("Failed to send Ping to {address}: {err:?}");
+                    break;
+                }
+            }
+            ws_msg = recver_ws.next() => {
+                match ws_msg {
+                    Some(Ok(Message::Ping(data))) => {
+                        if let Err(err) = sender_ws.send(Message::Pong(data)).await {
+                            tracing::warn!("Failed to send Pong to {address}: {err:?}");
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Pong(_))) => {}
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(msg)) => {
+                        tracing::warn!("Received unexpected message from {address}: {msg:?}");
+                        break;
+                    }
+                    Some(Err(err)) => {
+                        tracing::warn!("Error receiving message from {address}: {err:?}");
+                        break;
+                    }
+                }
+            }
+            _ = state.shutdown.cancelled() => break,
+        }
+    }
+
+    if let Err(err) = sender_ws.close().await {
+        tracing::warn!("Error while closing WebSocket connection for {address}: {err}");
+    }
+    tracing::debug!("WebSocket connection closed: {address}");
+}
+
+/// Create the router of the development order stream server
+pub fn dev_app(state: Arc<DevState>) -> Router {
+    let body_size_limit = RequestBodyLimitLayer::new(MAX_ORDER_SIZE);
+    let batch_body_size_limit = RequestBodyLimitLayer::new(MAX_ORDER_SIZE * MAX_ORDER_BATCH_SIZE);
+
+    Router::new()
+        .route(ORDER_SUBMISSION_PATH, post(submit_order).layer(body_size_limit))
+        .route(ORDER_BATCH_SUBMISSION_PATH, post(submit_orders).layer(batch_body_size_limit))
+        .route(ORDER_WITHDRAW_PATH, post(withdraw_order))
+        .route(ORDER_LIST_PATH, get(list_orders))
+        .route(&format!("{ORDER_LIST_PATH}/{{request_id}}"), get(find_orders_by_request_id))
+        .route(&format!("{AUTH_GET_NONCE}{{addr}}"), get(get_nonce))
+        .route(ORDER_WS_PATH, get(websocket_handler))
+        .route(HEALTH_CHECK, get(health))
+        .with_state(state)
+        .layer(TraceLayer::new_for_http())
+}
+
+/// Run the development order stream server
+pub async fn run_dev(args: &DevArgs) -> Result<()> {
+    let state = DevState::new(args.boundless_market_address, args.chain_id);
+    let listener = tokio::net::TcpListener::bind(&args.bind_addr)
+        .await
+        .context("Failed to bind a TCP listener")?;
+    run_dev_from_parts(state, listener).await
+}
+
+/// Run the development order stream server from parts
+pub async fn run_dev_from_parts(
+    state: Arc<DevState>,
+    listener: tokio::net::TcpListener,
+) -> Result<()> {
+    tracing::info!(
+        "Development order stream for market {} on chain {} listening on: {}",
+        state.market_address,
+        state.chain_id,
+        listener.local_addr()?
+    );
+    axum::serve(listener, dev_app(state.clone()))
+        .with_graceful_shutdown(shutdown_signal(state.shutdown.clone()))
+        .await
+        .context("Development order stream failed")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{primitives::U256, signers::local::PrivateKeySigner};
+    use boundless_market::{
+        contracts::{Offer, Predicate, ProofRequest, RequestId, Requirements},
+        input::GuestEnv,
+        order_stream_client::{order_stream, OrderStreamClient},
+    };
+    use reqwest::Url;
+    use risc0_zkvm::sha::Digest;
+
+    fn new_request(idx: u32, addr: &Address) -> ProofRequest {
+        ProofRequest::new(
+            RequestId::new(*addr, idx),
+            Requirements::new(Digest::from_bytes([1; 32]), Predicate::prefix_match([])),
+            "http://image_uri.null",
+            GuestEnv::builder().build_inline().unwrap(),
+            Offer {
+                minPrice: U256::from(1),
+                maxPrice: U256::from(2),
+                biddingStart: 1,
+                timeout: 100,
+                lockTimeout: 100,
+                rampUpPeriod: 1,
+                lockStake: U256::from(10),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn serves_orders_in_memory() {
+        let (market_address, chain_id) = (Address::repeat_byte(1), 31337);
+        let state = DevState::new(market_address, chain_id);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(run_dev_from_parts(state.clone(), listener));
+
+        let client = OrderStreamClient::new(
+            Url::parse(&format!("http://{addr}")).unwrap(),
+            market_address,
+            chain_id,
+        );
+        let (client_signer, broker_signer) =
+            (PrivateKeySigner::random(), PrivateKeySigner::random());
+        let mut orders = order_stream(client.connect_async(&broker_signer).await.unwrap());
+
+        let request = new_request(1, &client_signer.address());
+        let order = client.submit_request(&request, &client_signer).await.unwrap();
+        let pushed = tokio::time::timeout(Duration::from_secs(5), orders.next()).await.unwrap();
+        assert_eq!(pushed.unwrap().order, order);
+        assert_eq!(client.fetch_order(request.id, None).await.unwrap(), order);
+        assert_eq!(client.list_orders(0, 10).await.unwrap().len(), 1);
+
+        // Orders signed for another market are rejected
+        let other = OrderStreamClient::new(
+            Url::parse(&format!("http://{addr}")).unwrap(),
+            Address::repeat_byte(2),
+            chain_id,
+        );
+        other
+            .submit_request(&new_request(2, &client_signer.address()), &client_signer)
+            .await
+            .unwrap_err();
+
+        // Only the client can withdraw its order
+        client.withdraw_order(request.id, order.request_digest, &broker_signer).await.unwrap_err();
+        client.withdraw_order(request.id, order.request_digest, &client_signer).await.unwrap();
+        assert!(client.list_orders(0, 10).await.unwrap().is_empty());
+
+        state.shutdown.cancel();
+        server.await.unwrap().unwrap();
+    }
+}
@@ -19,7 +19,7 @@ use std::time::{Duration, Instant};
 use alloy::providers::fillers::{ChainIdFiller, FillProvider, JoinFill};
 use alloy::providers::Identity;
 use alloy::{
-    primitives::{utils::parse_ether, Address, U256},
+    primitives::{utils::parse_ether, Address, B256, U256},
     providers::{Provider, ProviderBuilder, RootProvider},
     rpc::client::RpcClient,
     transports::layers::RetryBackoffLayer,
@@ -33,8 +33,9 @@ use axum::{
     Router,
 };
 use boundless_market::order_stream_client::{
-    AuthMsg, ErrMsg, Order, OrderError, AUTH_GET_NONCE, HEALTH_CHECK, ORDER_LIST_PATH,
-    ORDER_SUBMISSION_PATH, ORDER_WS_PATH,
+    AuthMsg, ConsumerGroup, ErrMsg, HealthInfo, Order, OrderError, OrderFilter, AUTH_GET_NONCE,
+    HEALTH_CHECK, MAX_ORDER_BATCH_SIZE, ORDER_BATCH_SUBMISSION_PATH, ORDER_LIST_PATH,
+    ORDER_SUBMISSION_PATH, ORDER_WITHDRAW_PATH, ORDER_WS_PATH,
 };
 use clap::Parser;
 use reqwest::Url;
@@ -49,12 +50,14 @@ use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 mod api;
+pub mod dev;
 mod order_db;
 mod ws;
 
 use api::{
     __path_find_orders_by_request_id, __path_get_nonce, __path_health, __path_list_orders,
-    __path_submit_order, find_orders_by_request_id, get_nonce, health, list_orders, submit_order,
+    __path_submit_order, __path_submit_orders, __path_withdraw_order, find_orders_by_request_id,
+    get_nonce, health, list_orders, submit_order, submit_orders, withdraw_order,
 };
 use order_db::OrderDb;
 use ws::{__path_websocket_handler, start_broadcast_task, websocket_handler, ConnectionsMap};
@@ -71,6 +74,15 @@ pub enum AppError {
     #[error("address not found")]
     AddrNotFound(Address),
 
+    #[error("too many orders in batch: {0}, max {max}", max = MAX_ORDER_BATCH_SIZE)]
+    BatchTooLarge(usize),
+
+    #[error("order not found: {0}")]
+    OrderNotFound(B256),
+
+    #[error("signer {0} is not the client of the order")]
+    NotOrderClient(Address),
+
     #[error("internal error")]
     InternalErr(AnyhowErr),
 }
@@ -81,6 +93,9 @@ impl AppError {
             Self::InvalidOrder(_) => "InvalidOrder",
             Self::QueryParamErr(_) => "QueryParamErr",
             Self::AddrNotFound(_) => "AddrNotFound",
+            Self::BatchTooLarge(_) => "BatchTooLarge",
+            Self::OrderNotFound(_) => "OrderNotFound",
+            Self::NotOrderClient(_) => "NotOrderClient",
             Self::InternalErr(_) => "InternalErr",
         }
         .into()
@@ -102,8 +117,11 @@ impl From<OrderError> for AppError {
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let code = match self {
-            Self::InvalidOrder(_) | Self::QueryParamErr(_) => StatusCode::BAD_REQUEST,
-            Self::AddrNotFound(_) => StatusCode::NOT_FOUND,
+            Self::InvalidOrder(_) | Self::QueryParamErr(_) | Self::BatchTooLarge(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            Self::AddrNotFound(_) | Self::OrderNotFound(_) => StatusCode::NOT_FOUND,
+            Self::NotOrderClient(_) => StatusCode::FORBIDDEN,
             Self::InternalErr(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
         tracing::error!("api error, code {code}: {self:?}");
@@ -421,13 +439,15 @@ const MAX_ORDER_SIZE: usize = 100 * 1024; // 100 KiB
 #[openapi(
     paths(
         submit_order,
+        submit_orders,
+        withdraw_order,
         list_orders,
         find_orders_by_request_id,
         get_nonce,
         health,
         websocket_handler
     ),
-    components(schemas(AuthMsg)),
+    components(schemas(AuthMsg, ConsumerGroup, HealthInfo, OrderFilter)),
     info(
         title = "Boundless Order Stream service",
         description = r#"
@@ -441,9 +461,12 @@ struct ApiDoc;
 /// Create the application router
 pub fn app(state: Arc<AppState>) -> Router {
     let body_size_limit = RequestBodyLimitLayer::new(MAX_ORDER_SIZE);
+    let batch_body_size_limit = RequestBodyLimitLayer::new(MAX_ORDER_SIZE * MAX_ORDER_BATCH_SIZE);
 
     Router::new()
         .route(ORDER_SUBMISSION_PATH, post(submit_order).layer(body_size_limit))
+        .route(ORDER_BATCH_SUBMISSION_PATH, post(submit_orders).layer(batch_body_size_limit))
+        .route(ORDER_WITHDRAW_PATH, post(withdraw_order))
         .route(ORDER_LIST_PATH, get(list_orders))
         .route(&format!("{ORDER_LIST_PATH}/{{request_id}}"), get(find_orders_by_request_id))
         .route(&format!("{AUTH_GET_NONCE}{{addr}}"), get(get_nonce))
@@ -493,14 +516,14 @@ pub async fn run_from_parts(
 
     tracing::info!("REST API listening on: {}", listener.local_addr().unwrap());
     axum::serve(listener, self::app(app_state.clone()))
-        .with_graceful_shutdown(async { shutdown_signal(app_state).await })
+        .with_graceful_shutdown(shutdown_signal(app_state.shutdown.clone()))
         .await
         .context("REST API service failed")?;
 
     Ok(())
 }
 
-async fn shutdown_signal(state: Arc<AppState>) {
+async fn shutdown_signal(shutdown: CancellationToken) {
     let ctrl_c = async {
         tokio::signal::ctrl_c().await.expect("failed to install Ctrl+C handler");
     };
@@ -518,7 +541,7 @@ async fn shutdown_signal(state: Arc<AppState>) {
     }
 
     tracing::info!("Triggering shutdown");
-    state.shutdown.cancel();
+    shutdown.cancel();
 }
 
 #[cfg(test)]
@@ -535,7 +558,7 @@ mod tests {
             hit_points::default_allowance, Offer, Predicate, ProofRequest, RequestId, Requirements,
         },
         input::GuestEnv,
-        order_stream_client::{order_stream, OrderStreamClient},
+        order_stream_client::{order_stream, OrderEncoding, OrderStreamClient, OrderStreamErr},
     };
     use boundless_market_test_utils::{create_test_ctx, TestCtx};
 
@@ -773,6 +796,205 @@ mod tests {
         server_handle.abort();
     }
 
+    #[sqlx::test]
+    async fn filtered_connection(pool: PgPool) {
+        let listener = tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)))
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (app_state, ctx, _anvil) = setup_test_env(pool, 20, Some(&listener)).await;
+
+        let client = OrderStreamClient::new(
+            Url::parse(&format!("http://{addr}")).unwrap(),
+            app_state.config.market_address,
+            app_state.chain_id,
+        );
+        let app_state_clone = app_state.clone();
+        let server_handle = tokio::spawn(async move {
+            self::run_from_parts(app_state_clone, listener).await.unwrap();
+        });
+        wait_for_server_health(&client, &addr, 5).await;
+
+        // The prover only accepts orders from the customer, the customer accepts all orders,
+        // compressed in binary frames.
+        let filter = OrderFilter {
+            client_addresses: Some(vec![ctx.customer_signer.address()]),
+            ..Default::default()
+        };
+        let prover_socket =
+            client.clone().with_filter(filter).connect_async(&ctx.prover_signer).await.unwrap();
+        let customer_socket = client
+            .clone()
+            .with_encoding(OrderEncoding::MSGPACK_DEFLATE)
+            .connect_async(&ctx.customer_signer)
+            .await
+            .unwrap();
+        let mut prover_stream = order_stream(prover_socket);
+        let mut customer_stream = order_stream(customer_socket);
+
+        let timeout = tokio::time::Duration::from_secs(4);
+        let filtered_order = client
+            .submit_request(&new_request(1, &ctx.prover_signer.address()), &ctx.prover_signer)
+            .await
+            .unwrap();
+        let received = tokio::time::timeout(timeout, customer_stream.next()).await.unwrap();
+        assert_eq!(received.unwrap().order, filtered_order);
+
+        let matching_order = client
+            .submit_request(&new_request(2, &ctx.customer_signer.address()), &ctx.customer_signer)
+            .await
+            .unwrap();
+        // The first order pushed to the prover is the one matching its filter.
+        let received = tokio::time::timeout(timeout, prover_stream.next()).await.unwrap();
+        assert_eq!(received.unwrap().order, matching_order);
+
+        app_state.shutdown.cancel();
+        server_handle.abort();
+    }
+
+    #[sqlx::test]
+    async fn batch_submission(pool: PgPool) {
+        let listener = tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)))
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (app_state, ctx, _anvil) = setup_test_env(pool, 20, Some(&listener)).await;
+
+        let client = OrderStreamClient::new(
+            Url::parse(&format!("http://{addr}")).unwrap(),
+            app_state.config.market_address,
+            app_state.chain_id,
+        );
+        let app_state_clone = app_state.clone();
+        let server_handle = tokio::spawn(async move {
+            self::run_from_parts(app_state_clone, listener).await.unwrap();
+        });
+        wait_for_server_health(&client, &addr, 5).await;
+
+        // The second request belongs to another client, so cannot be signed by the prover.
+        let prover_addr = ctx.prover_signer.address();
+        let requests = [
+            new_request(1, &prover_addr),
+            new_request(2, &ctx.customer_signer.address()),
+            new_request(3, &prover_addr),
+        ];
+        let results = client.submit_requests(&requests, &ctx.prover_signer).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results[1].is_err());
+        for idx in [0, 2] {
+            let order = results[idx].as_ref().unwrap();
+            assert_eq!(order.request, requests[idx]);
+            let stored = client.fetch_order(requests[idx].id, None).await.unwrap();
+            assert_eq!(&stored, order);
+        }
+        assert!(matches!(
+            client.fetch_order(requests[1].id, None).await,
+            Err(OrderStreamErr::NotFound)
+        ));
+
+        app_state.shutdown.cancel();
+        server_handle.abort();
+    }
+
+    #[sqlx::test]
+    async fn withdraw_order(pool: PgPool) {
+        let listener = tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)))
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (app_state, ctx, _anvil) = setup_test_env(pool, 20, Some(&listener)).await;
+
+        let client = OrderStreamClient::new(
+            Url::parse(&format!("http://{addr}")).unwrap(),
+            app_state.config.market_address,
+            app_state.chain_id,
+        );
+        let app_state_clone = app_state.clone();
+        let server_handle = tokio::spawn(async move {
+            self::run_from_parts(app_state_clone, listener).await.unwrap();
+        });
+        wait_for_server_health(&client, &addr, 5).await;
+
+        let request = new_request(1, &ctx.prover_signer.address());
+        let order = client.submit_request(&request, &ctx.prover_signer).await.unwrap();
+
+        // Only the client of the request can withdraw it
+        assert!(matches!(
+            client.withdraw_order(request.id, order.request_digest, &ctx.customer_signer).await,
+            Err(OrderStreamErr::Auth(_))
+        ));
+        assert!(matches!(
+            client.withdraw_order(request.id, B256::ZERO, &ctx.prover_signer).await,
+            Err(OrderStreamErr::NotFound)
+        ));
+        client.withdraw_order(request.id, order.request_digest, &ctx.prover_signer).await.unwrap();
+        assert!(matches!(
+            client.fetch_order(request.id, None).await,
+            Err(OrderStreamErr::NotFound)
+        ));
+
+        app_state.shutdown.cancel();
+        server_handle.abort();
+    }
+
+    #[sqlx::test]
+    async fn consumer_group_connection(pool: PgPool) {
+        let listener = tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)))
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (app_state, ctx, _anvil) = setup_test_env(pool, 20, Some(&listener)).await;
+
+        let client = OrderStreamClient::new(
+            Url::parse(&format!("http://{addr}")).unwrap(),
+            app_state.config.market_address,
+            app_state.chain_id,
+        );
+        let app_state_clone = app_state.clone();
+        let server_handle = tokio::spawn(async move {
+            self::run_from_parts(app_state_clone, listener).await.unwrap();
+        });
+        wait_for_server_health(&client, &addr, 5).await;
+
+        // Both members of the group share the orders, each order is pushed to one of them.
+        let group_client = client.clone().with_consumer_group(ConsumerGroup::new("fleet"));
+        let prover_socket = group_client.connect_async(&ctx.prover_signer).await.unwrap();
+        let customer_socket = group_client.connect_async(&ctx.customer_signer).await.unwrap();
+        let mut group_stream = futures_util::stream::select(
+            order_stream(prover_socket),
+            order_stream(customer_socket),
+        );
+
+        let timeout = tokio::time::Duration::from_secs(4);
+        let mut submitted = Vec::new();
+        for idx in 1..=4 {
+            submitted.push(
+                client
+                    .submit_request(
+                        &new_request(idx, &ctx.customer_signer.address()),
+                        &ctx.customer_signer,
+                    )
+                    .await
+                    .unwrap(),
+            );
+        }
+        let mut received = Vec::new();
+        for _ in 0..submitted.len() {
+            let order = tokio::time::timeout(timeout, group_stream.next()).await.unwrap();
+            received.push(order.unwrap().order);
+        }
+        for order in &submitted {
+            assert!(received.contains(order));
+        }
+        // No order is pushed twice
+        let next =
+            tokio::time::timeout(tokio::time::Duration::from_secs(1), group_stream.next()).await;
+        assert!(next.is_err());
+
+        app_state.shutdown.cancel();
+        server_handle.abort();
+    }
+
     #[sqlx::test]
     async fn test_pending_connection_timeout(pool: PgPool) {
         // No need for a listener in this test
@@ -50,7 +50,7 @@ pub enum OrderDbErr {
     JsonErr(#[from] serde_json::Error),
 }
 
-#[derive(Serialize, Deserialize, sqlx::FromRow, Debug)]
+#[derive(Serialize, Deserialize, sqlx::FromRow, Debug, Clone)]
 pub struct DbOrder {
     pub id: i64,
     #[sqlx(rename = "order_data", json)]
@@ -201,7 +201,6 @@ impl OrderDb {
     }
 
     /// Deletes a order from the database
-    #[cfg(test)]
     pub async fn delete_order(&self, id: i64) -> Result<(), OrderDbErr> {
         if sqlx::query("DELETE FROM orders WHERE id = $1")
             .bind(id)
@@ -261,9 +260,14 @@ impl OrderDb {
     }
 
     /// Simple health check to test postgesql connectivity
-    pub async fn health_check(&self) -> Result<(), OrderDbErr> {
-        sqlx::query("SELECT COUNT(*) FROM orders LIMIT 1").execute(&self.pool).await?;
-        Ok(())
+    ///
+    /// Returns the time the last order was submitted, if any.
+    pub async fn health_check(&self) -> Result<Option<DateTime<Utc>>, OrderDbErr> {
+        let last_order_at: Option<Option<DateTime<Utc>>> =
+            sqlx::query_scalar("SELECT created_at FROM orders ORDER BY id DESC LIMIT 1")
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(last_order_at.flatten())
     }
 }
 
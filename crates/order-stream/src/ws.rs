@@ -24,7 +24,10 @@ use axum::{
 };
 use boundless_market::{
     contracts::IBoundlessMarket,
-    order_stream_client::{AuthMsg, ErrMsg, ORDER_WS_PATH},
+    order_stream_client::{
+        encode_order, AuthMsg, ConsumerGroup, ErrMsg, OrderEncoding, OrderFilter,
+        CONSUMER_GROUP_HEADER, ORDER_ENCODING_HEADER, ORDER_FILTER_HEADER, ORDER_WS_PATH,
+    },
 };
 use futures_util::{SinkExt, StreamExt};
 use rand::{seq::SliceRandom, Rng};
@@ -36,16 +39,33 @@ use crate::order_db::{DbOrder, OrderDbErr, OrderStream};
 use crate::{AppError, AppState};
 
 pub(crate) struct ClientConnection {
-    sender: mpsc::Sender<String>, // Channel to send messages to this client
+    sender: mpsc::Sender<Message>, // Channel to send messages to this client
+    filter: OrderFilter,           // Only orders matching the filter are sent
+    group: Option<ConsumerGroup>,  // Orders are sent to one member of the group
+    encoding: OrderEncoding,       // Encoding of the orders sent
 }
 
 pub(crate) type ConnectionsMap = HashMap<Address, ClientConnection>;
 
-fn parse_auth_msg(value: &HeaderValue) -> Result<AuthMsg> {
+pub(crate) fn parse_auth_msg(value: &HeaderValue) -> Result<AuthMsg> {
+    let json_str = value.to_str().context("Invalid header encoding")?;
+    serde_json::from_str(json_str).context("Failed to parse JSON")
+}
+
+pub(crate) fn parse_order_filter(value: &HeaderValue) -> Result<OrderFilter> {
     let json_str = value.to_str().context("Invalid header encoding")?;
     serde_json::from_str(json_str).context("Failed to parse JSON")
 }
 
+fn parse_consumer_group(value: &HeaderValue) -> Result<ConsumerGroup> {
+    let json_str = value.to_str().context("Invalid header encoding")?;
+    serde_json::from_str(json_str).context("Failed to parse JSON")
+}
+
+pub(crate) fn parse_order_encoding(value: &HeaderValue) -> Result<OrderEncoding> {
+    value.to_str().context("Invalid header encoding")?.parse()
+}
+
 #[utoipa::path(
     get,
     path = ORDER_WS_PATH,
@@ -53,6 +73,18 @@ fn parse_auth_msg(value: &HeaderValue) -> Result<AuthMsg> {
         (
             "X-Auth-Data" = AuthMsg, 
             description = "SIWE authentication message (AuthMsg) as a JSON object"
+        ),
+        (
+            "X-Order-Filter" = Option<OrderFilter>,
+            description = "Optional filter (OrderFilter) as a JSON object, only matching orders are pushed"
+        ),
+        (
+            "X-Consumer-Group" = Option<ConsumerGroup>,
+            description = "Optional consumer group (ConsumerGroup) as a JSON object, each order is pushed to one member of the group"
+        ),
+        (
+            "X-Order-Encoding" = Option<String>,
+            description = "Optional encoding of pushed orders: json (default), msgpack, json+deflate or msgpack+deflate"
         )
     ),
     responses(
@@ -83,6 +115,32 @@ pub(crate) async fn websocket_handler(
         }
     };
 
+    let filter = match headers.get(ORDER_FILTER_HEADER).map(parse_order_filter) {
+        Some(Ok(filter)) => filter,
+        Some(Err(err)) => {
+            tracing::warn!("Invalid order filter format: {err:?}");
+            return Ok((StatusCode::BAD_REQUEST, "Invalid order filter format").into_response());
+        }
+        None => OrderFilter::default(),
+    };
+
+    let group = match headers.get(CONSUMER_GROUP_HEADER).map(parse_consumer_group).transpose() {
+        Ok(group) => group,
+        Err(err) => {
+            tracing::warn!("Invalid consumer group format: {err:?}");
+            return Ok((StatusCode::BAD_REQUEST, "Invalid consumer group format").into_response());
+        }
+    };
+
+    let encoding = match headers.get(ORDER_ENCODING_HEADER).map(parse_order_encoding) {
+        Some(Ok(encoding)) => encoding,
+        Some(Err(err)) => {
+            tracing::warn!("Invalid order encoding: {err:?}");
+            return Ok((StatusCode::BAD_REQUEST, "Invalid order encoding").into_response());
+        }
+        None => OrderEncoding::default(),
+    };
+
     let client_addr = auth_msg.address();
     let addr_nonce = match state.db.get_nonce(client_addr).await {
         Ok(res) => res,
@@ -169,42 +227,104 @@ pub(crate) async fn websocket_handler(
         .on_failed_upgrade(move |error| {
             tracing::warn!("Failed to upgrade connection for {client_addr}: {error:?}");
         })
-        .on_upgrade(move |socket| websocket_connection(socket, client_addr, state)))
+        .on_upgrade(move |socket| {
+            websocket_connection(socket, client_addr, filter, group, encoding, state)
+        }))
 }
 
-// Function to broadcast an order to all WebSocket clients in random order
-async fn broadcast_order(db_order: &DbOrder, state: Arc<AppState>) {
-    let order_json = match serde_json::to_string(&db_order) {
-        Ok(order_json) => order_json,
-        Err(err) => {
-            tracing::error!("Failed to serialize order 0x{:x}: {}", db_order.order.request.id, err);
-            return;
+// Queue a message for a client, returning whether it was queued
+fn try_send(
+    address: Address,
+    sender: &mpsc::Sender<Message>,
+    msg: Message,
+    clients_to_remove: &mut Vec<Address>,
+) -> bool {
+    match sender.try_send(msg) {
+        Ok(_) => true,
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            tracing::warn!("Client {}'s message queue is full, message dropped", address);
+            false
         }
-    };
+        Err(mpsc::error::TrySendError::Closed(_)) => {
+            tracing::warn!("Client {}'s message queue is closed, removing client", address);
+            // Add the client to the list of clients to remove
+            clients_to_remove.push(address);
+            false
+        }
+    }
+}
+
+// Encode an order in the encoding of a client, as a text frame for JSON and a binary frame for
+// any other encoding. Orders are encoded once per encoding and broadcast.
+pub(crate) fn order_message(
+    db_order: &DbOrder,
+    encoding: OrderEncoding,
+    messages: &mut HashMap<OrderEncoding, Option<Message>>,
+) -> Option<Message> {
+    messages
+        .entry(encoding)
+        .or_insert_with(|| {
+            let encoded: Result<Message> = if encoding.is_text() {
+                serde_json::to_string(db_order)
+                    .map(|json| Message::Text(json.into()))
+                    .map_err(Into::into)
+            } else {
+                encode_order(db_order, encoding).map(|bytes| Message::Binary(bytes.into()))
+            };
+            encoded
+                .inspect_err(|err| {
+                    tracing::error!(
+                        "Failed to serialize order 0x{:x} as {encoding}: {err:?}",
+                        db_order.order.request.id
+                    );
+                })
+                .ok()
+        })
+        .clone()
+}
+
+// Function to broadcast an order to all WebSocket clients whose filter matches it, in random order.
+// Clients in a consumer group share the order, it is sent to the member with the most room in its
+// queue.
+async fn broadcast_order(db_order: &DbOrder, state: Arc<AppState>) {
+    let mut messages = HashMap::new();
 
     // Shuffle the connections
     let connections_list = {
         let connections = state.connections.read().await;
-        let mut connections_list: Vec<_> =
-            connections.iter().map(|(addr, conn)| (*addr, conn.sender.clone())).collect();
+        let mut connections_list: Vec<_> = connections
+            .iter()
+            .filter(|(_, conn)| conn.filter.matches(&db_order.order.request))
+            .map(|(addr, conn)| (*addr, conn.group.clone(), conn.encoding, conn.sender.clone()))
+            .collect();
         connections_list.shuffle(&mut rand::rng());
         connections_list
     };
 
     let mut clients_to_remove = Vec::new();
-    for (address, sender) in connections_list {
-        match sender.try_send(order_json.clone()) {
-            Ok(_) => {}
-            Err(mpsc::error::TrySendError::Full(_)) => {
-                tracing::warn!("Client {}'s message queue is full, message dropped", address);
-            }
-            Err(mpsc::error::TrySendError::Closed(_)) => {
-                tracing::warn!("Client {}'s message queue is closed, removing client", address);
-                // Add the client to the list of clients to remove
-                clients_to_remove.push(address);
+    let mut groups: HashMap<ConsumerGroup, Vec<(Address, OrderEncoding, mpsc::Sender<Message>)>> =
+        HashMap::new();
+    for (address, group, encoding, sender) in connections_list {
+        match group {
+            Some(group) => groups.entry(group).or_default().push((address, encoding, sender)),
+            None => {
+                if let Some(msg) = order_message(db_order, encoding, &mut messages) {
+                    try_send(address, &sender, msg, &mut clients_to_remove);
+                }
             }
         }
     }
+    for (group, mut members) in groups {
+        // Stable sort, so members with equal room are tried in random order
+        members.sort_by_key(|(_, _, sender)| std::cmp::Reverse(sender.capacity()));
+        let sent = members.iter().any(|(address, encoding, sender)| {
+            order_message(db_order, *encoding, &mut messages)
+                .is_some_and(|msg| try_send(*address, sender, msg, &mut clients_to_remove))
+        });
+        if !sent {
+            tracing::warn!("No member of consumer group {} could receive the order", group.name);
+        }
+    }
     // Remove the clients that have closed their connections
     if !clients_to_remove.is_empty() {
         {
@@ -218,10 +338,17 @@ async fn broadcast_order(db_order: &DbOrder, state: Arc<AppState>) {
     tracing::debug!("Order 0x{:x} broadcasted", db_order.order.request.id);
 }
 
-async fn websocket_connection(socket: WebSocket, address: Address, state: Arc<AppState>) {
+async fn websocket_connection(
+    socket: WebSocket,
+    address: Address,
+    filter: OrderFilter,
+    group: Option<ConsumerGroup>,
+    encoding: OrderEncoding,
+    state: Arc<AppState>,
+) {
     let (mut sender_ws, mut recver_ws) = socket.split();
 
-    let (sender_channel, mut receiver_channel) = mpsc::channel::<String>(state.config.queue_size);
+    let (sender_channel, mut receiver_channel) = mpsc::channel::<Message>(state.config.queue_size);
 
     let is_connected;
     // Add sender to the list of connections
@@ -234,7 +361,21 @@ async fn websocket_connection(socket: WebSocket, address: Address, state: Arc<Ap
             }
             Entry::Vacant(entry) => {
                 is_connected = false;
-                entry.insert(ClientConnection { sender: sender_channel.clone() });
+                if !filter.is_empty() {
+                    tracing::debug!("Client {address} connected with order filter: {filter:?}");
+                }
+                if let Some(group) = &group {
+                    tracing::debug!("Client {address} joined consumer group {}", group.name);
+                }
+                if !encoding.is_text() {
+                    tracing::debug!("Client {address} receives orders encoded as {encoding}");
+                }
+                entry.insert(ClientConnection {
+                    sender: sender_channel.clone(),
+                    filter,
+                    group,
+                    encoding,
+                });
             }
         }
     }
@@ -258,7 +399,7 @@ async fn websocket_connection(socket: WebSocket, address: Address, state: Arc<Ap
             msg = receiver_channel.recv() => {
                 match msg {
                     Some(msg) => {
-                        match sender_ws.send(Message::Text(msg.into())).await {
+                        match sender_ws.send(msg).await {
                             Ok(_) => {
                                 // Reset the error counter on successful send
                                 errors_counter = 0;